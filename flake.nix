@@ -83,6 +83,7 @@
               "pdf417-0.3.0" = "sha256-9qyCrRWync4hasSblYJUBXBbbFvservSeXJOjOcu0r0=";
             };
           };
+          buildFeatures = [ "full" ];
           nativeBuildInputs = [ pkg-config ];
           buildInputs = [ libheif ];
 
@@ -153,8 +154,10 @@
               mkdir -p "$ZIG_GLOBAL_CACHE_DIR"
               mkdir -p frontend/dist
               cp -r ${frontendDeps}/* frontend/dist/ || true
+              # No heif (libheif) or pdf (PDFium, loaded at runtime)
               cargo build -j $NIX_BUILD_CORES \
                 --release --frozen --no-default-features \
+                --features server,tui,sqlite,html --bin estrella \
                 --target ${target}
             '';
 
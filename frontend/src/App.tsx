@@ -24,12 +24,14 @@ import {
 } from './components/WeaveForm'
 import {
   EditorForm,
-  editorPreviewUrl,
+  editorPreviewUrls,
   editorCustomized,
   cut as editorCut,
+  editLink as editorEditLink,
   editorCanPrint,
   loading as editorLoading,
   triggerEditorPrint,
+  loadSavedDocument,
   editorCanvasOverlay,
   editorCanvasElementIndex,
   handleCanvasOverlaySelect,
@@ -39,19 +41,22 @@ import {
   handleCanvasOverlayDragEnd,
 } from './components/EditorForm'
 import { PhotoForm, photoPreviewUrl, photoGrayscaleActive, handlePhotoDrop } from './components/PhotoForm'
-import { JsonForm, jsonPreviewUrl, jsonCustomized } from './components/JsonForm'
+import { JsonForm, jsonPreviewUrls, jsonCustomized } from './components/JsonForm'
 import { PrintOptions } from './components/PrintOptions'
 import { LayerCanvas } from './components/LayerCanvas'
 
 export const activeTab = signal<'receipt' | 'patterns' | 'weave' | 'composer' | 'photos' | 'json'>('photos')
 
+const single = (url: string) => (url ? [url] : [])
+
+// Long documents preview as a stack of pages, one image each
 const previewUrls = {
-  receipt: () => receiptPreviewUrl.value,
-  patterns: () => patternPreviewUrl.value,
-  weave: () => weavePreviewUrl.value,
-  composer: () => editorPreviewUrl.value,
-  photos: () => photoPreviewUrl.value,
-  json: () => jsonPreviewUrl.value,
+  receipt: () => single(receiptPreviewUrl.value),
+  patterns: () => single(patternPreviewUrl.value),
+  weave: () => single(weavePreviewUrl.value),
+  composer: () => editorPreviewUrls.value,
+  photos: () => single(photoPreviewUrl.value),
+  json: () => jsonPreviewUrls.value,
 }
 
 const placeholderTexts = {
@@ -78,9 +83,18 @@ effect(() => {
 })
 
 export function App() {
-  const previewUrl = previewUrls[activeTab.value]()
+  const previewPages = previewUrls[activeTab.value]()
   const placeholderText = placeholderTexts[activeTab.value]
 
+  // Receipts printed with an edit link reopen here as /?doc=<id>
+  useEffect(() => {
+    const id = new URLSearchParams(window.location.search).get('doc')
+    if (id) {
+      activeTab.value = 'composer'
+      loadSavedDocument(id)
+    }
+  }, [])
+
   useEffect(() => {
     const root = document.getElementById('app')
     if (!root) return
@@ -147,8 +161,8 @@ export function App() {
           <h3>Preview</h3>
           <div class="preview-container">
             <div class="preview-stage">
-              {previewUrl ? (
-                <img src={previewUrl} alt="Preview" class="preview-image" />
+              {previewPages.length > 0 ? (
+                previewPages.map(url => <img key={url} src={url} alt="Preview" class="preview-image" />)
               ) : (
                 <div class="preview-placeholder">
                   <div class="preview-placeholder-text">{placeholderText}</div>
@@ -203,7 +217,7 @@ export function App() {
           )}
           {activeTab.value === 'composer' && (
             <>
-              <PrintOptions cut={editorCut} />
+              <PrintOptions cut={editorCut} editLink={editorEditLink} />
               <button
                 type="button"
                 class="print-button"
@@ -14,6 +14,9 @@ export interface ParamSpec {
 
 export interface PatternInfo {
   name: string
+  category: string | null
+  /// Seed the params were randomized from (randomize only).
+  seed?: number
   params: Record<string, string>
   specs: ParamSpec[]
 }
@@ -22,6 +25,8 @@ export interface PrintResult {
   success: boolean
   message?: string
   error?: string
+  /// Set when the print was saved for an edit-link QR code.
+  document_id?: string
 }
 
 /// Fetch the list of available patterns.
@@ -134,9 +139,21 @@ export async function fetchDefaultComponent(type: string): Promise<any> {
 
 // ===== JSON API =====
 
-/// Fetch JSON API preview as a blob URL.
-export async function fetchJsonPreview(jsonBody: string): Promise<string> {
-  const response = await fetch('/api/json/preview', {
+/// Preview page height in dots (~25cm of paper), as the server defaults to.
+const PREVIEW_PAGE_ROWS = 2000
+
+/// `draft` computes patterns and canvases at reduced vertical resolution for
+/// live editing; `full` is what gets printed.
+export type PreviewQuality = 'full' | 'draft'
+
+/// Fetch one page of a JSON API preview.
+async function fetchJsonPreviewPage(
+  jsonBody: string,
+  page: number,
+  quality: PreviewQuality,
+): Promise<Response> {
+  const params = `rows=${PREVIEW_PAGE_ROWS}&quality=${quality}`
+  const response = await fetch(`/api/json/preview/page/${page}?${params}`, {
     method: 'POST',
     headers: { 'Content-Type': 'application/json' },
     body: jsonBody,
@@ -146,9 +163,31 @@ export async function fetchJsonPreview(jsonBody: string): Promise<string> {
     const text = await response.text()
     throw new Error(text || 'Failed to fetch JSON preview')
   }
+  return response
+}
 
-  const blob = await response.blob()
-  return URL.createObjectURL(blob)
+/// Fetch JSON API preview as blob URLs, one per page.
+///
+/// Long documents come back page by page, so neither the server nor the
+/// browser has to hold a multi-meter preview as one image.
+export async function fetchJsonPreview(
+  jsonBody: string,
+  quality: PreviewQuality = 'full',
+): Promise<string[]> {
+  const first = await fetchJsonPreviewPage(jsonBody, 0, quality)
+  const pageCount = Number(first.headers.get('X-Page-Count')) || 1
+  const blobs = [await first.blob()]
+  for (let page = 1; page < pageCount; page++) {
+    blobs.push(await (await fetchJsonPreviewPage(jsonBody, page, quality)).blob())
+  }
+  return blobs.map(blob => URL.createObjectURL(blob))
+}
+
+/// Release blob URLs from a previous preview.
+export function revokePreviewUrls(urls: string[]) {
+  for (const url of urls) {
+    if (url.startsWith('blob:')) URL.revokeObjectURL(url)
+  }
 }
 
 /// Canvas layout response from the backend.
@@ -184,9 +223,10 @@ export async function fetchCanvasLayout(
   return response.json()
 }
 
-/// Print a JSON document.
-export async function printJson(jsonBody: string): Promise<PrintResult> {
-  const response = await fetch('/api/json/print', {
+/// Print a JSON document, optionally ending with a QR code that reopens it
+/// in the editor.
+export async function printJson(jsonBody: string, editLink: boolean = false): Promise<PrintResult> {
+  const response = await fetch(`/api/json/print${editLink ? '?edit_link=true' : ''}`, {
     method: 'POST',
     headers: { 'Content-Type': 'application/json' },
     body: jsonBody,
@@ -194,6 +234,17 @@ export async function printJson(jsonBody: string): Promise<PrintResult> {
   return response.json()
 }
 
+/// Fetch a document saved by an edit-link print.
+export async function fetchSavedDocument(id: string): Promise<any> {
+  const response = await fetch(`/api/json/documents/${encodeURIComponent(id)}`)
+
+  if (!response.ok) {
+    throw new Error('Saved document not found')
+  }
+
+  return response.json()
+}
+
 // ===== Weave API =====
 
 /// A pattern entry for weave requests.
@@ -271,6 +322,14 @@ export interface PhotoUploadResponse {
   height: number
   /** True if the image is already binary (1-bit black/white) */
   is_binary: boolean
+  /** Date taken and camera, when the photo has EXIF */
+  exif: PhotoExif
+}
+
+/// EXIF details of an uploaded photo.
+export interface PhotoExif {
+  date_taken?: string
+  camera?: string
 }
 
 /// Upload an image and get a session ID.
@@ -298,6 +357,8 @@ export function buildPhotoPreviewUrl(
   dither: string,
   brightness: number,
   contrast: number,
+  background: string,
+  halftone: string,
   cacheKey?: number
 ): string {
   const searchParams = new URLSearchParams({
@@ -305,7 +366,12 @@ export function buildPhotoPreviewUrl(
     dither,
     brightness: brightness.toString(),
     contrast: contrast.toString(),
+    background,
   })
+  // Empty means dither as usual
+  if (halftone) {
+    searchParams.set('halftone', halftone)
+  }
   // Add cache key if provided to bust browser cache
   if (cacheKey !== undefined) {
     searchParams.set('_t', cacheKey.toString())
@@ -320,8 +386,11 @@ export async function printPhoto(
   dither: string,
   brightness: number,
   contrast: number,
+  background: string,
+  halftone: string,
   mode: string,
-  cut: boolean
+  cut: boolean,
+  caption: string[] = []
 ): Promise<PrintResult> {
   const response = await fetch(`/api/photo/${sessionId}/print`, {
     method: 'POST',
@@ -331,8 +400,11 @@ export async function printPhoto(
       dither,
       brightness,
       contrast,
+      background,
+      halftone: halftone || undefined,
       mode,
       cut,
+      caption,
     }),
   })
   return response.json()
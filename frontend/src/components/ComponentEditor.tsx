@@ -51,7 +51,12 @@ export function getComponentSummary(comp: any): string {
     case 'blank_line':
       return ''
     case 'columns':
+      if (comp.columns?.length) return comp.columns.map((c: string) => truncate(c, 8)).join(' | ')
       return `${truncate(comp.left, 12)} | ${truncate(comp.right, 12)}`
+    case 'definitions':
+      return `${comp.items?.length || 0} entries`
+    case 'data_dump':
+      return comp.format === 'yaml' ? 'YAML' : 'JSON'
     case 'line_item':
       return `${comp.name} $${Number(comp.price || 0).toFixed(2)}`
     case 'total':
@@ -60,8 +65,14 @@ export function getComponentSummary(comp: any): string {
       return `${comp.headers?.length || 0} cols, ${comp.rows?.length || 0} rows`
     case 'markdown':
       return truncate(comp.content, 30)
+    case 'html':
+      return truncate((comp.content || '').replace(/<[^>]*>/g, ' ').replace(/\s+/g, ' ').trim(), 30)
     case 'chart':
       return `${comp.style} chart`
+    case 'gauge':
+      return `${comp.value}${comp.unit || ''}${comp.label ? ' ' + truncate(comp.label, 15) : ''}`
+    case 'progress_bar':
+      return `${comp.label ? truncate(comp.label, 15) + ': ' : ''}${comp.value}/${comp.max ?? 100}`
     case 'qr_code':
       return truncate(comp.data, 20)
     case 'pdf417':
@@ -76,6 +87,12 @@ export function getComponentSummary(comp: any): string {
       return `${comp.elements?.length || 0} elements`
     case 'nv_logo':
       return `key: ${comp.key}`
+    case 'text_mask':
+      return truncate(comp.content, 25)
+    case 'station_group':
+      return `${(comp.stations || []).join(', ')}: ${comp.components?.length || 0} components`
+    case 'order_ticket':
+      return `#${comp.order?.number || '?'}, ${comp.order?.items?.length || 0} items`
     default:
       return ''
   }
@@ -116,6 +133,8 @@ export function ComponentEditor({ component, onUpdate, canvasElementIndex, onCan
       return <TableEditor comp={component} onUpdate={onUpdate} />
     case 'markdown':
       return <MarkdownEditor comp={component} onUpdate={onUpdate} />
+    case 'html':
+      return <HtmlEditor comp={component} onUpdate={onUpdate} />
     case 'chart':
       return <ChartEditor comp={component} onUpdate={onUpdate} />
     case 'qr_code':
@@ -164,7 +183,7 @@ function BoolToggle({
   )
 }
 
-const BLEND_MODES = ['normal', 'multiply', 'screen', 'overlay', 'add', 'difference', 'min', 'max']
+const BLEND_MODES = ['normal', 'multiply', 'screen', 'overlay', 'add', 'difference', 'min', 'max', 'mask', 'knockout']
 
 // ============================================================================
 // Type-specific editors
@@ -427,7 +446,9 @@ function TotalEditor({ comp, onUpdate }: EditorProps) {
 
 function TableEditor({ comp, onUpdate }: EditorProps) {
   const headers: string[] = comp.headers || []
-  const rows: string[][] = comp.rows || []
+  const rows: any[][] = comp.rows || []
+  // Cells with a colspan are objects; edit their text, keep the span
+  const cellText = (c: any) => (typeof c === 'string' ? c : c.text)
 
   return (
     <div class="component-editor">
@@ -469,9 +490,11 @@ function TableEditor({ comp, onUpdate }: EditorProps) {
           <div key={i} class="table-row-editor">
             <input
               type="text"
-              value={row.join(', ')}
+              value={row.map(cellText).join(', ')}
               onInput={(e) => {
-                const newRow = (e.target as HTMLInputElement).value.split(',').map((s) => s.trim())
+                const newRow = (e.target as HTMLInputElement).value
+                  .split(',')
+                  .map((s, j) => (typeof row[j] === 'object' ? { ...row[j], text: s.trim() } : s.trim()))
                 const newRows = [...rows]
                 newRows[i] = newRow
                 onUpdate({ rows: newRows })
@@ -515,6 +538,23 @@ function MarkdownEditor({ comp, onUpdate }: EditorProps) {
   )
 }
 
+function HtmlEditor({ comp, onUpdate }: EditorProps) {
+  return (
+    <div class="component-editor">
+      <div class="form-group">
+        <label>HTML</label>
+        <textarea
+          value={comp.content || ''}
+          onInput={(e) => onUpdate({ content: (e.target as HTMLTextAreaElement).value })}
+          rows={6}
+          class="component-textarea"
+        />
+        <p class="hint">Supports h1-h3, p, b/i/u, hr, table, ul/ol, img and center</p>
+      </div>
+    </div>
+  )
+}
+
 function ChartEditor({ comp, onUpdate }: EditorProps) {
   return (
     <div class="component-editor">
@@ -622,6 +662,8 @@ function BarcodeEditor({ comp, onUpdate }: EditorProps) {
             <option value="ean13">EAN-13</option>
             <option value="upca">UPC-A</option>
             <option value="itf">ITF</option>
+            <option value="code93">Code 93</option>
+            <option value="nw7">NW-7 (Codabar)</option>
           </select>
         </div>
         <div class="form-group">
@@ -718,7 +760,9 @@ function PatternEditor({ comp, onUpdate }: EditorProps) {
             <option value="jarvis">Jarvis</option>
             <option value="atkinson">Atkinson</option>
             <option value="bayer">Bayer</option>
+            <option value="blue-noise">Blue noise</option>
             <option value="floyd-steinberg">Floyd-Steinberg</option>
+            <option value="auto">Auto (per region)</option>
           </select>
         </div>
       </div>
@@ -802,6 +846,80 @@ function ImageEditor({ comp, onUpdate }: EditorProps) {
           </select>
         </div>
       </div>
+      <div class="editor-row">
+        <div class="form-group">
+          <label>Fit</label>
+          <select
+            value={comp.fit || 'contain'}
+            onChange={(e) => onUpdate({ fit: (e.target as HTMLSelectElement).value })}
+          >
+            <option value="contain">Contain</option>
+            <option value="width">Full width</option>
+            <option value="crop">Crop</option>
+          </select>
+        </div>
+        <div class="form-group">
+          <label>Dither</label>
+          <select
+            value={comp.dither || 'floyd-steinberg'}
+            onChange={(e) => onUpdate({ dither: (e.target as HTMLSelectElement).value })}
+          >
+            <option value="floyd-steinberg">Floyd-Steinberg</option>
+            <option value="atkinson">Atkinson</option>
+            <option value="jarvis">Jarvis</option>
+            <option value="bayer">Bayer</option>
+            <option value="blue-noise">Blue noise</option>
+            <option value="auto">Auto</option>
+            <option value="none">None</option>
+          </select>
+        </div>
+      </div>
+      <div class="editor-row">
+        <div class="form-group">
+          <label>Brightness</label>
+          <input
+            type="number"
+            min="-100"
+            max="100"
+            value={comp.brightness ?? 0}
+            onInput={(e) => {
+              const v = parseInt((e.target as HTMLInputElement).value)
+              onUpdate({ brightness: isNaN(v) || v === 0 ? undefined : v })
+            }}
+          />
+        </div>
+        <div class="form-group">
+          <label>Contrast</label>
+          <input
+            type="number"
+            min="-100"
+            max="100"
+            value={comp.contrast ?? 0}
+            onInput={(e) => {
+              const v = parseInt((e.target as HTMLInputElement).value)
+              onUpdate({ contrast: isNaN(v) || v === 0 ? undefined : v })
+            }}
+          />
+        </div>
+      </div>
+      <div class="form-group">
+        <label>Background</label>
+        <input
+          type="text"
+          placeholder="white, black, 0.5 or a pattern name"
+          value={comp.background ?? ''}
+          onInput={(e) => onUpdate({ background: (e.target as HTMLInputElement).value || undefined })}
+        />
+      </div>
+      <div class="form-group">
+        <label>Halftone</label>
+        <input
+          type="text"
+          placeholder="dot, line or diagonal (e.g. dot:25:45)"
+          value={comp.halftone ?? ''}
+          onInput={(e) => onUpdate({ halftone: (e.target as HTMLInputElement).value || undefined })}
+        />
+      </div>
     </div>
   )
 }
@@ -893,6 +1011,7 @@ function CanvasEditor({ comp, onUpdate, expandedElement, onElementSelect }: Edit
             <option value="none">None</option>
             <option value="atkinson">Atkinson</option>
             <option value="bayer">Bayer</option>
+            <option value="blue-noise">Blue noise</option>
             <option value="floyd-steinberg">Floyd-Steinberg</option>
           </select>
         </div>
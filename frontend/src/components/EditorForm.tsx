@@ -1,6 +1,13 @@
 import { signal, effect, computed } from '@preact/signals'
 import { useEffect } from 'preact/hooks'
-import { fetchJsonPreview, fetchCanvasLayout, printJson } from '../api'
+import {
+  fetchJsonPreview,
+  fetchCanvasLayout,
+  fetchSavedDocument,
+  printJson,
+  revokePreviewUrls,
+  type PreviewQuality,
+} from '../api'
 import type { OverlayLayer } from './LayerCanvas'
 import {
   ComponentEditor,
@@ -20,6 +27,7 @@ const editorSelectedIndex = signal<number | null>(null)
 
 // Print options
 export const cut = signal(true)
+export const editLink = signal(false)
 
 // UI state
 const status = signal<{ type: 'success' | 'error'; message: string } | null>(null)
@@ -28,7 +36,7 @@ const showAdvanced = signal(false)
 const jsonError = signal<string | null>(null)
 
 // Exports for App.tsx
-export const editorPreviewUrl = signal<string>('')
+export const editorPreviewUrls = signal<string[]>([])
 export const editorCustomized = computed(() => editorComponents.value.length > 0)
 export const editorCanPrint = computed(() => editorComponents.value.length > 0 && !loading.value)
 
@@ -61,36 +69,54 @@ function buildDocumentJson(): string {
   })
 }
 
-// Throttled preview refresh (fires every 500ms during continuous changes, not just after idle)
+// Throttled draft preview refresh (fires every 500ms during continuous changes, not just after idle),
+// followed by a full-quality render once editing pauses
 let previewTimeout: number | null = null
+let fullPreviewTimeout: number | null = null
 let lastPreviewTime = 0
+let previewRequest = 0
+let shownPreviewRequest = 0
 const PREVIEW_THROTTLE = 500
+const FULL_PREVIEW_IDLE = 1000
+
+async function refreshPreview(quality: PreviewQuality) {
+  const request = ++previewRequest
+  try {
+    const urls = await fetchJsonPreview(buildDocumentJson(), quality)
+    // A slow draft must not replace the full render that followed it
+    if (request < shownPreviewRequest) {
+      revokePreviewUrls(urls)
+      return
+    }
+    shownPreviewRequest = request
+    revokePreviewUrls(editorPreviewUrls.value)
+    editorPreviewUrls.value = urls
+  } catch (err) {
+    console.error('Preview error:', err)
+  }
+}
 
 effect(() => {
   const components = editorComponents.value
   void cut.value
 
   if (previewTimeout) clearTimeout(previewTimeout)
+  if (fullPreviewTimeout) clearTimeout(fullPreviewTimeout)
 
   if (components.length === 0) {
-    editorPreviewUrl.value = ''
+    revokePreviewUrls(editorPreviewUrls.value)
+    editorPreviewUrls.value = []
     return
   }
 
   const elapsed = Date.now() - lastPreviewTime
   const delay = elapsed >= PREVIEW_THROTTLE ? 0 : PREVIEW_THROTTLE - elapsed
 
-  previewTimeout = window.setTimeout(async () => {
+  previewTimeout = window.setTimeout(() => {
     lastPreviewTime = Date.now()
-    try {
-      const url = await fetchJsonPreview(buildDocumentJson())
-      const prev = editorPreviewUrl.value
-      if (prev && prev.startsWith('blob:')) URL.revokeObjectURL(prev)
-      editorPreviewUrl.value = url
-    } catch (err) {
-      console.error('Preview error:', err)
-    }
+    refreshPreview('draft')
   }, delay)
+  fullPreviewTimeout = window.setTimeout(() => refreshPreview('full'), FULL_PREVIEW_IDLE)
 })
 
 // Throttled canvas overlay layout fetch (fires every 500ms during continuous changes)
@@ -244,7 +270,7 @@ async function handlePrint() {
   status.value = null
 
   try {
-    const result = await printJson(buildDocumentJson())
+    const result = await printJson(buildDocumentJson(), editLink.value)
     if (result.success) {
       status.value = { type: 'success', message: result.message || 'Printed!' }
     } else {
@@ -261,6 +287,18 @@ export function triggerEditorPrint() {
   return handlePrint()
 }
 
+/// Load a document saved by an edit-link print (from a scanned receipt QR).
+export async function loadSavedDocument(id: string) {
+  try {
+    const doc = await fetchSavedDocument(id)
+    editorComponents.value = doc.document ?? []
+    if (doc.cut !== undefined) cut.value = doc.cut
+    editorSelectedIndex.value = null
+  } catch (err) {
+    status.value = { type: 'error', message: `${err}` }
+  }
+}
+
 // Component operations
 async function addComponent(type: string) {
   const comp = await createDefaultComponent(type)
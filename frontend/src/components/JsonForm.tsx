@@ -1,5 +1,5 @@
 import { signal, effect, computed } from '@preact/signals'
-import { fetchJsonPreview, printJson } from '../api'
+import { fetchJsonPreview, printJson, revokePreviewUrls } from '../api'
 import DEFAULT_JSON from '../../../src/fixtures/morning-briefing.json?raw'
 
 const jsonText = signal(DEFAULT_JSON)
@@ -9,8 +9,13 @@ const parseError = signal<string | null>(null)
 
 export const jsonCustomized = computed(() => jsonText.value !== DEFAULT_JSON)
 
-// Export preview URL for App.tsx
-export const jsonPreviewUrl = signal('')
+// Export preview URLs (one per page) for App.tsx
+export const jsonPreviewUrls = signal<string[]>([])
+
+function setPreview(urls: string[]) {
+  revokePreviewUrls(jsonPreviewUrls.value)
+  jsonPreviewUrls.value = urls
+}
 
 // Debounce timer for preview
 let previewTimeout: number | null = null
@@ -24,7 +29,7 @@ effect(() => {
   }
 
   if (!currentJson.trim()) {
-    jsonPreviewUrl.value = ''
+    setPreview([])
     parseError.value = null
     return
   }
@@ -35,19 +40,18 @@ effect(() => {
     parseError.value = null
   } catch (err) {
     parseError.value = `JSON syntax error: ${(err as Error).message}`
-    jsonPreviewUrl.value = ''
+    setPreview([])
     return
   }
 
   // Debounce preview requests
   previewTimeout = window.setTimeout(async () => {
     try {
-      const url = await fetchJsonPreview(currentJson)
-      jsonPreviewUrl.value = url
+      setPreview(await fetchJsonPreview(currentJson))
       parseError.value = null
     } catch (err) {
       parseError.value = `${err}`
-      jsonPreviewUrl.value = ''
+      setPreview([])
     }
   }, 500)
 })
@@ -101,9 +105,10 @@ export function JsonForm() {
         {parseError.value && <p class="hint error-hint">{parseError.value}</p>}
         <p class="hint">
           Components: text, header, banner, line_item, total, divider, spacer, blank_line, columns,
-          table, markdown, chart, qr_code, pdf417, barcode, pattern, nv_logo. Use {'{{'}<em>name</em>{'}}'}  in
+          progress_bar, table, markdown, html, chart, gauge, qr_code, pdf417, barcode, pattern, nv_logo, order_ticket. Use {'{{'}<em>name</em>{'}}'}  in
           text with a top-level "variables" object. Built-ins: date, date_short, day, time,
-          time_12h, datetime, year, iso_date.
+          time_12h, datetime, year, iso_date, greeting, moon_phase, and sunrise/sunset with a
+          top-level "location": "LAT,LON".
         </p>
       </div>
 
@@ -13,7 +13,7 @@ const selectedPattern = signal('estrella')
 const params = signal<Record<string, string>>({})
 const specs = signal<ParamSpec[]>([])
 const lengthMm = signal(100)
-const dithering = signal<'bayer' | 'floyd-steinberg' | 'atkinson' | 'jarvis'>('floyd-steinberg')
+const dithering = signal<'bayer' | 'blue-noise' | 'floyd-steinberg' | 'atkinson' | 'jarvis'>('floyd-steinberg')
 const renderMode = signal<'raster' | 'band'>('raster')
 export const cut = signal(true)
 export const printDetails = signal(true)
@@ -319,13 +319,14 @@ export function PatternForm() {
           id="dither"
           value={dithering.value}
           onChange={(e) => {
-            dithering.value = (e.target as HTMLSelectElement).value as 'bayer' | 'floyd-steinberg' | 'atkinson' | 'jarvis'
+            dithering.value = (e.target as HTMLSelectElement).value as 'bayer' | 'blue-noise' | 'floyd-steinberg' | 'atkinson' | 'jarvis'
             handleSettingChange()
           }}
         >
           <option value="jarvis">Jarvis (smooth)</option>
           <option value="atkinson">Atkinson (classic Mac)</option>
           <option value="bayer">Bayer (ordered)</option>
+          <option value="blue-noise">Blue noise (smooth, ordered)</option>
           <option value="floyd-steinberg">Floyd-Steinberg (diffusion)</option>
         </select>
       </div>
@@ -1,16 +1,21 @@
 import { signal } from '@preact/signals'
-import { uploadPhoto, buildPhotoPreviewUrl, printPhoto } from '../api'
+import { uploadPhoto, buildPhotoPreviewUrl, printPhoto, type PhotoExif } from '../api'
 
 // Photo session state
 const sessionId = signal<string | null>(null)
 const filename = signal('')
 const isBinary = signal(false) // True if image is already 1-bit
 const rotation = signal<0 | 90 | 180 | 270>(0)
-const dithering = signal<'none' | 'jarvis' | 'atkinson' | 'bayer' | 'floyd-steinberg'>('floyd-steinberg')
+const dithering = signal<'none' | 'jarvis' | 'atkinson' | 'bayer' | 'blue-noise' | 'floyd-steinberg' | 'auto'>('floyd-steinberg')
 const brightness = signal(0)
 const contrast = signal(0)
+const background = signal('white')
+const halftone = signal('')
 const renderMode = signal<'raster' | 'band'>('raster')
 const cut = signal(true)
+const exif = signal<PhotoExif>({})
+const captionDate = signal(false)
+const captionCamera = signal(false)
 const status = signal<{ type: 'success' | 'error'; message: string } | null>(null)
 const uploading = signal(false)
 const printing = signal(false)
@@ -30,6 +35,14 @@ let grayscaleOnNextLoad = false
 let storedImageData: ArrayBuffer | null = null
 let storedFilename: string = ''
 
+// EXIF fields to print under the photo
+function captionFields(): string[] {
+  const fields: string[] = []
+  if (captionDate.value) fields.push('date')
+  if (captionCamera.value) fields.push('camera')
+  return fields
+}
+
 // Build current preview URL from current state
 function buildCurrentPreviewUrl(): string {
   if (!sessionId.value) return ''
@@ -39,6 +52,8 @@ function buildCurrentPreviewUrl(): string {
     dithering.value,
     brightness.value,
     contrast.value,
+    background.value,
+    halftone.value,
     Date.now() // Cache bust
   )
 }
@@ -117,6 +132,7 @@ async function handleFileSelect(file: File) {
     sessionId.value = response.id
     filename.value = response.filename
     isBinary.value = response.is_binary
+    exif.value = response.exif ?? {}
     // Auto-select "none" for binary images, otherwise use Floyd-Steinberg
     dithering.value = response.is_binary ? 'none' : 'floyd-steinberg'
     grayscaleOnNextLoad = true
@@ -199,8 +215,11 @@ export function PhotoForm() {
         dithering.value,
         brightness.value,
         contrast.value,
+        background.value,
+        halftone.value,
         renderMode.value,
-        cut.value
+        cut.value,
+        captionFields()
       )
 
       if (result.success) {
@@ -217,8 +236,11 @@ export function PhotoForm() {
               dithering.value,
               brightness.value,
               contrast.value,
+              background.value,
+              halftone.value,
               renderMode.value,
-              cut.value
+              cut.value,
+              captionFields()
             )
             if (retryResult.success) {
               status.value = { type: 'success', message: retryResult.message || 'Printed successfully!' }
@@ -268,6 +290,11 @@ export function PhotoForm() {
     rotation.value = 0
     brightness.value = 0
     contrast.value = 0
+    background.value = 'white'
+    halftone.value = ''
+    exif.value = {}
+    captionDate.value = false
+    captionCamera.value = false
     storedImageData = null
     storedFilename = ''
     status.value = null
@@ -375,7 +402,9 @@ export function PhotoForm() {
                   | 'jarvis'
                   | 'atkinson'
                   | 'bayer'
+                  | 'blue-noise'
                   | 'floyd-steinberg'
+                  | 'auto'
                 handleSettingChangeImmediate()
               }}
             >
@@ -383,7 +412,44 @@ export function PhotoForm() {
               <option value="jarvis">Jarvis (smooth)</option>
               <option value="atkinson">Atkinson (classic Mac)</option>
               <option value="bayer">Bayer (ordered)</option>
+              <option value="blue-noise">Blue noise (smooth, ordered)</option>
               <option value="floyd-steinberg">Floyd-Steinberg (diffusion)</option>
+              <option value="auto">Auto (per region)</option>
+            </select>
+          </div>
+
+          <div class="form-group">
+            <label for="halftone">Halftone</label>
+            <select
+              id="halftone"
+              value={halftone.value}
+              onChange={(e) => {
+                halftone.value = (e.target as HTMLSelectElement).value
+                handleSettingChangeImmediate()
+              }}
+            >
+              <option value="">Off (use dithering)</option>
+              <option value="dot">Dots (newspaper)</option>
+              <option value="dot:15">Coarse dots</option>
+              <option value="line">Line screen</option>
+              <option value="diagonal">Diagonal lines</option>
+            </select>
+          </div>
+
+          <div class="form-group">
+            <label for="background">Transparent Areas</label>
+            <select
+              id="background"
+              value={background.value}
+              onChange={(e) => {
+                background.value = (e.target as HTMLSelectElement).value
+                handleSettingChangeImmediate()
+              }}
+            >
+              <option value="white">White (paper)</option>
+              <option value="0.25">Light gray</option>
+              <option value="0.5">Gray</option>
+              <option value="black">Black</option>
             </select>
           </div>
 
@@ -411,6 +477,32 @@ export function PhotoForm() {
               Cut page after printing
             </label>
           </div>
+
+          {exif.value.date_taken && (
+            <div class="form-group checkbox-group">
+              <label>
+                <input
+                  type="checkbox"
+                  checked={captionDate.value}
+                  onChange={(e) => (captionDate.value = (e.target as HTMLInputElement).checked)}
+                />
+                Print date taken ({exif.value.date_taken})
+              </label>
+            </div>
+          )}
+
+          {exif.value.camera && (
+            <div class="form-group checkbox-group">
+              <label>
+                <input
+                  type="checkbox"
+                  checked={captionCamera.value}
+                  onChange={(e) => (captionCamera.value = (e.target as HTMLInputElement).checked)}
+                />
+                Print camera ({exif.value.camera})
+              </label>
+            </div>
+          )}
         </>
       )}
 
@@ -4,10 +4,12 @@ export function PrintOptions({
   cut,
   printDetails,
   detailsLabel,
+  editLink,
 }: {
   cut: Signal<boolean>
   printDetails?: Signal<boolean>
   detailsLabel?: string
+  editLink?: Signal<boolean>
 }) {
   return (
     <div class="form-group checkbox-group receipt-options">
@@ -29,6 +31,16 @@ export function PrintOptions({
           {detailsLabel}
         </label>
       )}
+      {editLink && (
+        <label>
+          <input
+            type="checkbox"
+            checked={editLink.value}
+            onChange={(e) => (editLink.value = (e.target as HTMLInputElement).checked)}
+          />
+          Add QR code to reopen for editing
+        </label>
+      )}
     </div>
   )
 }
@@ -28,7 +28,7 @@ const weavePatterns = signal<WeavePatternEntry[]>([])
 const weaveLengthMm = signal(200)
 const crossfadeMm = signal(30)
 const blendCurve = signal<string>('smooth')
-const dithering = signal<'bayer' | 'floyd-steinberg' | 'atkinson' | 'jarvis'>('floyd-steinberg')
+const dithering = signal<'bayer' | 'blue-noise' | 'floyd-steinberg' | 'atkinson' | 'jarvis'>('floyd-steinberg')
 const renderMode = signal<'raster' | 'band'>('raster')
 export const cut = signal(true)
 export const printDetails = signal(true)
@@ -290,13 +290,14 @@ export function WeaveForm() {
           id="weave-dither"
           value={dithering.value}
           onChange={(e) => {
-            dithering.value = (e.target as HTMLSelectElement).value as 'bayer' | 'floyd-steinberg' | 'atkinson' | 'jarvis'
+            dithering.value = (e.target as HTMLSelectElement).value as 'bayer' | 'blue-noise' | 'floyd-steinberg' | 'atkinson' | 'jarvis'
             handleSettingChange()
           }}
         >
           <option value="jarvis">Jarvis (smooth)</option>
           <option value="atkinson">Atkinson (classic Mac)</option>
           <option value="bayer">Bayer (ordered)</option>
+          <option value="blue-noise">Blue noise (smooth, ordered)</option>
           <option value="floyd-steinberg">Floyd-Steinberg (diffusion)</option>
         </select>
       </div>
@@ -0,0 +1,30 @@
+//! QR and PDF417 payloads: length headers must match the payload, and
+//! UTF-8 payloads must survive a codegen/decode round trip.
+
+#![no_main]
+
+use estrella::ir::{Op, Program};
+use estrella::protocol::barcode::qr::QrErrorLevel;
+use estrella::protocol::barcode::{pdf417, qr};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let cmd = qr::set_data(data);
+    assert_eq!(u16::from_le_bytes([cmd[6], cmd[7]]) as usize, cmd.len() - 8);
+    let cmd = pdf417::set_data(data);
+    assert_eq!(u16::from_le_bytes([cmd[4], cmd[5]]) as usize, cmd.len() - 6);
+
+    if let Ok(text) = std::str::from_utf8(data) {
+        if text.len() > u16::MAX as usize {
+            return;
+        }
+        let qr = Op::QrCode {
+            data: text.to_string(),
+            cell_size: 4,
+            error_level: QrErrorLevel::M,
+        };
+        let program = Program { ops: vec![qr] };
+        let decoded = Program::from_bytes(&program.to_bytes()).unwrap();
+        assert_eq!(decoded.ops, program.ops);
+    }
+});
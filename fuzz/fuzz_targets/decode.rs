@@ -0,0 +1,14 @@
+//! Decode arbitrary bytes, then re-encode whatever decoded.
+//!
+//! Neither step may panic, whatever the input.
+
+#![no_main]
+
+use estrella::ir::Program;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(program) = Program::from_bytes(data) {
+        let _ = program.to_bytes();
+    }
+});
@@ -76,8 +76,7 @@ impl Default for Params {
 }
 
 impl Params {
-    pub fn random() -> Self {
-        let mut rng = rand::rng();
+    pub fn random(rng: &mut super::PatternRng) -> Self {
         let attractor = match rng.random_range(0..3) {
             0 => AttractorType::Lorenz,
             1 => AttractorType::Rossler,
@@ -334,9 +333,9 @@ impl Attractor {
         }
     }
 
-    pub fn random() -> Self {
+    pub fn random(rng: &mut super::PatternRng) -> Self {
         Self {
-            params: Params::random(),
+            params: Params::random(rng),
             cache: Mutex::new(None),
         }
     }
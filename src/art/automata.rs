@@ -66,8 +66,7 @@ impl Default for Params {
 }
 
 impl Params {
-    pub fn random() -> Self {
-        let mut rng = rand::rng();
+    pub fn random(rng: &mut super::PatternRng) -> Self {
         // Interesting rules: 30, 45, 54, 60, 73, 90, 105, 110, 124, 135, 150, 169, 182
         let interesting_rules = [30, 45, 54, 60, 73, 90, 105, 110, 124, 135, 150, 169, 182];
         let rule = interesting_rules[rng.random_range(0..interesting_rules.len())];
@@ -231,9 +230,9 @@ impl Automata {
         }
     }
 
-    pub fn random() -> Self {
+    pub fn random(rng: &mut super::PatternRng) -> Self {
         Self {
-            params: Params::random(),
+            params: Params::random(rng),
         }
     }
 }
@@ -93,7 +93,7 @@ impl Calibration {
     }
 
     // No randomization for calibration - it's a diagnostic pattern
-    pub fn random() -> Self {
+    pub fn random(_rng: &mut super::PatternRng) -> Self {
         Self::golden()
     }
 }
@@ -44,8 +44,7 @@ impl Default for Params {
 }
 
 impl Params {
-    pub fn random() -> Self {
-        let mut rng = rand::rng();
+    pub fn random(rng: &mut super::PatternRng) -> Self {
         Self {
             bar_width: rng.random_range(2..8),
             corruption: rng.random_range(0.3..0.9),
@@ -146,9 +145,9 @@ impl CorruptBarcode {
         }
     }
 
-    pub fn random() -> Self {
+    pub fn random(rng: &mut super::PatternRng) -> Self {
         Self {
-            params: Params::random(),
+            params: Params::random(rng),
         }
     }
 }
@@ -50,8 +50,7 @@ impl Default for Params {
 }
 
 impl Params {
-    pub fn random() -> Self {
-        let mut rng = rand::rng();
+    pub fn random(rng: &mut super::PatternRng) -> Self {
         Self {
             spacing: rng.random_range(4.0..10.0),
             thickness: rng.random_range(1.0..2.5),
@@ -164,9 +163,9 @@ impl Crosshatch {
         }
     }
 
-    pub fn random() -> Self {
+    pub fn random(rng: &mut super::PatternRng) -> Self {
         Self {
-            params: Params::random(),
+            params: Params::random(rng),
         }
     }
 }
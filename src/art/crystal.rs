@@ -48,8 +48,7 @@ impl Default for Params {
 
 impl Params {
     /// Generate randomized parameters for unique prints.
-    pub fn random() -> Self {
-        let mut rng = rand::rng();
+    pub fn random(rng: &mut super::PatternRng) -> Self {
         Self {
             symmetry: *[1, 4, 5, 6, 8].get(rng.random_range(0..5)).unwrap_or(&6),
             levels: rng.random_range(3..6),
@@ -207,9 +206,9 @@ impl Crystal {
         }
     }
 
-    pub fn random() -> Self {
+    pub fn random(rng: &mut super::PatternRng) -> Self {
         Self {
-            params: Params::random(),
+            params: Params::random(rng),
         }
     }
 }
@@ -44,8 +44,7 @@ impl Default for Params {
 }
 
 impl Params {
-    pub fn random() -> Self {
-        let mut rng = rand::rng();
+    pub fn random(rng: &mut super::PatternRng) -> Self {
         Self {
             block_size: rng.random_range(4..16),
             corruption_rate: rng.random_range(0.05..0.3),
@@ -165,9 +164,9 @@ impl Databend {
         }
     }
 
-    pub fn random() -> Self {
+    pub fn random(rng: &mut super::PatternRng) -> Self {
         Self {
-            params: Params::random(),
+            params: Params::random(rng),
         }
     }
 }
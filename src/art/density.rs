@@ -50,8 +50,7 @@ impl Default for Params {
 
 impl Params {
     /// Generate randomized parameters for unique prints.
-    pub fn random() -> Self {
-        let mut rng = rand::rng();
+    pub fn random(rng: &mut super::PatternRng) -> Self {
         Self {
             scale: rng.random_range(4.0..10.0),
             drift: rng.random_range(50.0..120.0),
@@ -125,9 +124,9 @@ impl Density {
         }
     }
 
-    pub fn random() -> Self {
+    pub fn random(rng: &mut super::PatternRng) -> Self {
         Self {
-            params: Params::random(),
+            params: Params::random(rng),
         }
     }
 }
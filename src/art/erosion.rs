@@ -48,8 +48,7 @@ impl Default for Params {
 
 impl Params {
     /// Generate randomized parameters for unique prints.
-    pub fn random() -> Self {
-        let mut rng = rand::rng();
+    pub fn random(rng: &mut super::PatternRng) -> Self {
         Self {
             terrain_scale: rng.random_range(0.004..0.01),
             octaves: rng.random_range(4..7),
@@ -91,9 +90,9 @@ impl Erosion {
         }
     }
 
-    pub fn random() -> Self {
+    pub fn random(rng: &mut super::PatternRng) -> Self {
         Self {
-            params: Params::random(),
+            params: Params::random(rng),
         }
     }
 }
@@ -62,8 +62,7 @@ impl Default for Params {
 
 impl Params {
     /// Generate randomized parameters for unique prints.
-    pub fn random() -> Self {
-        let mut rng = rand::rng();
+    pub fn random(rng: &mut super::PatternRng) -> Self {
         Self {
             center_x: rng.random_range(0.4..0.6),
             center_y: rng.random_range(0.45..0.55),
@@ -402,9 +401,9 @@ impl Estrella {
     }
 
     /// Create with randomized params for unique prints.
-    pub fn random() -> Self {
+    pub fn random(rng: &mut super::PatternRng) -> Self {
         Self {
-            params: Params::random(),
+            params: Params::random(rng),
         }
     }
 }
@@ -0,0 +1,744 @@
+//! # Expression Pattern
+//!
+//! A pattern written as a formula instead of Rust, so new designs don't
+//! need a recompile. The formula is parsed once when it's set, then
+//! evaluated for every pixel using the [`crate::shader`] primitives.
+//!
+//! ```text
+//! estrella print expr --param formula="0.5 + 0.5*sin(r*20 - y/30)"
+//! ```
+//!
+//! ## Syntax
+//!
+//! Numbers, parentheses, `+ - * / %`, `^` (power, binds tightest, right to
+//! left) and `<`, `>` (1.0 when true, 0.0 otherwise). The result is clamped
+//! to 0.0 (white) .. 1.0 (black).
+//!
+//! | Variable | Meaning |
+//! |----------|---------|
+//! | `x`, `y` | pixel position |
+//! | `w`, `h` | image size in pixels |
+//! | `u`, `v` | position as a fraction of the size (0.0-1.0) |
+//! | `cx`, `cy` | center in pixels |
+//! | `r` | distance from the center, 1.0 at the left and right edges |
+//! | `a` | angle around the center in radians (-pi..pi) |
+//! | `t` | animation time, 0.0-1.0 over one loop |
+//! | `pi`, `tau` | constants |
+//!
+//! Functions: `sin cos tan asin acos atan atan2 abs sign floor ceil round
+//! fract sqrt exp ln pow min max clamp mod step`, and from the shader
+//! library `dist(x, y, cx, cy)`, `noise(x, y[, seed])`,
+//! `fbm(x, y, octaves[, seed])`, `wave_sin`, `wave_cos`, `wave_triangle`,
+//! `wave_sawtooth` (all `(coord, freq, phase)`),
+//! `wave_square(coord, freq, phase, duty)`, `smoothstep(e0, e1, x)`,
+//! `mix(a, b, t)`, `gamma(v, g)` and `clamp01(v)`.
+
+use crate::shader::{
+    clamp01, dist, fbm, gamma, lerp, noise2d, smoothstep, wave_cos, wave_sawtooth, wave_sin,
+    wave_square, wave_triangle,
+};
+use async_trait::async_trait;
+use rand::RngExt;
+use std::f32::consts::{PI, TAU};
+use std::fmt;
+
+/// Longest formula accepted, in bytes.
+pub const MAX_FORMULA_LEN: usize = 1024;
+
+/// Formulas picked from by [`Expr::random`].
+pub const EXAMPLES: &[&str] = &[
+    "0.5 + 0.5*sin(r*20 - y/30)",
+    "wave_sin(a, 8, r*12) * (1 - r)",
+    "fbm(u*6, v*6, 5) > 0.5",
+    "smoothstep(0.3, 0.7, noise(x/40, y/40)) * wave_triangle(r, 30, 0)",
+    "mod(floor(u*12) + floor(v*10), 2) * 0.8",
+    "0.5 + 0.5*sin(x/9 + 4*sin(y/47)) * cos(y/13 + 3*cos(x/61))",
+];
+
+/// A variable a formula can read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Var {
+    X,
+    Y,
+    W,
+    H,
+    U,
+    V,
+    Cx,
+    Cy,
+    R,
+    A,
+    T,
+}
+
+impl Var {
+    fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "x" => Var::X,
+            "y" => Var::Y,
+            "w" => Var::W,
+            "h" => Var::H,
+            "u" => Var::U,
+            "v" => Var::V,
+            "cx" => Var::Cx,
+            "cy" => Var::Cy,
+            "r" => Var::R,
+            "a" => Var::A,
+            "t" => Var::T,
+            _ => return None,
+        })
+    }
+}
+
+/// Values of every [`Var`] at one pixel.
+#[derive(Debug, Clone, Copy)]
+pub struct Vars {
+    values: [f32; 11],
+}
+
+impl Vars {
+    /// The variables at pixel (`x`, `y`) of a `width` × `height` image, at time `t`.
+    pub fn new(x: usize, y: usize, width: usize, height: usize, t: f32) -> Self {
+        let (x, y) = (x as f32, y as f32);
+        let (w, h) = (width.max(1) as f32, height.max(1) as f32);
+        let (cx, cy) = (w / 2.0, h / 2.0);
+        Vars {
+            values: [
+                x,
+                y,
+                w,
+                h,
+                x / w,
+                y / h,
+                cx,
+                cy,
+                dist(x, y, cx, cy) / cx,
+                (y - cy).atan2(x - cx),
+                t,
+            ],
+        }
+    }
+
+    fn get(&self, var: Var) -> f32 {
+        self.values[var as usize]
+    }
+}
+
+/// A function a formula can call.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Func {
+    Sin,
+    Cos,
+    Tan,
+    Asin,
+    Acos,
+    Atan,
+    Atan2,
+    Abs,
+    Sign,
+    Floor,
+    Ceil,
+    Round,
+    Fract,
+    Sqrt,
+    Exp,
+    Ln,
+    Pow,
+    Min,
+    Max,
+    Clamp,
+    Mod,
+    Step,
+    Dist,
+    Noise,
+    Fbm,
+    WaveSin,
+    WaveCos,
+    WaveTriangle,
+    WaveSawtooth,
+    WaveSquare,
+    Smoothstep,
+    Mix,
+    Gamma,
+    Clamp01,
+}
+
+/// Most arguments any [`Func`] takes.
+const MAX_ARGS: usize = 4;
+
+impl Func {
+    /// Look up a function by name, with how many arguments it takes.
+    fn from_name(name: &str) -> Option<(Self, usize, usize)> {
+        Some(match name {
+            "sin" => (Func::Sin, 1, 1),
+            "cos" => (Func::Cos, 1, 1),
+            "tan" => (Func::Tan, 1, 1),
+            "asin" => (Func::Asin, 1, 1),
+            "acos" => (Func::Acos, 1, 1),
+            "atan" => (Func::Atan, 1, 1),
+            "atan2" => (Func::Atan2, 2, 2),
+            "abs" => (Func::Abs, 1, 1),
+            "sign" => (Func::Sign, 1, 1),
+            "floor" => (Func::Floor, 1, 1),
+            "ceil" => (Func::Ceil, 1, 1),
+            "round" => (Func::Round, 1, 1),
+            "fract" => (Func::Fract, 1, 1),
+            "sqrt" => (Func::Sqrt, 1, 1),
+            "exp" => (Func::Exp, 1, 1),
+            "ln" | "log" => (Func::Ln, 1, 1),
+            "pow" => (Func::Pow, 2, 2),
+            "min" => (Func::Min, 2, 2),
+            "max" => (Func::Max, 2, 2),
+            "clamp" => (Func::Clamp, 3, 3),
+            "mod" => (Func::Mod, 2, 2),
+            "step" => (Func::Step, 2, 2),
+            "dist" => (Func::Dist, 4, 4),
+            "noise" => (Func::Noise, 2, 3),
+            "fbm" => (Func::Fbm, 3, 4),
+            "wave_sin" => (Func::WaveSin, 3, 3),
+            "wave_cos" => (Func::WaveCos, 3, 3),
+            "wave_triangle" => (Func::WaveTriangle, 3, 3),
+            "wave_sawtooth" => (Func::WaveSawtooth, 3, 3),
+            "wave_square" => (Func::WaveSquare, 4, 4),
+            "smoothstep" => (Func::Smoothstep, 3, 3),
+            "mix" | "lerp" => (Func::Mix, 3, 3),
+            "gamma" => (Func::Gamma, 2, 2),
+            "clamp01" => (Func::Clamp01, 1, 1),
+            _ => return None,
+        })
+    }
+
+    /// Apply to `args`, which has as many values as the call had arguments.
+    fn apply(self, args: &[f32]) -> f32 {
+        let arg = |i: usize| args.get(i).copied().unwrap_or(0.0);
+        let seed = |i: usize| args.get(i).map_or(0, |&s| s as u32);
+        let (a, b, c, d) = (arg(0), arg(1), arg(2), arg(3));
+        match self {
+            Func::Sin => a.sin(),
+            Func::Cos => a.cos(),
+            Func::Tan => a.tan(),
+            Func::Asin => a.asin(),
+            Func::Acos => a.acos(),
+            Func::Atan => a.atan(),
+            Func::Atan2 => a.atan2(b),
+            Func::Abs => a.abs(),
+            Func::Sign => {
+                if a == 0.0 {
+                    0.0
+                } else {
+                    a.signum()
+                }
+            }
+            Func::Floor => a.floor(),
+            Func::Ceil => a.ceil(),
+            Func::Round => a.round(),
+            Func::Fract => a - a.floor(),
+            Func::Sqrt => a.sqrt(),
+            Func::Exp => a.exp(),
+            Func::Ln => a.ln(),
+            Func::Pow => a.powf(b),
+            Func::Min => a.min(b),
+            Func::Max => a.max(b),
+            Func::Clamp => a.max(b).min(c),
+            Func::Mod => a.rem_euclid(b),
+            Func::Step => {
+                if b >= a {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+            Func::Dist => dist(a, b, c, d),
+            Func::Noise => noise2d(a, b, seed(2)),
+            Func::Fbm => fbm(a, b, (c.max(1.0) as usize).min(8), seed(3)),
+            Func::WaveSin => wave_sin(a, b, c),
+            Func::WaveCos => wave_cos(a, b, c),
+            Func::WaveTriangle => wave_triangle(a, b, c),
+            Func::WaveSawtooth => wave_sawtooth(a, b, c),
+            Func::WaveSquare => wave_square(a, b, c, d),
+            Func::Smoothstep => smoothstep(a, b, c),
+            Func::Mix => lerp(a, b, c),
+            Func::Gamma => gamma(a, b),
+            Func::Clamp01 => clamp01(a),
+        }
+    }
+}
+
+/// A binary operator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    Pow,
+    Less,
+    Greater,
+}
+
+impl BinOp {
+    fn apply(self, a: f32, b: f32) -> f32 {
+        match self {
+            BinOp::Add => a + b,
+            BinOp::Sub => a - b,
+            BinOp::Mul => a * b,
+            BinOp::Div => a / b,
+            BinOp::Rem => a.rem_euclid(b),
+            BinOp::Pow => a.powf(b),
+            BinOp::Less => (a < b) as u8 as f32,
+            BinOp::Greater => (a > b) as u8 as f32,
+        }
+    }
+}
+
+/// A parsed formula.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Formula {
+    Num(f32),
+    Var(Var),
+    Neg(Box<Formula>),
+    Binary(BinOp, Box<Formula>, Box<Formula>),
+    Call(Func, Vec<Formula>),
+}
+
+impl Formula {
+    /// Parse a formula, reporting the first mistake and where it is.
+    pub fn parse(source: &str) -> Result<Self, String> {
+        if source.len() > MAX_FORMULA_LEN {
+            return Err(format!(
+                "Formula is {} bytes; the limit is {}",
+                source.len(),
+                MAX_FORMULA_LEN
+            ));
+        }
+        let mut parser = Parser {
+            tokens: tokenize(source)?,
+            pos: 0,
+        };
+        let formula = parser.comparison()?;
+        match parser.peek() {
+            None => Ok(formula),
+            Some((token, col)) => Err(format!("Unexpected {} at column {}", token, col)),
+        }
+    }
+
+    /// Evaluate at one pixel.
+    pub fn eval(&self, vars: &Vars) -> f32 {
+        match self {
+            Formula::Num(n) => *n,
+            Formula::Var(var) => vars.get(*var),
+            Formula::Neg(inner) => -inner.eval(vars),
+            Formula::Binary(op, a, b) => op.apply(a.eval(vars), b.eval(vars)),
+            Formula::Call(func, args) => {
+                let mut values = [0.0; MAX_ARGS];
+                for (value, arg) in values.iter_mut().zip(args) {
+                    *value = arg.eval(vars);
+                }
+                func.apply(&values[..args.len()])
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Num(f32),
+    Ident(String),
+    Op(char),
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Num(n) => write!(f, "number {}", n),
+            Token::Ident(name) => write!(f, "'{}'", name),
+            Token::Op(c) => write!(f, "'{}'", c),
+        }
+    }
+}
+
+/// Split a formula into tokens, each with its 1-based column.
+fn tokenize(source: &str) -> Result<Vec<(Token, usize)>, String> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let col = i + 1;
+        if c.is_whitespace() {
+            i += 1;
+        } else if c.is_ascii_digit() || c == '.' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+            let text: String = chars[start..i].iter().collect();
+            let n = text
+                .parse()
+                .map_err(|_| format!("Invalid number '{}' at column {}", text, col))?;
+            tokens.push((Token::Num(n), col));
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            let name: String = chars[start..i].iter().collect();
+            tokens.push((Token::Ident(name.to_lowercase()), col));
+        } else if "+-*/%^<>(),".contains(c) {
+            tokens.push((Token::Op(c), col));
+            i += 1;
+        } else {
+            return Err(format!("Unexpected '{}' at column {}", c, col));
+        }
+    }
+    Ok(tokens)
+}
+
+/// Recursive descent, lowest precedence first:
+/// comparison → sum → product → unary → power → atom.
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&(Token, usize)> {
+        self.tokens.get(self.pos)
+    }
+
+    fn peek_op(&self) -> Option<char> {
+        match self.peek() {
+            Some((Token::Op(c), _)) => Some(*c),
+            _ => None,
+        }
+    }
+
+    fn expect_op(&mut self, op: char) -> Result<(), String> {
+        match self.tokens.get(self.pos) {
+            Some((Token::Op(c), _)) if *c == op => {
+                self.pos += 1;
+                Ok(())
+            }
+            Some((token, col)) => Err(format!(
+                "Expected '{}' at column {}, found {}",
+                op, col, token
+            )),
+            None => Err(format!("Expected '{}' at the end of the formula", op)),
+        }
+    }
+
+    fn comparison(&mut self) -> Result<Formula, String> {
+        let mut left = self.sum()?;
+        while let Some(op @ ('<' | '>')) = self.peek_op() {
+            self.pos += 1;
+            let op = if op == '<' {
+                BinOp::Less
+            } else {
+                BinOp::Greater
+            };
+            left = Formula::Binary(op, Box::new(left), Box::new(self.sum()?));
+        }
+        Ok(left)
+    }
+
+    fn sum(&mut self) -> Result<Formula, String> {
+        let mut left = self.product()?;
+        while let Some(op @ ('+' | '-')) = self.peek_op() {
+            self.pos += 1;
+            let op = if op == '+' { BinOp::Add } else { BinOp::Sub };
+            left = Formula::Binary(op, Box::new(left), Box::new(self.product()?));
+        }
+        Ok(left)
+    }
+
+    fn product(&mut self) -> Result<Formula, String> {
+        let mut left = self.unary()?;
+        while let Some(op @ ('*' | '/' | '%')) = self.peek_op() {
+            self.pos += 1;
+            let op = match op {
+                '*' => BinOp::Mul,
+                '/' => BinOp::Div,
+                _ => BinOp::Rem,
+            };
+            left = Formula::Binary(op, Box::new(left), Box::new(self.unary()?));
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Formula, String> {
+        match self.peek_op() {
+            Some('-') => {
+                self.pos += 1;
+                Ok(Formula::Neg(Box::new(self.unary()?)))
+            }
+            Some('+') => {
+                self.pos += 1;
+                self.unary()
+            }
+            _ => self.power(),
+        }
+    }
+
+    fn power(&mut self) -> Result<Formula, String> {
+        let base = self.atom()?;
+        if self.peek_op() == Some('^') {
+            self.pos += 1;
+            // Right-associative, and `2^-1` is allowed
+            let exponent = self.unary()?;
+            return Ok(Formula::Binary(
+                BinOp::Pow,
+                Box::new(base),
+                Box::new(exponent),
+            ));
+        }
+        Ok(base)
+    }
+
+    fn atom(&mut self) -> Result<Formula, String> {
+        let Some((token, col)) = self.tokens.get(self.pos).cloned() else {
+            return Err("Formula ends too early".to_string());
+        };
+        self.pos += 1;
+        match token {
+            Token::Num(n) => Ok(Formula::Num(n)),
+            Token::Op('(') => {
+                let inner = self.comparison()?;
+                self.expect_op(')')?;
+                Ok(inner)
+            }
+            Token::Ident(name) if self.peek_op() == Some('(') => {
+                self.pos += 1;
+                let (func, min, max) = Func::from_name(&name)
+                    .ok_or_else(|| format!("Unknown function '{}' at column {}", name, col))?;
+                let mut args = Vec::new();
+                if self.peek_op() != Some(')') {
+                    args.push(self.comparison()?);
+                    while self.peek_op() == Some(',') {
+                        self.pos += 1;
+                        args.push(self.comparison()?);
+                    }
+                }
+                self.expect_op(')')?;
+                if args.len() < min || args.len() > max {
+                    let expected = if min == max {
+                        min.to_string()
+                    } else {
+                        format!("{} to {}", min, max)
+                    };
+                    return Err(format!(
+                        "{}() at column {} takes {} arguments, got {}",
+                        name,
+                        col,
+                        expected,
+                        args.len()
+                    ));
+                }
+                Ok(Formula::Call(func, args))
+            }
+            Token::Ident(name) => match name.as_str() {
+                "pi" => Ok(Formula::Num(PI)),
+                "tau" => Ok(Formula::Num(TAU)),
+                _ => Var::from_name(&name).map(Formula::Var).ok_or_else(|| {
+                    format!(
+                        "Unknown variable '{}' at column {}. Available: x, y, w, h, u, v, cx, cy, r, a, t, pi, tau",
+                        name, col
+                    )
+                }),
+            },
+            token => Err(format!("Unexpected {} at column {}", token, col)),
+        }
+    }
+}
+
+/// Pattern drawn by a user-supplied formula.
+#[derive(Debug, Clone)]
+pub struct Expr {
+    name: &'static str,
+    source: String,
+    formula: Formula,
+}
+
+impl Default for Expr {
+    fn default() -> Self {
+        Self::golden()
+    }
+}
+
+impl Expr {
+    /// Create from a formula, or explain why it doesn't parse.
+    pub fn new(source: &str) -> Result<Self, String> {
+        Ok(Self {
+            name: "expr",
+            source: source.trim().to_string(),
+            formula: Formula::parse(source)?,
+        })
+    }
+
+    /// Report `name` as the pattern name, for formulas registered as
+    /// patterns of their own (see [`super::registry::register_formula`]).
+    pub fn with_name(self, name: &'static str) -> Self {
+        Self { name, ..self }
+    }
+
+    /// Create with golden (deterministic) params for reproducible output.
+    pub fn golden() -> Self {
+        Self::from_example(0)
+    }
+
+    /// Create with one of the [`EXAMPLES`], picked at random.
+    pub fn random(rng: &mut super::PatternRng) -> Self {
+        Self::from_example(rng.random_range(0..EXAMPLES.len()))
+    }
+
+    fn from_example(index: usize) -> Self {
+        let source = EXAMPLES[index];
+        Self {
+            name: "expr",
+            source: source.to_string(),
+            formula: Formula::parse(source).unwrap_or(Formula::Num(0.0)),
+        }
+    }
+
+    /// The formula as written.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    fn shade(&self, x: usize, y: usize, width: usize, height: usize, t: f32) -> f32 {
+        let v = self.formula.eval(&Vars::new(x, y, width, height, t));
+        // NaN (e.g. sqrt of a negative) prints as paper
+        if v.is_nan() { 0.0 } else { clamp01(v) }
+    }
+}
+
+#[async_trait]
+impl super::Pattern for Expr {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn intensity(&self, x: usize, y: usize, width: usize, height: usize) -> f32 {
+        self.shade(x, y, width, height, 0.0)
+    }
+
+    fn intensity_at_time(&self, x: usize, y: usize, width: usize, height: usize, t: f32) -> f32 {
+        self.shade(x, y, width, height, t)
+    }
+
+    fn params_description(&self) -> String {
+        format!("formula={}", self.source)
+    }
+
+    fn set_param(&mut self, name: &str, value: &str) -> Result<(), String> {
+        match name {
+            "formula" => {
+                *self = Self::new(value)?.with_name(self.name);
+                Ok(())
+            }
+            _ => Err(format!(
+                "Unknown param '{}' for {}. Available: formula",
+                name, self.name
+            )),
+        }
+    }
+
+    fn list_params(&self) -> Vec<(&'static str, String)> {
+        vec![("formula", self.source.clone())]
+    }
+
+    fn param_specs(&self) -> Vec<super::ParamSpec> {
+        vec![super::ParamSpec {
+            name: "formula",
+            label: "Formula",
+            param_type: super::ParamType::Text,
+            description: Some("Intensity per pixel, e.g. 0.5 + 0.5*sin(r*20 - y/30)"),
+        }]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::art::Pattern;
+
+    fn eval(source: &str) -> f32 {
+        Formula::parse(source)
+            .unwrap()
+            .eval(&Vars::new(30, 10, 100, 40, 0.5))
+    }
+
+    #[test]
+    fn test_precedence() {
+        assert_eq!(eval("1 + 2 * 3"), 7.0);
+        assert_eq!(eval("(1 + 2) * 3"), 9.0);
+        assert_eq!(eval("2 ^ 3 ^ 2"), 512.0);
+        assert_eq!(eval("-2 ^ 2"), -4.0);
+        assert_eq!(eval("2 ^ -1"), 0.5);
+        assert_eq!(eval("7 % 4 - 1"), 2.0);
+        assert_eq!(eval("1 + 1 > 1"), 1.0);
+        assert_eq!(eval("3 < 2"), 0.0);
+    }
+
+    #[test]
+    fn test_variables_and_functions() {
+        assert_eq!(eval("x + y"), 40.0);
+        assert_eq!(eval("u"), 0.3);
+        assert_eq!(eval("cx * 2 - w"), 0.0);
+        // (30, 10) is 20 left of and 10 above (50, 20); r is in half-widths
+        assert!((eval("r") - 500f32.sqrt() / 50.0).abs() < 1e-6);
+        assert_eq!(eval("dist(x, y, cx, cy) / cx"), eval("r"));
+        assert_eq!(eval("t"), 0.5);
+        assert_eq!(eval("max(min(5, 2), clamp(9, 0, 1))"), 2.0);
+        assert_eq!(eval("wave_sin(0, 1, 0)"), 0.5);
+        assert_eq!(eval("fract(2.25) + mod(-1, 3)"), 2.25);
+        assert!((eval("cos(pi)") + 1.0).abs() < 1e-6);
+        let n = eval("noise(x/7, y/7) + fbm(u, v, 3, 9)");
+        assert!((0.0..=2.0).contains(&n));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let err = |s: &str| Formula::parse(s).unwrap_err();
+        assert_eq!(err("sin(x"), "Expected ')' at the end of the formula");
+        assert!(err("x + q").contains("Unknown variable 'q' at column 5"));
+        assert!(err("blur(x)").contains("Unknown function 'blur'"));
+        assert!(err("min(x)").contains("takes 2 arguments, got 1"));
+        assert!(err("noise(x)").contains("takes 2 to 3 arguments"));
+        assert!(err("1 +").contains("ends too early"));
+        assert!(err("x y").contains("Unexpected 'y' at column 3"));
+        assert!(err("x $ 2").contains("Unexpected '$'"));
+        assert!(err(&"x+".repeat(600)).contains("limit"));
+    }
+
+    #[test]
+    fn test_pattern() {
+        let mut pattern = Expr::golden();
+        for y in (0..500).step_by(50) {
+            for x in (0..576).step_by(50) {
+                let v = pattern.intensity(x, y, 576, 500);
+                assert!((0.0..=1.0).contains(&v));
+            }
+        }
+
+        pattern.set_param("formula", "u").unwrap();
+        assert_eq!(pattern.intensity(0, 0, 100, 100), 0.0);
+        assert_eq!(pattern.intensity(50, 0, 100, 100), 0.5);
+        pattern.set_param("formula", "sqrt(-1) + 2").unwrap();
+        assert_eq!(pattern.intensity(0, 0, 100, 100), 0.0);
+        pattern.set_param("formula", "t").unwrap();
+        assert_eq!(pattern.intensity_at_time(0, 0, 100, 100, 0.25), 0.25);
+
+        // A bad formula leaves the old one in place
+        assert!(pattern.set_param("formula", "sin(").is_err());
+        assert_eq!(pattern.source(), "t");
+        assert!(pattern.set_param("scale", "2").is_err());
+    }
+
+    #[test]
+    fn test_examples_parse() {
+        for example in EXAMPLES {
+            assert!(Formula::parse(example).is_ok(), "{}", example);
+        }
+    }
+}
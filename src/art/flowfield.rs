@@ -48,8 +48,7 @@ impl Default for Params {
 
 impl Params {
     /// Generate randomized parameters for unique prints.
-    pub fn random() -> Self {
-        let mut rng = rand::rng();
+    pub fn random(rng: &mut super::PatternRng) -> Self {
         Self {
             noise_scale: rng.random_range(0.005..0.015),
             octaves: rng.random_range(3..6),
@@ -150,9 +149,9 @@ impl Flowfield {
         }
     }
 
-    pub fn random() -> Self {
+    pub fn random(rng: &mut super::PatternRng) -> Self {
         Self {
-            params: Params::random(),
+            params: Params::random(rng),
         }
     }
 }
@@ -50,7 +50,7 @@ impl ImagePattern {
         Self::default()
     }
 
-    pub fn random() -> Self {
+    pub fn random(_rng: &mut super::PatternRng) -> Self {
         Self {
             url: "https://picsum.photos/800/576".to_string(),
             buffer: None,
@@ -73,7 +73,7 @@ impl Microfeed {
     }
 
     // No randomization for microfeed - it's a diagnostic pattern
-    pub fn random() -> Self {
+    pub fn random(_rng: &mut super::PatternRng) -> Self {
         Self::golden()
     }
 }
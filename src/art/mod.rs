@@ -7,10 +7,14 @@
 //!
 //! 1. Create `src/art/mypattern.rs` with a struct implementing [`Pattern`]
 //! 2. Add `pub mod mypattern;` below
-//! 3. Add to [`PATTERNS`] array
+//! 3. Add an `entry!` line to [`PATTERN_ENTRIES`]
 //! 4. Run `make golden` to generate test files
+//!
+//! Patterns that live outside this crate are added at runtime instead,
+//! through the [`registry`].
 
 use async_trait::async_trait;
+use rand::SeedableRng;
 use serde::Serialize;
 
 use crate::render::context::RenderContext;
@@ -25,6 +29,7 @@ pub mod databend;
 pub mod density;
 pub mod erosion;
 pub mod estrella;
+pub mod expr;
 pub mod flowfield;
 pub mod glitch;
 pub mod image;
@@ -35,6 +40,7 @@ pub mod mycelium;
 pub mod overburn;
 pub mod plasma;
 pub mod reaction_diffusion;
+pub mod registry;
 pub mod riley;
 pub mod riley_check;
 pub mod riley_curve;
@@ -54,57 +60,141 @@ pub mod weave;
 pub mod woodgrain;
 pub mod zebra;
 
-/// All available patterns, in display order.
-pub const PATTERNS: &[&str] = &[
-    // Classic patterns
-    "ripple",
-    "waves",
-    "plasma",
-    "rings",
-    "topography",
-    "glitch",
-    // Op art
-    "riley",
-    "riley_check",
-    "riley_curve",
-    "vasarely",
-    "vasarely_hex",
-    "vasarely_bubbles",
-    "scintillate",
-    "tunnel",
-    "zebra",
-    // Generative/organic
-    "flowfield",
-    "erosion",
-    "crystal",
-    "mycelium",
-    // Mascot
-    "estrella",
-    // Glitch / Digital
-    "corrupt_barcode",
-    "databend",
-    "scanline_tear",
-    // Algorithmic / Mathematical
-    "moire",
-    "reaction_diffusion",
-    "attractor",
-    "automata",
-    "voronoi",
-    // Texture / Tactile
-    "crosshatch",
-    "stipple",
-    "woodgrain",
-    "weave",
-    // Diagnostic
-    "microfeed",
-    "density",
-    "overburn",
-    "jitter",
-    "calibration",
-    // External
-    "image",
+/// Group a pattern is listed under.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Category {
+    Classic,
+    OpArt,
+    Generative,
+    Mascot,
+    Glitch,
+    Algorithmic,
+    Texture,
+    Diagnostic,
+    /// Formulas, including patterns added through the [`registry`].
+    Custom,
+    /// Backed by outside resources (images).
+    External,
+}
+
+impl Category {
+    /// All categories, in display order.
+    pub const ALL: &[Category] = &[
+        Category::Classic,
+        Category::OpArt,
+        Category::Generative,
+        Category::Mascot,
+        Category::Glitch,
+        Category::Algorithmic,
+        Category::Texture,
+        Category::Diagnostic,
+        Category::Custom,
+        Category::External,
+    ];
+
+    /// Human-readable name for menus.
+    pub fn label(self) -> &'static str {
+        match self {
+            Category::Classic => "Classic",
+            Category::OpArt => "Op Art",
+            Category::Generative => "Generative",
+            Category::Mascot => "Mascot",
+            Category::Glitch => "Glitch / Digital",
+            Category::Algorithmic => "Algorithmic",
+            Category::Texture => "Texture",
+            Category::Diagnostic => "Diagnostic",
+            Category::Custom => "Custom",
+            Category::External => "External",
+        }
+    }
+}
+
+/// A built-in pattern: its name, category and constructors.
+#[derive(Debug, Clone, Copy)]
+pub struct PatternEntry {
+    pub name: &'static str,
+    pub category: Category,
+    /// Build with golden (deterministic) parameters.
+    pub golden_fn: fn() -> Box<dyn Pattern>,
+    /// Build with parameters drawn from the RNG.
+    pub random_fn: fn(&mut PatternRng) -> Box<dyn Pattern>,
+}
+
+/// Entry for a pattern type with `golden()` and `random(rng)` constructors.
+macro_rules! entry {
+    ($name:literal, $category:ident, $pattern:ty) => {
+        PatternEntry {
+            name: $name,
+            category: Category::$category,
+            golden_fn: || Box::new(<$pattern>::golden()),
+            random_fn: |rng| Box::new(<$pattern>::random(rng)),
+        }
+    };
+}
+
+/// Every built-in pattern, in display order. [`PATTERNS`], [`by_name_golden`]
+/// and [`by_name_random`] all read from this table.
+pub const PATTERN_ENTRIES: &[PatternEntry] = &[
+    entry!("ripple", Classic, ripple::Ripple),
+    entry!("waves", Classic, waves::Waves),
+    entry!("plasma", Classic, plasma::Plasma),
+    entry!("rings", Classic, rings::Rings),
+    entry!("topography", Classic, topography::Topography),
+    entry!("glitch", Classic, glitch::Glitch),
+    entry!("riley", OpArt, riley::Riley),
+    entry!("riley_check", OpArt, riley_check::RileyCheck),
+    entry!("riley_curve", OpArt, riley_curve::RileyCurve),
+    entry!("vasarely", OpArt, vasarely::Vasarely),
+    entry!("vasarely_hex", OpArt, vasarely_hex::VasarelyHex),
+    entry!("vasarely_bubbles", OpArt, vasarely_bubbles::VasarelyBubbles),
+    entry!("scintillate", OpArt, scintillate::Scintillate),
+    entry!("tunnel", OpArt, tunnel::Tunnel),
+    entry!("zebra", OpArt, zebra::Zebra),
+    entry!("flowfield", Generative, flowfield::Flowfield),
+    entry!("erosion", Generative, erosion::Erosion),
+    entry!("crystal", Generative, crystal::Crystal),
+    entry!("mycelium", Generative, mycelium::Mycelium),
+    entry!("estrella", Mascot, estrella::Estrella),
+    entry!("corrupt_barcode", Glitch, corrupt_barcode::CorruptBarcode),
+    entry!("databend", Glitch, databend::Databend),
+    entry!("scanline_tear", Glitch, scanline_tear::ScanlineTear),
+    entry!("moire", Algorithmic, moire::Moire),
+    entry!(
+        "reaction_diffusion",
+        Algorithmic,
+        reaction_diffusion::ReactionDiffusion
+    ),
+    entry!("attractor", Algorithmic, attractor::Attractor),
+    entry!("automata", Algorithmic, automata::Automata),
+    entry!("voronoi", Algorithmic, voronoi::Voronoi),
+    entry!("crosshatch", Texture, crosshatch::Crosshatch),
+    entry!("stipple", Texture, stipple::Stipple),
+    entry!("woodgrain", Texture, woodgrain::Woodgrain),
+    entry!("weave", Texture, weave::Weave),
+    entry!("microfeed", Diagnostic, microfeed::Microfeed),
+    entry!("density", Diagnostic, density::Density),
+    entry!("overburn", Diagnostic, overburn::Overburn),
+    entry!("jitter", Diagnostic, jitter::Jitter),
+    entry!("calibration", Diagnostic, calibration::Calibration),
+    entry!("expr", Custom, expr::Expr),
+    entry!("image", External, image::ImagePattern),
 ];
 
+/// Other names patterns can be looked up by.
+const ALIASES: &[(&str, &str)] = &[("demo", "calibration")];
+
+/// All available pattern names, in display order.
+pub const PATTERNS: &[&str] = &{
+    let mut names = [""; PATTERN_ENTRIES.len()];
+    let mut i = 0;
+    while i < names.len() {
+        names[i] = PATTERN_ENTRIES[i].name;
+        i += 1;
+    }
+    names
+};
+
 /// Input type for a pattern parameter.
 #[derive(Debug, Clone, Serialize)]
 #[serde(rename_all = "snake_case")]
@@ -228,6 +318,16 @@ pub trait Pattern: Send + Sync {
     /// Compute intensity at a pixel position. Returns 0.0 (white) to 1.0 (black).
     fn intensity(&self, x: usize, y: usize, width: usize, height: usize) -> f32;
 
+    /// Intensity at time `t`, for animation strips (see
+    /// [`crate::render::animation`]). `t` runs from 0.0 to 1.0 over one loop,
+    /// so `t = 1.0` should look like `t = 0.0`.
+    ///
+    /// Defaults to the static [`Pattern::intensity`]: patterns that don't
+    /// move print the same frame every time.
+    fn intensity_at_time(&self, x: usize, y: usize, width: usize, height: usize, _t: f32) -> f32 {
+        self.intensity(x, y, width, height)
+    }
+
     /// Prepare for rendering at the given dimensions.
     ///
     /// Called once before rendering begins. Patterns that need external resources
@@ -281,108 +381,69 @@ pub fn by_name(name: &str) -> Option<Box<dyn Pattern>> {
     by_name_golden(name)
 }
 
+/// The built-in pattern called `name` (or one of its aliases).
+pub fn entry(name: &str) -> Option<&'static PatternEntry> {
+    let name = name.to_lowercase();
+    let name = ALIASES
+        .iter()
+        .find(|(alias, _)| *alias == name)
+        .map_or(name.as_str(), |(_, target)| target);
+    PATTERN_ENTRIES.iter().find(|entry| entry.name == name)
+}
+
+/// Category of a built-in or registered pattern.
+pub fn category(name: &str) -> Option<Category> {
+    match entry(name) {
+        Some(entry) => Some(entry.category),
+        None => registry::names()
+            .contains(&name.to_lowercase().as_str())
+            .then_some(Category::Custom),
+    }
+}
+
 /// Get a pattern by name with golden (deterministic) parameters.
 pub fn by_name_golden(name: &str) -> Option<Box<dyn Pattern>> {
-    match name.to_lowercase().as_str() {
-        "ripple" => Some(Box::new(ripple::Ripple::golden())),
-        "waves" => Some(Box::new(waves::Waves::golden())),
-        "plasma" => Some(Box::new(plasma::Plasma::golden())),
-        "rings" => Some(Box::new(rings::Rings::golden())),
-        "topography" => Some(Box::new(topography::Topography::golden())),
-        "glitch" => Some(Box::new(glitch::Glitch::golden())),
-        "riley" => Some(Box::new(riley::Riley::golden())),
-        "riley_check" => Some(Box::new(riley_check::RileyCheck::golden())),
-        "riley_curve" => Some(Box::new(riley_curve::RileyCurve::golden())),
-        "vasarely" => Some(Box::new(vasarely::Vasarely::golden())),
-        "vasarely_hex" => Some(Box::new(vasarely_hex::VasarelyHex::golden())),
-        "vasarely_bubbles" => Some(Box::new(vasarely_bubbles::VasarelyBubbles::golden())),
-        "scintillate" => Some(Box::new(scintillate::Scintillate::golden())),
-        "tunnel" => Some(Box::new(tunnel::Tunnel::golden())),
-        "zebra" => Some(Box::new(zebra::Zebra::golden())),
-        "flowfield" => Some(Box::new(flowfield::Flowfield::golden())),
-        "erosion" => Some(Box::new(erosion::Erosion::golden())),
-        "crystal" => Some(Box::new(crystal::Crystal::golden())),
-        "mycelium" => Some(Box::new(mycelium::Mycelium::golden())),
-        // Mascot
-        "estrella" => Some(Box::new(estrella::Estrella::golden())),
-        // Glitch / Digital
-        "corrupt_barcode" => Some(Box::new(corrupt_barcode::CorruptBarcode::golden())),
-        "databend" => Some(Box::new(databend::Databend::golden())),
-        "scanline_tear" => Some(Box::new(scanline_tear::ScanlineTear::golden())),
-        // Algorithmic / Mathematical
-        "moire" => Some(Box::new(moire::Moire::golden())),
-        "reaction_diffusion" => Some(Box::new(reaction_diffusion::ReactionDiffusion::golden())),
-        "attractor" => Some(Box::new(attractor::Attractor::golden())),
-        "automata" => Some(Box::new(automata::Automata::golden())),
-        "voronoi" => Some(Box::new(voronoi::Voronoi::golden())),
-        // Texture / Tactile
-        "crosshatch" => Some(Box::new(crosshatch::Crosshatch::golden())),
-        "stipple" => Some(Box::new(stipple::Stipple::golden())),
-        "woodgrain" => Some(Box::new(woodgrain::Woodgrain::golden())),
-        "weave" => Some(Box::new(weave::Weave::golden())),
-        // Diagnostic
-        "microfeed" => Some(Box::new(microfeed::Microfeed::golden())),
-        "density" => Some(Box::new(density::Density::golden())),
-        "overburn" => Some(Box::new(overburn::Overburn::golden())),
-        "jitter" => Some(Box::new(jitter::Jitter::golden())),
-        "calibration" | "demo" => Some(Box::new(calibration::Calibration::golden())),
-        // External
-        "image" => Some(Box::new(image::ImagePattern::golden())),
-        _ => None,
+    match entry(name) {
+        Some(entry) => Some((entry.golden_fn)()),
+        None => registry::golden(&name.to_lowercase()),
     }
 }
 
+/// Random number generator that `random(rng)` constructors draw their
+/// parameters from.
+///
+/// PCG's output is fixed for a given seed across releases (unlike
+/// [`rand::rngs::StdRng`]'s), so seeds printed in footers and recipe QR
+/// codes keep giving the same pattern.
+pub type PatternRng = rand_pcg::Pcg64;
+
 /// Get a pattern by name with randomized parameters for unique prints.
 pub fn by_name_random(name: &str) -> Option<Box<dyn Pattern>> {
-    match name.to_lowercase().as_str() {
-        "ripple" => Some(Box::new(ripple::Ripple::random())),
-        "waves" => Some(Box::new(waves::Waves::random())),
-        "plasma" => Some(Box::new(plasma::Plasma::random())),
-        "rings" => Some(Box::new(rings::Rings::random())),
-        "topography" => Some(Box::new(topography::Topography::random())),
-        "glitch" => Some(Box::new(glitch::Glitch::random())),
-        "riley" => Some(Box::new(riley::Riley::random())),
-        "riley_check" => Some(Box::new(riley_check::RileyCheck::random())),
-        "riley_curve" => Some(Box::new(riley_curve::RileyCurve::random())),
-        "vasarely" => Some(Box::new(vasarely::Vasarely::random())),
-        "vasarely_hex" => Some(Box::new(vasarely_hex::VasarelyHex::random())),
-        "vasarely_bubbles" => Some(Box::new(vasarely_bubbles::VasarelyBubbles::random())),
-        "scintillate" => Some(Box::new(scintillate::Scintillate::random())),
-        "tunnel" => Some(Box::new(tunnel::Tunnel::random())),
-        "zebra" => Some(Box::new(zebra::Zebra::random())),
-        "flowfield" => Some(Box::new(flowfield::Flowfield::random())),
-        "erosion" => Some(Box::new(erosion::Erosion::random())),
-        "crystal" => Some(Box::new(crystal::Crystal::random())),
-        "mycelium" => Some(Box::new(mycelium::Mycelium::random())),
-        // Mascot
-        "estrella" => Some(Box::new(estrella::Estrella::random())),
-        // Glitch / Digital
-        "corrupt_barcode" => Some(Box::new(corrupt_barcode::CorruptBarcode::random())),
-        "databend" => Some(Box::new(databend::Databend::random())),
-        "scanline_tear" => Some(Box::new(scanline_tear::ScanlineTear::random())),
-        // Algorithmic / Mathematical
-        "moire" => Some(Box::new(moire::Moire::random())),
-        "reaction_diffusion" => Some(Box::new(reaction_diffusion::ReactionDiffusion::random())),
-        "attractor" => Some(Box::new(attractor::Attractor::random())),
-        "automata" => Some(Box::new(automata::Automata::random())),
-        "voronoi" => Some(Box::new(voronoi::Voronoi::random())),
-        // Texture / Tactile
-        "crosshatch" => Some(Box::new(crosshatch::Crosshatch::random())),
-        "stipple" => Some(Box::new(stipple::Stipple::random())),
-        "woodgrain" => Some(Box::new(woodgrain::Woodgrain::random())),
-        "weave" => Some(Box::new(weave::Weave::random())),
-        // Diagnostic
-        "microfeed" => Some(Box::new(microfeed::Microfeed::random())),
-        "density" => Some(Box::new(density::Density::random())),
-        "overburn" => Some(Box::new(overburn::Overburn::random())),
-        "jitter" => Some(Box::new(jitter::Jitter::random())),
-        "calibration" | "demo" => Some(Box::new(calibration::Calibration::random())),
-        // External
-        "image" => Some(Box::new(image::ImagePattern::random())),
-        _ => None,
+    by_name_with_rng(name, &mut PatternRng::from_rng(&mut rand::rng()))
+}
+
+/// Get a pattern by name with parameters randomized from `seed`.
+///
+/// The same name and seed always give the same parameters, so a print
+/// can be made again from the seed in its footer.
+pub fn by_name_seeded(name: &str, seed: u64) -> Option<Box<dyn Pattern>> {
+    by_name_with_rng(name, &mut PatternRng::seed_from_u64(seed))
+}
+
+/// Get a pattern by name with parameters drawn from `rng`.
+pub fn by_name_with_rng(name: &str, rng: &mut PatternRng) -> Option<Box<dyn Pattern>> {
+    match entry(name) {
+        Some(entry) => Some((entry.random_fn)(rng)),
+        None => registry::random(&name.to_lowercase(), rng),
     }
 }
 
+/// A fresh seed for [`by_name_seeded`]. Seeds fit in 32 bits so they
+/// survive a round trip through JSON numbers in a browser.
+pub fn random_seed() -> u64 {
+    u64::from(rand::random::<u32>())
+}
+
 /// Clamp a value to [0.0, 1.0].
 #[inline]
 pub fn clamp01(v: f32) -> f32 {
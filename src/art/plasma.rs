@@ -12,6 +12,7 @@
 use crate::shader::{clamp01, dist, gamma};
 use async_trait::async_trait;
 use rand::RngExt;
+use std::f32::consts::TAU;
 use std::fmt;
 
 /// Parameters for the plasma effect.
@@ -49,8 +50,7 @@ impl Default for Params {
 
 impl Params {
     /// Generate randomized parameters for unique prints.
-    pub fn random() -> Self {
-        let mut rng = rand::rng();
+    pub fn random(rng: &mut super::PatternRng) -> Self {
         Self {
             freq1: rng.random_range(7.0..18.0),
             freq2: rng.random_range(12.0..30.0),
@@ -83,6 +83,13 @@ impl fmt::Display for Params {
 ///
 /// Returns intensity in [0.0, 1.0] with internal gamma applied.
 pub fn shade(x: usize, y: usize, width: usize, height: usize, params: &Params) -> f32 {
+    shade_at(x, y, width, height, params, 0.0)
+}
+
+/// [`shade`] at time `t` (0.0-1.0): the four waves drift in alternating
+/// directions, one period per loop.
+pub fn shade_at(x: usize, y: usize, width: usize, height: usize, params: &Params, t: f32) -> f32 {
+    let phase = TAU * t;
     let xf = x as f32;
     let yf = y as f32;
     let wf = width as f32;
@@ -92,10 +99,10 @@ pub fn shade(x: usize, y: usize, width: usize, height: usize, params: &Params) -
     let cy = hf * params.center_y;
 
     // Multiple overlapping sine waves using shader distance primitive
-    let plasma = (xf / params.freq1).sin()
-        + ((xf + yf) / params.freq2).sin()
-        + (yf / params.freq3).cos()
-        + (dist(xf, yf, cx, cy) / params.freq4).sin();
+    let plasma = (xf / params.freq1 + phase).sin()
+        + ((xf + yf) / params.freq2 - phase).sin()
+        + (yf / params.freq3 + phase).cos()
+        + (dist(xf, yf, cx, cy) / params.freq4 - phase).sin();
 
     // Normalize from roughly [-4, 4] to [0, 1]
     let normalized = (plasma + 4.0) / 8.0;
@@ -123,9 +130,9 @@ impl Plasma {
         }
     }
 
-    pub fn random() -> Self {
+    pub fn random(rng: &mut super::PatternRng) -> Self {
         Self {
-            params: Params::random(),
+            params: Params::random(rng),
         }
     }
 }
@@ -140,6 +147,10 @@ impl super::Pattern for Plasma {
         shade(x, y, width, height, &self.params)
     }
 
+    fn intensity_at_time(&self, x: usize, y: usize, width: usize, height: usize, t: f32) -> f32 {
+        shade_at(x, y, width, height, &self.params, t)
+    }
+
     fn params_description(&self) -> String {
         self.params.to_string()
     }
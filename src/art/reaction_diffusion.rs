@@ -44,8 +44,7 @@ impl Default for Params {
 }
 
 impl Params {
-    pub fn random() -> Self {
-        let mut rng = rand::rng();
+    pub fn random(rng: &mut super::PatternRng) -> Self {
         // Different feed/kill ratios produce different patterns:
         // stripes: f=0.022, k=0.051
         // spots: f=0.035, k=0.065
@@ -152,9 +151,9 @@ impl ReactionDiffusion {
         }
     }
 
-    pub fn random() -> Self {
+    pub fn random(rng: &mut super::PatternRng) -> Self {
         Self {
-            params: Params::random(),
+            params: Params::random(rng),
         }
     }
 }
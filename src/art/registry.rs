@@ -0,0 +1,257 @@
+//! # Pattern Registry
+//!
+//! Patterns added at runtime, next to the built-in ones, so applications
+//! and plugin directories can ship patterns without patching this crate.
+//!
+//! A registered pattern is found by [`super::by_name`],
+//! [`super::by_name_golden`], [`super::by_name_random`] and
+//! [`super::by_name_seeded`], and listed by
+//! [`crate::render::patterns::list_patterns`] after the built-ins, so the
+//! CLI, TUI and server all pick it up:
+//!
+//! ```
+//! use estrella::art::{self, registry, ripple::Ripple};
+//!
+//! registry::register_pattern(
+//!     "calm_ripple",
+//!     || Box::new(Ripple::golden()),
+//!     |rng| Box::new(Ripple::random(rng)),
+//! )
+//! .unwrap();
+//! assert!(art::by_name("calm_ripple").is_some());
+//!
+//! // An expr formula becomes a pattern of its own
+//! registry::register_formula("bullseye", "wave_square(r, 12, 0, 0.5)").unwrap();
+//! assert_eq!(art::by_name("bullseye").unwrap().name(), "bullseye");
+//! ```
+//!
+//! [`load_formula_dir`] registers every `NAME.expr` file in a directory;
+//! the CLI does this for `--pattern-dir`. Lines starting with `#` in a
+//! formula file are comments.
+//!
+//! Names are lowercase ASCII letters, digits and `_`, and can't shadow a
+//! built-in pattern. Registered names live for the rest of the process.
+
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+
+use super::expr::Expr;
+use super::{Pattern, PatternRng};
+
+/// Longest allowed pattern name.
+pub const MAX_NAME_LEN: usize = 32;
+
+/// Builds a fresh pattern instance.
+type Factory = Arc<dyn Fn() -> Box<dyn Pattern> + Send + Sync>;
+
+/// Builds a fresh pattern instance with parameters drawn from the RNG.
+type RandomFactory = Arc<dyn Fn(&mut PatternRng) -> Box<dyn Pattern> + Send + Sync>;
+
+struct Registered {
+    name: &'static str,
+    golden: Factory,
+    random: RandomFactory,
+}
+
+static REGISTRY: RwLock<Vec<Registered>> = RwLock::new(Vec::new());
+
+/// Register a pattern under `name`.
+///
+/// `golden` builds the pattern with fixed parameters (used by `--golden`
+/// and previews), `random` with fresh ones for each print. `random` must
+/// draw them only from the RNG it is given, so a seed prints the same
+/// pattern again.
+pub fn register_pattern<G, R>(name: &str, golden: G, random: R) -> Result<(), String>
+where
+    G: Fn() -> Box<dyn Pattern> + Send + Sync + 'static,
+    R: Fn(&mut PatternRng) -> Box<dyn Pattern> + Send + Sync + 'static,
+{
+    let name = claim_name(name)?;
+    insert(name, Arc::new(golden), Arc::new(random))
+}
+
+/// Register an [`Expr`] formula as a pattern called `name`.
+pub fn register_formula(name: &str, formula: &str) -> Result<(), String> {
+    let expr = Expr::new(formula).map_err(|e| format!("Pattern '{}': {}", name, e))?;
+    let name = claim_name(name)?;
+    let expr = expr.with_name(name);
+    let golden = expr.clone();
+    insert(
+        name,
+        Arc::new(move || Box::new(golden.clone())),
+        Arc::new(move |_| Box::new(expr.clone())),
+    )
+}
+
+/// Register every `NAME.expr` file in `dir` as a formula pattern.
+///
+/// Returns the registered names, sorted. Stops at the first file that
+/// can't be read or doesn't parse.
+pub fn load_formula_dir(dir: &Path) -> Result<Vec<String>, String> {
+    let entries = std::fs::read_dir(dir)
+        .map_err(|e| format!("Cannot read pattern directory {}: {}", dir.display(), e))?;
+    let mut files: Vec<_> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "expr"))
+        .collect();
+    files.sort();
+
+    let mut names = Vec::new();
+    for path in files {
+        let Some(name) = path.file_stem().and_then(|stem| stem.to_str()) else {
+            continue;
+        };
+        let source = std::fs::read_to_string(&path)
+            .map_err(|e| format!("Cannot read {}: {}", path.display(), e))?;
+        register_formula(name, &formula_source(&source))
+            .map_err(|e| format!("{}: {}", path.display(), e))?;
+        names.push(name.to_string());
+    }
+    Ok(names)
+}
+
+/// Names of registered patterns, in registration order.
+pub fn names() -> Vec<&'static str> {
+    read().iter().map(|entry| entry.name).collect()
+}
+
+/// A registered pattern with golden parameters.
+pub(crate) fn golden(name: &str) -> Option<Box<dyn Pattern>> {
+    let factory = read()
+        .iter()
+        .find(|entry| entry.name == name)
+        .map(|entry| entry.golden.clone())?;
+    Some(factory())
+}
+
+/// A registered pattern with parameters drawn from `rng`.
+pub(crate) fn random(name: &str, rng: &mut PatternRng) -> Option<Box<dyn Pattern>> {
+    let factory = read()
+        .iter()
+        .find(|entry| entry.name == name)
+        .map(|entry| entry.random.clone())?;
+    Some(factory(rng))
+}
+
+fn read() -> std::sync::RwLockReadGuard<'static, Vec<Registered>> {
+    REGISTRY.read().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Check that `name` is valid and free, and make it `'static`.
+fn claim_name(name: &str) -> Result<&'static str, String> {
+    check_name(name)?;
+    // Also finds earlier registrations, so this must run unlocked
+    if super::by_name_golden(name).is_some() {
+        return Err(format!("Pattern '{}' already exists", name));
+    }
+    Ok(Box::leak(name.to_string().into_boxed_str()))
+}
+
+fn insert(name: &'static str, golden: Factory, random: RandomFactory) -> Result<(), String> {
+    let mut registry = REGISTRY.write().unwrap_or_else(|e| e.into_inner());
+    if registry.iter().any(|entry| entry.name == name) {
+        return Err(format!("Pattern '{}' already exists", name));
+    }
+    registry.push(Registered {
+        name,
+        golden,
+        random,
+    });
+    Ok(())
+}
+
+fn check_name(name: &str) -> Result<(), String> {
+    let valid = !name.is_empty()
+        && name.len() <= MAX_NAME_LEN
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+    if valid && name != "all" {
+        Ok(())
+    } else {
+        Err(format!(
+            "Invalid pattern name '{}' (use a-z, 0-9 and _, up to {} characters)",
+            name, MAX_NAME_LEN
+        ))
+    }
+}
+
+/// The formula in a `.expr` file: its non-comment lines, joined.
+fn formula_source(file: &str) -> String {
+    file.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::art::{by_name_golden, by_name_random, by_name_seeded, ripple::Ripple};
+
+    #[test]
+    fn test_register_pattern() {
+        register_pattern(
+            "test_ripple",
+            || Box::new(Ripple::golden()),
+            |rng| Box::new(Ripple::random(rng)),
+        )
+        .unwrap();
+        assert!(names().contains(&"test_ripple"));
+        assert_eq!(by_name_golden("TEST_RIPPLE").unwrap().name(), "ripple");
+        assert!(by_name_random("test_ripple").is_some());
+        // Seeds reach registered patterns too
+        assert_eq!(
+            by_name_seeded("test_ripple", 7).unwrap().list_params(),
+            by_name_seeded("ripple", 7).unwrap().list_params()
+        );
+
+        let again = register_pattern(
+            "test_ripple",
+            || Box::new(Ripple::golden()),
+            |_| Box::new(Ripple::golden()),
+        );
+        assert!(again.unwrap_err().contains("already exists"));
+    }
+
+    #[test]
+    fn test_names_are_checked() {
+        for name in ["ripple", "demo", "all", "", "Bad", "a-b", "../x"] {
+            assert!(
+                register_formula(name, "u").is_err(),
+                "{:?} should be rejected",
+                name
+            );
+        }
+        assert!(register_formula("test_broken", "sin(").is_err());
+        assert!(by_name_golden("test_broken").is_none());
+    }
+
+    #[test]
+    fn test_load_formula_dir() {
+        let dir = std::env::temp_dir().join(format!("estrella-patterns-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("test_stripes.expr"),
+            "# Vertical stripes\nwave_square(x, 0.05,\n  0, 0.5)\n",
+        )
+        .unwrap();
+        std::fs::write(dir.join("test_fade.expr"), "u").unwrap();
+        std::fs::write(dir.join("notes.txt"), "not a pattern").unwrap();
+
+        let loaded = load_formula_dir(&dir);
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(loaded.unwrap(), ["test_fade", "test_stripes"]);
+
+        let mut stripes = by_name_golden("test_stripes").unwrap();
+        assert_eq!(stripes.name(), "test_stripes");
+        assert_eq!(
+            stripes.list_params(),
+            [("formula", "wave_square(x, 0.05, 0, 0.5)".to_string())]
+        );
+        stripes.set_param("formula", "1 - u").unwrap();
+        assert_eq!(stripes.name(), "test_stripes");
+        assert_eq!(stripes.intensity(0, 0, 10, 10), 1.0);
+    }
+}
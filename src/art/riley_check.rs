@@ -48,8 +48,7 @@ impl Default for Params {
 }
 
 impl Params {
-    pub fn random() -> Self {
-        let mut rng = rand::rng();
+    pub fn random(rng: &mut super::PatternRng) -> Self {
         Self {
             cell_size: rng.random_range(12.0..28.0),
             wave_amplitude: rng.random_range(0.2..0.6),
@@ -123,9 +122,9 @@ impl RileyCheck {
         }
     }
 
-    pub fn random() -> Self {
+    pub fn random(rng: &mut super::PatternRng) -> Self {
         Self {
-            params: Params::random(),
+            params: Params::random(rng),
         }
     }
 }
@@ -47,8 +47,7 @@ impl Default for Params {
 }
 
 impl Params {
-    pub fn random() -> Self {
-        let mut rng = rand::rng();
+    pub fn random(rng: &mut super::PatternRng) -> Self {
         Self {
             stripe_width: rng.random_range(8.0..18.0),
             curve_strength: rng.random_range(40.0..120.0),
@@ -132,9 +131,9 @@ impl RileyCurve {
         }
     }
 
-    pub fn random() -> Self {
+    pub fn random(rng: &mut super::PatternRng) -> Self {
         Self {
-            params: Params::random(),
+            params: Params::random(rng),
         }
     }
 }
@@ -14,6 +14,7 @@
 use crate::shader::{clamp01, dist, gamma, lerp, wave_cos, wave_sin};
 use async_trait::async_trait;
 use rand::RngExt;
+use std::f32::consts::TAU;
 use std::fmt;
 
 /// Parameters for the ripple effect.
@@ -64,8 +65,7 @@ impl Params {
     }
 
     /// Generate randomized parameters for unique prints.
-    pub fn random() -> Self {
-        let mut rng = rand::rng();
+    pub fn random(rng: &mut super::PatternRng) -> Self {
         Self {
             center_x: rng.random_range(0.3..0.7),
             center_y: rng.random_range(0.3..0.7),
@@ -92,6 +92,12 @@ impl fmt::Display for Params {
 ///
 /// Returns intensity in [0.0, 1.0] with gamma applied.
 pub fn shade(x: usize, y: usize, width: usize, height: usize, params: &Params) -> f32 {
+    shade_at(x, y, width, height, params, 0.0)
+}
+
+/// [`shade`] at time `t` (0.0-1.0): the rings travel outward one
+/// wavelength per loop.
+pub fn shade_at(x: usize, y: usize, width: usize, height: usize, params: &Params, t: f32) -> f32 {
     // Border check
     if params.border > 0.0 && super::in_border(x, y, width, height, params.border) {
         return 1.0;
@@ -110,7 +116,7 @@ pub fn shade(x: usize, y: usize, width: usize, height: usize, params: &Params) -
     let r = dist(xf, yf, cx, cy);
 
     // Ripple: concentric circles with vertical drift
-    let ripple = wave_cos(r, 1.0 / params.scale, -yf / params.drift);
+    let ripple = wave_cos(r, 1.0 / params.scale, -yf / params.drift - TAU * t);
 
     // Wobble: interference pattern
     let wobble = wave_sin(xf, 1.0 / 37.0, 0.7 * (yf / 53.0).cos());
@@ -142,9 +148,9 @@ impl Ripple {
     }
 
     /// Create with randomized params for unique prints.
-    pub fn random() -> Self {
+    pub fn random(rng: &mut super::PatternRng) -> Self {
         Self {
-            params: Params::random(),
+            params: Params::random(rng),
         }
     }
 
@@ -166,6 +172,10 @@ impl super::Pattern for Ripple {
         shade(x, y, width, height, &self.params)
     }
 
+    fn intensity_at_time(&self, x: usize, y: usize, width: usize, height: usize, t: f32) -> f32 {
+        shade_at(x, y, width, height, &self.params, t)
+    }
+
     fn params_description(&self) -> String {
         self.params.to_string()
     }
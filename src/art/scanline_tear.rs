@@ -47,8 +47,7 @@ impl Default for Params {
 }
 
 impl Params {
-    pub fn random() -> Self {
-        let mut rng = rand::rng();
+    pub fn random(rng: &mut super::PatternRng) -> Self {
         Self {
             pattern_freq: rng.random_range(0.01..0.04),
             tear_intensity: rng.random_range(0.4..0.9),
@@ -177,9 +176,9 @@ impl ScanlineTear {
         }
     }
 
-    pub fn random() -> Self {
+    pub fn random(rng: &mut super::PatternRng) -> Self {
         Self {
-            params: Params::random(),
+            params: Params::random(rng),
         }
     }
 }
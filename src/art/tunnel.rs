@@ -47,8 +47,7 @@ impl Default for Params {
 }
 
 impl Params {
-    pub fn random() -> Self {
-        let mut rng = rand::rng();
+    pub fn random(rng: &mut super::PatternRng) -> Self {
         Self {
             frame_thickness: rng.random_range(8.0..25.0),
             gap_thickness: rng.random_range(8.0..25.0),
@@ -141,9 +140,9 @@ impl Tunnel {
         }
     }
 
-    pub fn random() -> Self {
+    pub fn random(rng: &mut super::PatternRng) -> Self {
         Self {
-            params: Params::random(),
+            params: Params::random(rng),
         }
     }
 }
@@ -10,7 +10,7 @@
 
 use crate::shader::*;
 use async_trait::async_trait;
-use rand::RngExt;
+use rand::{RngExt, SeedableRng};
 use std::fmt;
 
 /// A single bubble/sphere distortion.
@@ -73,8 +73,7 @@ impl Default for Params {
 }
 
 impl Params {
-    pub fn random() -> Self {
-        let mut rng = rand::rng();
+    pub fn random(rng: &mut super::PatternRng) -> Self {
         let num_bubbles = rng.random_range(2..6);
         let mut bubbles = Vec::with_capacity(num_bubbles);
 
@@ -189,9 +188,9 @@ impl VasarelyBubbles {
         }
     }
 
-    pub fn random() -> Self {
+    pub fn random(rng: &mut super::PatternRng) -> Self {
         Self {
-            params: Params::random(),
+            params: Params::random(rng),
         }
     }
 }
@@ -231,8 +230,9 @@ impl super::Pattern for VasarelyBubbles {
             "checkerboard" => self.params.checkerboard = parse_bool(value)?,
             "num_bubbles" => {
                 let n = parse_usize(value)?;
-                // Regenerate bubbles with random positions
-                let mut rng = rand::rng();
+                // Regenerate bubbles, placed from the count so a recipe
+                // that sets it reprints the same
+                let mut rng = super::PatternRng::seed_from_u64(n as u64);
                 self.params.bubbles.clear();
                 for _ in 0..n {
                     self.params.bubbles.push(Bubble {
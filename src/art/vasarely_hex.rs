@@ -45,8 +45,7 @@ impl Default for Params {
 }
 
 impl Params {
-    pub fn random() -> Self {
-        let mut rng = rand::rng();
+    pub fn random(rng: &mut super::PatternRng) -> Self {
         // Randomize which face is which shade
         let mut intensities = [
             rng.random_range(0.0..0.3),
@@ -135,9 +134,9 @@ impl VasarelyHex {
         }
     }
 
-    pub fn random() -> Self {
+    pub fn random(rng: &mut super::PatternRng) -> Self {
         Self {
-            params: Params::random(),
+            params: Params::random(rng),
         }
     }
 }
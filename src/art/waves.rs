@@ -14,6 +14,7 @@
 use crate::shader::{clamp01, dist, gamma, normalize};
 use async_trait::async_trait;
 use rand::RngExt;
+use std::f32::consts::TAU;
 use std::fmt;
 
 /// Parameters for the waves effect.
@@ -51,8 +52,7 @@ impl Default for Params {
 
 impl Params {
     /// Generate randomized parameters for unique prints.
-    pub fn random() -> Self {
-        let mut rng = rand::rng();
+    pub fn random(rng: &mut super::PatternRng) -> Self {
         Self {
             horiz_freq: rng.random_range(12.0..30.0),
             vert_freq: rng.random_range(15.0..35.0),
@@ -85,6 +85,13 @@ impl fmt::Display for Params {
 ///
 /// Returns intensity in [0.0, 1.0] with gamma applied.
 pub fn shade(x: usize, y: usize, width: usize, height: usize, params: &Params) -> f32 {
+    shade_at(x, y, width, height, params, 0.0)
+}
+
+/// [`shade`] at time `t` (0.0-1.0): each oscillator shifts one full
+/// period per loop.
+pub fn shade_at(x: usize, y: usize, width: usize, height: usize, params: &Params, t: f32) -> f32 {
+    let phase = TAU * t;
     let xf = x as f32;
     let yf = y as f32;
     let wf = width as f32;
@@ -96,13 +103,13 @@ pub fn shade(x: usize, y: usize, width: usize, height: usize, params: &Params) -
     let r = dist(nx, ny, 0.0, 0.0);
 
     // Horizontal waves with vertical modulation
-    let horiz = (xf / params.horiz_freq + 0.7 * (yf / 37.0).sin()).sin();
+    let horiz = (xf / params.horiz_freq + 0.7 * (yf / 37.0).sin() + phase).sin();
 
     // Vertical waves with horizontal modulation
-    let vert = (yf / params.vert_freq + 0.9 * (xf / 41.0).cos()).cos();
+    let vert = (yf / params.vert_freq + 0.9 * (xf / 41.0).cos() - phase).cos();
 
     // Radial waves with vertical drift
-    let radial = (r * params.radial_freq - yf / 29.0).cos();
+    let radial = (r * params.radial_freq - yf / 29.0 - phase).cos();
 
     // Normalize to [0, 1]
     let horiz_norm = 0.5 + 0.5 * horiz;
@@ -136,9 +143,9 @@ impl Waves {
         }
     }
 
-    pub fn random() -> Self {
+    pub fn random(rng: &mut super::PatternRng) -> Self {
         Self {
-            params: Params::random(),
+            params: Params::random(rng),
         }
     }
 }
@@ -153,6 +160,10 @@ impl super::Pattern for Waves {
         shade(x, y, width, height, &self.params)
     }
 
+    fn intensity_at_time(&self, x: usize, y: usize, width: usize, height: usize, t: f32) -> f32 {
+        shade_at(x, y, width, height, &self.params, t)
+    }
+
     fn params_description(&self) -> String {
         self.params.to_string()
     }
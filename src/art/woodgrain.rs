@@ -50,8 +50,7 @@ impl Default for Params {
 }
 
 impl Params {
-    pub fn random() -> Self {
-        let mut rng = rand::rng();
+    pub fn random(rng: &mut super::PatternRng) -> Self {
         Self {
             ring_spacing: rng.random_range(5.0..15.0),
             ring_thickness: rng.random_range(1.0..4.0),
@@ -193,9 +192,9 @@ impl Woodgrain {
         }
     }
 
-    pub fn random() -> Self {
+    pub fn random(rng: &mut super::PatternRng) -> Self {
         Self {
-            params: Params::random(),
+            params: Params::random(rng),
         }
     }
 }
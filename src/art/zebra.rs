@@ -54,8 +54,7 @@ impl Default for Params {
 }
 
 impl Params {
-    pub fn random() -> Self {
-        let mut rng = rand::rng();
+    pub fn random(rng: &mut super::PatternRng) -> Self {
         Self {
             stripe_width: rng.random_range(10.0..28.0),
             wave1_amp: rng.random_range(20.0..60.0),
@@ -126,9 +125,9 @@ impl Zebra {
         }
     }
 
-    pub fn random() -> Self {
+    pub fn random(rng: &mut super::PatternRng) -> Self {
         Self {
-            params: Params::random(),
+            params: Params::random(rng),
         }
     }
 }
@@ -0,0 +1,192 @@
+//! # Estrella Lite
+//!
+//! Print-only command-line interface for small devices: JSON documents and
+//! raw StarPRNT bytes, without the HTTP server, web UI, patterns or photo
+//! commands of the full `estrella` binary.
+//!
+//! Build it without default features so the server, pattern and image
+//! stacks aren't compiled in:
+//!
+//! ```bash
+//! cargo build --profile lite --bin estrella-lite --no-default-features
+//! ```
+//!
+//! Documents with `image` components need `--features images`; without it
+//! they fail like any other unprintable component (see `on_error`).
+//!
+//! ## Usage
+//!
+//! ```bash
+//! # Print a JSON document (any supported version)
+//! estrella-lite print receipt.json
+//!
+//! # Read the document from stdin
+//! curl -s https://example.com/agenda.json | estrella-lite print
+//!
+//! # Write the printer bytes to a file instead of printing
+//! estrella-lite print receipt.json --output receipt.bin
+//!
+//! # Allow file:///home/pi/logos/... images in the document (`images` feature)
+//! estrella-lite print receipt.json --image-dir /home/pi/logos
+//!
+//! # Send bytes as they are
+//! estrella-lite raw receipt.bin --device tcp://192.168.1.50
+//! ```
+
+use clap::{Parser, Subcommand};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+use estrella::document::{OnError, compat};
+use estrella::{EstrellaError, PrinterConfig, transport};
+
+/// Estrella Lite - print JSON documents and raw bytes
+#[derive(Parser, Debug)]
+#[command(name = "estrella-lite")]
+#[command(author, version, about, long_about = None)]
+struct Cli {
+    #[command(subcommand)]
+    command: Commands,
+}
+
+#[derive(Subcommand, Debug)]
+enum Commands {
+    /// Print a JSON document
+    Print {
+        /// Document file ("-" or omitted: read stdin)
+        file: Option<PathBuf>,
+
+        /// Printer device path, or tcp://host[:port] for a network printer
+        #[arg(long, default_value = "/dev/rfcomm0")]
+        device: String,
+
+        /// Printer model (tsp650ii, tsp100, tsp700ii, mc-print2, mc-print3, sm-l200)
+        /// or paper width for other printers (58mm, 80mm, 112mm)
+        #[arg(long, default_value = "tsp650ii")]
+        printer: PrinterConfig,
+
+        /// Write the printer bytes to this file instead of printing
+        #[arg(long, value_name = "FILE")]
+        output: Option<PathBuf>,
+
+        /// Directory that file:// images may be read from
+        /// (can be used multiple times; local images are refused without one)
+        #[cfg(feature = "images")]
+        #[arg(long = "image-dir", value_name = "DIR")]
+        image_dirs: Vec<PathBuf>,
+
+        /// Largest file:// or data: image accepted, in bytes
+        #[cfg(feature = "images")]
+        #[arg(long, default_value_t = estrella::document::DEFAULT_MAX_IMAGE_BYTES)]
+        max_image_bytes: usize,
+    },
+
+    /// Send raw StarPRNT bytes to the printer
+    Raw {
+        /// Byte file ("-" or omitted: read stdin)
+        file: Option<PathBuf>,
+
+        /// Printer device path, or tcp://host[:port] for a network printer
+        #[arg(long, default_value = "/dev/rfcomm0")]
+        device: String,
+    },
+}
+
+fn main() {
+    if let Err(e) = run() {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    }
+}
+
+fn run() -> Result<(), EstrellaError> {
+    match Cli::parse().command {
+        Commands::Print {
+            file,
+            device,
+            printer,
+            output,
+            #[cfg(feature = "images")]
+            image_dirs,
+            #[cfg(feature = "images")]
+            max_image_bytes,
+        } => {
+            let input = read_input(file.as_deref())?;
+            let value = serde_json::from_slice(&input)
+                .map_err(|e| EstrellaError::InvalidCommand(format!("Invalid JSON: {}", e)))?;
+            let upgraded = compat::parse_document(value)
+                .map_err(|e| EstrellaError::InvalidCommand(format!("Invalid document: {}", e)))?;
+            for warning in &upgraded.warnings {
+                eprintln!("Warning: {}", warning);
+            }
+
+            let doc = upgraded.document;
+            #[cfg(feature = "images")]
+            let doc = resolve_images(doc, &printer, image_dirs, max_image_bytes)?;
+
+            let errors = doc.component_errors();
+            if doc.on_error == OnError::Fail && !errors.is_empty() {
+                let messages: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+                return Err(EstrellaError::InvalidCommand(format!(
+                    "Document has errors: {}",
+                    messages.join("; ")
+                )));
+            }
+
+            let program = doc.print_program_with_config(&printer)?;
+            match output {
+                Some(path) => {
+                    let bytes = program.to_bytes_with_config(&printer);
+                    std::fs::write(&path, &bytes)?;
+                    eprintln!("Wrote {} bytes to {}", bytes.len(), path.display());
+                }
+                None => transport::open(&device)?.send_program(&program, &printer)?,
+            }
+        }
+        Commands::Raw { file, device } => {
+            let data = read_input(file.as_deref())?;
+            let mut printer = transport::open(&device)?;
+            printer.write_all(&data)?;
+            printer.flush()?;
+        }
+    }
+    Ok(())
+}
+
+/// Fetch and decode the document's images.
+#[cfg(feature = "images")]
+fn resolve_images(
+    mut doc: estrella::document::Document,
+    printer: &PrinterConfig,
+    allowed_dirs: Vec<PathBuf>,
+    max_bytes: usize,
+) -> Result<estrella::document::Document, EstrellaError> {
+    use estrella::document::{ImageResolver, ImageSources};
+    use std::collections::HashMap;
+    use std::sync::Arc;
+    use tokio::sync::RwLock;
+
+    let resolver = ImageResolver::new(Arc::new(RwLock::new(HashMap::new())))
+        .with_printer(printer)
+        .with_sources(ImageSources {
+            allowed_dirs,
+            max_bytes,
+        });
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?
+        .block_on(resolver.resolve(&mut doc))?;
+    Ok(doc)
+}
+
+/// Read a file, or stdin for `None` and `-`.
+fn read_input(file: Option<&Path>) -> Result<Vec<u8>, EstrellaError> {
+    match file {
+        Some(path) if path != Path::new("-") => Ok(std::fs::read(path)?),
+        _ => {
+            let mut data = Vec::new();
+            io::stdin().read_to_end(&mut data)?;
+            Ok(data)
+        }
+    }
+}
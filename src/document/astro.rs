@@ -0,0 +1,212 @@
+//! Sunrise, sunset and moon phase for the built-in variables.
+//!
+//! Computed locally from the date and the document's `location`, with no
+//! network access:
+//!
+//! | Variable | Example | Needs `location` |
+//! |----------|---------|------------------|
+//! | `{{sunrise}}` | `07:42` | yes |
+//! | `{{sunset}}` | `16:51` | yes |
+//! | `{{moon_phase}}` | `Waxing Gibbous` | no |
+//!
+//! Sun times use the NOAA sunrise equation and are good to about a minute.
+//! Where the sun doesn't rise or set that day (polar day and night) they
+//! print as `--:--`.
+
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+/// Julian date of 2000-01-01 12:00 UTC.
+const J2000: f64 = 2451545.0;
+
+/// Julian date of the Unix epoch.
+const UNIX_EPOCH_JD: f64 = 2440587.5;
+
+/// Mean length of a lunar month, in days.
+const SYNODIC_MONTH: f64 = 29.530588853;
+
+/// Julian date of a known new moon (2000-01-06 18:14 UTC).
+const NEW_MOON_JD: f64 = 2451550.26;
+
+/// Printed when the sun doesn't rise or set.
+const NO_SUN_TIME: &str = "--:--";
+
+const MOON_PHASES: [&str; 8] = [
+    "New Moon",
+    "Waxing Crescent",
+    "First Quarter",
+    "Waxing Gibbous",
+    "Full Moon",
+    "Waning Gibbous",
+    "Last Quarter",
+    "Waning Crescent",
+];
+
+/// Where the printer is, for `{{sunrise}}` and `{{sunset}}`.
+///
+/// In JSON either `{"lat": 52.52, "lon": 13.40}` or `"52.52,13.40"`;
+/// longitudes east of Greenwich are positive.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "LocationRepr")]
+pub struct Location {
+    pub lat: f64,
+    pub lon: f64,
+}
+
+impl Location {
+    /// Checked constructor: latitude within ±90°, longitude within ±180°.
+    pub fn new(lat: f64, lon: f64) -> Result<Self, String> {
+        if !(-90.0..=90.0).contains(&lat) || !(-180.0..=180.0).contains(&lon) {
+            return Err(format!("Location {},{} is out of range", lat, lon));
+        }
+        Ok(Self { lat, lon })
+    }
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{},{}", self.lat, self.lon)
+    }
+}
+
+impl FromStr for Location {
+    type Err = String;
+
+    /// Parse `"LAT,LON"` in decimal degrees.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid location '{}'. Use LAT,LON, e.g. 52.52,13.40", s);
+        let (lat, lon) = s.split_once(',').ok_or_else(invalid)?;
+        let lat = lat.trim().parse().map_err(|_| invalid())?;
+        let lon = lon.trim().parse().map_err(|_| invalid())?;
+        Self::new(lat, lon)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum LocationRepr {
+    Coordinates { lat: f64, lon: f64 },
+    Text(String),
+}
+
+impl TryFrom<LocationRepr> for Location {
+    type Error = String;
+
+    fn try_from(repr: LocationRepr) -> Result<Self, Self::Error> {
+        match repr {
+            LocationRepr::Coordinates { lat, lon } => Self::new(lat, lon),
+            LocationRepr::Text(s) => s.parse(),
+        }
+    }
+}
+
+/// `sunrise` and `sunset` on `date` at `location`, in the `offset` time zone.
+pub(crate) fn sun_variables(
+    date: NaiveDate,
+    location: &Location,
+    offset: FixedOffset,
+) -> HashMap<String, String> {
+    let (sunrise, sunset) = match sun_times(date, location) {
+        Some((rise, set)) => {
+            let local = |t: DateTime<Utc>| t.with_timezone(&offset).format("%H:%M").to_string();
+            (local(rise), local(set))
+        }
+        None => (NO_SUN_TIME.to_string(), NO_SUN_TIME.to_string()),
+    };
+    HashMap::from([("sunrise".into(), sunrise), ("sunset".into(), sunset)])
+}
+
+/// Sunrise and sunset (UTC) on `date`, or `None` during polar day or night.
+fn sun_times(date: NaiveDate, location: &Location) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+    let j2000 = NaiveDate::from_ymd_opt(2000, 1, 1)?;
+    let days = (date - j2000).num_days() as f64;
+
+    // Mean solar noon, solar mean anomaly and equation of the center
+    let noon = days - location.lon / 360.0;
+    let anomaly = (357.5291 + 0.98560028 * noon).rem_euclid(360.0);
+    let m = anomaly.to_radians();
+    let center = 1.9148 * m.sin() + 0.02 * (2.0 * m).sin() + 0.0003 * (3.0 * m).sin();
+
+    // Ecliptic longitude, solar transit and declination
+    let ecliptic = (anomaly + center + 180.0 + 102.9372)
+        .rem_euclid(360.0)
+        .to_radians();
+    let transit = J2000 + noon + 0.0053 * m.sin() - 0.0069 * (2.0 * ecliptic).sin();
+    let declination = (ecliptic.sin() * 23.4397_f64.to_radians().sin()).asin();
+
+    // Hour angle of the sun's upper limb on the horizon, with refraction
+    let lat = location.lat.to_radians();
+    let cos_hour_angle = ((-0.833_f64).to_radians().sin() - lat.sin() * declination.sin())
+        / (lat.cos() * declination.cos());
+    if !(-1.0..=1.0).contains(&cos_hour_angle) {
+        return None;
+    }
+    let half_day = cos_hour_angle.acos().to_degrees() / 360.0;
+
+    Some((
+        from_julian(transit - half_day)?,
+        from_julian(transit + half_day)?,
+    ))
+}
+
+/// Name of the moon's phase at `now` (one of eight, e.g. "First Quarter").
+pub(crate) fn moon_phase(now: &NaiveDateTime) -> &'static str {
+    let age = (julian(now) - NEW_MOON_JD).rem_euclid(SYNODIC_MONTH) / SYNODIC_MONTH;
+    MOON_PHASES[(age * 8.0).round() as usize % 8]
+}
+
+fn julian(t: &NaiveDateTime) -> f64 {
+    t.and_utc().timestamp() as f64 / 86400.0 + UNIX_EPOCH_JD
+}
+
+fn from_julian(jd: f64) -> Option<DateTime<Utc>> {
+    DateTime::from_timestamp(((jd - UNIX_EPOCH_JD) * 86400.0).round() as i64, 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn test_sun_times() {
+        let berlin = Location::new(52.52, 13.405).unwrap();
+        let cest = FixedOffset::east_opt(2 * 3600).unwrap();
+        let vars = sun_variables(date(2026, 6, 21), &berlin, cest);
+        assert_eq!(vars["sunrise"], "04:43");
+        assert_eq!(vars["sunset"], "21:33");
+
+        // Midnight sun in Tromsø
+        let tromso = Location::new(69.65, 18.96).unwrap();
+        assert!(sun_times(date(2026, 6, 21), &tromso).is_none());
+    }
+
+    #[test]
+    fn test_moon_phase() {
+        let at = |d: NaiveDate, h| d.and_hms_opt(h, 0, 0).unwrap();
+        assert_eq!(moon_phase(&at(date(2026, 1, 3), 10)), "Full Moon");
+        assert_eq!(moon_phase(&at(date(2026, 1, 18), 20)), "New Moon");
+        assert_eq!(moon_phase(&at(date(2026, 1, 26), 4)), "First Quarter");
+    }
+
+    #[test]
+    fn test_parse_location() {
+        let json = |s: &str| serde_json::from_str::<Location>(s);
+        assert_eq!(
+            json(r#"{"lat": 52.52, "lon": 13.4}"#).unwrap(),
+            json(r#""52.52, 13.4""#).unwrap()
+        );
+        assert!(json(r#"{"lat": 95, "lon": 0}"#).is_err());
+        assert!("north".parse::<Location>().is_err());
+        assert_eq!(
+            "-33.9,18.4".parse::<Location>().unwrap().to_string(),
+            "-33.9,18.4"
+        );
+    }
+}
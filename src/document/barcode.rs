@@ -1,13 +1,16 @@
 //! Emit logic for barcode components: QrCode, Pdf417, Barcode.
 
+use super::EmitContext;
 use super::types::{Barcode, Pdf417, QrCode};
 use crate::ir::{BarcodeKind, Op};
+use crate::preview::barcode;
+use crate::protocol::barcode::barcode1d::HriPosition;
 use crate::protocol::barcode::qr::QrErrorLevel;
 use crate::protocol::text::Alignment;
 
 impl QrCode {
     /// Emit IR ops for this QR code component.
-    pub fn emit(&self, ops: &mut Vec<Op>) {
+    pub fn emit(&self, _ctx: &EmitContext, ops: &mut Vec<Op>) {
         // Resolve alignment (default: center)
         let alignment = match self.align.as_deref() {
             Some("left") => Alignment::Left,
@@ -15,33 +18,53 @@ impl QrCode {
             _ => Alignment::Center, // default
         };
 
-        // Resolve error level (default: M)
-        let error_level = match self
-            .error_level
-            .as_deref()
-            .map(|s| s.to_uppercase())
-            .as_deref()
-        {
-            Some("L") => QrErrorLevel::L,
-            Some("Q") => QrErrorLevel::Q,
-            Some("H") => QrErrorLevel::H,
-            _ => QrErrorLevel::M, // default
-        };
-
+        let error_level = self.resolved_error_level();
         let cell_size = self.cell_size.unwrap_or(4).clamp(1, 8);
 
+        // Scope the alignment so it doesn't leak into following components
+        ops.push(Op::PushStyle);
         ops.push(Op::SetAlign(alignment));
         ops.push(Op::QrCode {
             data: self.data.clone(),
             cell_size,
             error_level,
         });
+        ops.push(Op::PopStyle);
+    }
+
+    /// Why this QR code can't be printed, if it can't: the data must fit
+    /// the largest symbol at the chosen error level.
+    pub fn check(&self) -> Result<(), String> {
+        let ec_level = match self.resolved_error_level() {
+            QrErrorLevel::L => qrcode::EcLevel::L,
+            QrErrorLevel::M => qrcode::EcLevel::M,
+            QrErrorLevel::Q => qrcode::EcLevel::Q,
+            QrErrorLevel::H => qrcode::EcLevel::H,
+        };
+        qrcode::QrCode::with_error_correction_level(&self.data, ec_level)
+            .map(|_| ())
+            .map_err(|e| format!("{} bytes don't fit in a QR code: {}", self.data.len(), e))
+    }
+
+    /// Error correction level (default: M).
+    fn resolved_error_level(&self) -> QrErrorLevel {
+        match self
+            .error_level
+            .as_deref()
+            .map(|s| s.to_uppercase())
+            .as_deref()
+        {
+            Some("L") => QrErrorLevel::L,
+            Some("Q") => QrErrorLevel::Q,
+            Some("H") => QrErrorLevel::H,
+            _ => QrErrorLevel::M,
+        }
     }
 }
 
 impl Pdf417 {
     /// Emit IR ops for this PDF417 barcode component.
-    pub fn emit(&self, ops: &mut Vec<Op>) {
+    pub fn emit(&self, _ctx: &EmitContext, ops: &mut Vec<Op>) {
         // Resolve alignment (default: center)
         let alignment = match self.align.as_deref() {
             Some("left") => Alignment::Left,
@@ -52,35 +75,77 @@ impl Pdf417 {
         let module_width = self.module_width.unwrap_or(3).clamp(1, 15);
         let ecc_level = self.ecc_level.unwrap_or(2).min(8);
 
+        ops.push(Op::PushStyle);
         ops.push(Op::SetAlign(alignment));
         ops.push(Op::Pdf417 {
             data: self.data.clone(),
             module_width,
             ecc_level,
         });
+        ops.push(Op::PopStyle);
     }
 }
 
 impl Barcode {
     /// Emit IR ops for this 1D barcode component.
-    pub fn emit(&self, ops: &mut Vec<Op>) {
-        let kind = match self.format.to_lowercase().as_str() {
-            "code39" => BarcodeKind::Code39,
-            "code128" => BarcodeKind::Code128,
-            "ean13" => BarcodeKind::Ean13,
-            "upca" => BarcodeKind::UpcA,
-            "itf" => BarcodeKind::Itf,
-            _ => return, // Unknown format — emit nothing
+    pub fn emit(&self, ctx: &EmitContext, ops: &mut Vec<Op>) {
+        let Some(kind) = self.kind() else {
+            return; // Unknown format — emit nothing
+        };
+
+        let height = self
+            .height_mm
+            .map(|mm| ctx.mm_to_dots(mm).min(255) as u8)
+            .or(self.height)
+            .unwrap_or(80)
+            .max(1);
+
+        let hri = if self.hri {
+            HriPosition::Below
+        } else {
+            HriPosition::None
         };
 
-        let height = self.height.unwrap_or(80).max(1);
+        if self.raster {
+            // Same bars and text the preview draws, sent as graphics
+            if let Some(barcode) = barcode::raster(kind, &self.data, height as usize, hri) {
+                ops.push(Op::Raster {
+                    width: barcode.width as u16,
+                    height: barcode.height as u16,
+                    data: barcode.data,
+                });
+            }
+            return;
+        }
 
         ops.push(Op::Barcode1D {
             kind,
             data: self.data.clone(),
             height,
+            hri,
         });
     }
+
+    /// Why this barcode can't be printed, if it can't.
+    pub fn check(&self) -> Result<(), String> {
+        match self.kind() {
+            Some(_) => Ok(()),
+            None => Err(format!("unknown barcode format '{}'", self.format)),
+        }
+    }
+
+    fn kind(&self) -> Option<BarcodeKind> {
+        match self.format.to_lowercase().as_str() {
+            "code39" => Some(BarcodeKind::Code39),
+            "code128" => Some(BarcodeKind::Code128),
+            "ean13" => Some(BarcodeKind::Ean13),
+            "upca" => Some(BarcodeKind::UpcA),
+            "itf" => Some(BarcodeKind::Itf),
+            "code93" => Some(BarcodeKind::Code93),
+            "nw7" | "codabar" => Some(BarcodeKind::Nw7),
+            _ => None,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -91,7 +156,7 @@ mod tests {
     fn test_qr_code_default() {
         let qr = QrCode::new("https://example.com");
         let mut ops = Vec::new();
-        qr.emit(&mut ops);
+        qr.emit(&EmitContext::default(), &mut ops);
         assert!(ops.iter().any(|op| matches!(
             op,
             Op::QrCode {
@@ -115,7 +180,7 @@ mod tests {
             align: Some("left".into()),
         };
         let mut ops = Vec::new();
-        qr.emit(&mut ops);
+        qr.emit(&EmitContext::default(), &mut ops);
         assert!(ops.iter().any(|op| matches!(
             op,
             Op::QrCode {
@@ -128,6 +193,9 @@ mod tests {
             ops.iter()
                 .any(|op| matches!(op, Op::SetAlign(Alignment::Left)))
         );
+        // Alignment is scoped to the QR code
+        assert!(matches!(ops.first(), Some(Op::PushStyle)));
+        assert!(matches!(ops.last(), Some(Op::PopStyle)));
     }
 
     #[test]
@@ -139,7 +207,7 @@ mod tests {
             ..Default::default()
         };
         let mut ops = Vec::new();
-        pdf.emit(&mut ops);
+        pdf.emit(&EmitContext::default(), &mut ops);
         assert!(ops.iter().any(|op| matches!(
             op,
             Op::Pdf417 {
@@ -156,9 +224,12 @@ mod tests {
             format: "code128".into(),
             data: "ABC-123".into(),
             height: Some(100),
+            height_mm: None,
+            hri: true,
+            raster: false,
         };
         let mut ops = Vec::new();
-        barcode.emit(&mut ops);
+        barcode.emit(&EmitContext::default(), &mut ops);
         assert!(ops.iter().any(|op| matches!(
             op,
             Op::Barcode1D {
@@ -169,15 +240,67 @@ mod tests {
         )));
     }
 
+    #[test]
+    fn test_barcode_height_mm() {
+        let barcode = Barcode {
+            format: "code128".into(),
+            data: "ABC-123".into(),
+            height: Some(100),
+            height_mm: Some(10.0),
+            hri: true,
+            raster: false,
+        };
+        let mut ops = Vec::new();
+        barcode.emit(&EmitContext::default(), &mut ops);
+        assert!(
+            ops.iter()
+                .any(|op| matches!(op, Op::Barcode1D { height: 80, .. }))
+        );
+    }
+
     #[test]
     fn test_barcode_invalid_format() {
         let barcode = Barcode {
             format: "invalid".into(),
             data: "123".into(),
             height: None,
+            height_mm: None,
+            hri: true,
+            raster: false,
         };
         let mut ops = Vec::new();
-        barcode.emit(&mut ops);
+        barcode.emit(&EmitContext::default(), &mut ops);
         assert!(ops.is_empty());
     }
+    #[test]
+    fn test_barcode_raster_fallback() {
+        let barcode = Barcode {
+            format: "ean13".into(),
+            data: "590123412345".into(),
+            height: Some(40),
+            height_mm: None,
+            hri: false,
+            raster: true,
+        };
+        let mut ops = Vec::new();
+        barcode.emit(&EmitContext::default(), &mut ops);
+        // 95 modules at 3 dots each
+        assert!(matches!(
+            ops.as_slice(),
+            [Op::Raster {
+                width: 285,
+                height: 40,
+                ..
+            }]
+        ));
+
+        // HRI text is drawn into the raster, below the bars
+        let barcode = Barcode {
+            hri: true,
+            ..barcode
+        };
+        let mut ops = Vec::new();
+        barcode.emit(&EmitContext::default(), &mut ops);
+        assert!(matches!(ops.as_slice(), [Op::Raster { height: 68, .. }]));
+    }
 }
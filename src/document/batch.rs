@@ -0,0 +1,498 @@
+//! Batch label generation.
+//!
+//! Expands one template document into many: one per code, either counted
+//! up from a starting number or read from a CSV file. Each copy gets the
+//! batch variables below on top of the template's own variables, so any
+//! component that interpolates can use them.
+//!
+//! | Variable    | Value                                           |
+//! |-------------|-------------------------------------------------|
+//! | `{{code}}`  | The code for this label                         |
+//! | `{{index}}` | 1-based position in the batch                   |
+//! | `{{count}}` | Number of labels in the batch                   |
+//! | `{{<col>}}` | Every CSV column, by header name (CSV only)     |
+//!
+//! Raffle tickets are a batch of two documents per number — the body the
+//! buyer keeps and the stub the organizer keeps — each ending in a partial
+//! cut (see [`ticket_documents`]).
+//!
+//! Where one item ends and the next begins is decided by a [`CutPolicy`],
+//! for batches and for `print all` alike:
+//!
+//! | Policy | Between items | At the end |
+//! |--------|---------------|------------|
+//! | `full` | full cut | full cut |
+//! | `partial` (default) | partial cut | partial cut |
+//! | `none` | dashed separator | partial cut |
+//! | `every:N` | partial cut after every N items, separators between | partial cut |
+//!
+//! Items whose document has `"cut": false` run straight into the next.
+
+use std::collections::HashMap;
+
+use super::{Barcode, Component, Divider, DividerStyle, Document, EmitContext, Spacer, Text};
+use crate::EstrellaError;
+use crate::ir::{Op, Program};
+use crate::printer::PrinterConfig;
+
+/// Variables for one label in a batch.
+pub type BatchRow = HashMap<String, String>;
+
+/// Sequential codes `start, start+1, ...`.
+///
+/// Leading zeros in `start` are kept: `"0098"` counts `0098, 0099, 0100`.
+pub fn sequential_rows(start: &str, count: usize) -> Result<Vec<BatchRow>, EstrellaError> {
+    let first: u64 = start.parse().map_err(|_| {
+        EstrellaError::InvalidCommand(format!("Batch start '{}' is not a number", start))
+    })?;
+    let width = start.len();
+
+    Ok((0..count)
+        .map(|i| {
+            let mut row = BatchRow::new();
+            row.insert(
+                "code".into(),
+                format!("{:0width$}", first + i as u64, width = width),
+            );
+            row
+        })
+        .collect())
+}
+
+/// One row per CSV record, keyed by the header line.
+///
+/// The `code` column is used as the code; without one, the first column is.
+/// Fields may be quoted (`"Widget, large"`), with `""` for a literal quote.
+pub fn csv_rows(input: &str) -> Result<Vec<BatchRow>, EstrellaError> {
+    let mut lines = input.lines().filter(|line| !line.trim().is_empty());
+    let header = lines
+        .next()
+        .ok_or_else(|| EstrellaError::InvalidCommand("CSV file is empty".into()))?;
+    let columns: Vec<String> = split_csv_line(header)
+        .into_iter()
+        .map(|c| c.trim().to_string())
+        .collect();
+    let code_column = if columns.iter().any(|c| c == "code") {
+        "code".to_string()
+    } else {
+        columns[0].clone()
+    };
+
+    lines
+        .enumerate()
+        .map(|(i, line)| {
+            let fields = split_csv_line(line);
+            if fields.len() != columns.len() {
+                return Err(EstrellaError::InvalidCommand(format!(
+                    "CSV line {} has {} fields, expected {}",
+                    i + 2,
+                    fields.len(),
+                    columns.len()
+                )));
+            }
+            let mut row: BatchRow = columns.iter().cloned().zip(fields).collect();
+            let code = row[&code_column].clone();
+            row.insert("code".into(), code);
+            Ok(row)
+        })
+        .collect()
+}
+
+/// Split one CSV line into fields, honouring double quotes.
+fn split_csv_line(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            _ => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+/// Default label: the barcode with its code printed underneath.
+pub fn default_template(format: &str) -> Document {
+    let mut doc = Document::new();
+    doc.push(Component::Barcode(Barcode {
+        format: format.to_string(),
+        data: "{{code}}".into(),
+        height: Some(80),
+        height_mm: None,
+        hri: false,
+        raster: false,
+    }));
+    doc.push(Component::Text(Text {
+        content: "{{code}}".into(),
+        center: true,
+        ..Default::default()
+    }));
+    doc
+}
+
+/// Expand `template` into one document per row.
+///
+/// When `format` is given it replaces the format of every barcode in the
+/// template, so one template can serve several symbologies.
+pub fn documents(template: &Document, rows: &[BatchRow], format: Option<&str>) -> Vec<Document> {
+    let mut base = template.clone();
+    if let Some(format) = format {
+        for component in &mut base.document {
+            if let Component::Barcode(barcode) = component {
+                barcode.format = format.to_string();
+            }
+        }
+    }
+
+    rows.iter()
+        .enumerate()
+        .map(|(i, row)| {
+            let mut doc = base.clone();
+            doc.variables.insert("index".into(), (i + 1).to_string());
+            doc.variables.insert("count".into(), rows.len().to_string());
+            doc.variables
+                .extend(row.iter().map(|(k, v)| (k.clone(), v.clone())));
+            doc
+        })
+        .collect()
+}
+
+/// Default raffle ticket body: a large ticket number under a heading.
+pub fn ticket_body_template() -> Document {
+    let mut doc = Document::new();
+    doc.push(Component::Text(Text {
+        content: "RAFFLE TICKET".into(),
+        center: true,
+        bold: true,
+        size: [2, 2],
+        ..Default::default()
+    }));
+    doc.push(Component::Text(Text {
+        content: "No. {{code}}".into(),
+        center: true,
+        bold: true,
+        size: [3, 3],
+        ..Default::default()
+    }));
+    doc.push(Component::Spacer(Spacer {
+        mm: Some(3.0),
+        ..Default::default()
+    }));
+    doc
+}
+
+/// Default raffle ticket stub: the number plus lines for the buyer's details.
+pub fn ticket_stub_template() -> Document {
+    let mut doc = Document::new();
+    doc.push(Component::Text(Text {
+        content: "STUB No. {{code}}".into(),
+        center: true,
+        bold: true,
+        size: [2, 2],
+        ..Default::default()
+    }));
+    doc.push(Component::Divider(Divider::default()));
+    doc.push(Component::Text(Text::new("Name:  ______________________")));
+    doc.push(Component::Text(Text::new("Phone: ______________________")));
+    doc.push(Component::Spacer(Spacer {
+        mm: Some(3.0),
+        ..Default::default()
+    }));
+    doc
+}
+
+/// Expand a raffle batch into `body, stub, body, stub, ...`.
+///
+/// Both halves get the same batch variables, and both end in a partial
+/// cut so the body tears off its stub and each ticket off the next.
+pub fn ticket_documents(body: &Document, stub: &Document, rows: &[BatchRow]) -> Vec<Document> {
+    let bodies = documents(body, rows, None);
+    let stubs = documents(stub, rows, None);
+    bodies
+        .into_iter()
+        .zip(stubs)
+        .flat_map(|(mut body, mut stub)| {
+            body.cut = true;
+            stub.cut = true;
+            [body, stub]
+        })
+        .collect()
+}
+
+/// How the paper is cut between the items of a multi-item print.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CutPolicy {
+    /// Full cut after every item.
+    Full,
+    /// Partial cut after every item, leaving a hinge.
+    #[default]
+    Partial,
+    /// No cuts between items, only a dashed separator. Saves the leader
+    /// paper each cut feeds out.
+    Separator,
+    /// Partial cut after every N items, separators between the others.
+    Every(usize),
+}
+
+impl CutPolicy {
+    /// The cut after item `index` (0-based) of `count`: `Some(partial)`,
+    /// or `None` for a separator. The last item is always cut.
+    pub fn cut_after(self, index: usize, count: usize) -> Option<bool> {
+        let last = index + 1 >= count;
+        match self {
+            Self::Full => Some(false),
+            Self::Partial => Some(true),
+            Self::Separator => last.then_some(true),
+            Self::Every(n) => (last || (index + 1).is_multiple_of(n.max(1))).then_some(true),
+        }
+    }
+
+    /// Replace the cut ending item `index` of `count` with this policy's.
+    ///
+    /// A program that doesn't end in a cut is left alone. When the cut
+    /// becomes a separator, the feed before it goes too.
+    pub fn finish(self, program: &mut Program, index: usize, count: usize, config: &PrinterConfig) {
+        if !matches!(program.ops.last(), Some(Op::Cut { .. })) {
+            return;
+        }
+        program.ops.pop();
+        match self.cut_after(index, count) {
+            Some(partial) => program.push(Op::Cut { partial }),
+            None => {
+                while matches!(program.ops.last(), Some(Op::Feed { .. })) {
+                    program.ops.pop();
+                }
+                let separator = Divider {
+                    style: DividerStyle::Dashed,
+                    small: true,
+                    ..Default::default()
+                };
+                separator.emit(&EmitContext::new(config), &mut program.ops);
+            }
+        }
+    }
+}
+
+impl std::str::FromStr for CutPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.to_lowercase();
+        if let Some(n) = s.strip_prefix("every:") {
+            return match n.parse() {
+                Ok(n) if n > 0 => Ok(Self::Every(n)),
+                _ => Err(format!("Invalid cut interval '{}' (use every:N, N > 0)", n)),
+            };
+        }
+        match s.as_str() {
+            "full" | "each" => Ok(Self::Full),
+            "partial" => Ok(Self::Partial),
+            "none" | "separator" => Ok(Self::Separator),
+            _ => Err(format!(
+                "Unknown cut policy '{}'. Use 'full', 'partial', 'none' or 'every:N'",
+                s
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for CutPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Full => f.write_str("full"),
+            Self::Partial => f.write_str("partial"),
+            Self::Separator => f.write_str("none"),
+            Self::Every(n) => write!(f, "every:{}", n),
+        }
+    }
+}
+
+/// Concatenate the print programs of a batch into one job, so the whole
+/// batch goes to the printer over a single connection.
+pub fn batch_program(docs: &[Document]) -> Result<Program, EstrellaError> {
+    batch_program_with_config(docs, &PrinterConfig::TSP650II)
+}
+
+/// Like [`batch_program`], compiling each document for `config`.
+pub fn batch_program_with_config(
+    docs: &[Document],
+    config: &PrinterConfig,
+) -> Result<Program, EstrellaError> {
+    batch_program_with_cuts(docs, CutPolicy::default(), config)
+}
+
+/// Like [`batch_program_with_config`], cutting between documents as
+/// `cuts` says.
+pub fn batch_program_with_cuts(
+    docs: &[Document],
+    cuts: CutPolicy,
+    config: &PrinterConfig,
+) -> Result<Program, EstrellaError> {
+    let programs = docs
+        .iter()
+        .map(|doc| doc.print_program_with_config(config))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(join_programs(programs, cuts, config))
+}
+
+/// Concatenate item programs into one job, cut between items as `cuts`
+/// says (see [`CutPolicy::finish`]).
+pub fn join_programs(programs: Vec<Program>, cuts: CutPolicy, config: &PrinterConfig) -> Program {
+    let count = programs.len();
+    let mut program = Program::new();
+    for (i, mut item) in programs.into_iter().enumerate() {
+        cuts.finish(&mut item, i, count, config);
+        program.ops.extend(item.ops);
+    }
+    // One Init at the start is enough; later ones would reset the printer mid-job
+    let mut seen_init = false;
+    program.ops.retain(|op| match op {
+        Op::Init if seen_init => false,
+        Op::Init => {
+            seen_init = true;
+            true
+        }
+        _ => true,
+    });
+    program
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sequential_rows_keep_padding() {
+        let rows = sequential_rows("0098", 3).unwrap();
+        let codes: Vec<&str> = rows.iter().map(|r| r["code"].as_str()).collect();
+        assert_eq!(codes, ["0098", "0099", "0100"]);
+        assert!(sequential_rows("ABC", 1).is_err());
+    }
+
+    #[test]
+    fn test_csv_rows() {
+        let csv = "sku,name\nA-1,\"Widget, large\"\nA-2,\"Say \"\"hi\"\"\"\n";
+        let rows = csv_rows(csv).unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0]["code"], "A-1");
+        assert_eq!(rows[0]["name"], "Widget, large");
+        assert_eq!(rows[1]["name"], "Say \"hi\"");
+
+        assert!(csv_rows("code,name\n1\n").is_err());
+    }
+
+    #[test]
+    fn test_documents_set_batch_variables() {
+        let rows = sequential_rows("1000", 2).unwrap();
+        let docs = documents(&default_template("code128"), &rows, Some("code39"));
+        assert_eq!(docs.len(), 2);
+        assert_eq!(docs[1].variables["code"], "1001");
+        assert_eq!(docs[1].variables["index"], "2");
+        assert_eq!(docs[1].variables["count"], "2");
+        assert!(matches!(
+            &docs[0].document[0],
+            Component::Barcode(b) if b.format == "code39"
+        ));
+
+        let program = batch_program(&docs).unwrap();
+        let barcodes: Vec<&str> = program
+            .ops
+            .iter()
+            .filter_map(|op| match op {
+                Op::Barcode1D { data, .. } => Some(data.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(barcodes, ["1000", "1001"]);
+        assert_eq!(
+            program
+                .ops
+                .iter()
+                .filter(|op| matches!(op, Op::Init))
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_ticket_documents_interleave_body_and_stub() {
+        let rows = sequential_rows("001", 2).unwrap();
+        let docs = ticket_documents(&ticket_body_template(), &ticket_stub_template(), &rows);
+        assert_eq!(docs.len(), 4);
+        assert!(docs.iter().all(|doc| doc.cut));
+
+        let texts: Vec<String> = docs
+            .iter()
+            .map(|doc| {
+                let program = doc.compile();
+                program
+                    .ops
+                    .iter()
+                    .filter_map(|op| match op {
+                        Op::Text(t) => Some(t.clone()),
+                        _ => None,
+                    })
+                    .collect()
+            })
+            .collect();
+        assert!(texts[0].contains("No. 001"));
+        assert!(texts[1].contains("STUB No. 001"));
+        assert!(texts[2].contains("No. 002"));
+        assert!(texts[3].contains("STUB No. 002"));
+    }
+
+    fn cuts(program: &Program) -> Vec<bool> {
+        program
+            .ops
+            .iter()
+            .filter_map(|op| match op {
+                Op::Cut { partial } => Some(*partial),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_cut_policies() {
+        let docs = documents(
+            &default_template("code128"),
+            &sequential_rows("1", 5).unwrap(),
+            None,
+        );
+        let config = PrinterConfig::TSP650II;
+        let program = |policy: &str| {
+            batch_program_with_cuts(&docs, policy.parse().unwrap(), &config).unwrap()
+        };
+        assert_eq!(cuts(&program("partial")), [true; 5]);
+        assert_eq!(cuts(&program("full")), [false; 5]);
+        assert_eq!(cuts(&program("every:2")), [true; 3]);
+
+        let joined = program("none");
+        assert_eq!(cuts(&joined), [true]);
+        assert!(matches!(joined.ops.last(), Some(Op::Cut { .. })));
+        let separators = joined
+            .ops
+            .iter()
+            .filter(|op| matches!(op, Op::Text(t) if t.starts_with("---")))
+            .count();
+        assert_eq!(separators, 4);
+    }
+
+    #[test]
+    fn test_cut_policy_parse() {
+        assert_eq!("every:3".parse(), Ok(CutPolicy::Every(3)));
+        assert_eq!("NONE".parse(), Ok(CutPolicy::Separator));
+        assert_eq!(CutPolicy::Every(3).to_string(), "every:3");
+        assert!("every:0".parse::<CutPolicy>().is_err());
+        assert!("sometimes".parse::<CutPolicy>().is_err());
+    }
+}
@@ -1,21 +1,23 @@
 //! Emit logic for the Canvas component.
 //!
 //! Renders elements onto an f32 intensity buffer using the same compositing
-//! model as `render::composer`, then dithers to 1-bit and emits `Op::Raster`.
+//! model as `render::composer`, then emits `Op::GrayRaster` so dithering
+//! happens once, at codegen/preview time.
 
 use serde::Serialize;
 
-use super::Component;
-use super::types::Canvas;
+use super::types::{Canvas, ProgressBarStyle};
+use super::{Component, EmitContext};
 use crate::ir::{Op, Program};
-use crate::preview::render_raw;
-use crate::render::composer::BlendMode;
+use crate::preview::{render_gray_with_config, render_raw_with_config};
+use crate::render::composer::{BlendMode, transform};
 use crate::render::dither::{self, DitheringAlgorithm};
 use crate::shader::lerp;
 
 use super::graphics::parse_dither_algorithm;
-use super::types::CanvasElement;
+use super::types::{CanvasElement, Clip, ClipShape, Length, Position};
 use crate::preview::RawRaster;
+use crate::printer::PrinterConfig;
 
 /// Bounding box of a rendered canvas element (content bounds in canvas space).
 #[derive(Debug, Clone, Serialize)]
@@ -100,6 +102,19 @@ fn content_bounds(raster: &RawRaster) -> Option<(usize, usize, usize, usize)> {
     }
 }
 
+/// Tight bounds of the pixels a gray buffer would print black (≥ 0.5).
+fn gray_content_bounds(data: &[f32], width: usize) -> Option<(usize, usize, usize, usize)> {
+    let mut bounds: Option<(usize, usize, usize, usize)> = None;
+    for (i, _) in data.iter().enumerate().filter(|(_, v)| **v >= 0.5) {
+        let (x, y) = (i % width, i / width);
+        bounds = Some(match bounds {
+            None => (x, y, x, y),
+            Some((x0, y0, x1, y1)) => (x0.min(x), y0.min(y), x1.max(x), y1.max(y)),
+        });
+    }
+    bounds
+}
+
 /// Layout metadata for a canvas: overall dimensions and per-element bounding boxes.
 #[derive(Debug, Clone, Serialize)]
 pub struct CanvasLayout {
@@ -110,43 +125,42 @@ pub struct CanvasLayout {
 
 impl Canvas {
     /// Emit IR ops for this canvas component.
-    pub fn emit(&self, ops: &mut Vec<Op>) {
+    pub fn emit(&self, ctx: &EmitContext, ops: &mut Vec<Op>) {
         if self.elements.is_empty() {
             return;
         }
 
-        let canvas_width = self.width.unwrap_or(576);
+        let canvas_width = ctx
+            .dots_or_mm(self.width, self.width_mm)
+            .unwrap_or(ctx.print_width());
 
-        // Render each element to an f32 intensity buffer.
+        // Render each element to an f32 intensity buffer, then place them.
         // Elements without position flow top-to-bottom; positioned elements are independent.
-        let mut rendered = Vec::new();
-        let mut flow_y: i32 = 0;
-
-        for el in &self.elements {
-            if let Some(mut r) = render_element(el, canvas_width) {
-                if el.position.is_none() {
-                    // Flow mode: stack below previous flow elements
-                    r.y = flow_y;
-                    flow_y += r.height as i32;
-                }
-                rendered.push(r);
+        let mut rendered: Vec<Option<RenderedElement>> = self
+            .elements
+            .iter()
+            .map(|e| render_element(e, ctx))
+            .collect();
+        let boxes: Vec<Option<ElementBox>> = rendered
+            .iter()
+            .map(|r| {
+                r.as_ref().map(|r| ElementBox {
+                    width: r.width,
+                    height: r.height,
+                    baseline: r.baseline,
+                })
+            })
+            .collect();
+        let (origins, canvas_height) = self.place_elements(&boxes, canvas_width, ctx);
+        for (r, (x, y)) in rendered.iter_mut().zip(origins) {
+            if let Some(r) = r {
+                r.x = x;
+                r.y = y;
             }
         }
+        let rendered: Vec<RenderedElement> = rendered.into_iter().flatten().collect();
 
-        if rendered.is_empty() {
-            return;
-        }
-
-        // Determine canvas height: explicit or bounding box of all elements
-        let canvas_height = self.height.unwrap_or_else(|| {
-            rendered
-                .iter()
-                .map(|r| (r.y + r.height as i32).max(0) as usize)
-                .max()
-                .unwrap_or(1)
-        });
-
-        if canvas_height == 0 {
+        if rendered.is_empty() || canvas_height == 0 {
             return;
         }
 
@@ -154,9 +168,11 @@ impl Canvas {
         let dither_algo = self.resolve_dither();
 
         // Composite all elements onto a single f32 intensity buffer
-        let raster_data = dither::generate_raster(
+        // (draft previews composite every Nth row only)
+        let data = dither::generate_intensities_draft(
             canvas_width,
             canvas_height,
+            self.row_step,
             |px, py, _w, _h| {
                 let mut result: f32 = 0.0; // white background
 
@@ -169,25 +185,31 @@ impl Canvas {
                         || local_x >= el.width as i32
                         || local_y >= el.height as i32
                     {
+                        // A mask hides everything it doesn't cover
+                        if el.blend_mode == BlendMode::Mask {
+                            result = lerp(result, 0.0, el.opacity);
+                        }
                         continue;
                     }
 
                     let idx = local_y as usize * el.width + local_x as usize;
                     let intensity = el.intensity.get(idx).copied().unwrap_or(0.0);
 
+                    let coverage = el.mask.as_ref().map_or(1.0, |mask| mask[idx]);
+
                     let blended = el.blend_mode.apply(result, intensity);
-                    result = lerp(result, blended, el.opacity);
+                    result = lerp(result, blended, el.opacity * coverage);
                 }
 
                 result.clamp(0.0, 1.0)
             },
-            dither_algo,
         );
 
-        ops.push(Op::Raster {
+        ops.push(Op::GrayRaster {
             width: canvas_width as u16,
             height: canvas_height as u16,
-            data: raster_data,
+            data: dither::to_levels(&data),
+            dither: dither_algo,
         });
     }
 
@@ -198,82 +220,89 @@ impl Canvas {
     ///
     /// The primary x/y/width/height in each `ElementLayout` represent the
     /// tight content bounds (non-white pixels). The `content_offset_x/y` fields
-    /// give the offset from content origin to element origin, so the frontend
-    /// can map drag positions back: `element_position = content_position - offset`.
+    /// give the offset from content origin to the element's anchor point, so
+    /// the frontend can map drag positions back:
+    /// `element_position = content_position - offset`.
     pub fn compute_layout(&self) -> CanvasLayout {
-        let canvas_width = self.width.unwrap_or(576);
-        let mut layouts = Vec::new();
-        // Track full element bottom edges for canvas height (must match emit())
-        let mut full_bottoms: Vec<usize> = Vec::new();
-        let mut flow_y: i32 = 0;
+        self.compute_layout_with_config(&PrinterConfig::TSP650II)
+    }
 
-        for el in &self.elements {
-            if let Some((mut elem_x, mut elem_y, measurement)) = measure_element(el) {
-                if el.position.is_none() {
-                    elem_x = 0;
-                    elem_y = flow_y;
-                    flow_y += measurement.full_height as i32;
+    /// [`Canvas::compute_layout`] for a specific printer: the canvas defaults
+    /// to its print width.
+    pub fn compute_layout_with_config(&self, config: &PrinterConfig) -> CanvasLayout {
+        let ctx = EmitContext::new(config);
+        let canvas_width = ctx
+            .dots_or_mm(self.width, self.width_mm)
+            .unwrap_or(ctx.print_width());
+        let measurements: Vec<Option<ElementMeasurement>> = self
+            .elements
+            .iter()
+            .map(|e| measure_element(e, &ctx))
+            .collect();
+        let boxes: Vec<Option<ElementBox>> = measurements
+            .iter()
+            .map(|m| {
+                m.as_ref().map(|m| ElementBox {
+                    width: m.full_width,
+                    height: m.full_height,
+                    baseline: m
+                        .content_bounds
+                        .map(|(_, _, _, max_y)| max_y + 1)
+                        .unwrap_or(m.full_height),
+                })
+            })
+            .collect();
+        let (origins, canvas_height) = self.place_elements(&boxes, canvas_width, &ctx);
+
+        let layouts = self
+            .elements
+            .iter()
+            .zip(measurements.iter().zip(&boxes))
+            .zip(origins)
+            .map(|((el, (measurement, b)), (elem_x, elem_y))| {
+                let (Some(measurement), Some(b)) = (measurement, b) else {
+                    // Element produced no output — zero-size placeholder
+                    return ElementLayout {
+                        x: 0,
+                        y: 0,
+                        width: 0,
+                        height: 0,
+                        content_offset_x: 0,
+                        content_offset_y: 0,
+                        full_width: 0,
+                        full_height: 0,
+                    };
+                };
+
+                // Anchor point in canvas space (top-left for flow elements)
+                let (anchor_dx, anchor_dy) = el
+                    .position
+                    .map(|p| p.anchor.offset(b.width, b.height, b.baseline))
+                    .unwrap_or((0, 0));
+                let (anchor_x, anchor_y) = (elem_x + anchor_dx, elem_y + anchor_dy);
+
+                // All-white elements fall back to full bounds
+                let (min_x, min_y, max_x, max_y) = measurement.content_bounds.unwrap_or((
+                    0,
+                    0,
+                    measurement.full_width.saturating_sub(1),
+                    measurement.full_height.saturating_sub(1),
+                ));
+                let content_x = elem_x + min_x as i32;
+                let content_y = elem_y + min_y as i32;
+
+                ElementLayout {
+                    x: content_x,
+                    y: content_y,
+                    width: max_x - min_x + 1,
+                    height: max_y - min_y + 1,
+                    content_offset_x: content_x - anchor_x,
+                    content_offset_y: content_y - anchor_y,
+                    full_width: measurement.full_width,
+                    full_height: measurement.full_height,
                 }
-
-                // Track full element bounds for canvas height calculation
-                full_bottoms.push((elem_y + measurement.full_height as i32).max(0) as usize);
-
-                match measurement.content_bounds {
-                    Some((min_x, min_y, max_x, max_y)) => {
-                        // Content bounds in canvas space
-                        let content_x = elem_x + min_x as i32;
-                        let content_y = elem_y + min_y as i32;
-                        let content_w = max_x - min_x + 1;
-                        let content_h = max_y - min_y + 1;
-
-                        // Offset: how far content origin is from element origin
-                        let offset_x = content_x - elem_x;
-                        let offset_y = content_y - elem_y;
-
-                        layouts.push(ElementLayout {
-                            x: content_x,
-                            y: content_y,
-                            width: content_w,
-                            height: content_h,
-                            content_offset_x: offset_x,
-                            content_offset_y: offset_y,
-                            full_width: measurement.full_width,
-                            full_height: measurement.full_height,
-                        });
-                    }
-                    None => {
-                        // All-white element — fall back to full bounds
-                        layouts.push(ElementLayout {
-                            x: elem_x,
-                            y: elem_y,
-                            width: measurement.full_width,
-                            height: measurement.full_height,
-                            content_offset_x: 0,
-                            content_offset_y: 0,
-                            full_width: measurement.full_width,
-                            full_height: measurement.full_height,
-                        });
-                    }
-                }
-            } else {
-                // Element produced no output — zero-size placeholder
-                layouts.push(ElementLayout {
-                    x: 0,
-                    y: 0,
-                    width: 0,
-                    height: 0,
-                    content_offset_x: 0,
-                    content_offset_y: 0,
-                    full_width: 0,
-                    full_height: 0,
-                });
-            }
-        }
-
-        // Canvas height uses full element bounds (must match emit() behavior)
-        let canvas_height = self
-            .height
-            .unwrap_or_else(|| full_bottoms.iter().copied().max().unwrap_or(1));
+            })
+            .collect();
 
         CanvasLayout {
             width: canvas_width,
@@ -282,6 +311,74 @@ impl Canvas {
         }
     }
 
+    /// Resolve every element's top-left corner (in dots) and the canvas height.
+    ///
+    /// Flow elements (no `position`) stack from the top at x = 0. Lengths
+    /// resolve against the printer config; percentages of height need the
+    /// canvas height, which without an explicit `height` comes from the
+    /// elements that don't use one. Uses full element bounds, so `emit()`
+    /// and `compute_layout()` agree.
+    fn place_elements(
+        &self,
+        boxes: &[Option<ElementBox>],
+        canvas_width: usize,
+        ctx: &EmitContext,
+    ) -> (Vec<(i32, i32)>, usize) {
+        let config = ctx.printer;
+        let resolve = |pos: &Position, b: &ElementBox, canvas_height: usize| {
+            let (dx, dy) = pos.anchor.offset(b.width, b.height, b.baseline);
+            (
+                pos.x.to_dots(canvas_width, &config) - dx,
+                pos.y.to_dots(canvas_height, &config) - dy,
+            )
+        };
+
+        let mut flow_y: i32 = 0;
+        let mut flow_tops = vec![None; boxes.len()];
+        for (i, (el, b)) in self.elements.iter().zip(boxes).enumerate() {
+            if let (None, Some(b)) = (&el.position, b) {
+                flow_tops[i] = Some(flow_y);
+                flow_y += b.height as i32;
+            }
+        }
+
+        let canvas_height = ctx
+            .dots_or_mm(self.height, self.height_mm)
+            .unwrap_or_else(|| {
+                self.elements
+                    .iter()
+                    .zip(boxes)
+                    .zip(&flow_tops)
+                    .filter_map(|((el, b), flow_top)| {
+                        let b = b.as_ref()?;
+                        let top = match (&el.position, flow_top) {
+                            (_, Some(top)) => *top,
+                            (Some(pos), None) if !matches!(pos.y, Length::Percent(_)) => {
+                                resolve(pos, b, 0).1
+                            }
+                            _ => return None,
+                        };
+                        Some((top + b.height as i32).max(0) as usize)
+                    })
+                    .max()
+                    .unwrap_or(1)
+            });
+
+        let origins = self
+            .elements
+            .iter()
+            .zip(boxes)
+            .zip(flow_tops)
+            .map(|((el, b), flow_top)| match (&el.position, b, flow_top) {
+                (_, _, Some(top)) => (0, top),
+                (Some(pos), Some(b), None) => resolve(pos, b, canvas_height),
+                _ => (0, 0),
+            })
+            .collect();
+
+        (origins, canvas_height)
+    }
+
     /// Resolve the dithering algorithm for this canvas.
     fn resolve_dither(&self) -> DitheringAlgorithm {
         let dither_str = self.dither.as_deref().unwrap_or("auto");
@@ -301,9 +398,16 @@ impl Canvas {
 /// that benefits from dithering.
 fn has_continuous_tone_content(elements: &[CanvasElement]) -> bool {
     elements.iter().any(|e| match &e.component {
-        Component::Pattern(_) | Component::Image(_) | Component::Chart(_) => true,
+        Component::Pattern(_)
+        | Component::Image(_)
+        | Component::Chart(_)
+        | Component::Gauge(_)
+        | Component::Texture(_)
+        | Component::Composition(_)
+        | Component::TextMask(_) => true,
         Component::Text(t) => t.font.is_some(),
         Component::Banner(b) => b.font.is_some(),
+        Component::ProgressBar(p) => p.style == ProgressBarStyle::Raster,
         Component::Canvas(c) => has_continuous_tone_content(&c.elements),
         _ => false,
     })
@@ -315,79 +419,158 @@ struct RenderedElement {
     y: i32,
     width: usize,
     height: usize,
+    /// Row below the lowest inked pixel (for baseline anchoring).
+    baseline: usize,
     intensity: Vec<f32>,
+    /// Clip coverage per pixel (0.0 = clipped away), if the element is clipped.
+    mask: Option<Vec<f32>>,
     blend_mode: BlendMode,
     opacity: f32,
 }
 
+impl RenderedElement {
+    /// Intensity as it will show on the canvas, with clipped pixels removed.
+    fn visible_intensity(&self) -> Vec<f32> {
+        match &self.mask {
+            Some(mask) => self
+                .intensity
+                .iter()
+                .zip(mask)
+                .map(|(v, m)| v * m)
+                .collect(),
+            None => self.intensity.clone(),
+        }
+    }
+}
+
+/// Full size of a rendered element, for placement.
+#[derive(Debug, Clone, Copy)]
+struct ElementBox {
+    width: usize,
+    height: usize,
+    baseline: usize,
+}
+
 /// Measure a single canvas element: emit → render_raw → scan content bounds.
 ///
-/// Returns the element's position, full size, and content bounds.
-fn measure_element(element: &CanvasElement) -> Option<(i32, i32, ElementMeasurement)> {
+/// Returns the element's full size and content bounds.
+fn measure_element(element: &CanvasElement, ctx: &EmitContext) -> Option<ElementMeasurement> {
+    if element.is_transformed() || element.clip.is_some() {
+        // Measure what is actually composited: the resampled, clipped gray buffer
+        let r = render_element(element, ctx)?;
+        return Some(ElementMeasurement {
+            full_width: r.width,
+            full_height: r.height,
+            content_bounds: gray_content_bounds(&r.visible_intensity(), r.width),
+        });
+    }
+
     let mut sub_ops = Vec::new();
-    element.component.emit(&mut sub_ops);
+    element.component.emit(ctx, &mut sub_ops);
     if sub_ops.is_empty() {
         return None;
     }
     let program = Program { ops: sub_ops };
-    let raw = render_raw(&program).ok()?;
-    let (x, y) = element.position.map(|p| (p.x, p.y)).unwrap_or((0, 0));
+    let raw = render_raw_with_config(&program, &ctx.printer).ok()?;
     let cb = content_bounds(&raw);
-    Some((
-        x,
-        y,
-        ElementMeasurement {
-            full_width: raw.width,
-            full_height: raw.height,
-            content_bounds: cb,
-        },
-    ))
+    Some(ElementMeasurement {
+        full_width: raw.width,
+        full_height: raw.height,
+        content_bounds: cb,
+    })
 }
 
 /// Render a single canvas element to an f32 intensity buffer.
 ///
-/// Uses the standard path: emit IR ops → render_raw() → convert 1-bit to f32.
-/// Position is set from the element's `position` field; flow positioning is
-/// handled by the caller for elements without explicit position.
+/// Uses the standard path: emit IR ops → render_gray(), so grayscale content
+/// (images, charts, TTF text) is composited before any dithering.
+/// Placement is resolved by the caller (see `Canvas::place_elements`).
 /// Returns None if the element produces no output.
-fn render_element(element: &CanvasElement, _canvas_width: usize) -> Option<RenderedElement> {
+fn render_element(element: &CanvasElement, ctx: &EmitContext) -> Option<RenderedElement> {
     let mut sub_ops = Vec::new();
-    element.component.emit(&mut sub_ops);
+    element.component.emit(ctx, &mut sub_ops);
 
     if sub_ops.is_empty() {
         return None;
     }
 
     let program = Program { ops: sub_ops };
-    let raw = render_raw(&program).ok()?;
-
-    // Convert 1-bit packed data to f32 intensity buffer
-    let width = raw.width;
-    let height = raw.height;
-    let width_bytes = width.div_ceil(8);
-    let mut intensity = vec![0.0f32; width * height];
-
-    for y in 0..height {
-        for x in 0..width {
-            let byte_idx = y * width_bytes + x / 8;
-            let bit_idx = 7 - (x % 8);
-            let is_black = (raw.data.get(byte_idx).copied().unwrap_or(0) >> bit_idx) & 1 == 1;
-            if is_black {
-                intensity[y * width + x] = 1.0;
-            }
-        }
+    let gray = render_gray_with_config(&program, &ctx.printer).ok()?;
+    let (intensity, width, height) = if element.is_transformed() {
+        transform(
+            &gray.data,
+            gray.width,
+            gray.height,
+            element.rotate,
+            element.scale[0],
+            element.scale[1],
+        )
+    } else {
+        (gray.data, gray.width, gray.height)
+    };
+    if width == 0 || height == 0 {
+        return None;
     }
 
-    let (x, y) = element.position.map(|p| (p.x, p.y)).unwrap_or((0, 0));
-
-    Some(RenderedElement {
-        x,
-        y,
+    let mut rendered = RenderedElement {
+        x: 0,
+        y: 0,
         width,
         height,
+        baseline: height,
+        mask: element
+            .clip
+            .as_ref()
+            .map(|clip| clip_mask(clip, &intensity, width, height)),
         intensity,
         blend_mode: element.blend_mode,
         opacity: element.opacity,
+    };
+
+    // Same threshold as the 1-bit preview, so layout and render agree
+    let visible = rendered.visible_intensity();
+    if let Some(y) = (0..height).rev().find(|&y| {
+        visible[y * width..(y + 1) * width]
+            .iter()
+            .any(|&v| v >= 0.5)
+    }) {
+        rendered.baseline = y + 1;
+    }
+    Some(rendered)
+}
+
+/// Coverage of a clip shape over an element's box: 1.0 inside, 0.0
+/// outside, with a one-dot antialiased edge so circles stay round after
+/// dithering.
+fn clip_mask(clip: &Clip, intensity: &[f32], width: usize, height: usize) -> Vec<f32> {
+    let (bx0, by0, bx1, by1) = gray_content_bounds(intensity, width)
+        .map(|(x0, y0, x1, y1)| (x0, y0, x1 + 1, y1 + 1))
+        .unwrap_or((0, 0, width, height));
+    let x0 = clip.x.unwrap_or(bx0) as f32;
+    let y0 = clip.y.unwrap_or(by0) as f32;
+    let w = clip.width.map_or((bx1 as f32 - x0).max(0.0), |w| w as f32);
+    let h = clip.height.map_or((by1 as f32 - y0).max(0.0), |h| h as f32);
+    let (cx, cy) = (x0 + w / 2.0, y0 + h / 2.0);
+
+    // Every shape is a rounded rectangle: half extents plus corner radius
+    let (half_w, half_h, radius) = match clip.shape {
+        ClipShape::Rect => (w / 2.0, h / 2.0, 0.0),
+        ClipShape::RoundedRect => {
+            let radius = clip.radius.unwrap_or(0.0).clamp(0.0, w.min(h) / 2.0);
+            (w / 2.0, h / 2.0, radius)
+        }
+        ClipShape::Circle => {
+            let radius = clip.radius.unwrap_or(w.min(h) / 2.0).max(0.0);
+            (radius, radius, radius)
+        }
+    };
+
+    dither::generate_intensities(width, height, |x, y, _, _| {
+        // Signed distance from the pixel center to the shape's edge
+        let qx = (x as f32 + 0.5 - cx).abs() - (half_w - radius);
+        let qy = (y as f32 + 0.5 - cy).abs() - (half_h - radius);
+        let distance = qx.max(0.0).hypot(qy.max(0.0)) + qx.max(qy).min(0.0) - radius;
+        (0.5 - distance).clamp(0.0, 1.0)
     })
 }
 
@@ -395,7 +578,7 @@ fn render_element(element: &CanvasElement, _canvas_width: usize) -> Option<Rende
 mod tests {
     use super::*;
     use crate::document::Position;
-    use crate::preview::RawRaster;
+    use crate::preview::{RawRaster, render_raw};
 
     /// Build a RawRaster with 1-bit packed data from a list of (x, y) black pixels.
     fn make_raster(width: usize, height: usize, black_pixels: &[(usize, usize)]) -> RawRaster {
@@ -497,6 +680,9 @@ mod tests {
             position,
             blend_mode: Default::default(),
             opacity: 1.0,
+            rotate: 0.0,
+            scale: [1.0, 1.0],
+            clip: None,
         }
     }
 
@@ -510,6 +696,9 @@ mod tests {
             position,
             blend_mode: Default::default(),
             opacity: 1.0,
+            rotate: 0.0,
+            scale: [1.0, 1.0],
+            clip: None,
         }
     }
 
@@ -543,7 +732,7 @@ mod tests {
     fn layout_offsets_map_back_to_element_position() {
         // An element at position (50, 100) should be recoverable from content bounds - offset
         let canvas = Canvas {
-            elements: vec![centered_text_element("X", Some(Position { x: 50, y: 100 }))],
+            elements: vec![centered_text_element("X", Some(Position::dots(50, 100)))],
             ..Default::default()
         };
         let layout = canvas.compute_layout();
@@ -622,10 +811,7 @@ mod tests {
     #[test]
     fn layout_positioned_element_preserves_offset() {
         let canvas = Canvas {
-            elements: vec![centered_text_element(
-                "AB",
-                Some(Position { x: 100, y: 50 }),
-            )],
+            elements: vec![centered_text_element("AB", Some(Position::dots(100, 50)))],
             ..Default::default()
         };
         let layout = canvas.compute_layout();
@@ -647,6 +833,9 @@ mod tests {
                 position: None,
                 blend_mode: Default::default(),
                 opacity: 1.0,
+                rotate: 0.0,
+                scale: [1.0, 1.0],
+                clip: None,
             }],
             ..Default::default()
         };
@@ -658,4 +847,193 @@ mod tests {
         assert_eq!(el.width, el.full_width);
         assert_eq!(el.height, el.full_height);
     }
+
+    #[test]
+    fn layout_resolves_mm_percent_and_anchor() {
+        let json = r#"{"type": "canvas", "height": 200, "elements": [
+            {"text": "A", "position": {"x": "10mm", "y": 0}},
+            {"text": "B", "position": {"x": "50%", "y": "50%", "anchor": "center"}}
+        ]}"#;
+        let canvas = match serde_json::from_str::<Component>(json).unwrap() {
+            Component::Canvas(c) => c,
+            other => panic!("expected canvas, got {:?}", other),
+        };
+        let layout = canvas.compute_layout();
+
+        let a = &layout.elements[0];
+        let mm = PrinterConfig::TSP650II.mm_to_dots(10.0) as i32;
+        assert_eq!(a.x - a.content_offset_x, mm);
+
+        // Anchor point of the centered element maps back to the canvas center
+        let b = &layout.elements[1];
+        assert_eq!(b.x - b.content_offset_x, 288);
+        assert_eq!(b.y - b.content_offset_y, 100);
+    }
+
+    #[test]
+    fn length_round_trips_through_json() {
+        let pos: Position =
+            serde_json::from_str(r#"{"x": "12.5mm", "y": "25%", "anchor": "baseline"}"#).unwrap();
+        assert_eq!(pos.x, Length::Mm(12.5));
+        assert_eq!(pos.y, Length::Percent(25.0));
+        assert_eq!(
+            serde_json::to_string(&Position::dots(3, 4)).unwrap(),
+            r#"{"x":3,"y":4}"#
+        );
+    }
+
+    #[test]
+    fn rotated_element_swaps_bounding_box() {
+        let mut element = text_element("ROTATED", None);
+        let upright = canvas_with(element.clone()).compute_layout().elements[0].clone();
+
+        element.rotate = 90.0;
+        element.scale = [2.0, 2.0];
+        let rotated = canvas_with(element).compute_layout().elements[0].clone();
+
+        assert_eq!(rotated.full_width, upright.full_height * 2);
+        assert_eq!(rotated.full_height, upright.full_width * 2);
+        // Tall and narrow once the text runs downwards
+        assert!(rotated.height > rotated.width);
+    }
+
+    #[test]
+    fn clip_mask_shapes() {
+        let solid = vec![1.0; 20 * 20];
+        let at = |mask: &[f32], x: usize, y: usize| mask[y * 20 + x];
+
+        let circle = clip_mask(
+            &Clip {
+                shape: ClipShape::Circle,
+                ..Default::default()
+            },
+            &solid,
+            20,
+            20,
+        );
+        assert_eq!(at(&circle, 10, 10), 1.0);
+        assert_eq!(at(&circle, 0, 0), 0.0);
+        // The edge pixel is antialiased but mostly covered
+        assert!(at(&circle, 0, 10) > 0.9);
+
+        let rounded = clip_mask(
+            &Clip {
+                shape: ClipShape::RoundedRect,
+                radius: Some(6.0),
+                ..Default::default()
+            },
+            &solid,
+            20,
+            20,
+        );
+        assert_eq!(at(&rounded, 0, 0), 0.0);
+        assert_eq!(at(&rounded, 0, 10), 1.0);
+
+        let rect = clip_mask(
+            &Clip {
+                x: Some(5),
+                width: Some(10),
+                ..Default::default()
+            },
+            &solid,
+            20,
+            20,
+        );
+        assert_eq!(at(&rect, 4, 10), 0.0);
+        assert_eq!(at(&rect, 5, 10), 1.0);
+        assert_eq!(at(&rect, 14, 10), 1.0);
+        assert_eq!(at(&rect, 15, 10), 0.0);
+    }
+
+    #[test]
+    fn clipped_element_is_transparent_outside_clip() {
+        let json = r#"{"type": "canvas", "elements": [
+            {"text": "BACKGROUND TEXT", "position": {"x": 0, "y": 0}},
+            {"text": "████████████████████████████████████████████████",
+             "position": {"x": 0, "y": 0}, "clip": {"shape": "circle", "x": 400, "width": 40}}
+        ]}"#;
+        let canvas = match serde_json::from_str::<Component>(json).unwrap() {
+            Component::Canvas(c) => c,
+            other => panic!("expected canvas, got {:?}", other),
+        };
+        assert_eq!(
+            canvas.elements[1].clip.as_ref().map(|c| c.shape),
+            Some(ClipShape::Circle)
+        );
+
+        // The solid bar covers the text's box, but only inside its circle,
+        // so the text underneath survives
+        let layout = canvas.compute_layout();
+        assert!(layout.elements[1].x >= 400);
+        assert!(layout.elements[1].width <= 40);
+
+        let unclipped = {
+            let mut canvas = canvas.clone();
+            canvas.elements[1].clip = None;
+            render_raw(&canvas_program(&canvas)).unwrap()
+        };
+        let clipped = render_raw(&canvas_program(&canvas)).unwrap();
+        assert_ne!(clipped.data, unclipped.data);
+    }
+
+    #[test]
+    fn text_mask_stencils_layers_below() {
+        let bar = "█".repeat(48);
+        let composite = |mask: Option<&str>| {
+            let mask = mask.map_or(String::new(), |blend| {
+                format!(
+                    r#", {{"type": "text_mask", "content": "HI", "height": 24,
+                        "blend_mode": "{}", "position": {{"x": 0, "y": 0}}}}"#,
+                    blend
+                )
+            });
+            let json = format!(
+                r#"{{"type": "canvas", "height": 124, "elements": [
+                    {{"text": "{bar}", "position": {{"x": 0, "y": 0}}}},
+                    {{"text": "{bar}", "position": {{"x": 0, "y": 100}}}}{mask}
+                ]}}"#
+            );
+            let Component::Canvas(canvas) = serde_json::from_str(&json).unwrap() else {
+                panic!("expected canvas");
+            };
+            match canvas_program(&canvas).ops.pop() {
+                Some(Op::GrayRaster { data, .. }) => data,
+                other => panic!("expected a gray raster, got {:?}", other),
+            }
+        };
+        let inked = |data: &[u8], rows: std::ops::Range<usize>| {
+            data[rows.start * 576..rows.end * 576]
+                .iter()
+                .filter(|v| **v >= 128)
+                .count()
+        };
+
+        let bars = composite(None);
+        let (top, bottom) = (inked(&bars, 0..24), inked(&bars, 100..124));
+        assert!(top > 0 && bottom > 0);
+
+        // Mask: the top bar shows only through the glyphs, and the bottom
+        // bar, outside the mask's box, is gone
+        let masked = composite(Some("mask"));
+        assert!((1..top / 4).contains(&inked(&masked, 0..24)));
+        assert_eq!(inked(&masked, 100..124), 0);
+
+        // Knockout: the glyphs are cut out of the top bar only
+        let knocked = composite(Some("knockout"));
+        assert!((top * 3 / 4..top).contains(&inked(&knocked, 0..24)));
+        assert_eq!(inked(&knocked, 100..124), bottom);
+    }
+
+    fn canvas_program(canvas: &Canvas) -> Program {
+        let mut ops = Vec::new();
+        canvas.emit(&EmitContext::default(), &mut ops);
+        Program { ops }
+    }
+
+    fn canvas_with(element: CanvasElement) -> Canvas {
+        Canvas {
+            elements: vec![element],
+            ..Default::default()
+        }
+    }
 }
@@ -0,0 +1,167 @@
+//! Compact mode: the same receipt on less paper.
+//!
+//! With [`Document::compact`] set, the document is tightened before it
+//! compiles, so routine prints save paper without a second, hand-shrunk
+//! copy of every template:
+//!
+//! | What | Compact |
+//! |------|---------|
+//! | plain `text` (normal size, no emphasis) | Font B |
+//! | `divider` | Font B; `double` prints `solid` and `equals` prints `dashed` |
+//! | `spacer` | half the space |
+//! | a run of `blank_line`s and `spacer`s | only the first |
+//! | `blank_line`s and `spacer`s at the end | dropped |
+//! | line feeds | 3mm instead of 4mm |
+//!
+//! Headers, totals, bold or enlarged text, graphics and barcodes keep their
+//! size. Station groups are compacted too; canvases keep their layout.
+
+use super::types::{Divider, DividerStyle, Spacer, Text};
+use super::{Component, Document};
+
+impl Document {
+    /// Tighten the layout in place (see the module docs).
+    pub(crate) fn compact_layout(&mut self) {
+        fn walk(components: Vec<Component>) -> Vec<Component> {
+            let mut out: Vec<Component> = Vec::with_capacity(components.len());
+            for component in components {
+                if is_gap(&component) && out.last().is_some_and(is_gap) {
+                    continue;
+                }
+                out.push(compact(component));
+            }
+            while out.last().is_some_and(is_gap) {
+                out.pop();
+            }
+            out
+        }
+
+        fn compact(component: Component) -> Component {
+            match component {
+                Component::Text(text) if is_plain(&text) => Component::Text(Text {
+                    size: [0, 0],
+                    ..text
+                }),
+                Component::Divider(divider) => Component::Divider(Divider {
+                    style: match divider.style {
+                        DividerStyle::Double => DividerStyle::Solid,
+                        DividerStyle::Equals => DividerStyle::Dashed,
+                        style => style,
+                    },
+                    small: true,
+                    ..divider
+                }),
+                Component::Spacer(spacer) => Component::Spacer(Spacer {
+                    units: Some(spacer.feed_units() / 2),
+                    ..Default::default()
+                }),
+                Component::StationGroup(mut group) => {
+                    group.components = walk(group.components);
+                    Component::StationGroup(group)
+                }
+                other => other,
+            }
+        }
+
+        self.document = walk(std::mem::take(&mut self.document));
+    }
+}
+
+/// Blank space between components.
+fn is_gap(component: &Component) -> bool {
+    matches!(component, Component::BlankLine(_) | Component::Spacer(_))
+}
+
+/// Body text: Font A at normal size, with no emphasis.
+fn is_plain(text: &Text) -> bool {
+    text.size == [1, 1]
+        && text.scale.is_none()
+        && text.font.is_none()
+        && !(text.bold
+            || text.underline
+            || text.upperline
+            || text.invert
+            || text.reduced
+            || text.double_width
+            || text.double_height)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::Op;
+    use crate::protocol::text::{Font, LineSpacing};
+
+    fn compacted(json: &str) -> Document {
+        let mut doc: Document = serde_json::from_str(json).unwrap();
+        doc.compact_layout();
+        doc
+    }
+
+    #[test]
+    fn test_gaps_collapse() {
+        let doc = compacted(
+            r#"{"document": [
+                {"text": "a"},
+                {"type": "blank_line"},
+                {"type": "spacer", "mm": 4},
+                {"type": "blank_line"},
+                {"text": "b"},
+                {"type": "spacer", "mm": 3},
+                {"type": "blank_line"}
+            ]}"#,
+        );
+        let labels: Vec<_> = doc.document.iter().map(|c| c.label()).collect();
+        assert_eq!(labels, ["Text", "Blank Line", "Text"]);
+    }
+
+    #[test]
+    fn test_plain_text_and_dividers_shrink() {
+        let doc = compacted(
+            r#"{"document": [
+                {"text": "fine print"},
+                {"text": "TOTAL", "bold": true},
+                {"text": "BIG", "size": 2},
+                {"type": "divider", "style": "double"},
+                {"type": "spacer", "lines": 1}
+            ]}"#,
+        );
+        let sizes: Vec<_> = doc
+            .document
+            .iter()
+            .filter_map(|c| match c {
+                Component::Text(text) => Some(text.size),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(sizes, [[0, 0], [1, 1], [2, 2]]);
+        let Component::Divider(divider) = &doc.document[3] else {
+            panic!("expected a divider");
+        };
+        assert_eq!(divider.style, DividerStyle::Solid);
+        assert!(divider.small);
+    }
+
+    #[test]
+    fn test_compile_compact() {
+        let json = r#"{"document": [
+            {"text": "a"},
+            {"type": "spacer", "mm": 4},
+            {"type": "divider"}
+        ], "compact": true}"#;
+        let doc: Document = serde_json::from_str(json).unwrap();
+        let ops = doc.compile().ops;
+        assert!(ops.contains(&Op::SetLineSpacing(LineSpacing::Tight)));
+        assert!(ops.contains(&Op::Feed { units: 8 }));
+        assert!(ops.contains(&Op::SetFont(Font::B)));
+        assert!(ops.contains(&Op::Text("-".repeat(64))));
+
+        let normal = Document {
+            compact: false,
+            ..doc
+        };
+        let ops = normal.compile().ops;
+        assert!(!ops.iter().any(|op| matches!(op, Op::SetLineSpacing(_))));
+        assert!(ops.contains(&Op::Feed { units: 16 }));
+    }
+}
@@ -0,0 +1,122 @@
+//! JSON wire-format versioning.
+//!
+//! Documents carry a `"version"` field. Payloads from older clients are
+//! upgraded to the current shape before deserialization, and every rewrite
+//! is reported as a deprecation warning so automations can be fixed before
+//! the old shape stops being accepted.
+//!
+//! | Version | Shape                                                   |
+//! |---------|---------------------------------------------------------|
+//! | none    | Unversioned: read as version 1, and a bare component array is wrapped with a warning |
+//! | 1       | Current model                                           |
+//!
+//! No component type or field has been renamed since the first release, so
+//! there are no renames to upgrade yet. When one is renamed, the upgrade
+//! belongs in [`upgrade`], keyed on the version that still used the old name.
+
+use serde_json::Value;
+
+use super::Document;
+
+/// The wire-format version this build reads and writes.
+pub const CURRENT_VERSION: u32 = 1;
+
+/// Keys of components that hold nested component lists.
+pub(crate) const NESTED_KEYS: &[&str] = &["label", "elements", "tile", "components"];
+
+/// A document parsed from JSON, with the deprecations found on the way.
+#[derive(Debug)]
+pub struct Upgraded {
+    pub document: Document,
+    pub warnings: Vec<String>,
+}
+
+/// Parse a JSON payload of any supported version into a [`Document`].
+pub fn parse_document(mut value: Value) -> Result<Upgraded, String> {
+    let warnings = upgrade(&mut value)?;
+    let document = serde_json::from_value(value).map_err(|e| e.to_string())?;
+    Ok(Upgraded { document, warnings })
+}
+
+/// Rewrite a JSON payload to the current shape in place.
+///
+/// Returns one deprecation warning per kind of rewrite. Fails for versions
+/// newer than [`CURRENT_VERSION`].
+pub fn upgrade(value: &mut Value) -> Result<Vec<String>, String> {
+    let mut warnings = Vec::new();
+
+    if value.is_array() {
+        *value = serde_json::json!({ "document": value.take() });
+        warnings
+            .push("bare component arrays are deprecated; send {\"document\": [...]}".to_string());
+    }
+
+    let Some(root) = value.as_object_mut() else {
+        return Err("document must be a JSON object".into());
+    };
+
+    if let Some(v) = root.get("version") {
+        let version = v
+            .as_u64()
+            .and_then(|v| u32::try_from(v).ok())
+            .ok_or_else(|| format!("invalid document version {}", v))?;
+        if version > CURRENT_VERSION {
+            return Err(format!(
+                "document version {} is newer than supported version {}",
+                version, CURRENT_VERSION
+            ));
+        }
+    }
+
+    root.insert("version".into(), CURRENT_VERSION.into());
+    Ok(warnings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document::Component;
+    use serde_json::json;
+
+    #[test]
+    fn test_current_documents_pass_through() {
+        let upgraded = parse_document(json!({
+            "version": 1,
+            "document": [{"text": "hi"}]
+        }))
+        .unwrap();
+        assert!(upgraded.warnings.is_empty());
+        assert_eq!(upgraded.document.version, CURRENT_VERSION);
+    }
+
+    #[test]
+    fn test_bare_arrays_are_wrapped_with_a_warning() {
+        let upgraded = parse_document(json!([
+            {"type": "qr_code", "data": "https://example.com"},
+            {"type": "label_sheet", "label": [{"type": "blank_line"}]}
+        ]))
+        .unwrap();
+
+        let doc = upgraded.document;
+        assert!(
+            matches!(&doc.document[0], Component::QrCode(qr) if qr.data == "https://example.com")
+        );
+        assert!(matches!(&doc.document[1], Component::LabelSheet(_)));
+        assert_eq!(upgraded.warnings.len(), 1);
+    }
+
+    #[test]
+    fn test_unknown_names_are_not_rewritten() {
+        // Names that never shipped are errors, not deprecations
+        assert!(parse_document(json!({"document": [{"type": "qr", "data": "x"}]})).is_err());
+    }
+
+    #[test]
+    fn test_newer_and_invalid_versions_are_rejected() {
+        assert!(parse_document(json!({"version": 99, "document": []})).is_err());
+        // Would truncate to 1 as a u32
+        let err = parse_document(json!({"version": (1u64 << 32) + 1, "document": []}));
+        assert!(err.unwrap_err().contains("invalid document version"));
+        assert!(parse_document(json!({"version": -1, "document": []})).is_err());
+    }
+}
@@ -0,0 +1,53 @@
+//! Compile-time settings shared by every component's emit logic.
+//!
+//! Components don't know which printer they will end up on; the
+//! [`EmitContext`] tells them. Anything that depends on the paper (full-width
+//! rules, banner fitting, table widths, default image and pattern sizes)
+//! reads it from here instead of assuming the 576-dot TSP650II, and sizes
+//! authored in millimeters are converted at the printer's resolution.
+
+use crate::printer::PrinterConfig;
+use crate::protocol::text::Font;
+
+/// Settings for emitting a document's components.
+#[derive(Debug, Clone, Copy)]
+pub struct EmitContext {
+    /// Printer the document is compiled for.
+    pub printer: PrinterConfig,
+}
+
+impl EmitContext {
+    /// Context for compiling to `printer`.
+    pub fn new(printer: &PrinterConfig) -> Self {
+        Self { printer: *printer }
+    }
+
+    /// Printable width in dots (576 on 80mm paper, 384 on 58mm).
+    pub fn print_width(&self) -> usize {
+        self.printer.width_dots as usize
+    }
+
+    /// Characters that fit on one line in `font` at normal width
+    /// (Font A: 48 on 80mm paper, 32 on 58mm).
+    pub fn chars_per_line(&self, font: Font) -> usize {
+        self.print_width() / font.char_width()
+    }
+
+    /// Millimeters in dots at the printer's resolution.
+    pub fn mm_to_dots(&self, mm: f32) -> usize {
+        self.printer.mm_to_dots(mm.max(0.0)) as usize
+    }
+
+    /// A size given in dots and/or millimeters, in dots. Millimeters win
+    /// when both are set.
+    pub fn dots_or_mm(&self, dots: Option<usize>, mm: Option<f32>) -> Option<usize> {
+        mm.map(|mm| self.mm_to_dots(mm)).or(dots)
+    }
+}
+
+impl Default for EmitContext {
+    /// The TSP650II (576 dots).
+    fn default() -> Self {
+        Self::new(&PrinterConfig::TSP650II)
+    }
+}
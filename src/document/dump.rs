@@ -0,0 +1,409 @@
+//! Emit logic for the DataDump component.
+//!
+//! The value is first laid out as logical lines (a nesting depth plus runs
+//! of bold or plain text), then each line is cut to the paper width.
+
+use std::borrow::Cow;
+
+use serde_json::Value;
+
+use super::EmitContext;
+use super::types::{DataDump, DumpFormat};
+use crate::ir::Op;
+use crate::protocol::text::{Alignment, Font};
+
+/// Spaces per nesting level.
+const INDENT: usize = 2;
+
+/// Narrowest line a dump is wrapped to.
+const MIN_DUMP_WIDTH: usize = 8;
+
+/// A line before wrapping.
+struct DumpLine {
+    depth: usize,
+    /// `(text, bold)` runs.
+    spans: Vec<(String, bool)>,
+}
+
+impl DumpLine {
+    fn new(depth: usize) -> Self {
+        Self {
+            depth,
+            spans: Vec::new(),
+        }
+    }
+
+    fn push(&mut self, text: impl Into<String>, bold: bool) {
+        self.spans.push((text.into(), bold));
+    }
+}
+
+impl DataDump {
+    /// Emit IR ops for this data dump component.
+    pub fn emit(&self, ctx: &EmitContext, ops: &mut Vec<Op>) {
+        let width = self
+            .width
+            .unwrap_or(ctx.chars_per_line(Font::B))
+            .max(MIN_DUMP_WIDTH);
+        let data = self.value();
+        let mut lines = Vec::new();
+        match self.format {
+            DumpFormat::Json => self.json(&data, 0, None, false, &mut lines),
+            DumpFormat::Yaml => self.yaml(&data, 0, &mut lines),
+        }
+        let mut rows: Vec<_> = lines.iter().flat_map(|line| wrap(line, width)).collect();
+        match self.max_lines {
+            Some(max) if rows.len() > max => {
+                // The note takes the last allowed line
+                let kept = max.saturating_sub(1);
+                let hidden = rows.len() - kept;
+                rows.truncate(kept);
+                rows.push(vec![(format!("... {} more lines", hidden), false)]);
+            }
+            _ => {}
+        }
+
+        ops.push(Op::PushStyle);
+        ops.push(Op::SetFont(Font::B));
+        ops.push(Op::SetAlign(Alignment::Left));
+        for row in rows {
+            for (text, bold) in row {
+                if text.is_empty() {
+                    continue;
+                }
+                if bold {
+                    ops.push(Op::SetBold(true));
+                    ops.push(Op::Text(text));
+                    ops.push(Op::SetBold(false));
+                } else {
+                    ops.push(Op::Text(text));
+                }
+            }
+            ops.push(Op::Newline);
+        }
+        ops.push(Op::PopStyle);
+    }
+
+    /// `data`, with a string holding a JSON object or array parsed.
+    fn value(&self) -> Cow<'_, Value> {
+        match &self.data {
+            Value::String(s) => match serde_json::from_str::<Value>(s) {
+                Ok(parsed) if parsed.is_object() || parsed.is_array() => Cow::Owned(parsed),
+                _ => Cow::Borrowed(&self.data),
+            },
+            data => Cow::Borrowed(data),
+        }
+    }
+
+    /// Whether `value`, nested `depth` levels deep, prints its contents.
+    fn is_open(&self, value: &Value, depth: usize) -> bool {
+        let filled = match value {
+            Value::Object(map) => !map.is_empty(),
+            Value::Array(items) => !items.is_empty(),
+            _ => false,
+        };
+        filled && self.max_depth.is_none_or(|max| depth < max)
+    }
+
+    /// `s`, cut to `max_string` characters.
+    fn cut<'a>(&self, s: &'a str) -> Cow<'a, str> {
+        match self.max_string {
+            Some(max) if s.chars().count() > max => {
+                Cow::Owned(format!("{}...", s.chars().take(max).collect::<String>()))
+            }
+            _ => Cow::Borrowed(s),
+        }
+    }
+
+    /// A value printed on one line: scalars, empty containers and
+    /// containers past `max_depth`.
+    fn scalar(&self, value: &Value) -> String {
+        match value {
+            Value::String(s) => match self.format {
+                DumpFormat::Json => quote(&self.cut(s)),
+                DumpFormat::Yaml => yaml_string(&self.cut(s)),
+            },
+            Value::Object(map) if map.is_empty() => "{}".into(),
+            Value::Object(_) => "{...}".into(),
+            Value::Array(items) if items.is_empty() => "[]".into(),
+            Value::Array(_) => "[...]".into(),
+            other => other.to_string(),
+        }
+    }
+
+    fn json(
+        &self,
+        value: &Value,
+        depth: usize,
+        key: Option<&str>,
+        comma: bool,
+        lines: &mut Vec<DumpLine>,
+    ) {
+        let mut line = DumpLine::new(depth);
+        if let Some(key) = key {
+            line.push(quote(key), self.bold_keys);
+            line.push(": ", false);
+        }
+        let tail = if comma { "," } else { "" };
+        match value {
+            Value::Object(map) if self.is_open(value, depth) => {
+                line.push("{", false);
+                lines.push(line);
+                for (i, (key, value)) in map.iter().enumerate() {
+                    self.json(value, depth + 1, Some(key), i + 1 < map.len(), lines);
+                }
+                let mut close = DumpLine::new(depth);
+                close.push(format!("}}{}", tail), false);
+                lines.push(close);
+            }
+            Value::Array(items) if self.is_open(value, depth) => {
+                line.push("[", false);
+                lines.push(line);
+                for (i, item) in items.iter().enumerate() {
+                    self.json(item, depth + 1, None, i + 1 < items.len(), lines);
+                }
+                let mut close = DumpLine::new(depth);
+                close.push(format!("]{}", tail), false);
+                lines.push(close);
+            }
+            _ => {
+                line.push(format!("{}{}", self.scalar(value), tail), false);
+                lines.push(line);
+            }
+        }
+    }
+
+    fn yaml(&self, value: &Value, depth: usize, lines: &mut Vec<DumpLine>) {
+        match value {
+            Value::Object(map) if self.is_open(value, depth) => {
+                for (key, value) in map {
+                    let mut line = DumpLine::new(depth);
+                    line.push(yaml_string(key), self.bold_keys);
+                    if self.is_open(value, depth + 1) {
+                        line.push(":", false);
+                        lines.push(line);
+                        self.yaml(value, depth + 1, lines);
+                    } else {
+                        line.push(format!(": {}", self.scalar(value)), false);
+                        lines.push(line);
+                    }
+                }
+            }
+            Value::Array(items) if self.is_open(value, depth) => {
+                for item in items {
+                    if self.is_open(item, depth + 1) {
+                        // The item's first line moves up beside its dash
+                        let first = lines.len();
+                        self.yaml(item, depth + 1, lines);
+                        lines[first].depth = depth;
+                        lines[first].spans.insert(0, ("- ".into(), false));
+                    } else {
+                        let mut line = DumpLine::new(depth);
+                        line.push(format!("- {}", self.scalar(item)), false);
+                        lines.push(line);
+                    }
+                }
+            }
+            _ => {
+                let mut line = DumpLine::new(depth);
+                line.push(self.scalar(value), false);
+                lines.push(line);
+            }
+        }
+    }
+}
+
+/// `s` as a JSON string literal.
+fn quote(s: &str) -> String {
+    Value::String(s.to_string()).to_string()
+}
+
+/// `s` bare if YAML would read it back as the same string, else quoted.
+fn yaml_string(s: &str) -> String {
+    let plain = s
+        .chars()
+        .all(|c| c.is_alphanumeric() || " _-./@+()".contains(c))
+        && s.starts_with(|c: char| c.is_alphanumeric() || c == '_' || c == '/')
+        && !s.ends_with(' ')
+        && s.parse::<f64>().is_err()
+        && !matches!(
+            s.to_lowercase().as_str(),
+            "true" | "false" | "null" | "yes" | "no" | "on" | "off"
+        );
+    if plain { s.to_string() } else { quote(s) }
+}
+
+/// Cut a line into rows of at most `width` characters. Rows after the
+/// first are indented one more level, under the line's start.
+fn wrap(line: &DumpLine, width: usize) -> Vec<Vec<(String, bool)>> {
+    let chars: Vec<(char, bool)> = line
+        .spans
+        .iter()
+        .flat_map(|(text, bold)| text.chars().map(move |c| (c, *bold)))
+        .collect();
+    let indent = line.depth * INDENT;
+    // Deep nesting still leaves half the line for text
+    let mut pad = indent.min(width / 2);
+    let mut rest = chars.as_slice();
+    let mut rows = Vec::new();
+    loop {
+        let (row, tail) = rest.split_at(rest.len().min(width - pad));
+        let mut spans = vec![(" ".repeat(pad), false)];
+        for &(c, bold) in row {
+            match spans.last_mut() {
+                Some((text, b)) if *b == bold => text.push(c),
+                _ => spans.push((c.to_string(), bold)),
+            }
+        }
+        rows.push(spans);
+        rest = tail;
+        if rest.is_empty() {
+            return rows;
+        }
+        pad = (indent + INDENT).min(width / 2);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn dump_lines(dump: &DataDump) -> Vec<String> {
+        let mut ops = Vec::new();
+        dump.emit(&EmitContext::default(), &mut ops);
+        let mut lines = vec![String::new()];
+        for op in &ops {
+            match op {
+                Op::Text(text) => lines.last_mut().unwrap().push_str(text),
+                Op::Newline => lines.push(String::new()),
+                _ => {}
+            }
+        }
+        lines.pop();
+        lines
+    }
+
+    fn payload() -> Value {
+        json!({
+            "event": "door_open",
+            "sensor": {"id": 7, "tags": ["front", "true"]},
+            "readings": [{"at": "09:30", "ok": true}, []],
+            "note": null
+        })
+    }
+
+    #[test]
+    fn test_json_dump() {
+        assert_eq!(
+            dump_lines(&DataDump::new(payload())),
+            [
+                "{",
+                "  \"event\": \"door_open\",",
+                "  \"note\": null,",
+                "  \"readings\": [",
+                "    {",
+                "      \"at\": \"09:30\",",
+                "      \"ok\": true",
+                "    },",
+                "    []",
+                "  ],",
+                "  \"sensor\": {",
+                "    \"id\": 7,",
+                "    \"tags\": [",
+                "      \"front\",",
+                "      \"true\"",
+                "    ]",
+                "  }",
+                "}",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_yaml_dump() {
+        let dump = DataDump {
+            format: DumpFormat::Yaml,
+            ..DataDump::new(payload())
+        };
+        assert_eq!(
+            dump_lines(&dump),
+            [
+                "event: door_open",
+                "note: null",
+                "readings:",
+                "  - at: \"09:30\"",
+                "    ok: true",
+                "  - []",
+                "sensor:",
+                "  id: 7",
+                "  tags:",
+                "    - front",
+                "    - \"true\"",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_limits() {
+        let dump = DataDump {
+            format: DumpFormat::Yaml,
+            max_depth: Some(1),
+            max_string: Some(4),
+            ..DataDump::new(payload())
+        };
+        assert_eq!(
+            dump_lines(&dump),
+            [
+                "event: door...",
+                "note: null",
+                "readings: [...]",
+                "sensor: {...}"
+            ]
+        );
+
+        let dump = DataDump {
+            max_lines: Some(3),
+            ..DataDump::new(payload())
+        };
+        assert_eq!(
+            dump_lines(&dump),
+            ["{", "  \"event\": \"door_open\",", "... 16 more lines"]
+        );
+    }
+
+    #[test]
+    fn test_wrapping_and_bold_keys() {
+        let dump = DataDump {
+            width: Some(16),
+            ..DataDump::new(json!({"message": "the back door is open"}))
+        };
+        assert_eq!(
+            dump_lines(&dump),
+            [
+                "{",
+                "  \"message\": \"th",
+                "    e back door ",
+                "    is open\"",
+                "}"
+            ]
+        );
+
+        let mut ops = Vec::new();
+        dump.emit(&EmitContext::default(), &mut ops);
+        assert!(ops.contains(&Op::SetFont(Font::B)));
+        let bold = ops.iter().position(|op| *op == Op::SetBold(true)).unwrap();
+        assert_eq!(ops[bold + 1], Op::Text("\"message\"".into()));
+    }
+
+    #[test]
+    fn test_string_data_is_parsed() {
+        let dump = DataDump::new(json!(r#"{"a": [1, 2]}"#));
+        assert_eq!(
+            dump_lines(&dump),
+            ["{", "  \"a\": [", "    1,", "    2", "  ]", "}"]
+        );
+        let text = DataDump::new(json!("not json"));
+        assert_eq!(dump_lines(&text), ["\"not json\""]);
+    }
+}
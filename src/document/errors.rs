@@ -0,0 +1,195 @@
+//! Per-component error isolation.
+//!
+//! A component that can't be printed (an image that failed to download, a
+//! QR code with more data than fits, an unknown barcode format) fails the
+//! whole print by default. Unattended deployments such as kiosks would
+//! rather print the rest of the receipt, so the document's `on_error`
+//! policy decides:
+//!
+//! | `on_error` | Failing component |
+//! |------------|-------------------|
+//! | `fail` (default) | the print is rejected |
+//! | `skip` | left out |
+//! | `placeholder` | a "[QR Code unavailable]" line in its place |
+//!
+//! [`Document::component_errors`] lists the failures so they can be
+//! reported back. Components nested in canvases, station groups, label
+//! sheets and textures are checked with their top-level component, which
+//! is skipped or replaced as a whole.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use super::types::Text;
+use super::{Component, Document};
+
+/// What to do with components that can't be printed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OnError {
+    /// Reject the print.
+    #[default]
+    Fail,
+    /// Print the document without the failing components.
+    Skip,
+    /// Print a placeholder line where each failing component was.
+    Placeholder,
+}
+
+/// A top-level component that can't be printed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ComponentError {
+    /// Position in the document's `document` array.
+    pub index: usize,
+    /// Component label, e.g. "QR Code".
+    pub component: &'static str,
+    pub message: String,
+}
+
+impl fmt::Display for ComponentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "document[{}] ({}): {}",
+            self.index, self.component, self.message
+        )
+    }
+}
+
+impl Document {
+    /// Every top-level component that can't be printed, in order.
+    ///
+    /// Images are only known to be printable once resolved, so check after
+    /// [`ImageResolver::resolve`](super::ImageResolver::resolve).
+    ///
+    /// ```
+    /// use estrella::document::Document;
+    ///
+    /// let doc: Document = serde_json::from_str(r#"{"document": [
+    ///     {"text": "Thanks!"},
+    ///     {"type": "barcode", "format": "code11", "data": "123"}
+    /// ]}"#).unwrap();
+    ///
+    /// let errors = doc.component_errors();
+    /// assert_eq!(errors.len(), 1);
+    /// assert_eq!(errors[0].index, 1);
+    /// ```
+    pub fn component_errors(&self) -> Vec<ComponentError> {
+        self.document
+            .iter()
+            .enumerate()
+            .filter_map(|(index, component)| {
+                check(component).err().map(|message| ComponentError {
+                    index,
+                    component: component.label(),
+                    message,
+                })
+            })
+            .collect()
+    }
+
+    /// Skip or replace failing components as `on_error` says. With
+    /// [`OnError::Fail`] the document is left as it is.
+    pub(crate) fn isolate_errors(&mut self) {
+        let placeholder = match self.on_error {
+            OnError::Fail => return,
+            OnError::Skip => false,
+            OnError::Placeholder => true,
+        };
+        let components = std::mem::take(&mut self.document);
+        for component in components {
+            if check(&component).is_ok() {
+                self.document.push(component);
+            } else if placeholder {
+                self.document.push(Component::Text(Text {
+                    content: format!("[{} unavailable]", component.label()),
+                    center: true,
+                    invert: true,
+                    ..Default::default()
+                }));
+            }
+        }
+    }
+}
+
+/// Why pattern components can't be printed without the `patterns` feature.
+#[cfg(not(feature = "patterns"))]
+const NO_PATTERNS: &str = "this build can't draw patterns (`patterns` feature)";
+
+/// Why HTML components can't be printed without the `html` feature.
+#[cfg(not(feature = "html"))]
+const NO_HTML: &str = "this build can't convert HTML (`html` feature)";
+
+/// Why a component can't be printed, if it can't.
+fn check(component: &Component) -> Result<(), String> {
+    match component {
+        Component::QrCode(qr) => qr.check(),
+        Component::Barcode(barcode) => barcode.check(),
+        Component::Image(image) => image.check(),
+        Component::Canvas(canvas) => canvas
+            .elements
+            .iter()
+            .try_for_each(|element| check(&element.component)),
+        Component::StationGroup(group) => group.components.iter().try_for_each(check),
+        Component::Conditional(conditional) => conditional.components.iter().try_for_each(check),
+        Component::LabelSheet(sheet) => {
+            sheet.check()?;
+            sheet.label.iter().try_for_each(check)
+        }
+        #[cfg(not(feature = "patterns"))]
+        Component::Pattern(_) | Component::Composition(_) => Err(NO_PATTERNS.into()),
+        #[cfg(not(feature = "patterns"))]
+        Component::Texture(texture) if texture.pattern.is_some() => Err(NO_PATTERNS.into()),
+        Component::Texture(texture) => texture.tile.iter().try_for_each(check),
+        #[cfg(not(feature = "html"))]
+        Component::Html(_) => Err(NO_HTML.into()),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document::testing::{compiled_texts, fixture};
+    use crate::ir::Op;
+    use serde_json::json;
+
+    fn doc(on_error: &str) -> Document {
+        fixture(json!({"on_error": on_error, "document": [
+            {"text": "BEFORE"},
+            {"qr_code": "x".repeat(8000)},
+            {"image": "https://example.com/missing.png"},
+            {"text": "AFTER"}
+        ]}))
+    }
+
+    #[test]
+    fn test_component_errors() {
+        let errors = doc("fail").component_errors();
+        assert_eq!(errors.iter().map(|e| e.index).collect::<Vec<_>>(), [1, 2]);
+        assert_eq!(errors[0].component, "QR Code");
+        assert!(errors[1].to_string().contains("missing.png"));
+    }
+
+    #[test]
+    fn test_skip_and_placeholder() {
+        assert_eq!(compiled_texts(&doc("skip")), ["BEFORE", "AFTER"]);
+        assert_eq!(
+            compiled_texts(&doc("placeholder")),
+            [
+                "BEFORE",
+                "[QR Code unavailable]",
+                "[Image unavailable]",
+                "AFTER"
+            ]
+        );
+        // The default leaves failing components to the printer
+        assert!(
+            doc("fail")
+                .compile()
+                .ops
+                .iter()
+                .any(|op| matches!(op, Op::QrCode { .. }))
+        );
+    }
+}
@@ -0,0 +1,291 @@
+//! Filters in `{{variable}}` placeholders.
+//!
+//! A placeholder can pipe its variable through filters, left to right:
+//! `{{name | upper}}`, `{{price | currency:"EUR"}}`,
+//! `{{iso_date | format:"%d/%m"}}`. Arguments follow a colon, quoted or
+//! not.
+//!
+//! | Filter | Result |
+//! |--------|--------|
+//! | `upper`, `lower` | Case changed |
+//! | `capitalize` | First letter upper case |
+//! | `trim` | Surrounding whitespace removed |
+//! | `truncate:N` | At most N characters, the last one `…` if cut |
+//! | `number:N` | A number with N decimals (default 2) |
+//! | `currency:CODE` | A number as money: `$4.50`, `€4.50`, `CHF 4.50` (default USD) |
+//! | `format:FMT` | A date or time reformatted with strftime `FMT` |
+//! | `default:TEXT` | `TEXT` when the variable is unset or empty |
+//!
+//! A filter that doesn't apply (`currency` on text that isn't a number, a
+//! date that can't be read) leaves the value as it is. A placeholder with
+//! an unknown filter is left alone, so the mistake shows on the receipt.
+
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime};
+
+/// Replace every placeholder whose variable `lookup` knows. With
+/// `defaults`, unknown variables also take their `default` filter's text;
+/// other placeholders are left as they are.
+pub(crate) fn interpolate(
+    template: &str,
+    lookup: impl Fn(&str) -> Option<String>,
+    defaults: bool,
+) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        let end = start + len + 4;
+        out.push_str(&rest[..start]);
+        match fill(&rest[start + 2..end - 2], &lookup, defaults) {
+            Some(value) => out.push_str(&value),
+            None => out.push_str(&rest[start..end]),
+        }
+        rest = &rest[end..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// The text for one placeholder (what's between the braces), if it can be
+/// filled.
+fn fill(
+    placeholder: &str,
+    lookup: &impl Fn(&str) -> Option<String>,
+    defaults: bool,
+) -> Option<String> {
+    let mut parts = split_unquoted(placeholder, '|').into_iter();
+    let name = parts.next()?.trim().to_string();
+    let filters: Vec<Filter> = parts
+        .map(|part| Filter::parse(&part))
+        .collect::<Option<_>>()?;
+    let value = match lookup(&name) {
+        Some(value) => value,
+        None if defaults && filters.iter().any(|f| f.name == "default") => String::new(),
+        None => return None,
+    };
+    Some(
+        filters
+            .iter()
+            .fold(value, |value, filter| filter.apply(value)),
+    )
+}
+
+/// One filter with its arguments, unquoted.
+struct Filter {
+    name: String,
+    args: Vec<String>,
+}
+
+const FILTERS: &[&str] = &[
+    "upper",
+    "lower",
+    "capitalize",
+    "trim",
+    "truncate",
+    "number",
+    "currency",
+    "format",
+    "default",
+];
+
+impl Filter {
+    /// Parse `name:arg:arg`; `None` for filters that don't exist.
+    fn parse(text: &str) -> Option<Self> {
+        let mut parts = split_unquoted(text, ':')
+            .into_iter()
+            .map(|part| unquote(&part));
+        let name = parts.next()?;
+        FILTERS.contains(&name.as_str()).then(|| Self {
+            name,
+            args: parts.collect(),
+        })
+    }
+
+    fn arg(&self) -> Option<&str> {
+        self.args.first().map(String::as_str)
+    }
+
+    fn apply(&self, value: String) -> String {
+        match self.name.as_str() {
+            "upper" => value.to_uppercase(),
+            "lower" => value.to_lowercase(),
+            "capitalize" => {
+                let mut chars = value.chars();
+                match chars.next() {
+                    Some(first) => first.to_uppercase().chain(chars).collect(),
+                    None => value,
+                }
+            }
+            "trim" => value.trim().to_string(),
+            "truncate" => match self.arg().and_then(|n| n.parse::<usize>().ok()) {
+                Some(max) if value.chars().count() > max => match max {
+                    0 => String::new(),
+                    _ => value.chars().take(max - 1).chain(['…']).collect(),
+                },
+                _ => value,
+            },
+            "number" => {
+                let decimals = self.arg().and_then(|n| n.parse().ok()).unwrap_or(2);
+                match value.trim().parse::<f64>() {
+                    Ok(n) => format!("{:.*}", decimals, n),
+                    Err(_) => value,
+                }
+            }
+            "currency" => match value.trim().parse::<f64>() {
+                Ok(n) => currency(n, self.arg().unwrap_or("USD")),
+                Err(_) => value,
+            },
+            "format" => match self.arg() {
+                Some(format) => format_datetime(&value, format).unwrap_or(value),
+                None => value,
+            },
+            "default" if value.trim().is_empty() => self.arg().unwrap_or_default().to_string(),
+            _ => value,
+        }
+    }
+}
+
+/// `amount` as money in the ISO 4217 currency `code`.
+fn currency(amount: f64, code: &str) -> String {
+    let code = code.to_uppercase();
+    let (symbol, decimals) = match code.as_str() {
+        "USD" | "CAD" | "AUD" | "NZD" | "MXN" | "CLP" | "ARS" => ("$", 2),
+        "EUR" => ("€", 2),
+        "GBP" => ("£", 2),
+        "JPY" | "CNY" => ("¥", 0),
+        "KRW" => ("₩", 0),
+        "INR" => ("₹", 2),
+        _ => ("", 2),
+    };
+    let decimals = if code == "CLP" { 0 } else { decimals };
+    let sign = if amount < 0.0 { "-" } else { "" };
+    let number = format!("{:.*}", decimals, amount.abs());
+    match symbol {
+        "" => format!("{}{} {}", sign, code, number),
+        _ => format!("{}{}{}", sign, symbol, number),
+    }
+}
+
+/// Reformat a date, time or timestamp; `None` if `value` isn't one or
+/// `format` isn't a valid strftime format.
+fn format_datetime(value: &str, format: &str) -> Option<String> {
+    let items: Vec<Item> = StrftimeItems::new(format).collect();
+    if items.iter().any(|item| matches!(item, Item::Error)) {
+        return None;
+    }
+    let value = value.trim();
+    let datetime = DateTime::parse_from_rfc3339(value)
+        .map(|dt| dt.naive_local())
+        .ok()
+        .or_else(|| {
+            ["%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S", "%Y-%m-%d %H:%M"]
+                .iter()
+                .find_map(|f| NaiveDateTime::parse_from_str(value, f).ok())
+        })
+        .or_else(|| {
+            ["%Y-%m-%d", "%B %d, %Y", "%d/%m/%Y"]
+                .iter()
+                .find_map(|f| NaiveDate::parse_from_str(value, f).ok())
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+        })
+        .or_else(|| {
+            ["%H:%M:%S", "%H:%M", "%I:%M %p"]
+                .iter()
+                .find_map(|f| NaiveTime::parse_from_str(value, f).ok())
+                .map(|time| NaiveDate::default().and_time(time))
+        })
+        .or_else(|| {
+            // Unix timestamps, as Home Assistant sends them
+            let seconds = value.parse::<i64>().ok()?;
+            DateTime::from_timestamp(seconds, 0).map(|dt| dt.naive_utc())
+        })?;
+    Some(datetime.format_with_items(items.into_iter()).to_string())
+}
+
+/// Split on `separator` outside of single or double quotes.
+fn split_unquoted(text: &str, separator: char) -> Vec<String> {
+    let mut parts = vec![String::new()];
+    let mut quote = None;
+    for c in text.chars() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            None if c == '"' || c == '\'' => quote = Some(c),
+            None if c == separator => {
+                parts.push(String::new());
+                continue;
+            }
+            _ => {}
+        }
+        if let Some(part) = parts.last_mut() {
+            part.push(c);
+        }
+    }
+    parts
+}
+
+/// Trim `text` and drop one pair of surrounding quotes.
+fn unquote(text: &str) -> String {
+    let text = text.trim();
+    for q in ['"', '\''] {
+        if let Some(inner) = text.strip_prefix(q).and_then(|t| t.strip_suffix(q)) {
+            return inner.to_string();
+        }
+    }
+    text.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn render(template: &str) -> String {
+        let vars: HashMap<&str, &str> = HashMap::from([
+            ("name", "jojo"),
+            ("price", "4.5"),
+            ("when", "2026-01-27 09:30:00"),
+            ("note", "Leave at the front desk"),
+            ("empty", ""),
+        ]);
+        interpolate(template, |name| vars.get(name).map(|v| v.to_string()), true)
+    }
+
+    #[test]
+    fn test_filters() {
+        assert_eq!(render("{{ name | upper }}"), "JOJO");
+        assert_eq!(render("{{name|capitalize}}!"), "Jojo!");
+        assert_eq!(render("{{price | currency:\"EUR\"}}"), "€4.50");
+        assert_eq!(render("{{price | currency:CHF}}"), "CHF 4.50");
+        assert_eq!(render("{{price | currency}}"), "$4.50");
+        assert_eq!(render("{{price | number:1}}"), "4.5");
+        assert_eq!(render("{{when | format:\"%d/%m %H:%M\"}}"), "27/01 09:30");
+        assert_eq!(render("{{note | truncate:10}}"), "Leave at …");
+        assert_eq!(render("{{note | truncate:30}}"), "Leave at the front desk");
+        assert_eq!(render("{{name | trim | upper | truncate:3}}"), "JO…");
+    }
+
+    #[test]
+    fn test_defaults_and_unknowns() {
+        assert_eq!(render("Hi {{empty | default:'guest'}}"), "Hi guest");
+        assert_eq!(render("Hi {{nobody | default:\"guest\"}}"), "Hi guest");
+        // Left alone: unknown variables, unknown filters, values the
+        // filter can't read
+        assert_eq!(
+            render("{{nobody}} {{name | shout}}"),
+            "{{nobody}} {{name | shout}}"
+        );
+        assert_eq!(render("{{name | currency:EUR}}"), "jojo");
+        assert_eq!(render("{{name | format:\"%d\"}}"), "jojo");
+        // A colon or pipe inside quotes is part of the argument
+        assert_eq!(render("{{when | format:\"%H:%M|\"}}"), "09:30|");
+    }
+
+    #[test]
+    fn test_unknown_variable_default_is_kept_without_defaults() {
+        let out = interpolate("{{x | default:'y'}}", |_| None, false);
+        assert_eq!(out, "{{x | default:'y'}}");
+    }
+}
@@ -0,0 +1,262 @@
+//! Canonical formatting for JSON documents (`estrella fmt`).
+//!
+//! Formatting parses a document (upgrading legacy shapes, see [`compat`]),
+//! re-serializes it, and then:
+//!
+//! - drops every field whose value is the default, so files only say what
+//!   differs from a plain component;
+//! - orders keys canonically: `version` and the component `type` first,
+//!   then the rest alphabetically;
+//! - optionally collapses components to their shorthand form
+//!   (`{"text": "hi"}` instead of `{"type": "text", "content": "hi"}`).
+//!
+//! The formatted document always deserializes to the same [`Document`] as
+//! the input.
+//!
+//! [`compat`]: super::compat
+
+use serde_json::Value;
+
+use super::{Document, SHORTHANDS, compat};
+
+/// Keys written before all others, in this order.
+const LEAD_KEYS: &[&str] = &["version", "type"];
+
+/// A formatted document.
+#[derive(Debug)]
+pub struct Formatted {
+    /// Pretty-printed JSON, ending in a newline.
+    pub output: String,
+    /// Legacy shapes that were upgraded on the way.
+    pub warnings: Vec<String>,
+}
+
+/// Format a JSON document. With `compact`, components use shorthand keys
+/// where one exists.
+pub fn format_document(input: &str, compact: bool) -> Result<Formatted, String> {
+    let value: Value = serde_json::from_str(input).map_err(|e| e.to_string())?;
+    let upgraded = compat::parse_document(value)?;
+    let full = serde_json::to_value(&upgraded.document).map_err(|e| e.to_string())?;
+
+    let mut value = full.clone();
+    prune_defaults(&mut value, &full);
+    if compact && let Some(Value::Array(components)) = value.get_mut("document") {
+        collapse_shorthands(components);
+    }
+
+    let mut output = String::new();
+    write_value(&value, 0, &mut output);
+    output.push('\n');
+    Ok(Formatted {
+        output,
+        warnings: upgraded.warnings,
+    })
+}
+
+/// One step of a path into a JSON value.
+#[derive(Debug, Clone)]
+enum Step {
+    Key(String),
+    Index(usize),
+}
+
+/// Remove every field that doesn't change the parsed document.
+///
+/// Each field is dropped on a trial basis and kept out only if the document
+/// still round-trips to `full`, so defaults declared anywhere (serde
+/// attributes, custom deserializers) are handled alike.
+fn prune_defaults(value: &mut Value, full: &Value) {
+    let mut paths = Vec::new();
+    collect_paths(value, &mut Vec::new(), &mut paths);
+
+    for path in paths {
+        if matches!(path.as_slice(), [Step::Key(k)] if k == "version" || k == "document") {
+            continue;
+        }
+        if matches!(path.last(), Some(Step::Key(k)) if k == "type") {
+            continue;
+        }
+        let mut candidate = value.clone();
+        if remove_at(&mut candidate, &path) && round_trip(&candidate).as_ref() == Some(full) {
+            *value = candidate;
+        }
+    }
+}
+
+/// Paths of all object fields, parents before children.
+fn collect_paths(value: &Value, prefix: &mut Vec<Step>, out: &mut Vec<Vec<Step>>) {
+    match value {
+        Value::Object(map) => {
+            for (key, child) in map {
+                prefix.push(Step::Key(key.clone()));
+                out.push(prefix.clone());
+                collect_paths(child, prefix, out);
+                prefix.pop();
+            }
+        }
+        Value::Array(items) => {
+            for (i, child) in items.iter().enumerate() {
+                prefix.push(Step::Index(i));
+                collect_paths(child, prefix, out);
+                prefix.pop();
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Remove the field at `path`. Returns false if it no longer exists.
+fn remove_at(value: &mut Value, path: &[Step]) -> bool {
+    let Some((last, parents)) = path.split_last() else {
+        return false;
+    };
+    let mut target = value;
+    for step in parents {
+        target = match (step, target) {
+            (Step::Key(key), Value::Object(map)) => match map.get_mut(key) {
+                Some(child) => child,
+                None => return false,
+            },
+            (Step::Index(i), Value::Array(items)) => match items.get_mut(*i) {
+                Some(child) => child,
+                None => return false,
+            },
+            _ => return false,
+        };
+    }
+    match (last, target) {
+        (Step::Key(key), Value::Object(map)) => map.remove(key).is_some(),
+        _ => false,
+    }
+}
+
+fn round_trip(value: &Value) -> Option<Value> {
+    let doc: Document = serde_json::from_value(value.clone()).ok()?;
+    serde_json::to_value(&doc).ok()
+}
+
+/// Rewrite components to shorthand form, recursively.
+fn collapse_shorthands(components: &mut [Value]) {
+    for component in components {
+        let Value::Object(obj) = component else {
+            continue;
+        };
+        for key in compat::NESTED_KEYS {
+            if let Some(Value::Array(nested)) = obj.get_mut(*key) {
+                collapse_shorthands(nested);
+            }
+        }
+
+        let Some(type_name) = obj.get("type").and_then(Value::as_str) else {
+            continue;
+        };
+        let shorthand = SHORTHANDS.iter().find(|(key, ty, field)| {
+            *ty == type_name && obj.contains_key(*field) && !obj.contains_key(*key)
+        });
+        if let Some(&(key, _, field)) = shorthand
+            && let Some(value) = obj.remove(field)
+        {
+            obj.remove("type");
+            obj.insert(key.into(), value);
+        }
+    }
+}
+
+/// Sort keys: [`LEAD_KEYS`], then shorthand keys, then alphabetically.
+fn key_rank(key: &str) -> (usize, &str) {
+    let lead = LEAD_KEYS
+        .iter()
+        .position(|k| *k == key)
+        .or_else(|| {
+            SHORTHANDS
+                .iter()
+                .any(|(k, _, _)| *k == key)
+                .then_some(LEAD_KEYS.len())
+        })
+        .unwrap_or(usize::MAX);
+    (lead, key)
+}
+
+/// Pretty-print a value like `serde_json::to_string_pretty`, with
+/// canonical key order.
+fn write_value(value: &Value, indent: usize, out: &mut String) {
+    let pad = |level: usize| "  ".repeat(level);
+    match value {
+        Value::Object(map) if !map.is_empty() => {
+            let mut keys: Vec<&str> = map.keys().map(String::as_str).collect();
+            keys.sort_by_key(|&k| key_rank(k));
+            out.push_str("{\n");
+            for (i, key) in keys.iter().enumerate() {
+                out.push_str(&pad(indent + 1));
+                out.push_str(&Value::String(key.to_string()).to_string());
+                out.push_str(": ");
+                write_value(&map[*key], indent + 1, out);
+                out.push_str(if i + 1 < keys.len() { ",\n" } else { "\n" });
+            }
+            out.push_str(&pad(indent));
+            out.push('}');
+        }
+        Value::Array(items) if !items.is_empty() => {
+            out.push_str("[\n");
+            for (i, item) in items.iter().enumerate() {
+                out.push_str(&pad(indent + 1));
+                write_value(item, indent + 1, out);
+                out.push_str(if i + 1 < items.len() { ",\n" } else { "\n" });
+            }
+            out.push_str(&pad(indent));
+            out.push(']');
+        }
+        other => out.push_str(&other.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INPUT: &str = r#"{"document": [
+        {"text": "hi", "bold": true},
+        {"type": "divider", "style": "double"},
+        {"type": "canvas", "height": 80, "elements": [
+            {"text": "A", "position": {"x": 4, "y": 8}, "opacity": 1.0}
+        ]}
+    ], "cut": true}"#;
+
+    fn reparse(output: &str) -> Value {
+        serde_json::to_value(serde_json::from_str::<Document>(output).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_expanded_drops_defaults_and_round_trips() {
+        let formatted = format_document(INPUT, false).unwrap();
+        let out = &formatted.output;
+        assert!(out.starts_with(
+            "{\n  \"version\": 1,\n  \"document\": [\n    {\n      \"type\": \"text\""
+        ));
+        assert!(!out.contains("\"cut\""));
+        assert!(!out.contains("\"opacity\""));
+        assert!(!out.contains("null"));
+        assert!(out.contains("\"x\": 4"));
+        assert_eq!(reparse(out), reparse(INPUT));
+
+        // Formatting is idempotent
+        assert_eq!(format_document(out, false).unwrap().output, *out);
+    }
+
+    #[test]
+    fn test_compact_uses_shorthands() {
+        let out = format_document(INPUT, true).unwrap().output;
+        assert!(out.contains("\"text\": \"hi\""));
+        assert!(out.contains("\"divider\": \"double\""));
+        assert!(out.contains("\"canvas\": ["));
+        assert!(!out.contains("\"type\""));
+        assert_eq!(reparse(&out), reparse(INPUT));
+    }
+
+    #[test]
+    fn test_legacy_input_is_upgraded() {
+        let formatted = format_document(r#"[{"type": "qr_code", "data": "x"}]"#, false).unwrap();
+        assert!(formatted.output.contains("\"document\": ["));
+        assert!(!formatted.warnings.is_empty());
+    }
+}
@@ -0,0 +1,208 @@
+//! Gift receipts: the same receipt with the prices taken out.
+//!
+//! [`Document::as_gift_receipt`] compiles a document with its price-bearing
+//! components redacted, so a shop can hand out a gift receipt for any sale
+//! it has already described, or reprint one from the job history. Each
+//! component's `gift` field ([`GiftMode`]) says what happens to it:
+//!
+//! | Component | Default | `blank` prints |
+//! |-----------|---------|----------------|
+//! | `line_item` | `blank` | the name |
+//! | `total` | `replace` | the label |
+//! | `columns` | `keep` | the left side |
+//! | `text` | `keep` | nothing |
+
+use super::types::{Columns, GiftMode, Text};
+use super::{Component, Document};
+
+/// Printed in place of components with [`GiftMode::Replace`].
+const GIFT_RECEIPT_LABEL: &str = "GIFT RECEIPT";
+
+impl Document {
+    /// A copy of this document compiled as a gift receipt.
+    ///
+    /// ```
+    /// use estrella::document::Document;
+    ///
+    /// let doc: Document = serde_json::from_str(r#"{"document": [
+    ///     {"line_item": "Scarf", "price": 24.0},
+    ///     {"total": 24.0},
+    ///     {"text": "Paid by card", "gift": "hide"}
+    /// ]}"#).unwrap();
+    ///
+    /// let _program = doc.as_gift_receipt().compile();
+    /// // ...prints "Scarf" and "GIFT RECEIPT"
+    /// ```
+    pub fn as_gift_receipt(&self) -> Document {
+        Document {
+            gift_receipt: true,
+            ..self.clone()
+        }
+    }
+
+    /// Redact prices in place according to each component's [`GiftMode`].
+    ///
+    /// Station groups and canvas elements are redacted too. A run of
+    /// consecutive replaced components prints one "GIFT RECEIPT" line, so a
+    /// subtotal, tax and total don't repeat it.
+    pub(crate) fn redact_for_gift(&mut self) {
+        fn walk(components: Vec<Component>) -> Vec<Component> {
+            let mut out = Vec::with_capacity(components.len());
+            let mut replaced = false;
+            for component in components {
+                let redacted = match gift_mode(&component) {
+                    GiftMode::Keep => Some(component),
+                    GiftMode::Hide => None,
+                    GiftMode::Blank => blank(component),
+                    GiftMode::Replace => {
+                        if !replaced {
+                            out.push(Component::Text(Text {
+                                content: GIFT_RECEIPT_LABEL.to_string(),
+                                bold: true,
+                                center: true,
+                                ..Default::default()
+                            }));
+                        }
+                        replaced = true;
+                        continue;
+                    }
+                };
+                replaced = false;
+                out.extend(redacted.map(recurse));
+            }
+            out
+        }
+
+        fn recurse(component: Component) -> Component {
+            match component {
+                Component::StationGroup(mut group) => {
+                    group.components = walk(group.components);
+                    Component::StationGroup(group)
+                }
+                Component::Canvas(mut canvas) => {
+                    canvas.elements = std::mem::take(&mut canvas.elements)
+                        .into_iter()
+                        .filter_map(|mut element| {
+                            let mut redacted = walk(vec![element.component]);
+                            let component = redacted.pop()?;
+                            element.component = component;
+                            Some(element)
+                        })
+                        .collect();
+                    Component::Canvas(canvas)
+                }
+                other => other,
+            }
+        }
+
+        self.document = walk(std::mem::take(&mut self.document));
+    }
+}
+
+/// The component's `gift` setting, or its default.
+fn gift_mode(component: &Component) -> GiftMode {
+    match component {
+        Component::LineItem(item) => item.gift.unwrap_or(GiftMode::Blank),
+        Component::Total(total) => total.gift.unwrap_or(GiftMode::Replace),
+        Component::Columns(columns) => columns.gift.unwrap_or(GiftMode::Keep),
+        Component::Text(text) => text.gift.unwrap_or(GiftMode::Keep),
+        _ => GiftMode::Keep,
+    }
+}
+
+/// The component with its amount removed, or `None` if nothing is left.
+fn blank(component: Component) -> Option<Component> {
+    match component {
+        Component::LineItem(item) => Some(Component::Columns(Columns {
+            left: item.name,
+            right: String::new(),
+            width: item.width,
+            ..Default::default()
+        })),
+        Component::Total(total) => Some(Component::Text(Text {
+            content: total.label.unwrap_or_else(|| "TOTAL:".to_string()),
+            bold: total.bold.unwrap_or(false),
+            right: total.align.as_deref() != Some("left"),
+            ..Default::default()
+        })),
+        Component::Columns(mut columns) => {
+            if let Some(last) = columns.columns.last_mut() {
+                last.clear();
+            }
+            Some(Component::Columns(Columns {
+                right: String::new(),
+                gift: None,
+                ..columns
+            }))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document::testing::{compiled_texts, fixture};
+    use crate::document::{LineItem, Total};
+    use serde_json::json;
+
+    fn sale() -> Document {
+        fixture(json!({"document": [
+            {"text": "THE SHOP"},
+            {"line_item": "Scarf", "price": 24.0},
+            {"line_item": "Gift wrap", "price": 2.5, "gift": "hide"},
+            {"type": "columns", "left": "Items", "right": "2"},
+            {"total": 26.5, "label": "Subtotal:"},
+            {"total": 2.12, "label": "Tax:"},
+            {"total": 28.62},
+            {"text": "VISA ****1234", "gift": "hide"},
+            {"total": 28.62, "label": "Paid:", "gift": "blank"}
+        ]}))
+    }
+
+    #[test]
+    fn test_gift_receipt_redacts_prices() {
+        assert_eq!(
+            compiled_texts(&sale().as_gift_receipt()),
+            [
+                "THE SHOP",
+                "Scarf",
+                "Items                                          2",
+                GIFT_RECEIPT_LABEL,
+                "Paid:"
+            ]
+        );
+        // The original is untouched
+        assert!(compiled_texts(&sale()).iter().any(|t| t.ends_with("28.62")));
+    }
+
+    #[test]
+    fn test_gift_receipt_keeps_explicit_prices() {
+        let doc = Document {
+            document: vec![
+                Component::LineItem(LineItem {
+                    gift: Some(GiftMode::Keep),
+                    ..LineItem::new("Card", 3.0)
+                }),
+                Component::Total(Total {
+                    gift: Some(GiftMode::Hide),
+                    ..Total::new(3.0)
+                }),
+            ],
+            ..Default::default()
+        };
+        let gift = compiled_texts(&doc.as_gift_receipt());
+        assert_eq!(gift.len(), 1);
+        assert!(gift[0].starts_with("Card") && gift[0].ends_with("3.00"));
+    }
+
+    #[test]
+    fn test_gift_receipt_blanks_last_column() {
+        let doc = fixture(json!({"document": [
+            {"type": "columns", "columns": ["Mug", "x2", "$18.00"], "gift": "blank"}
+        ]}));
+        let gift = compiled_texts(&doc.as_gift_receipt());
+        assert_eq!(gift.len(), 1);
+        assert!(gift[0].starts_with("Mug") && gift[0].ends_with("x2"));
+    }
+}
@@ -1,21 +1,21 @@
-//! Emit logic for graphics components: Image, Pattern, NvLogo.
+//! Emit logic for graphics components: Image, Pattern, Texture, Composition, TextMask,
+//! NvLogo.
 
-use super::types::{Chart, Image, NvLogo, Pattern};
-use crate::ir::Op;
-use crate::render::{chart, dither, patterns};
+use std::borrow::Cow;
+
+use super::EmitContext;
+use super::types::{Chart, Composition, Gauge, Image, NvLogo, Pattern, TextMask, Texture};
+use crate::ir::{Op, Program};
+use crate::preview::{render_gray_with_config, ttf_font};
+#[cfg(feature = "patterns")]
+use crate::render::patterns;
+use crate::render::{chart, dither, gauge, halftone};
+#[cfg(feature = "patterns")]
+use crate::shader::lerp;
 
 /// Parse a dithering algorithm string.
 pub(crate) fn parse_dither_algorithm(s: &str) -> Option<dither::DitheringAlgorithm> {
-    match s.to_lowercase().as_str() {
-        "bayer" => Some(dither::DitheringAlgorithm::Bayer),
-        "floyd-steinberg" | "floyd_steinberg" | "fs" => {
-            Some(dither::DitheringAlgorithm::FloydSteinberg)
-        }
-        "atkinson" => Some(dither::DitheringAlgorithm::Atkinson),
-        "jarvis" | "jjn" => Some(dither::DitheringAlgorithm::Jarvis),
-        "none" | "threshold" => Some(dither::DitheringAlgorithm::None),
-        _ => None,
-    }
+    s.parse().ok()
 }
 
 impl Image {
@@ -23,9 +23,9 @@ impl Image {
     ///
     /// Requires that `resolved_data` has been populated by calling
     /// `Document::resolve()` before compilation.
-    pub fn emit(&self, ops: &mut Vec<Op>) {
+    pub fn emit(&self, ctx: &EmitContext, ops: &mut Vec<Op>) {
         if let Some(ref resolved) = self.resolved_data {
-            let print_width: u16 = 576;
+            let print_width = ctx.print_width() as u16;
             if resolved.width < print_width {
                 let align = self.align.as_deref().unwrap_or("center");
                 let position = match align {
@@ -37,20 +37,77 @@ impl Image {
                     ops.push(Op::SetAbsolutePosition(position));
                 }
             }
-            ops.push(Op::Raster {
+            if let Some(halftone) = &self.halftone {
+                // Screened here: halftone cells depend on the printer's DPI
+                ops.push(Op::Raster {
+                    width: resolved.width,
+                    height: resolved.height,
+                    data: halftone::screen(
+                        resolved.width as usize,
+                        resolved.height as usize,
+                        &resolved.intensities,
+                        halftone,
+                        ctx.printer.dpi,
+                    ),
+                });
+                return;
+            }
+            let dither = self
+                .dither
+                .as_deref()
+                .and_then(parse_dither_algorithm)
+                .unwrap_or(dither::DitheringAlgorithm::FloydSteinberg);
+            ops.push(Op::GrayRaster {
                 width: resolved.width,
                 height: resolved.height,
-                data: resolved.raster_data.clone(),
+                data: dither::to_levels(&resolved.intensities),
+                dither,
             });
         }
     }
+
+    /// Why this image can't be printed, if it can't: it failed to download
+    /// or decode, or was never resolved.
+    pub fn check(&self) -> Result<(), String> {
+        if let Some(error) = &self.resolve_error {
+            return Err(error.clone());
+        }
+        if self.resolved_data.is_none() && !self.url.is_empty() {
+            let why = if cfg!(feature = "images") {
+                ""
+            } else {
+                " (built without the `images` feature)"
+            };
+            return Err(format!(
+                "{} was not loaded{}",
+                describe_source(&self.url),
+                why
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Short description of an image `url` for messages: data URIs can be
+/// megabytes long.
+fn describe_source(url: &str) -> Cow<'_, str> {
+    if url.starts_with("data:") {
+        Cow::Owned(format!("data URI ({} bytes)", url.len()))
+    } else {
+        Cow::Borrowed(url)
+    }
 }
 
+#[cfg(feature = "patterns")]
 impl Pattern {
     /// Emit IR ops for this pattern component.
-    pub fn emit(&self, ops: &mut Vec<Op>) {
+    pub fn emit(&self, ctx: &EmitContext, ops: &mut Vec<Op>) {
         // Look up pattern by name
-        let Some(mut pattern_impl) = patterns::by_name(&self.name) else {
+        let pattern_impl = match self.seed {
+            Some(seed) => patterns::by_name_seeded(&self.name, seed),
+            None => patterns::by_name(&self.name),
+        };
+        let Some(mut pattern_impl) = pattern_impl else {
             return; // Unknown pattern — emit nothing
         };
 
@@ -59,8 +116,8 @@ impl Pattern {
             let _ = pattern_impl.set_param(key, value);
         }
 
-        let height = self.height.unwrap_or(500);
-        let width = 576; // default printer width
+        let height = ctx.dots_or_mm(self.height, self.height_mm).unwrap_or(500);
+        let width = ctx.print_width();
 
         // Parse dithering algorithm
         let dithering = self
@@ -69,25 +126,208 @@ impl Pattern {
             .and_then(parse_dither_algorithm)
             .unwrap_or(dither::DitheringAlgorithm::Bayer);
 
-        let data = patterns::render(pattern_impl.as_ref(), width, height, dithering);
+        let data = patterns::render_gray_draft(pattern_impl.as_ref(), width, height, self.row_step);
 
-        // Emit raster graphics
-        ops.push(Op::Raster {
+        // Emit grayscale graphics; dithering happens at codegen/preview time
+        ops.push(Op::GrayRaster {
             width: width as u16,
             height: height as u16,
-            data,
+            data: dither::to_levels(&data),
+            dither: dithering,
+        });
+    }
+}
+
+#[cfg(not(feature = "patterns"))]
+impl Pattern {
+    /// Patterns print nothing without the `patterns` feature.
+    pub fn emit(&self, _ctx: &EmitContext, _ops: &mut Vec<Op>) {}
+}
+
+impl Texture {
+    /// Emit IR ops for this texture component.
+    pub fn emit(&self, ctx: &EmitContext, ops: &mut Vec<Op>) {
+        let width = ctx
+            .dots_or_mm(self.width, self.width_mm)
+            .unwrap_or(ctx.print_width());
+        let height = self.height_mm.map_or(self.height, |mm| ctx.mm_to_dots(mm));
+        if width == 0 || height == 0 {
+            return;
+        }
+        let Some((tile, tile_width, tile_height)) = self.render_tile(ctx) else {
+            return;
+        };
+
+        let dithering = self
+            .dither
+            .as_deref()
+            .and_then(parse_dither_algorithm)
+            .unwrap_or(dither::DitheringAlgorithm::Bayer);
+
+        let stagger = self.stagger;
+        let data = dither::generate_intensities(width, height, |x, y, _, _| {
+            let row = y / tile_height;
+            let shift = if stagger && row % 2 == 1 {
+                tile_width / 2
+            } else {
+                0
+            };
+            tile[(y % tile_height) * tile_width + (x + shift) % tile_width]
+        });
+
+        ops.push(Op::GrayRaster {
+            width: width as u16,
+            height: height as u16,
+            data: dither::to_levels(&data),
+            dither: dithering,
+        });
+    }
+
+    /// Evaluate a single tile: the pattern at tile size, or the tile
+    /// components rendered once and cropped to their content.
+    fn render_tile(&self, ctx: &EmitContext) -> Option<(Vec<f32>, usize, usize)> {
+        #[cfg(not(feature = "patterns"))]
+        if self.pattern.is_some() {
+            return None;
+        }
+        #[cfg(feature = "patterns")]
+        if let Some(name) = &self.pattern {
+            let mut pattern_impl = patterns::by_name(name)?;
+            for (key, value) in &self.params {
+                let _ = pattern_impl.set_param(key, value);
+            }
+            let (w, h) = (self.tile_width.max(1), self.tile_height.max(1));
+            return Some((patterns::render_gray(pattern_impl.as_ref(), w, h), w, h));
+        }
+
+        if self.tile.is_empty() {
+            return None;
+        }
+        let mut sub_ops = Vec::new();
+        for component in &self.tile {
+            component.emit(ctx, &mut sub_ops);
+        }
+        let gray = render_gray_with_config(&Program { ops: sub_ops }, &ctx.printer).ok()?;
+        let w = gray
+            .data
+            .chunks(gray.width)
+            .filter_map(|row| row.iter().rposition(|&v| v > 0.0))
+            .max()?
+            + 1;
+        let tile = gray
+            .data
+            .chunks(gray.width)
+            .flat_map(|row| row[..w].iter().copied())
+            .collect();
+        Some((tile, w, gray.height))
+    }
+}
+
+#[cfg(feature = "patterns")]
+impl Composition {
+    /// Emit IR ops for this composition component.
+    pub fn emit(&self, ctx: &EmitContext, ops: &mut Vec<Op>) {
+        let width = ctx
+            .dots_or_mm(self.width, self.width_mm)
+            .unwrap_or(ctx.print_width());
+        let height = ctx.dots_or_mm(self.height, self.height_mm).unwrap_or(500);
+        if width == 0 || height == 0 {
+            return;
+        }
+
+        // Composite over white paper, bottom layer first
+        let mut data = vec![0.0f32; width * height];
+        let mut drawn = false;
+        for layer in &self.layers {
+            let Some(mut pattern_impl) = patterns::by_name(&layer.pattern) else {
+                continue; // Unknown pattern — skip the layer
+            };
+            for (key, value) in &layer.params {
+                let _ = pattern_impl.set_param(key, value);
+            }
+            let intensities =
+                patterns::render_gray_draft(pattern_impl.as_ref(), width, height, self.row_step);
+            let opacity = layer.opacity.clamp(0.0, 1.0);
+            for (base, &value) in data.iter_mut().zip(&intensities) {
+                *base = lerp(*base, layer.blend.apply(*base, value), opacity).clamp(0.0, 1.0);
+            }
+            drawn = true;
+        }
+        if !drawn {
+            return;
+        }
+
+        let dithering = self
+            .dither
+            .as_deref()
+            .and_then(parse_dither_algorithm)
+            .unwrap_or(dither::DitheringAlgorithm::Bayer);
+
+        ops.push(Op::GrayRaster {
+            width: width as u16,
+            height: height as u16,
+            data: dither::to_levels(&data),
+            dither: dithering,
+        });
+    }
+}
+
+#[cfg(not(feature = "patterns"))]
+impl Composition {
+    /// Compositions print nothing without the `patterns` feature.
+    pub fn emit(&self, _ctx: &EmitContext, _ops: &mut Vec<Op>) {}
+}
+
+impl TextMask {
+    /// Emit IR ops for this text mask component.
+    pub fn emit(&self, ctx: &EmitContext, ops: &mut Vec<Op>) {
+        if self.content.trim().is_empty() {
+            return;
+        }
+        let print_width = ctx.print_width();
+        let font = self.font.as_deref().unwrap_or("ibm");
+        let mut pixel_height = ctx
+            .dots_or_mm(Some(self.height), self.height_mm)
+            .unwrap_or(self.height) as f32;
+        if self.fit {
+            // Glyph advances scale with the height, so one measurement will do
+            let natural =
+                ttf_font::render_ttf_text(&self.content, font, self.bold, pixel_height, usize::MAX);
+            pixel_height *= print_width as f32 / natural.width as f32;
+        }
+        let rendered =
+            ttf_font::render_ttf_text(&self.content, font, self.bold, pixel_height, print_width);
+        if rendered.width == 0 || rendered.height == 0 {
+            return;
+        }
+
+        let x_offset = if self.center {
+            (print_width - rendered.width) / 2
+        } else {
+            0
+        };
+        let data = dither::generate_intensities(print_width, rendered.height, |x, y, _, _| {
+            x.checked_sub(x_offset)
+                .filter(|&x| x < rendered.width)
+                .map_or(0.0, |x| rendered.data[y * rendered.width + x])
+        });
+        ops.push(Op::GrayRaster {
+            width: print_width as u16,
+            height: rendered.height as u16,
+            data: dither::to_levels(&data),
+            dither: dither::DitheringAlgorithm::Atkinson,
         });
     }
 }
 
 impl Chart {
     /// Emit IR ops for this chart component.
-    pub fn emit(&self, ops: &mut Vec<Op>) {
+    pub fn emit(&self, ctx: &EmitContext, ops: &mut Vec<Op>) {
         if self.values.is_empty() {
             return;
         }
 
-        let width = 576; // default printer width
+        let width = ctx.print_width();
 
         let dithering = self
             .dither
@@ -95,21 +335,41 @@ impl Chart {
             .and_then(parse_dither_algorithm)
             .unwrap_or(dither::DitheringAlgorithm::Bayer);
 
-        let (data, w, h) = chart::render(self, width, dithering);
+        let (data, w, h) = chart::render_gray(self, width);
 
         if !data.is_empty() {
-            ops.push(Op::Raster {
+            ops.push(Op::GrayRaster {
                 width: w,
                 height: h,
-                data,
+                data: dither::to_levels(&data),
+                dither: dithering,
             });
         }
     }
 }
 
+impl Gauge {
+    /// Emit IR ops for this gauge component.
+    pub fn emit(&self, ctx: &EmitContext, ops: &mut Vec<Op>) {
+        let dithering = self
+            .dither
+            .as_deref()
+            .and_then(parse_dither_algorithm)
+            .unwrap_or(dither::DitheringAlgorithm::Bayer);
+
+        let (data, width, height) = gauge::render_gray(self, ctx.print_width());
+        ops.push(Op::GrayRaster {
+            width,
+            height,
+            data: dither::to_levels(&data),
+            dither: dithering,
+        });
+    }
+}
+
 impl NvLogo {
     /// Emit IR ops for this NV logo component.
-    pub fn emit(&self, ops: &mut Vec<Op>) {
+    pub fn emit(&self, ctx: &EmitContext, ops: &mut Vec<Op>) {
         // Resolve scale: scale_x/scale_y take precedence over uniform scale
         let scale_x = self.scale_x.or(self.scale).unwrap_or(1).clamp(1, 2);
         let scale_y = self.scale_y.or(self.scale).unwrap_or(1).clamp(1, 2);
@@ -118,7 +378,7 @@ impl NvLogo {
         if self.center
             && let Some(raster) = crate::logos::get_raster(&self.key)
         {
-            let print_width: u32 = 576; // DEFAULT_PRINT_WIDTH
+            let print_width = ctx.print_width() as u32;
             let scaled_width = (raster.width as u32) * (scale_x as u32);
             if scaled_width < print_width {
                 let position = (print_width - scaled_width) / 2;
@@ -137,7 +397,9 @@ impl NvLogo {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::document::types::ResolvedImage;
 
+    #[cfg(feature = "patterns")]
     #[test]
     fn test_pattern_ripple() {
         let pattern = Pattern {
@@ -146,17 +408,37 @@ mod tests {
             ..Default::default()
         };
         let mut ops = Vec::new();
-        pattern.emit(&mut ops);
+        pattern.emit(&EmitContext::default(), &mut ops);
         assert!(ops.iter().any(|op| matches!(
             op,
-            Op::Raster {
+            Op::GrayRaster {
                 width: 576,
                 height: 100,
+                dither: dither::DitheringAlgorithm::Bayer,
                 ..
             }
         )));
     }
 
+    #[cfg(feature = "patterns")]
+    #[test]
+    fn test_pattern_seed() {
+        let render = |seed| {
+            let pattern = Pattern {
+                name: "ripple".into(),
+                height: Some(40),
+                seed,
+                ..Default::default()
+            };
+            let mut ops = Vec::new();
+            pattern.emit(&EmitContext::default(), &mut ops);
+            ops
+        };
+        assert_eq!(render(Some(7)), render(Some(7)));
+        assert_ne!(render(Some(7)), render(Some(8)));
+        assert_ne!(render(Some(7)), render(None));
+    }
+
     #[test]
     fn test_pattern_unknown() {
         let pattern = Pattern {
@@ -165,10 +447,140 @@ mod tests {
             ..Default::default()
         };
         let mut ops = Vec::new();
-        pattern.emit(&mut ops);
+        pattern.emit(&EmitContext::default(), &mut ops);
         assert!(ops.is_empty());
     }
 
+    #[cfg(feature = "patterns")]
+    #[test]
+    fn test_texture_repeats_tile() {
+        let texture = Texture {
+            pattern: Some("ripple".into()),
+            height: 40,
+            tile_width: 16,
+            tile_height: 16,
+            ..Default::default()
+        };
+        let mut ops = Vec::new();
+        texture.emit(&EmitContext::default(), &mut ops);
+        let Some(Op::GrayRaster {
+            width: 576,
+            height: 40,
+            data,
+            ..
+        }) = ops.first()
+        else {
+            panic!("expected a full-width gray raster, got {:?}", ops);
+        };
+        // Same pixel one tile to the right and one tile down
+        assert_eq!(data[3 * 576 + 5], data[3 * 576 + 5 + 16]);
+        assert_eq!(data[3 * 576 + 5], data[19 * 576 + 5]);
+    }
+
+    #[test]
+    fn test_texture_from_components() {
+        let texture = Texture {
+            tile: vec![crate::document::Component::Text(
+                crate::document::Text::new("VOID"),
+            )],
+            width: Some(200),
+            height: 60,
+            stagger: true,
+            ..Default::default()
+        };
+        let mut ops = Vec::new();
+        texture.emit(&EmitContext::default(), &mut ops);
+        assert!(matches!(
+            ops.as_slice(),
+            [Op::GrayRaster {
+                width: 200,
+                height: 60,
+                ..
+            }]
+        ));
+    }
+
+    #[cfg(feature = "patterns")]
+    #[test]
+    fn test_composition_blends_layers() {
+        use crate::document::CompositionLayer;
+        use crate::render::composer::BlendMode;
+
+        let layer = |pattern: &str, blend, opacity| CompositionLayer {
+            pattern: pattern.into(),
+            blend,
+            opacity,
+            ..Default::default()
+        };
+        let render = |layers| {
+            let mut ops = Vec::new();
+            Composition {
+                layers,
+                width: Some(64),
+                height: Some(32),
+                ..Default::default()
+            }
+            .emit(&EmitContext::default(), &mut ops);
+            match ops.pop() {
+                Some(Op::GrayRaster { data, .. }) => data,
+                other => panic!("expected a gray raster, got {:?}", other),
+            }
+        };
+
+        let ripple = render(vec![layer("ripple", BlendMode::Normal, 1.0)]);
+        // A transparent layer leaves the stack unchanged; unknown patterns are skipped
+        let stacked = render(vec![
+            layer("ripple", BlendMode::Normal, 1.0),
+            layer("crosshatch", BlendMode::Multiply, 0.0),
+            layer("nonexistent", BlendMode::Normal, 1.0),
+        ]);
+        assert_eq!(ripple, stacked);
+
+        let half = render(vec![layer("ripple", BlendMode::Normal, 0.5)]);
+        assert!(half[100].abs_diff(ripple[100] / 2) <= 1);
+    }
+
+    #[test]
+    fn test_text_mask_fit_spans_print_width() {
+        let render = |mask: TextMask| {
+            let mut ops = Vec::new();
+            mask.emit(&EmitContext::default(), &mut ops);
+            match ops.pop() {
+                Some(Op::GrayRaster {
+                    width,
+                    height,
+                    data,
+                    ..
+                }) => (width as usize, height as usize, data),
+                other => panic!("expected a gray raster, got {:?}", other),
+            }
+        };
+        let inked_columns = |(width, _, data): &(usize, usize, Vec<u8>)| {
+            let columns: Vec<usize> = (0..*width)
+                .filter(|&x| data.iter().skip(x).step_by(*width).any(|&v| v >= 128))
+                .collect();
+            (columns[0], columns[columns.len() - 1])
+        };
+
+        let small = render(TextMask {
+            content: "SALE".into(),
+            height: 40,
+            ..Default::default()
+        });
+        assert_eq!(small.0, 576);
+        assert!(inked_columns(&small).1 < 200);
+
+        let fit = render(TextMask {
+            content: "SALE".into(),
+            height: 40,
+            fit: true,
+            ..Default::default()
+        });
+        let (left, right) = inked_columns(&fit);
+        assert!(left < 40 && right > 530, "{}..{}", left, right);
+        assert!(fit.1 > small.1 * 2);
+    }
+
     #[test]
     fn test_nv_logo_default() {
         let logo = NvLogo {
@@ -176,7 +588,7 @@ mod tests {
             ..Default::default()
         };
         let mut ops = Vec::new();
-        logo.emit(&mut ops);
+        logo.emit(&EmitContext::default(), &mut ops);
         assert!(ops.iter().any(|op| matches!(
             op,
             Op::NvPrint {
@@ -195,7 +607,7 @@ mod tests {
             ..Default::default()
         };
         let mut ops = Vec::new();
-        logo.emit(&mut ops);
+        logo.emit(&EmitContext::default(), &mut ops);
         assert!(ops.iter().any(|op| matches!(
             op,
             Op::NvPrint {
@@ -206,6 +618,31 @@ mod tests {
         )));
     }
 
+    #[test]
+    fn test_image_halftone() {
+        let img = Image {
+            halftone: Some("dot".parse().unwrap()),
+            resolved_data: Some(ResolvedImage {
+                intensities: vec![0.5; 64 * 8],
+                width: 64,
+                height: 8,
+            }),
+            ..Default::default()
+        };
+        let mut ops = Vec::new();
+        img.emit(&EmitContext::default(), &mut ops);
+        let raster = ops.iter().find_map(|op| match op {
+            Op::Raster {
+                width,
+                height,
+                data,
+            } => Some((*width, *height, data.len())),
+            _ => None,
+        });
+        assert_eq!(raster, Some((64, 8, 64)));
+        assert!(!ops.iter().any(|op| matches!(op, Op::GrayRaster { .. })));
+    }
+
     #[test]
     fn test_image_unresolved() {
         let img = Image {
@@ -213,7 +650,7 @@ mod tests {
             ..Default::default()
         };
         let mut ops = Vec::new();
-        img.emit(&mut ops);
+        img.emit(&EmitContext::default(), &mut ops);
         // Unresolved images emit nothing
         assert!(ops.is_empty());
     }
@@ -0,0 +1,228 @@
+//! Print-time document hooks.
+//!
+//! A hook transforms a `Document` after it is received and before it is
+//! compiled: append a footer, inject variables, enforce a house style. The
+//! server applies its registered hooks to every document it previews or
+//! prints, so org-wide policies don't need changes in every client.
+//!
+//! Hooks are either Rust callbacks (any `Fn(&mut Document) -> Result<..>`)
+//! or external commands ([`CommandHook`]) that receive the document as JSON
+//! on stdin and write the transformed document to stdout.
+
+use std::io::{ErrorKind, Read, Write};
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use super::Document;
+use crate::EstrellaError;
+
+/// A transformation applied to a document before compilation.
+pub trait DocumentHook: Send + Sync {
+    /// Transform the document in place.
+    fn apply(&self, doc: &mut Document) -> Result<(), EstrellaError>;
+}
+
+impl<F> DocumentHook for F
+where
+    F: Fn(&mut Document) -> Result<(), EstrellaError> + Send + Sync,
+{
+    fn apply(&self, doc: &mut Document) -> Result<(), EstrellaError> {
+        self(doc)
+    }
+}
+
+/// Runs a shell command as a hook.
+///
+/// The document is written to the command's stdin as JSON; its stdout must
+/// be the transformed document JSON. A non-zero exit status aborts the print,
+/// and so does a command still running after the timeout, which is killed.
+#[derive(Debug, Clone)]
+pub struct CommandHook {
+    command: String,
+    timeout: Duration,
+}
+
+impl CommandHook {
+    /// How long a command may run by default.
+    pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+    /// Create a hook that runs `command` through `sh -c`.
+    pub fn new(command: impl Into<String>) -> Self {
+        Self {
+            command: command.into(),
+            timeout: Self::DEFAULT_TIMEOUT,
+        }
+    }
+
+    /// Kill the command and fail the print after `timeout`.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+}
+
+/// How often a running command is checked for exit.
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+impl DocumentHook for CommandHook {
+    fn apply(&self, doc: &mut Document) -> Result<(), EstrellaError> {
+        let input = serde_json::to_vec(doc)
+            .map_err(|e| EstrellaError::Hook(format!("failed to serialize document: {}", e)))?;
+
+        let mut child = Command::new("sh")
+            .arg("-c")
+            .arg(&self.command)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .spawn()
+            .map_err(|e| EstrellaError::Hook(format!("failed to run '{}': {}", self.command, e)))?;
+
+        // Feed stdin and drain stdout on their own threads: a command that
+        // echoes as it reads would otherwise fill its stdout pipe and block
+        // while we are still writing
+        let writer = child.stdin.take().map(|mut stdin| {
+            thread::spawn(move || match stdin.write_all(&input) {
+                // The command exited without reading all of its input
+                Err(e) if e.kind() == ErrorKind::BrokenPipe => Ok(()),
+                result => result,
+            })
+        });
+        let reader = child.stdout.take().map(|mut stdout| {
+            thread::spawn(move || {
+                let mut output = Vec::new();
+                stdout.read_to_end(&mut output).map(|_| output)
+            })
+        });
+
+        let deadline = Instant::now() + self.timeout;
+        let status = loop {
+            if let Some(status) = child.try_wait()? {
+                break status;
+            }
+            if Instant::now() >= deadline {
+                // Killing closes the pipes, which ends both threads
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(EstrellaError::Hook(format!(
+                    "'{}' timed out after {:?}",
+                    self.command, self.timeout
+                )));
+            }
+            thread::sleep(POLL_INTERVAL);
+        };
+
+        if let Some(writer) = writer {
+            join(writer)?;
+        }
+        let stdout = match reader {
+            Some(reader) => join(reader)?,
+            None => Vec::new(),
+        };
+
+        if !status.success() {
+            return Err(EstrellaError::Hook(format!(
+                "'{}' exited with {}",
+                self.command, status
+            )));
+        }
+
+        *doc = serde_json::from_slice(&stdout).map_err(|e| {
+            EstrellaError::Hook(format!("'{}' returned invalid JSON: {}", self.command, e))
+        })?;
+        Ok(())
+    }
+}
+
+/// Wait for a pipe thread and return its result.
+fn join<T>(handle: thread::JoinHandle<std::io::Result<T>>) -> Result<T, EstrellaError> {
+    handle
+        .join()
+        .map_err(|_| EstrellaError::Hook("hook pipe thread panicked".into()))?
+        .map_err(EstrellaError::from)
+}
+
+/// Apply hooks in order, stopping at the first failure.
+pub fn apply_hooks(
+    hooks: &[Box<dyn DocumentHook>],
+    doc: &mut Document,
+) -> Result<(), EstrellaError> {
+    for hook in hooks {
+        hook.apply(doc)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document::{Component, Text};
+
+    #[test]
+    fn test_closure_hooks_run_in_order() {
+        let hooks: Vec<Box<dyn DocumentHook>> = vec![
+            Box::new(|doc: &mut Document| -> Result<(), EstrellaError> {
+                doc.push(Component::Text(Text::new("footer")));
+                Ok(())
+            }),
+            Box::new(|doc: &mut Document| -> Result<(), EstrellaError> {
+                doc.variables.insert("org".into(), "ACME".into());
+                Ok(())
+            }),
+        ];
+        let mut doc = Document::new();
+        apply_hooks(&hooks, &mut doc).unwrap();
+        assert_eq!(doc.document.len(), 1);
+        assert_eq!(doc.variables["org"], "ACME");
+    }
+
+    #[test]
+    fn test_failing_hook_stops_chain() {
+        let hooks: Vec<Box<dyn DocumentHook>> = vec![
+            Box::new(|_: &mut Document| -> Result<(), EstrellaError> {
+                Err(EstrellaError::Hook("policy violation".into()))
+            }),
+            Box::new(|doc: &mut Document| -> Result<(), EstrellaError> {
+                doc.cut = false;
+                Ok(())
+            }),
+        ];
+        let mut doc = Document::new();
+        assert!(apply_hooks(&hooks, &mut doc).is_err());
+        assert!(doc.cut);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_command_hook_round_trips_json() {
+        let hook = CommandHook::new("cat");
+        let mut doc = Document::new();
+        doc.push(Component::Text(Text::new("hello")));
+        hook.apply(&mut doc).unwrap();
+        assert_eq!(doc.document.len(), 1);
+
+        assert!(CommandHook::new("exit 3").apply(&mut doc).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_command_hook_echoes_large_document() {
+        // Larger than a pipe buffer, so writing and reading must overlap
+        let hook = CommandHook::new("cat");
+        let mut doc = Document::new();
+        doc.push(Component::Text(Text::new("x".repeat(1 << 20))));
+        hook.apply(&mut doc).unwrap();
+        assert_eq!(doc.document.len(), 1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_command_hook_times_out() {
+        let hook = CommandHook::new("sleep 5").with_timeout(Duration::from_millis(100));
+        let started = Instant::now();
+        let err = hook.apply(&mut Document::new()).unwrap_err();
+        assert!(err.to_string().contains("timed out"), "{}", err);
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+}
@@ -0,0 +1,591 @@
+//! Conversion logic for the HTML component.
+//!
+//! A safe subset of HTML becomes ordinary components, so receipts that
+//! services send as HTML print like any other document:
+//!
+//! | HTML | Printed as |
+//! |------|------------|
+//! | `<h1>` – `<h3>` | Bold [`Text`], sized like Markdown headings (`h1`, `h2` centered) |
+//! | `<p>`, `<div>`, `<br>` | [`Text`] lines, wrapped; paragraphs get a blank line between them |
+//! | `<b>`, `<strong>` | Bold runs |
+//! | `<i>`, `<em>`, `<u>` | Underlined runs (the printer has no italics) |
+//! | `<hr>` | A solid [`Divider`] |
+//! | `<table>` | A [`Table`]: `<th>` or `<thead>` rows as headers, `<tfoot>` as footers |
+//! | `<ul>`, `<ol>` | Lines with `* ` or `1. ` markers, nested lists indented |
+//! | `<img src>` | An [`Image`], resolved like any other image URL |
+//! | `<center>`, `align="..."` | Alignment of the lines inside |
+//!
+//! Other elements print their text; `<script>`, `<style>`, `<head>` and
+//! form controls are dropped with their contents. Nothing is fetched
+//! except `<img>` sources, which go through the document's image resolver
+//! and its rules for local files.
+
+use scraper::node::Element;
+use scraper::{ElementRef, Node};
+
+use super::Component;
+use super::EmitContext;
+use super::types::{
+    BlankLine, ColumnAlign, Divider, DividerStyle, Html, Image, Table, TableCell, Text,
+};
+use crate::ir::Op;
+
+/// Elements dropped along with everything inside them.
+const SKIPPED: &[&str] = &[
+    "head", "script", "style", "template", "noscript", "iframe", "object", "svg", "math", "button",
+    "input", "select", "textarea",
+];
+
+impl Html {
+    /// The components this HTML converts to.
+    ///
+    /// ```
+    /// use estrella::document::{Component, Html};
+    ///
+    /// let html = Html::new("<h2>Order #12</h2><p>Thanks, <b>Ada</b>!</p>");
+    /// let components = html.components();
+    /// assert!(matches!(&components[0], Component::Text(t) if t.content == "Order #12"));
+    /// ```
+    pub fn components(&self) -> Vec<Component> {
+        if self.content.trim().is_empty() {
+            return Vec::new();
+        }
+        let dom = scraper::Html::parse_document(&self.content);
+        let mut converter = Converter::new(self.show_urls);
+        converter.children(dom.root_element());
+        converter.finish()
+    }
+
+    /// Emit IR ops for this HTML component.
+    ///
+    /// Documents convert HTML before compiling (see
+    /// [`Document::expand_templates`](super::Document::expand_templates)),
+    /// so its images are resolved; emitting it directly prints them only
+    /// when they were.
+    pub fn emit(&self, ctx: &EmitContext, ops: &mut Vec<Op>) {
+        for component in self.components() {
+            component.emit(ctx, ops);
+        }
+    }
+}
+
+/// A stretch of text in one style.
+#[derive(Debug, Clone, PartialEq)]
+struct Run {
+    text: String,
+    bold: bool,
+    underline: bool,
+}
+
+/// Walks the DOM, collecting inline text into lines and blocks into
+/// components.
+struct Converter {
+    show_urls: bool,
+    components: Vec<Component>,
+    /// Text of the line being built.
+    runs: Vec<Run>,
+    bold: usize,
+    underline: usize,
+    /// Alignments of the enclosing `<center>` and `align="..."` elements.
+    align: Vec<&'static str>,
+    /// Size of the heading being built (`[h, w]` as in [`Text::size`]).
+    heading: Option<[u8; 2]>,
+    /// Open lists: the next number of ordered ones, `None` for bullets.
+    lists: Vec<Option<usize>>,
+    /// Marker of the list item whose first line is being built.
+    marker: Option<String>,
+    /// A block with margins ended: the next component gets a blank line
+    /// before it.
+    gap: bool,
+}
+
+impl Converter {
+    fn new(show_urls: bool) -> Self {
+        Self {
+            show_urls,
+            components: Vec::new(),
+            runs: Vec::new(),
+            bold: 0,
+            underline: 0,
+            align: Vec::new(),
+            heading: None,
+            lists: Vec::new(),
+            marker: None,
+            gap: false,
+        }
+    }
+
+    fn finish(mut self) -> Vec<Component> {
+        self.flush();
+        self.components
+    }
+
+    fn children(&mut self, element: ElementRef) {
+        for child in element.children() {
+            match child.value() {
+                Node::Text(text) => self.text(text),
+                Node::Element(_) => {
+                    if let Some(child) = ElementRef::wrap(child) {
+                        self.element(child);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn element(&mut self, element: ElementRef) {
+        let name = element.value().name();
+        if SKIPPED.contains(&name) {
+            return;
+        }
+        let aligned = element_align(element.value());
+        if let Some(align) = aligned {
+            self.flush();
+            self.align.push(align);
+        }
+        match name {
+            "br" => self.flush(),
+            "hr" => self.push(Component::Divider(Divider {
+                style: DividerStyle::Solid,
+                ..Default::default()
+            })),
+            "img" => {
+                if let Some(src) = element.attr("src").filter(|src| !src.trim().is_empty()) {
+                    self.push(Component::Image(Image {
+                        url: src.trim().to_string(),
+                        align: Some(self.alignment().unwrap_or("center").to_string()),
+                        ..Default::default()
+                    }));
+                }
+            }
+            "h1" | "h2" | "h3" => {
+                let (size, center) = match name {
+                    "h1" => ([4, 4], true),
+                    "h2" => ([3, 3], true),
+                    _ => ([2, 2], false),
+                };
+                self.margin();
+                if center && aligned.is_none() {
+                    self.align.push("center");
+                }
+                self.heading = Some(size);
+                self.children(element);
+                self.flush();
+                self.heading = None;
+                if center && aligned.is_none() {
+                    self.align.pop();
+                }
+                self.margin();
+            }
+            "h4" | "h5" | "h6" => {
+                self.margin();
+                self.bold += 1;
+                self.children(element);
+                self.bold -= 1;
+                self.margin();
+            }
+            "p" => {
+                self.margin();
+                self.children(element);
+                self.margin();
+            }
+            "center" => {
+                self.flush();
+                self.align.push("center");
+                self.children(element);
+                self.flush();
+                self.align.pop();
+            }
+            "b" | "strong" => {
+                self.bold += 1;
+                self.children(element);
+                self.bold -= 1;
+            }
+            "i" | "em" | "u" | "ins" => {
+                self.underline += 1;
+                self.children(element);
+                self.underline -= 1;
+            }
+            "a" => {
+                self.children(element);
+                if let Some(href) = element.attr("href").filter(|_| self.show_urls)
+                    && (href.starts_with("http://") || href.starts_with("https://"))
+                {
+                    self.text(&format!(" ({})", href));
+                }
+            }
+            "ul" | "ol" => {
+                if self.lists.is_empty() {
+                    self.margin();
+                } else {
+                    self.flush();
+                }
+                let start = element.attr("start").and_then(|s| s.parse().ok());
+                self.lists
+                    .push((name == "ol").then_some(start.unwrap_or(1)));
+                self.children(element);
+                self.flush();
+                self.lists.pop();
+                if self.lists.is_empty() {
+                    self.margin();
+                }
+            }
+            "li" => {
+                self.flush();
+                let marker = match self.lists.last_mut() {
+                    Some(Some(counter)) => {
+                        *counter += 1;
+                        format!("{}. ", *counter - 1)
+                    }
+                    _ => "* ".into(),
+                };
+                self.marker = Some(marker);
+                self.children(element);
+                self.flush();
+            }
+            "table" => {
+                self.margin();
+                if let Some(table) = table(element) {
+                    self.push(Component::Table(table));
+                }
+                self.margin();
+            }
+            "div" | "section" | "article" | "header" | "footer" | "main" | "address"
+            | "blockquote" | "tr" => {
+                self.flush();
+                self.children(element);
+                self.flush();
+            }
+            _ => self.children(element),
+        }
+        if aligned.is_some() {
+            self.flush();
+            self.align.pop();
+        }
+    }
+
+    /// Add text in the current style.
+    fn text(&mut self, text: &str) {
+        let bold = self.bold > 0;
+        let underline = self.underline > 0;
+        match self.runs.last_mut() {
+            Some(run) if run.bold == bold && run.underline == underline => run.text.push_str(text),
+            _ => self.runs.push(Run {
+                text: text.to_string(),
+                bold,
+                underline,
+            }),
+        }
+    }
+
+    fn alignment(&self) -> Option<&'static str> {
+        self.align.last().copied()
+    }
+
+    /// End the current block; the next one starts after a blank line.
+    fn margin(&mut self) {
+        self.flush();
+        self.gap = !self.components.is_empty();
+    }
+
+    fn push(&mut self, component: Component) {
+        self.flush();
+        if std::mem::take(&mut self.gap) {
+            self.components.push(Component::BlankLine(BlankLine {}));
+        }
+        self.components.push(component);
+    }
+
+    /// Turn the line being built into text components, one per run.
+    fn flush(&mut self) {
+        let mut runs = collapse_whitespace(std::mem::take(&mut self.runs));
+        if runs.is_empty() {
+            return;
+        }
+        // List items hang their wrapped lines under the text
+        let depth = self.lists.len().saturating_sub(1);
+        let indent = "  ".repeat(depth);
+        let marker = self.marker.take().unwrap_or_default();
+        let prefix = if marker.is_empty() && self.lists.is_empty() {
+            String::new()
+        } else if marker.is_empty() {
+            format!("{}  ", indent)
+        } else {
+            format!("{}{}", indent, marker)
+        };
+        if !prefix.is_empty() {
+            runs.insert(
+                0,
+                Run {
+                    text: prefix.clone(),
+                    bold: false,
+                    underline: false,
+                },
+            );
+        }
+
+        let count = runs.len();
+        let texts: Vec<Component> = runs
+            .into_iter()
+            .enumerate()
+            .map(|(i, run)| {
+                Component::Text(Text {
+                    content: run.text,
+                    bold: run.bold || self.heading.is_some(),
+                    underline: run.underline,
+                    align: self.alignment().map(str::to_string),
+                    size: self.heading.unwrap_or([1, 1]),
+                    hanging_indent: prefix.chars().count().min(u8::MAX as usize) as u8,
+                    is_inline: i + 1 < count,
+                    ..Default::default()
+                })
+            })
+            .collect();
+        for text in texts {
+            self.push(text);
+        }
+    }
+}
+
+/// Collapse whitespace as browsers do: runs of it become one space, and
+/// none is left at the ends of the line. Empty runs are dropped.
+fn collapse_whitespace(runs: Vec<Run>) -> Vec<Run> {
+    let mut out: Vec<Run> = Vec::new();
+    let mut after_space = true;
+    for run in runs {
+        let mut text = String::new();
+        for ch in run.text.chars() {
+            if ch.is_whitespace() {
+                if !after_space {
+                    text.push(' ');
+                }
+                after_space = true;
+            } else {
+                text.push(ch);
+                after_space = false;
+            }
+        }
+        if !text.is_empty() {
+            out.push(Run { text, ..run });
+        }
+    }
+    if let Some(last) = out.last_mut() {
+        last.text.truncate(last.text.trim_end().len());
+        if last.text.is_empty() {
+            out.pop();
+        }
+    }
+    out
+}
+
+/// Alignment set by an element's `align` attribute or `text-align` style.
+fn element_align(element: &Element) -> Option<&'static str> {
+    let style = element
+        .attr("style")
+        .unwrap_or_default()
+        .to_ascii_lowercase()
+        .replace(' ', "");
+    let value = element.attr("align").map(str::to_ascii_lowercase);
+    ["center", "right", "left"].into_iter().find(|align| {
+        value.as_deref() == Some(*align) || style.contains(&format!("text-align:{}", align))
+    })
+}
+
+/// One `<table>` as a [`Table`], or `None` when it has no cells.
+fn table(element: ElementRef) -> Option<Table> {
+    let mut headers: Option<Vec<String>> = None;
+    let mut rows: Vec<Vec<TableCell>> = Vec::new();
+    let mut footers: Vec<Vec<TableCell>> = Vec::new();
+    let mut align: Vec<ColumnAlign> = Vec::new();
+
+    for (section, row) in table_rows(element, "tbody") {
+        let cells: Vec<ElementRef> = row
+            .child_elements()
+            .filter(|cell| matches!(cell.value().name(), "td" | "th"))
+            .collect();
+        if cells.is_empty() {
+            continue;
+        }
+        let all_th = cells.iter().all(|cell| cell.value().name() == "th");
+        let text_cells: Vec<TableCell> = cells
+            .iter()
+            .map(|cell| {
+                let colspan = cell.attr("colspan").and_then(|n| n.parse().ok());
+                TableCell::spanning(cell_text(*cell), colspan.unwrap_or(1))
+            })
+            .collect();
+        let is_header = section == "thead" || (all_th && rows.is_empty() && headers.is_none());
+        if is_header && headers.is_none() && section != "tfoot" {
+            headers = Some(text_cells.into_iter().map(|cell| cell.text).collect());
+            continue;
+        }
+        if section == "tfoot" {
+            footers.push(text_cells);
+            continue;
+        }
+        if rows.is_empty() {
+            align = column_aligns(&cells);
+        }
+        rows.push(text_cells);
+    }
+    if headers.is_none() && rows.is_empty() && footers.is_empty() {
+        return None;
+    }
+    Some(Table {
+        headers,
+        rows,
+        footers,
+        align,
+        ..Default::default()
+    })
+}
+
+/// The rows of a table with the section each is in (`thead`, `tbody` or
+/// `tfoot`), leaving out the rows of nested tables.
+fn table_rows<'a>(
+    element: ElementRef<'a>,
+    section: &'static str,
+) -> Vec<(&'static str, ElementRef<'a>)> {
+    let mut rows = Vec::new();
+    for child in element.child_elements() {
+        match child.value().name() {
+            "tr" => rows.push((section, child)),
+            "thead" => rows.extend(table_rows(child, "thead")),
+            "tbody" => rows.extend(table_rows(child, "tbody")),
+            "tfoot" => rows.extend(table_rows(child, "tfoot")),
+            _ => {}
+        }
+    }
+    rows
+}
+
+/// Column alignments from the cells of a row, spans included.
+fn column_aligns(cells: &[ElementRef]) -> Vec<ColumnAlign> {
+    cells
+        .iter()
+        .flat_map(|cell| {
+            let align = match element_align(cell.value()) {
+                Some("center") => ColumnAlign::Center,
+                Some("right") => ColumnAlign::Right,
+                _ => ColumnAlign::Left,
+            };
+            let span = cell.attr("colspan").and_then(|n| n.parse().ok());
+            std::iter::repeat_n(align, span.unwrap_or(1).max(1))
+        })
+        .collect()
+}
+
+/// A cell's text with its whitespace collapsed.
+fn cell_text(cell: ElementRef) -> String {
+    cell.text()
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document::testing::text_contents;
+
+    #[test]
+    fn test_inline_runs() {
+        let html = Html::new("<p>Thanks,\n  <b>Ada</b> <i>and</i> <u>Bo</u>!</p>");
+        let components = html.components();
+        assert_eq!(
+            text_contents(&components),
+            vec!["Thanks, ", "Ada", " ", "and", " ", "Bo", "!"]
+        );
+        let Component::Text(name) = &components[1] else {
+            panic!("expected text, got {:?}", components[1]);
+        };
+        assert!(name.bold && name.is_inline);
+        assert!(matches!(&components[3], Component::Text(t) if t.underline));
+        assert!(matches!(components.last(), Some(Component::Text(t)) if !t.is_inline));
+    }
+
+    #[test]
+    fn test_blocks() {
+        let html =
+            Html::new("<h1>Cafe</h1><center>Main St</center><p>One</p><p>Two<br>lines</p><hr>");
+        let components = html.components();
+        assert!(matches!(&components[0],
+            Component::Text(t) if t.size == [4, 4] && t.bold && t.align.as_deref() == Some("center")));
+        assert!(matches!(&components[1], Component::BlankLine(_)));
+        assert!(matches!(&components[2],
+            Component::Text(t) if t.content == "Main St" && t.align.as_deref() == Some("center")));
+        assert_eq!(
+            text_contents(&components),
+            vec!["Cafe", "Main St", "One", "Two", "lines"]
+        );
+        // A blank line between paragraphs, none inside one
+        let blank_lines = components
+            .iter()
+            .filter(|c| matches!(c, Component::BlankLine(_)))
+            .count();
+        assert_eq!(blank_lines, 4);
+        assert!(matches!(components.last(), Some(Component::Divider(_))));
+    }
+
+    #[test]
+    fn test_lists() {
+        let html =
+            Html::new("<ul><li>Coffee<ol start=\"3\"><li>Oat milk</li></ol></li><li>Tea</li></ul>");
+        let components = html.components();
+        assert_eq!(
+            text_contents(&components),
+            vec!["* ", "Coffee", "  3. ", "Oat milk", "* ", "Tea"]
+        );
+        assert!(matches!(&components[3], Component::Text(t) if t.hanging_indent == 5));
+    }
+
+    #[test]
+    fn test_table() {
+        let html = Html::new(
+            "<table>
+               <tr><th>Item</th><th>Price</th></tr>
+               <tr><td>Latte</td><td align=\"right\">$4.50</td></tr>
+               <tfoot><tr><td colspan=\"2\">Paid</td></tr></tfoot>
+             </table>",
+        );
+        let components = html.components();
+        let [Component::Table(table)] = &components[..] else {
+            panic!("expected one table, got {:?}", components);
+        };
+        assert_eq!(table.headers, Some(vec!["Item".into(), "Price".into()]));
+        assert_eq!(
+            table.rows,
+            vec![vec![TableCell::from("Latte"), "$4.50".into()]]
+        );
+        assert_eq!(table.align, vec![ColumnAlign::Left, ColumnAlign::Right]);
+        assert_eq!(table.footers, vec![vec![TableCell::spanning("Paid", 2)]]);
+    }
+
+    #[test]
+    fn test_unsafe_content_is_dropped() {
+        let html = Html::new(
+            "<html><head><title>Receipt</title><style>p {}</style></head>
+             <body><script>alert(1)</script><p>Total <input value=\"x\">$5</p>
+             <img src=\"https://example.com/logo.png\" onerror=\"alert(1)\"></body></html>",
+        );
+        let components = html.components();
+        assert_eq!(text_contents(&components), vec!["Total $5"]);
+        assert!(matches!(components.last(),
+            Some(Component::Image(image)) if image.url == "https://example.com/logo.png"));
+    }
+
+    #[test]
+    fn test_links_and_empty() {
+        let html = Html {
+            content: "<a href=\"https://example.com\">Track</a>".into(),
+            show_urls: true,
+        };
+        assert_eq!(
+            text_contents(&html.components()),
+            vec!["Track (https://example.com)"]
+        );
+        assert!(Html::new("  ").components().is_empty());
+    }
+}
@@ -0,0 +1,223 @@
+//! Emit logic for the LabelSheet component.
+//!
+//! The label is rendered once through the preview engine, padded or cropped
+//! to the pitch, and emitted as one `Op::Raster` per copy. Raster rows
+//! advance the paper exactly one dot each, which is what makes the pitch
+//! exact — text-mode line spacing would drift over 20 labels.
+
+use super::EmitContext;
+use super::types::LabelSheet;
+use crate::ir::{Op, Program};
+use crate::preview::render_raw_with_config;
+use crate::printer::PrinterConfig;
+
+/// Length of each alignment mark, in dots from the paper edge.
+const MARK_LENGTH: usize = 24;
+
+/// Thickness of each alignment mark, in rows.
+const MARK_ROWS: usize = 2;
+
+/// Most labels one sheet may print. Every label is its own raster, so the
+/// count bounds the memory a single document can ask for.
+pub const MAX_LABEL_COUNT: usize = 500;
+
+/// Longest label pitch, in mm.
+pub const MAX_PITCH_MM: f32 = 500.0;
+
+impl LabelSheet {
+    /// Emit IR ops for this label sheet.
+    pub fn emit(&self, ctx: &EmitContext, ops: &mut Vec<Op>) {
+        if self.count == 0 || self.label.is_empty() || self.check().is_err() {
+            return;
+        }
+
+        let mut sub_ops = Vec::new();
+        for component in &self.label {
+            component.emit(ctx, &mut sub_ops);
+        }
+        let Ok(raw) = render_raw_with_config(&Program { ops: sub_ops }, &ctx.printer) else {
+            return;
+        };
+
+        let (Ok(pitch), Ok(width)) = (
+            self.pitch_dots(raw.height, &ctx.printer),
+            u16::try_from(raw.width),
+        ) else {
+            return;
+        };
+        if pitch == 0 {
+            return;
+        }
+        let rows = usize::from(pitch);
+
+        // One label frame: content at the top, white below, cropped to pitch
+        let width_bytes = raw.width.div_ceil(8);
+        let mut frame = vec![0u8; width_bytes * rows];
+        let content_bytes = raw.height.min(rows) * width_bytes;
+        frame[..content_bytes].copy_from_slice(&raw.data[..content_bytes]);
+
+        let mut marked = frame.clone();
+        if self.marks {
+            draw_marks(&mut marked, raw.width, rows);
+        }
+
+        for i in 0..self.count {
+            // Marks go between labels, not after the last one
+            let data = if i + 1 < self.count {
+                marked.clone()
+            } else {
+                frame.clone()
+            };
+            ops.push(Op::Raster {
+                width,
+                height: pitch,
+                data,
+            });
+        }
+    }
+
+    /// Why this label sheet can't be printed, if it can't: too many labels,
+    /// or a pitch or gap out of range.
+    pub fn check(&self) -> Result<(), String> {
+        if self.count > MAX_LABEL_COUNT {
+            return Err(format!(
+                "{} labels requested, at most {} per sheet",
+                self.count, MAX_LABEL_COUNT
+            ));
+        }
+        if let Some(mm) = self.pitch_mm
+            && !(mm > 0.0 && mm <= MAX_PITCH_MM)
+        {
+            return Err(format!(
+                "pitch of {}mm is out of range (0-{}mm)",
+                mm, MAX_PITCH_MM
+            ));
+        }
+        if !(self.gap_mm >= 0.0 && self.gap_mm <= MAX_PITCH_MM) {
+            return Err(format!(
+                "gap of {}mm is out of range (0-{}mm)",
+                self.gap_mm, MAX_PITCH_MM
+            ));
+        }
+        Ok(())
+    }
+
+    /// Resolve the label pitch in dots: explicit pitch, then the printer's
+    /// label stock, then content height plus gap. Fails when the pitch is
+    /// taller than one raster can be.
+    fn pitch_dots(&self, content_height: usize, config: &PrinterConfig) -> Result<u16, String> {
+        let dots = match self.pitch_mm.or(config.label_pitch_mm) {
+            Some(mm) => usize::from(config.mm_to_dots(mm)),
+            None => content_height + usize::from(config.mm_to_dots(self.gap_mm)),
+        };
+        u16::try_from(dots).map_err(|_| {
+            format!(
+                "label pitch of {} dots is more than the {} a raster holds",
+                dots,
+                u16::MAX
+            )
+        })
+    }
+}
+
+/// Draw short ticks at both paper edges along the bottom of a label frame.
+fn draw_marks(data: &mut [u8], width: usize, height: usize) {
+    let width_bytes = width.div_ceil(8);
+    let length = MARK_LENGTH.min(width);
+    for y in height.saturating_sub(MARK_ROWS)..height {
+        for x in (0..length).chain(width - length..width) {
+            data[y * width_bytes + x / 8] |= 0x80 >> (x % 8);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document::{Component, Text};
+    use crate::preview::render_raw;
+
+    fn sheet(count: usize, pitch_mm: Option<f32>) -> LabelSheet {
+        LabelSheet {
+            label: vec![Component::Text(Text::new("JAM"))],
+            count,
+            pitch_mm,
+            gap_mm: 3.0,
+            marks: true,
+        }
+    }
+
+    fn rasters(ops: &[Op]) -> Vec<(u16, &Vec<u8>)> {
+        ops.iter()
+            .filter_map(|op| match op {
+                Op::Raster { height, data, .. } => Some((*height, data)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_exact_pitch_and_marks_between_labels() {
+        let mut ops = Vec::new();
+        sheet(3, Some(20.0)).emit(&EmitContext::default(), &mut ops);
+
+        let labels = rasters(&ops);
+        assert_eq!(labels.len(), 3);
+        let pitch = PrinterConfig::TSP650II.mm_to_dots(20.0);
+        assert!(labels.iter().all(|(height, _)| *height == pitch));
+
+        // Last row: marks on the first two labels only
+        let last_row = |data: &Vec<u8>| data[data.len() - 72..].to_vec();
+        assert_eq!(last_row(labels[0].1)[0], 0xFF);
+        assert_eq!(last_row(labels[1].1)[71], 0xFF);
+        assert!(last_row(labels[2].1).iter().all(|&b| b == 0));
+    }
+
+    #[test]
+    fn test_default_pitch_is_content_plus_gap() {
+        let mut ops = Vec::new();
+        sheet(2, None).emit(&EmitContext::default(), &mut ops);
+
+        let content = render_raw(&Program {
+            ops: {
+                let mut ops = Vec::new();
+                Component::Text(Text::new("JAM")).emit(&EmitContext::default(), &mut ops);
+                ops
+            },
+        })
+        .unwrap();
+        let gap = PrinterConfig::TSP650II.mm_to_dots(3.0) as usize;
+        assert_eq!(rasters(&ops)[0].0 as usize, content.height + gap);
+    }
+
+    #[test]
+    fn test_out_of_range_sheets_are_rejected() {
+        assert!(sheet(MAX_LABEL_COUNT, None).check().is_ok());
+        let too_many = sheet(MAX_LABEL_COUNT + 1, None);
+        assert!(too_many.check().unwrap_err().contains("labels"));
+        let mut ops = Vec::new();
+        too_many.emit(&EmitContext::default(), &mut ops);
+        assert!(rasters(&ops).is_empty());
+
+        assert!(sheet(1, Some(-5.0)).check().is_err());
+        assert!(sheet(1, Some(MAX_PITCH_MM * 2.0)).check().is_err());
+
+        let tall = sheet(1, None).pitch_dots(usize::from(u16::MAX), &PrinterConfig::TSP650II);
+        assert!(tall.unwrap_err().contains("raster"));
+    }
+
+    #[test]
+    fn test_label_sheet_from_json() {
+        let json =
+            r#"{"document": [{"type": "label_sheet", "count": 2, "label": [{"text": "A"}]}]}"#;
+        let doc: crate::document::Document = serde_json::from_str(json).unwrap();
+        match &doc.document[0] {
+            Component::LabelSheet(sheet) => {
+                assert_eq!(sheet.count, 2);
+                assert!(sheet.marks);
+                assert!(matches!(sheet.label[0], Component::Text(_)));
+            }
+            other => panic!("expected label sheet, got {:?}", other),
+        }
+    }
+}
@@ -1,25 +1,31 @@
-//! Emit logic for layout components: Divider, Spacer, BlankLine, Columns, Banner.
+//! Emit logic for layout components: Divider, Spacer, BlankLine, Columns, Definitions,
+//! ProgressBar, Banner.
 
+use super::EmitContext;
+use super::text::truncate_chars;
 use super::types::{
-    Banner, BlankLine, BorderStyle, ColumnAlign, Columns, Divider, DividerStyle, Spacer, Table,
+    Banner, BlankLine, BorderStyle, ColumnAlign, Columns, Definitions, Divider, DividerStyle,
+    ProgressBar, ProgressBarStyle, Spacer, Table, TableCell, Text,
 };
-use crate::ir::{Op, Program};
-use crate::preview::ttf_font;
+use crate::ir::{Op, Program, word_wrap};
+use crate::preview::{bidi, ttf_font};
+use crate::protocol::codepage;
 use crate::protocol::text::{Alignment, Font};
 use crate::render::dither;
 
 impl Divider {
     /// Emit IR ops for this divider component.
-    pub fn emit(&self, ops: &mut Vec<Op>) {
-        let width = self.width.unwrap_or(48);
+    pub fn emit(&self, ctx: &EmitContext, ops: &mut Vec<Op>) {
+        let font = if self.small { Font::B } else { Font::A };
+        let width = self.width.unwrap_or(ctx.chars_per_line(font));
         let line = match self.style {
             DividerStyle::Dashed => "-".repeat(width),
             DividerStyle::Solid => "\u{2500}".repeat(width), // ─
             DividerStyle::Double => "\u{2550}".repeat(width), // ═
             DividerStyle::Equals => "=".repeat(width),
         };
-        // Reset to Font A to ensure correct width (48 chars × 12 dots = 576 = full print width)
-        ops.push(Op::SetFont(Font::A));
+        // Set the font so the width in characters fills the print width
+        ops.push(Op::SetFont(font));
         ops.push(Op::SetAlign(Alignment::Left));
         ops.push(Op::Text(line));
         ops.push(Op::Newline);
@@ -28,15 +34,8 @@ impl Divider {
 
 impl Spacer {
     /// Emit IR ops for this spacer component.
-    pub fn emit(&self, ops: &mut Vec<Op>) {
-        let units = if let Some(mm) = self.mm {
-            (mm * 4.0).round().clamp(0.0, 255.0) as u8
-        } else if let Some(lines) = self.lines {
-            (lines as f32 * 3.0 * 4.0).round().clamp(0.0, 255.0) as u8
-        } else {
-            self.units.unwrap_or_default()
-        };
-
+    pub fn emit(&self, _ctx: &EmitContext, ops: &mut Vec<Op>) {
+        let units = self.feed_units();
         if units > 0 {
             ops.push(Op::Feed { units });
         }
@@ -45,24 +44,24 @@ impl Spacer {
 
 impl BlankLine {
     /// Emit IR ops for this blank line component.
-    pub fn emit(&self, ops: &mut Vec<Op>) {
+    pub fn emit(&self, _ctx: &EmitContext, ops: &mut Vec<Op>) {
         ops.push(Op::Newline);
     }
 }
 
 impl Columns {
-    /// Emit IR ops for this two-column layout component.
-    pub fn emit(&self, ops: &mut Vec<Op>) {
-        let width = self.width.unwrap_or(48);
-        let padding = width.saturating_sub(self.left.len() + self.right.len());
-        let line = format!(
-            "{}{:>width$}",
-            self.left,
-            self.right,
-            width = padding + self.right.len()
-        );
+    /// Emit IR ops for this column layout component.
+    pub fn emit(&self, ctx: &EmitContext, ops: &mut Vec<Op>) {
+        let width = self.width.unwrap_or(ctx.chars_per_line(Font::A));
+        let lines = if self.columns.is_empty() {
+            let (left, right) = (bidi::display(&self.left), bidi::display(&self.right));
+            let padding = width.saturating_sub(left.chars().count() + right.chars().count());
+            vec![format!("{}{}{}", left, " ".repeat(padding), right)]
+        } else {
+            self.grid_lines(width)
+        };
 
-        // Reset to Font A to ensure correct width (48 chars × 12 dots = 576 = full print width)
+        // Reset to Font A so the width in characters fills the print width
         ops.push(Op::SetFont(Font::A));
         ops.push(Op::SetAlign(Alignment::Left));
         if self.bold {
@@ -75,8 +74,9 @@ impl Columns {
             ops.push(Op::SetInvert(true));
         }
 
-        ops.push(Op::Text(line));
-        ops.push(Op::Newline);
+        for line in lines {
+            push_grid_line(line, self.bold, self.invert, ctx, ops);
+        }
 
         if self.invert {
             ops.push(Op::SetInvert(false));
@@ -88,6 +88,279 @@ impl Columns {
             ops.push(Op::SetBold(false));
         }
     }
+
+    /// Lay out `columns` side by side, one space apart, each wrapping within
+    /// its share of `width`.
+    fn grid_lines(&self, width: usize) -> Vec<String> {
+        let count = self.columns.len();
+        let widths = ratio_widths(&self.ratios, count, width.saturating_sub(count - 1));
+        let cells: Vec<Vec<String>> = self
+            .columns
+            .iter()
+            .zip(&widths)
+            .map(|(cell, &w)| word_wrap(cell, w))
+            .collect();
+        let height = cells.iter().map(Vec::len).max().unwrap_or(0).max(1);
+
+        (0..height)
+            .map(|row| {
+                let padded: Vec<String> = cells
+                    .iter()
+                    .zip(&widths)
+                    .enumerate()
+                    .map(|(i, (lines, &w))| {
+                        let text = bidi::display(lines.get(row).map_or("", String::as_str));
+                        let last = if i == count - 1 {
+                            ColumnAlign::Right
+                        } else {
+                            ColumnAlign::Left
+                        };
+                        match self.align.get(i).copied().unwrap_or(last) {
+                            ColumnAlign::Left => format!("{:<w$}", text),
+                            ColumnAlign::Right => format!("{:>w$}", text),
+                            ColumnAlign::Center => format!("{:^w$}", text),
+                        }
+                    })
+                    .collect();
+                padded.join(" ")
+            })
+            .collect()
+    }
+}
+
+/// Split `available` characters between `count` columns in proportion to
+/// `ratios` (missing or zero ratios count as 1). Every column gets at least
+/// one character; rounding leftovers go to the leftmost columns.
+fn ratio_widths(ratios: &[u32], count: usize, available: usize) -> Vec<usize> {
+    let ratios: Vec<usize> = (0..count)
+        .map(|i| ratios.get(i).copied().filter(|&r| r > 0).unwrap_or(1) as usize)
+        .collect();
+    let total: usize = ratios.iter().sum();
+    let available = available.max(count);
+    let mut widths: Vec<usize> = ratios
+        .iter()
+        .map(|r| (available * r / total).max(1))
+        .collect();
+    let mut left = available.saturating_sub(widths.iter().sum());
+    for w in widths.iter_mut() {
+        if left == 0 {
+            break;
+        }
+        *w += 1;
+        left -= 1;
+    }
+    widths
+}
+
+/// Spaces plus the shortest leader between a label and its value.
+const MIN_DEFINITION_GAP: usize = 3;
+
+impl Definitions {
+    /// Emit IR ops for this definition list component.
+    pub fn emit(&self, ctx: &EmitContext, ops: &mut Vec<Op>) {
+        if self.items.is_empty() {
+            return;
+        }
+        let width = self.width.unwrap_or(ctx.chars_per_line(Font::A)).max(2);
+        let longest = self
+            .items
+            .iter()
+            .map(|(label, _)| label.chars().count())
+            .max()
+            .unwrap_or(0);
+        // By default the longest label still gets a two-character leader
+        let split = self
+            .split
+            .unwrap_or((longest + MIN_DEFINITION_GAP + 1).min(width / 2))
+            .clamp(1, width - 1);
+        let indent = " ".repeat(split);
+
+        ops.push(Op::PushStyle);
+        ops.push(Op::SetFont(Font::A));
+        ops.push(Op::SetAlign(Alignment::Left));
+        for (label, value) in &self.items {
+            let mut values = if value.is_empty() {
+                Vec::new()
+            } else {
+                word_wrap(value, width - split)
+            }
+            .into_iter();
+            let gap = split.saturating_sub(label.chars().count());
+
+            if gap == 0 {
+                // No room beside the label: it takes its own line(s)
+                for line in word_wrap(label, width) {
+                    self.emit_label(line, ops);
+                    ops.push(Op::Newline);
+                }
+            } else {
+                self.emit_label(label.clone(), ops);
+                if let Some(value) = values.next() {
+                    let fill = if gap >= MIN_DEFINITION_GAP {
+                        format!(" {} ", self.leader.to_string().repeat(gap - 2))
+                    } else {
+                        " ".repeat(gap)
+                    };
+                    ops.push(Op::Text(format!("{}{}", fill, value)));
+                }
+                ops.push(Op::Newline);
+            }
+            for value in values {
+                ops.push(Op::Text(format!("{}{}", indent, value)));
+                ops.push(Op::Newline);
+            }
+        }
+        ops.push(Op::PopStyle);
+    }
+
+    fn emit_label(&self, label: String, ops: &mut Vec<Op>) {
+        if self.bold_labels {
+            ops.push(Op::SetBold(true));
+            ops.push(Op::Text(label));
+            ops.push(Op::SetBold(false));
+        } else {
+            ops.push(Op::Text(label));
+        }
+    }
+}
+
+/// Narrowest text bar worth printing next to its label; below this the
+/// label goes on its own line.
+const MIN_PROGRESS_CELLS: usize = 8;
+
+/// Intensity of the empty part of a raster progress bar.
+const PROGRESS_TRACK_GRAY: f32 = 0.2;
+
+/// Border thickness of a raster progress bar, in dots.
+const PROGRESS_BORDER: usize = 2;
+
+impl ProgressBar {
+    /// Emit IR ops for this progress bar component.
+    pub fn emit(&self, ctx: &EmitContext, ops: &mut Vec<Op>) {
+        let fraction = self.fraction();
+        let percent = self
+            .show_percent
+            .then(|| format!("{:.0}%", fraction * 100.0));
+        let label = self.label.as_deref().filter(|l| !l.is_empty());
+
+        ops.push(Op::PushStyle);
+        // Font A: 12 dots per character (48 per line on 80mm paper)
+        ops.push(Op::SetFont(Font::A));
+        ops.push(Op::SetAlign(Alignment::Left));
+        match self.style {
+            ProgressBarStyle::Raster => self.emit_raster(fraction, label, percent, ctx, ops),
+            style => {
+                let width = self.width.unwrap_or(ctx.chars_per_line(Font::A));
+                let label_width = label.map_or(0, |l| l.chars().count() + 1);
+                // " 100%" at most, right-aligned so bars line up
+                let percent = percent.map(|p| format!(" {:>4}", p));
+                let percent_width = percent.as_ref().map_or(0, |p| p.len());
+
+                let mut cells = width.saturating_sub(label_width + percent_width);
+                let mut line = String::new();
+                if let Some(label) = label {
+                    if cells < MIN_PROGRESS_CELLS {
+                        ops.push(Op::Text(label.to_string()));
+                        ops.push(Op::Newline);
+                        cells = width.saturating_sub(percent_width);
+                    } else {
+                        line.push_str(label);
+                        line.push(' ');
+                    }
+                }
+                line.push_str(&progress_cells(style, fraction, cells.max(1)));
+                if let Some(percent) = percent {
+                    line.push_str(&percent);
+                }
+                ops.push(Op::Text(line));
+                ops.push(Op::Newline);
+            }
+        }
+        ops.push(Op::PopStyle);
+    }
+
+    /// Label and percentage on one line, then the bar as graphics.
+    fn emit_raster(
+        &self,
+        fraction: f64,
+        label: Option<&str>,
+        percent: Option<String>,
+        ctx: &EmitContext,
+        ops: &mut Vec<Op>,
+    ) {
+        let print_width = ctx.print_width();
+        let width = self
+            .width
+            .unwrap_or(print_width)
+            .clamp(PROGRESS_BORDER * 2 + 1, print_width);
+        let height = self
+            .height
+            .unwrap_or(24)
+            .clamp(PROGRESS_BORDER * 2 + 1, 255);
+
+        if label.is_some() || percent.is_some() {
+            let left = label.unwrap_or_default();
+            let right = percent.unwrap_or_default();
+            let chars = width / Font::A.char_width();
+            let padding = chars.saturating_sub(left.chars().count() + right.len());
+            ops.push(Op::Text(format!(
+                "{}{:>width$}",
+                left,
+                right,
+                width = padding + right.len()
+            )));
+            ops.push(Op::Newline);
+        }
+
+        let inner = width - PROGRESS_BORDER * 2;
+        let filled = PROGRESS_BORDER + (inner as f64 * fraction).round() as usize;
+        let mut data = Vec::with_capacity(width * height);
+        for y in 0..height {
+            for x in 0..width {
+                let border = x < PROGRESS_BORDER
+                    || x >= width - PROGRESS_BORDER
+                    || y < PROGRESS_BORDER
+                    || y >= height - PROGRESS_BORDER;
+                data.push(if border || x < filled {
+                    1.0
+                } else {
+                    PROGRESS_TRACK_GRAY
+                });
+            }
+        }
+
+        ops.push(Op::GrayRaster {
+            width: width as u16,
+            height: height as u16,
+            data: dither::to_levels(&data),
+            dither: dither::DitheringAlgorithm::Bayer,
+        });
+    }
+}
+
+/// Bar characters for a text progress bar `cells` characters wide.
+///
+/// `Blocks` rounds to half a character (`▌`); `Shade` to whole characters.
+fn progress_cells(style: ProgressBarStyle, fraction: f64, cells: usize) -> String {
+    const TRACK: char = '\u{2591}'; // ░
+    let (full, half, fill) = match style {
+        ProgressBarStyle::Shade => (
+            (fraction * cells as f64).round() as usize,
+            false,
+            '\u{2593}',
+        ), // ▓
+        _ => {
+            let halves = (fraction * cells as f64 * 2.0).round() as usize;
+            (halves / 2, halves % 2 == 1, '\u{2588}') // █
+        }
+    };
+    let mut bar: String = std::iter::repeat_n(fill, full).collect();
+    if half {
+        bar.push('\u{258C}'); // ▌
+    }
+    let empty = cells - full - usize::from(half);
+    bar.extend(std::iter::repeat_n(TRACK, empty));
+    bar
 }
 
 impl Banner {
@@ -95,13 +368,13 @@ impl Banner {
     ///
     /// Renders a box-drawing frame around the content text, auto-sizing
     /// the width to be as large as possible while fitting the content.
-    pub fn emit(&self, ops: &mut Vec<Op>) {
+    pub fn emit(&self, ctx: &EmitContext, ops: &mut Vec<Op>) {
         if let Some(ref font_name) = self.font {
-            self.emit_with_custom_font(font_name, ops);
+            self.emit_with_custom_font(font_name, ctx, ops);
             return;
         }
 
-        let (size, total_width) = Self::fit(self.content.len(), self.size, self.border);
+        let (size, total_width) = Self::fit(self.content.len(), self.size, self.border, ctx);
         let [h, w] = size;
         let font = if h == 0 && w == 0 { Font::B } else { Font::A };
         let esc_h = h.saturating_sub(1);
@@ -137,7 +410,13 @@ impl Banner {
     /// Emit a standard boxed banner (Single, Double, Heavy, Shade).
     fn emit_boxed(&self, ops: &mut Vec<Op>, total_width: usize) {
         let (tl, tr, bl, br, horiz, vert) = match self.border {
-            BorderStyle::Single | BorderStyle::Mixed => (
+            // Shadow, rule, heading and tag banners have emitters of their own
+            BorderStyle::Single
+            | BorderStyle::Mixed
+            | BorderStyle::Shadow
+            | BorderStyle::Rule
+            | BorderStyle::Heading
+            | BorderStyle::Tag => (
                 '\u{250C}', '\u{2510}', '\u{2514}', '\u{2518}', '\u{2500}', '\u{2502}',
             ),
             BorderStyle::Double => (
@@ -149,17 +428,10 @@ impl Banner {
             BorderStyle::Shade => (
                 '\u{2592}', '\u{2592}', '\u{2592}', '\u{2592}', '\u{2592}', '\u{2592}',
             ),
-            BorderStyle::Shadow | BorderStyle::Rule | BorderStyle::Heading | BorderStyle::Tag => {
-                unreachable!()
-            }
         };
 
-        let inner = total_width - 2;
-        let text = if self.content.len() > inner {
-            &self.content[..inner]
-        } else {
-            &self.content
-        };
+        let inner = total_width.saturating_sub(2);
+        let text = truncate_chars(&self.content, inner);
         let pad = inner.saturating_sub(text.len());
         let pad_left = pad / 2;
         let pad_right = pad - pad_left;
@@ -222,14 +494,10 @@ impl Banner {
         let shadow = '\u{2593}'; // ▓
 
         // Shadow takes 1 char on right, so the box is (total_width - 1) wide
-        let box_width = total_width - 1;
-        let inner = box_width - 2;
+        let box_width = total_width.saturating_sub(1);
+        let inner = box_width.saturating_sub(2);
 
-        let text = if self.content.len() > inner {
-            &self.content[..inner]
-        } else {
-            &self.content
-        };
+        let text = truncate_chars(&self.content, inner);
         let pad = inner.saturating_sub(text.len());
         let pad_left = pad / 2;
         let pad_right = pad - pad_left;
@@ -348,7 +616,7 @@ impl Banner {
 
     /// Emit a banner with custom font: render the banner frame using standard
     /// bitmap path, then composite TTF-rendered text content over the frame.
-    fn emit_with_custom_font(&self, font_name: &str, ops: &mut Vec<Op>) {
+    fn emit_with_custom_font(&self, font_name: &str, ctx: &EmitContext, ops: &mut Vec<Op>) {
         // Render the banner frame with spaces instead of real text — same length
         // preserves the fit() result (same expansion, same frame geometry) while
         // leaving the interior blank for clean TTF compositing.
@@ -356,37 +624,24 @@ impl Banner {
         let mut plain_banner = self.clone();
         plain_banner.font = None;
         plain_banner.content = " ".repeat(self.content.len());
-        plain_banner.emit(&mut banner_ops);
+        plain_banner.emit(ctx, &mut banner_ops);
 
         if banner_ops.is_empty() {
             return;
         }
 
-        // Render the bitmap banner to raw pixels
+        // Render the bitmap banner to an f32 intensity buffer
         let program = Program { ops: banner_ops };
-        let Ok(raw) = crate::preview::render_raw(&program) else {
+        let Ok(raw) = crate::preview::render_gray_with_config(&program, &ctx.printer) else {
             return;
         };
 
         let width = raw.width;
         let height = raw.height;
-        let width_bytes = width.div_ceil(8);
-
-        // Convert 1-bit banner to f32 intensity buffer
-        let mut buffer = vec![0.0f32; width * height];
-        for y in 0..height {
-            for x in 0..width {
-                let byte_idx = y * width_bytes + x / 8;
-                let bit_idx = 7 - (x % 8);
-                let is_black = (raw.data.get(byte_idx).copied().unwrap_or(0) >> bit_idx) & 1 == 1;
-                if is_black {
-                    buffer[y * width + x] = 1.0;
-                }
-            }
-        }
+        let mut buffer = raw.data;
 
         // Use the actual fitted size — fit() may cascade width or fall back to Font B
-        let (fitted_size, _) = Self::fit(self.content.len(), self.size, self.border);
+        let (fitted_size, _) = Self::fit(self.content.len(), self.size, self.border, ctx);
         let pixel_height = ttf_font::size_to_pixel_height(fitted_size);
         let text_render =
             ttf_font::render_ttf_text(&self.content, font_name, self.bold, pixel_height, width);
@@ -412,26 +667,26 @@ impl Banner {
             }
         }
 
-        // Dither the composite to 1-bit (Atkinson for smooth AA text)
-        let raster_data = dither::generate_raster(
-            width,
-            height,
-            |x, y, _w, _h| buffer[y * width + x],
-            dither::DitheringAlgorithm::Atkinson,
-        );
-
-        ops.push(Op::Raster {
+        // Dithered to 1-bit at codegen/preview time (Atkinson for smooth AA text)
+        ops.push(Op::GrayRaster {
             width: width as u16,
             height: height as u16,
-            data: raster_data,
+            data: dither::to_levels(&buffer),
+            dither: dither::DitheringAlgorithm::Atkinson,
         });
     }
 
-    /// Find the largest size that fits the content.
+    /// Find the largest size that fits the content on the context's print
+    /// width.
     ///
     /// Returns `([h, w], total_chars_per_line)`.
     /// Cascades width from `max_size` down to 1, then falls back to Font B.
-    pub fn fit(content_len: usize, max_size: u8, border: BorderStyle) -> ([u8; 2], usize) {
+    pub fn fit(
+        content_len: usize,
+        max_size: u8,
+        border: BorderStyle,
+        ctx: &EmitContext,
+    ) -> ([u8; 2], usize) {
         let border_overhead = match border {
             BorderStyle::Shadow => 3, // left + right + shadow column
             BorderStyle::Tag => 2,    // "■ " prefix
@@ -441,15 +696,15 @@ impl Banner {
 
         // Try each width from max down to 1 (Font A with ESC i)
         for w in (1..=max_size).rev() {
-            let chars_per_line = 48 / w as usize;
+            let chars_per_line = ctx.chars_per_line(Font::A) / w as usize;
             let usable = chars_per_line.saturating_sub(border_overhead);
             if content_len <= usable {
                 return ([max_size, w], chars_per_line);
             }
         }
 
-        // Font B fallback: 64 chars per line
-        ([0, 0], 64)
+        // Font B fallback: 64 chars per line on 80mm paper
+        ([0, 0], ctx.chars_per_line(Font::B))
     }
 }
 
@@ -534,11 +789,12 @@ fn table_chars(style: BorderStyle) -> TableChars {
     }
 }
 
-/// Build a horizontal rule line: `left` + (fill × col_width+2) + `junction` + ... + `right`.
+/// Build a horizontal rule line: `left` + (fill × col_width+2) + junction + ... + `right`,
+/// with one junction per inner column border.
 fn horizontal_line(
     left: char,
     fill: char,
-    junction: char,
+    junctions: &[char],
     right: char,
     col_widths: &[usize],
 ) -> String {
@@ -548,7 +804,7 @@ fn horizontal_line(
         for _ in 0..(w + 2) {
             line.push(fill);
         }
-        if i < col_widths.len() - 1 {
+        if let Some(&junction) = junctions.get(i) {
             line.push(junction);
         }
     }
@@ -556,34 +812,91 @@ fn horizontal_line(
     line
 }
 
-/// Build a data row: `vert` + ` cell ` + `vert` + ... + `vert`.
+/// Junctions for a rule between two rows, from `[none, up, down, cross]`
+/// depending on which of them has each inner column border.
+fn junctions(above: &[bool], below: &[bool], set: [char; 4]) -> Vec<char> {
+    let [none, up, down, cross] = set;
+    above
+        .iter()
+        .zip(below)
+        .map(|(&above, &below)| match (above, below) {
+            (false, false) => none,
+            (true, false) => up,
+            (false, true) => down,
+            (true, true) => cross,
+        })
+        .collect()
+}
+
+/// Which inner column borders a row has: one per border, false where a
+/// cell spans across it.
+fn row_borders(cells: &[TableCell], num_cols: usize) -> Vec<bool> {
+    let mut borders = vec![true; num_cols.saturating_sub(1)];
+    let mut col = 0;
+    for cell in cells {
+        if col >= num_cols {
+            break;
+        }
+        let end = (col + cell.colspan).min(num_cols);
+        for border in &mut borders[col..end - 1] {
+            *border = false;
+        }
+        col = end;
+    }
+    borders
+}
+
+/// Build a data row: `vert` + ` cell ` + `vert` + ... + `vert`. A cell with
+/// a `colspan` also takes the borders and padding between its columns.
 fn data_row(
     vert: char,
-    cells: &[String],
+    cells: &[TableCell],
     col_widths: &[usize],
     align: &[ColumnAlign],
     num_cols: usize,
 ) -> String {
     let mut line = String::new();
     line.push(vert);
-    for (i, &w) in col_widths.iter().enumerate().take(num_cols) {
-        let cell = cells.get(i).map(|s| s.as_str()).unwrap_or("");
-        let truncated = if cell.len() > w { &cell[..w] } else { cell };
-        let alignment = align.get(i).copied().unwrap_or(ColumnAlign::Left);
+    let mut cells = cells.iter();
+    let mut col = 0;
+    while col < num_cols {
+        let (cell, span) = match cells.next() {
+            Some(cell) => (cell.text.as_str(), cell.colspan.min(num_cols - col)),
+            None => ("", 1),
+        };
+        let w = col_widths[col..col + span].iter().sum::<usize>() + 3 * (span - 1);
+        let truncated = bidi::display(truncate_chars(cell, w));
+        let alignment = align.get(col).copied().unwrap_or(ColumnAlign::Left);
         let padded = match alignment {
             ColumnAlign::Left => format!(" {:<width$} ", truncated, width = w),
             ColumnAlign::Right => format!(" {:>width$} ", truncated, width = w),
             ColumnAlign::Center => format!(" {:^width$} ", truncated, width = w),
         };
         line.push_str(&padded);
-        if i < num_cols - 1 {
-            line.push(vert);
-        }
+        line.push(vert);
+        col += span;
     }
-    line.push(vert);
     line
 }
 
+/// Push one line of a character grid (columns, table rows). Lines with
+/// characters the printer's code pages don't have, like Hebrew, Arabic or
+/// CJK, become a raster line with every character in its cell.
+fn push_grid_line(line: String, bold: bool, invert: bool, ctx: &EmitContext, ops: &mut Vec<Op>) {
+    if line.chars().all(codepage::is_printable) {
+        ops.push(Op::Text(line));
+        ops.push(Op::Newline);
+        return;
+    }
+    let text = Text {
+        content: line,
+        bold,
+        invert,
+        ..Default::default()
+    };
+    text.emit_as_raster(true, ctx, ops);
+}
+
 /// Compute column widths distributed proportionally to max content widths.
 fn compute_col_widths(num_cols: usize, max_widths: &[usize], total_width: usize) -> Vec<usize> {
     if num_cols == 0 {
@@ -642,97 +955,170 @@ fn compute_col_widths(num_cols: usize, max_widths: &[usize], total_width: usize)
 
 impl Table {
     /// Emit IR ops for this table component.
-    pub fn emit(&self, ops: &mut Vec<Op>) {
-        let total_width = self.width.unwrap_or(48);
+    pub fn emit(&self, ctx: &EmitContext, ops: &mut Vec<Op>) {
+        let total_width = self.width.unwrap_or(ctx.chars_per_line(Font::A));
+        let headers: Option<Vec<TableCell>> = self.headers.as_ref().map(|headers| {
+            headers
+                .iter()
+                .map(|h| TableCell::from(h.as_str()))
+                .collect()
+        });
+        let span = |row: &Vec<TableCell>| row.iter().map(|cell| cell.colspan).sum::<usize>();
 
         // Determine number of columns
         let num_cols = {
-            let from_headers = self.headers.as_ref().map(|h| h.len()).unwrap_or(0);
-            let from_rows = self.rows.iter().map(|r| r.len()).max().unwrap_or(0);
-            from_headers.max(from_rows)
+            let from_headers = headers.as_ref().map(|h| h.len()).unwrap_or(0);
+            let from_rows = self.rows.iter().chain(&self.footers).map(span).max();
+            from_headers.max(from_rows.unwrap_or(0))
         };
 
         if num_cols == 0 {
             return;
         }
 
-        // Compute max content width per column
+        // Lines in print order, each with the rule above it (if any)
+        let mut lines: Vec<(&[TableCell], bool, Option<Rule>)> = Vec::new();
+        if let Some(ref headers) = headers {
+            lines.push((headers, true, None));
+        }
+        for (i, row) in self.rows.iter().enumerate() {
+            let rule = if i == 0 && headers.is_some() {
+                Some(Rule::Strong)
+            } else if i > 0 && self.row_separator {
+                Some(Rule::Inner)
+            } else {
+                None
+            };
+            lines.push((row, false, rule));
+        }
+        for (i, row) in self.footers.iter().enumerate() {
+            let rule = (i == 0 && !lines.is_empty()).then_some(Rule::Strong);
+            lines.push((row, true, rule));
+        }
+
+        // Compute max content width per column; a spanning cell that
+        // doesn't fit its columns widens the last of them
         let mut max_widths = vec![0usize; num_cols];
-        if let Some(ref headers) = self.headers {
-            for (i, h) in headers.iter().enumerate() {
-                max_widths[i] = max_widths[i].max(h.len());
+        for (row, _, _) in &lines {
+            let mut col = 0;
+            for cell in row.iter() {
+                if cell.colspan == 1 && col < num_cols {
+                    max_widths[col] = max_widths[col].max(cell.text.chars().count());
+                }
+                col += cell.colspan;
             }
         }
-        for row in &self.rows {
-            for (i, cell) in row.iter().enumerate() {
-                if i < num_cols {
-                    max_widths[i] = max_widths[i].max(cell.len());
+        for (row, _, _) in &lines {
+            let mut col = 0;
+            for cell in row.iter() {
+                let end = (col + cell.colspan).min(num_cols);
+                if cell.colspan > 1 && end > col {
+                    let room = max_widths[col..end].iter().sum::<usize>() + 3 * (end - col - 1);
+                    max_widths[end - 1] += cell.text.chars().count().saturating_sub(room);
                 }
+                col = end;
             }
         }
 
         let col_widths = compute_col_widths(num_cols, &max_widths, total_width);
         let chars = table_chars(self.border);
+        let inner = [chars.horiz, chars.t_up, chars.t_down, chars.cross];
+        // Header and footer separator: ├──┼──┤ or ╞══╪══╡ for mixed
+        let (strong_left, strong_fill, strong_right, strong) =
+            if matches!(self.border, BorderStyle::Mixed) {
+                let set = ['\u{2550}', '\u{2567}', '\u{2564}', '\u{256A}'];
+                ('\u{255E}', '\u{2550}', '\u{2561}', set)
+            } else {
+                (chars.t_right, chars.horiz, chars.t_left, inner)
+            };
 
         ops.push(Op::SetFont(Font::A));
         ops.push(Op::SetAlign(Alignment::Left));
 
-        // Top border: ┌──┬──┐
-        let top = horizontal_line(chars.tl, chars.horiz, chars.t_down, chars.tr, &col_widths);
-        ops.push(Op::Text(top));
-        ops.push(Op::Newline);
-
-        // Header row
-        if let Some(ref headers) = self.headers {
-            ops.push(Op::SetBold(true));
-            let header_row = data_row(chars.vert, headers, &col_widths, &self.align, num_cols);
-            ops.push(Op::Text(header_row));
-            ops.push(Op::Newline);
-            ops.push(Op::SetBold(false));
-
-            // Header separator: ├──┼──┤ or ╞══╪══╡ for mixed
-            let sep = if matches!(self.border, BorderStyle::Mixed) {
-                horizontal_line('\u{255E}', '\u{2550}', '\u{256A}', '\u{2561}', &col_widths)
-            } else {
-                horizontal_line(
-                    chars.t_right,
+        let mut above = vec![false; num_cols - 1];
+        for (i, &(row, bold, rule)) in lines.iter().enumerate() {
+            let borders = row_borders(row, num_cols);
+            let rule = if i == 0 {
+                // Top border: ┌──┬──┐
+                let junctions = junctions(&above, &borders, inner);
+                Some(horizontal_line(
+                    chars.tl,
                     chars.horiz,
-                    chars.cross,
-                    chars.t_left,
+                    &junctions,
+                    chars.tr,
                     &col_widths,
-                )
+                ))
+            } else {
+                rule.map(|rule| match rule {
+                    Rule::Inner => {
+                        let junctions = junctions(&above, &borders, inner);
+                        horizontal_line(
+                            chars.t_right,
+                            chars.horiz,
+                            &junctions,
+                            chars.t_left,
+                            &col_widths,
+                        )
+                    }
+                    Rule::Strong => {
+                        let junctions = junctions(&above, &borders, strong);
+                        horizontal_line(
+                            strong_left,
+                            strong_fill,
+                            &junctions,
+                            strong_right,
+                            &col_widths,
+                        )
+                    }
+                })
             };
-            ops.push(Op::Text(sep));
-            ops.push(Op::Newline);
+            if let Some(rule) = rule {
+                ops.push(Op::Text(rule));
+                ops.push(Op::Newline);
+            }
+
+            if bold {
+                ops.push(Op::SetBold(true));
+            }
+            let text = data_row(chars.vert, row, &col_widths, &self.align, num_cols);
+            push_grid_line(text, bold, false, ctx, ops);
+            if bold {
+                ops.push(Op::SetBold(false));
+            }
+            above = borders;
         }
 
-        // Data rows
-        for (i, row) in self.rows.iter().enumerate() {
-            let row_text = data_row(chars.vert, row, &col_widths, &self.align, num_cols);
-            ops.push(Op::Text(row_text));
+        // A header with nothing below it still gets its separator
+        if self.headers.is_some() && lines.len() == 1 {
+            let junctions = junctions(&above, &above, strong);
+            let sep = horizontal_line(
+                strong_left,
+                strong_fill,
+                &junctions,
+                strong_right,
+                &col_widths,
+            );
+            ops.push(Op::Text(sep));
             ops.push(Op::Newline);
-
-            // Row separator between rows, not after last
-            if self.row_separator && i < self.rows.len() - 1 {
-                let sep = horizontal_line(
-                    chars.t_right,
-                    chars.horiz,
-                    chars.cross,
-                    chars.t_left,
-                    &col_widths,
-                );
-                ops.push(Op::Text(sep));
-                ops.push(Op::Newline);
-            }
         }
 
         // Bottom border: └──┴──┘
-        let bottom = horizontal_line(chars.bl, chars.horiz, chars.t_up, chars.br, &col_widths);
+        let junctions = junctions(&above, &vec![false; num_cols - 1], inner);
+        let bottom = horizontal_line(chars.bl, chars.horiz, &junctions, chars.br, &col_widths);
         ops.push(Op::Text(bottom));
         ops.push(Op::Newline);
     }
 }
 
+/// Separator lines inside a table.
+#[derive(Clone, Copy)]
+enum Rule {
+    /// Between data rows.
+    Inner,
+    /// Below the header and above the footers.
+    Strong,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -742,9 +1128,10 @@ mod tests {
         let div = Divider {
             style: DividerStyle::Dashed,
             width: Some(10),
+            ..Default::default()
         };
         let mut ops = Vec::new();
-        div.emit(&mut ops);
+        div.emit(&EmitContext::default(), &mut ops);
         assert!(ops.iter().any(|op| *op == Op::Text("----------".into())));
     }
 
@@ -753,9 +1140,10 @@ mod tests {
         let div = Divider {
             style: DividerStyle::Equals,
             width: Some(5),
+            ..Default::default()
         };
         let mut ops = Vec::new();
-        div.emit(&mut ops);
+        div.emit(&EmitContext::default(), &mut ops);
         assert!(ops.iter().any(|op| *op == Op::Text("=====".into())));
     }
 
@@ -763,7 +1151,7 @@ mod tests {
     fn test_spacer_mm() {
         let spacer = Spacer::mm(5.0);
         let mut ops = Vec::new();
-        spacer.emit(&mut ops);
+        spacer.emit(&EmitContext::default(), &mut ops);
         assert!(ops.iter().any(|op| *op == Op::Feed { units: 20 }));
     }
 
@@ -771,7 +1159,7 @@ mod tests {
     fn test_spacer_lines() {
         let spacer = Spacer::lines(2);
         let mut ops = Vec::new();
-        spacer.emit(&mut ops);
+        spacer.emit(&EmitContext::default(), &mut ops);
         assert!(ops.iter().any(|op| *op == Op::Feed { units: 24 }));
     }
 
@@ -784,7 +1172,7 @@ mod tests {
             ..Default::default()
         };
         let mut ops = Vec::new();
-        cols.emit(&mut ops);
+        cols.emit(&EmitContext::default(), &mut ops);
         let has_columns = ops.iter().any(|op| {
             if let Op::Text(s) = op {
                 s.starts_with("Left") && s.ends_with("Right") && s.len() == 20
@@ -804,16 +1192,198 @@ mod tests {
             ..Default::default()
         };
         let mut ops = Vec::new();
-        cols.emit(&mut ops);
+        cols.emit(&EmitContext::default(), &mut ops);
         assert!(ops.contains(&Op::SetBold(true)));
         assert!(ops.contains(&Op::SetBold(false)));
     }
 
+    #[test]
+    fn test_columns_ratios_wrap() {
+        let cols = Columns {
+            columns: vec!["Espresso with oat milk".into(), "x2".into(), "$7.00".into()],
+            ratios: vec![2, 1, 1],
+            align: vec![ColumnAlign::Left, ColumnAlign::Center],
+            width: Some(22),
+            ..Default::default()
+        };
+        let mut ops = Vec::new();
+        cols.emit(&EmitContext::default(), &mut ops);
+        // 20 characters between the gaps: 10 + 5 + 5
+        assert_eq!(
+            text_lines(&ops),
+            vec![
+                "Espresso    x2   $7.00",
+                "with oat              ",
+                "milk                  ",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_ratio_widths() {
+        assert_eq!(ratio_widths(&[], 3, 10), vec![4, 3, 3]);
+        assert_eq!(ratio_widths(&[3, 1], 2, 8), vec![6, 2]);
+        assert_eq!(ratio_widths(&[0, 5], 2, 1), vec![1, 1]);
+    }
+
+    /// Printed lines: text ops joined up to each newline.
+    fn text_lines(ops: &[Op]) -> Vec<String> {
+        let mut lines = vec![String::new()];
+        for op in ops {
+            match op {
+                Op::Text(text) => lines.last_mut().unwrap().push_str(text),
+                Op::Newline => lines.push(String::new()),
+                _ => {}
+            }
+        }
+        lines.pop();
+        lines
+    }
+
+    #[test]
+    fn test_definitions_leaders_and_wrapping() {
+        let defs = Definitions {
+            width: Some(24),
+            ..Definitions::new([
+                ("Name", "Jojo"),
+                ("Order", "#1042"),
+                ("Notes", "Leave at the front desk"),
+                ("Empty", ""),
+            ])
+        };
+        let mut ops = Vec::new();
+        defs.emit(&EmitContext::default(), &mut ops);
+        assert_eq!(
+            text_lines(&ops),
+            [
+                "Name ... Jojo",
+                "Order .. #1042",
+                "Notes .. Leave at the",
+                "         front desk",
+                "Empty",
+            ]
+        );
+        assert_eq!(ops.first(), Some(&Op::PushStyle));
+        assert_eq!(ops.last(), Some(&Op::PopStyle));
+    }
+
+    #[test]
+    fn test_definitions_split_and_long_labels() {
+        let defs = Definitions {
+            leader: '_',
+            split: Some(8),
+            width: Some(20),
+            bold_labels: true,
+            ..Definitions::new([("Id", "7"), ("Long label here", "value"), ("Ab", "x")])
+        };
+        let mut ops = Vec::new();
+        defs.emit(&EmitContext::default(), &mut ops);
+        assert_eq!(
+            text_lines(&ops),
+            ["Id ____ 7", "Long label here", "        value", "Ab ____ x",]
+        );
+        assert_eq!(ops.iter().filter(|op| **op == Op::SetBold(true)).count(), 3);
+
+        // Values that don't fit the gap with a leader just get spaces
+        let tight = Definitions {
+            split: Some(7),
+            ..Definitions::new([("Total", "$5")])
+        };
+        let mut ops = Vec::new();
+        tight.emit(&EmitContext::default(), &mut ops);
+        assert_eq!(text_lines(&ops), ["Total  $5"]);
+    }
+
+    #[test]
+    fn test_definitions_json() {
+        let json = r#"{"document": [{"type": "definitions", "items": [["Name", "{{who}}"]]}]}"#;
+        let mut doc: crate::document::Document = serde_json::from_str(json).unwrap();
+        doc.variables.insert("who".into(), "Jojo".into());
+        let ops = doc.compile().ops;
+        assert!(
+            ops.iter()
+                .any(|op| matches!(op, Op::Text(s) if s.ends_with("Jojo")))
+        );
+    }
+
+    #[test]
+    fn test_progress_bar_blocks() {
+        let bar = ProgressBar {
+            label: Some("Battery".into()),
+            width: Some(24),
+            ..ProgressBar::new(57.0, 100.0)
+        };
+        let mut ops = Vec::new();
+        bar.emit(&EmitContext::default(), &mut ops);
+        // "Battery " + 11 cells + "  57%": 6.27 cells, rounded to 6.5
+        assert!(ops.contains(&Op::Text(format!(
+            "Battery {}\u{258C}{}  57%",
+            "\u{2588}".repeat(6),
+            "\u{2591}".repeat(4)
+        ))));
+        assert_eq!(ops.first(), Some(&Op::PushStyle));
+        assert_eq!(ops.last(), Some(&Op::PopStyle));
+    }
+
+    #[test]
+    fn test_progress_bar_shade_clamps_and_wraps_label() {
+        let bar = ProgressBar {
+            label: Some("A rather long label".into()),
+            width: Some(20),
+            style: ProgressBarStyle::Shade,
+            ..ProgressBar::new(150.0, 100.0)
+        };
+        let mut ops = Vec::new();
+        bar.emit(&EmitContext::default(), &mut ops);
+        let texts: Vec<&str> = ops
+            .iter()
+            .filter_map(|op| match op {
+                Op::Text(s) => Some(s.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            texts,
+            [
+                "A rather long label",
+                &format!("{} 100%", "\u{2593}".repeat(15))
+            ]
+        );
+    }
+
+    #[test]
+    fn test_progress_bar_raster() {
+        let bar = ProgressBar {
+            width: Some(100),
+            height: Some(10),
+            style: ProgressBarStyle::Raster,
+            show_percent: false,
+            ..ProgressBar::new(1.0, 4.0)
+        };
+        let mut ops = Vec::new();
+        bar.emit(&EmitContext::default(), &mut ops);
+        assert!(!ops.iter().any(|op| matches!(op, Op::Text(_))));
+        let Some(Op::GrayRaster {
+            width: 100,
+            height: 10,
+            data,
+            ..
+        }) = ops.iter().find(|op| matches!(op, Op::GrayRaster { .. }))
+        else {
+            panic!("expected a 100x10 gray raster");
+        };
+        // Middle row: border, 24 filled dots, then the gray track
+        let row = &data[500..600];
+        assert_eq!(row[2 + 23], 255);
+        assert_eq!(row[2 + 24], dither::level(PROGRESS_TRACK_GRAY));
+        assert_eq!(row[99], 255);
+    }
+
     #[test]
     fn test_blank_line() {
         let blank = BlankLine {};
         let mut ops = Vec::new();
-        blank.emit(&mut ops);
+        blank.emit(&EmitContext::default(), &mut ops);
         assert!(ops.iter().any(|op| *op == Op::Newline));
     }
 
@@ -824,7 +1394,7 @@ mod tests {
     #[test]
     fn test_banner_fit_short_text() {
         // "HELLO" (5 chars) fits at size 3×3 (16 chars/line, 14 usable)
-        let (size, total) = Banner::fit(5, 3, BorderStyle::Single);
+        let (size, total) = Banner::fit(5, 3, BorderStyle::Single, &EmitContext::default());
         assert_eq!(size, [3, 3]);
         assert_eq!(total, 16);
     }
@@ -832,7 +1402,7 @@ mod tests {
     #[test]
     fn test_banner_fit_medium_text() {
         // 15 chars won't fit at 3×3 (14 usable) but fits at 3×2 (22 usable)
-        let (size, total) = Banner::fit(15, 3, BorderStyle::Single);
+        let (size, total) = Banner::fit(15, 3, BorderStyle::Single, &EmitContext::default());
         assert_eq!(size, [3, 2]);
         assert_eq!(total, 24);
     }
@@ -840,7 +1410,7 @@ mod tests {
     #[test]
     fn test_banner_fit_long_text() {
         // 47 chars won't fit at 3×1 (46 usable) → Font B (62 usable)
-        let (size, total) = Banner::fit(47, 3, BorderStyle::Single);
+        let (size, total) = Banner::fit(47, 3, BorderStyle::Single, &EmitContext::default());
         assert_eq!(size, [0, 0]);
         assert_eq!(total, 64);
     }
@@ -848,7 +1418,7 @@ mod tests {
     #[test]
     fn test_banner_fit_size_0() {
         // max_size 0 → always Font B
-        let (size, total) = Banner::fit(5, 0, BorderStyle::Single);
+        let (size, total) = Banner::fit(5, 0, BorderStyle::Single, &EmitContext::default());
         assert_eq!(size, [0, 0]);
         assert_eq!(total, 64);
     }
@@ -857,7 +1427,7 @@ mod tests {
     fn test_banner_emit_basic() {
         let banner = Banner::new("TEST");
         let mut ops = Vec::new();
-        banner.emit(&mut ops);
+        banner.emit(&EmitContext::default(), &mut ops);
 
         // Should have font, alignment, size set, bold, and box-drawing text
         assert!(ops.contains(&Op::SetFont(Font::A)));
@@ -894,7 +1464,7 @@ mod tests {
             ..Default::default()
         };
         let mut ops = Vec::new();
-        banner.emit(&mut ops);
+        banner.emit(&EmitContext::default(), &mut ops);
 
         let has_double_top = ops.iter().any(|op| {
             if let Op::Text(s) = op {
@@ -916,7 +1486,7 @@ mod tests {
             ..Default::default()
         };
         let mut ops = Vec::new();
-        banner.emit(&mut ops);
+        banner.emit(&EmitContext::default(), &mut ops);
         assert!(ops.contains(&Op::SetFont(Font::B)));
         assert!(!ops.iter().any(|op| matches!(op, Op::SetSize { .. })));
     }
@@ -929,7 +1499,7 @@ mod tests {
             ..Default::default()
         };
         let mut ops = Vec::new();
-        banner.emit(&mut ops);
+        banner.emit(&EmitContext::default(), &mut ops);
 
         // Top line should be all full-block chars (█)
         let first_text = ops
@@ -956,7 +1526,7 @@ mod tests {
             ..Default::default()
         };
         let mut ops = Vec::new();
-        banner.emit(&mut ops);
+        banner.emit(&EmitContext::default(), &mut ops);
 
         // Top line should be all medium-shade chars (▒)
         let first_text = ops
@@ -983,7 +1553,7 @@ mod tests {
             ..Default::default()
         };
         let mut ops = Vec::new();
-        banner.emit(&mut ops);
+        banner.emit(&EmitContext::default(), &mut ops);
 
         // Top border: single-line, no shadow
         let first_text = ops
@@ -1052,12 +1622,12 @@ mod tests {
         // Shadow has overhead of 3 instead of 2
         // At size 3×3: 16 chars/line, 13 usable (16-3)
         // Content of 14 won't fit at 3×3 but fits at 3×2 (24-3 = 21 usable)
-        let (size, total) = Banner::fit(14, 3, BorderStyle::Shadow);
+        let (size, total) = Banner::fit(14, 3, BorderStyle::Shadow, &EmitContext::default());
         assert_eq!(size, [3, 2]);
         assert_eq!(total, 24);
 
         // Content of 13 fits at 3×3 (16-3 = 13 usable)
-        let (size, total) = Banner::fit(13, 3, BorderStyle::Shadow);
+        let (size, total) = Banner::fit(13, 3, BorderStyle::Shadow, &EmitContext::default());
         assert_eq!(size, [3, 3]);
         assert_eq!(total, 16);
     }
@@ -1071,7 +1641,7 @@ mod tests {
             ..Default::default()
         };
         let mut ops = Vec::new();
-        banner.emit(&mut ops);
+        banner.emit(&EmitContext::default(), &mut ops);
         let has_single_top = ops.iter().any(|op| {
             if let Op::Text(s) = op {
                 s.starts_with('\u{250C}') && s.ends_with('\u{2510}')
@@ -1091,7 +1661,7 @@ mod tests {
             ..Default::default()
         };
         let mut ops = Vec::new();
-        banner.emit(&mut ops);
+        banner.emit(&EmitContext::default(), &mut ops);
 
         // Should produce a single text line + newline (plus style ops)
         let texts: Vec<&str> = ops
@@ -1135,7 +1705,7 @@ mod tests {
             ..Default::default()
         };
         let mut ops = Vec::new();
-        banner.emit(&mut ops);
+        banner.emit(&EmitContext::default(), &mut ops);
 
         let texts: Vec<&str> = ops
             .iter()
@@ -1175,7 +1745,7 @@ mod tests {
             ..Default::default()
         };
         let mut ops = Vec::new();
-        banner.emit(&mut ops);
+        banner.emit(&EmitContext::default(), &mut ops);
 
         let texts: Vec<&str> = ops
             .iter()
@@ -1194,7 +1764,7 @@ mod tests {
     #[test]
     fn test_banner_fit_rule() {
         // Rule has 0 border overhead
-        let (size, total) = Banner::fit(5, 3, BorderStyle::Rule);
+        let (size, total) = Banner::fit(5, 3, BorderStyle::Rule, &EmitContext::default());
         assert_eq!(size, [3, 3]);
         assert_eq!(total, 16);
         // All 16 chars usable (no border deduction)
@@ -1205,7 +1775,7 @@ mod tests {
     #[test]
     fn test_banner_fit_tag() {
         // Tag has 2 chars overhead ("■ " prefix)
-        let (size, total) = Banner::fit(5, 3, BorderStyle::Tag);
+        let (size, total) = Banner::fit(5, 3, BorderStyle::Tag, &EmitContext::default());
         assert_eq!(size, [3, 3]);
         assert_eq!(total, 16);
     }
@@ -1222,7 +1792,7 @@ mod tests {
             ..Default::default()
         };
         let mut ops = Vec::new();
-        table.emit(&mut ops);
+        table.emit(&EmitContext::default(), &mut ops);
 
         // Should set Font A and Left alignment
         assert!(ops.contains(&Op::SetFont(Font::A)));
@@ -1258,6 +1828,36 @@ mod tests {
         assert!(last.contains('\u{2534}'), "Bottom has ┴ junction");
     }
 
+    #[test]
+    fn test_table_rtl_cells() {
+        let table = Table {
+            headers: Some(vec!["Item".into(), "Price".into()]),
+            rows: vec![vec!["שלום".into(), "5".into()]],
+            width: Some(20),
+            ..Default::default()
+        };
+        let mut ops = Vec::new();
+        table.emit(&EmitContext::default(), &mut ops);
+
+        // The header row stays text, the Hebrew row is a raster line
+        assert!(
+            ops.iter()
+                .any(|op| matches!(op, Op::Text(s) if s.contains("Item")))
+        );
+        let rasters: Vec<_> = ops
+            .iter()
+            .filter_map(|op| match op {
+                Op::Raster { width, height, .. } => Some((*width, *height)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(rasters, vec![(576, 24)]);
+
+        // Cells are in display order and padded by characters
+        let row = data_row('|', &table.rows[0], &[6, 3], &[], 2);
+        assert_eq!(row, "| םולש   | 5   |");
+    }
+
     #[test]
     fn test_table_with_headers() {
         let table = Table {
@@ -1267,7 +1867,7 @@ mod tests {
             ..Default::default()
         };
         let mut ops = Vec::new();
-        table.emit(&mut ops);
+        table.emit(&EmitContext::default(), &mut ops);
 
         // Header should be bold
         assert!(ops.contains(&Op::SetBold(true)));
@@ -1305,7 +1905,7 @@ mod tests {
             ..Default::default()
         };
         let mut ops = Vec::new();
-        table.emit(&mut ops);
+        table.emit(&EmitContext::default(), &mut ops);
 
         let row = ops
             .iter()
@@ -1344,7 +1944,7 @@ mod tests {
             ..Default::default()
         };
         let mut ops = Vec::new();
-        table.emit(&mut ops);
+        table.emit(&EmitContext::default(), &mut ops);
 
         let texts: Vec<&str> = ops
             .iter()
@@ -1372,7 +1972,7 @@ mod tests {
             ..Default::default()
         };
         let mut ops = Vec::new();
-        table.emit(&mut ops);
+        table.emit(&EmitContext::default(), &mut ops);
 
         // Top border should be single (┌)
         let texts: Vec<&str> = ops
@@ -1410,7 +2010,7 @@ mod tests {
             ..Default::default()
         };
         let mut ops = Vec::new();
-        table.emit(&mut ops);
+        table.emit(&EmitContext::default(), &mut ops);
 
         // Count separator lines (├...┤) — should be 2 (between 3 rows)
         let sep_count = ops
@@ -1439,7 +2039,7 @@ mod tests {
             ..Default::default()
         };
         let mut ops = Vec::new();
-        table.emit(&mut ops);
+        table.emit(&EmitContext::default(), &mut ops);
 
         // Short row should still produce a valid line with 3 │ separators
         let first_data_row = ops.iter().find_map(|op| {
@@ -1464,8 +2064,71 @@ mod tests {
             ..Default::default()
         };
         let mut ops = Vec::new();
-        table.emit(&mut ops);
+        table.emit(&EmitContext::default(), &mut ops);
         // Empty table with no rows and no headers → no output
         assert!(ops.is_empty(), "Empty table should produce no ops");
     }
+
+    #[test]
+    fn test_table_footer_colspan() {
+        let table: Table = serde_json::from_str(
+            r#"{
+                "headers": ["Item", "Qty", "Price"],
+                "rows": [["Tea", "2", "$6"]],
+                "footers": [[{"text": "Total", "colspan": 2}, "$6"]],
+                "align": ["left", "right", "right"],
+                "width": 22
+            }"#,
+        )
+        .unwrap();
+        let mut ops = Vec::new();
+        table.emit(&EmitContext::default(), &mut ops);
+        assert_eq!(
+            text_lines(&ops),
+            vec![
+                "┌──────┬─────┬───────┐",
+                "│ Item │ Qty │ Price │",
+                "├──────┼─────┼───────┤",
+                "│ Tea  │   2 │    $6 │",
+                "├──────┴─────┼───────┤",
+                "│ Total      │    $6 │",
+                "└────────────┴───────┘",
+            ]
+        );
+        // The footer row is bold
+        let bold = ops.iter().rposition(|op| *op == Op::SetBold(true)).unwrap();
+        assert_eq!(ops[bold + 1], Op::Text("│ Total      │    $6 │".into()));
+
+        // Plain cells stay strings when serialized
+        let json = serde_json::to_value(&table.footers).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!([[{"text": "Total", "colspan": 2}, "$6"]])
+        );
+    }
+
+    #[test]
+    fn test_table_span_widens_columns() {
+        let table = Table {
+            rows: vec![
+                vec!["a".into(), "b".into()],
+                vec![TableCell::spanning("wide label", 2)],
+            ],
+            row_separator: true,
+            width: Some(16),
+            ..Default::default()
+        };
+        let mut ops = Vec::new();
+        table.emit(&EmitContext::default(), &mut ops);
+        assert_eq!(
+            text_lines(&ops),
+            vec![
+                "┌───┬──────────┐",
+                "│ a │ b        │",
+                "├───┴──────────┤",
+                "│ wide label   │",
+                "└──────────────┘",
+            ]
+        );
+    }
 }
@@ -0,0 +1,375 @@
+//! Localized names for the built-in date variables.
+//!
+//! `{{date}}`, `{{date_short}}`, `{{day}}`, `{{datetime}}` and `{{greeting}}` follow the
+//! document's `locale`; every supported locale is also available explicitly
+//! as `{{date@es}}`, `{{day@de}}`, etc.
+
+use chrono::{Datelike, Timelike};
+
+/// Month/day names and date layouts for one language.
+///
+/// Layouts use `{d}` (day of month), `{month}`, `{mon}`, `{y}`, `{day}`,
+/// `{dy}` (short weekday) and `{time}` (HH:MM) placeholders.
+struct DateLocale {
+    code: &'static str,
+    months: [&'static str; 12],
+    months_short: [&'static str; 12],
+    /// Monday first.
+    days: [&'static str; 7],
+    days_short: [&'static str; 7],
+    date: &'static str,
+    date_short: &'static str,
+    datetime: &'static str,
+    /// Morning, afternoon and evening.
+    greetings: [&'static str; 3],
+}
+
+const LOCALES: &[DateLocale] = &[
+    DateLocale {
+        code: "en",
+        months: [
+            "January",
+            "February",
+            "March",
+            "April",
+            "May",
+            "June",
+            "July",
+            "August",
+            "September",
+            "October",
+            "November",
+            "December",
+        ],
+        months_short: [
+            "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+        ],
+        days: [
+            "Monday",
+            "Tuesday",
+            "Wednesday",
+            "Thursday",
+            "Friday",
+            "Saturday",
+            "Sunday",
+        ],
+        days_short: ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"],
+        date: "{month} {d}, {y}",
+        date_short: "{mon} {d}",
+        datetime: "{dy}, {mon} {d} {time}",
+        greetings: ["Good morning", "Good afternoon", "Good evening"],
+    },
+    DateLocale {
+        code: "es",
+        months: [
+            "enero",
+            "febrero",
+            "marzo",
+            "abril",
+            "mayo",
+            "junio",
+            "julio",
+            "agosto",
+            "septiembre",
+            "octubre",
+            "noviembre",
+            "diciembre",
+        ],
+        months_short: [
+            "ene", "feb", "mar", "abr", "may", "jun", "jul", "ago", "sep", "oct", "nov", "dic",
+        ],
+        days: [
+            "lunes",
+            "martes",
+            "miércoles",
+            "jueves",
+            "viernes",
+            "sábado",
+            "domingo",
+        ],
+        days_short: ["lun", "mar", "mié", "jue", "vie", "sáb", "dom"],
+        date: "{d} de {month} de {y}",
+        date_short: "{d} {mon}",
+        datetime: "{dy}, {d} {mon} {time}",
+        greetings: ["Buenos días", "Buenas tardes", "Buenas noches"],
+    },
+    DateLocale {
+        code: "de",
+        months: [
+            "Januar",
+            "Februar",
+            "März",
+            "April",
+            "Mai",
+            "Juni",
+            "Juli",
+            "August",
+            "September",
+            "Oktober",
+            "November",
+            "Dezember",
+        ],
+        months_short: [
+            "Jan", "Feb", "Mär", "Apr", "Mai", "Jun", "Jul", "Aug", "Sep", "Okt", "Nov", "Dez",
+        ],
+        days: [
+            "Montag",
+            "Dienstag",
+            "Mittwoch",
+            "Donnerstag",
+            "Freitag",
+            "Samstag",
+            "Sonntag",
+        ],
+        days_short: ["Mo", "Di", "Mi", "Do", "Fr", "Sa", "So"],
+        date: "{d}. {month} {y}",
+        date_short: "{d}. {mon}",
+        datetime: "{dy}, {d}. {mon} {time}",
+        greetings: ["Guten Morgen", "Guten Tag", "Guten Abend"],
+    },
+    DateLocale {
+        code: "fr",
+        months: [
+            "janvier",
+            "février",
+            "mars",
+            "avril",
+            "mai",
+            "juin",
+            "juillet",
+            "août",
+            "septembre",
+            "octobre",
+            "novembre",
+            "décembre",
+        ],
+        months_short: [
+            "janv", "févr", "mars", "avr", "mai", "juin", "juil", "août", "sept", "oct", "nov",
+            "déc",
+        ],
+        days: [
+            "lundi", "mardi", "mercredi", "jeudi", "vendredi", "samedi", "dimanche",
+        ],
+        days_short: ["lun", "mar", "mer", "jeu", "ven", "sam", "dim"],
+        date: "{d} {month} {y}",
+        date_short: "{d} {mon}",
+        datetime: "{dy} {d} {mon} {time}",
+        greetings: ["Bonjour", "Bon après-midi", "Bonsoir"],
+    },
+    DateLocale {
+        code: "it",
+        months: [
+            "gennaio",
+            "febbraio",
+            "marzo",
+            "aprile",
+            "maggio",
+            "giugno",
+            "luglio",
+            "agosto",
+            "settembre",
+            "ottobre",
+            "novembre",
+            "dicembre",
+        ],
+        months_short: [
+            "gen", "feb", "mar", "apr", "mag", "giu", "lug", "ago", "set", "ott", "nov", "dic",
+        ],
+        days: [
+            "lunedì",
+            "martedì",
+            "mercoledì",
+            "giovedì",
+            "venerdì",
+            "sabato",
+            "domenica",
+        ],
+        days_short: ["lun", "mar", "mer", "gio", "ven", "sab", "dom"],
+        date: "{d} {month} {y}",
+        date_short: "{d} {mon}",
+        datetime: "{dy} {d} {mon} {time}",
+        greetings: ["Buongiorno", "Buon pomeriggio", "Buonasera"],
+    },
+    DateLocale {
+        code: "pt",
+        months: [
+            "janeiro",
+            "fevereiro",
+            "março",
+            "abril",
+            "maio",
+            "junho",
+            "julho",
+            "agosto",
+            "setembro",
+            "outubro",
+            "novembro",
+            "dezembro",
+        ],
+        months_short: [
+            "jan", "fev", "mar", "abr", "mai", "jun", "jul", "ago", "set", "out", "nov", "dez",
+        ],
+        days: [
+            "segunda-feira",
+            "terça-feira",
+            "quarta-feira",
+            "quinta-feira",
+            "sexta-feira",
+            "sábado",
+            "domingo",
+        ],
+        days_short: ["seg", "ter", "qua", "qui", "sex", "sáb", "dom"],
+        date: "{d} de {month} de {y}",
+        date_short: "{d} {mon}",
+        datetime: "{dy}, {d} {mon} {time}",
+        greetings: ["Bom dia", "Boa tarde", "Boa noite"],
+    },
+    DateLocale {
+        code: "nl",
+        months: [
+            "januari",
+            "februari",
+            "maart",
+            "april",
+            "mei",
+            "juni",
+            "juli",
+            "augustus",
+            "september",
+            "oktober",
+            "november",
+            "december",
+        ],
+        months_short: [
+            "jan", "feb", "mrt", "apr", "mei", "jun", "jul", "aug", "sep", "okt", "nov", "dec",
+        ],
+        days: [
+            "maandag",
+            "dinsdag",
+            "woensdag",
+            "donderdag",
+            "vrijdag",
+            "zaterdag",
+            "zondag",
+        ],
+        days_short: ["ma", "di", "wo", "do", "vr", "za", "zo"],
+        date: "{d} {month} {y}",
+        date_short: "{d} {mon}",
+        datetime: "{dy} {d} {mon} {time}",
+        greetings: ["Goedemorgen", "Goedemiddag", "Goedenavond"],
+    },
+];
+
+/// Codes of all supported locales.
+pub fn supported_locales() -> impl Iterator<Item = &'static str> {
+    LOCALES.iter().map(|l| l.code)
+}
+
+/// Find a locale by code. Accepts region-qualified codes (`es-MX`, `de_AT`)
+/// by matching on the language part.
+fn find(code: &str) -> Option<&'static DateLocale> {
+    let lang = code
+        .split(['-', '_'])
+        .next()
+        .unwrap_or_default()
+        .to_lowercase();
+    LOCALES.iter().find(|l| l.code == lang)
+}
+
+/// Localized `(name, value)` pairs for `date`, `date_short`, `day`,
+/// `datetime` and `greeting`. Returns `None` for unsupported locales.
+pub fn date_variables<T: Datelike + Timelike>(
+    now: &T,
+    code: &str,
+) -> Option<Vec<(&'static str, String)>> {
+    let locale = find(code)?;
+    let month = now.month0() as usize;
+    let weekday = now.weekday().num_days_from_monday() as usize;
+
+    let fill = |layout: &str| {
+        layout
+            .replace("{month}", locale.months[month])
+            .replace("{mon}", locale.months_short[month])
+            .replace("{day}", locale.days[weekday])
+            .replace("{dy}", locale.days_short[weekday])
+            .replace("{d}", &now.day().to_string())
+            .replace("{y}", &now.year().to_string())
+            .replace("{time}", &format!("{:02}:{:02}", now.hour(), now.minute()))
+    };
+
+    Some(vec![
+        ("date", fill(locale.date)),
+        ("date_short", fill(locale.date_short)),
+        ("day", locale.days[weekday].to_string()),
+        ("datetime", fill(locale.datetime)),
+        (
+            "greeting",
+            locale.greetings[day_period(now.hour())].to_string(),
+        ),
+    ])
+}
+
+/// Greeting for an hour of the day: morning from 5:00, afternoon from
+/// 12:00, evening from 18:00 through the night.
+fn day_period(hour: u32) -> usize {
+    match hour {
+        5..=11 => 0,
+        12..=17 => 1,
+        _ => 2,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn sample() -> chrono::NaiveDateTime {
+        // Tuesday
+        NaiveDate::from_ymd_opt(2026, 1, 27)
+            .unwrap()
+            .and_hms_opt(9, 30, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_english_matches_default_format() {
+        let now = sample();
+        let vars = date_variables(&now, "en").unwrap();
+        assert_eq!(vars[0], ("date", now.format("%B %-d, %Y").to_string()));
+        assert_eq!(vars[1], ("date_short", now.format("%b %-d").to_string()));
+        assert_eq!(vars[2], ("day", now.format("%A").to_string()));
+        assert_eq!(
+            vars[3],
+            ("datetime", now.format("%a, %b %-d %H:%M").to_string())
+        );
+    }
+
+    #[test]
+    fn test_spanish() {
+        let vars = date_variables(&sample(), "es").unwrap();
+        assert_eq!(vars[0].1, "27 de enero de 2026");
+        assert_eq!(vars[2].1, "martes");
+        assert_eq!(vars[3].1, "mar, 27 ene 09:30");
+        assert_eq!(vars[4].1, "Buenos días");
+    }
+
+    #[test]
+    fn test_greeting() {
+        let at = |hour| sample().date().and_hms_opt(hour, 0, 0).unwrap();
+        let greeting = |hour| date_variables(&at(hour), "en").unwrap()[4].1.clone();
+        assert_eq!(greeting(5), "Good morning");
+        assert_eq!(greeting(12), "Good afternoon");
+        assert_eq!(greeting(18), "Good evening");
+        assert_eq!(greeting(2), "Good evening");
+    }
+
+    #[test]
+    fn test_region_qualified_and_unknown() {
+        assert_eq!(
+            date_variables(&sample(), "de_AT").unwrap()[0].1,
+            "27. Januar 2026"
+        );
+        assert!(date_variables(&sample(), "xx").is_none());
+    }
+}
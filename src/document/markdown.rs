@@ -1,14 +1,22 @@
 //! Emit logic for the Markdown component.
+//!
+//! Headings, emphasis, links and inline code become text styles. Block
+//! elements are laid out for the receipt: lists and block quotes indent
+//! their lines (wrapped lines too), code blocks print in Font B, and
+//! tables and rules become [`Table`] and [`Divider`] components.
 
-use pulldown_cmark::{Event, HeadingLevel, Parser, Tag, TagEnd};
+use pulldown_cmark::{
+    Alignment as CellAlignment, Event, HeadingLevel, Options, Parser, Tag, TagEnd,
+};
 
-use super::types::Markdown;
-use crate::ir::Op;
+use super::EmitContext;
+use super::types::{ColumnAlign, Divider, DividerStyle, Markdown, Table, TableCell};
+use crate::ir::{Op, Wrap, WrapMode};
 use crate::protocol::text::{Alignment, Font};
 
 impl Markdown {
     /// Emit IR ops for this markdown component.
-    pub fn emit(&self, ops: &mut Vec<Op>) {
+    pub fn emit(&self, ctx: &EmitContext, ops: &mut Vec<Op>) {
         // Skip empty content
         if self.content.trim().is_empty() {
             return;
@@ -17,20 +25,41 @@ impl Markdown {
         // Ensure we start in a known state (left-aligned)
         ops.push(Op::SetAlign(Alignment::Left));
 
-        let parser = Parser::new(&self.content);
+        let parser = Parser::new_ext(&self.content, Options::ENABLE_TABLES);
         let mut state = ParserState::new(self.show_urls);
 
         for event in parser {
+            // Table cells are collected as plain text, then laid out at once
+            if let Some(table) = state.table.as_mut() {
+                if table.handle(event) {
+                    let table = state.table.take().unwrap_or_default();
+                    table.emit(ctx, ops);
+                }
+                continue;
+            }
+
             match event {
+                Event::Start(Tag::Table(alignments)) => {
+                    finish_line(ops);
+                    state.just_finished_heading = false;
+                    state.table = Some(MarkdownTable::new(&alignments));
+                }
                 Event::Start(tag) => state.handle_start_tag(tag, ops),
                 Event::End(tag_end) => state.handle_end_tag(tag_end, ops),
                 Event::Text(text) => state.handle_text(&text, ops),
                 Event::Code(code) => state.handle_inline_code(&code, ops),
                 Event::SoftBreak => ops.push(Op::Text(" ".into())),
-                Event::HardBreak => ops.push(Op::Newline),
-                Event::Rule => {
-                    ops.push(Op::Text("\u{2500}".repeat(48)));
+                Event::HardBreak => {
                     ops.push(Op::Newline);
+                    state.pending_prefix = state.line_prefix();
+                }
+                Event::Rule => {
+                    finish_line(ops);
+                    let divider = Divider {
+                        style: DividerStyle::Solid,
+                        ..Default::default()
+                    };
+                    divider.emit(ctx, ops);
                 }
                 _ => {}
             }
@@ -38,39 +67,89 @@ impl Markdown {
     }
 }
 
+/// Quote bar printed before each line of a block quote.
+const QUOTE_BAR: &str = "\u{2502} "; // │
+
 /// Internal state for tracking nested formatting during parsing.
 struct ParserState {
     show_urls: bool,
-    list_depth: usize,
-    list_counters: Vec<usize>,
+    /// Open lists: the next number of ordered ones, `None` for bullets.
+    lists: Vec<Option<u64>>,
+    /// Width of the marker of each open list item ("* ", "12. ").
+    item_widths: Vec<usize>,
+    quote_depth: usize,
     pending_url: Option<String>,
-    pending_list_prefix: Option<String>,
+    /// Printed before the next text: list markers, quote bars, indentation.
+    pending_prefix: Option<String>,
+    /// Hanging indent currently set, so wrapped lines line up.
+    hanging_indent: usize,
     in_heading: bool,
     heading_level: Option<HeadingLevel>,
     just_finished_heading: bool,
+    table: Option<MarkdownTable>,
 }
 
 impl ParserState {
     fn new(show_urls: bool) -> Self {
         Self {
             show_urls,
-            list_depth: 0,
-            list_counters: Vec::new(),
+            lists: Vec::new(),
+            item_widths: Vec::new(),
+            quote_depth: 0,
             pending_url: None,
-            pending_list_prefix: None,
+            pending_prefix: None,
+            hanging_indent: 0,
             in_heading: false,
             heading_level: None,
             just_finished_heading: false,
+            table: None,
+        }
+    }
+
+    /// What starts a line at the current nesting: a bar per block quote,
+    /// then spaces up to the text of the enclosing list items.
+    fn line_prefix(&self) -> Option<String> {
+        let indent: usize = self.item_widths.iter().sum();
+        let prefix = format!(
+            "{}{}",
+            QUOTE_BAR.repeat(self.quote_depth),
+            " ".repeat(indent)
+        );
+        (!prefix.is_empty()).then_some(prefix)
+    }
+
+    /// Indent wrapped lines to the current nesting.
+    fn update_hanging_indent(&mut self, mode: WrapMode, ops: &mut Vec<Op>) {
+        let indent = self
+            .line_prefix()
+            .map_or(0, |prefix| prefix.chars().count());
+        if indent != self.hanging_indent || mode != WrapMode::Word {
+            ops.push(Op::SetWrap(Wrap {
+                mode,
+                hanging_indent: indent.min(u8::MAX as usize) as u8,
+            }));
         }
+        self.hanging_indent = indent;
+    }
+
+    fn in_block(&self) -> bool {
+        self.quote_depth > 0 || !self.lists.is_empty()
     }
 
     fn handle_start_tag(&mut self, tag: Tag, ops: &mut Vec<Op>) {
+        // Only a paragraph right after a heading needs space before it
+        if !matches!(tag, Tag::Paragraph) && is_block(&tag) {
+            self.just_finished_heading = false;
+        }
         match tag {
             Tag::Paragraph => {
                 if self.just_finished_heading {
                     ops.push(Op::Newline);
                     self.just_finished_heading = false;
                 }
+                if self.pending_prefix.is_none() {
+                    self.pending_prefix = self.line_prefix();
+                }
             }
             Tag::Heading { level, .. } => {
                 self.in_heading = true;
@@ -117,6 +196,11 @@ impl ParserState {
                     }
                 }
             }
+            Tag::BlockQuote(_) => {
+                finish_line(ops);
+                self.quote_depth += 1;
+                self.update_hanging_indent(WrapMode::Word, ops);
+            }
             Tag::Strong => {
                 ops.push(Op::SetBold(true));
             }
@@ -130,30 +214,33 @@ impl ParserState {
                 }
             }
             Tag::List(start_num) => {
-                if let Some(start) = start_num {
-                    self.list_counters.push(start as usize);
-                }
-                self.list_depth += 1;
-                if self.list_depth == 1 {
+                if self.lists.is_empty() && self.quote_depth == 0 {
                     ops.push(Op::Newline);
                 }
+                // A nested list starts below its item's text
+                finish_line(ops);
+                self.lists.push(start_num);
             }
             Tag::Item => {
-                let indent_dots = (self.list_depth - 1) * 32;
-                if indent_dots > 0 {
-                    ops.push(Op::SetAbsolutePosition(indent_dots as u16));
-                }
-
-                if let Some(counter) = self.list_counters.last_mut() {
-                    self.pending_list_prefix = Some(format!("{}. ", counter));
-                    *counter += 1;
-                } else {
-                    self.pending_list_prefix = Some("* ".into());
-                }
+                finish_line(ops);
+                let marker = match self.lists.last_mut() {
+                    Some(Some(counter)) => {
+                        *counter += 1;
+                        format!("{}. ", *counter - 1)
+                    }
+                    _ => "* ".into(),
+                };
+                let indent = self.line_prefix().unwrap_or_default();
+                self.item_widths.push(marker.chars().count());
+                self.pending_prefix = Some(indent + &marker);
+                self.update_hanging_indent(WrapMode::Word, ops);
             }
             Tag::CodeBlock(_kind) => {
+                finish_line(ops);
                 ops.push(Op::SetInvert(true));
                 ops.push(Op::SetFont(Font::B));
+                // Code is broken mid-word rather than reflowed
+                self.update_hanging_indent(WrapMode::Char, ops);
             }
             _ => {}
         }
@@ -162,8 +249,13 @@ impl ParserState {
     fn handle_end_tag(&mut self, tag_end: TagEnd, ops: &mut Vec<Op>) {
         match tag_end {
             TagEnd::Paragraph => {
-                ops.push(Op::Newline);
-                ops.push(Op::Newline);
+                if self.in_block() {
+                    finish_line(ops);
+                } else {
+                    ops.push(Op::Newline);
+                    ops.push(Op::Newline);
+                }
+                self.pending_prefix = None;
             }
             TagEnd::Heading(_level) => {
                 self.in_heading = false;
@@ -226,6 +318,14 @@ impl ParserState {
                 self.heading_level = None;
                 self.just_finished_heading = true;
             }
+            TagEnd::BlockQuote(_) => {
+                finish_line(ops);
+                self.quote_depth = self.quote_depth.saturating_sub(1);
+                self.update_hanging_indent(WrapMode::Word, ops);
+                if !self.in_block() {
+                    ops.push(Op::Newline);
+                }
+            }
             TagEnd::Strong => {
                 ops.push(Op::SetBold(false));
             }
@@ -244,17 +344,26 @@ impl ParserState {
                 }
             }
             TagEnd::List(_is_ordered) => {
-                if !self.list_counters.is_empty() {
-                    self.list_counters.pop();
+                self.lists.pop();
+                if !self.in_block() {
+                    ops.push(Op::Newline);
                 }
-                self.list_depth = self.list_depth.saturating_sub(1);
-                ops.push(Op::Newline);
             }
             TagEnd::Item => {
-                ops.push(Op::Newline);
-                ops.push(Op::SetAbsolutePosition(0));
+                finish_line(ops);
+                self.item_widths.pop();
+                self.pending_prefix = None;
+                self.update_hanging_indent(WrapMode::Word, ops);
             }
             TagEnd::CodeBlock => {
+                // The code ends with its last line's newline; the line
+                // break below replaces it
+                if let Some(Op::Text(code)) = ops.last_mut()
+                    && code.ends_with('\n')
+                {
+                    code.pop();
+                }
+                self.update_hanging_indent(WrapMode::Word, ops);
                 ops.push(Op::SetInvert(false));
                 ops.push(Op::SetFont(Font::A));
                 ops.push(Op::Newline);
@@ -264,7 +373,7 @@ impl ParserState {
     }
 
     fn handle_text(&mut self, text: &str, ops: &mut Vec<Op>) {
-        if let Some(prefix) = self.pending_list_prefix.take() {
+        if let Some(prefix) = self.pending_prefix.take() {
             ops.push(Op::Text(format!("{}{}", prefix, text)));
         } else {
             ops.push(Op::Text(text.to_string()));
@@ -272,20 +381,111 @@ impl ParserState {
     }
 
     fn handle_inline_code(&mut self, code: &str, ops: &mut Vec<Op>) {
+        if let Some(prefix) = self.pending_prefix.take() {
+            ops.push(Op::Text(prefix));
+        }
         ops.push(Op::SetInvert(true));
         ops.push(Op::Text(code.to_string()));
         ops.push(Op::SetInvert(false));
     }
 }
 
+/// Whether `tag` starts a block (as opposed to inline styling).
+fn is_block(tag: &Tag) -> bool {
+    matches!(
+        tag,
+        Tag::Paragraph
+            | Tag::Heading { .. }
+            | Tag::BlockQuote(_)
+            | Tag::CodeBlock(_)
+            | Tag::List(_)
+            | Tag::Item
+            | Tag::Table(_)
+    )
+}
+
+/// End the current line, unless nothing was printed on it yet.
+fn finish_line(ops: &mut Vec<Op>) {
+    let line_open = ops.iter().rev().find_map(|op| match op {
+        Op::Text(_) => Some(true),
+        Op::Newline | Op::Feed { .. } => Some(false),
+        _ => None,
+    });
+    if line_open == Some(true) {
+        ops.push(Op::Newline);
+    }
+}
+
+/// A Markdown table being collected, cell by cell, as plain text.
+#[derive(Default)]
+struct MarkdownTable {
+    align: Vec<ColumnAlign>,
+    headers: Option<Vec<String>>,
+    rows: Vec<Vec<String>>,
+    row: Vec<String>,
+    cell: String,
+}
+
+impl MarkdownTable {
+    fn new(alignments: &[CellAlignment]) -> Self {
+        let align = alignments
+            .iter()
+            .map(|alignment| match alignment {
+                CellAlignment::Center => ColumnAlign::Center,
+                CellAlignment::Right => ColumnAlign::Right,
+                CellAlignment::Left | CellAlignment::None => ColumnAlign::Left,
+            })
+            .collect();
+        Self {
+            align,
+            ..Default::default()
+        }
+    }
+
+    /// Take the next event inside the table. Returns true at its end.
+    fn handle(&mut self, event: Event) -> bool {
+        match event {
+            Event::Text(text) | Event::Code(text) => self.cell.push_str(&text),
+            Event::SoftBreak | Event::HardBreak => self.cell.push(' '),
+            Event::End(TagEnd::TableCell) => {
+                let cell = std::mem::take(&mut self.cell);
+                self.row.push(cell.trim().to_string());
+            }
+            Event::End(TagEnd::TableHead) => self.headers = Some(std::mem::take(&mut self.row)),
+            Event::End(TagEnd::TableRow) => self.rows.push(std::mem::take(&mut self.row)),
+            Event::End(TagEnd::Table) => return true,
+            _ => {}
+        }
+        false
+    }
+
+    /// Lay the table out as a [`Table`] at the full line width, followed
+    /// by a blank line like a paragraph.
+    fn emit(self, ctx: &EmitContext, ops: &mut Vec<Op>) {
+        let table = Table {
+            headers: self.headers,
+            rows: self
+                .rows
+                .into_iter()
+                .map(|row| row.into_iter().map(TableCell::from).collect())
+                .collect(),
+            align: self.align,
+            ..Default::default()
+        };
+        table.emit(ctx, ops);
+        ops.push(Op::Newline);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::document::testing::texts;
 
     fn compile_markdown(text: &str) -> Vec<Op> {
         let md = Markdown::new(text);
         let mut ops = Vec::new();
-        md.emit(&mut ops);
+        md.emit(&EmitContext::default(), &mut ops);
         ops
     }
 
@@ -347,4 +547,50 @@ mod tests {
             .collect();
         assert!(text_ops.iter().any(|s: &&str| s.contains("\u{2500}")));
     }
+
+    #[test]
+    fn test_nested_list_lines() {
+        let ops = compile_markdown("- Coffee\n  1. Oat milk\n  2. Sugar\n- Tea");
+        assert_eq!(
+            texts(&ops),
+            vec!["* Coffee", "  1. Oat milk", "  2. Sugar", "* Tea"]
+        );
+        // Each item is its own line; wrapped lines hang under the text
+        let item = ops
+            .iter()
+            .position(|op| *op == Op::Text("  1. Oat milk".into()));
+        assert_eq!(ops[item.unwrap() + 1], Op::Newline);
+        assert!(ops.contains(&Op::SetWrap(Wrap {
+            mode: WrapMode::Word,
+            hanging_indent: 5,
+        })));
+        assert_eq!(
+            ops.iter().rev().find(|op| matches!(op, Op::SetWrap(_))),
+            Some(&Op::SetWrap(Wrap::default()))
+        );
+    }
+
+    #[test]
+    fn test_block_quote_and_code() {
+        let ops = compile_markdown("> Ship it\n> today\n\n```\nfn main() {}\nexit\n```");
+        assert_eq!(
+            texts(&ops),
+            vec!["\u{2502} Ship it", " ", "today", "fn main() {}\nexit"]
+        );
+        assert!(ops.contains(&Op::SetWrap(Wrap {
+            mode: WrapMode::Char,
+            hanging_indent: 0,
+        })));
+        assert!(ops.contains(&Op::SetFont(Font::B)));
+    }
+
+    #[test]
+    fn test_table() {
+        let ops = compile_markdown("| Item | Price |\n|:-----|------:|\n| Tea | $3 |\n");
+        let texts = texts(&ops);
+        assert!(texts[0].starts_with('\u{250C}'), "top border: {:?}", texts);
+        assert!(texts[1].contains("Item") && texts[1].contains("Price"));
+        assert!(texts[3].contains("Tea") && texts[3].trim_end_matches('\u{2502}').ends_with("$3 "));
+        assert!(texts.last().unwrap().starts_with('\u{2514}'));
+    }
 }
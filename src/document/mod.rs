@@ -20,24 +20,70 @@
 //! let doc: Document = serde_json::from_str(r#"{"document":[{"type":"text","content":"Hello"}]}"#).unwrap();
 //!
 //! // Both produce bytes the same way
-//! let bytes = doc.build();
+//! let bytes = doc.build()?;
 //! ```
+//!
+//! Nothing here panics on a document's content: documents come from HTTP
+//! requests, and what can't be printed is reported as an error instead.
+
+#![deny(clippy::unwrap_used, clippy::expect_used)]
 
 pub mod types;
 
+mod astro;
 mod barcode;
+pub mod batch;
 pub mod canvas;
+mod compact;
+pub mod compat;
+mod context;
+mod dump;
+mod errors;
+mod filters;
+pub mod format;
+mod gift;
 mod graphics;
+pub mod hooks;
+#[cfg(feature = "html")]
+mod html;
+mod label;
 mod layout;
+pub mod locale;
 mod markdown;
+#[cfg(feature = "pdf")]
+pub mod pdf;
+pub mod poster;
+#[cfg(feature = "images")]
 pub mod resolve;
+mod routing;
+#[cfg(feature = "server")]
+pub mod shorten;
+mod template;
 mod text;
-
-pub use resolve::{ImageResolver, fetch_image, fetch_image_with_ctx};
+mod ticket;
+
+pub use astro::Location;
+pub(crate) use astro::sun_variables;
+pub use context::EmitContext;
+pub use errors::{ComponentError, OnError};
+pub use hooks::{CommandHook, DocumentHook};
+#[cfg(feature = "images")]
+pub use resolve::{
+    DEFAULT_MAX_IMAGE_BYTES, ImageResolver, ImageSources, fetch_image, fetch_image_with_ctx,
+};
+#[cfg(feature = "server")]
+pub use shorten::{ShortenerConfig, UrlShortener};
 pub use types::*;
 
-use crate::ir::{Op, Program};
+use crate::EstrellaError;
+use crate::ir::{Op, OptimizeLevel, Program};
+use crate::job::JobId;
+use crate::metrics::{self, Stage};
+use crate::preview::{PreviewError, RawRaster, render_raw_with_config};
 use crate::printer::PrinterConfig;
+use crate::protocol::commands::PrintSpeed;
+use crate::protocol::text::{Alignment, Font, LineSpacing};
+use crate::render::dither::DitheringAlgorithm;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
@@ -45,6 +91,10 @@ fn default_true() -> bool {
     true
 }
 
+fn default_version() -> u32 {
+    compat::CURRENT_VERSION
+}
+
 // ============================================================================
 // SHORTHAND DESERIALIZATION
 // ============================================================================
@@ -66,7 +116,12 @@ const SHORTHANDS: &[(&str, &str, &str)] = &[
     ("image", "image", "url"),
     ("qr_code", "qr_code", "data"),
     ("markdown", "markdown", "content"),
+    ("html", "html", "content"),
     ("canvas", "canvas", "elements"),
+    ("texture", "texture", "pattern"),
+    ("composition", "composition", "layers"),
+    ("pattern_stack", "composition", "layers"),
+    ("progress", "progress_bar", "value"),
 ];
 
 /// Rewrite a shorthand JSON object to canonical `{"type": ...}` form.
@@ -103,32 +158,83 @@ where
         .into_iter()
         .enumerate()
         .map(|(i, v)| {
-            let mut obj = match v {
-                serde_json::Value::Object(map) => map,
-                other => {
-                    return Err(serde::de::Error::custom(format!(
-                        "document[{}]: expected object, got {}",
-                        i, other
-                    )));
-                }
-            };
+            parse_component(v)
+                .map_err(|e| serde::de::Error::custom(format!("document[{}]{}", i, e)))
+        })
+        .collect()
+}
 
-            if !obj.contains_key("type") {
-                normalize_shorthand(&mut obj)
-                    .map_err(|e| serde::de::Error::custom(format!("document[{}]: {}", i, e)))?;
-            }
+/// Parse one component, with shorthand, `"if"`/`"unless"` and `"stations"`
+/// support. Errors start with the path inside the component (`": ..."`,
+/// `".stations: ..."`) so callers can prefix where it is.
+pub(crate) fn parse_component(v: serde_json::Value) -> Result<Component, String> {
+    let mut obj = match v {
+        serde_json::Value::Object(map) => map,
+        other => return Err(format!(": expected object, got {}", other)),
+    };
+
+    if !obj.contains_key("type") {
+        normalize_shorthand(&mut obj).map_err(|e| format!(": {}", e))?;
+    }
+    let kind = obj
+        .get("type")
+        .and_then(|t| t.as_str())
+        .unwrap_or_default()
+        .to_string();
+
+    // `"if"` / `"unless"` on any other component wraps it in a conditional
+    if kind != "conditional" && (obj.contains_key("if") || obj.contains_key("unless")) {
+        let mut condition = |key: &str| {
+            obj.remove(key)
+                .map(serde_json::from_value::<String>)
+                .transpose()
+                .map_err(|e| format!(".{}: {}", key, e))
+        };
+        let (if_set, unless) = (condition("if")?, condition("unless")?);
+        let component = parse_component(serde_json::Value::Object(obj))?;
+        return Ok(Component::Conditional(Conditional {
+            if_set,
+            unless,
+            components: vec![component],
+        }));
+    }
+
+    // `"stations"` on any other component routes it through a group
+    if kind != "station_group"
+        && let Some(stations) = obj.remove("stations")
+    {
+        let stations = serde_json::from_value(stations).map_err(|e| format!(".stations: {}", e))?;
+        let component = serde_json::from_value(serde_json::Value::Object(obj))
+            .map_err(|e| format!(": {}", e))?;
+        return Ok(Component::StationGroup(StationGroup {
+            stations,
+            components: vec![component],
+        }));
+    }
+
+    serde_json::from_value(serde_json::Value::Object(obj)).map_err(|e| format!(": {}", e))
+}
 
-            serde_json::from_value(serde_json::Value::Object(obj))
-                .map_err(|e| serde::de::Error::custom(format!("document[{}]: {}", i, e)))
+/// Deserialize `variables`, keeping non-string values as JSON text.
+fn deserialize_variables<'de, D>(deserializer: D) -> Result<HashMap<String, String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let values: HashMap<String, serde_json::Value> = HashMap::deserialize(deserializer)?;
+    Ok(values
+        .into_iter()
+        .map(|(name, value)| match value {
+            serde_json::Value::String(text) => (name, text),
+            other => (name, other.to_string()),
         })
-        .collect()
+        .collect())
 }
 
 /// Deserialize a `Vec<CanvasElement>` with shorthand support for the inner component.
 ///
 /// Each element is first parsed as raw JSON. Canvas-specific keys (`position`,
-/// `blend_mode`, `opacity`) are extracted, then the remaining object is
-/// deserialized as a `Component` (with shorthand normalization).
+/// `blend_mode`, `opacity`, `rotate`, `scale`) are extracted, then the remaining object is
+/// parsed as a `Component` like any other (shorthand, `if` / `unless`).
 fn deserialize_canvas_elements<'de, D>(deserializer: D) -> Result<Vec<CanvasElement>, D::Error>
 where
     D: serde::Deserializer<'de>,
@@ -168,21 +274,35 @@ where
                 .transpose()?
                 .unwrap_or(1.0);
 
-            // Normalize shorthand if no "type" key
-            if !obj.contains_key("type") {
-                normalize_shorthand(&mut obj).map_err(|e| {
-                    serde::de::Error::custom(format!("canvas.elements[{}]: {}", i, e))
-                })?;
-            }
+            let rotate: f32 = obj
+                .remove("rotate")
+                .map(|v| serde_json::from_value(v).map_err(serde::de::Error::custom))
+                .transpose()?
+                .unwrap_or(0.0);
+
+            let scale: [f32; 2] = obj
+                .remove("scale")
+                .map(|v| types::deserialize_scale(v).map_err(serde::de::Error::custom))
+                .transpose()?
+                .unwrap_or([1.0, 1.0]);
 
-            let component: Component = serde_json::from_value(serde_json::Value::Object(obj))
-                .map_err(|e| serde::de::Error::custom(format!("canvas.elements[{}]: {}", i, e)))?;
+            let clip: Option<Clip> = obj
+                .remove("clip")
+                .map(|v| serde_json::from_value(v).map_err(serde::de::Error::custom))
+                .transpose()?;
+
+            // The rest is the component, with shorthand and `if` / `unless`
+            let component = parse_component(serde_json::Value::Object(obj))
+                .map_err(|e| serde::de::Error::custom(format!("canvas.elements[{}]{}", i, e)))?;
 
             Ok(CanvasElement {
                 component,
                 position,
                 blend_mode,
                 opacity,
+                rotate,
+                scale,
+                clip,
             })
         })
         .collect()
@@ -194,6 +314,10 @@ where
 /// Construct it in Rust or deserialize it from JSON — the same type works for both.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Document {
+    /// Wire-format version (see [`compat`]). Older payloads are upgraded by
+    /// [`compat::parse_document`].
+    #[serde(default = "default_version")]
+    pub version: u32,
     /// The components that make up this document.
     ///
     /// Supports shorthand syntax: `{"text": "hi"}` instead of `{"type": "text", "content": "hi"}`.
@@ -202,8 +326,10 @@ pub struct Document {
     /// Whether to cut the paper after printing (default: true).
     #[serde(default = "default_true")]
     pub cut: bool,
-    /// User-defined variables for `{{template}}` interpolation.
-    #[serde(default)]
+    /// User-defined variables for `{{template}}` interpolation. JSON
+    /// values other than strings are kept as their JSON text, so arrays
+    /// can be given as-is for a [`Repeat`].
+    #[serde(default, deserialize_with = "deserialize_variables")]
     pub variables: HashMap<String, String>,
     /// Whether to interpolate `{{variables}}` in text content (default: true).
     #[serde(default = "default_true")]
@@ -213,16 +339,81 @@ pub struct Document {
     /// the result as a single raster image. Experimental.
     #[serde(default)]
     pub raster: bool,
+    /// Insert `Op::Marker` debug markers at component boundaries.
+    /// Markers emit no printer bytes; they show up in the IR disassembly
+    /// and as ticks in the preview margin.
+    #[serde(default)]
+    pub debug: bool,
+    /// Optimizer level: `"none"`, `"safe"`, or `"aggressive"` (default).
+    #[serde(default)]
+    pub optimize: OptimizeLevel,
+    /// Language for the built-in date variables (`"es"`, `"de"`, `"fr"`, ...).
+    /// Unsupported locales fall back to English.
+    #[serde(default)]
+    pub locale: Option<String>,
+    /// Where the printer is, for `{{sunrise}}` and `{{sunset}}` (see
+    /// [`Location`]). Those variables are left alone when unset.
+    #[serde(default)]
+    pub location: Option<Location>,
+    /// Station this copy is compiled for (e.g. `"kitchen"`). Station groups
+    /// for other stations are dropped and order tickets only list this
+    /// station's items. Everything prints when unset.
+    #[serde(default)]
+    pub station: Option<String>,
+    /// Print job this document is compiled for. The server assigns one to
+    /// every print; [`Document::compile`] makes one up when it's needed
+    /// for the stamp and unset.
+    #[serde(default)]
+    pub job_id: Option<JobId>,
+    /// Stamp the job ID in small type at the bottom (default: false), so
+    /// the receipt can be traced back to its job record.
+    #[serde(default)]
+    pub stamp_job_id: bool,
+    /// Compile as a gift receipt (default: false): prices are redacted as
+    /// each component's `gift` setting says (see
+    /// [`Document::as_gift_receipt`]).
+    #[serde(default)]
+    pub gift_receipt: bool,
+    /// Save paper (default: false): 3mm line feeds, fewer blank lines,
+    /// Font B for plain text and thinner dividers.
+    #[serde(default)]
+    pub compact: bool,
+    /// Print density from -3 (lightest) to 3 (darkest); the printer's
+    /// setting when unset. Lower it for large dark graphics, which
+    /// otherwise overheat the head and fade.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub density: Option<i8>,
+    /// Print speed: `"high"`, `"medium"` or `"low"` (darker); the
+    /// printer's setting when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub speed: Option<PrintSpeed>,
+    /// What to do with components that can't be printed (default:
+    /// `"fail"`); see [`OnError`] and [`Document::component_errors`].
+    #[serde(default)]
+    pub on_error: OnError,
 }
 
 impl Default for Document {
     fn default() -> Self {
         Self {
+            version: compat::CURRENT_VERSION,
             document: Vec::new(),
             cut: true,
             variables: HashMap::new(),
             interpolate: true,
             raster: false,
+            debug: false,
+            optimize: OptimizeLevel::default(),
+            locale: None,
+            location: None,
+            station: None,
+            job_id: None,
+            stamp_job_id: false,
+            gift_receipt: false,
+            compact: false,
+            density: None,
+            speed: None,
+            on_error: OnError::default(),
         }
     }
 }
@@ -242,9 +433,30 @@ impl Document {
     ///
     /// This performs template variable interpolation (if enabled),
     /// emits IR ops for each component, adds Init/Cut ops, and
-    /// runs the optimizer (word-wrapping, redundancy elimination, etc.).
+    /// runs the optimizer (word-wrapping, redundancy elimination, etc.)
+    /// at the document's [`OptimizeLevel`].
     pub fn compile(&self) -> Program {
+        self.compile_with_config(&PrinterConfig::TSP650II)
+    }
+
+    /// [`Document::compile`] for a specific printer: components lay out and
+    /// text wraps at the printer's print width (e.g. 384 dots on 58mm paper).
+    pub fn compile_with_config(&self, config: &PrinterConfig) -> Program {
+        let ctx = EmitContext::new(config);
         let mut doc = self.clone();
+        // Templates that fail to expand are left out; `expand_templates`
+        // reports why
+        doc.expand_templates().ok();
+        if let Some(station) = doc.station.take() {
+            doc.route(&station);
+        }
+        if doc.gift_receipt {
+            doc.redact_for_gift();
+        }
+        if doc.compact {
+            doc.compact_layout();
+        }
+        doc.isolate_errors();
 
         // Interpolate template variables
         if doc.interpolate {
@@ -255,9 +467,30 @@ impl Document {
         }
 
         let mut ops = vec![Op::Init, Op::SetCodepage(1)];
+        ops.extend(doc.head_settings());
+        if doc.compact {
+            ops.push(Op::SetLineSpacing(LineSpacing::Tight));
+        }
 
-        for component in &doc.document {
-            component.emit(&mut ops);
+        for (i, component) in doc.document.iter().enumerate() {
+            if doc.debug {
+                ops.push(Op::Marker(format!("[{}] {}", i, component.label())));
+            }
+            metrics::timed(Stage::Compile, component.label(), || {
+                component.emit(&ctx, &mut ops)
+            });
+        }
+
+        if doc.stamp_job_id {
+            let id = doc.job_id.unwrap_or_default();
+            ops.extend([
+                Op::PushStyle,
+                Op::SetFont(Font::B),
+                Op::SetAlign(Alignment::Center),
+                Op::Text(id.to_string()),
+                Op::Newline,
+                Op::PopStyle,
+            ]);
         }
 
         if doc.cut {
@@ -265,42 +498,156 @@ impl Document {
         }
 
         let program = Program { ops };
-        program.optimize()
+        program.optimize_with_config(doc.optimize, config)
     }
 
     /// Compile and generate StarPRNT bytes.
-    pub fn build(&self) -> Vec<u8> {
+    pub fn build(&self) -> Result<Vec<u8>, EstrellaError> {
         self.build_with_config(&PrinterConfig::TSP650II)
     }
 
     /// Compile and generate bytes with a specific printer config.
+    pub fn build_with_config(&self, config: &PrinterConfig) -> Result<Vec<u8>, EstrellaError> {
+        Ok(self
+            .print_program_with_config(config)?
+            .to_bytes_with_config(config))
+    }
+
+    /// Render the document to the 1-bit bitmap the printer would print.
+    ///
+    /// The raster is `config.width_dots` wide with no margins, trimmed below
+    /// the last inked row, packed MSB-first with 1 = black. It is the same
+    /// image that `raster` mode sends, so embedders can post-process it or
+    /// drive other devices (e-paper displays, label printers) with it.
+    pub fn to_raster(&self, config: &PrinterConfig) -> Result<RawRaster, PreviewError> {
+        render_raw_with_config(&self.compile_with_config(config), config)
+    }
+
+    /// Ops setting the print density and speed, when the document sets them.
+    fn head_settings(&self) -> impl Iterator<Item = Op> {
+        let density = self.density.map(Op::SetDensity);
+        let speed = self.speed.map(Op::SetSpeed);
+        density.into_iter().chain(speed)
+    }
+
+    /// Compile the program that is actually sent to the printer.
     ///
     /// When `raster` is true, renders the entire document through the bitmap
-    /// preview engine and sends it as a single raster image.
-    pub fn build_with_config(&self, config: &PrinterConfig) -> Vec<u8> {
-        if self.raster {
-            let program = self.compile();
-            let raw = crate::preview::render_raw(&program).expect("raster render failed");
-            let mut raster_program = Program::new();
-            raster_program.push(Op::Init);
+    /// preview engine and sends it as a single raster image. Transports can
+    /// stream the result with [`Program::write_to`].
+    ///
+    /// Fails only in `raster` mode, when the document can't be rendered
+    /// (e.g. a QR code with more data than fits).
+    pub fn print_program(&self) -> Result<Program, EstrellaError> {
+        self.print_program_with_config(&PrinterConfig::TSP650II)
+    }
+
+    /// [`Document::print_program`] for a specific printer; `raster` mode
+    /// renders at the printer's print width.
+    pub fn print_program_with_config(
+        &self,
+        config: &PrinterConfig,
+    ) -> Result<Program, EstrellaError> {
+        if !self.raster {
+            return Ok(self.compile_with_config(config));
+        }
+
+        let raw = self
+            .to_raster(config)
+            .map_err(|e| EstrellaError::Image(format!("Raster render failed: {}", e)))?;
+        let mut raster_program = Program::new();
+        raster_program.push(Op::Init);
+        raster_program.ops.extend(self.head_settings());
+        // Raster ops are at most u16::MAX rows tall
+        let row_bytes = raw.width.div_ceil(8).max(1);
+        let max_rows = u16::MAX as usize;
+        for (i, data) in raw.data.chunks(row_bytes * max_rows).enumerate() {
             raster_program.push(Op::Raster {
                 width: raw.width as u16,
-                height: raw.height as u16,
-                data: raw.data,
+                height: (raw.height - i * max_rows).min(max_rows) as u16,
+                data: data.to_vec(),
             });
-            if self.cut {
-                raster_program.push(Op::Feed { units: 24 });
-                raster_program.push(Op::Cut { partial: true });
+        }
+        if self.cut {
+            raster_program.push(Op::Feed { units: 24 });
+            raster_program.push(Op::Cut { partial: true });
+        }
+        Ok(raster_program)
+    }
+
+    /// Switch patterns and canvases to draft rendering for previews: only
+    /// every `row_step`-th row is computed and the rest repeat it.
+    ///
+    /// Used for live previews while tweaking layered compositions; a step of
+    /// 1 restores full quality.
+    pub fn set_preview_row_step(&mut self, row_step: usize) {
+        fn walk(components: &mut [Component], row_step: usize) {
+            for component in components {
+                match component {
+                    Component::Pattern(pattern) => pattern.row_step = row_step,
+                    Component::Composition(composition) => composition.row_step = row_step,
+                    Component::Canvas(canvas) => {
+                        canvas.row_step = row_step;
+                        for element in &mut canvas.elements {
+                            walk(std::slice::from_mut(&mut element.component), row_step);
+                        }
+                    }
+                    Component::LabelSheet(sheet) => walk(&mut sheet.label, row_step),
+                    Component::StationGroup(group) => walk(&mut group.components, row_step),
+                    Component::Conditional(c) => walk(&mut c.components, row_step),
+                    Component::Texture(texture) => walk(&mut texture.tile, row_step),
+                    _ => {}
+                }
             }
-            raster_program.to_bytes_with_config(config)
-        } else {
-            self.compile().to_bytes_with_config(config)
         }
+        walk(&mut self.document, row_step);
+    }
+
+    /// Fill in the dithering of images (`image`) and generative components
+    /// (`pattern`: patterns, textures, compositions) that don't choose one.
+    ///
+    /// Components with an explicit `dither` keep it. Used by the server to
+    /// apply its configured defaults.
+    pub fn set_default_dither(&mut self, image: DitheringAlgorithm, pattern: DitheringAlgorithm) {
+        fn fill(dither: &mut Option<String>, default: DitheringAlgorithm) {
+            dither.get_or_insert_with(|| default.as_str().to_string());
+        }
+        fn walk(
+            components: &mut [Component],
+            image: DitheringAlgorithm,
+            pattern: DitheringAlgorithm,
+        ) {
+            for component in components {
+                match component {
+                    Component::Image(c) => fill(&mut c.dither, image),
+                    Component::Pattern(c) => fill(&mut c.dither, pattern),
+                    Component::Composition(c) => fill(&mut c.dither, pattern),
+                    Component::Texture(c) => {
+                        fill(&mut c.dither, pattern);
+                        walk(&mut c.tile, image, pattern);
+                    }
+                    Component::Canvas(canvas) => {
+                        for element in &mut canvas.elements {
+                            walk(std::slice::from_mut(&mut element.component), image, pattern);
+                        }
+                    }
+                    Component::LabelSheet(sheet) => walk(&mut sheet.label, image, pattern),
+                    Component::StationGroup(group) => walk(&mut group.components, image, pattern),
+                    Component::Conditional(c) => walk(&mut c.components, image, pattern),
+                    _ => {}
+                }
+            }
+        }
+        walk(&mut self.document, image, pattern);
     }
 
     /// Build the merged variable map: built-in datetime helpers + user overrides.
     fn build_variable_map(&self) -> HashMap<String, String> {
-        let mut vars = builtin_variables();
+        let mut vars = builtin_variables(self.locale.as_deref());
+        if let Some(location) = &self.location {
+            let now = chrono::Local::now();
+            vars.extend(sun_variables(now.date_naive(), location, *now.offset()));
+        }
         // User variables override builtins
         vars.extend(self.variables.clone());
         vars
@@ -309,10 +656,11 @@ impl Document {
 
 /// Define the Component enum and all dispatch methods from a single list.
 ///
-/// Adding a new component: add one line here, then define the struct in
-/// `types.rs` with `impl ComponentMeta`. That's it.
+/// Adding a new component: add one line here (serde attributes such as an
+/// `alias` go above it), then define the struct in `types.rs` with
+/// `impl ComponentMeta`. That's it.
 macro_rules! define_components {
-    ($($variant:ident($inner:ty)),+ $(,)?) => {
+    ($($(#[$attr:meta])* $variant:ident($inner:ty)),+ $(,)?) => {
         /// The unified component enum.
         ///
         /// Each variant corresponds to a document component type. The `#[serde(tag = "type")]`
@@ -320,13 +668,13 @@ macro_rules! define_components {
         #[derive(Debug, Clone, Serialize, Deserialize)]
         #[serde(tag = "type", rename_all = "snake_case")]
         pub enum Component {
-            $($variant($inner),)+
+            $($(#[$attr])* $variant($inner),)+
         }
 
         impl Component {
             /// Emit IR ops for this component.
-            pub fn emit(&self, ops: &mut Vec<Op>) {
-                match self { $(Component::$variant(c) => c.emit(ops),)+ }
+            pub fn emit(&self, ctx: &EmitContext, ops: &mut Vec<Op>) {
+                match self { $(Component::$variant(c) => c.emit(ctx, ops),)+ }
             }
 
             /// Interpolate template variables in this component's text fields.
@@ -360,8 +708,12 @@ define_components! {
     Spacer(Spacer),
     BlankLine(BlankLine),
     Columns(Columns),
+    Definitions(Definitions),
+    DataDump(DataDump),
+    ProgressBar(ProgressBar),
     Table(Table),
     Markdown(Markdown),
+    Html(Html),
     QrCode(QrCode),
     Pdf417(Pdf417),
     Barcode(Barcode),
@@ -369,14 +721,33 @@ define_components! {
     Pattern(Pattern),
     NvLogo(NvLogo),
     Chart(Chart),
+    Gauge(Gauge),
     Canvas(Canvas),
+    TextMask(TextMask),
+    LabelSheet(LabelSheet),
+    OrderTicket(OrderTicket),
+    StationGroup(StationGroup),
+    Conditional(Conditional),
+    #[serde(alias = "for_each")]
+    Repeat(Repeat),
+    Texture(Texture),
+    #[serde(alias = "pattern_stack")]
+    Composition(Composition),
 }
 
 /// Generate built-in datetime template variables.
-fn builtin_variables() -> HashMap<String, String> {
-    use chrono::Local;
+///
+/// Names follow `locale` (English by default); every supported locale is
+/// also available as `{{date@es}}`, `{{day@de}}`, etc.
+fn builtin_variables(locale: Option<&str>) -> HashMap<String, String> {
+    builtin_variables_at(&chrono::Local::now().naive_local(), locale)
+}
 
-    let now = Local::now();
+/// Built-in datetime variables for a fixed point in time.
+pub(crate) fn builtin_variables_at(
+    now: &chrono::NaiveDateTime,
+    locale: Option<&str>,
+) -> HashMap<String, String> {
     let mut vars = HashMap::new();
 
     vars.insert("date".into(), now.format("%B %-d, %Y").to_string()); // January 27, 2026
@@ -390,6 +761,20 @@ fn builtin_variables() -> HashMap<String, String> {
     ); // Mon, Jan 27 09:30
     vars.insert("year".into(), now.format("%Y").to_string()); // 2026
     vars.insert("iso_date".into(), now.format("%Y-%m-%d").to_string()); // 2026-01-27
+    vars.insert("moon_phase".into(), astro::moon_phase(now).to_string()); // Waxing Gibbous
+
+    for code in locale::supported_locales() {
+        for (name, value) in locale::date_variables(now, code).unwrap_or_default() {
+            vars.insert(format!("{}@{}", name, code), value);
+        }
+    }
+    // `{{greeting}}` only comes from the locale tables, so English fills in
+    let localized = locale
+        .and_then(|code| locale::date_variables(now, code))
+        .or_else(|| locale::date_variables(now, "en"));
+    for (name, value) in localized.unwrap_or_default() {
+        vars.insert(name.into(), value);
+    }
 
     vars
 }
@@ -404,10 +789,10 @@ pub struct ComponentTypeMeta {
 
 /// Extract the serde type tag from a Component (the `"type"` field).
 fn serde_type_name(comp: &Component) -> String {
-    serde_json::to_value(comp).unwrap()["type"]
-        .as_str()
-        .unwrap()
-        .to_string()
+    serde_json::to_value(comp)
+        .ok()
+        .and_then(|value| value["type"].as_str().map(str::to_string))
+        .unwrap_or_default()
 }
 
 /// Component type metadata for the frontend.
@@ -435,6 +820,48 @@ pub fn default_component(type_name: &str) -> Option<Component> {
         .find(|c| serde_type_name(c) == type_name)
 }
 
+/// Fixtures and assertions shared by the component tests.
+#[cfg(test)]
+pub(crate) mod testing {
+    use super::{Component, Document};
+    use crate::ir::Op;
+
+    /// Parse a document fixture, e.g. `fixture(json!({"document": [...]}))`.
+    pub fn fixture(json: serde_json::Value) -> Document {
+        serde_json::from_value(json).unwrap()
+    }
+
+    /// Every `Op::Text` in order.
+    pub fn texts(ops: &[Op]) -> Vec<&str> {
+        ops.iter()
+            .filter_map(|op| match op {
+                Op::Text(t) => Some(t.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// The text a document prints, one entry per text op, with the padding
+    /// that fills lines to the paper width trimmed.
+    pub fn compiled_texts(doc: &Document) -> Vec<String> {
+        texts(&doc.compile().ops)
+            .into_iter()
+            .map(|t| t.trim_end().to_string())
+            .collect()
+    }
+
+    /// The content of every text component in order.
+    pub fn text_contents(components: &[Component]) -> Vec<&str> {
+        components
+            .iter()
+            .filter_map(|component| match component {
+                Component::Text(text) => Some(text.content.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -444,10 +871,109 @@ mod tests {
         let json = r#"{"document": [{"type": "text", "content": "hi"}]}"#;
         let doc: Document = serde_json::from_str(json).unwrap();
         assert!(doc.cut);
-        let bytes = doc.build();
+        let bytes = doc.build().unwrap();
         assert!(!bytes.is_empty());
     }
 
+    #[test]
+    fn test_to_raster_matches_raster_mode() {
+        let mut doc = Document::new();
+        doc.push(Component::Text(Text::new("RASTER")));
+        let raw = doc.to_raster(&PrinterConfig::TSP650II).unwrap();
+        assert_eq!(raw.width, 576);
+        assert!(raw.data.iter().any(|&b| b != 0));
+
+        doc.raster = true;
+        let sent = doc
+            .print_program()
+            .unwrap()
+            .ops
+            .into_iter()
+            .find_map(|op| match op {
+                Op::Raster { data, .. } => Some(data),
+                _ => None,
+            });
+        assert_eq!(sent, Some(raw.data));
+
+        let narrow = PrinterConfig {
+            width_dots: 384,
+            width_bytes: 48,
+            ..PrinterConfig::TSP650II
+        };
+        assert_eq!(doc.to_raster(&narrow).unwrap().width, 384);
+    }
+
+    #[test]
+    fn test_raster_render_failure_is_an_error() {
+        let doc: Document = serde_json::from_str(&format!(
+            r#"{{"raster": true, "document": [{{"qr_code": "{}"}}]}}"#,
+            "x".repeat(8000)
+        ))
+        .unwrap();
+        let err = doc.print_program().unwrap_err();
+        assert!(err.to_string().contains("Raster render failed"));
+    }
+
+    #[test]
+    fn test_multibyte_content_is_truncated_by_character() {
+        let doc: Document = serde_json::from_str(
+            r#"{"document": [
+                {"type": "banner", "content": "xÉÉÉÉÉÉÉÉÉÉÉÉÉÉÉÉÉÉÉÉÉÉÉÉÉÉÉÉÉÉÉÉÉÉÉÉÉÉÉÉÉÉÉÉÉÉÉÉÉÉÉÉÉÉÉÉÉÉÉÉÉÉÉÉÉÉÉÉÉ"},
+                {"type": "line_item", "name": "Crème brûlée au café", "price": 4.5, "width": 15}
+            ]}"#,
+        )
+        .unwrap();
+        assert!(doc.build().is_ok());
+    }
+
+    #[test]
+    fn test_set_default_dither_keeps_explicit_choices() {
+        let json = r#"{"document": [
+            {"type": "image", "url": "a.png"},
+            {"type": "pattern", "name": "ripple", "dither": "jarvis"},
+            {"type": "canvas", "elements": [{"type": "pattern", "name": "waves"}]}
+        ]}"#;
+        let mut doc: Document = serde_json::from_str(json).unwrap();
+        doc.set_default_dither(DitheringAlgorithm::Atkinson, DitheringAlgorithm::Bayer);
+
+        let dithers: Vec<Option<&str>> = [&doc.document[0], &doc.document[1]]
+            .into_iter()
+            .chain(match &doc.document[2] {
+                Component::Canvas(canvas) => vec![&canvas.elements[0].component],
+                _ => vec![],
+            })
+            .map(|component| match component {
+                Component::Image(image) => image.dither.as_deref(),
+                Component::Pattern(pattern) => pattern.dither.as_deref(),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(dithers, [Some("atkinson"), Some("jarvis"), Some("bayer")]);
+    }
+
+    #[test]
+    fn test_density_and_speed() {
+        let json = r#"{"document": [{"text": "a"}], "density": -2, "speed": "low"}"#;
+        let doc: Document = serde_json::from_str(json).unwrap();
+        let ops = doc.compile().ops;
+        assert_eq!(ops[2], Op::SetDensity(-2));
+        assert_eq!(ops[3], Op::SetSpeed(PrintSpeed::Low));
+
+        let raster = Document {
+            raster: true,
+            ..doc
+        };
+        let ops = raster.print_program().unwrap().ops;
+        assert_eq!(ops[..2], [Op::Init, Op::SetDensity(-2)]);
+        assert!(
+            !Document::default()
+                .compile()
+                .ops
+                .iter()
+                .any(|op| matches!(op, Op::SetDensity(_)))
+        );
+    }
+
     #[test]
     fn test_cut_false() {
         let json = r#"{"document": [{"type": "text", "content": "hi"}], "cut": false}"#;
@@ -457,6 +983,36 @@ mod tests {
         assert!(!ir.ops.iter().any(|op| matches!(op, Op::Cut { .. })));
     }
 
+    #[test]
+    fn test_job_id_stamp() {
+        let json = r#"{"document": [{"text": "hi"}], "stamp_job_id": true,
+            "job_id": "01HF7YAT00000000000000001A"}"#;
+        let doc: Document = serde_json::from_str(json).unwrap();
+        let ops = doc.compile().ops;
+        let stamp = ops
+            .iter()
+            .position(|op| *op == Op::Text("01HF7YAT00000000000000001A".into()))
+            .expect("stamp printed");
+        let cut = ops
+            .iter()
+            .position(|op| matches!(op, Op::Cut { .. }))
+            .unwrap();
+        assert!(stamp < cut);
+        assert!(ops[..stamp].contains(&Op::SetFont(Font::B)));
+
+        let unstamped = Document {
+            stamp_job_id: false,
+            ..doc
+        };
+        assert!(
+            !unstamped
+                .compile()
+                .ops
+                .iter()
+                .any(|op| matches!(op, Op::Text(t) if t.starts_with("01HF")))
+        );
+    }
+
     #[test]
     fn test_text_bold_center() {
         let json =
@@ -601,6 +1157,33 @@ mod tests {
         assert!(has_dashes);
     }
 
+    #[test]
+    fn test_compile_for_58mm_printer() {
+        let json = r#"{"document": [
+            {"type": "divider"},
+            {"text": "the quick brown fox jumps over the lazy dog"}
+        ]}"#;
+        let doc: Document = serde_json::from_str(json).unwrap();
+        let texts = |ir: Program| -> Vec<String> {
+            ir.ops
+                .into_iter()
+                .filter_map(|op| match op {
+                    Op::Text(s) => Some(s),
+                    _ => None,
+                })
+                .collect()
+        };
+
+        let wide = texts(doc.compile());
+        assert_eq!(wide[0].chars().count(), 48);
+        assert_eq!(wide.len(), 2);
+
+        let narrow = texts(doc.compile_with_config(&PrinterConfig::SM_L200));
+        assert_eq!(narrow[0].chars().count(), 32);
+        assert!(narrow.len() > 2);
+        assert!(narrow[1..].iter().all(|s| s.chars().count() <= 32));
+    }
+
     #[test]
     fn test_spacer_mm() {
         let json = r#"{"document": [{"type": "spacer", "mm": 5.0}]}"#;
@@ -617,6 +1200,41 @@ mod tests {
         assert!(ir.ops.iter().any(|op| matches!(op, Op::QrCode { .. })));
     }
 
+    #[test]
+    fn test_debug_markers() {
+        let json = r#"{"document": [{"text": "a", "bold": true}, {"text": "b", "bold": true}]}"#;
+        let mut doc: Document = serde_json::from_str(json).unwrap();
+        let plain = doc.build().unwrap();
+
+        doc.debug = true;
+        let ir = doc.compile();
+        let markers: Vec<&Op> = ir
+            .ops
+            .iter()
+            .filter(|op| matches!(op, Op::Marker(_)))
+            .collect();
+        assert_eq!(
+            markers,
+            vec![
+                &Op::Marker("[0] Text".into()),
+                &Op::Marker("[1] Text".into())
+            ]
+        );
+        // Markers never change the printed bytes
+        assert_eq!(doc.build().unwrap(), plain);
+    }
+
+    #[test]
+    fn test_optimize_none_keeps_ops() {
+        let json = r#"{"document": [{"text": "a"}], "optimize": "none", "cut": false}"#;
+        let doc: Document = serde_json::from_str(json).unwrap();
+        assert_eq!(doc.optimize, OptimizeLevel::None);
+        let mut ops = vec![Op::Init, Op::SetCodepage(1)];
+        doc.document[0].emit(&EmitContext::default(), &mut ops);
+        assert_eq!(doc.compile().ops, ops);
+    }
+
+    #[cfg(feature = "patterns")]
     #[test]
     fn test_pattern() {
         let json = r#"{"document": [{"type": "pattern", "name": "ripple", "height": 100}]}"#;
@@ -624,7 +1242,7 @@ mod tests {
         let ir = doc.compile();
         assert!(ir.ops.iter().any(|op| matches!(
             op,
-            Op::Raster {
+            Op::GrayRaster {
                 width: 576,
                 height: 100,
                 ..
@@ -632,6 +1250,32 @@ mod tests {
         )));
     }
 
+    #[cfg(feature = "patterns")]
+    #[test]
+    fn test_mm_sizes_follow_printer_dpi() {
+        let json = r#"{"document": [
+            {"type": "pattern", "name": "ripple", "height": 100, "height_mm": 10}
+        ]}"#;
+        let doc: Document = serde_json::from_str(json).unwrap();
+        let size = |config: &PrinterConfig| {
+            doc.compile_with_config(config)
+                .ops
+                .into_iter()
+                .find_map(|op| match op {
+                    Op::GrayRaster { width, height, .. } => Some((width, height)),
+                    _ => None,
+                })
+                .unwrap()
+        };
+        assert_eq!(size(&PrinterConfig::TSP650II), (576, 80));
+        assert_eq!(size(&PrinterConfig::PAPER_58MM), (384, 80));
+        let fine = PrinterConfig {
+            dpi: 300,
+            ..PrinterConfig::TSP650II
+        };
+        assert_eq!(size(&fine).1, 118);
+    }
+
     #[test]
     fn test_all_component_types() {
         let json = r#"{
@@ -770,7 +1414,7 @@ mod tests {
 
     #[test]
     fn test_builtin_variables() {
-        let vars = builtin_variables();
+        let vars = builtin_variables(None);
         assert!(vars.contains_key("date"));
         assert!(vars.contains_key("day"));
         assert!(vars.contains_key("time"));
@@ -779,6 +1423,50 @@ mod tests {
         assert!(vars.contains_key("datetime"));
         assert!(vars.contains_key("date_short"));
         assert!(vars.contains_key("time_12h"));
+        assert!(vars.contains_key("greeting"));
+        assert!(vars.contains_key("moon_phase"));
+        assert!(!vars.contains_key("sunrise"));
+    }
+
+    #[test]
+    fn test_sun_variables_need_location() {
+        let json = r#"{
+            "location": "52.52,13.40",
+            "document": [{"text": "{{greeting}}! Sunrise {{sunrise}}, sunset {{sunset}}"}]
+        }"#;
+        let doc: Document = serde_json::from_str(json).unwrap();
+        let text = doc
+            .compile()
+            .ops
+            .into_iter()
+            .find_map(|op| match op {
+                Op::Text(s) if s.contains("Sunrise") => Some(s),
+                _ => None,
+            })
+            .unwrap();
+        assert!(text.starts_with("Good "), "{}", text);
+        assert!(!text.contains("{{"), "{}", text);
+    }
+
+    #[test]
+    fn test_builtin_variables_locale() {
+        let vars = builtin_variables(Some("es"));
+        let spanish_days = [
+            "lunes",
+            "martes",
+            "miércoles",
+            "jueves",
+            "viernes",
+            "sábado",
+            "domingo",
+        ];
+        assert!(spanish_days.contains(&vars["day"].as_str()));
+        assert_eq!(vars["day"], vars["day@es"]);
+        assert!(vars.contains_key("date@de"));
+
+        // Unknown locales keep the English defaults
+        let vars = builtin_variables(Some("xx"));
+        assert_eq!(vars["day"], vars["day@en"]);
     }
 
     #[test]
@@ -834,7 +1522,7 @@ mod tests {
             cut: true,
             ..Default::default()
         };
-        let bytes = doc.build();
+        let bytes = doc.build().unwrap();
         assert!(!bytes.is_empty());
     }
 
@@ -972,6 +1660,42 @@ mod tests {
         assert!(ir.ops.iter().any(|op| matches!(op, Op::SetBold(true))));
     }
 
+    #[cfg(feature = "patterns")]
+    #[test]
+    fn test_pattern_stack_is_a_composition() {
+        let layers = r#"[
+            {"name": "ripple", "params": {"scale": "12"}},
+            {"name": "crosshatch", "blend_mode": "multiply", "opacity": 0.5}
+        ]"#;
+        for json in [
+            format!(
+                r#"{{"type": "pattern_stack", "height": 40, "layers": {}}}"#,
+                layers
+            ),
+            format!(r#"{{"pattern_stack": {}, "height": 40}}"#, layers),
+        ] {
+            let doc: Document =
+                serde_json::from_str(&format!(r#"{{"document": [{}]}}"#, json)).unwrap();
+            let Component::Composition(stack) = &doc.document[0] else {
+                panic!("expected a composition, got {:?}", doc.document[0]);
+            };
+            assert_eq!(stack.layers[1].pattern, "crosshatch");
+            assert_eq!(
+                stack.layers[1].blend,
+                crate::render::composer::BlendMode::Multiply
+            );
+            // Saved documents replay as a composition
+            let saved = serde_json::to_value(&doc).unwrap();
+            assert_eq!(saved["document"][0]["type"], "composition");
+            assert!(
+                doc.compile()
+                    .ops
+                    .iter()
+                    .any(|op| matches!(op, Op::GrayRaster { height: 40, .. }))
+            );
+        }
+    }
+
     #[test]
     fn test_shorthand_ignored_when_type_present() {
         // "type" takes precedence; "text" key is just an unknown field (ignored by serde)
@@ -0,0 +1,206 @@
+//! PDF pages as documents.
+//!
+//! [`documents`] renders each page of a PDF with PDFium and turns it into a
+//! document holding one image, scaled to the print width and dithered like
+//! any other `image` component. Blank margins are trimmed first, so a
+//! receipt laid out on an A4 page fills the paper instead of printing as a
+//! narrow strip; blank pages are skipped.
+//!
+//! PDFium is loaded at runtime: from the directory in `$PDFIUM_LIB_PATH`,
+//! next to the executable, or from the system library path.
+//!
+//! ```no_run
+//! use estrella::PrinterConfig;
+//! use estrella::document::{batch, pdf};
+//!
+//! let data = std::fs::read("receipt.pdf")?;
+//! let docs = pdf::documents(&data, &pdf::PdfOptions::default(), &PrinterConfig::TSP650II)?;
+//! let program = batch::batch_program_with_cuts(
+//!     &docs,
+//!     batch::CutPolicy::default(),
+//!     &PrinterConfig::TSP650II,
+//! )?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::path::{Path, PathBuf};
+
+use image::{DynamicImage, GrayImage};
+use pdfium_render::prelude::{PdfColor, PdfRenderConfig, Pdfium, PdfiumLibraryBindings};
+
+use super::types::Image;
+use super::{Component, Document};
+use crate::EstrellaError;
+use crate::printer::PrinterConfig;
+
+/// Environment variable naming the directory that holds the PDFium library.
+pub const LIBRARY_PATH_VAR: &str = "PDFIUM_LIB_PATH";
+
+/// Pages render this many times wider than the print width, so trimmed
+/// pages still have detail to scale down from.
+const OVERSAMPLE: usize = 2;
+
+/// Pixels lighter than this count as paper when trimming.
+const BLANK_LUMA: u8 = 245;
+
+/// How PDF pages are printed.
+#[derive(Debug, Clone, Default)]
+pub struct PdfOptions {
+    /// Dithering algorithm (see [`Image::dither`]); the image default when `None`.
+    pub dither: Option<String>,
+    /// Print whole pages, margins included, instead of trimming them.
+    pub keep_margins: bool,
+    /// Password of an encrypted PDF.
+    pub password: Option<String>,
+}
+
+/// One document per printable page of a PDF.
+///
+/// Fails when PDFium can't be loaded, the PDF can't be read, or every page
+/// is blank.
+pub fn documents(
+    data: &[u8],
+    options: &PdfOptions,
+    printer: &PrinterConfig,
+) -> Result<Vec<Document>, EstrellaError> {
+    let width = printer.width_dots as usize * OVERSAMPLE;
+    let docs: Vec<Document> = render_pages(data, width, options.password.as_deref())?
+        .into_iter()
+        .filter_map(|page| trim(page, options.keep_margins))
+        .map(|page| {
+            let mut image = Image {
+                dither: options.dither.clone(),
+                ..Image::default()
+            };
+            image.resolve_source(page, printer);
+            Document {
+                document: vec![Component::Image(image)],
+                ..Document::default()
+            }
+        })
+        .collect();
+    if docs.is_empty() {
+        return Err(EstrellaError::Image("PDF has no printable pages".into()));
+    }
+    Ok(docs)
+}
+
+/// Render every page of a PDF `width` pixels wide, on white.
+pub fn render_pages(
+    data: &[u8],
+    width: usize,
+    password: Option<&str>,
+) -> Result<Vec<DynamicImage>, EstrellaError> {
+    let pdfium = Pdfium::new(bind()?);
+    let document = pdfium
+        .load_pdf_from_byte_slice(data, password)
+        .map_err(|e| EstrellaError::Image(format!("Failed to open PDF: {}", e)))?;
+    let config = PdfRenderConfig::new()
+        .set_target_width(width as i32)
+        .set_clear_color(PdfColor::WHITE)
+        .render_form_data(true);
+    document
+        .pages()
+        .iter()
+        .enumerate()
+        .map(|(i, page)| {
+            page.render_with_config(&config)
+                .map(|bitmap| bitmap.as_image())
+                .map_err(|e| {
+                    EstrellaError::Image(format!("Failed to render page {}: {}", i + 1, e))
+                })
+        })
+        .collect()
+}
+
+/// Load PDFium from `$PDFIUM_LIB_PATH`, the executable's directory or the
+/// system, in that order.
+fn bind() -> Result<Box<dyn PdfiumLibraryBindings>, EstrellaError> {
+    let dirs = std::env::var_os(LIBRARY_PATH_VAR)
+        .map(PathBuf::from)
+        .into_iter()
+        .chain(
+            std::env::current_exe()
+                .ok()
+                .and_then(|exe| exe.parent().map(Path::to_path_buf)),
+        );
+    for dir in dirs {
+        let path = Pdfium::pdfium_platform_library_name_at_path(&dir);
+        if let Ok(bindings) = Pdfium::bind_to_library(path) {
+            return Ok(bindings);
+        }
+    }
+    Pdfium::bind_to_system_library().map_err(|e| {
+        EstrellaError::Image(format!(
+            "PDFium library not found ({}); set {} to the directory holding {}",
+            e,
+            LIBRARY_PATH_VAR,
+            Pdfium::pdfium_platform_library_name().to_string_lossy()
+        ))
+    })
+}
+
+/// Crop a page to its inked area (unless `keep_margins`), or `None` when
+/// it is blank.
+fn trim(page: DynamicImage, keep_margins: bool) -> Option<DynamicImage> {
+    let (left, top, right, bottom) = inked_bounds(&page.to_luma8())?;
+    if keep_margins {
+        return Some(page);
+    }
+    Some(page.crop_imm(left, top, right - left + 1, bottom - top + 1))
+}
+
+/// Inclusive bounds (left, top, right, bottom) of the non-blank pixels.
+fn inked_bounds(gray: &GrayImage) -> Option<(u32, u32, u32, u32)> {
+    let mut bounds: Option<(u32, u32, u32, u32)> = None;
+    for (x, y, pixel) in gray.enumerate_pixels() {
+        if pixel.0[0] >= BLANK_LUMA {
+            continue;
+        }
+        bounds = Some(match bounds {
+            None => (x, y, x, y),
+            Some((l, t, r, b)) => (l.min(x), t.min(y), r.max(x), b.max(y)),
+        });
+    }
+    bounds
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Luma;
+
+    #[test]
+    fn test_trim_crops_to_ink() {
+        let mut page = GrayImage::from_pixel(100, 80, Luma([255]));
+        page.put_pixel(10, 20, Luma([0]));
+        page.put_pixel(59, 39, Luma([120]));
+        // Anti-aliasing fringe too light to count
+        page.put_pixel(90, 70, Luma([250]));
+
+        let page = DynamicImage::ImageLuma8(page);
+        let trimmed = trim(page.clone(), false).unwrap();
+        assert_eq!((trimmed.width(), trimmed.height()), (50, 20));
+        let kept = trim(page, true).unwrap();
+        assert_eq!((kept.width(), kept.height()), (100, 80));
+    }
+
+    #[test]
+    fn test_blank_page_is_skipped() {
+        let page = GrayImage::from_pixel(100, 80, Luma([255]));
+        let page = DynamicImage::ImageLuma8(page);
+        assert!(trim(page.clone(), false).is_none());
+        assert!(trim(page, true).is_none());
+    }
+
+    #[test]
+    fn test_unreadable_pdf_errors() {
+        // Fails on the library or the data, but never panics
+        let result = documents(
+            b"not a pdf",
+            &PdfOptions::default(),
+            &PrinterConfig::TSP650II,
+        );
+        assert!(matches!(result, Err(EstrellaError::Image(_))));
+    }
+}
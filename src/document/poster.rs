@@ -0,0 +1,190 @@
+//! Posters: a pattern, a title and the details, laid out in one go.
+//!
+//! [`Poster::document`] builds a finished design that needs no JSON:
+//!
+//! 1. a band of the chosen pattern across the top,
+//! 2. the title in IBM Plex Sans Bold, grown to span the paper,
+//! 3. the subtitle and detail lines, centered,
+//! 4. an optional QR code footer with its URL underneath.
+//!
+//! ```
+//! use estrella::document::poster::Poster;
+//!
+//! let poster = Poster {
+//!     subtitle: Some("Sat 9PM".into()),
+//!     qr: Some("https://example.com/party".into()),
+//!     ..Poster::new("riley", "PARTY")
+//! };
+//! let doc = poster.document();
+//! assert!(doc.document.len() > 4);
+//! ```
+
+use std::collections::HashMap;
+
+use super::{Component, Divider, DividerStyle, Document, Pattern, QrCode, Spacer, Text, TextMask};
+
+/// Height of the pattern band when none is given.
+pub const DEFAULT_BAND_MM: f32 = 40.0;
+
+/// A poster design.
+#[derive(Debug, Clone)]
+pub struct Poster {
+    /// Pattern printed in the header band.
+    pub pattern: String,
+    /// Parameters for the pattern (see `estrella print PATTERN --list-params`).
+    pub params: HashMap<String, String>,
+    /// Seed for the pattern's randomness, see [`Pattern::seed`].
+    pub seed: Option<u64>,
+    /// Height of the pattern band in mm.
+    pub band_mm: f32,
+    /// Headline, printed as large as the paper allows.
+    pub title: String,
+    pub subtitle: Option<String>,
+    /// Lines under the subtitle: time, place, price...
+    pub details: Vec<String>,
+    /// URL (or any text) for a QR code footer.
+    pub qr: Option<String>,
+}
+
+impl Default for Poster {
+    fn default() -> Self {
+        Self {
+            pattern: "riley".into(),
+            params: HashMap::new(),
+            seed: None,
+            band_mm: DEFAULT_BAND_MM,
+            title: String::new(),
+            subtitle: None,
+            details: Vec::new(),
+            qr: None,
+        }
+    }
+}
+
+impl Poster {
+    pub fn new(pattern: impl Into<String>, title: impl Into<String>) -> Self {
+        Self {
+            pattern: pattern.into(),
+            title: title.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Lay the poster out as a document.
+    pub fn document(&self) -> Document {
+        let mut doc = Document::new();
+        doc.push(Component::Pattern(Pattern {
+            name: self.pattern.clone(),
+            height_mm: Some(self.band_mm),
+            params: self.params.clone(),
+            seed: self.seed,
+            ..Default::default()
+        }));
+        doc.push(Component::Spacer(Spacer::mm(4.0)));
+
+        if !self.title.trim().is_empty() {
+            doc.push(Component::TextMask(TextMask {
+                content: self.title.clone(),
+                fit: true,
+                center: true,
+                ..Default::default()
+            }));
+            doc.push(Component::Spacer(Spacer::mm(3.0)));
+        }
+        if let Some(subtitle) = &self.subtitle {
+            doc.push(Component::Text(Text {
+                content: subtitle.clone(),
+                center: true,
+                bold: true,
+                size: [2, 2],
+                font: Some("ibm".into()),
+                ..Default::default()
+            }));
+        }
+        for detail in &self.details {
+            doc.push(Component::Text(Text {
+                content: detail.clone(),
+                center: true,
+                ..Default::default()
+            }));
+        }
+
+        if let Some(qr) = &self.qr {
+            doc.push(Component::Spacer(Spacer::mm(4.0)));
+            doc.push(Component::Divider(Divider {
+                style: DividerStyle::Dashed,
+                ..Default::default()
+            }));
+            doc.push(Component::Spacer(Spacer::mm(2.0)));
+            doc.push(Component::QrCode(QrCode::new(qr.clone())));
+            doc.push(Component::Text(Text {
+                content: qr.clone(),
+                center: true,
+                size: [0, 0],
+                ..Default::default()
+            }));
+        }
+        doc.push(Component::Spacer(Spacer::mm(4.0)));
+        // Titles and URLs are printed as given
+        doc.interpolate = false;
+        doc
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "patterns")]
+    use crate::ir::Op;
+
+    #[cfg(feature = "patterns")]
+    #[test]
+    fn test_poster_layout() {
+        let poster = Poster {
+            subtitle: Some("Sat 9PM".into()),
+            details: vec!["Rooftop".into(), "Bring {{snacks}}".into()],
+            qr: Some("https://example.com/p".into()),
+            band_mm: 20.0,
+            ..Poster::new("waves", "PARTY")
+        };
+        let doc = poster.document();
+        let labels: Vec<_> = doc.document.iter().map(|c| c.label()).collect();
+        assert_eq!(
+            labels,
+            [
+                "Pattern",
+                "Spacer",
+                "Text Mask",
+                "Spacer",
+                "Text",
+                "Text",
+                "Text",
+                "Spacer",
+                "Divider",
+                "Spacer",
+                "QR Code",
+                "Text",
+                "Spacer",
+            ]
+        );
+
+        let ops = doc.compile().ops;
+        // 20mm of pattern at 8 dots per mm
+        assert!(
+            ops.iter()
+                .any(|op| matches!(op, Op::GrayRaster { height: 160, .. }))
+        );
+        assert!(ops.contains(&Op::Text("Bring {{snacks}}".into())));
+        assert!(
+            ops.iter()
+                .any(|op| matches!(op, Op::QrCode { data, .. } if data == "https://example.com/p"))
+        );
+    }
+
+    #[test]
+    fn test_minimal_poster() {
+        let doc = Poster::new("ripple", "").document();
+        let labels: Vec<_> = doc.document.iter().map(|c| c.label()).collect();
+        assert_eq!(labels, ["Pattern", "Spacer", "Spacer"]);
+    }
+}
@@ -2,43 +2,101 @@
 //!
 //! `ImageResolver` handles all image fetching concerns so that `Document`
 //! stays a pure data model with no HTTP or caching knowledge.
+//!
+//! Besides `http(s)://` URLs, an image's `url` can be:
+//!
+//! - a `data:image/png;base64,...` URI, up to [`ImageSources::max_bytes`]
+//! - a `file:///path/to/logo.png` path inside one of
+//!   [`ImageSources::allowed_dirs`]; local files are refused when no
+//!   directory is allowed, so a server never reads files it wasn't told to
+//!
+//! Photos are turned upright from their EXIF orientation before scaling.
 
 use std::collections::HashMap;
+use std::path::{self, Path, PathBuf};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
 use image::{DynamicImage, imageops::FilterType};
 
-use super::graphics::parse_dither_algorithm;
-use super::types::ResolvedImage;
-use super::{Component, Document};
+use super::types::{Image, ImageFit, ResolvedImage};
+use super::{Component, Document, EmitContext, OnError};
 use crate::EstrellaError;
+use crate::printer::PrinterConfig;
+use crate::render::context::PhotoSession;
 use crate::render::context::RenderContext;
-use crate::render::dither::{self, DitheringAlgorithm};
-use crate::server::PhotoSession;
+use crate::render::{adjust, alpha, exif};
+
+/// Largest local file or data URI accepted by default: 10 MiB.
+pub const DEFAULT_MAX_IMAGE_BYTES: usize = 10 * 1024 * 1024;
+
+/// Where images may come from besides HTTP(S) URLs.
+#[derive(Debug, Clone)]
+pub struct ImageSources {
+    /// Directories (and their subdirectories) `file://` images may be read
+    /// from. Local files are refused when empty.
+    pub allowed_dirs: Vec<PathBuf>,
+    /// Largest local file or decoded `data:` URI, in bytes.
+    pub max_bytes: usize,
+}
+
+impl Default for ImageSources {
+    fn default() -> Self {
+        Self {
+            allowed_dirs: Vec::new(),
+            max_bytes: DEFAULT_MAX_IMAGE_BYTES,
+        }
+    }
+}
 
 /// Resolves external resources (images) in a document.
 ///
 /// Downloads images from URLs, caches them in the shared photo session store,
-/// and processes them into raster data ready for printing.
+/// and processes them into raster data ready for printing. Local files and
+/// data URIs are read as [`ImageSources`] allows and not cached.
 pub struct ImageResolver {
     sessions: Arc<RwLock<HashMap<String, PhotoSession>>>,
+    /// Images without a `width` are scaled to this printer's print width.
+    printer: PrinterConfig,
+    sources: ImageSources,
 }
 
 impl ImageResolver {
-    /// Create a resolver backed by a shared session cache.
+    /// Create a resolver backed by a shared session cache, sizing images
+    /// for the TSP650II.
     pub fn new(sessions: Arc<RwLock<HashMap<String, PhotoSession>>>) -> Self {
-        Self { sessions }
+        Self {
+            sessions,
+            printer: PrinterConfig::TSP650II,
+            sources: ImageSources::default(),
+        }
+    }
+
+    /// Size images without a `width` for `printer` instead.
+    pub fn with_printer(mut self, printer: &PrinterConfig) -> Self {
+        self.printer = *printer;
+        self
+    }
+
+    /// Allow local files and set the size limit for local files and data URIs.
+    pub fn with_sources(mut self, sources: ImageSources) -> Self {
+        self.sources = sources;
+        self
     }
 
     /// Resolve all Image components in a document.
     ///
     /// Downloads images from URLs (using the cache when possible),
-    /// resizes and dithers them, and populates `resolved_data`.
-    /// Recurses into Canvas elements to resolve nested images.
+    /// resizes them to grayscale intensities, and populates `resolved_data`.
+    /// Recurses into Canvas elements and label sheets to resolve nested images.
+    ///
+    /// A failed image fails the document unless its `on_error` policy
+    /// isolates errors; then the failure is kept in the image's
+    /// `resolve_error` and reported by [`Document::component_errors`].
     pub async fn resolve(&self, doc: &mut Document) -> Result<(), EstrellaError> {
+        let isolate = doc.on_error != OnError::Fail;
         for component in &mut doc.document {
-            self.resolve_component(component).await?;
+            self.resolve_component(component, isolate).await?;
         }
         Ok(())
     }
@@ -47,25 +105,48 @@ impl ImageResolver {
     fn resolve_component<'a>(
         &'a self,
         component: &'a mut Component,
+        isolate: bool,
     ) -> std::pin::Pin<Box<dyn std::future::Future<Output = Result<(), EstrellaError>> + Send + 'a>>
     {
         Box::pin(async move {
             match component {
                 Component::Image(img) => {
                     if !img.url.is_empty() && img.resolved_data.is_none() {
-                        let source = fetch_image(&img.url, &self.sessions).await?;
-                        let resolved = process_image(
-                            source,
-                            img.width.unwrap_or(576),
-                            img.height,
-                            img.dither.as_deref(),
-                        );
-                        img.resolved_data = Some(resolved);
+                        let source = match self.load_image(&img.url).await {
+                            Ok(source) => source,
+                            Err(e) if isolate => {
+                                img.resolve_error = Some(e.to_string());
+                                return Ok(());
+                            }
+                            Err(e) => return Err(e),
+                        };
+                        img.resolve_source(source, &self.printer);
                     }
                 }
                 Component::Canvas(canvas) => {
                     for element in &mut canvas.elements {
-                        self.resolve_component(&mut element.component).await?;
+                        self.resolve_component(&mut element.component, isolate)
+                            .await?;
+                    }
+                }
+                Component::LabelSheet(sheet) => {
+                    for inner in &mut sheet.label {
+                        self.resolve_component(inner, isolate).await?;
+                    }
+                }
+                Component::Texture(texture) => {
+                    for inner in &mut texture.tile {
+                        self.resolve_component(inner, isolate).await?;
+                    }
+                }
+                Component::StationGroup(group) => {
+                    for inner in &mut group.components {
+                        self.resolve_component(inner, isolate).await?;
+                    }
+                }
+                Component::Conditional(conditional) => {
+                    for inner in &mut conditional.components {
+                        self.resolve_component(inner, isolate).await?;
                     }
                 }
                 _ => {}
@@ -73,6 +154,176 @@ impl ImageResolver {
             Ok(())
         })
     }
+
+    /// Load an image from a data URI, a local file or a URL.
+    async fn load_image(&self, url: &str) -> Result<DynamicImage, EstrellaError> {
+        if let Some(uri) = url.strip_prefix("data:") {
+            decode_data_uri(uri, self.sources.max_bytes)
+        } else if let Some(path) = url.strip_prefix("file://") {
+            read_local_image(path, &self.sources).await
+        } else {
+            fetch_image(url, &self.sessions).await
+        }
+    }
+}
+
+/// Decode the part of a `data:` URI after the scheme, e.g.
+/// `image/png;base64,iVBORw0...`.
+fn decode_data_uri(uri: &str, max_bytes: usize) -> Result<DynamicImage, EstrellaError> {
+    let (media_type, payload) = uri
+        .split_once(',')
+        .ok_or_else(|| EstrellaError::Image("Invalid data URI: missing ','".into()))?;
+    let Some(mime) = media_type.strip_suffix(";base64") else {
+        return Err(EstrellaError::Image(
+            "Only base64 data URIs are supported".into(),
+        ));
+    };
+    if !mime.starts_with("image/") {
+        return Err(EstrellaError::Image(format!(
+            "Data URI is '{}', not an image",
+            mime
+        )));
+    }
+    if payload.len() / 4 * 3 > max_bytes {
+        return Err(EstrellaError::Image(format!(
+            "Data URI is larger than the {} byte limit",
+            max_bytes
+        )));
+    }
+    let bytes = decode_base64(payload)
+        .ok_or_else(|| EstrellaError::Image("Invalid base64 in data URI".into()))?;
+    exif::decode(&bytes).map_err(|e| EstrellaError::Image(format!("Failed to decode image: {}", e)))
+}
+
+/// Read the image at the path of a `file://` URL (everything after the
+/// scheme) if it's inside an allowed directory and within the size limit.
+async fn read_local_image(
+    path: &str,
+    sources: &ImageSources,
+) -> Result<DynamicImage, EstrellaError> {
+    let path = path.strip_prefix("localhost").unwrap_or(path);
+    if !path.starts_with('/') {
+        return Err(EstrellaError::Image(format!(
+            "Invalid file URL 'file://{}': use file:///absolute/path",
+            path
+        )));
+    }
+    if sources.allowed_dirs.is_empty() {
+        return Err(EstrellaError::Image(
+            "Local images are disabled: no image directory is allowed".into(),
+        ));
+    }
+    let path = PathBuf::from(percent_decode(path));
+
+    // Check where the path points before touching the filesystem, then again
+    // once symlinks are resolved. Missing and forbidden files get the same
+    // error so requests can't probe what exists outside the allowed dirs.
+    let not_allowed = || EstrellaError::Image(format!("{}: path not allowed", path.display()));
+    let lexical = normalize(&path);
+    if !sources
+        .allowed_dirs
+        .iter()
+        .any(|dir| lexical.starts_with(normalize(dir)))
+    {
+        return Err(not_allowed());
+    }
+    let real = tokio::fs::canonicalize(&lexical)
+        .await
+        .map_err(|_| not_allowed())?;
+    if !is_allowed(&real, &sources.allowed_dirs).await {
+        return Err(not_allowed());
+    }
+    let missing = |e: std::io::Error| {
+        EstrellaError::Image(format!("Failed to read {}: {}", path.display(), e))
+    };
+    let size = tokio::fs::metadata(&real).await.map_err(missing)?.len();
+    if size > sources.max_bytes as u64 {
+        return Err(EstrellaError::Image(format!(
+            "{} is larger than the {} byte limit",
+            path.display(),
+            sources.max_bytes
+        )));
+    }
+
+    let bytes = tokio::fs::read(&real).await.map_err(missing)?;
+    exif::decode(&bytes).map_err(|e| EstrellaError::Image(format!("Failed to decode image: {}", e)))
+}
+
+/// Make `path` absolute and resolve `.` and `..` without following symlinks.
+fn normalize(path: &Path) -> PathBuf {
+    let path = path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+    let mut normal = PathBuf::new();
+    for component in path.components() {
+        match component {
+            path::Component::CurDir => {}
+            path::Component::ParentDir => {
+                normal.pop();
+            }
+            other => normal.push(other),
+        }
+    }
+    normal
+}
+
+/// Whether `path` (already canonical) is inside one of `dirs`.
+async fn is_allowed(path: &Path, dirs: &[PathBuf]) -> bool {
+    for dir in dirs {
+        if let Ok(dir) = tokio::fs::canonicalize(dir).await
+            && path.starts_with(&dir)
+        {
+            return true;
+        }
+    }
+    false
+}
+
+/// Decode `%XX` escapes in a URL path, leaving malformed ones as they are.
+pub(crate) fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%')
+            .then(|| text.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Decode standard or URL-safe base64, ignoring whitespace and padding.
+fn decode_base64(text: &str) -> Option<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(text.len() / 4 * 3);
+    let mut buffer = 0u32;
+    let mut bits = 0;
+    for c in text.bytes().filter(|c| !c.is_ascii_whitespace()) {
+        let value = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' | b'-' => 62,
+            b'/' | b'_' => 63,
+            b'=' => break,
+            _ => return None,
+        };
+        buffer = (buffer << 6 | value as u32) & 0xFFFF;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((buffer >> bits) as u8);
+        }
+    }
+    Some(bytes)
 }
 
 /// Fetch an image from a URL using the render context's shared resources.
@@ -111,7 +362,7 @@ pub async fn fetch_image_with_ctx(
         .await
         .map_err(|e| EstrellaError::Image(format!("Failed to read image data: {}", e)))?;
 
-    let image = image::load_from_memory(&bytes)
+    let image = exif::decode(&bytes)
         .map_err(|e| EstrellaError::Image(format!("Failed to decode image: {}", e)))?;
 
     // Store in cache
@@ -141,51 +392,163 @@ pub async fn fetch_image(
     fetch_image_with_ctx(url, &ctx).await
 }
 
+impl Image {
+    /// Resolve from an image that is already decoded (e.g. a page sent
+    /// over IPP), sized for `printer` as a downloaded one would be.
+    pub fn resolve_source(&mut self, source: DynamicImage, printer: &PrinterConfig) {
+        let ctx = EmitContext::new(printer);
+        let width = ctx
+            .dots_or_mm(self.width, self.width_mm)
+            .unwrap_or(ctx.print_width());
+        let height = ctx.dots_or_mm(self.height, self.height_mm);
+        self.resolved_data = Some(process_image(source, width, height, self));
+    }
+}
+
 /// Process a downloaded image for printing.
 ///
-/// Resizes to `target_width` (default 576 dots) preserving aspect ratio.
-/// If `max_height` is set and the result is taller, resizes to fit within
-/// that height constraint. Dithers with the specified algorithm (default:
-/// Floyd-Steinberg).
+/// Scales it to `target_width` and `height` as the image's `fit` says,
+/// applies its brightness and contrast, and lays transparent pixels over
+/// its background. The result stays grayscale; the Image component picks
+/// the dithering algorithm when it emits `Op::GrayRaster`.
 fn process_image(
     source: DynamicImage,
     target_width: usize,
-    max_height: Option<usize>,
-    dither_str: Option<&str>,
+    height: Option<usize>,
+    image: &Image,
 ) -> ResolvedImage {
-    let dither_algo = dither_str
-        .and_then(parse_dither_algorithm)
-        .unwrap_or(DitheringAlgorithm::FloydSteinberg);
-
-    // Resize to target width, preserving aspect ratio
-    let aspect = source.height() as f32 / source.width() as f32;
-    let scaled_height = (target_width as f32 * aspect).round() as u32;
-    let mut resized = source.resize_exact(target_width as u32, scaled_height, FilterType::Lanczos3);
-
-    // Apply max height constraint
-    if let Some(max_h) = max_height
-        && scaled_height > max_h as u32
-    {
-        resized = resized.resize(target_width as u32, max_h as u32, FilterType::Lanczos3);
-    }
-
-    let width = resized.width() as usize;
-    let height = resized.height() as usize;
-    let grayscale = resized.to_luma8();
+    let width = target_width.max(1) as u32;
+    let aspect = source.height() as f32 / source.width().max(1) as f32;
+    let scaled_height = ((width as f32 * aspect).round() as u32).max(1);
+    let height = height.filter(|&h| h > 0).map(|h| h as u32);
 
-    let raster_data = dither::generate_raster(
-        width,
-        height,
-        |x, y, _w, _h| {
-            let pixel = grayscale.get_pixel(x as u32, y as u32);
-            1.0 - (pixel[0] as f32 / 255.0)
-        },
-        dither_algo,
+    let resized = match (image.fit.unwrap_or_default(), height) {
+        (ImageFit::Crop, Some(h)) => source.resize_to_fill(width, h, FilterType::Lanczos3),
+        // Keeps proportions within the box
+        (ImageFit::Contain, Some(h)) if scaled_height > h => {
+            source.resize(width, h, FilterType::Lanczos3)
+        }
+        _ => source.resize_exact(width, scaled_height, FilterType::Lanczos3),
+    };
+    let adjusted = adjust::apply_brightness_contrast_if_needed(
+        &resized,
+        image.brightness.unwrap_or(0),
+        image.contrast.unwrap_or(0),
     );
+    let background = image.background.clone().unwrap_or_default();
 
     ResolvedImage {
-        raster_data,
-        width: width as u16,
-        height: height as u16,
+        intensities: alpha::intensities(&adjusted, &background),
+        width: adjusted.width() as u16,
+        height: adjusted.height() as u16,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 1×1 PNG.
+    const PIXEL_PNG: &str = "iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAYAAAAfFcSJAAAADUlEQVR42mNkYPhfDwAChwGA60e6kgAAAABJRU5ErkJggg==";
+
+    fn resolver(sources: ImageSources) -> ImageResolver {
+        ImageResolver::new(Arc::new(RwLock::new(HashMap::new()))).with_sources(sources)
+    }
+
+    #[test]
+    fn test_process_image_fit() {
+        let source = DynamicImage::new_rgb8(100, 50);
+        let size = |fit, height| {
+            let image = Image {
+                fit: Some(fit),
+                ..Default::default()
+            };
+            let resolved = process_image(source.clone(), 50, height, &image);
+            (resolved.width, resolved.height)
+        };
+        assert_eq!(size(ImageFit::Contain, None), (50, 25));
+        assert_eq!(size(ImageFit::Contain, Some(10)), (20, 10));
+        assert_eq!(size(ImageFit::Width, Some(10)), (50, 25));
+        assert_eq!(size(ImageFit::Crop, Some(10)), (50, 10));
+    }
+
+    #[test]
+    fn test_decode_base64() {
+        assert_eq!(decode_base64("Zm9vYmFy").unwrap(), b"foobar");
+        assert_eq!(decode_base64("Zm9v\nYg==").unwrap(), b"foob");
+        assert_eq!(decode_base64("Pz8_").unwrap(), b"???");
+        assert!(decode_base64("Zm9v!").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_data_uri() {
+        let resolver = resolver(ImageSources::default());
+        let image = resolver
+            .load_image(&format!("data:image/png;base64,{}", PIXEL_PNG))
+            .await
+            .unwrap();
+        assert_eq!((image.width(), image.height()), (1, 1));
+
+        assert!(
+            resolver
+                .load_image("data:text/plain;base64,aGk=")
+                .await
+                .is_err()
+        );
+        assert!(resolver.load_image("data:image/png,raw").await.is_err());
+        let limited = self::resolver(ImageSources {
+            max_bytes: 16,
+            ..Default::default()
+        });
+        let err = limited
+            .load_image(&format!("data:image/png;base64,{}", PIXEL_PNG))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("byte limit"));
+    }
+
+    #[tokio::test]
+    async fn test_local_file_allowlist() {
+        let dir = std::env::temp_dir().join(format!("estrella-images-{}", std::process::id()));
+        let allowed = dir.join("allowed");
+        std::fs::create_dir_all(&allowed).unwrap();
+        std::fs::write(allowed.join("pixel.png"), decode_base64(PIXEL_PNG).unwrap()).unwrap();
+        std::fs::write(dir.join("secret.png"), decode_base64(PIXEL_PNG).unwrap()).unwrap();
+        let url = |path: &Path| format!("file://{}", path.display());
+
+        // Nothing is allowed by default
+        let closed = resolver(ImageSources::default());
+        assert!(
+            closed
+                .load_image(&url(&allowed.join("pixel.png")))
+                .await
+                .is_err()
+        );
+
+        let open = resolver(ImageSources {
+            allowed_dirs: vec![allowed.clone()],
+            ..Default::default()
+        });
+        assert!(
+            open.load_image(&url(&allowed.join("pixel.png")))
+                .await
+                .is_ok()
+        );
+        let escape = open
+            .load_image(&url(&allowed.join("../secret.png")))
+            .await
+            .unwrap_err();
+        let missing = open
+            .load_image(&url(&allowed.join("nope.png")))
+            .await
+            .unwrap_err();
+        assert!(escape.to_string().contains("path not allowed"));
+        assert_eq!(
+            missing.to_string().replace("nope", "../secret"),
+            escape.to_string()
+        );
+        assert!(open.load_image("file://relative.png").await.is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
     }
 }
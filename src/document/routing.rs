@@ -0,0 +1,196 @@
+//! Station routing: compiling one document differently per target printer.
+//!
+//! A kitchen, a bar and a front counter can all receive the same submitted
+//! document. [`Document::for_station`] drops the [`StationGroup`]s meant for
+//! other stations and narrows order tickets to the station's items, so the
+//! client never has to split documents itself.
+
+use super::types::StationGroup;
+use super::{Component, Document, EmitContext};
+use crate::ir::Op;
+
+impl StationGroup {
+    /// Emit IR ops for this station group.
+    ///
+    /// Routing happens at compile time; a group that is still present
+    /// prints all its components.
+    pub fn emit(&self, ctx: &EmitContext, ops: &mut Vec<Op>) {
+        for component in &self.components {
+            component.emit(ctx, ops);
+        }
+    }
+}
+
+impl Document {
+    /// A copy of this document compiled for `station`.
+    ///
+    /// ```
+    /// use estrella::document::Document;
+    ///
+    /// let doc: Document = serde_json::from_str(r#"{"document": [
+    ///     {"text": "ORDER 42"},
+    ///     {"text": "FIRE!", "stations": ["kitchen"]}
+    /// ]}"#).unwrap();
+    ///
+    /// assert_eq!(doc.stations(), ["kitchen"]);
+    /// for station in doc.stations() {
+    ///     let _program = doc.for_station(&station).compile();
+    ///     // ...send `program` to this station's printer
+    /// }
+    /// ```
+    pub fn for_station(&self, station: &str) -> Document {
+        Document {
+            station: Some(station.to_string()),
+            ..self.clone()
+        }
+    }
+
+    /// Every station named in this document (station groups and order
+    /// ticket items), in order of first appearance.
+    ///
+    /// Send [`Document::for_station`] of each to its printer to fan an
+    /// order out to the kitchen.
+    pub fn stations(&self) -> Vec<String> {
+        fn walk(components: &[Component], out: &mut Vec<String>) {
+            for component in components {
+                let named: Vec<&str> = match component {
+                    Component::StationGroup(group) => {
+                        let named = group.stations.iter().map(String::as_str).collect();
+                        walk(&group.components, out);
+                        named
+                    }
+                    Component::Conditional(conditional) => {
+                        walk(&conditional.components, out);
+                        Vec::new()
+                    }
+                    Component::OrderTicket(ticket) => match &ticket.station {
+                        Some(station) => vec![station.as_str()],
+                        None => ticket.stations(),
+                    },
+                    _ => Vec::new(),
+                };
+                for station in named {
+                    if !out.iter().any(|s| s.eq_ignore_ascii_case(station)) {
+                        out.push(station.to_string());
+                    }
+                }
+            }
+        }
+        let mut out = Vec::new();
+        walk(&self.document, &mut out);
+        out
+    }
+
+    /// Apply routing for `station` in place: drop station groups for other
+    /// stations, unwrap the rest, and restrict order tickets that aren't
+    /// pinned to a station to this one (dropping them if none of their
+    /// items are for it).
+    pub(crate) fn route(&mut self, station: &str) {
+        fn walk(components: Vec<Component>, station: &str, out: &mut Vec<Component>) {
+            for component in components {
+                match component {
+                    Component::StationGroup(group) => {
+                        if group.includes(station) {
+                            walk(group.components, station, out);
+                        }
+                    }
+                    Component::OrderTicket(mut ticket) => {
+                        if ticket.station.is_none() {
+                            if !ticket.serves(station) {
+                                continue;
+                            }
+                            ticket.station = Some(station.to_string());
+                            ticket.per_station = false;
+                        }
+                        out.push(Component::OrderTicket(ticket));
+                    }
+                    other => out.push(other),
+                }
+            }
+        }
+        let components = std::mem::take(&mut self.document);
+        walk(components, station, &mut self.document);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document::testing::{compiled_texts, fixture};
+    use crate::document::{Order, OrderItem, OrderTicket, Text};
+    use serde_json::json;
+
+    fn doc() -> Document {
+        fixture(json!({"document": [
+            {"text": "ORDER 42"},
+            {"text": "NO ICE", "stations": ["Bar"]},
+            {"type": "station_group", "stations": ["kitchen", "expo"],
+             "components": [{"text": "FIRE"}]}
+        ]}))
+    }
+
+    #[test]
+    fn test_stations_key_wraps_component() {
+        let doc = doc();
+        match &doc.document[1] {
+            Component::StationGroup(group) => {
+                assert_eq!(group.stations, ["Bar"]);
+                assert!(
+                    matches!(&group.components[0], Component::Text(t) if t.content == "NO ICE")
+                );
+            }
+            other => panic!("expected station group, got {:?}", other),
+        }
+        assert_eq!(doc.stations(), ["Bar", "kitchen", "expo"]);
+    }
+
+    #[test]
+    fn test_compile_per_station() {
+        let doc = doc();
+        assert_eq!(
+            compiled_texts(&doc.for_station("bar")),
+            ["ORDER 42", "NO ICE"]
+        );
+        assert_eq!(
+            compiled_texts(&doc.for_station("kitchen")),
+            ["ORDER 42", "FIRE"]
+        );
+        assert_eq!(compiled_texts(&doc.for_station("front")), ["ORDER 42"]);
+        // Without a target station everything prints
+        assert_eq!(compiled_texts(&doc), ["ORDER 42", "NO ICE", "FIRE"]);
+    }
+
+    #[test]
+    fn test_order_ticket_follows_station() {
+        let doc = Document {
+            document: vec![
+                Component::Text(Text::new("HEADER")),
+                Component::OrderTicket(OrderTicket {
+                    order: Order {
+                        number: "7".into(),
+                        items: vec![
+                            OrderItem {
+                                station: Some("grill".into()),
+                                ..OrderItem::new("Burger")
+                            },
+                            OrderItem {
+                                station: Some("bar".into()),
+                                ..OrderItem::new("Negroni")
+                            },
+                        ],
+                        ..Default::default()
+                    },
+                    per_station: true,
+                    ..Default::default()
+                }),
+            ],
+            ..Default::default()
+        };
+        assert_eq!(doc.stations(), ["grill", "bar"]);
+
+        let bar = compiled_texts(&doc.for_station("bar"));
+        assert!(bar.iter().any(|t| t == " 1x Negroni"));
+        assert!(!bar.iter().any(|t| t == " 1x Burger"));
+        assert_eq!(compiled_texts(&doc.for_station("pastry")), ["HEADER"]);
+    }
+}
@@ -0,0 +1,204 @@
+//! URL shortening for long QR code payloads.
+//!
+//! `UrlShortener` rewrites long links in QR codes through a self-hosted
+//! shortener before compilation, keeping the printed codes small enough to
+//! scan reliably. Like `ImageResolver`, it keeps HTTP concerns out of
+//! `Document`. It only runs when the server is configured with an endpoint,
+//! and only for prints and `/api/json/compile`: previews keep the long link.
+
+use serde::Deserialize;
+
+use super::{Component, Document};
+use crate::EstrellaError;
+
+/// Shortener settings. Absent from the server config means "never shorten".
+#[derive(Debug, Clone)]
+pub struct ShortenerConfig {
+    /// Endpoint that accepts `POST {"url": "<long url>"}` and returns the
+    /// short URL, either as plain text or as JSON (`short_url`, `shortUrl`,
+    /// `link`, or `url`).
+    pub endpoint: String,
+    /// Payloads longer than this many bytes are shortened.
+    pub threshold: usize,
+}
+
+/// JSON response shapes accepted from the shortener.
+///
+/// Many shorteners echo the long URL back as `url` next to the short one,
+/// so the dedicated short-URL fields take precedence.
+#[derive(Deserialize)]
+struct ShortenResponse {
+    #[serde(default, alias = "shortUrl")]
+    short_url: Option<String>,
+    #[serde(default)]
+    link: Option<String>,
+    #[serde(default)]
+    url: Option<String>,
+}
+
+/// Shortens long links in QR code components.
+pub struct UrlShortener {
+    config: ShortenerConfig,
+    client: reqwest::Client,
+}
+
+impl UrlShortener {
+    /// Create a shortener for the given endpoint.
+    pub fn new(config: ShortenerConfig) -> Result<Self, EstrellaError> {
+        let client = reqwest::Client::builder()
+            .user_agent("estrella/0.1")
+            .timeout(std::time::Duration::from_secs(5))
+            .build()
+            .map_err(|e| EstrellaError::Transport(format!("HTTP client error: {}", e)))?;
+        Ok(Self { config, client })
+    }
+
+    /// Shorten every oversized QR code link in a document, including those
+    /// nested in Canvas elements and label sheets.
+    ///
+    /// A shortener failure is logged and leaves the original link in place:
+    /// a large QR code is still better than no print.
+    pub async fn resolve(&self, doc: &mut Document) {
+        let mut pending = Vec::new();
+        for component in &mut doc.document {
+            collect_qr_data(component, &mut pending);
+        }
+
+        for data in pending {
+            if !needs_shortening(data, self.config.threshold) {
+                continue;
+            }
+            match self.shorten(data).await {
+                Ok(short) => *data = short,
+                Err(e) => eprintln!("[shorten] Keeping original link: {}", e),
+            }
+        }
+    }
+
+    /// Ask the shortener endpoint for a short form of `url`.
+    async fn shorten(&self, url: &str) -> Result<String, String> {
+        let response = self
+            .client
+            .post(&self.config.endpoint)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(serde_json::json!({ "url": url }).to_string())
+            .send()
+            .await
+            .map_err(|e| format!("request to {} failed: {}", self.config.endpoint, e))?;
+        if !response.status().is_success() {
+            return Err(format!(
+                "{} returned HTTP {}",
+                self.config.endpoint,
+                response.status()
+            ));
+        }
+        let body = response
+            .text()
+            .await
+            .map_err(|e| format!("failed to read response: {}", e))?;
+
+        let short = parse_response(&body)
+            .ok_or_else(|| format!("unrecognized response: {}", body.trim()))?;
+        if short.len() >= url.len() {
+            return Err(format!("'{}' is not shorter than the original", short));
+        }
+        Ok(short)
+    }
+}
+
+/// Collect mutable references to every QR code payload in a component tree.
+fn collect_qr_data<'a>(component: &'a mut Component, out: &mut Vec<&'a mut String>) {
+    match component {
+        Component::QrCode(qr) => out.push(&mut qr.data),
+        Component::Canvas(canvas) => {
+            for element in &mut canvas.elements {
+                collect_qr_data(&mut element.component, out);
+            }
+        }
+        Component::LabelSheet(sheet) => {
+            for inner in &mut sheet.label {
+                collect_qr_data(inner, out);
+            }
+        }
+        Component::Texture(texture) => {
+            for inner in &mut texture.tile {
+                collect_qr_data(inner, out);
+            }
+        }
+        Component::StationGroup(group) => {
+            for inner in &mut group.components {
+                collect_qr_data(inner, out);
+            }
+        }
+        Component::Conditional(conditional) => {
+            for inner in &mut conditional.components {
+                collect_qr_data(inner, out);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Only http(s) links over the threshold are worth shortening; other
+/// payloads (WiFi configs, vCards, plain text) must be encoded verbatim.
+fn needs_shortening(data: &str, threshold: usize) -> bool {
+    data.len() > threshold && (data.starts_with("http://") || data.starts_with("https://"))
+}
+
+/// Extract the short URL from a plain-text or JSON response body.
+fn parse_response(body: &str) -> Option<String> {
+    let body = body.trim();
+    let short = match serde_json::from_str::<ShortenResponse>(body) {
+        Ok(json) => json.short_url.or(json.link).or(json.url)?,
+        Err(_) => body.to_string(),
+    };
+    (short.starts_with("http://") || short.starts_with("https://")).then_some(short)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_needs_shortening() {
+        let long = format!("https://example.com/{}", "a".repeat(100));
+        assert!(needs_shortening(&long, 80));
+        assert!(!needs_shortening("https://example.com", 80));
+        let wifi = format!("WIFI:T:WPA;S:{};P:secret;;", "n".repeat(100));
+        assert!(!needs_shortening(&wifi, 80));
+    }
+
+    #[test]
+    fn test_parse_response() {
+        assert_eq!(
+            parse_response("https://s.example/abc\n"),
+            Some("https://s.example/abc".into())
+        );
+        assert_eq!(
+            parse_response(r#"{"shortUrl": "https://s.example/abc"}"#),
+            Some("https://s.example/abc".into())
+        );
+        assert_eq!(
+            parse_response(
+                r#"{"url": "https://long.example/x", "short_url": "https://s.example/x"}"#
+            ),
+            Some("https://s.example/x".into())
+        );
+        assert_eq!(parse_response("<html>error</html>"), None);
+    }
+
+    #[test]
+    fn test_collect_qr_data_recurses_into_canvas() {
+        let json = r#"{"document": [
+            {"qr_code": "https://a.example"},
+            {"canvas": [{"qr_code": "https://b.example"}]}
+        ]}"#;
+        let mut doc: Document = serde_json::from_str(json).unwrap();
+        let mut found = Vec::new();
+        for component in &mut doc.document {
+            collect_qr_data(component, &mut found);
+        }
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[1].as_str(), "https://b.example");
+    }
+}
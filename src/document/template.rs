@@ -0,0 +1,438 @@
+//! Template expansion: conditionals and loops.
+//!
+//! Documents are often filled in from variables: a customer name, a member
+//! flag, the items of an order. [`Conditional`] prints components only when
+//! a variable is set (any component takes `"if"` / `"unless"` directly), and
+//! [`Repeat`] prints its components once per entry of a JSON array:
+//!
+//! ```json
+//! {
+//!   "variables": {"member": true, "items": [{"name": "Latte", "price": 4.5}]},
+//!   "document": [
+//!     {"type": "repeat", "each": "items", "components": [
+//!       {"line_item": "{{item.name}}", "price": "{{item.price}}"}
+//!     ]},
+//!     {"text": "Thanks for being a member!", "if": "member"}
+//!   ]
+//! }
+//! ```
+//!
+//! Both are expanded before anything else happens to the document, so the
+//! components they produce are routed, redacted and interpolated like any
+//! other. `html` components are converted to their components at the same
+//! time, so their images are resolved too.
+
+use serde_json::Value;
+use std::collections::HashMap;
+
+#[cfg(not(feature = "html"))]
+use super::types::Html;
+use super::types::{CanvasElement, Conditional, Repeat};
+use super::{Component, Document, EmitContext, filters, parse_component};
+use crate::EstrellaError;
+use crate::ir::Op;
+
+impl Conditional {
+    /// Emit IR ops for this conditional.
+    ///
+    /// Conditions are evaluated at compile time; a conditional that is
+    /// still present prints all its components.
+    pub fn emit(&self, ctx: &EmitContext, ops: &mut Vec<Op>) {
+        for component in &self.components {
+            component.emit(ctx, ops);
+        }
+    }
+}
+
+impl Repeat {
+    /// Emit IR ops for this repeat.
+    ///
+    /// Repeats are expanded at compile time; one that is still present has
+    /// no entries to print.
+    pub fn emit(&self, _ctx: &EmitContext, _ops: &mut Vec<Op>) {}
+}
+
+#[cfg(not(feature = "html"))]
+impl Html {
+    /// HTML prints nothing without the `html` feature.
+    pub fn emit(&self, _ctx: &EmitContext, _ops: &mut Vec<Op>) {}
+}
+
+impl Document {
+    /// Expand conditionals and repeats with the document's variables, and
+    /// convert `html` components to the components they stand for.
+    ///
+    /// Compiling does this too; call it first when later steps, such as
+    /// downloading images, should see the components a template produces.
+    ///
+    /// ```
+    /// use estrella::document::{Component, Document};
+    ///
+    /// let mut doc: Document = serde_json::from_str(r#"{
+    ///     "variables": {"items": ["Latte", "Scone"]},
+    ///     "document": [
+    ///         {"type": "repeat", "each": "items", "components": [{"text": "{{item}}"}]},
+    ///         {"text": "Member price", "if": "member"}
+    ///     ]
+    /// }"#).unwrap();
+    ///
+    /// doc.expand_templates().unwrap();
+    /// assert_eq!(doc.document.len(), 2);
+    /// assert!(matches!(&doc.document[1], Component::Text(t) if t.content == "Scone"));
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns [`EstrellaError::Template`] for the first repeat whose
+    /// `each` isn't an array or whose components don't parse once filled
+    /// in. Everything else is still expanded; the failing repeats (or the
+    /// failing entries of them) are left out.
+    pub fn expand_templates(&mut self) -> Result<(), EstrellaError> {
+        if !has_templates(&self.document) {
+            return Ok(());
+        }
+        let vars = self.build_variable_map();
+        let scope = Scope {
+            vars: &vars,
+            locals: HashMap::new(),
+        };
+        let mut errors = Vec::new();
+        let mut expanded = Vec::new();
+        for (i, component) in std::mem::take(&mut self.document).into_iter().enumerate() {
+            let label = component.label();
+            let mut failed = Vec::new();
+            expand(component, &scope, &mut expanded, &mut failed);
+            errors.extend(
+                failed
+                    .into_iter()
+                    .map(|e| format!("document[{}] ({}): {}", i, label, e)),
+            );
+        }
+        self.document = expanded;
+        match errors.into_iter().next() {
+            Some(error) => Err(EstrellaError::Template(error)),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Whether any component, or any component nested in one, is a template
+/// or HTML.
+fn has_templates(components: &[Component]) -> bool {
+    components.iter().any(|component| match component {
+        Component::Conditional(_) | Component::Repeat(_) => true,
+        #[cfg(feature = "html")]
+        Component::Html(_) => true,
+        Component::StationGroup(group) => has_templates(&group.components),
+        Component::LabelSheet(sheet) => has_templates(&sheet.label),
+        Component::Texture(texture) => has_templates(&texture.tile),
+        Component::Canvas(canvas) => canvas
+            .elements
+            .iter()
+            .any(|element| has_templates(std::slice::from_ref(&element.component))),
+        _ => false,
+    })
+}
+
+/// Variables visible while expanding: the document's, plus the entries of
+/// the loops around this point, kept as JSON.
+struct Scope<'a> {
+    vars: &'a HashMap<String, String>,
+    locals: HashMap<String, Value>,
+}
+
+impl Scope<'_> {
+    /// A variable's value as text.
+    fn get(&self, name: &str) -> Option<String> {
+        match self.locals.get(name) {
+            Some(Value::String(s)) => Some(s.clone()),
+            Some(value) => Some(value.to_string()),
+            None => self.vars.get(name).cloned(),
+        }
+    }
+
+    /// Whether `name` is set: present, and not empty, zero, `false`,
+    /// `null`, `[]` or `{}`.
+    fn is_set(&self, name: &str) -> bool {
+        self.get(name.trim()).is_some_and(|value| {
+            let value = value.trim();
+            !(value.is_empty()
+                || matches!(value, "false" | "null" | "[]" | "{}")
+                || value.parse::<f64>() == Ok(0.0))
+        })
+    }
+
+    fn allows(&self, conditional: &Conditional) -> bool {
+        conditional
+            .if_set
+            .as_deref()
+            .is_none_or(|name| self.is_set(name))
+            && !conditional
+                .unless
+                .as_deref()
+                .is_some_and(|name| self.is_set(name))
+    }
+
+    /// The entries of the array variable `name`. An unset variable has none.
+    fn entries(&self, name: &str) -> Result<Vec<Value>, String> {
+        let value = match self.locals.get(name) {
+            Some(value) => value.clone(),
+            None => match self.vars.get(name) {
+                Some(text) => serde_json::from_str(text)
+                    .map_err(|_| format!("'{}' is not a JSON array", name))?,
+                None => Value::Null,
+            },
+        };
+        match value {
+            Value::Array(entries) => Ok(entries),
+            Value::Null => Ok(Vec::new()),
+            _ => Err(format!("'{}' is not a JSON array", name)),
+        }
+    }
+
+    /// This scope plus the loop variables for entry `index` of `count`.
+    fn with_entry(&self, name: &str, entry: Value, index: usize, count: usize) -> Scope<'_> {
+        let mut locals = self.locals.clone();
+        if let Value::Object(fields) = &entry {
+            for (key, value) in fields {
+                locals.insert(format!("{}.{}", name, key), value.clone());
+            }
+        }
+        locals.insert(name.to_string(), entry);
+        locals.insert("loop.index".into(), Value::from(index + 1));
+        locals.insert("loop.count".into(), Value::from(count));
+        locals.insert("loop.first".into(), Value::from(index == 0));
+        locals.insert("loop.last".into(), Value::from(index + 1 == count));
+        Scope {
+            vars: self.vars,
+            locals,
+        }
+    }
+}
+
+/// Expand `component` into `out`, recording why anything was left out.
+fn expand(component: Component, scope: &Scope, out: &mut Vec<Component>, errors: &mut Vec<String>) {
+    match component {
+        Component::Conditional(conditional) => {
+            if scope.allows(&conditional) {
+                for component in conditional.components {
+                    expand(component, scope, out, errors);
+                }
+            }
+        }
+        Component::Repeat(repeat) => {
+            let entries = match scope.entries(&repeat.each) {
+                Ok(entries) => entries,
+                Err(e) => return errors.push(e),
+            };
+            let count = entries.len();
+            for (index, entry) in entries.into_iter().enumerate() {
+                let inner = scope.with_entry(&repeat.item, entry, index, count);
+                for template in &repeat.components {
+                    match parse_component(fill(template.clone(), &inner.locals)) {
+                        Ok(component) => expand(component, &inner, out, errors),
+                        Err(e) => errors.push(format!("{}[{}]{}", repeat.each, index, e)),
+                    }
+                }
+            }
+        }
+        Component::StationGroup(mut group) => {
+            group.components = expand_all(std::mem::take(&mut group.components), scope, errors);
+            out.push(Component::StationGroup(group));
+        }
+        Component::LabelSheet(mut sheet) => {
+            sheet.label = expand_all(std::mem::take(&mut sheet.label), scope, errors);
+            out.push(Component::LabelSheet(sheet));
+        }
+        Component::Texture(mut texture) => {
+            texture.tile = expand_all(std::mem::take(&mut texture.tile), scope, errors);
+            out.push(Component::Texture(texture));
+        }
+        Component::Canvas(mut canvas) => {
+            // An element expands to one element per component it produces,
+            // each placed and blended like the original
+            let mut elements = Vec::new();
+            for element in std::mem::take(&mut canvas.elements) {
+                let mut components = Vec::new();
+                expand(element.component.clone(), scope, &mut components, errors);
+                elements.extend(components.into_iter().map(|component| CanvasElement {
+                    component,
+                    ..element.clone()
+                }));
+            }
+            canvas.elements = elements;
+            out.push(Component::Canvas(canvas));
+        }
+        #[cfg(feature = "html")]
+        Component::Html(html) => out.extend(html.components()),
+        other => out.push(other),
+    }
+}
+
+/// Expand each of `components` in order.
+fn expand_all(
+    components: Vec<Component>,
+    scope: &Scope,
+    errors: &mut Vec<String>,
+) -> Vec<Component> {
+    let mut out = Vec::new();
+    for component in components {
+        expand(component, scope, &mut out, errors);
+    }
+    out
+}
+
+/// Fill loop variables into a component's JSON. A string that is exactly
+/// one placeholder (without filters) takes the variable's JSON value, so
+/// numbers stay numbers; other placeholders are replaced by text.
+fn fill(value: Value, locals: &HashMap<String, Value>) -> Value {
+    match value {
+        Value::String(s) => {
+            let whole = s
+                .trim()
+                .strip_prefix("{{")
+                .and_then(|rest| rest.strip_suffix("}}"))
+                .and_then(|name| locals.get(name.trim()));
+            if let Some(value) = whole {
+                return value.clone();
+            }
+            let text = |name: &str| match locals.get(name)? {
+                Value::String(text) => Some(text.clone()),
+                other => Some(other.to_string()),
+            };
+            Value::String(filters::interpolate(&s, text, false))
+        }
+        Value::Array(items) => Value::Array(items.into_iter().map(|v| fill(v, locals)).collect()),
+        Value::Object(fields) => Value::Object(
+            fields
+                .into_iter()
+                .map(|(key, v)| (key, fill(v, locals)))
+                .collect(),
+        ),
+        other => other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document::testing::{compiled_texts, fixture, text_contents};
+    use serde_json::json;
+
+    fn order(variables: Value) -> Document {
+        fixture(json!({"variables": variables, "document": [
+            {"text": "Hi {{name}}", "if": "name"},
+            {"type": "repeat", "each": "items", "components": [
+                {"line_item": "{{loop.index}}. {{item.name}}", "price": "{{item.price}}", "width": 20},
+                {"text": "Note: {{item.note}}", "if": "item.note"},
+                {"divider": "dashed", "width": 5, "unless": "loop.last"}
+            ]},
+            {"text": "Members save 10%", "unless": "member"}
+        ]}))
+    }
+
+    #[test]
+    fn test_repeat_and_conditions() {
+        let doc = order(json!({"name": "Jojo", "member": true, "items": [
+            {"name": "Latte", "price": 4.5, "note": "oat milk"},
+            {"name": "Scone", "price": 3}
+        ]}));
+        assert_eq!(
+            compiled_texts(&doc),
+            [
+                "Hi Jojo",
+                "1. Latte        4.50",
+                "Note: oat milk",
+                "-----",
+                "2. Scone        3.00",
+            ]
+        );
+    }
+
+    #[cfg(feature = "html")]
+    #[test]
+    fn test_html_is_converted() {
+        let mut doc = fixture(json!({"variables": {"items": ["Latte"]}, "document": [
+            {"type": "repeat", "each": "items", "components": [
+                {"html": "<p><b>{{item}}</b></p><img src='https://example.com/a.png'>"}
+            ]}
+        ]}));
+        doc.expand_templates().unwrap();
+        assert!(matches!(&doc.document[0], Component::Text(t) if t.content == "Latte" && t.bold));
+        assert!(matches!(&doc.document[2], Component::Image(i) if i.url.ends_with("a.png")));
+    }
+
+    #[test]
+    fn test_repeat_filters() {
+        let doc = fixture(
+            json!({"variables": {"items": [{"name": "latte", "price": 4.5}]}, "document": [
+                {"type": "repeat", "each": "items", "components": [
+                    {"text": "{{item.name | upper}} {{item.price | currency:EUR}}"}
+                ]}
+            ]}),
+        );
+        assert_eq!(compiled_texts(&doc), ["LATTE €4.50"]);
+    }
+
+    #[test]
+    fn test_unset_variables() {
+        // No items: the loop prints nothing; `member` unset shows the pitch
+        let doc = order(json!({"name": ""}));
+        assert_eq!(compiled_texts(&doc), ["Members save 10%"]);
+    }
+
+    #[test]
+    fn test_array_as_json_string() {
+        let mut doc = order(json!({"items": "[{\"name\": \"Tea\", \"price\": 2}]", "member": "0"}));
+        doc.expand_templates().unwrap();
+        assert!(matches!(&doc.document[0], Component::LineItem(item) if item.price == 2.0));
+        assert!(matches!(&doc.document[1], Component::Text(t) if t.content.starts_with("Members")));
+    }
+
+    #[test]
+    fn test_templates_inside_containers() {
+        let mut doc = fixture(json!({"variables": {"items": ["A", "B"]}, "document": [
+            {"type": "label_sheet", "label": [
+                {"text": "LABEL"},
+                {"text": "LABEL-VIP", "if": "vip"},
+                {"type": "repeat", "each": "items", "components": [{"text": "{{item}}"}]}
+            ]},
+            {"type": "canvas", "elements": [
+                {"type": "repeat", "each": "items", "components": [{"text": "{{item}}"}]},
+                {"text": "VIP", "if": "vip", "position": {"x": 0, "y": 0}}
+            ]}
+        ]}));
+        doc.expand_templates().unwrap();
+        let Component::LabelSheet(sheet) = &doc.document[0] else {
+            panic!("expected label sheet, got {:?}", doc.document[0]);
+        };
+        assert_eq!(text_contents(&sheet.label), ["LABEL", "A", "B"]);
+        let Component::Canvas(canvas) = &doc.document[1] else {
+            panic!("expected canvas, got {:?}", doc.document[1]);
+        };
+        let elements: Vec<_> = canvas
+            .elements
+            .iter()
+            .map(|e| e.component.clone())
+            .collect();
+        assert_eq!(text_contents(&elements), ["A", "B"]);
+    }
+
+    #[test]
+    fn test_expand_errors() {
+        let mut doc = order(json!({"items": "Latte"}));
+        let err = doc.expand_templates().unwrap_err().to_string();
+        assert!(
+            err.contains("document[1] (Repeat): 'items' is not a JSON array"),
+            "{}",
+            err
+        );
+
+        // An entry whose price isn't a number is left out
+        let mut doc =
+            order(json!({"items": [{"name": "A", "price": "free"}, {"name": "B", "price": 1}]}));
+        let err = doc.expand_templates().unwrap_err().to_string();
+        assert!(err.contains("items[0]: "), "{}", err);
+        assert!(compiled_texts(&doc).iter().any(|t| t.starts_with("2. B")));
+    }
+}
@@ -1,28 +1,79 @@
 //! Emit logic for text components: Text, Header, LineItem, Total.
 
+use super::EmitContext;
 use super::types::{Header, LineItem, Text, Total};
-use crate::ir::Op;
-use crate::preview::{FontMetrics, emoji, generate_glyph, ttf_font};
+use crate::ir::{Op, Wrap};
+use crate::preview::{FontMetrics, bidi, emoji, generate_glyph, ttf_font};
+use crate::protocol::codepage;
 use crate::protocol::text::{Alignment, Font};
 use crate::render::dither;
 
 impl Text {
+    /// This text shaped and reordered for drawing left to right. Text that
+    /// reads right to left is aligned right unless aligned explicitly.
+    fn in_display_order(&self) -> Text {
+        let aligned = self.align.is_some() || self.center || self.right;
+        Text {
+            content: bidi::display(&self.content),
+            right: self.right || (!aligned && bidi::is_rtl(&self.content)),
+            ..self.clone()
+        }
+    }
+
     /// Emit IR ops for this text component.
-    pub fn emit(&self, ops: &mut Vec<Op>) {
+    pub fn emit(&self, ctx: &EmitContext, ops: &mut Vec<Op>) {
+        // Inline markup styles printer text; text rendered as an image is
+        // styled as a whole, so it only loses the delimiters
+        if self.markup {
+            let plain: String = parse_markup(&self.content)
+                .into_iter()
+                .map(|(_, run)| run)
+                .collect();
+            if self.font.is_some()
+                || bidi::has_rtl(&plain)
+                || emoji::contains_emoji(&plain)
+                || !plain.chars().all(codepage::is_printable)
+            {
+                let text = Text {
+                    content: plain,
+                    markup: false,
+                    ..self.clone()
+                };
+                text.emit(ctx, ops);
+                return;
+            }
+        }
+
+        // Right-to-left text: shaped and reordered for drawing, then rendered
+        // (the printer's code pages have no Hebrew or Arabic)
+        if bidi::has_rtl(&self.content) {
+            let text = self.in_display_order();
+            match text.font {
+                Some(ref font_name) if emoji::contains_emoji(&text.content) => {
+                    text.emit_with_font_and_emoji(font_name, ctx, ops)
+                }
+                Some(ref font_name) => text.emit_with_custom_font(font_name, ctx, ops),
+                None => text.emit_as_raster(false, ctx, ops),
+            }
+            return;
+        }
+
         // Priority 1: Custom font specified → TTF rendering
         if let Some(ref font_name) = self.font {
             // With custom font, also handle emoji if present
             if emoji::contains_emoji(&self.content) {
-                self.emit_with_font_and_emoji(font_name, ops);
+                self.emit_with_font_and_emoji(font_name, ctx, ops);
             } else {
-                self.emit_with_custom_font(font_name, ops);
+                self.emit_with_custom_font(font_name, ctx, ops);
             }
             return;
         }
 
-        // Priority 2: Contains emoji (no custom font) → bitmap font + emoji sprites
-        if emoji::contains_emoji(&self.content) {
-            self.emit_with_emoji(ops);
+        // Priority 2: Contains emoji or characters no code page has (no
+        // custom font) → bitmap font + emoji sprites + TTF glyphs
+        if emoji::contains_emoji(&self.content) || !self.content.chars().all(codepage::is_printable)
+        {
+            self.emit_as_raster(false, ctx, ops);
             return;
         }
 
@@ -108,14 +159,29 @@ impl Text {
                 width: esc_w,
             });
         }
+        let wrap = Wrap {
+            mode: self.wrap,
+            hanging_indent: self.hanging_indent,
+        };
+        let custom_wrap = wrap != Wrap::default();
+        if custom_wrap {
+            ops.push(Op::SetWrap(wrap));
+        }
 
         // Emit text
-        ops.push(Op::Text(self.content.clone()));
+        if self.markup {
+            self.emit_markup(ops);
+        } else {
+            ops.push(Op::Text(self.content.clone()));
+        }
         if !self.is_inline {
             ops.push(Op::Newline);
         }
 
         // Reset styles that were changed (reverse order)
+        if custom_wrap {
+            ops.push(Op::SetWrap(Wrap::default()));
+        }
         if esc_h > 0 || esc_w > 0 {
             ops.push(Op::SetSize {
                 height: 0,
@@ -153,10 +219,32 @@ impl Text {
         // Note: alignment and font are NOT reset - they persist
     }
 
+    /// Emit content with inline markup as text ops, switching bold,
+    /// underline and invert between runs and back at the end. Markup adds
+    /// to the component's own style.
+    fn emit_markup(&self, ops: &mut Vec<Op>) {
+        let base = InlineStyle {
+            bold: self.bold,
+            underline: self.underline,
+            invert: self.invert,
+        };
+        let mut current = base;
+        for (style, run) in parse_markup(&self.content) {
+            let style = InlineStyle {
+                bold: base.bold || style.bold,
+                underline: base.underline || style.underline,
+                invert: base.invert || style.invert,
+            };
+            current.switch_to(style, ops);
+            ops.push(Op::Text(run));
+        }
+        current.switch_to(base, ops);
+    }
+
     /// Emit text rendered with a custom TTF font as a raster image.
-    fn emit_with_custom_font(&self, font_name: &str, ops: &mut Vec<Op>) {
+    fn emit_with_custom_font(&self, font_name: &str, ctx: &EmitContext, ops: &mut Vec<Op>) {
         let pixel_height = ttf_font::size_to_pixel_height(self.size);
-        let print_width: usize = 576;
+        let print_width = ctx.print_width();
 
         let rendered = ttf_font::render_ttf_text(
             &self.content,
@@ -170,7 +258,7 @@ impl Text {
             return;
         }
 
-        // Handle alignment: compute x offset within 576 dots
+        // Handle alignment: compute x offset within the print width
         let x_offset = if self.center || self.align.as_deref() == Some("center") {
             (print_width.saturating_sub(rendered.width)) / 2
         } else if self.right || self.align.as_deref() == Some("right") {
@@ -179,42 +267,47 @@ impl Text {
             0
         };
 
-        // Dither the anti-aliased f32 buffer to 1-bit raster
-        // Place the rendered text at the correct x offset within full print width
-        let raster_data = dither::generate_raster(
-            print_width,
-            rendered.height,
-            |x, y, _w, _h| {
+        // Place the anti-aliased f32 buffer at the correct x offset within full
+        // print width. Dithering (Atkinson) happens at codegen/preview time.
+        let intensities =
+            dither::generate_intensities(print_width, rendered.height, |x, y, _w, _h| {
                 let local_x = x as i32 - x_offset as i32;
                 if local_x < 0 || local_x >= rendered.width as i32 {
                     return 0.0;
                 }
                 let idx = y * rendered.width + local_x as usize;
                 rendered.data.get(idx).copied().unwrap_or(0.0)
-            },
-            dither::DitheringAlgorithm::Atkinson,
-        );
+            });
 
-        // Handle invert: flip all bits
-        let raster_data = if self.invert {
-            raster_data.iter().map(|b| !b).collect()
+        // Handle invert: flip intensities
+        let intensities = if self.invert {
+            intensities.iter().map(|v| 1.0 - v).collect()
         } else {
-            raster_data
+            intensities
         };
 
-        ops.push(Op::Raster {
+        ops.push(Op::GrayRaster {
             width: print_width as u16,
             height: rendered.height as u16,
-            data: raster_data,
+            data: dither::to_levels(&intensities),
+            dither: dither::DitheringAlgorithm::Atkinson,
         });
     }
 
-    /// Emit text with emoji using bitmap fonts (no custom font specified).
+    /// Emit text the printer's fonts can't print as a raster line (no custom
+    /// font specified).
     ///
-    /// Uses the standard bitmap font system (Spleen) for regular characters
-    /// and emoji sprites for supported emoji. Both are 1-bit, so no dithering needed.
-    fn emit_with_emoji(&self, ops: &mut Vec<Op>) {
-        let print_width: usize = 576;
+    /// Uses the standard bitmap font system (Spleen) for regular characters,
+    /// emoji sprites for supported emoji, and the TTF renderer (thresholded
+    /// to 1-bit) for runs of characters no code page has, such as CJK. All
+    /// of it is 1-bit, so no dithering needed. The raster is a line of its
+    /// own, even for inline text.
+    ///
+    /// With `grid`, each TTF run is fitted to the character cells it takes,
+    /// so the line lines up with the printed text lines of a table or
+    /// columns.
+    pub(super) fn emit_as_raster(&self, grid: bool, ctx: &EmitContext, ops: &mut Vec<Op>) {
+        let print_width = ctx.print_width();
 
         // Determine font based on size field
         let [h, _w] = self.size;
@@ -237,7 +330,7 @@ impl Text {
             match segment {
                 emoji::TextSegment::Emoji(ch) => {
                     if let Some(sprite) = emoji::get_emoji_bitmap(*ch, char_height) {
-                        glyphs.push(GlyphData::Emoji {
+                        glyphs.push(GlyphData::Sprite {
                             width: sprite.width,
                             height: sprite.height,
                             data: sprite.data,
@@ -247,7 +340,7 @@ impl Text {
                 }
                 emoji::TextSegment::KeycapEmoji(seq) => {
                     if let Some(sprite) = emoji::get_keycap_bitmap(seq, char_height) {
-                        glyphs.push(GlyphData::Emoji {
+                        glyphs.push(GlyphData::Sprite {
                             width: sprite.width,
                             height: sprite.height,
                             data: sprite.data,
@@ -256,15 +349,26 @@ impl Text {
                     }
                 }
                 emoji::TextSegment::Text(text) => {
-                    for ch in text.chars() {
-                        let glyph = generate_glyph(font, ch);
-                        glyphs.push(GlyphData::Char {
-                            width: metrics.char_width,
-                            height: metrics.char_height,
-                            data: glyph,
-                            scale: height_mult,
-                        });
-                        total_width += char_width;
+                    for (printable, run) in printable_runs(text) {
+                        if !printable {
+                            let mut sprite = ttf_sprite(run, char_height, print_width);
+                            if grid {
+                                sprite = sprite.fit_width(run.chars().count() * char_width);
+                            }
+                            total_width += sprite.width();
+                            glyphs.push(sprite);
+                            continue;
+                        }
+                        for ch in run.chars() {
+                            let glyph = generate_glyph(font, ch);
+                            glyphs.push(GlyphData::Char {
+                                width: metrics.char_width,
+                                height: metrics.char_height,
+                                data: glyph,
+                                scale: height_mult,
+                            });
+                            total_width += char_width;
+                        }
                     }
                 }
             }
@@ -290,12 +394,12 @@ impl Text {
         // Second pass: render glyphs into buffer
         for glyph in &glyphs {
             match glyph {
-                GlyphData::Emoji {
+                GlyphData::Sprite {
                     width,
                     height,
                     data,
                 } => {
-                    // Copy emoji sprite to buffer
+                    // Copy sprite to buffer
                     for y in 0..*height {
                         for x in 0..*width {
                             let src_idx = y * width + x;
@@ -385,9 +489,9 @@ impl Text {
     ///
     /// Uses TTF rendering for regular characters and emoji sprites for emoji.
     /// Both produce f32 buffers, dithered with Atkinson.
-    fn emit_with_font_and_emoji(&self, font_name: &str, ops: &mut Vec<Op>) {
+    fn emit_with_font_and_emoji(&self, font_name: &str, ctx: &EmitContext, ops: &mut Vec<Op>) {
         let pixel_height = ttf_font::size_to_pixel_height(self.size);
-        let print_width: usize = 576;
+        let print_width = ctx.print_width();
         let target_height = pixel_height.ceil() as usize;
 
         // Parse text into segments (handles both single-char and keycap emoji)
@@ -513,36 +617,27 @@ impl Text {
             cursor_x += width;
         }
 
-        // Dither to 1-bit raster
-        let raster_data = dither::generate_raster(
-            print_width,
-            target_height,
-            |x, y, _w, _h| {
-                let idx = y * print_width + x;
-                buffer.get(idx).copied().unwrap_or(0.0)
-            },
-            dither::DitheringAlgorithm::Atkinson,
-        );
-
         // Handle invert
-        let raster_data = if self.invert {
-            raster_data.iter().map(|b| !b).collect()
-        } else {
-            raster_data
-        };
+        if self.invert {
+            for v in &mut buffer {
+                *v = 1.0 - *v;
+            }
+        }
 
-        ops.push(Op::Raster {
+        // Dithered to 1-bit (Atkinson) at codegen/preview time
+        ops.push(Op::GrayRaster {
             width: print_width as u16,
             height: target_height as u16,
-            data: raster_data,
+            data: dither::to_levels(&buffer),
+            dither: dither::DitheringAlgorithm::Atkinson,
         });
     }
 }
 
 /// Glyph data for compositing text with emoji.
 enum GlyphData {
-    /// Emoji sprite (already at target size).
-    Emoji {
+    /// Emoji sprite or TTF-rendered run (already at target size).
+    Sprite {
         width: usize,
         height: usize,
         data: Vec<u8>,
@@ -556,6 +651,186 @@ enum GlyphData {
     },
 }
 
+impl GlyphData {
+    /// Width in dots once placed.
+    fn width(&self) -> usize {
+        match self {
+            GlyphData::Sprite { width, .. } => *width,
+            GlyphData::Char { width, scale, .. } => width * scale,
+        }
+    }
+
+    /// Fit a sprite to exactly `width` dots: squeezed when it's wider,
+    /// centered when it's narrower.
+    fn fit_width(self, width: usize) -> GlyphData {
+        let GlyphData::Sprite {
+            width: from,
+            height,
+            data,
+        } = self
+        else {
+            return self;
+        };
+        if from == 0 || from == width {
+            return GlyphData::Sprite {
+                width: from,
+                height,
+                data,
+            };
+        }
+        let mut fitted = vec![0u8; width * height];
+        let offset = width.saturating_sub(from) / 2;
+        for y in 0..height {
+            let row = &data[y * from..(y + 1) * from];
+            for x in 0..width {
+                // Squeezed columns keep any dot of the columns they merge
+                let set = if from > width {
+                    let (start, end) = (x * from / width, (x + 1) * from / width);
+                    row[start..end.max(start + 1)].iter().any(|&dot| dot != 0)
+                } else {
+                    x >= offset && x - offset < from && row[x - offset] != 0
+                };
+                fitted[y * width + x] = set as u8;
+            }
+        }
+        GlyphData::Sprite {
+            width,
+            height,
+            data: fitted,
+        }
+    }
+}
+
+/// Bold, underline and invert, as set by inline markup.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct InlineStyle {
+    bold: bool,
+    underline: bool,
+    invert: bool,
+}
+
+impl InlineStyle {
+    /// The flag a markup delimiter toggles.
+    fn flag(&mut self, delimiter: char) -> &mut bool {
+        match delimiter {
+            '*' => &mut self.bold,
+            '_' => &mut self.underline,
+            _ => &mut self.invert,
+        }
+    }
+
+    /// Push the set ops that change this style to `target`.
+    fn switch_to(&mut self, target: InlineStyle, ops: &mut Vec<Op>) {
+        if self.bold != target.bold {
+            ops.push(Op::SetBold(target.bold));
+        }
+        if self.underline != target.underline {
+            ops.push(Op::SetUnderline(target.underline));
+        }
+        if self.invert != target.invert {
+            ops.push(Op::SetInvert(target.invert));
+        }
+        *self = target;
+    }
+}
+
+/// Inline markup delimiters: bold, underline, invert.
+const MARKUP_DELIMITERS: [char; 3] = ['*', '_', '~'];
+
+/// Split content with inline markup into styled runs.
+///
+/// Like Markdown emphasis, a delimiter opens a span before a non-space
+/// character and closes it after one, so `*bold*` is bold while `5 * 3`
+/// and `snake_case` stay as they are. Delimiters that aren't closed are
+/// kept, and a backslash makes one literal.
+fn parse_markup(content: &str) -> Vec<(InlineStyle, String)> {
+    let chars: Vec<char> = content.chars().collect();
+    let mut runs: Vec<(InlineStyle, String)> = Vec::new();
+    let mut style = InlineStyle::default();
+    let mut i = 0;
+    while i < chars.len() {
+        let mut ch = chars[i];
+        if ch == '\\'
+            && chars
+                .get(i + 1)
+                .is_some_and(|c| MARKUP_DELIMITERS.contains(c))
+        {
+            i += 1;
+            ch = chars[i];
+        } else if MARKUP_DELIMITERS.contains(&ch) {
+            let open = *style.flag(ch);
+            if (open && closes_span(&chars, i)) || (!open && opens_span(&chars, i)) {
+                *style.flag(ch) = !open;
+                i += 1;
+                continue;
+            }
+        }
+        match runs.last_mut() {
+            Some((last, run)) if *last == style => run.push(ch),
+            _ => runs.push((style, ch.to_string())),
+        }
+        i += 1;
+    }
+    runs
+}
+
+/// Whether the delimiter at `i` can close a span: it follows a non-space
+/// character and no letter or digit follows it.
+fn closes_span(chars: &[char], i: usize) -> bool {
+    i > 0
+        && !chars[i - 1].is_whitespace()
+        && chars[i - 1] != '\\'
+        && !chars.get(i + 1).is_some_and(|c| c.is_alphanumeric())
+}
+
+/// Whether the delimiter at `i` opens a span: no letter or digit comes
+/// before it, a non-space character follows it, and a later one closes it.
+fn opens_span(chars: &[char], i: usize) -> bool {
+    let delimiter = chars[i];
+    !(i > 0 && chars[i - 1].is_alphanumeric())
+        && chars
+            .get(i + 1)
+            .is_some_and(|c| !c.is_whitespace() && *c != delimiter)
+        && (i + 2..chars.len()).any(|j| chars[j] == delimiter && closes_span(chars, j))
+}
+
+/// Split `text` into runs the printer's code pages can and can't print.
+fn printable_runs(text: &str) -> Vec<(bool, &str)> {
+    let mut runs: Vec<(bool, &str)> = Vec::new();
+    let mut start = 0;
+    for (i, ch) in text.char_indices() {
+        let printable = codepage::is_printable(ch);
+        match runs.last_mut() {
+            Some((last, run)) if *last == printable => {
+                *run = &text[start..i + ch.len_utf8()];
+            }
+            _ => {
+                start = i;
+                runs.push((printable, &text[i..i + ch.len_utf8()]));
+            }
+        }
+    }
+    runs
+}
+
+/// Render a run with the TTF renderer as a 1-bit sprite `height` dots tall.
+fn ttf_sprite(run: &str, height: usize, max_width: usize) -> GlyphData {
+    let rendered = ttf_font::render_ttf_text(run, "ibm", false, height as f32, max_width);
+    let mut data = vec![0u8; rendered.width * height];
+    for y in 0..rendered.height.min(height) {
+        for x in 0..rendered.width {
+            if rendered.data[y * rendered.width + x] >= 0.5 {
+                data[y * rendered.width + x] = 1;
+            }
+        }
+    }
+    GlyphData::Sprite {
+        width: rendered.width,
+        height,
+        data,
+    }
+}
+
 /// Content segment for mixed text/emoji rendering.
 enum ContentSegment {
     Text(String),
@@ -565,7 +840,7 @@ enum ContentSegment {
 
 impl Header {
     /// Emit IR ops for this header component.
-    pub fn emit(&self, ops: &mut Vec<Op>) {
+    pub fn emit(&self, ctx: &EmitContext, ops: &mut Vec<Op>) {
         let variant = self.variant.as_deref().unwrap_or("normal");
         let text = match variant {
             "small" => Text {
@@ -582,21 +857,17 @@ impl Header {
                 ..Default::default()
             },
         };
-        text.emit(ops);
+        text.emit(ctx, ops);
     }
 }
 
 impl LineItem {
     /// Emit IR ops for this line item component.
-    pub fn emit(&self, ops: &mut Vec<Op>) {
-        let width = self.width.unwrap_or(48);
+    pub fn emit(&self, ctx: &EmitContext, ops: &mut Vec<Op>) {
+        let width = self.width.unwrap_or(ctx.chars_per_line(Font::A));
         let price_str = format!("{:.2}", self.price);
         let name_max_width = width.saturating_sub(price_str.len() + 1);
-        let name = if self.name.len() > name_max_width {
-            &self.name[..name_max_width]
-        } else {
-            &self.name
-        };
+        let name = truncate_chars(&self.name, name_max_width);
         let padding = width.saturating_sub(name.len() + price_str.len());
         let line = format!(
             "{}{:>pad$}",
@@ -605,7 +876,7 @@ impl LineItem {
             pad = padding + price_str.len()
         );
 
-        // Reset to Font A to ensure correct width (48 chars × 12 dots = 576 = full print width)
+        // Reset to Font A so the width in characters fills the print width
         ops.push(Op::SetFont(Font::A));
         ops.push(Op::SetAlign(Alignment::Left));
         ops.push(Op::Text(line));
@@ -615,7 +886,7 @@ impl LineItem {
 
 impl Total {
     /// Emit IR ops for this total component.
-    pub fn emit(&self, ops: &mut Vec<Op>) {
+    pub fn emit(&self, _ctx: &EmitContext, ops: &mut Vec<Op>) {
         let label = self.label.as_deref().unwrap_or("TOTAL:");
         let bold = self.bold.unwrap_or(self.label.is_none());
         let right_align = match self.align.as_deref() {
@@ -649,19 +920,183 @@ impl Total {
     }
 }
 
+/// The first `max_chars` characters of `s`.
+///
+/// Content comes from documents, so it can't be cut at a byte index.
+pub(super) fn truncate_chars(s: &str, max_chars: usize) -> &str {
+    match s.char_indices().nth(max_chars) {
+        Some((end, _)) => &s[..end],
+        None => s,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::ir::WrapMode;
+
+    #[test]
+    fn test_truncate_chars() {
+        assert_eq!(truncate_chars("Crème brûlée", 4), "Crèm");
+        assert_eq!(truncate_chars("Café", 10), "Café");
+        assert_eq!(truncate_chars("🍩🍩", 1), "🍩");
+    }
 
     #[test]
     fn test_simple_text() {
         let text = Text::new("Hello");
         let mut ops = Vec::new();
-        text.emit(&mut ops);
+        text.emit(&EmitContext::default(), &mut ops);
         assert!(ops.iter().any(|op| *op == Op::Text("Hello".into())));
         assert!(ops.iter().any(|op| *op == Op::Newline));
     }
 
+    #[test]
+    fn test_unprintable_characters_become_raster() {
+        let text = Text {
+            content: "Sushi 寿司 5€".into(),
+            center: true,
+            ..Default::default()
+        };
+        let mut ops = Vec::new();
+        text.emit(&EmitContext::default(), &mut ops);
+        assert!(!ops.iter().any(|op| matches!(op, Op::Text(_))));
+        let Some(Op::Raster { width, height, .. }) = ops.first() else {
+            panic!("expected a raster, got {:?}", ops);
+        };
+        assert_eq!((*width, *height), (576, 24));
+
+        // Everything on a code page stays text
+        let mut ops = Vec::new();
+        Text::new("Łódź ─ 5€").emit(&EmitContext::default(), &mut ops);
+        assert!(ops.contains(&Op::Text("Łódź ─ 5€".into())));
+
+        assert_eq!(
+            printable_runs("ab寿司c"),
+            vec![(true, "ab"), (false, "寿司"), (true, "c")]
+        );
+    }
+
+    #[test]
+    fn test_parse_markup() {
+        let plain = InlineStyle::default();
+        let bold = InlineStyle {
+            bold: true,
+            ..plain
+        };
+        let runs = parse_markup(r"Now *$5* \*each\*, 5 * 3 for snake_case");
+        assert_eq!(
+            runs,
+            vec![
+                (plain, "Now ".to_string()),
+                (bold, "$5".to_string()),
+                (plain, " *each*, 5 * 3 for snake_case".to_string()),
+            ]
+        );
+
+        // Nested spans, and an unclosed delimiter stays
+        let runs = parse_markup("*a _b_* ~c");
+        assert_eq!(
+            runs[1].0,
+            InlineStyle {
+                underline: true,
+                ..bold
+            }
+        );
+        assert_eq!(runs.last().unwrap().1, " ~c");
+    }
+
+    #[test]
+    fn test_text_markup() {
+        let text = Text {
+            content: "Door *B* is ~closed~".into(),
+            markup: true,
+            underline: true,
+            ..Default::default()
+        };
+        let mut ops = Vec::new();
+        text.emit(&EmitContext::default(), &mut ops);
+        let start = ops
+            .iter()
+            .position(|op| *op == Op::SetUnderline(true))
+            .unwrap();
+        assert_eq!(
+            ops[start + 1..start + 9],
+            [
+                Op::Text("Door ".into()),
+                Op::SetBold(true),
+                Op::Text("B".into()),
+                Op::SetBold(false),
+                Op::Text(" is ".into()),
+                Op::SetInvert(true),
+                Op::Text("closed".into()),
+                Op::SetInvert(false),
+            ]
+        );
+
+        // Without `markup` the delimiters are text
+        let mut ops = Vec::new();
+        Text::new("*B*").emit(&EmitContext::default(), &mut ops);
+        assert!(ops.contains(&Op::Text("*B*".into())));
+    }
+
+    #[test]
+    fn test_text_wrap() {
+        let text = Text {
+            content: "Pickup at the side door, ring twice".into(),
+            wrap: WrapMode::Word,
+            hanging_indent: 2,
+            size: [0, 0],
+            ..Default::default()
+        };
+        let mut ops = Vec::new();
+        text.emit(&EmitContext::default(), &mut ops);
+        let set_wrap = Op::SetWrap(Wrap {
+            mode: WrapMode::Word,
+            hanging_indent: 2,
+        });
+        assert!(ops.contains(&set_wrap));
+        assert_eq!(ops.last(), Some(&Op::SetWrap(Wrap::default())));
+
+        // Default wrapping adds no ops
+        let mut ops = Vec::new();
+        Text::new("Hi").emit(&EmitContext::default(), &mut ops);
+        assert!(!ops.iter().any(|op| matches!(op, Op::SetWrap(_))));
+
+        let json = r#"{"content": "x", "wrap": "char", "hanging_indent": 4}"#;
+        let text: Text = serde_json::from_str(json).unwrap();
+        assert_eq!((text.wrap, text.hanging_indent), (WrapMode::Char, 4));
+    }
+
+    #[test]
+    fn test_rtl_text() {
+        let text = Text::new("שלום 12");
+        let display = text.in_display_order();
+        assert_eq!(display.content, "12 םולש");
+        assert!(display.right, "right-to-left text aligns right");
+
+        let centered = Text {
+            center: true,
+            ..text.clone()
+        };
+        assert!(!centered.in_display_order().right);
+
+        let mut ops = Vec::new();
+        text.emit(&EmitContext::default(), &mut ops);
+        assert!(matches!(ops.first(), Some(Op::Raster { width: 576, .. })));
+
+        // Runs in a grid line are fitted to their cells
+        let sprite = GlyphData::Sprite {
+            width: 4,
+            height: 1,
+            data: vec![1, 0, 0, 1],
+        };
+        let GlyphData::Sprite { data, .. } = sprite.fit_width(2) else {
+            unreachable!();
+        };
+        assert_eq!(data, vec![1, 1]);
+    }
+
     #[test]
     fn test_inline_text() {
         let text = Text {
@@ -670,7 +1105,7 @@ mod tests {
             ..Default::default()
         };
         let mut ops = Vec::new();
-        text.emit(&mut ops);
+        text.emit(&EmitContext::default(), &mut ops);
         assert!(ops.iter().any(|op| *op == Op::Text("Hello".into())));
         assert!(!ops.iter().any(|op| *op == Op::Newline));
     }
@@ -683,7 +1118,7 @@ mod tests {
             ..Default::default()
         };
         let mut ops = Vec::new();
-        text.emit(&mut ops);
+        text.emit(&EmitContext::default(), &mut ops);
         let bold_on = ops.iter().position(|op| *op == Op::SetBold(true));
         let text_pos = ops.iter().position(|op| *op == Op::Text("Bold".into()));
         let bold_off = ops.iter().position(|op| *op == Op::SetBold(false));
@@ -699,7 +1134,7 @@ mod tests {
             ..Default::default()
         };
         let mut ops = Vec::new();
-        text.emit(&mut ops);
+        text.emit(&EmitContext::default(), &mut ops);
         assert!(ops.iter().any(|op| *op == Op::SetAlign(Alignment::Center)));
     }
 
@@ -712,7 +1147,7 @@ mod tests {
             ..Default::default()
         };
         let mut ops = Vec::new();
-        text.emit(&mut ops);
+        text.emit(&EmitContext::default(), &mut ops);
         assert!(ops.iter().any(|op| *op == Op::SetAlign(Alignment::Right)));
         assert!(!ops.iter().any(|op| *op == Op::SetAlign(Alignment::Center)));
     }
@@ -721,7 +1156,7 @@ mod tests {
     fn test_header_normal() {
         let header = Header::new("STORE");
         let mut ops = Vec::new();
-        header.emit(&mut ops);
+        header.emit(&EmitContext::default(), &mut ops);
         assert!(ops.iter().any(|op| *op == Op::SetAlign(Alignment::Center)));
         assert!(ops.iter().any(|op| *op == Op::SetBold(true)));
         assert!(ops.iter().any(|op| *op
@@ -738,7 +1173,7 @@ mod tests {
             variant: Some("small".into()),
         };
         let mut ops = Vec::new();
-        header.emit(&mut ops);
+        header.emit(&EmitContext::default(), &mut ops);
         assert!(ops.iter().any(|op| *op == Op::SetBold(true)));
         assert!(!ops.iter().any(|op| matches!(op, Op::SetSize { .. })));
     }
@@ -747,7 +1182,7 @@ mod tests {
     fn test_line_item() {
         let item = LineItem::new("Coffee", 4.50);
         let mut ops = Vec::new();
-        item.emit(&mut ops);
+        item.emit(&EmitContext::default(), &mut ops);
         let has_formatted_line = ops.iter().any(|op| {
             if let Op::Text(s) = op {
                 s.contains("Coffee") && s.contains("4.50")
@@ -762,7 +1197,7 @@ mod tests {
     fn test_total_default() {
         let total = Total::new(19.99);
         let mut ops = Vec::new();
-        total.emit(&mut ops);
+        total.emit(&EmitContext::default(), &mut ops);
         assert!(ops.iter().any(|op| *op == Op::SetBold(true)));
         assert!(ops.iter().any(|op| *op == Op::SetAlign(Alignment::Right)));
         let has_total = ops.iter().any(|op| {
@@ -784,7 +1219,7 @@ mod tests {
             ..Default::default()
         };
         let mut ops = Vec::new();
-        total.emit(&mut ops);
+        total.emit(&EmitContext::default(), &mut ops);
         assert!(!ops.iter().any(|op| *op == Op::SetBold(true)));
     }
 
@@ -796,7 +1231,7 @@ mod tests {
             ..Default::default()
         };
         let mut ops = Vec::new();
-        text.emit(&mut ops);
+        text.emit(&EmitContext::default(), &mut ops);
         assert!(ops.contains(&Op::SetSmoothing(true)));
         assert!(ops.contains(&Op::SetSmoothing(false)));
     }
@@ -809,7 +1244,7 @@ mod tests {
             ..Default::default()
         };
         let mut ops = Vec::new();
-        text.emit(&mut ops);
+        text.emit(&EmitContext::default(), &mut ops);
         assert!(ops.contains(&Op::SetSmoothing(true)));
         assert!(ops.contains(&Op::SetSmoothing(false)));
     }
@@ -818,7 +1253,7 @@ mod tests {
     fn test_no_auto_smoothing_normal_text() {
         let text = Text::new("normal");
         let mut ops = Vec::new();
-        text.emit(&mut ops);
+        text.emit(&EmitContext::default(), &mut ops);
         assert!(!ops.iter().any(|op| matches!(op, Op::SetSmoothing(_))));
     }
 
@@ -830,7 +1265,7 @@ mod tests {
             ..Default::default()
         };
         let mut ops = Vec::new();
-        text.emit(&mut ops);
+        text.emit(&EmitContext::default(), &mut ops);
         assert!(ops.contains(&Op::SetSmoothing(true)));
         assert!(!ops.contains(&Op::SetSmoothing(false)));
     }
@@ -843,7 +1278,7 @@ mod tests {
             ..Default::default()
         };
         let mut ops = Vec::new();
-        text.emit(&mut ops);
+        text.emit(&EmitContext::default(), &mut ops);
         assert!(ops.contains(&Op::SetFont(Font::B)));
         assert!(!ops.iter().any(|op| matches!(op, Op::SetSize { .. })));
     }
@@ -852,7 +1287,7 @@ mod tests {
     fn test_size_1_uses_font_a_no_expansion() {
         let text = Text::new("normal");
         let mut ops = Vec::new();
-        text.emit(&mut ops);
+        text.emit(&EmitContext::default(), &mut ops);
         assert!(ops.contains(&Op::SetFont(Font::A)));
         assert!(!ops.iter().any(|op| matches!(op, Op::SetSize { .. })));
     }
@@ -865,7 +1300,7 @@ mod tests {
             ..Default::default()
         };
         let mut ops = Vec::new();
-        text.emit(&mut ops);
+        text.emit(&EmitContext::default(), &mut ops);
         assert!(ops.contains(&Op::SetFont(Font::A)));
         assert!(ops.contains(&Op::SetSize {
             height: 2,
@@ -881,7 +1316,7 @@ mod tests {
             ..Default::default()
         };
         let mut ops = Vec::new();
-        text.emit(&mut ops);
+        text.emit(&EmitContext::default(), &mut ops);
         assert!(ops.contains(&Op::SetFont(Font::A)));
         // [3, 1] → ESC i [2, 0] — height expansion only
         assert!(ops.contains(&Op::SetSize {
@@ -902,7 +1337,7 @@ mod tests {
             ..Default::default()
         };
         let mut ops = Vec::new();
-        text.emit(&mut ops);
+        text.emit(&EmitContext::default(), &mut ops);
 
         // Should have a Raster op
         assert!(
@@ -921,7 +1356,7 @@ mod tests {
         // Text without emoji should emit normal Text op
         let text = Text::new("Hello World");
         let mut ops = Vec::new();
-        text.emit(&mut ops);
+        text.emit(&EmitContext::default(), &mut ops);
 
         // Should have a Text op
         assert!(
@@ -944,12 +1379,12 @@ mod tests {
             ..Default::default()
         };
         let mut ops = Vec::new();
-        text.emit(&mut ops);
+        text.emit(&EmitContext::default(), &mut ops);
 
-        // Should have a Raster op
+        // Should have a GrayRaster op (anti-aliased TTF, dithered late)
         assert!(
-            ops.iter().any(|op| matches!(op, Op::Raster { .. })),
-            "Text with emoji and custom font should emit Raster op"
+            ops.iter().any(|op| matches!(op, Op::GrayRaster { .. })),
+            "Text with emoji and custom font should emit GrayRaster op"
         );
     }
 
@@ -961,7 +1396,7 @@ mod tests {
             ..Default::default()
         };
         let mut ops = Vec::new();
-        text.emit(&mut ops);
+        text.emit(&EmitContext::default(), &mut ops);
 
         if let Some(Op::Raster {
             width,
@@ -0,0 +1,312 @@
+//! Emit logic for the OrderTicket component.
+//!
+//! A ticket is assembled from the regular text components (banner, columns,
+//! inverted text), so it prints as device text and stays sharp and fast.
+//! Per-station copies are separated by a partial cut.
+
+use super::EmitContext;
+use super::types::{
+    Banner, BorderStyle, Columns, Divider, DividerStyle, Order, OrderItem, OrderTicket, Text,
+};
+use crate::ir::Op;
+use crate::protocol::text::Font;
+
+/// Indent for modifiers, notes and allergen lines under an item.
+const DETAIL_INDENT: &str = "     ";
+
+impl OrderTicket {
+    /// Emit IR ops for this order ticket.
+    pub fn emit(&self, ctx: &EmitContext, ops: &mut Vec<Op>) {
+        let tickets: Vec<Option<&str>> = match (&self.station, self.per_station) {
+            (Some(station), _) => vec![Some(station.as_str())],
+            (None, true) => {
+                let stations = self.stations();
+                if stations.is_empty() {
+                    vec![None]
+                } else {
+                    stations.into_iter().map(Some).collect()
+                }
+            }
+            (None, false) => vec![None],
+        };
+
+        let count = tickets.len();
+        for (i, station) in tickets.into_iter().enumerate() {
+            if i > 0 {
+                ops.push(Op::Feed { units: 24 });
+                ops.push(Op::Cut { partial: true });
+            }
+            let header = station.map(|s| {
+                if count > 1 {
+                    format!("{} {}/{}", s.to_uppercase(), i + 1, count)
+                } else {
+                    s.to_uppercase()
+                }
+            });
+            emit_ticket(&self.order, header.as_deref(), station, ctx, ops);
+        }
+    }
+
+    /// Whether any item goes on `station`'s ticket.
+    pub fn serves(&self, station: &str) -> bool {
+        self.order
+            .items
+            .iter()
+            .any(|item| on_station(item, Some(station)))
+    }
+
+    /// Stations named by the order's items, in order of first appearance.
+    pub fn stations(&self) -> Vec<&str> {
+        let mut stations: Vec<&str> = Vec::new();
+        for station in self.order.items.iter().filter_map(|i| i.station.as_deref()) {
+            if !stations.iter().any(|s| s.eq_ignore_ascii_case(station)) {
+                stations.push(station);
+            }
+        }
+        stations
+    }
+}
+
+/// Whether an item is printed on `station`'s ticket. Items without a
+/// station go on every ticket.
+fn on_station(item: &OrderItem, station: Option<&str>) -> bool {
+    match (station, item.station.as_deref()) {
+        (Some(station), Some(own)) => own.eq_ignore_ascii_case(station),
+        _ => true,
+    }
+}
+
+fn emit_ticket(
+    order: &Order,
+    header: Option<&str>,
+    station: Option<&str>,
+    ctx: &EmitContext,
+    ops: &mut Vec<Op>,
+) {
+    if let Some(header) = header {
+        // Double width: half the characters of a normal line
+        let width = ctx.chars_per_line(Font::A) / 2;
+        Text {
+            content: format!("{:^width$}", header, width = width),
+            bold: true,
+            invert: true,
+            size: [2, 2],
+            ..Default::default()
+        }
+        .emit(ctx, ops);
+    }
+
+    Banner {
+        content: format!("#{}", order.number),
+        border: BorderStyle::Heavy,
+        ..Default::default()
+    }
+    .emit(ctx, ops);
+
+    let service = [
+        order.service.clone(),
+        order.table.as_ref().map(|t| format!("TABLE {}", t)),
+    ]
+    .into_iter()
+    .flatten()
+    .collect::<Vec<_>>()
+    .join(", ");
+    let details = [
+        (service, order.time.clone().unwrap_or_default()),
+        (
+            order.server.clone().unwrap_or_default(),
+            match item_count(order, station) {
+                1 => "1 item".to_string(),
+                n => format!("{} items", n),
+            },
+        ),
+    ];
+    for (left, right) in details {
+        if left.is_empty() && right.is_empty() {
+            continue;
+        }
+        Columns {
+            bold: true,
+            ..Columns::new(left, right)
+        }
+        .emit(ctx, ops);
+    }
+
+    let divider = Divider {
+        style: DividerStyle::Solid,
+        ..Default::default()
+    };
+    divider.emit(ctx, ops);
+    for item in order.items.iter().filter(|i| on_station(i, station)) {
+        emit_item(item, ctx, ops);
+    }
+    divider.emit(ctx, ops);
+
+    if let Some(notes) = order.notes.as_deref().filter(|n| !n.is_empty()) {
+        Text {
+            content: format!("NOTE: {}", notes),
+            bold: true,
+            ..Default::default()
+        }
+        .emit(ctx, ops);
+    }
+}
+
+fn emit_item(item: &OrderItem, ctx: &EmitContext, ops: &mut Vec<Op>) {
+    // Double height keeps a full line of characters but reads across the pass
+    Text {
+        content: format!("{:>2}x {}", item.qty, item.name),
+        bold: true,
+        size: [2, 1],
+        ..Default::default()
+    }
+    .emit(ctx, ops);
+
+    for modifier in &item.modifiers {
+        Text::new(format!("{}- {}", DETAIL_INDENT, modifier)).emit(ctx, ops);
+    }
+    if let Some(note) = item.note.as_deref().filter(|n| !n.is_empty()) {
+        Text::new(format!("{}> {}", DETAIL_INDENT, note)).emit(ctx, ops);
+    }
+    if !item.allergens.is_empty() {
+        // Only the warning is inverted, not the indent before it
+        Text {
+            content: DETAIL_INDENT.into(),
+            is_inline: true,
+            ..Default::default()
+        }
+        .emit(ctx, ops);
+        Text {
+            content: format!(" ALLERGY: {} ", item.allergens.join(", ").to_uppercase()),
+            bold: true,
+            invert: true,
+            ..Default::default()
+        }
+        .emit(ctx, ops);
+    }
+}
+
+/// Total quantity of the items on `station`'s ticket.
+fn item_count(order: &Order, station: Option<&str>) -> u32 {
+    order
+        .items
+        .iter()
+        .filter(|i| on_station(i, station))
+        .map(|i| i.qty)
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document::testing::texts;
+
+    fn order() -> Order {
+        Order {
+            number: "42".into(),
+            service: Some("DINE IN".into()),
+            table: Some("12".into()),
+            items: vec![
+                OrderItem {
+                    qty: 2,
+                    modifiers: vec!["no onions".into()],
+                    station: Some("grill".into()),
+                    ..OrderItem::new("Burger")
+                },
+                OrderItem {
+                    allergens: vec!["fish".into(), "egg".into()],
+                    station: Some("Cold".into()),
+                    ..OrderItem::new("Caesar")
+                },
+                OrderItem::new("Water"),
+                OrderItem {
+                    station: Some("GRILL".into()),
+                    ..OrderItem::new("Fries")
+                },
+            ],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_single_ticket_has_all_items() {
+        let mut ops = Vec::new();
+        OrderTicket {
+            order: order(),
+            ..Default::default()
+        }
+        .emit(&EmitContext::default(), &mut ops);
+
+        let texts = texts(&ops);
+        assert!(texts.iter().any(|t| t.contains("#42")));
+        assert!(texts.contains(&" 2x Burger"));
+        assert!(texts.contains(&"     - no onions"));
+        assert!(texts.iter().any(|t| t.starts_with("DINE IN, TABLE 12")));
+        assert!(texts.iter().any(|t| t.ends_with("5 items")));
+        assert!(!ops.iter().any(|op| matches!(op, Op::Cut { .. })));
+    }
+
+    #[test]
+    fn test_allergens_are_inverted() {
+        let mut ops = Vec::new();
+        OrderTicket {
+            order: order(),
+            ..Default::default()
+        }
+        .emit(&EmitContext::default(), &mut ops);
+
+        let warning = ops
+            .iter()
+            .position(|op| *op == Op::Text(" ALLERGY: FISH, EGG ".into()))
+            .unwrap();
+        assert_eq!(ops[warning - 1], Op::SetInvert(true));
+    }
+
+    #[test]
+    fn test_per_station_tickets() {
+        let ticket = OrderTicket {
+            order: order(),
+            per_station: true,
+            ..Default::default()
+        };
+        assert_eq!(ticket.stations(), ["grill", "Cold"]);
+
+        let mut ops = Vec::new();
+        ticket.emit(&EmitContext::default(), &mut ops);
+        let cut = ops
+            .iter()
+            .position(|op| matches!(op, Op::Cut { .. }))
+            .unwrap();
+        assert_eq!(
+            ops.iter().filter(|op| matches!(op, Op::Cut { .. })).count(),
+            1
+        );
+
+        // Grill gets its items plus the unassigned water, not the salad
+        let grill = texts(&ops[..cut]);
+        assert!(grill.iter().any(|t| t.trim() == "GRILL 1/2"));
+        assert!(grill.contains(&" 1x Fries"));
+        assert!(grill.contains(&" 1x Water"));
+        assert!(!grill.contains(&" 1x Caesar"));
+
+        let cold = texts(&ops[cut..]);
+        assert!(cold.iter().any(|t| t.trim() == "COLD 2/2"));
+        assert!(cold.contains(&" 1x Caesar"));
+        assert!(cold.contains(&" 1x Water"));
+        assert!(!cold.contains(&" 2x Burger"));
+    }
+
+    #[test]
+    fn test_order_ticket_from_json() {
+        let json = r#"{"document": [{"type": "order_ticket", "station": "bar",
+            "order": {"number": "7", "items": [{"name": "Negroni", "station": "bar"}]}}]}"#;
+        let doc: crate::document::Document = serde_json::from_str(json).unwrap();
+        match &doc.document[0] {
+            crate::document::Component::OrderTicket(ticket) => {
+                assert_eq!(ticket.station.as_deref(), Some("bar"));
+                assert_eq!(ticket.order.items[0].qty, 1);
+            }
+            other => panic!("expected order ticket, got {:?}", other),
+        }
+    }
+}
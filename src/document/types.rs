@@ -9,7 +9,10 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+use crate::ir::WrapMode;
+use crate::render::alpha::Background;
 use crate::render::composer::BlendMode;
+use crate::render::halftone::Halftone;
 
 /// Metadata that every component struct must provide.
 ///
@@ -135,6 +138,19 @@ pub struct Text {
     /// Optional custom font: "ibm" for IBM Plex Sans. When set, text renders as raster.
     #[serde(default)]
     pub font: Option<String>,
+    /// How long lines wrap: "word" (default), "char" or "none" (cut off).
+    #[serde(default)]
+    pub wrap: WrapMode,
+    /// Spaces before each continuation line of a wrapped paragraph.
+    #[serde(default)]
+    pub hanging_indent: u8,
+    /// Inline markup in `content`: `*bold*`, `_underline_`, `~invert~`
+    /// (`\*` for a literal asterisk).
+    #[serde(default)]
+    pub markup: bool,
+    /// What a gift receipt does with this text (default: keep).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gift: Option<GiftMode>,
 }
 
 impl Default for Text {
@@ -157,6 +173,10 @@ impl Default for Text {
             double_height: false,
             is_inline: false,
             font: None,
+            wrap: WrapMode::Word,
+            hanging_indent: 0,
+            markup: false,
+            gift: None,
         }
     }
 }
@@ -320,6 +340,22 @@ impl Interpolatable for Banner {
     }
 }
 
+/// What a gift receipt does with a price-bearing component (see
+/// [`Document::as_gift_receipt`](super::Document::as_gift_receipt)).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GiftMode {
+    /// Print unchanged.
+    Keep,
+    /// Print without the amount: a line item's name, a total's label, the
+    /// left side of columns. Text is dropped.
+    Blank,
+    /// Leave it out.
+    Hide,
+    /// Print "GIFT RECEIPT" in its place, once per run of replaced components.
+    Replace,
+}
+
 /// Line item: name on left, price on right.
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct LineItem {
@@ -327,6 +363,9 @@ pub struct LineItem {
     pub price: f64,
     #[serde(default)]
     pub width: Option<usize>,
+    /// What a gift receipt does with this item (default: blank the price).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gift: Option<GiftMode>,
 }
 
 impl ComponentMeta for LineItem {
@@ -364,6 +403,10 @@ pub struct Total {
     /// "right" (default) or "left".
     #[serde(default)]
     pub align: Option<String>,
+    /// What a gift receipt does with this total (default: replace it with
+    /// "GIFT RECEIPT").
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gift: Option<GiftMode>,
 }
 
 impl ComponentMeta for Total {
@@ -413,8 +456,12 @@ pub enum DividerStyle {
 pub struct Divider {
     #[serde(default)]
     pub style: DividerStyle,
+    /// Width in characters (default: a full line).
     #[serde(default)]
     pub width: Option<usize>,
+    /// Print in Font B: a thinner rule that takes less paper.
+    #[serde(default)]
+    pub small: bool,
 }
 
 impl Default for Divider {
@@ -422,6 +469,7 @@ impl Default for Divider {
         Self {
             style: DividerStyle::Dashed,
             width: None,
+            small: false,
         }
     }
 }
@@ -475,6 +523,17 @@ impl Spacer {
             ..Default::default()
         }
     }
+
+    /// The space in 1/4mm feed units: `mm` if set, else `lines`, else `units`.
+    pub fn feed_units(&self) -> u8 {
+        if let Some(mm) = self.mm {
+            crate::units::mm_to_feed_units(mm)
+        } else if let Some(lines) = self.lines {
+            crate::units::mm_to_feed_units(lines as f32 * 3.0)
+        } else {
+            self.units.unwrap_or_default()
+        }
+    }
 }
 
 /// Empty line.
@@ -490,11 +549,38 @@ impl ComponentMeta for BlankLine {
     }
 }
 
-/// Two-column layout.
+/// Column layout: `left` and `right` at either edge, or any number of
+/// `columns` sharing the width by `ratios`.
+///
+/// ## Example (JSON)
+///
+/// ```json
+/// {
+///   "type": "columns",
+///   "columns": ["Espresso, double shot", "x2", "$7.00"],
+///   "ratios": [2, 1, 1],
+///   "align": ["left", "center", "right"]
+/// }
+/// ```
+///
+/// Text wraps within its column; the row is as tall as its longest cell.
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct Columns {
+    #[serde(default)]
     pub left: String,
+    #[serde(default)]
     pub right: String,
+    /// Cell text for a layout with any number of columns, one space apart.
+    /// When set, `left` and `right` are ignored.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub columns: Vec<String>,
+    /// Relative column widths (default: equal).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ratios: Vec<u32>,
+    /// Per-column alignment. Columns beyond this list default to left,
+    /// except the last one, which defaults to right.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub align: Vec<ColumnAlign>,
     #[serde(default)]
     pub width: Option<usize>,
     #[serde(default)]
@@ -503,6 +589,9 @@ pub struct Columns {
     pub underline: bool,
     #[serde(default)]
     pub invert: bool,
+    /// What a gift receipt does with these columns (default: keep).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gift: Option<GiftMode>,
 }
 
 impl ComponentMeta for Columns {
@@ -528,6 +617,285 @@ impl Columns {
     }
 }
 
+/// Definition list: labels and values lined up with dot leaders.
+///
+/// ```text
+/// Name ....... Jojo
+/// Order ...... #1042
+/// Notes ...... Leave at the front
+///              desk, ring twice
+/// ```
+///
+/// Long values wrap in the value column; a label too long for its column
+/// gets a line of its own with the value below.
+///
+/// ## Example (JSON)
+///
+/// ```json
+/// {
+///   "type": "definitions",
+///   "items": [["Name", "Jojo"], ["Order", "#1042"]],
+///   "leader": "."
+/// }
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Definitions {
+    /// `[label, value]` pairs, printed in order.
+    #[serde(default)]
+    pub items: Vec<(String, String)>,
+    /// Fills the gap between label and value (default: `.`; `" "` for none).
+    #[serde(default = "default_leader")]
+    pub leader: char,
+    /// Column values start at, in characters (default: after the longest
+    /// label, at most half the line).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub split: Option<usize>,
+    /// Line width in characters (default: the full Font A line).
+    #[serde(default)]
+    pub width: Option<usize>,
+    /// Print labels in bold.
+    #[serde(default)]
+    pub bold_labels: bool,
+}
+
+fn default_leader() -> char {
+    '.'
+}
+
+impl Default for Definitions {
+    fn default() -> Self {
+        Self {
+            items: Vec::new(),
+            leader: default_leader(),
+            split: None,
+            width: None,
+            bold_labels: false,
+        }
+    }
+}
+
+impl ComponentMeta for Definitions {
+    fn label() -> &'static str {
+        "Definitions"
+    }
+    fn editor_default() -> Self {
+        Self {
+            items: vec![
+                ("Name".into(), "Jojo".into()),
+                ("Order".into(), "#1042".into()),
+            ],
+            ..Default::default()
+        }
+    }
+}
+
+impl Definitions {
+    pub fn new<L: Into<String>, V: Into<String>>(items: impl IntoIterator<Item = (L, V)>) -> Self {
+        Self {
+            items: items
+                .into_iter()
+                .map(|(label, value)| (label.into(), value.into()))
+                .collect(),
+            ..Default::default()
+        }
+    }
+}
+
+/// Syntax a [`DataDump`] is printed in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DumpFormat {
+    /// Indented JSON.
+    #[default]
+    Json,
+    /// Block-style YAML: less punctuation, easier to read on paper.
+    Yaml,
+}
+
+/// Pretty-printed JSON value, for webhook payloads and debug info.
+///
+/// Prints in Font B with keys in bold. Long lines wrap, indented under
+/// their start; `max_depth`, `max_string` and `max_lines` keep big
+/// payloads from running on. A `data` string that holds JSON is parsed
+/// first, so `"data": "{{payload}}"` prints a payload passed in as a
+/// template variable. Object keys print in alphabetical order.
+///
+/// ## Example (JSON)
+///
+/// ```json
+/// {
+///   "type": "data_dump",
+///   "data": {"event": "door_open", "sensor": {"id": 7, "battery": 0.82}},
+///   "format": "yaml"
+/// }
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DataDump {
+    /// The value to print.
+    #[serde(default)]
+    pub data: serde_json::Value,
+    #[serde(default)]
+    pub format: DumpFormat,
+    /// Nesting levels to print; deeper objects and arrays print as `{...}`
+    /// and `[...]` (default: all).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_depth: Option<usize>,
+    /// Longest string printed in full; longer ones are cut with `...`
+    /// (default: no limit).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_string: Option<usize>,
+    /// Most lines printed, counting wrapped lines (default: no limit).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_lines: Option<usize>,
+    /// Line width in characters (default: the full Font B line).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub width: Option<usize>,
+    /// Print keys in bold.
+    #[serde(default = "default_dump_bold_keys")]
+    pub bold_keys: bool,
+}
+
+fn default_dump_bold_keys() -> bool {
+    true
+}
+
+impl Default for DataDump {
+    fn default() -> Self {
+        Self {
+            data: serde_json::Value::Null,
+            format: DumpFormat::default(),
+            max_depth: None,
+            max_string: None,
+            max_lines: None,
+            width: None,
+            bold_keys: default_dump_bold_keys(),
+        }
+    }
+}
+
+impl ComponentMeta for DataDump {
+    fn label() -> &'static str {
+        "Data Dump"
+    }
+    fn editor_default() -> Self {
+        Self {
+            data: serde_json::json!({
+                "event": "door_open",
+                "sensor": {"id": 7, "battery": 0.82},
+                "tags": ["front", "night"]
+            }),
+            ..Default::default()
+        }
+    }
+}
+
+impl DataDump {
+    pub fn new(data: serde_json::Value) -> Self {
+        Self {
+            data,
+            ..Default::default()
+        }
+    }
+}
+
+/// Progress bar style options.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProgressBarStyle {
+    /// Solid blocks (`█`) over a light shade track (`░`).
+    #[default]
+    Blocks,
+    /// Dark shade (`▓`) over a light shade track (`░`), lighter on ink.
+    Shade,
+    /// Raster graphics: a solid fill over a dithered gray track, sized in dots.
+    Raster,
+}
+
+/// Horizontal progress bar, e.g. battery level or goal completion.
+///
+/// The bar fills `value / max` of its width. Text styles print one line
+/// (`label`, bar, percentage) in Font A; `raster` prints the label and
+/// percentage on a line above a graphics bar.
+///
+/// ## Example (JSON)
+///
+/// ```json
+/// {"type": "progress_bar", "value": 75, "label": "Battery"}
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProgressBar {
+    pub value: f64,
+    /// Value of a full bar (default: 100).
+    #[serde(default)]
+    pub max: Option<f64>,
+    #[serde(default)]
+    pub label: Option<String>,
+    /// Total width: characters for text styles (default: a full line, 48
+    /// on 80mm paper), dots for `raster` (default: the print width).
+    #[serde(default)]
+    pub width: Option<usize>,
+    /// Bar height in dots, `raster` only (default: 24).
+    #[serde(default)]
+    pub height: Option<usize>,
+    #[serde(default)]
+    pub style: ProgressBarStyle,
+    /// Print the percentage after the bar.
+    #[serde(default = "default_progress_percent")]
+    pub show_percent: bool,
+}
+
+fn default_progress_percent() -> bool {
+    true
+}
+
+impl Default for ProgressBar {
+    fn default() -> Self {
+        Self {
+            value: 0.0,
+            max: None,
+            label: None,
+            width: None,
+            height: None,
+            style: ProgressBarStyle::Blocks,
+            show_percent: true,
+        }
+    }
+}
+
+impl ComponentMeta for ProgressBar {
+    fn label() -> &'static str {
+        "Progress Bar"
+    }
+    fn editor_default() -> Self {
+        Self {
+            value: 75.0,
+            label: Some("Battery".into()),
+            ..Default::default()
+        }
+    }
+}
+
+impl ProgressBar {
+    pub fn new(value: f64, max: f64) -> Self {
+        Self {
+            value,
+            max: Some(max),
+            ..Default::default()
+        }
+    }
+
+    /// Filled fraction of the bar, clamped to 0.0..=1.0.
+    pub fn fraction(&self) -> f64 {
+        let max = self.max.unwrap_or(100.0);
+        let fraction = self.value / max;
+        if max > 0.0 && fraction.is_finite() {
+            fraction.clamp(0.0, 1.0)
+        } else {
+            0.0
+        }
+    }
+}
+
 /// Column alignment for Table cells.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
@@ -549,13 +917,31 @@ pub enum ColumnAlign {
 /// - `mixed`: single borders + double header separator (╞═╪═╡)
 /// - `heavy`: full block character (█)
 /// - `shade`: medium shade character (▒)
+///
+/// ## Footers and spans
+///
+/// `footers` rows print bold below a separator, after the data rows. Any
+/// row cell can be an object with a `colspan` to cover several columns:
+///
+/// ```json
+/// {
+///   "type": "table",
+///   "headers": ["Item", "Qty", "Price"],
+///   "rows": [["Coffee", "2", "$7.00"], ["Bagel", "1", "$3.50"]],
+///   "footers": [[{"text": "Total", "colspan": 2}, "$10.50"]],
+///   "align": ["left", "right", "right"]
+/// }
+/// ```
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Table {
     /// Optional header row. If present, rendered bold with a separator below.
     #[serde(default)]
     pub headers: Option<Vec<String>>,
-    /// Data rows. Each inner Vec is one row of cell values.
-    pub rows: Vec<Vec<String>>,
+    /// Data rows. Each inner Vec is one row of cells.
+    pub rows: Vec<Vec<TableCell>>,
+    /// Footer rows, rendered bold with a separator above.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub footers: Vec<Vec<TableCell>>,
     /// Border style (default: single).
     #[serde(default)]
     pub border: BorderStyle,
@@ -565,7 +951,8 @@ pub struct Table {
     /// Draw separator lines between data rows (default: false).
     #[serde(default)]
     pub row_separator: bool,
-    /// Override total width in characters (default: 48 for Font A).
+    /// Override total width in characters (default: the Font A line width,
+    /// 48 on 80mm paper).
     #[serde(default)]
     pub width: Option<usize>,
 }
@@ -575,6 +962,7 @@ impl Default for Table {
         Self {
             headers: None,
             rows: Vec::new(),
+            footers: Vec::new(),
             border: BorderStyle::Single,
             align: Vec::new(),
             row_separator: false,
@@ -597,7 +985,7 @@ impl ComponentMeta for Table {
 }
 
 impl Table {
-    pub fn new(rows: Vec<Vec<String>>) -> Self {
+    pub fn new(rows: Vec<Vec<TableCell>>) -> Self {
         Self {
             rows,
             ..Default::default()
@@ -605,6 +993,84 @@ impl Table {
     }
 }
 
+/// One table cell. JSON accepts a plain string or
+/// `{"text": "...", "colspan": 2}`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TableCell {
+    pub text: String,
+    /// Number of columns the cell covers (at least 1).
+    pub colspan: usize,
+}
+
+impl TableCell {
+    pub fn spanning(text: impl Into<String>, colspan: usize) -> Self {
+        Self {
+            text: text.into(),
+            colspan: colspan.max(1),
+        }
+    }
+}
+
+impl From<&str> for TableCell {
+    fn from(text: &str) -> Self {
+        Self::spanning(text, 1)
+    }
+}
+
+impl From<String> for TableCell {
+    fn from(text: String) -> Self {
+        Self::spanning(text, 1)
+    }
+}
+
+impl Serialize for TableCell {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::SerializeStruct;
+
+        if self.colspan == 1 {
+            return serializer.serialize_str(&self.text);
+        }
+        let mut cell = serializer.serialize_struct("TableCell", 2)?;
+        cell.serialize_field("text", &self.text)?;
+        cell.serialize_field("colspan", &self.colspan)?;
+        cell.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for TableCell {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        fn one() -> usize {
+            1
+        }
+
+        #[derive(Deserialize)]
+        struct CellFields {
+            #[serde(default)]
+            text: String,
+            #[serde(default = "one")]
+            colspan: usize,
+        }
+
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum CellValue {
+            Text(String),
+            Fields(CellFields),
+        }
+
+        Ok(match CellValue::deserialize(deserializer)? {
+            CellValue::Text(text) => TableCell::from(text),
+            CellValue::Fields(f) => TableCell::spanning(f.text, f.colspan),
+        })
+    }
+}
+
 // ============================================================================
 // CONTENT COMPONENTS
 // ============================================================================
@@ -638,6 +1104,38 @@ impl Markdown {
     }
 }
 
+/// HTML content: a safe subset (headings, paragraphs, emphasis, rules,
+/// tables, lists, images, centering) converted to components, e.g. a
+/// receipt a service sends as HTML.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct Html {
+    pub content: String,
+    /// Print a link's URL after its text.
+    #[serde(default)]
+    pub show_urls: bool,
+}
+
+impl ComponentMeta for Html {
+    fn label() -> &'static str {
+        "HTML"
+    }
+    fn editor_default() -> Self {
+        Self {
+            content: "<h2>Order #1042</h2><p>Thanks, <b>Ada</b>!</p>".into(),
+            ..Default::default()
+        }
+    }
+}
+
+impl Html {
+    pub fn new(content: impl Into<String>) -> Self {
+        Self {
+            content: content.into(),
+            ..Default::default()
+        }
+    }
+}
+
 // ============================================================================
 // BARCODE COMPONENTS
 // ============================================================================
@@ -714,12 +1212,27 @@ impl Pdf417 {
 /// 1D barcode.
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct Barcode {
-    /// "code39", "code128", "ean13", "upca", "itf".
+    /// "code39", "code128", "ean13", "upca", "itf", "code93", "nw7".
     #[serde(default)]
     pub format: String,
     pub data: String,
+    /// Bar height in dots (default: 80).
     #[serde(default)]
     pub height: Option<u8>,
+    /// Bar height in mm (overrides `height`; at most 255 dots).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub height_mm: Option<f32>,
+    /// Print the human-readable text under the bars (default: true).
+    #[serde(default = "default_barcode_hri")]
+    pub hri: bool,
+    /// Print as raster graphics instead of the printer's barcode command,
+    /// for printers without native support for the symbology.
+    #[serde(default)]
+    pub raster: bool,
+}
+
+fn default_barcode_hri() -> bool {
+    true
 }
 
 impl ComponentMeta for Barcode {
@@ -731,6 +1244,9 @@ impl ComponentMeta for Barcode {
             format: "code128".into(),
             data: "ABC-123".into(),
             height: Some(60),
+            height_mm: None,
+            hri: true,
+            raster: false,
         }
     }
 }
@@ -794,7 +1310,8 @@ pub struct Chart {
     /// Optional title rendered above the chart.
     #[serde(default)]
     pub title: Option<String>,
-    /// Dithering algorithm: "bayer" (default), "floyd-steinberg", "atkinson", "jarvis", "none".
+    /// Dithering algorithm: "bayer" (default), "floyd-steinberg", "atkinson", "jarvis",
+    /// "blue-noise", "none".
     #[serde(default)]
     pub dither: Option<String>,
 }
@@ -829,44 +1346,185 @@ impl ComponentMeta for Chart {
     }
 }
 
-// ============================================================================
-// GRAPHICS COMPONENTS
-// ============================================================================
+/// Gauge component: a radial dial showing a single value.
+///
+/// Rendered as a raster image: a thick arc filled up to the value, tick
+/// marks with labels, a needle, and the value (plus `unit`) in IBM Plex
+/// Sans under the hub.
+///
+/// ## Example (JSON)
+///
+/// ```json
+/// {
+///   "type": "gauge",
+///   "value": 21.5,
+///   "min": -10,
+///   "max": 40,
+///   "unit": "°C",
+///   "label": "Living room"
+/// }
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Gauge {
+    pub value: f64,
+    /// Value at the start of the dial (default: 0).
+    #[serde(default)]
+    pub min: f64,
+    /// Value at the end of the dial (default: 100).
+    #[serde(default)]
+    pub max: Option<f64>,
+    /// Caption printed below the dial.
+    #[serde(default)]
+    pub label: Option<String>,
+    /// Suffix for the value readout (e.g., "%", "°C").
+    #[serde(default)]
+    pub unit: Option<String>,
+    /// Number of labeled tick intervals (default: 5).
+    #[serde(default)]
+    pub ticks: Option<usize>,
+    /// Angle covered by the dial in degrees (default: 240, max 360).
+    #[serde(default)]
+    pub sweep: Option<f64>,
+    /// Dial diameter in dots (default: 320).
+    #[serde(default)]
+    pub size: Option<usize>,
+    /// Dithering algorithm: "bayer" (default), "floyd-steinberg", "atkinson", "jarvis",
+    /// "blue-noise", "none".
+    #[serde(default)]
+    pub dither: Option<String>,
+}
 
-impl ComponentMeta for Image {
+impl Default for Gauge {
+    fn default() -> Self {
+        Self {
+            value: 0.0,
+            min: 0.0,
+            max: None,
+            label: None,
+            unit: None,
+            ticks: None,
+            sweep: None,
+            size: None,
+            dither: None,
+        }
+    }
+}
+
+impl ComponentMeta for Gauge {
     fn label() -> &'static str {
-        "Image"
+        "Gauge"
     }
     fn editor_default() -> Self {
-        Self::default()
+        Self {
+            value: 72.0,
+            unit: Some("%".into()),
+            label: Some("Humidity".into()),
+            ..Default::default()
+        }
     }
 }
 
-/// Image from URL (resolved at compile time).
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
-pub struct Image {
-    pub url: String,
-    /// Dithering algorithm: "bayer", "floyd-steinberg", "atkinson", "jarvis".
+impl Gauge {
+    /// Position of the value along the dial, clamped to 0.0..=1.0.
+    pub fn fraction(&self) -> f64 {
+        let max = self.max.unwrap_or(100.0);
+        let fraction = (self.value - self.min) / (max - self.min);
+        if max > self.min && fraction.is_finite() {
+            fraction.clamp(0.0, 1.0)
+        } else {
+            0.0
+        }
+    }
+}
+
+// ============================================================================
+// GRAPHICS COMPONENTS
+// ============================================================================
+
+impl ComponentMeta for Image {
+    fn label() -> &'static str {
+        "Image"
+    }
+    fn editor_default() -> Self {
+        Self::default()
+    }
+}
+
+/// How an image is scaled to its `width` and `height`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImageFit {
+    /// As large as fits within `width` × `height`, keeping its proportions.
+    #[default]
+    Contain,
+    /// Always the full `width`; `height` is ignored.
+    Width,
+    /// Fill `width` × `height` exactly, cropping what sticks out (centered).
+    Crop,
+}
+
+/// Image from URL (resolved at compile time).
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct Image {
+    /// `http(s)://` URL, `data:image/...;base64,` URI or `file:///` path
+    /// (see [`ImageResolver`](super::ImageResolver)).
+    pub url: String,
+    /// Dithering algorithm: "floyd-steinberg" (default), "bayer", "atkinson", "jarvis",
+    /// "blue-noise", "none" or "auto" (chosen per region, for images mixing line art
+    /// and photos).
     #[serde(default)]
     pub dither: Option<String>,
-    /// Target width in dots (default: 576).
+    /// Target width in dots (default: the printer's print width).
     #[serde(default)]
     pub width: Option<usize>,
-    /// Optional max height constraint.
+    /// Height in dots: the most the image may take, or what it fills with
+    /// `fit: crop`.
     #[serde(default)]
     pub height: Option<usize>,
+    /// Target width in mm (overrides `width`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub width_mm: Option<f32>,
+    /// Max height in mm (overrides `height`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub height_mm: Option<f32>,
     /// Image alignment when narrower than paper: "left", "center" (default), "right".
     #[serde(default)]
     pub align: Option<String>,
+    /// What prints behind transparent pixels: "white" (default), "black",
+    /// a shade (0.0–1.0) or a pattern name.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub background: Option<Background>,
+    /// Scaling: "contain" (default), "width" or "crop".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fit: Option<ImageFit>,
+    /// Brightness from -100 to 100 (default 0). Photos often print too dark
+    /// without a little.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub brightness: Option<i32>,
+    /// Contrast from -100 to 100 (default 0).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub contrast: Option<i32>,
+    /// Halftone screen instead of dithering: "dot", "line" or "diagonal",
+    /// optionally with LPI and angle ("dot:30:45"). Overrides `dither`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub halftone: Option<Halftone>,
     /// Resolved image data (populated by `Document::resolve()`).
     #[serde(skip)]
     pub resolved_data: Option<ResolvedImage>,
+    /// Why resolving failed, when the document's `on_error` policy let
+    /// compilation go on without the image.
+    #[serde(skip)]
+    pub resolve_error: Option<String>,
 }
 
 /// Resolved image data ready for emit.
+///
+/// Kept as grayscale so the image can be dithered (or re-dithered) at the
+/// final codegen/preview stage.
 #[derive(Debug, Clone)]
 pub struct ResolvedImage {
-    pub raster_data: Vec<u8>,
+    /// One intensity per pixel, row-major (0.0 = white, 1.0 = black).
+    pub intensities: Vec<f32>,
     pub width: u16,
     pub height: u16,
 }
@@ -888,14 +1546,27 @@ impl ComponentMeta for Pattern {
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct Pattern {
     pub name: String,
+    /// Height in dots (default: 500).
     #[serde(default)]
     pub height: Option<usize>,
+    /// Height in mm (overrides `height`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub height_mm: Option<f32>,
     /// Pattern-specific parameters.
     #[serde(default)]
     pub params: HashMap<String, String>,
-    /// Dithering algorithm: "bayer" (default), "floyd-steinberg", "atkinson", "jarvis", "none".
+    /// Randomize the parameters from this seed before `params` are applied
+    /// (see [`crate::art::by_name_seeded`]). Golden parameters when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+    /// Dithering algorithm: "bayer" (default), "floyd-steinberg", "atkinson", "jarvis",
+    /// "blue-noise", "none".
     #[serde(default)]
     pub dither: Option<String>,
+    /// Draft previews only: compute every Nth row and repeat it (0 or 1 =
+    /// full quality). Set by [`Document::set_preview_row_step`], never serialized.
+    #[serde(skip)]
+    pub row_step: usize,
 }
 
 impl ComponentMeta for NvLogo {
@@ -931,13 +1602,76 @@ pub struct NvLogo {
 // CANVAS COMPONENT
 // ============================================================================
 
+/// A canvas coordinate: dots (`42`), millimeters (`"12.5mm"`) or a
+/// percentage of the canvas size (`"50%"`).
+pub use crate::units::Length;
+
+/// Which point of a canvas element its `position` refers to.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Anchor {
+    #[default]
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+    /// Left end of the lowest inked row — the text baseline for text
+    /// without descenders.
+    Baseline,
+}
+
+impl Anchor {
+    fn is_top_left(&self) -> bool {
+        *self == Anchor::TopLeft
+    }
+
+    /// Offset from an element's top-left corner to this anchor point.
+    ///
+    /// `baseline` is the row below the element's lowest inked pixel.
+    pub fn offset(self, width: usize, height: usize, baseline: usize) -> (i32, i32) {
+        let (w, h) = (width as i32, height as i32);
+        match self {
+            Anchor::TopLeft => (0, 0),
+            Anchor::Top => (w / 2, 0),
+            Anchor::TopRight => (w, 0),
+            Anchor::Left => (0, h / 2),
+            Anchor::Center => (w / 2, h / 2),
+            Anchor::Right => (w, h / 2),
+            Anchor::BottomLeft => (0, h),
+            Anchor::Bottom => (w / 2, h),
+            Anchor::BottomRight => (w, h),
+            Anchor::Baseline => (0, baseline as i32),
+        }
+    }
+}
+
 /// Position for absolute placement of canvas elements.
+///
+/// `x`/`y` locate the element's `anchor` point (top-left by default).
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, Default)]
 pub struct Position {
     #[serde(default)]
-    pub x: i32,
+    pub x: Length,
     #[serde(default)]
-    pub y: i32,
+    pub y: Length,
+    #[serde(default, skip_serializing_if = "Anchor::is_top_left")]
+    pub anchor: Anchor,
+}
+
+impl Position {
+    /// Top-left anchored position in dots.
+    pub fn dots(x: i32, y: i32) -> Self {
+        Self {
+            x: Length::Dots(x),
+            y: Length::Dots(y),
+            anchor: Anchor::TopLeft,
+        }
+    }
 }
 
 fn default_opacity() -> f32 {
@@ -948,6 +1682,8 @@ fn default_opacity() -> f32 {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CanvasElement {
     /// The inner component (custom deserialization in mod.rs handles this).
+    /// Serialized inline, alongside the element fields, as it is read.
+    #[serde(flatten)]
     pub component: super::Component,
     /// Absolute position within the canvas. If absent, element flows top-to-bottom.
     #[serde(default)]
@@ -958,6 +1694,120 @@ pub struct CanvasElement {
     /// Opacity (0.0 = transparent, 1.0 = fully opaque).
     #[serde(default = "default_opacity")]
     pub opacity: f32,
+    /// Rotation in degrees, clockwise about the element's center.
+    #[serde(default)]
+    pub rotate: f32,
+    /// Scale factors `[x, y]`; JSON also accepts a single number.
+    #[serde(default = "default_scale", deserialize_with = "deserialize_scale")]
+    pub scale: [f32; 2],
+    /// Clip shape; pixels outside it are transparent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub clip: Option<Clip>,
+}
+
+/// Shape of a canvas element's clip region.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClipShape {
+    #[default]
+    Rect,
+    RoundedRect,
+    /// The largest circle centered in the clip box (or `radius`, if set).
+    Circle,
+}
+
+/// Clip region for a canvas element, applied after rotation and scaling.
+///
+/// The clip box is in dots relative to the element's rendered box; each
+/// omitted side defaults to the element's inked content bounds. JSON accepts
+/// a bare shape name (`"clip": "circle"`) or an object:
+///
+/// ```json
+/// {"shape": "rounded_rect", "radius": 12, "x": 0, "y": 0, "width": 200, "height": 120}
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Clip {
+    pub shape: ClipShape,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub x: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub y: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub width: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub height: Option<usize>,
+    /// Corner radius (rounded_rect) or circle radius, in dots.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub radius: Option<f32>,
+}
+
+impl<'de> Deserialize<'de> for Clip {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct ClipFields {
+            #[serde(default)]
+            shape: ClipShape,
+            x: Option<usize>,
+            y: Option<usize>,
+            width: Option<usize>,
+            height: Option<usize>,
+            radius: Option<f32>,
+        }
+
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum ClipValue {
+            Shape(ClipShape),
+            Fields(ClipFields),
+        }
+
+        Ok(match ClipValue::deserialize(deserializer)? {
+            ClipValue::Shape(shape) => Clip {
+                shape,
+                ..Default::default()
+            },
+            ClipValue::Fields(f) => Clip {
+                shape: f.shape,
+                x: f.x,
+                y: f.y,
+                width: f.width,
+                height: f.height,
+                radius: f.radius,
+            },
+        })
+    }
+}
+
+fn default_scale() -> [f32; 2] {
+    [1.0, 1.0]
+}
+
+/// Deserialize a scale as either a uniform factor (`2`) or `[x, y]`.
+pub(crate) fn deserialize_scale<'de, D>(deserializer: D) -> Result<[f32; 2], D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum ScaleValue {
+        Uniform(f32),
+        Pair([f32; 2]),
+    }
+
+    match ScaleValue::deserialize(deserializer)? {
+        ScaleValue::Uniform(n) => Ok([n, n]),
+        ScaleValue::Pair(pair) => Ok(pair),
+    }
+}
+
+impl CanvasElement {
+    /// Whether the element is rotated or scaled.
+    pub fn is_transformed(&self) -> bool {
+        self.rotate % 360.0 != 0.0 || self.scale != [1.0, 1.0]
+    }
 }
 
 /// Canvas component: absolute-positioned raster compositing surface.
@@ -979,19 +1829,30 @@ pub struct CanvasElement {
 /// ```
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct Canvas {
-    /// Canvas width in dots (default: 576).
+    /// Canvas width in dots (default: the printer's print width).
     #[serde(default)]
     pub width: Option<usize>,
     /// Canvas height in dots. Auto-detected from elements if absent.
     #[serde(default)]
     pub height: Option<usize>,
-    /// Dithering: "auto" (default), "none", "bayer", "atkinson", "floyd-steinberg", "jarvis".
+    /// Canvas width in mm (overrides `width`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub width_mm: Option<f32>,
+    /// Canvas height in mm (overrides `height`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub height_mm: Option<f32>,
+    /// Dithering: "auto" (default), "none", "bayer", "atkinson", "floyd-steinberg", "jarvis",
+    /// "blue-noise".
     /// "auto" uses Atkinson if any element has continuous-tone content, otherwise None.
     #[serde(default)]
     pub dither: Option<String>,
     /// Elements to composite onto the canvas.
     #[serde(default, deserialize_with = "super::deserialize_canvas_elements")]
     pub elements: Vec<CanvasElement>,
+    /// Draft previews only: composite every Nth row and repeat it (0 or 1 =
+    /// full quality). Set by [`Document::set_preview_row_step`], never serialized.
+    #[serde(skip)]
+    pub row_step: usize,
 }
 
 impl ComponentMeta for Canvas {
@@ -1006,6 +1867,612 @@ impl ComponentMeta for Canvas {
     }
 }
 
+fn default_text_mask_height() -> usize {
+    120
+}
+
+fn default_text_mask_bold() -> bool {
+    true
+}
+
+/// Text mask: a large TTF headline meant as a stencil on a canvas.
+///
+/// On its own it prints as solid text. As a canvas element with
+/// `"blend_mode": "mask"` the layers below it show only through the
+/// glyphs; with `"blend_mode": "knockout"` the text is cut out of them.
+///
+/// ## Example (JSON)
+///
+/// ```json
+/// {
+///   "type": "canvas",
+///   "elements": [
+///     {"type": "pattern", "name": "plasma", "height": 160},
+///     {"type": "text_mask", "content": "SALE", "fit": true, "blend_mode": "mask",
+///      "position": {"x": 0, "y": 0}}
+///   ]
+/// }
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TextMask {
+    pub content: String,
+    /// Glyph height in dots (default: 120).
+    #[serde(default = "default_text_mask_height")]
+    pub height: usize,
+    /// Glyph height in mm (overrides `height`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub height_mm: Option<f32>,
+    /// Grow or shrink the text to span the print width (overrides the height).
+    #[serde(default)]
+    pub fit: bool,
+    /// TTF font (default: "ibm", IBM Plex Sans).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub font: Option<String>,
+    /// Bold weight (default: true; thick strokes make better masks).
+    #[serde(default = "default_text_mask_bold")]
+    pub bold: bool,
+    #[serde(default)]
+    pub center: bool,
+}
+
+impl Default for TextMask {
+    fn default() -> Self {
+        Self {
+            content: String::new(),
+            height: default_text_mask_height(),
+            height_mm: None,
+            fit: false,
+            font: None,
+            bold: default_text_mask_bold(),
+            center: false,
+        }
+    }
+}
+
+impl ComponentMeta for TextMask {
+    fn label() -> &'static str {
+        "Text Mask"
+    }
+    fn editor_default() -> Self {
+        Self {
+            content: "SALE".into(),
+            fit: true,
+            ..Default::default()
+        }
+    }
+}
+
+// ============================================================================
+// LABEL SHEET COMPONENT
+// ============================================================================
+
+fn default_label_count() -> usize {
+    1
+}
+
+fn default_label_gap_mm() -> f32 {
+    3.0
+}
+
+fn default_label_marks() -> bool {
+    true
+}
+
+/// Label sheet: repeats a small sub-document down the roll at an exact pitch.
+///
+/// Each label is rendered once and printed `count` times as raster graphics,
+/// so every copy lands exactly `pitch_mm` below the previous one. Short
+/// alignment marks at both edges show where to cut between labels.
+///
+/// ## Example (JSON)
+///
+/// ```json
+/// {
+///   "type": "label_sheet",
+///   "count": 20,
+///   "pitch_mm": 40,
+///   "label": [
+///     {"text": "STRAWBERRY JAM", "bold": true, "center": true, "size": 2},
+///     {"text": "{{date_short}}", "center": true}
+///   ]
+/// }
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct LabelSheet {
+    /// Components making up one label.
+    #[serde(deserialize_with = "super::deserialize_components")]
+    pub label: Vec<super::Component>,
+    /// Number of labels to print (default: 1, at most 500).
+    #[serde(default = "default_label_count")]
+    pub count: usize,
+    /// Distance from the top of one label to the top of the next, in mm.
+    /// Defaults to the printer's label stock pitch, or to the label's own
+    /// height plus `gap_mm` on continuous paper. Taller labels are cropped.
+    #[serde(default)]
+    pub pitch_mm: Option<f32>,
+    /// Space below each label when no pitch is known (default: 3mm).
+    #[serde(default = "default_label_gap_mm")]
+    pub gap_mm: f32,
+    /// Draw alignment marks between labels (default: true).
+    #[serde(default = "default_label_marks")]
+    pub marks: bool,
+}
+
+impl ComponentMeta for LabelSheet {
+    fn label() -> &'static str {
+        "Label Sheet"
+    }
+    fn editor_default() -> Self {
+        Self {
+            label: vec![super::Component::Text(Text {
+                content: "LABEL".into(),
+                center: true,
+                bold: true,
+                size: [2, 2],
+                ..Default::default()
+            })],
+            count: 3,
+            pitch_mm: Some(25.0),
+            gap_mm: default_label_gap_mm(),
+            marks: true,
+        }
+    }
+}
+
+// ============================================================================
+// ORDER TICKET COMPONENT
+// ============================================================================
+
+fn default_order_qty() -> u32 {
+    1
+}
+
+/// One line of a kitchen order.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct OrderItem {
+    pub name: String,
+    /// Quantity (default: 1).
+    #[serde(default = "default_order_qty")]
+    pub qty: u32,
+    /// Changes to the dish, printed indented under it (e.g. "no onions").
+    #[serde(default)]
+    pub modifiers: Vec<String>,
+    /// Allergens, printed as an inverted warning line.
+    #[serde(default)]
+    pub allergens: Vec<String>,
+    /// Kitchen station that prepares the item (e.g. "grill"). Items without
+    /// one appear on every station's ticket.
+    #[serde(default)]
+    pub station: Option<String>,
+    /// Free-form note for this item.
+    #[serde(default)]
+    pub note: Option<String>,
+}
+
+impl OrderItem {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            qty: 1,
+            ..Default::default()
+        }
+    }
+}
+
+/// A kitchen order, as a point-of-sale system would send it.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct Order {
+    /// Order number, printed as a large banner.
+    pub number: String,
+    /// Service type, e.g. "DINE IN", "TAKEOUT", "DELIVERY".
+    #[serde(default)]
+    pub service: Option<String>,
+    #[serde(default)]
+    pub table: Option<String>,
+    #[serde(default)]
+    pub server: Option<String>,
+    /// Time the order was placed (printed as given).
+    #[serde(default)]
+    pub time: Option<String>,
+    #[serde(default)]
+    pub items: Vec<OrderItem>,
+    /// Note for the whole order, printed at the bottom.
+    #[serde(default)]
+    pub notes: Option<String>,
+}
+
+/// Kitchen order ticket built from a structured order.
+///
+/// Lays out a ticket the way kitchen display systems do: station header,
+/// order number banner, service/table/time details, then the items with
+/// modifiers indented and allergens inverted so they can't be missed.
+///
+/// With `per_station`, one ticket is printed for each station named by the
+/// items, holding only that station's items (plus items without a station),
+/// with a partial cut between tickets. `station` prints a single ticket for
+/// one station instead.
+///
+/// ## Example (JSON)
+///
+/// ```json
+/// {
+///   "type": "order_ticket",
+///   "per_station": true,
+///   "order": {
+///     "number": "42",
+///     "service": "DINE IN",
+///     "table": "12",
+///     "time": "19:04",
+///     "items": [
+///       {"name": "Smash Burger", "qty": 2, "modifiers": ["no onions"], "station": "grill"},
+///       {"name": "Caesar Salad", "allergens": ["fish", "egg"], "station": "cold"}
+///     ]
+///   }
+/// }
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct OrderTicket {
+    pub order: Order,
+    /// Only print the items for this station, under its header.
+    #[serde(default)]
+    pub station: Option<String>,
+    /// Print one ticket per station (default: false).
+    #[serde(default)]
+    pub per_station: bool,
+}
+
+impl ComponentMeta for OrderTicket {
+    fn label() -> &'static str {
+        "Order Ticket"
+    }
+    fn editor_default() -> Self {
+        Self {
+            order: Order {
+                number: "42".into(),
+                service: Some("DINE IN".into()),
+                table: Some("12".into()),
+                time: Some("{{time}}".into()),
+                items: vec![
+                    OrderItem {
+                        qty: 2,
+                        modifiers: vec!["No onions".into(), "Extra pickles".into()],
+                        station: Some("grill".into()),
+                        ..OrderItem::new("Smash Burger")
+                    },
+                    OrderItem {
+                        allergens: vec!["Fish".into(), "Egg".into()],
+                        station: Some("cold".into()),
+                        ..OrderItem::new("Caesar Salad")
+                    },
+                ],
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+}
+
+// ============================================================================
+// STATION GROUP COMPONENT
+// ============================================================================
+
+/// Components printed only at some stations.
+///
+/// When a document is compiled for a station (see
+/// [`Document::station`](super::Document::station)), groups for other
+/// stations are dropped; without a target station everything prints. Any
+/// component can be routed directly with a `stations` key, which wraps it
+/// in a group when the JSON is parsed.
+///
+/// ## Example (JSON)
+///
+/// ```json
+/// {"text": "FIRE TABLE 12", "bold": true, "stations": ["kitchen", "bar"]}
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct StationGroup {
+    /// Stations that print these components (case-insensitive).
+    pub stations: Vec<String>,
+    #[serde(deserialize_with = "super::deserialize_components")]
+    pub components: Vec<super::Component>,
+}
+
+impl ComponentMeta for StationGroup {
+    fn label() -> &'static str {
+        "Station Group"
+    }
+    fn editor_default() -> Self {
+        Self {
+            stations: vec!["kitchen".into()],
+            components: vec![super::Component::Text(Text {
+                content: "KITCHEN COPY".into(),
+                bold: true,
+                center: true,
+                ..Default::default()
+            })],
+        }
+    }
+}
+
+impl StationGroup {
+    /// Whether this group prints at `station`.
+    pub fn includes(&self, station: &str) -> bool {
+        self.stations
+            .iter()
+            .any(|s| s.eq_ignore_ascii_case(station))
+    }
+}
+
+// ============================================================================
+// TEMPLATE COMPONENTS
+// ============================================================================
+
+/// Components printed only when a variable is (or isn't) set.
+///
+/// A variable is set when it exists and isn't empty, `0`, `false`, `null`,
+/// `[]` or `{}`. Both conditions must hold when both are given. Any
+/// component can take `"if"` or `"unless"` directly, which wraps it in a
+/// conditional when the JSON is parsed.
+///
+/// ## Example (JSON)
+///
+/// ```json
+/// {"text": "Member price applied", "if": "member"}
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct Conditional {
+    /// Variable that must be set.
+    #[serde(rename = "if", default, skip_serializing_if = "Option::is_none")]
+    pub if_set: Option<String>,
+    /// Variable that must not be set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unless: Option<String>,
+    #[serde(deserialize_with = "super::deserialize_components")]
+    pub components: Vec<super::Component>,
+}
+
+impl ComponentMeta for Conditional {
+    fn label() -> &'static str {
+        "Conditional"
+    }
+    fn editor_default() -> Self {
+        Self {
+            if_set: Some("member".into()),
+            unless: None,
+            components: vec![super::Component::Text(Text::new("Member price applied"))],
+        }
+    }
+}
+
+fn default_repeat_as() -> String {
+    "item".into()
+}
+
+/// Components printed once per entry of a JSON array variable.
+///
+/// Inside, `{{item}}` is the entry, `{{item.<field>}}` each field of an
+/// object entry, and `{{loop.index}}` (from 1), `{{loop.count}}`,
+/// `{{loop.first}}` and `{{loop.last}}` where the entry is. A string that
+/// is nothing but one of these placeholders takes the value as it is, so
+/// `"price": "{{item.price}}"` stays a number. Nested loops need their own
+/// `as` name.
+///
+/// ## Example (JSON)
+///
+/// ```json
+/// {
+///   "variables": {"items": [{"name": "Latte", "price": 4.5}, {"name": "Scone", "price": 3}]},
+///   "document": [
+///     {"type": "repeat", "each": "items", "components": [
+///       {"line_item": "{{item.name}}", "price": "{{item.price}}"}
+///     ]}
+///   ]
+/// }
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct Repeat {
+    /// Variable holding the array (a JSON array, or a string of one).
+    pub each: String,
+    /// Name of the entry inside the loop (default: `item`).
+    #[serde(rename = "as", default = "default_repeat_as")]
+    pub item: String,
+    /// Components printed per entry. Kept as JSON until expanded, since
+    /// placeholders can stand in for numbers and other non-string fields.
+    pub components: Vec<serde_json::Value>,
+}
+
+impl ComponentMeta for Repeat {
+    fn label() -> &'static str {
+        "Repeat"
+    }
+    fn editor_default() -> Self {
+        Self {
+            each: "items".into(),
+            item: default_repeat_as(),
+            components: vec![serde_json::json!({"text": "{{loop.index}}. {{item}}"})],
+        }
+    }
+}
+
+// ============================================================================
+// TEXTURE COMPONENT
+// ============================================================================
+
+fn default_texture_height() -> usize {
+    100
+}
+
+fn default_tile_size() -> usize {
+    64
+}
+
+/// Texture: a small tile repeated across a region.
+///
+/// The tile is either a pattern evaluated at tile size (`pattern`, `params`)
+/// or a small sub-document rendered once (`tile`). Only one tile's worth of
+/// pixels is computed, so large regions stay cheap. Meant as a canvas
+/// element behind positioned content — security-paper backgrounds, "VOID"
+/// watermarks — with the element's `opacity` setting its strength.
+///
+/// ## Example (JSON)
+///
+/// ```json
+/// {
+///   "type": "canvas",
+///   "height": 120,
+///   "elements": [
+///     {"texture": "crosshatch", "height": 120, "tile_width": 48, "tile_height": 48, "opacity": 0.3},
+///     {"text": "PAID", "size": 3, "center": true, "position": {"x": 0, "y": 40}}
+///   ]
+/// }
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct Texture {
+    /// Pattern to evaluate for the tile.
+    #[serde(default)]
+    pub pattern: Option<String>,
+    /// Pattern-specific parameters.
+    #[serde(default)]
+    pub params: HashMap<String, String>,
+    /// Components rendered once as the tile (used when `pattern` is absent).
+    #[serde(default, deserialize_with = "super::deserialize_components")]
+    pub tile: Vec<super::Component>,
+    /// Region width in dots (default: the printer's print width).
+    #[serde(default)]
+    pub width: Option<usize>,
+    /// Region height in dots (default: 100).
+    #[serde(default = "default_texture_height")]
+    pub height: usize,
+    /// Region width in mm (overrides `width`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub width_mm: Option<f32>,
+    /// Region height in mm (overrides `height`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub height_mm: Option<f32>,
+    /// Pattern tile width in dots (default: 64).
+    #[serde(default = "default_tile_size")]
+    pub tile_width: usize,
+    /// Pattern tile height in dots (default: 64).
+    #[serde(default = "default_tile_size")]
+    pub tile_height: usize,
+    /// Shift every other row of tiles by half a tile (brick layout).
+    #[serde(default)]
+    pub stagger: bool,
+    /// Dithering algorithm: "bayer" (default), "floyd-steinberg", "atkinson", "jarvis",
+    /// "blue-noise", "none".
+    #[serde(default)]
+    pub dither: Option<String>,
+}
+
+impl ComponentMeta for Texture {
+    fn label() -> &'static str {
+        "Texture"
+    }
+    fn editor_default() -> Self {
+        Self {
+            pattern: Some("crosshatch".into()),
+            height: default_texture_height(),
+            tile_width: default_tile_size(),
+            tile_height: default_tile_size(),
+            ..Default::default()
+        }
+    }
+}
+
+// ============================================================================
+// COMPOSITION COMPONENT
+// ============================================================================
+
+/// One pattern layer of a [`Composition`].
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct CompositionLayer {
+    /// Pattern name (see `estrella print --list`).
+    #[serde(alias = "name")]
+    pub pattern: String,
+    /// Pattern-specific parameters.
+    #[serde(default)]
+    pub params: HashMap<String, String>,
+    /// How the layer combines with the layers below it.
+    #[serde(default, alias = "blend_mode")]
+    pub blend: BlendMode,
+    /// Opacity (0.0 = transparent, 1.0 = fully opaque).
+    #[serde(default = "default_opacity")]
+    pub opacity: f32,
+}
+
+/// Composition: pattern layers blended into one piece of generative art.
+///
+/// Layers are stacked bottom to top over white paper, each combined with
+/// its `blend` mode and `opacity`, and the result is dithered once.
+///
+/// Also accepted as `"type": "pattern_stack"`, with each layer's pattern
+/// under `name` and its blend under `blend_mode`, so saved layered art
+/// replays as is.
+///
+/// ## Example (JSON)
+///
+/// ```json
+/// {
+///   "type": "composition",
+///   "height": 300,
+///   "layers": [
+///     {"pattern": "ripple", "params": {"scale": "12"}},
+///     {"pattern": "crosshatch", "blend": "multiply", "opacity": 0.5}
+///   ]
+/// }
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct Composition {
+    /// Layers, bottom first.
+    #[serde(default)]
+    pub layers: Vec<CompositionLayer>,
+    /// Width in dots (default: the printer's print width).
+    #[serde(default)]
+    pub width: Option<usize>,
+    /// Height in dots (default: 500).
+    #[serde(default)]
+    pub height: Option<usize>,
+    /// Width in mm (overrides `width`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub width_mm: Option<f32>,
+    /// Height in mm (overrides `height`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub height_mm: Option<f32>,
+    /// Dithering algorithm: "bayer" (default), "floyd-steinberg", "atkinson", "jarvis",
+    /// "blue-noise", "none".
+    #[serde(default)]
+    pub dither: Option<String>,
+    /// Draft previews only: compute every Nth row and repeat it (0 or 1 =
+    /// full quality). Set by [`Document::set_preview_row_step`], never serialized.
+    #[serde(skip)]
+    pub row_step: usize,
+}
+
+impl ComponentMeta for Composition {
+    fn label() -> &'static str {
+        "Composition"
+    }
+    fn editor_default() -> Self {
+        Self {
+            layers: vec![
+                CompositionLayer {
+                    pattern: "ripple".into(),
+                    opacity: 1.0,
+                    ..Default::default()
+                },
+                CompositionLayer {
+                    pattern: "crosshatch".into(),
+                    blend: BlendMode::Multiply,
+                    opacity: 0.5,
+                    ..Default::default()
+                },
+            ],
+            height: Some(300),
+            ..Default::default()
+        }
+    }
+}
+
 // ============================================================================
 // HELPER: parse text fields for variable interpolation
 // ============================================================================
@@ -1017,11 +2484,8 @@ pub trait Interpolatable {
 }
 
 fn interpolate_string(s: &mut String, vars: &HashMap<String, String>) {
-    for (key, value) in vars {
-        let placeholder = format!("{{{{{}}}}}", key);
-        if s.contains(&placeholder) {
-            *s = s.replace(&placeholder, value);
-        }
+    if s.contains("{{") {
+        *s = super::filters::interpolate(s, |name| vars.get(name).cloned(), true);
     }
 }
 
@@ -1055,6 +2519,36 @@ impl Interpolatable for Columns {
     fn interpolate(&mut self, vars: &HashMap<String, String>) {
         interpolate_string(&mut self.left, vars);
         interpolate_string(&mut self.right, vars);
+        for cell in &mut self.columns {
+            interpolate_string(cell, vars);
+        }
+    }
+}
+
+impl Interpolatable for Definitions {
+    fn interpolate(&mut self, vars: &HashMap<String, String>) {
+        for (label, value) in &mut self.items {
+            interpolate_string(label, vars);
+            interpolate_string(value, vars);
+        }
+    }
+}
+
+impl Interpolatable for DataDump {
+    fn interpolate(&mut self, vars: &HashMap<String, String>) {
+        // Only a string `data`, so a payload variable can be dropped in
+        // whole; values inside an object print as they are
+        if let serde_json::Value::String(s) = &mut self.data {
+            interpolate_string(s, vars);
+        }
+    }
+}
+
+impl Interpolatable for ProgressBar {
+    fn interpolate(&mut self, vars: &HashMap<String, String>) {
+        if let Some(ref mut label) = self.label {
+            interpolate_string(label, vars);
+        }
     }
 }
 
@@ -1064,6 +2558,12 @@ impl Interpolatable for Markdown {
     }
 }
 
+impl Interpolatable for Html {
+    fn interpolate(&mut self, vars: &HashMap<String, String>) {
+        interpolate_string(&mut self.content, vars);
+    }
+}
+
 impl Interpolatable for QrCode {
     fn interpolate(&mut self, vars: &HashMap<String, String>) {
         interpolate_string(&mut self.data, vars);
@@ -1089,9 +2589,9 @@ impl Interpolatable for Table {
                 interpolate_string(h, vars);
             }
         }
-        for row in &mut self.rows {
+        for row in self.rows.iter_mut().chain(&mut self.footers) {
             for cell in row.iter_mut() {
-                interpolate_string(cell, vars);
+                interpolate_string(&mut cell.text, vars);
             }
         }
     }
@@ -1126,12 +2626,25 @@ impl Interpolatable for Chart {
         }
     }
 }
+impl Interpolatable for Gauge {
+    fn interpolate(&mut self, vars: &HashMap<String, String>) {
+        if let Some(ref mut label) = self.label {
+            interpolate_string(label, vars);
+        }
+        if let Some(ref mut unit) = self.unit {
+            interpolate_string(unit, vars);
+        }
+    }
+}
 impl Interpolatable for Pattern {
     fn interpolate(&mut self, _vars: &HashMap<String, String>) {}
 }
 impl Interpolatable for NvLogo {
     fn interpolate(&mut self, _vars: &HashMap<String, String>) {}
 }
+impl Interpolatable for Composition {
+    fn interpolate(&mut self, _vars: &HashMap<String, String>) {}
+}
 impl Interpolatable for Canvas {
     fn interpolate(&mut self, vars: &HashMap<String, String>) {
         for element in &mut self.elements {
@@ -1139,3 +2652,74 @@ impl Interpolatable for Canvas {
         }
     }
 }
+
+impl Interpolatable for TextMask {
+    fn interpolate(&mut self, vars: &HashMap<String, String>) {
+        interpolate_string(&mut self.content, vars);
+    }
+}
+
+impl Interpolatable for Texture {
+    fn interpolate(&mut self, vars: &HashMap<String, String>) {
+        for component in &mut self.tile {
+            component.interpolate(vars);
+        }
+    }
+}
+
+impl Interpolatable for OrderTicket {
+    fn interpolate(&mut self, vars: &HashMap<String, String>) {
+        let order = &mut self.order;
+        interpolate_string(&mut order.number, vars);
+        for field in [
+            &mut order.service,
+            &mut order.table,
+            &mut order.server,
+            &mut order.time,
+            &mut order.notes,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            interpolate_string(field, vars);
+        }
+        for item in &mut order.items {
+            interpolate_string(&mut item.name, vars);
+            for modifier in &mut item.modifiers {
+                interpolate_string(modifier, vars);
+            }
+            if let Some(ref mut note) = item.note {
+                interpolate_string(note, vars);
+            }
+        }
+    }
+}
+
+impl Interpolatable for StationGroup {
+    fn interpolate(&mut self, vars: &HashMap<String, String>) {
+        for component in &mut self.components {
+            component.interpolate(vars);
+        }
+    }
+}
+
+impl Interpolatable for Conditional {
+    fn interpolate(&mut self, vars: &HashMap<String, String>) {
+        for component in &mut self.components {
+            component.interpolate(vars);
+        }
+    }
+}
+
+// Repeats are expanded (and their components parsed) before interpolation
+impl Interpolatable for Repeat {
+    fn interpolate(&mut self, _vars: &HashMap<String, String>) {}
+}
+
+impl Interpolatable for LabelSheet {
+    fn interpolate(&mut self, vars: &HashMap<String, String>) {
+        for component in &mut self.label {
+            component.interpolate(vars);
+        }
+    }
+}
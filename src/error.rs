@@ -23,6 +23,15 @@ pub enum EstrellaError {
     #[error("Image error: {0}")]
     Image(String),
 
+    /// Document hook failure
+    #[error("Hook error: {0}")]
+    Hook(String),
+
+    /// Template expansion failure (a `repeat` over something that isn't an
+    /// array, or components that don't parse once filled in)
+    #[error("Template error: {0}")]
+    Template(String),
+
     /// I/O error wrapper
     #[error("I/O error: {0}")]
     Io(#[from] std::io::Error),
@@ -1,6 +1,7 @@
 //! IR analysis utilities for measuring optimization potential.
 
 use super::ops::{Op, Program, StyleState};
+use std::ops::Range;
 
 /// Analysis results for an IR program
 #[derive(Debug, Default)]
@@ -45,7 +46,13 @@ fn is_style_op(op: &Op) -> bool {
             | Op::SetExpandedHeight(_)
             | Op::SetSize { .. }
             | Op::SetCodepage(_)
+            | Op::SetLineSpacing(_)
+            | Op::SetDensity(_)
+            | Op::SetSpeed(_)
+            | Op::SetWrap(_)
             | Op::ResetStyle
+            | Op::PushStyle
+            | Op::PopStyle
     )
 }
 
@@ -56,6 +63,7 @@ fn is_content_op(op: &Op) -> bool {
         Op::Text(_)
             | Op::Raw(_)
             | Op::Raster { .. }
+            | Op::GrayRaster { .. }
             | Op::Band { .. }
             | Op::QrCode { .. }
             | Op::Pdf417 { .. }
@@ -180,6 +188,118 @@ pub fn print_analysis(name: &str, results: &AnalysisResults) {
     println!();
 }
 
+/// Render a program as a human-readable listing, one op per line.
+///
+/// Graphics payloads are summarized by size instead of dumped, and
+/// `Op::Marker`s are shown as `;` comment lines so component boundaries
+/// stand out in large documents.
+pub fn disassemble(program: &Program) -> String {
+    let mut out = String::new();
+    for (i, op) in program.ops.iter().enumerate() {
+        match op {
+            Op::Marker(label) => out.push_str(&format!("      ; {}\n", label)),
+            _ => out.push_str(&format!("{:5} {}\n", i, describe_op(op))),
+        }
+    }
+    out
+}
+
+/// The ops each `Op::Marker` covers: from the marker up to the next one,
+/// the last reaching the end of the program.
+///
+/// With a document compiled in `debug` mode this maps every component to
+/// the ops it emitted (after optimization).
+pub fn marker_ranges(program: &Program) -> Vec<(&str, Range<usize>)> {
+    let starts: Vec<(usize, &str)> = program
+        .ops
+        .iter()
+        .enumerate()
+        .filter_map(|(i, op)| match op {
+            Op::Marker(label) => Some((i, label.as_str())),
+            _ => None,
+        })
+        .collect();
+    starts
+        .iter()
+        .enumerate()
+        .map(|(n, &(start, label))| {
+            let end = starts
+                .get(n + 1)
+                .map_or(program.ops.len(), |&(next, _)| next);
+            (label, start..end)
+        })
+        .collect()
+}
+
+/// Render bytes as a hexdump: offset, 16 bytes in hex, then the printable
+/// ASCII characters (`.` for the rest).
+///
+/// ```
+/// let dump = estrella::ir::analyze::hexdump(b"\x1b@Hi\n");
+/// assert_eq!(
+///     dump,
+///     "00000000  1b 40 48 69 0a                                    |.@Hi.|\n"
+/// );
+/// ```
+pub fn hexdump(bytes: &[u8]) -> String {
+    let mut out = String::new();
+    for (row, chunk) in bytes.chunks(16).enumerate() {
+        let mut hex = String::new();
+        for (i, byte) in chunk.iter().enumerate() {
+            if i == 8 {
+                hex.push(' ');
+            }
+            hex.push_str(&format!("{:02x} ", byte));
+        }
+        let ascii: String = chunk
+            .iter()
+            .map(|&b| {
+                if b.is_ascii_graphic() || b == b' ' {
+                    b as char
+                } else {
+                    '.'
+                }
+            })
+            .collect();
+        out.push_str(&format!("{:08x}  {:<49} |{}|\n", row * 16, hex, ascii));
+    }
+    out
+}
+
+/// One-line description of an op, with bulky payloads summarized.
+fn describe_op(op: &Op) -> String {
+    match op {
+        Op::Raw(bytes) => format!("Raw ({} bytes)", bytes.len()),
+        Op::Raster {
+            width,
+            height,
+            data,
+        } => format!("Raster {}x{} ({} bytes)", width, height, data.len()),
+        Op::GrayRaster {
+            width,
+            height,
+            dither,
+            ..
+        } => format!("GrayRaster {}x{} dither={:?}", width, height, dither),
+        Op::Band { width_bytes, data } => {
+            format!("Band width_bytes={} ({} bytes)", width_bytes, data.len())
+        }
+        Op::NvStore {
+            key,
+            width,
+            height,
+            data,
+        } => format!(
+            "NvStore {:?} {}x{} ({} bytes)",
+            key,
+            width,
+            height,
+            data.len()
+        ),
+        other => format!("{:?}", other),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -241,6 +361,61 @@ mod tests {
         assert_eq!(results.mergeable_feeds, 1);
     }
 
+    #[test]
+    fn test_disassemble_markers_and_graphics() {
+        let program = Program {
+            ops: vec![
+                Op::Init,
+                Op::Marker("[0] Image".into()),
+                Op::Raster {
+                    width: 8,
+                    height: 2,
+                    data: vec![0xFF, 0x00],
+                },
+            ],
+        };
+        let listing = disassemble(&program);
+        let lines: Vec<&str> = listing.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].ends_with("Init"));
+        assert_eq!(lines[1].trim(), "; [0] Image");
+        assert!(lines[2].ends_with("Raster 8x2 (2 bytes)"));
+    }
+
+    #[test]
+    fn test_marker_ranges() {
+        let program = Program {
+            ops: vec![
+                Op::Init,
+                Op::Marker("[0] Text".into()),
+                Op::Text("a".into()),
+                Op::Newline,
+                Op::Marker("[1] Divider".into()),
+                Op::Text("---".into()),
+                Op::Cut { partial: true },
+            ],
+        };
+        assert_eq!(
+            marker_ranges(&program),
+            [("[0] Text", 1..4), ("[1] Divider", 4..7)]
+        );
+    }
+
+    #[test]
+    fn test_hexdump_rows() {
+        let bytes: Vec<u8> = (0x40..0x52).collect();
+        let dump = hexdump(&bytes);
+        let lines: Vec<&str> = dump.lines().collect();
+        assert_eq!(
+            lines,
+            [
+                "00000000  40 41 42 43 44 45 46 47  48 49 4a 4b 4c 4d 4e 4f  |@ABCDEFGHIJKLMNO|",
+                "00000010  50 51                                             |PQ|",
+            ]
+        );
+        assert_eq!(hexdump(&[]), "");
+    }
+
     /// Dump ops for all receipts to see patterns
     /// Run with: cargo test dump_receipt_ops -- --nocapture
     #[test]
@@ -2,9 +2,18 @@
 //!
 //! Converts IR programs to StarPRNT protocol bytes.
 
-use super::ops::{BarcodeKind, Op, Program};
-use crate::printer::PrinterConfig;
+use std::io::{self, Write};
+
+use super::ops::{Op, Program, StyleStack};
+use crate::metrics::{self, Stage};
+use crate::printer::{Media, PrinterConfig};
+use crate::protocol::codepage::Encoder;
 use crate::protocol::{barcode, commands, graphics, nv_graphics, text};
+use crate::render::dither;
+
+/// Feed after a receipt on printers without a cutter (12mm, in 1/4mm units),
+/// so the end of it clears the tear bar.
+const TEAR_OFF_FEED_UNITS: u8 = 48;
 
 impl Program {
     /// Compile the IR program to StarPRNT bytes.
@@ -15,220 +24,446 @@ impl Program {
     }
 
     /// Compile the IR program to StarPRNT bytes with a specific printer config.
-    pub fn to_bytes_with_config(&self, _config: &PrinterConfig) -> Vec<u8> {
+    pub fn to_bytes_with_config(&self, config: &PrinterConfig) -> Vec<u8> {
         let mut out = Vec::new();
+        self.write_to(&mut out, config)
+            .expect("writing to a Vec cannot fail");
+        out
+    }
 
-        for op in &self.ops {
-            match op {
-                // ===== Printer Control =====
-                Op::Init => {
-                    out.extend(commands::init());
-                }
-                Op::Cut { partial } => {
-                    if *partial {
-                        out.extend(commands::cut_partial_feed());
-                    } else {
-                        out.extend(commands::cut_full_feed());
-                    }
-                }
-                Op::Feed { units } => {
-                    out.extend(commands::feed_units(*units));
-                }
-
-                // ===== Style Changes =====
-                Op::SetAlign(align) => {
-                    out.extend(text::align(*align));
-                }
-                Op::SetFont(font) => {
-                    out.extend(text::font(*font));
-                }
-                Op::SetBold(enabled) => {
-                    if *enabled {
-                        out.extend(text::bold_on());
-                    } else {
-                        out.extend(text::bold_off());
-                    }
-                }
-                Op::SetUnderline(enabled) => {
-                    if *enabled {
-                        out.extend(text::underline_on());
-                    } else {
-                        out.extend(text::underline_off());
-                    }
-                }
-                Op::SetInvert(enabled) => {
-                    if *enabled {
-                        out.extend(text::invert_on());
-                    } else {
-                        out.extend(text::invert_off());
-                    }
-                }
-                Op::SetSize { height, width } => {
-                    out.extend(text::size(*height, *width));
-                }
-                Op::SetExpandedWidth(mult) => {
-                    out.extend(text::expanded_width(*mult));
-                }
-                Op::SetExpandedHeight(mult) => {
-                    out.extend(text::expanded_height(*mult));
-                }
-                Op::SetSmoothing(enabled) => {
-                    if *enabled {
-                        out.extend(text::smoothing_on());
-                    } else {
-                        out.extend(text::smoothing_off());
-                    }
-                }
-                Op::SetUpperline(enabled) => {
-                    if *enabled {
-                        out.extend(text::upperline_on());
-                    } else {
-                        out.extend(text::upperline_off());
-                    }
-                }
-                Op::SetUpsideDown(enabled) => {
-                    if *enabled {
-                        out.extend(text::upside_down_on());
-                    } else {
-                        out.extend(text::upside_down_off());
-                    }
-                }
-                Op::SetReduced(enabled) => {
-                    if *enabled {
-                        out.extend(text::reduced(1, 1)); // Horizontal and vertical reduction
-                    } else {
-                        out.extend(text::reduced_off());
-                    }
-                }
-                Op::SetCodepage(page) => {
-                    out.extend(text::codepage_raw(*page));
-                }
-                Op::ResetStyle => {
-                    out.extend(text::TextStyle::reset());
-                }
+    /// Stream the compiled StarPRNT bytes into a writer.
+    ///
+    /// Commands are written op by op (and raster graphics chunk by chunk),
+    /// so a multi-meter print never needs its whole byte stream in memory.
+    /// Wrap unbuffered writers in a `BufWriter`: most commands are only a
+    /// few bytes long.
+    ///
+    /// Ops the printer can't perform are adapted to `config`: raster
+    /// graphics are chunked to `max_chunk_rows`, cuts fall back to what the
+    /// cutter supports, and NV logo commands are dropped without NV support.
+    ///
+    /// On [`Media::Label`], the black mark sensor is turned on after each
+    /// `Init`, cuts feed to the next mark first, and the feeds leading into
+    /// a cut are dropped (the mark decides where the label ends).
+    ///
+    /// Text is transcoded to the active code page, switching pages for
+    /// characters it lacks (see [`crate::protocol::codepage`]).
+    pub fn write_to<W: Write>(&self, out: &mut W, config: &PrinterConfig) -> io::Result<()> {
+        let mut codegen = Codegen::new(self, config);
+        while codegen.write_next(out)? {}
+        Ok(())
+    }
 
-                // ===== Content =====
-                Op::Text(s) => {
-                    out.extend(crate::protocol::cp437::encode(s));
-                }
-                Op::Newline => {
-                    out.push(0x0A);
-                }
-                Op::Raw(bytes) => {
-                    out.extend(bytes);
-                }
+    /// Compile the program into byte chunks of at least `size` bytes (the
+    /// last one may be shorter), generating each only when it is asked for.
+    ///
+    /// The async counterpart of [`write_to`](Self::write_to): an async
+    /// transport awaits each chunk's write before the next is generated, so
+    /// a long print never holds its whole byte stream. An op whose bytes
+    /// are longer than `size` (a tall raster) comes out in one chunk.
+    pub fn byte_chunks<'a>(&'a self, config: &'a PrinterConfig, size: usize) -> ByteChunks<'a> {
+        ByteChunks {
+            codegen: Codegen::new(self, config),
+            size,
+        }
+    }
 
-                // ===== Graphics =====
-                Op::Raster {
+    /// Lower every `GrayRaster` op to a 1-bit `Raster` op.
+    ///
+    /// Codegen and preview dither grayscale graphics on the fly, so this is
+    /// only needed by passes that work on packed raster data (e.g. splitting
+    /// long prints into chunks).
+    pub fn dither_graphics(self) -> Self {
+        let ops = self
+            .ops
+            .into_iter()
+            .map(|op| match op {
+                Op::GrayRaster {
                     width,
                     height,
                     data,
-                } => {
-                    // Chunk raster images for compatibility
-                    // 256 rows per chunk is the StarPRNT standard
-                    let width_bytes = width.div_ceil(8) as usize;
-                    let chunk_rows = 256usize;
-                    let total_height = *height as usize;
-
-                    let mut row_offset = 0;
-                    while row_offset < total_height {
-                        let chunk_height = (total_height - row_offset).min(chunk_rows);
-                        let byte_start = row_offset * width_bytes;
-                        let byte_end = (row_offset + chunk_height) * width_bytes;
-                        let chunk_data = &data[byte_start..byte_end];
-
-                        out.extend(graphics::raster(*width, chunk_height as u16, chunk_data));
-                        row_offset += chunk_height;
-                    }
-                }
-                Op::Band { width_bytes, data } => {
-                    // Band mode: 24-row chunks with feed after each band
-                    // Matches Python sick.py behavior
-                    let band_size = *width_bytes as usize * 24;
-
-                    for chunk in data.chunks(band_size) {
-                        if chunk.len() == band_size {
-                            out.extend(graphics::band(*width_bytes, chunk));
-                        } else {
-                            // Pad last band to 24 rows with white
-                            let mut padded = chunk.to_vec();
-                            padded.resize(band_size, 0x00);
-                            out.extend(graphics::band(*width_bytes, &padded));
-                        }
-                        // Feed 3mm after each band (12 units = 3mm)
-                        out.extend(commands::feed_units(12));
-                    }
-                }
+                    dither,
+                } => Op::Raster {
+                    width,
+                    height,
+                    data: dither::dither_levels(width as usize, height as usize, &data, dither),
+                },
+                other => other,
+            })
+            .collect();
+        Program { ops }
+    }
+}
 
-                // ===== Barcodes =====
-                Op::QrCode {
-                    data,
-                    cell_size,
-                    error_level,
-                } => {
-                    out.extend(barcode::qr::generate(
-                        data.as_bytes(),
-                        *cell_size,
-                        *error_level,
-                    ));
-                }
-                Op::Pdf417 {
-                    data,
-                    module_width,
-                    ecc_level,
-                } => {
-                    out.extend(barcode::pdf417::generate(
-                        data.as_bytes(),
-                        *module_width,
-                        *ecc_level,
-                    ));
-                }
-                Op::Barcode1D { kind, data, height } => {
-                    let barcode_fn = match kind {
-                        BarcodeKind::Code39 => barcode::barcode1d::code39,
-                        BarcodeKind::Code128 => barcode::barcode1d::code128,
-                        BarcodeKind::Ean13 => barcode::barcode1d::ean13,
-                        BarcodeKind::UpcA => barcode::barcode1d::upca,
-                        BarcodeKind::Itf => barcode::barcode1d::itf,
-                    };
-                    out.extend(barcode_fn(data.as_bytes(), *height));
-                }
+/// Iterator over a program's bytes in chunks; see [`Program::byte_chunks`].
+pub struct ByteChunks<'a> {
+    codegen: Codegen<'a>,
+    size: usize,
+}
 
-                // ===== Position =====
-                Op::SetAbsolutePosition(dots) => {
-                    out.extend(text::absolute_position(*dots));
-                }
+impl Iterator for ByteChunks<'_> {
+    type Item = Vec<u8>;
+
+    fn next(&mut self) -> Option<Vec<u8>> {
+        let mut chunk = Vec::with_capacity(self.size);
+        while chunk.len() < self.size
+            && self
+                .codegen
+                .write_next(&mut chunk)
+                .expect("writing to a Vec cannot fail")
+        {}
+        (!chunk.is_empty()).then_some(chunk)
+    }
+}
 
-                // ===== NV Graphics =====
-                Op::NvStore {
-                    key,
-                    width,
-                    height,
-                    data,
-                } => {
-                    if let Some(cmd) = nv_graphics::define(key, *width, *height, data) {
-                        out.extend(cmd);
-                    }
+/// Codegen state carried from one op to the next.
+struct Codegen<'a> {
+    ops: &'a [Op],
+    config: &'a PrinterConfig,
+    next: usize,
+    styles: StyleStack,
+    encoder: Encoder,
+}
+
+impl<'a> Codegen<'a> {
+    fn new(program: &'a Program, config: &'a PrinterConfig) -> Self {
+        Self {
+            ops: &program.ops,
+            config,
+            next: 0,
+            styles: StyleStack::default(),
+            encoder: Encoder::default(),
+        }
+    }
+
+    /// Write the bytes of the next op; `false` once every op is written.
+    fn write_next<W: Write>(&mut self, out: &mut W) -> io::Result<bool> {
+        let Some(op) = self.ops.get(self.next) else {
+            return Ok(false);
+        };
+        let rest = &self.ops[self.next..];
+        self.next += 1;
+        if self.config.media == Media::Label && feeds_into_cut(rest) {
+            return Ok(true);
+        }
+        match self.styles.resolve(op) {
+            Some(replacement) => {
+                for op in &replacement {
+                    write_timed(out, op, self.config, &mut self.encoder)?;
                 }
-                Op::NvPrint {
-                    key,
-                    scale_x,
-                    scale_y,
-                } => {
-                    if let Some(cmd) = nv_graphics::print(key, *scale_x, *scale_y) {
-                        out.extend(cmd);
-                    }
+            }
+            None => write_timed(out, op, self.config, &mut self.encoder)?,
+        }
+        Ok(true)
+    }
+}
+
+/// Whether `ops` starts with a feed that only runs into a cut.
+fn feeds_into_cut(ops: &[Op]) -> bool {
+    matches!(ops.first(), Some(Op::Feed { .. }))
+        && ops
+            .iter()
+            .find(|op| !matches!(op, Op::Feed { .. }))
+            .is_some_and(|op| matches!(op, Op::Cut { .. }))
+}
+
+/// [`write_op`], recorded in the codegen timings (see [`crate::metrics`]).
+fn write_timed<W: Write>(
+    out: &mut W,
+    op: &Op,
+    config: &PrinterConfig,
+    encoder: &mut Encoder,
+) -> io::Result<()> {
+    metrics::timed(Stage::Codegen, op.name(), || {
+        write_op(out, op, config, encoder)
+    })
+}
+
+/// Write the printer bytes for a single op.
+///
+/// `PushStyle`/`PopStyle` never reach this: [`StyleStack`] turns them into
+/// set ops first. `encoder` tracks the printer's code page across ops.
+fn write_op<W: Write>(
+    out: &mut W,
+    op: &Op,
+    config: &PrinterConfig,
+    encoder: &mut Encoder,
+) -> io::Result<()> {
+    match op {
+        // ===== Printer Control =====
+        Op::Init => {
+            out.write_all(&commands::init())?;
+            *encoder = Encoder::default();
+            if config.media == Media::Label {
+                out.write_all(&commands::black_mark_sensor(true))?;
+            }
+        }
+        Op::Cut { partial } if config.media == Media::Label => {
+            // The gap after the mark sits at the cutter (or tear bar)
+            out.write_all(&commands::feed_to_mark())?;
+            if config.cutter {
+                if *partial && config.partial_cut {
+                    out.write_all(&commands::cut_partial())?;
+                } else {
+                    out.write_all(&commands::cut_full())?;
                 }
-                Op::NvDelete { key } => {
-                    if let Some(cmd) = nv_graphics::erase(key) {
-                        out.extend(cmd);
-                    }
+            }
+        }
+        Op::Cut { .. } if !config.cutter => {
+            // No cutter: feed the receipt past the tear bar
+            out.write_all(&commands::feed_units(TEAR_OFF_FEED_UNITS))?;
+        }
+        Op::Cut { partial } => {
+            if *partial && config.partial_cut {
+                out.write_all(&commands::cut_partial_feed())?;
+            } else {
+                out.write_all(&commands::cut_full_feed())?;
+            }
+        }
+        Op::Feed { units } => {
+            out.write_all(&commands::feed_units(*units))?;
+        }
+        Op::FeedToMark => {
+            out.write_all(&commands::feed_to_mark())?;
+        }
+
+        // ===== Style Changes =====
+        Op::SetAlign(align) => {
+            out.write_all(&text::align(*align))?;
+        }
+        Op::SetFont(font) => {
+            out.write_all(&text::font(*font))?;
+        }
+        Op::SetBold(enabled) => {
+            if *enabled {
+                out.write_all(&text::bold_on())?;
+            } else {
+                out.write_all(&text::bold_off())?;
+            }
+        }
+        Op::SetUnderline(enabled) => {
+            if *enabled {
+                out.write_all(&text::underline_on())?;
+            } else {
+                out.write_all(&text::underline_off())?;
+            }
+        }
+        Op::SetInvert(enabled) => {
+            if *enabled {
+                out.write_all(&text::invert_on())?;
+            } else {
+                out.write_all(&text::invert_off())?;
+            }
+        }
+        Op::SetSize { height, width } => {
+            out.write_all(&text::size(*height, *width))?;
+        }
+        Op::SetExpandedWidth(mult) => {
+            out.write_all(&text::expanded_width(*mult))?;
+        }
+        Op::SetExpandedHeight(mult) => {
+            out.write_all(&text::expanded_height(*mult))?;
+        }
+        Op::SetSmoothing(enabled) => {
+            if *enabled {
+                out.write_all(&text::smoothing_on())?;
+            } else {
+                out.write_all(&text::smoothing_off())?;
+            }
+        }
+        Op::SetUpperline(enabled) => {
+            if *enabled {
+                out.write_all(&text::upperline_on())?;
+            } else {
+                out.write_all(&text::upperline_off())?;
+            }
+        }
+        Op::SetUpsideDown(enabled) => {
+            if *enabled {
+                out.write_all(&text::upside_down_on())?;
+            } else {
+                out.write_all(&text::upside_down_off())?;
+            }
+        }
+        Op::SetReduced(enabled) => {
+            if *enabled {
+                out.write_all(&text::reduced(1, 1))?; // Horizontal and vertical reduction
+            } else {
+                out.write_all(&text::reduced_off())?;
+            }
+        }
+        Op::SetCodepage(page) => {
+            out.write_all(&text::codepage_raw(*page))?;
+            encoder.set_page(*page);
+        }
+        Op::SetLineSpacing(spacing) => {
+            out.write_all(&text::line_spacing(*spacing))?;
+        }
+        Op::SetDensity(level) => {
+            out.write_all(&commands::set_print_density(*level))?;
+        }
+        Op::SetSpeed(speed) => {
+            out.write_all(&commands::set_print_speed(*speed))?;
+        }
+        Op::ResetStyle => {
+            out.write_all(&text::TextStyle::reset())?;
+        }
+        Op::PushStyle | Op::PopStyle => {
+            // Resolved to set ops by `write_to`
+        }
+
+        // ===== Content =====
+        Op::Text(s) => {
+            out.write_all(&encoder.encode(s))?;
+        }
+        Op::Newline => {
+            out.write_all(&[0x0A])?;
+        }
+        Op::Raw(bytes) => {
+            out.write_all(bytes)?;
+        }
+
+        // ===== Graphics =====
+        Op::Raster {
+            width,
+            height,
+            data,
+        } => {
+            emit_raster(out, *width, *height, data, config)?;
+        }
+        Op::GrayRaster {
+            width,
+            height,
+            data,
+            dither,
+        } => {
+            let packed = dither::dither_levels(*width as usize, *height as usize, data, *dither);
+            emit_raster(out, *width, *height, &packed, config)?;
+        }
+        Op::Band { width_bytes, data } => {
+            // Band mode: 24-row chunks with feed after each band
+            // Matches Python sick.py behavior
+            let band_size = *width_bytes as usize * 24;
+
+            for chunk in data.chunks(band_size) {
+                if chunk.len() == band_size {
+                    out.write_all(&graphics::band(*width_bytes, chunk))?;
+                } else {
+                    // Pad last band to 24 rows with white
+                    let mut padded = chunk.to_vec();
+                    padded.resize(band_size, 0x00);
+                    out.write_all(&graphics::band(*width_bytes, &padded))?;
                 }
+                // Feed 3mm after each band (12 units = 3mm)
+                out.write_all(&commands::feed_units(12))?;
             }
         }
 
-        out
+        // ===== Barcodes =====
+        Op::QrCode {
+            data,
+            cell_size,
+            error_level,
+        } => {
+            out.write_all(&barcode::qr::generate(
+                data.as_bytes(),
+                *cell_size,
+                *error_level,
+            ))?;
+        }
+        Op::Pdf417 {
+            data,
+            module_width,
+            ecc_level,
+        } => {
+            out.write_all(&barcode::pdf417::generate(
+                data.as_bytes(),
+                *module_width,
+                *ecc_level,
+            ))?;
+        }
+        Op::Barcode1D {
+            kind,
+            data,
+            height,
+            hri,
+        } => {
+            out.write_all(&barcode::barcode1d::barcode(
+                kind.barcode_type(),
+                data.as_bytes(),
+                *height,
+                *hri,
+                barcode::barcode1d::HriFont::FontA,
+                kind.module_width(),
+            ))?;
+        }
+
+        // ===== Position =====
+        Op::SetAbsolutePosition(dots) => {
+            out.write_all(&text::absolute_position(*dots))?;
+        }
+
+        // ===== NV Graphics =====
+        Op::NvStore { .. } | Op::NvPrint { .. } | Op::NvDelete { .. } if !config.nv_graphics => {
+            // Printer has no NV memory
+        }
+        Op::NvStore {
+            key,
+            width,
+            height,
+            data,
+        } => {
+            if let Some(cmd) = nv_graphics::define(key, *width, *height, data) {
+                out.write_all(&cmd)?;
+            }
+        }
+        Op::NvPrint {
+            key,
+            scale_x,
+            scale_y,
+        } => {
+            if let Some(cmd) = nv_graphics::print(key, *scale_x, *scale_y) {
+                out.write_all(&cmd)?;
+            }
+        }
+        Op::NvDelete { key } => {
+            if let Some(cmd) = nv_graphics::erase(key) {
+                out.write_all(&cmd)?;
+            }
+        }
+        Op::SetWrap(_) | Op::Marker(_) => {
+            // Optimizer and debug only: no printer bytes
+        }
+    }
+    Ok(())
+}
+
+/// Write raster graphics commands, chunked for compatibility.
+///
+/// Chunks are `config.max_chunk_rows` tall: 256 rows is the StarPRNT
+/// standard, printers with small buffers need less.
+fn emit_raster<W: Write>(
+    out: &mut W,
+    width: u16,
+    height: u16,
+    data: &[u8],
+    config: &PrinterConfig,
+) -> io::Result<()> {
+    let width_bytes = width.div_ceil(8) as usize;
+    let chunk_rows = (config.max_chunk_rows as usize).max(1);
+    let total_height = height as usize;
+
+    let mut row_offset = 0;
+    while row_offset < total_height {
+        let chunk_height = (total_height - row_offset).min(chunk_rows);
+        let byte_start = row_offset * width_bytes;
+        let byte_end = (row_offset + chunk_height) * width_bytes;
+        let chunk_data = &data[byte_start..byte_end];
+
+        out.write_all(&graphics::raster(width, chunk_height as u16, chunk_data))?;
+        row_offset += chunk_height;
     }
+    Ok(())
 }
 
 #[cfg(test)]
@@ -303,6 +538,44 @@ mod tests {
         assert!(bytes.ends_with(&[0x1B, 0x64, 0x03]));
     }
 
+    #[test]
+    fn test_cut_adapts_to_printer() {
+        let mut program = Program::with_init();
+        program.push(Op::Cut { partial: true });
+
+        // No cutter: feed to the tear bar (ESC J 48)
+        let bytes = program.to_bytes_with_config(&PrinterConfig::SM_L200);
+        assert!(bytes.ends_with(&[0x1B, 0x4A, 48]));
+
+        // No partial cut: full cut instead
+        let full_only = PrinterConfig {
+            partial_cut: false,
+            ..PrinterConfig::TSP650II
+        };
+        let bytes = program.to_bytes_with_config(&full_only);
+        assert!(bytes.ends_with(&[0x1B, 0x64, 0x02]));
+    }
+
+    #[test]
+    fn test_label_media() {
+        let mut program = Program::with_init();
+        program.push(Op::Text("LOT 7".into()));
+        program.push(Op::Feed { units: 24 });
+        program.push(Op::Cut { partial: true });
+        program.push(Op::FeedToMark);
+
+        let labels = PrinterConfig::TSP700II.with_media(Media::Label);
+        let bytes = program.to_bytes_with_config(&labels);
+        // Sensor on after init, leader feed dropped, cut at the mark
+        assert!(bytes.starts_with(&[0x1B, 0x40, 0x1B, 0x1E, 0x6D, 1]));
+        assert!(!bytes.windows(2).any(|w| w == [0x1B, 0x4A]));
+        assert!(bytes.ends_with(b"LOT 7\x0C\x1B\x64\x01\x0C"));
+
+        // No cutter: tear off at the mark
+        let bytes = program.to_bytes_with_config(&PrinterConfig::SM_L200.with_media(Media::Label));
+        assert!(bytes.ends_with(b"LOT 7\x0C\x0C"));
+    }
+
     #[test]
     fn test_feed() {
         let mut program = Program::new();
@@ -329,6 +602,29 @@ mod tests {
         assert!(bytes.starts_with(&[0x1B, 0x1D, 0x53]));
     }
 
+    #[test]
+    fn test_gray_raster_matches_dithered_raster() {
+        let gray: Vec<u8> = (0..16 * 4).map(|i| (i % 16) * 17).collect();
+        let packed = dither::dither_levels(16, 4, &gray, dither::DitheringAlgorithm::Bayer);
+
+        let mut gray_program = Program::new();
+        gray_program.push(Op::GrayRaster {
+            width: 16,
+            height: 4,
+            data: gray,
+            dither: dither::DitheringAlgorithm::Bayer,
+        });
+        let mut raster_program = Program::new();
+        raster_program.push(Op::Raster {
+            width: 16,
+            height: 4,
+            data: packed,
+        });
+
+        assert_eq!(gray_program.to_bytes(), raster_program.to_bytes());
+        assert_eq!(gray_program.dither_graphics().ops, raster_program.ops);
+    }
+
     #[test]
     fn test_qr_code() {
         let mut program = Program::new();
@@ -357,4 +653,104 @@ mod tests {
         let bytes = program.to_bytes();
         assert_eq!(bytes, vec![0x01, 0x02, 0x03]);
     }
+
+    #[test]
+    fn test_marker_emits_nothing() {
+        let mut program = Program::new();
+        program.push(Op::Marker("component 0".into()));
+        program.push(Op::Raw(vec![0x01]));
+        program.push(Op::Marker("component 1".into()));
+
+        assert_eq!(program.to_bytes(), vec![0x01]);
+    }
+
+    #[test]
+    fn test_write_to_streams_raster_in_chunks() {
+        /// Records the size of every write.
+        struct Recorder {
+            bytes: Vec<u8>,
+            largest_write: usize,
+        }
+        impl Write for Recorder {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                self.largest_write = self.largest_write.max(buf.len());
+                self.bytes.extend_from_slice(buf);
+                Ok(buf.len())
+            }
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let mut program = Program::new();
+        program.push(Op::Init);
+        program.push(Op::Raster {
+            width: 576,
+            height: 1000,
+            data: vec![0xAA; 72 * 1000],
+        });
+        program.push(Op::Cut { partial: true });
+
+        let mut recorder = Recorder {
+            bytes: Vec::new(),
+            largest_write: 0,
+        };
+        program
+            .write_to(&mut recorder, &PrinterConfig::TSP650II)
+            .unwrap();
+
+        assert_eq!(recorder.bytes, program.to_bytes());
+        // No write is larger than one 256-row raster chunk (plus header)
+        assert!(recorder.largest_write < 72 * 257);
+    }
+
+    #[test]
+    fn test_byte_chunks_match_to_bytes() {
+        let mut program = Program::with_init();
+        for i in 0..100 {
+            program.push(Op::Text(format!("line {}", i)));
+            program.push(Op::Newline);
+        }
+        program.push(Op::Cut { partial: true });
+
+        let chunks: Vec<Vec<u8>> = program.byte_chunks(&PrinterConfig::TSP650II, 64).collect();
+        assert!(chunks.len() > 1);
+        assert!(chunks[..chunks.len() - 1].iter().all(|c| c.len() >= 64));
+        assert_eq!(chunks.concat(), program.to_bytes());
+        assert_eq!(
+            Program::new()
+                .byte_chunks(&PrinterConfig::TSP650II, 64)
+                .count(),
+            0
+        );
+    }
+    #[test]
+    fn test_style_stack_expands_to_set_commands() {
+        let mut program = Program::with_init();
+        program.push(Op::SetAlign(Alignment::Center));
+        program.push(Op::PushStyle);
+        program.push(Op::SetBold(true));
+        program.push(Op::SetAlign(Alignment::Right));
+        program.push(Op::PushStyle);
+        program.push(Op::SetUnderline(true));
+        program.push(Op::PopStyle);
+        program.push(Op::Text("inner".into()));
+        program.push(Op::PopStyle);
+        program.push(Op::Text("outer".into()));
+        program.push(Op::PopStyle); // Unbalanced: ignored
+
+        let mut explicit = Program::with_init();
+        explicit.push(Op::SetAlign(Alignment::Center));
+        explicit.push(Op::SetBold(true));
+        explicit.push(Op::SetAlign(Alignment::Right));
+        explicit.push(Op::SetUnderline(true));
+        explicit.push(Op::SetUnderline(false));
+        explicit.push(Op::Text("inner".into()));
+        explicit.push(Op::SetAlign(Alignment::Center));
+        explicit.push(Op::SetBold(false));
+        explicit.push(Op::Text("outer".into()));
+
+        assert_eq!(program.to_bytes(), explicit.to_bytes());
+        assert_eq!(program.optimize().ops, explicit.optimize().ops);
+    }
 }
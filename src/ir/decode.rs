@@ -0,0 +1,525 @@
+//! # Decoder
+//!
+//! Parses StarPRNT bytes back into IR ops: the inverse of codegen.
+//!
+//! Only the commands codegen emits are understood. Decoding is lossy where
+//! the protocol is: raster widths round up to whole bytes, long rasters come
+//! back as 256-row chunks, and `ResetStyle` comes back as the individual
+//! style ops it expands to. Malformed or unknown input is an error, never a
+//! panic, so the decoder is safe to point at arbitrary bytes.
+
+use thiserror::Error;
+
+use super::ops::{BarcodeKind, Op, Program};
+use crate::protocol::barcode::barcode1d::HriPosition;
+use crate::protocol::barcode::qr::QrErrorLevel;
+use crate::protocol::codepage;
+use crate::protocol::commands::{ESC, FF, GS, LF, MAX_DENSITY, PrintSpeed, RS};
+use crate::protocol::text::{Alignment, Font, LineSpacing};
+
+const SI: u8 = 0x0F;
+const DC2: u8 = 0x12;
+
+/// Errors that can occur while decoding printer bytes.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum DecodeError {
+    #[error("truncated command at byte {0}")]
+    Truncated(usize),
+
+    #[error("unknown command at byte {offset}: {bytes:02X?}")]
+    Unknown { offset: usize, bytes: Vec<u8> },
+
+    #[error("invalid parameter at byte {offset}: {message}")]
+    InvalidParam { offset: usize, message: String },
+}
+
+/// Barcode settings accumulated across QR/PDF417 setup commands.
+#[derive(Default)]
+struct BarcodeState {
+    qr_cell_size: u8,
+    qr_error_level: QrErrorLevel,
+    qr_data: Option<String>,
+    pdf417_module_width: u8,
+    pdf417_ecc_level: u8,
+    pdf417_data: Option<String>,
+}
+
+struct Decoder<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    ops: Vec<Op>,
+    text: Vec<u8>,
+    /// Code page the pending text was printed on
+    page: u8,
+    barcode: BarcodeState,
+}
+
+impl Program {
+    /// Decode StarPRNT bytes back into an IR program.
+    ///
+    /// ```
+    /// use estrella::ir::{Op, Program};
+    ///
+    /// let mut program = Program::new();
+    /// program.push(Op::SetBold(true));
+    /// program.push(Op::Text("Hi".into()));
+    ///
+    /// let decoded = Program::from_bytes(&program.to_bytes()).unwrap();
+    /// assert_eq!(decoded.ops, program.ops);
+    /// ```
+    pub fn from_bytes(bytes: &[u8]) -> Result<Program, DecodeError> {
+        let mut decoder = Decoder {
+            bytes,
+            pos: 0,
+            ops: Vec::new(),
+            text: Vec::new(),
+            page: codepage::Encoder::default().page(),
+            barcode: BarcodeState::default(),
+        };
+        decoder.run()?;
+        Ok(Program { ops: decoder.ops })
+    }
+}
+
+impl Decoder<'_> {
+    fn run(&mut self) -> Result<(), DecodeError> {
+        while self.pos < self.bytes.len() {
+            let byte = self.bytes[self.pos];
+            match byte {
+                ESC => {
+                    self.flush_text();
+                    let start = self.pos;
+                    self.pos += 1;
+                    self.escape(start)?;
+                }
+                LF => self.push(Op::Newline),
+                FF => self.push(Op::FeedToMark),
+                SI => self.push(Op::SetUpsideDown(true)),
+                DC2 => self.push(Op::SetUpsideDown(false)),
+                0x20..=0x7E | 0x80..=0xFF => {
+                    self.text.push(byte);
+                    self.pos += 1;
+                }
+                _ => return Err(self.unknown(self.pos, 1)),
+            }
+        }
+        self.flush_text();
+        Ok(())
+    }
+
+    /// Push a single-byte op.
+    fn push(&mut self, op: Op) {
+        self.flush_text();
+        self.ops.push(op);
+        self.pos += 1;
+    }
+
+    fn flush_text(&mut self) {
+        if !self.text.is_empty() {
+            self.ops
+                .push(Op::Text(codepage::decode(&self.text, self.page)));
+            self.text.clear();
+        }
+    }
+
+    /// Consume `n` bytes.
+    fn take(&mut self, n: usize) -> Result<&[u8], DecodeError> {
+        let end = self
+            .pos
+            .checked_add(n)
+            .filter(|&end| end <= self.bytes.len())
+            .ok_or(DecodeError::Truncated(self.pos))?;
+        let slice = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn byte(&mut self) -> Result<u8, DecodeError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16_le(&mut self) -> Result<u16, DecodeError> {
+        let b = self.take(2)?;
+        Ok(u16::from_le_bytes([b[0], b[1]]))
+    }
+
+    fn utf8(&mut self, len: usize, offset: usize) -> Result<String, DecodeError> {
+        let bytes = self.take(len)?.to_vec();
+        String::from_utf8(bytes).map_err(|_| invalid(offset, "data is not valid UTF-8"))
+    }
+
+    fn unknown(&self, offset: usize, len: usize) -> DecodeError {
+        let end = (offset + len).min(self.bytes.len());
+        DecodeError::Unknown {
+            offset,
+            bytes: self.bytes[offset..end].to_vec(),
+        }
+    }
+
+    /// Decode a command starting with ESC (already consumed).
+    fn escape(&mut self, start: usize) -> Result<(), DecodeError> {
+        let op = match self.byte()? {
+            b'@' => {
+                self.page = codepage::Encoder::default().page();
+                Op::Init
+            }
+            b'd' => Op::Cut {
+                partial: (self.byte()? & 1) == 1,
+            },
+            b'J' => Op::Feed {
+                units: self.byte()?,
+            },
+            b'E' => Op::SetBold(true),
+            b'F' => Op::SetBold(false),
+            b'-' => Op::SetUnderline(self.byte()? != 0),
+            b'_' => Op::SetUpperline(self.byte()? != 0),
+            b'4' => Op::SetInvert(true),
+            b'5' => Op::SetInvert(false),
+            b'i' => {
+                let height = self.byte()?;
+                let width = self.byte()?;
+                Op::SetSize { height, width }
+            }
+            b'z' => Op::SetLineSpacing(match self.byte()? {
+                0 => LineSpacing::Tight,
+                _ => LineSpacing::Normal,
+            }),
+            b'W' => Op::SetExpandedWidth(self.byte()?),
+            b'h' => Op::SetExpandedHeight(self.byte()?),
+            b'k' => {
+                let width_bytes = self.byte()?;
+                self.byte()?; // n2 is always 0
+                let data = self.take(width_bytes as usize * 24)?.to_vec();
+                Op::Band { width_bytes, data }
+            }
+            b'b' => return self.barcode_1d(start),
+            GS => return self.escape_gs(start),
+            RS => match self.byte()? {
+                b'F' => Op::SetFont(match self.byte()? {
+                    0 => Font::A,
+                    1 => Font::B,
+                    2 => Font::C,
+                    n => return Err(invalid(start, format!("font {}", n))),
+                }),
+                b'd' => match self.byte()? {
+                    n @ 0..=6 => Op::SetDensity(MAX_DENSITY - n as i8),
+                    n => return Err(invalid(start, format!("print density {}", n))),
+                },
+                b'm' => {
+                    // Label media setup from codegen, not an op of its own
+                    self.byte()?;
+                    return Ok(());
+                }
+                b'r' => Op::SetSpeed(match self.byte()? {
+                    0 => PrintSpeed::High,
+                    1 => PrintSpeed::Medium,
+                    2 => PrintSpeed::Low,
+                    n => return Err(invalid(start, format!("print speed {}", n))),
+                }),
+                _ => return Err(self.unknown(start, 3)),
+            },
+            _ => return Err(self.unknown(start, 2)),
+        };
+        self.ops.push(op);
+        Ok(())
+    }
+
+    /// Decode a command starting with ESC GS.
+    fn escape_gs(&mut self, start: usize) -> Result<(), DecodeError> {
+        let op = match self.byte()? {
+            b'a' => Op::SetAlign(match self.byte()? {
+                0 => Alignment::Left,
+                1 => Alignment::Center,
+                2 => Alignment::Right,
+                n => return Err(invalid(start, format!("alignment {}", n))),
+            }),
+            b'A' => Op::SetAbsolutePosition(self.u16_le()?),
+            b'c' => {
+                let horizontal = self.byte()?;
+                let vertical = self.byte()?;
+                Op::SetReduced(horizontal != 0 || vertical != 0)
+            }
+            b't' => {
+                self.page = self.byte()?;
+                Op::SetCodepage(self.page)
+            }
+            b'b' => Op::SetSmoothing(self.byte()? != 0),
+            b'S' => {
+                self.byte()?; // m = 1 (monochrome)
+                let width_bytes = self.u16_le()?;
+                let height = self.u16_le()?;
+                self.byte()?; // n = 0 (black)
+                let data = self.take(width_bytes as usize * height as usize)?.to_vec();
+                let width = width_bytes
+                    .checked_mul(8)
+                    .ok_or_else(|| invalid(start, "raster wider than 65535 dots"))?;
+                Op::Raster {
+                    width,
+                    height,
+                    data,
+                }
+            }
+            b'y' => return self.qr(start),
+            b'x' => return self.pdf417(start),
+            b'(' => return self.nv_graphics(start),
+            _ => return Err(self.unknown(start, 3)),
+        };
+        self.ops.push(op);
+        Ok(())
+    }
+
+    /// ESC b n1 n2 n3 n4 data RS
+    fn barcode_1d(&mut self, start: usize) -> Result<(), DecodeError> {
+        let kind = match self.byte()? {
+            49 => BarcodeKind::UpcA,
+            51 => BarcodeKind::Ean13,
+            52 => BarcodeKind::Code39,
+            53 => BarcodeKind::Itf,
+            54 => BarcodeKind::Code128,
+            55 => BarcodeKind::Code93,
+            56 => BarcodeKind::Nw7,
+            n => return Err(invalid(start, format!("barcode type {}", n))),
+        };
+        let hri = match self.byte()? {
+            1 | 3 | 49 | 51 => HriPosition::None,
+            _ => HriPosition::Below,
+        };
+        self.take(1)?; // Module width
+        let height = self.byte()?;
+
+        let data_start = self.pos;
+        let len = self.bytes[data_start..]
+            .iter()
+            .position(|&b| b == RS)
+            .ok_or(DecodeError::Truncated(start))?;
+        let data = self.utf8(len, start)?;
+        self.pos += 1; // RS terminator
+
+        self.ops.push(Op::Barcode1D {
+            kind,
+            data,
+            height,
+            hri,
+        });
+        Ok(())
+    }
+
+    /// ESC GS y S/D/P - QR code setup, data and print.
+    fn qr(&mut self, start: usize) -> Result<(), DecodeError> {
+        match self.byte()? {
+            b'S' => {
+                let function = self.byte()?;
+                let value = self.byte()?;
+                match function {
+                    b'0' => {} // Model is always 2
+                    b'1' => {
+                        self.barcode.qr_error_level = match value {
+                            0 => QrErrorLevel::L,
+                            1 => QrErrorLevel::M,
+                            2 => QrErrorLevel::Q,
+                            3 => QrErrorLevel::H,
+                            n => return Err(invalid(start, format!("QR error level {}", n))),
+                        }
+                    }
+                    b'2' => self.barcode.qr_cell_size = value,
+                    _ => return Err(self.unknown(start, 5)),
+                }
+            }
+            b'D' => {
+                self.take(2)?; // '1', mode 0 (auto)
+                let len = self.u16_le()? as usize;
+                self.barcode.qr_data = Some(self.utf8(len, start)?);
+            }
+            b'P' => {
+                let data = self
+                    .barcode
+                    .qr_data
+                    .take()
+                    .ok_or_else(|| invalid(start, "QR print without data"))?;
+                self.ops.push(Op::QrCode {
+                    data,
+                    cell_size: self.barcode.qr_cell_size,
+                    error_level: self.barcode.qr_error_level,
+                });
+            }
+            _ => return Err(self.unknown(start, 4)),
+        }
+        Ok(())
+    }
+
+    /// ESC GS x S/D/P - PDF417 setup, data and print.
+    fn pdf417(&mut self, start: usize) -> Result<(), DecodeError> {
+        match self.byte()? {
+            b'S' => match self.byte()? {
+                b'0' => {
+                    self.take(3)?; // Size mode and two parameters
+                }
+                b'1' => self.barcode.pdf417_ecc_level = self.byte()?,
+                b'2' => self.barcode.pdf417_module_width = self.byte()?,
+                b'3' => {
+                    self.byte()?; // Aspect is always 3
+                }
+                _ => return Err(self.unknown(start, 5)),
+            },
+            b'D' => {
+                let len = self.u16_le()? as usize;
+                self.barcode.pdf417_data = Some(self.utf8(len, start)?);
+            }
+            b'P' => {
+                let data = self
+                    .barcode
+                    .pdf417_data
+                    .take()
+                    .ok_or_else(|| invalid(start, "PDF417 print without data"))?;
+                self.ops.push(Op::Pdf417 {
+                    data,
+                    module_width: self.barcode.pdf417_module_width,
+                    ecc_level: self.barcode.pdf417_ecc_level,
+                });
+            }
+            _ => return Err(self.unknown(start, 4)),
+        }
+        Ok(())
+    }
+
+    /// ESC GS ( L pL pH m fn ... - NV graphics.
+    fn nv_graphics(&mut self, start: usize) -> Result<(), DecodeError> {
+        if self.byte()? != b'L' {
+            return Err(self.unknown(start, 4));
+        }
+        let body_len = self.u16_le()? as usize;
+        let body = self.take(body_len)?.to_vec();
+        let key = |at: usize| -> Result<String, DecodeError> {
+            let kc = body.get(at..at + 2).ok_or(DecodeError::Truncated(start))?;
+            Ok(kc.iter().map(|&b| b as char).collect())
+        };
+
+        let op = match body.get(..2) {
+            Some([48, 66]) => Op::NvDelete { key: key(2)? },
+            Some([48, 67]) => {
+                // a kc1 kc2 b xL xH yL yH c data...
+                let header = body.get(2..11).ok_or(DecodeError::Truncated(start))?;
+                Op::NvStore {
+                    key: key(3)?,
+                    width: u16::from_le_bytes([header[4], header[5]]),
+                    height: u16::from_le_bytes([header[6], header[7]]),
+                    data: body[11..].to_vec(),
+                }
+            }
+            Some([48, 69]) => {
+                let scale = body.get(4..6).ok_or(DecodeError::Truncated(start))?;
+                Op::NvPrint {
+                    key: key(2)?,
+                    scale_x: scale[0],
+                    scale_y: scale[1],
+                }
+            }
+            _ => return Err(self.unknown(start, 8)),
+        };
+        self.ops.push(op);
+        Ok(())
+    }
+}
+
+fn invalid(offset: usize, message: impl Into<String>) -> DecodeError {
+    DecodeError::InvalidParam {
+        offset,
+        message: message.into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::text;
+
+    #[test]
+    fn test_round_trip_text_and_styles() {
+        let mut program = Program::with_init();
+        program.push(Op::SetAlign(Alignment::Center));
+        program.push(Op::SetSize {
+            height: 1,
+            width: 2,
+        });
+        program.push(Op::Text("Año 2026".into()));
+        program.push(Op::Newline);
+        program.push(Op::Cut { partial: true });
+
+        let decoded = Program::from_bytes(&program.to_bytes()).unwrap();
+        assert_eq!(decoded.ops, program.ops);
+    }
+
+    #[test]
+    fn test_round_trip_barcodes() {
+        let mut program = Program::new();
+        program.push(Op::QrCode {
+            data: "https://example.com".into(),
+            cell_size: 4,
+            error_level: QrErrorLevel::H,
+        });
+        program.push(Op::Pdf417 {
+            data: "PDF417".into(),
+            module_width: 3,
+            ecc_level: 2,
+        });
+        program.push(Op::Barcode1D {
+            kind: BarcodeKind::Code128,
+            data: "ABC-123".into(),
+            height: 80,
+            hri: HriPosition::Below,
+        });
+
+        let decoded = Program::from_bytes(&program.to_bytes()).unwrap();
+        assert_eq!(decoded.ops, program.ops);
+    }
+
+    #[test]
+    fn test_label_media_round_trip() {
+        let mut program = Program::with_init();
+        program.push(Op::Text("LOT 7".into()));
+        program.push(Op::FeedToMark);
+
+        let labels =
+            crate::printer::PrinterConfig::TSP700II.with_media(crate::printer::Media::Label);
+        let decoded = Program::from_bytes(&program.to_bytes_with_config(&labels)).unwrap();
+        assert_eq!(decoded.ops, program.ops);
+    }
+
+    #[test]
+    fn test_round_trip_code_page_switches() {
+        let mut program = Program::with_init();
+        program.push(Op::Text("Grüße 5€, Łódź ─".into()));
+
+        let decoded = Program::from_bytes(&program.to_bytes()).unwrap();
+        let text: String = decoded
+            .ops
+            .iter()
+            .filter_map(|op| match op {
+                Op::Text(s) => Some(s.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(text, "Grüße 5€, Łódź ─");
+        assert!(decoded.ops.contains(&Op::SetCodepage(3)));
+        assert!(decoded.ops.contains(&Op::SetCodepage(4)));
+    }
+
+    #[test]
+    fn test_reset_style_expands() {
+        let decoded = Program::from_bytes(&text::TextStyle::reset()).unwrap();
+        assert!(decoded.ops.contains(&Op::SetBold(false)));
+    }
+
+    #[test]
+    fn test_malformed_input_is_an_error() {
+        assert_eq!(
+            Program::from_bytes(&[ESC, GS, b'S', 1, 72, 0, 10]).unwrap_err(),
+            DecodeError::Truncated(6)
+        );
+        assert!(matches!(
+            Program::from_bytes(&[ESC, b'~']),
+            Err(DecodeError::Unknown { offset: 0, .. })
+        ));
+        assert!(Program::from_bytes(&[0x01]).is_err());
+    }
+}
@@ -18,7 +18,7 @@
 //! 1. **Inspectable**: Debug and visualize what will be printed
 //! 2. **Optimizable**: Remove redundant style changes, merge text
 //! 3. **Testable**: Unit test components without actual printer
-//! 4. **Serializable**: Export/import print jobs (future)
+//! 4. **Serializable**: Export/import print jobs as JSON ([`Program::to_json`])
 //!
 //! ## Example
 //!
@@ -43,9 +43,12 @@
 
 pub mod analyze;
 pub mod codegen;
+pub mod decode;
 mod ops;
 mod optimize;
 pub mod split;
 
 // Re-export the ops types (codegen and optimize add methods to Program via impl)
 pub use ops::*;
+pub use optimize::OptimizeLevel;
+pub(crate) use optimize::word_wrap;
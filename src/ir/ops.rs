@@ -14,9 +14,22 @@
 //!
 //! Each opcode represents a single, atomic operation. Style changes are
 //! individual ops (not combined) to enable fine-grained optimization.
-
+//!
+//! ## JSON
+//!
+//! Programs serialize to JSON so print jobs can be stored, queued and
+//! replayed on another machine ([`Program::to_json`]). Ops are
+//! externally tagged in snake_case (`"init"`, `{"set_bold": true}`,
+//! `{"cut": {"partial": true}}`); binary payloads are hex strings.
+
+#[cfg(feature = "images")]
+use crate::printer::PrinterConfig;
+use crate::protocol::barcode::barcode1d::{BarcodeType, HriPosition, ModuleWidth};
 use crate::protocol::barcode::qr::QrErrorLevel;
-use crate::protocol::text::{Alignment, Font};
+use crate::protocol::commands::PrintSpeed;
+use crate::protocol::text::{Alignment, Font, LineSpacing};
+use crate::render::dither::DitheringAlgorithm;
+use serde::{Deserialize, Serialize};
 
 /// Graphics rendering mode.
 ///
@@ -40,6 +53,57 @@ pub enum BarcodeKind {
     #[serde(rename = "upca")]
     UpcA,
     Itf,
+    Code93,
+    Nw7,
+}
+
+impl BarcodeKind {
+    /// The printer's barcode type for this kind.
+    pub fn barcode_type(self) -> BarcodeType {
+        match self {
+            BarcodeKind::Code39 => BarcodeType::Code39,
+            BarcodeKind::Code128 => BarcodeType::Code128,
+            BarcodeKind::Ean13 => BarcodeType::Ean13,
+            BarcodeKind::UpcA => BarcodeType::UpcA,
+            BarcodeKind::Itf => BarcodeType::Itf,
+            BarcodeKind::Code93 => BarcodeType::Code93,
+            BarcodeKind::Nw7 => BarcodeType::Nw7,
+        }
+    }
+
+    /// Module width used for this kind: 2 dots for the dense symbologies,
+    /// 3 for retail and numeric ones.
+    pub fn module_width(self) -> ModuleWidth {
+        match self {
+            BarcodeKind::Code39 | BarcodeKind::Code128 | BarcodeKind::Code93 => ModuleWidth::Dots2,
+            BarcodeKind::Ean13 | BarcodeKind::UpcA | BarcodeKind::Itf | BarcodeKind::Nw7 => {
+                ModuleWidth::Dots3
+            }
+        }
+    }
+}
+
+/// How the optimizer breaks text that doesn't fit on a line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WrapMode {
+    /// Break between words; words longer than a line are split.
+    #[default]
+    Word,
+    /// Break at the last character that fits, mid-word.
+    Char,
+    /// Don't wrap: cut each line off at the edge of the paper.
+    None,
+}
+
+/// Text wrapping, set with [`Op::SetWrap`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct Wrap {
+    #[serde(default)]
+    pub mode: WrapMode,
+    /// Spaces before each continuation line of a wrapped paragraph.
+    #[serde(default)]
+    pub hanging_indent: u8,
 }
 
 /// Style state tracked for optimization.
@@ -61,22 +125,141 @@ pub struct StyleState {
     pub expanded_height: u8,
     pub height_mult: u8,
     pub width_mult: u8,
+    /// Text wrapping (optimizer only, not a printer setting)
+    pub wrap: Wrap,
     /// Horizontal print position in dots (resets to 0 after Newline)
     pub absolute_position: u16,
 }
 
 impl StyleState {
-    /// Calculate the number of characters that fit on one line given current style.
+    /// Calculate the number of characters that fit on one line of
+    /// `print_width` dots given current style.
     ///
-    /// Font A = 48 base chars, Font B/C = 64 base chars.
+    /// Font A is 12 dots per character (48 on 576 dots), Font B/C 9 (64).
     /// Width multipliers reduce the count proportionally.
-    pub(crate) fn chars_per_line(&self) -> usize {
-        let base: usize = match self.font {
-            Font::A => 48,
-            Font::B | Font::C => 64,
-        };
+    pub(crate) fn chars_per_line(&self, print_width: usize) -> usize {
+        print_width / self.char_dots()
+    }
+
+    /// Width of one character in dots given current style.
+    pub(crate) fn char_dots(&self) -> usize {
         let width = (self.width_mult as usize + 1) * (self.expanded_width as usize + 1);
-        base / width
+        self.font.char_width() * width
+    }
+}
+
+impl StyleState {
+    /// Update the state for an op. Ops that don't touch style are ignored.
+    pub fn apply(&mut self, op: &Op) {
+        match op {
+            Op::Init | Op::ResetStyle => *self = Self::default(),
+            Op::Newline => self.absolute_position = 0,
+            Op::SetAbsolutePosition(pos) => self.absolute_position = *pos,
+            Op::SetAlign(a) => self.alignment = *a,
+            Op::SetFont(f) => self.font = *f,
+            Op::SetBold(b) => self.bold = *b,
+            Op::SetUnderline(u) => self.underline = *u,
+            Op::SetUpperline(u) => self.upperline = *u,
+            Op::SetInvert(i) => self.invert = *i,
+            Op::SetSmoothing(s) => self.smoothing = *s,
+            Op::SetUpsideDown(u) => self.upside_down = *u,
+            Op::SetReduced(r) => self.reduced = *r,
+            Op::SetExpandedWidth(w) => self.expanded_width = *w,
+            Op::SetExpandedHeight(h) => self.expanded_height = *h,
+            Op::SetSize { height, width } => {
+                self.height_mult = *height;
+                self.width_mult = *width;
+            }
+            Op::SetWrap(wrap) => self.wrap = *wrap,
+            _ => {}
+        }
+    }
+
+    /// Set ops that change this style into `target`, one per differing
+    /// field. The print position is not part of the style and is left alone.
+    pub fn changes_to(&self, target: &StyleState) -> Vec<Op> {
+        let mut ops = Vec::new();
+        if self.alignment != target.alignment {
+            ops.push(Op::SetAlign(target.alignment));
+        }
+        if self.font != target.font {
+            ops.push(Op::SetFont(target.font));
+        }
+        if self.bold != target.bold {
+            ops.push(Op::SetBold(target.bold));
+        }
+        if self.underline != target.underline {
+            ops.push(Op::SetUnderline(target.underline));
+        }
+        if self.upperline != target.upperline {
+            ops.push(Op::SetUpperline(target.upperline));
+        }
+        if self.invert != target.invert {
+            ops.push(Op::SetInvert(target.invert));
+        }
+        if self.smoothing != target.smoothing {
+            ops.push(Op::SetSmoothing(target.smoothing));
+        }
+        if self.upside_down != target.upside_down {
+            ops.push(Op::SetUpsideDown(target.upside_down));
+        }
+        if self.reduced != target.reduced {
+            ops.push(Op::SetReduced(target.reduced));
+        }
+        if self.expanded_width != target.expanded_width {
+            ops.push(Op::SetExpandedWidth(target.expanded_width));
+        }
+        if self.expanded_height != target.expanded_height {
+            ops.push(Op::SetExpandedHeight(target.expanded_height));
+        }
+        if self.height_mult != target.height_mult || self.width_mult != target.width_mult {
+            ops.push(Op::SetSize {
+                height: target.height_mult,
+                width: target.width_mult,
+            });
+        }
+        if self.wrap != target.wrap {
+            ops.push(Op::SetWrap(target.wrap));
+        }
+        ops
+    }
+}
+
+/// Resolves [`Op::PushStyle`]/[`Op::PopStyle`] into concrete set ops.
+///
+/// Feed every op of a program through [`StyleStack::resolve`] in order; it
+/// tracks the style and says what to emit in place of each push and pop.
+#[derive(Debug, Clone, Default)]
+pub struct StyleStack {
+    state: StyleState,
+    saved: Vec<StyleState>,
+}
+
+impl StyleStack {
+    /// Track `op`. Returns the ops to emit instead of it for pushes (none)
+    /// and pops (the set ops restoring the saved style), or `None` if `op`
+    /// should be emitted as is.
+    pub fn resolve(&mut self, op: &Op) -> Option<Vec<Op>> {
+        match op {
+            Op::PushStyle => {
+                self.saved.push(self.state.clone());
+                Some(Vec::new())
+            }
+            Op::PopStyle => {
+                let Some(saved) = self.saved.pop() else {
+                    return Some(Vec::new());
+                };
+                let changes = self.state.changes_to(&saved);
+                for change in &changes {
+                    self.state.apply(change);
+                }
+                Some(changes)
+            }
+            _ => {
+                self.state.apply(op);
+                None
+            }
+        }
     }
 }
 
@@ -96,6 +279,7 @@ impl Default for StyleState {
             expanded_height: 0,
             height_mult: 0,
             width_mult: 0,
+            wrap: Wrap::default(),
             absolute_position: 0,
         }
     }
@@ -107,7 +291,9 @@ impl Default for StyleState {
 /// - Inspected for debugging (`{:#?}`)
 /// - Optimized to remove redundant operations
 /// - Compiled to StarPRNT bytes
-#[derive(Debug, Clone, PartialEq)]
+/// - Serialized to JSON
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum Op {
     // ========== Printer Control ==========
     /// Initialize printer (ESC @). Resets to default state.
@@ -119,6 +305,9 @@ pub enum Op {
     /// Feed paper. Units are 1/4mm (4 units = 1mm).
     Feed { units: u8 },
 
+    /// Feed to the next black mark, i.e. the top of the next label (FF).
+    FeedToMark,
+
     // ========== Style Changes ==========
     /// Set text alignment.
     SetAlign(Alignment),
@@ -161,10 +350,26 @@ pub enum Op {
 
     /// Set code page (character set).
     SetCodepage(u8),
+    /// Set the line feed pitch (ESC z n).
+    SetLineSpacing(LineSpacing),
+    /// Set the print density, -3 (lightest) to 3 (darkest) (ESC RS d n).
+    SetDensity(i8),
+    /// Set the print speed (ESC RS r n).
+    SetSpeed(PrintSpeed),
+    /// Set how the optimizer wraps the text that follows. Emits no bytes.
+    SetWrap(Wrap),
 
     /// Reset all styles to default.
     ResetStyle,
 
+    /// Save the current style. The matching [`Op::PopStyle`] restores it,
+    /// so a component can change styles freely without resetting each one.
+    PushStyle,
+
+    /// Restore the style saved by the matching [`Op::PushStyle`]. A pop
+    /// without a push is ignored.
+    PopStyle,
+
     // ========== Content ==========
     /// Raw text (no trailing newline).
     Text(String),
@@ -173,19 +378,37 @@ pub enum Op {
     Newline,
 
     /// Raw bytes (for special characters or direct protocol access).
-    Raw(Vec<u8>),
+    Raw(#[serde(with = "hex_bytes")] Vec<u8>),
 
     // ========== Graphics ==========
     /// Raster graphics (ESC GS S). Arbitrary height.
     Raster {
         width: u16,
         height: u16,
+        #[serde(with = "hex_bytes")]
         data: Vec<u8>,
     },
 
     /// Band graphics (ESC k). Fixed 24-row height.
     /// Data length must be `width_bytes * 24`.
-    Band { width_bytes: u8, data: Vec<u8> },
+    Band {
+        width_bytes: u8,
+        #[serde(with = "hex_bytes")]
+        data: Vec<u8>,
+    },
+
+    /// Grayscale raster graphics, dithered to 1-bit at codegen/preview time.
+    ///
+    /// One 8-bit gray level per pixel (0 = white, 255 = black), row-major.
+    /// Keeping continuous-tone data in the IR lets consumers (Canvas
+    /// compositing, scaling, inversion) work on the original tones instead
+    /// of already-binarized dots.
+    GrayRaster {
+        width: u16,
+        height: u16,
+        data: Vec<u8>,
+        dither: DitheringAlgorithm,
+    },
 
     // ========== Barcodes ==========
     /// QR code.
@@ -207,6 +430,9 @@ pub enum Op {
         kind: BarcodeKind,
         data: String,
         height: u8,
+        /// Human-readable text. The printer only prints it below the bars,
+        /// so anything but `None` means below.
+        hri: HriPosition,
     },
 
     // ========== Position ==========
@@ -219,6 +445,7 @@ pub enum Op {
         key: String,
         width: u16,
         height: u16,
+        #[serde(with = "hex_bytes")]
         data: Vec<u8>,
     },
 
@@ -231,12 +458,65 @@ pub enum Op {
 
     /// Delete image from NV memory.
     NvDelete { key: String },
+
+    // ========== Debug ==========
+    /// Debug marker. Emits no bytes; shown by the preview and the disassembler.
+    ///
+    /// `Document` inserts these at component boundaries when `debug` is set,
+    /// so optimized IR can be traced back to the component that produced it.
+    Marker(String),
+}
+
+impl Op {
+    /// The op's name as it appears in JSON, e.g. `"gray_raster"`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Op::Init => "init",
+            Op::Cut { .. } => "cut",
+            Op::Feed { .. } => "feed",
+            Op::FeedToMark => "feed_to_mark",
+            Op::SetAlign(_) => "set_align",
+            Op::SetFont(_) => "set_font",
+            Op::SetBold(_) => "set_bold",
+            Op::SetUnderline(_) => "set_underline",
+            Op::SetInvert(_) => "set_invert",
+            Op::SetSize { .. } => "set_size",
+            Op::SetExpandedWidth(_) => "set_expanded_width",
+            Op::SetExpandedHeight(_) => "set_expanded_height",
+            Op::SetSmoothing(_) => "set_smoothing",
+            Op::SetUpperline(_) => "set_upperline",
+            Op::SetUpsideDown(_) => "set_upside_down",
+            Op::SetReduced(_) => "set_reduced",
+            Op::SetCodepage(_) => "set_codepage",
+            Op::SetLineSpacing(_) => "set_line_spacing",
+            Op::SetDensity(_) => "set_density",
+            Op::SetSpeed(_) => "set_speed",
+            Op::SetWrap(_) => "set_wrap",
+            Op::ResetStyle => "reset_style",
+            Op::PushStyle => "push_style",
+            Op::PopStyle => "pop_style",
+            Op::Text(_) => "text",
+            Op::Newline => "newline",
+            Op::Raw(_) => "raw",
+            Op::Raster { .. } => "raster",
+            Op::Band { .. } => "band",
+            Op::GrayRaster { .. } => "gray_raster",
+            Op::QrCode { .. } => "qr_code",
+            Op::Pdf417 { .. } => "pdf417",
+            Op::Barcode1D { .. } => "barcode1_d",
+            Op::SetAbsolutePosition(_) => "set_absolute_position",
+            Op::NvStore { .. } => "nv_store",
+            Op::NvPrint { .. } => "nv_print",
+            Op::NvDelete { .. } => "nv_delete",
+            Op::Marker(_) => "marker",
+        }
+    }
 }
 
 /// A compiled IR program.
 ///
 /// Contains a sequence of ops that can be optimized and compiled to bytes.
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct Program {
     pub ops: Vec<Op>,
 }
@@ -247,6 +527,27 @@ impl Program {
         Self { ops: Vec::new() }
     }
 
+    /// Serialize the program to JSON, e.g. to persist or queue a print job.
+    ///
+    /// ```
+    /// use estrella::ir::{Op, Program};
+    ///
+    /// let program = Program {
+    ///     ops: vec![Op::Init, Op::SetBold(true), Op::Text("Hi".into())],
+    /// };
+    /// let json = program.to_json();
+    /// assert_eq!(json, r#"{"ops":["init",{"set_bold":true},{"text":"Hi"}]}"#);
+    /// assert_eq!(Program::from_json(&json).unwrap(), program);
+    /// ```
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("IR programs always serialize")
+    }
+
+    /// Read a program written by [`Program::to_json`].
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+
     /// Render the program to PNG bytes for preview.
     ///
     /// This renders what the receipt would look like when printed,
@@ -265,10 +566,20 @@ impl Program {
     /// let png_bytes = program.to_preview_png().unwrap();
     /// // png_bytes can be written to a file or displayed
     /// ```
+    #[cfg(feature = "images")]
     pub fn to_preview_png(&self) -> Result<Vec<u8>, crate::preview::PreviewError> {
         crate::preview::render_preview(self)
     }
 
+    /// [`Program::to_preview_png`] on a printer's paper width.
+    #[cfg(feature = "images")]
+    pub fn to_preview_png_with_config(
+        &self,
+        config: &PrinterConfig,
+    ) -> Result<Vec<u8>, crate::preview::PreviewError> {
+        crate::preview::render_preview_with_config(self, config)
+    }
+
     /// Create a program with an initial Init op and CP437 code page.
     pub fn with_init() -> Self {
         Self {
@@ -328,6 +639,32 @@ impl<'a> IntoIterator for &'a Program {
     }
 }
 
+/// Serde helpers writing byte payloads as lowercase hex strings: half the
+/// size of a JSON number array and still readable.
+mod hex_bytes {
+    use serde::{Deserialize, Deserializer, Serializer, de::Error};
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+        serializer.serialize_str(&hex)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let hex = String::deserialize(deserializer)?;
+        if hex.len() % 2 != 0 {
+            return Err(D::Error::custom("hex data has an odd number of digits"));
+        }
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| {
+                hex.get(i..i + 2)
+                    .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+                    .ok_or_else(|| D::Error::custom(format!("invalid hex at {}", i)))
+            })
+            .collect()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -384,4 +721,65 @@ mod tests {
         assert!(debug.contains("QrCode"));
         assert!(debug.contains("example.com"));
     }
+    #[test]
+    fn test_json_round_trip() {
+        let program = Program {
+            ops: vec![
+                Op::Init,
+                Op::SetAlign(Alignment::Center),
+                Op::SetFont(Font::B),
+                Op::SetSize {
+                    height: 1,
+                    width: 1,
+                },
+                Op::Text("Café ☕".into()),
+                Op::Raw(vec![0x1B, 0x40]),
+                Op::Raster {
+                    width: 16,
+                    height: 1,
+                    data: vec![0xFF, 0x0A],
+                },
+                Op::GrayRaster {
+                    width: 2,
+                    height: 1,
+                    data: vec![64, 26],
+                    dither: DitheringAlgorithm::Atkinson,
+                },
+                Op::QrCode {
+                    data: "https://example.com".into(),
+                    cell_size: 4,
+                    error_level: QrErrorLevel::H,
+                },
+                Op::Barcode1D {
+                    kind: BarcodeKind::UpcA,
+                    data: "012345678905".into(),
+                    height: 80,
+                    hri: HriPosition::Below,
+                },
+                Op::Marker("[0] Text".into()),
+                Op::Cut { partial: true },
+            ],
+        };
+        let json = program.to_json();
+        assert!(json.contains(r#"{"raw":"1b40"}"#));
+        assert!(json.contains(r#""data":"ff0a""#));
+        assert_eq!(Program::from_json(&json).unwrap(), program);
+
+        // Op::name matches the JSON tag
+        for op in &program.ops {
+            let tag = match serde_json::to_value(op).unwrap() {
+                serde_json::Value::String(tag) => tag,
+                serde_json::Value::Object(map) => map.keys().next().unwrap().clone(),
+                other => panic!("unexpected op JSON {}", other),
+            };
+            assert_eq!(op.name(), tag);
+        }
+    }
+
+    #[test]
+    fn test_json_rejects_bad_hex() {
+        assert!(Program::from_json(r#"{"ops":[{"raw":"1b4"}]}"#).is_err());
+        assert!(Program::from_json(r#"{"ops":[{"raw":"zz"}]}"#).is_err());
+        assert!(Program::from_json(r#"{"ops":[{"teleport":1}]}"#).is_err());
+    }
 }
@@ -5,6 +5,7 @@
 //!
 //! ## Optimization Passes
 //!
+//! 0. **Expand style stack**: Replace PushStyle/PopStyle with concrete set ops
 //! 1. **Remove redundant init**: Only keep the first Init op
 //! 2. **Collapse style toggles**: Remove off/on pairs (e.g., SetBold(false), SetBold(true))
 //! 3. **Remove redundant styles**: Don't emit style changes that match current state
@@ -12,6 +13,14 @@
 //! 4. **Remove empty text**: Filter out Text("") ops
 //! 5. **Merge adjacent text**: Combine consecutive Text ops
 //! 6. **Remove trailing dead styles**: Remove unused style changes before Cut
+//! 7. **Merge adjacent rasters**: Join back-to-back raster graphics of the
+//!    same width, so the printer doesn't pause (and band) between them
+//!
+//! ## Levels
+//!
+//! [`OptimizeLevel`] selects which passes run: `none` skips all of them,
+//! `safe` runs passes 0, 1, 3, 4 and word-wrapping, and `aggressive` (the
+//! default) runs everything.
 //!
 //! ## Important: Newline-Style Ordering
 //!
@@ -27,23 +36,111 @@
 //! ADJACENT pairs, so style resets separated by other ops (Feed, Align, etc.)
 //! are preserved.
 
-use super::ops::{Op, Program, StyleState};
+use serde::{Deserialize, Serialize};
+
+use super::ops::{Op, Program, StyleStack, StyleState, Wrap, WrapMode};
+use crate::metrics::{self, Stage};
+use crate::printer::PrinterConfig;
+
+/// How much the optimizer is allowed to rewrite a program.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OptimizeLevel {
+    /// Preserve ops verbatim. Useful when tracking down a layout bug that
+    /// the optimizer might otherwise mask.
+    None,
+    /// Only drop ops that provably have no effect (duplicate Init, styles
+    /// that match the current state, empty text) and word-wrap long text.
+    Safe,
+    /// All passes, including toggle collapsing, text merging and trailing
+    /// dead-style removal.
+    #[default]
+    Aggressive,
+}
+
+impl std::str::FromStr for OptimizeLevel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "none" | "off" => Ok(Self::None),
+            "safe" => Ok(Self::Safe),
+            "aggressive" | "full" => Ok(Self::Aggressive),
+            _ => Err(format!(
+                "Unknown optimize level '{}'. Use 'none', 'safe', or 'aggressive'",
+                s
+            )),
+        }
+    }
+}
 
 impl Program {
-    /// Apply all optimization passes.
+    /// Apply all optimization passes ([`OptimizeLevel::Aggressive`]).
     pub fn optimize(self) -> Self {
+        self.optimize_with(OptimizeLevel::Aggressive)
+    }
+
+    /// Apply the optimization passes enabled at `level`, wrapping text for
+    /// the TSP650II.
+    pub fn optimize_with(self, level: OptimizeLevel) -> Self {
+        self.optimize_with_config(level, &PrinterConfig::TSP650II)
+    }
+
+    /// [`Program::optimize_with`] for a specific printer: text is wrapped at
+    /// the printer's print width.
+    pub fn optimize_with_config(self, level: OptimizeLevel, config: &PrinterConfig) -> Self {
+        let print_width = config.width_dots as usize;
         let ops = self.ops;
-        let ops = remove_redundant_init(ops);
-        let ops = collapse_style_toggles(ops);
-        let ops = remove_redundant_styles(ops);
-        let ops = remove_empty_text(ops);
-        let ops = merge_adjacent_text(ops);
-        let ops = wrap_long_text(ops);
-        let ops = remove_trailing_dead_styles(ops);
+        let wrap = |ops| wrap_long_text(ops, print_width);
+        let ops = match level {
+            OptimizeLevel::None => ops,
+            OptimizeLevel::Safe => {
+                let ops = pass("expand_style_stack", ops, expand_style_stack);
+                let ops = pass("remove_redundant_init", ops, remove_redundant_init);
+                let ops = pass("remove_redundant_styles", ops, remove_redundant_styles);
+                let ops = pass("remove_empty_text", ops, remove_empty_text);
+                pass("wrap_long_text", ops, wrap)
+            }
+            OptimizeLevel::Aggressive => {
+                let ops = pass("expand_style_stack", ops, expand_style_stack);
+                let ops = pass("remove_redundant_init", ops, remove_redundant_init);
+                let ops = pass("collapse_style_toggles", ops, collapse_style_toggles);
+                let ops = pass("remove_redundant_styles", ops, remove_redundant_styles);
+                let ops = pass("remove_empty_text", ops, remove_empty_text);
+                let ops = pass("merge_adjacent_text", ops, merge_adjacent_text);
+                let ops = pass("wrap_long_text", ops, wrap);
+                let ops = pass(
+                    "remove_trailing_dead_styles",
+                    ops,
+                    remove_trailing_dead_styles,
+                );
+                pass("merge_adjacent_rasters", ops, merge_adjacent_rasters)
+            }
+        };
         Program { ops }
     }
 }
 
+/// Run one optimizer pass, recorded in the optimize timings (see
+/// [`crate::metrics`]).
+fn pass(name: &'static str, ops: Vec<Op>, run: impl FnOnce(Vec<Op>) -> Vec<Op>) -> Vec<Op> {
+    metrics::timed(Stage::Optimize, name, || run(ops))
+}
+
+/// Replace `PushStyle`/`PopStyle` with the set ops they stand for, so the
+/// other passes only see concrete style changes.
+fn expand_style_stack(ops: Vec<Op>) -> Vec<Op> {
+    let mut styles = StyleStack::default();
+    let mut result = Vec::with_capacity(ops.len());
+    for op in ops {
+        match styles.resolve(&op) {
+            Some(replacement) => result.extend(replacement),
+            None => result.push(op),
+        }
+    }
+    result
+}
+
 /// Remove style off/on pairs (e.g., SetBold(false), SetBold(true) → remove both).
 /// This optimizes patterns where Text components auto-reset styles.
 fn collapse_style_toggles(ops: Vec<Op>) -> Vec<Op> {
@@ -55,9 +152,14 @@ fn collapse_style_toggles(ops: Vec<Op>) -> Vec<Op> {
     let mut i = 0;
 
     while i < ops.len() {
-        // Check for off/on toggle pairs
-        if i + 1 < ops.len() {
-            let collapse = match (&ops[i], &ops[i + 1]) {
+        // Check for off/on toggle pairs. Debug markers between the pair don't
+        // count as separating it, so `debug` builds optimize identically.
+        let mut next = i + 1;
+        while next < ops.len() && matches!(ops[next], Op::Marker(_)) {
+            next += 1;
+        }
+        if next < ops.len() {
+            let collapse = match (&ops[i], &ops[next]) {
                 (Op::SetBold(false), Op::SetBold(true)) => true,
                 (Op::SetUnderline(false), Op::SetUnderline(true)) => true,
                 (Op::SetUpperline(false), Op::SetUpperline(true)) => true,
@@ -79,15 +181,17 @@ fn collapse_style_toggles(ops: Vec<Op>) -> Vec<Op> {
                     },
                 ) if *h > 0 || *w > 0 => {
                     // Keep the second SetSize, skip the reset
-                    result.push(ops[i + 1].clone());
-                    i += 2;
+                    result.extend_from_slice(&ops[i + 1..=next]);
+                    i = next + 1;
                     continue;
                 }
                 _ => false,
             };
 
             if collapse {
-                i += 2; // Skip both ops
+                // Skip both ops, keeping any markers between them
+                result.extend_from_slice(&ops[i + 1..next]);
+                i = next + 1;
                 continue;
             }
         }
@@ -213,6 +317,12 @@ fn remove_redundant_styles(ops: Vec<Op>) -> Vec<Op> {
                     result.push(op);
                 }
             }
+            Op::SetWrap(wrap) => {
+                if *wrap != state.wrap {
+                    state.wrap = *wrap;
+                    result.push(op);
+                }
+            }
 
             // Non-style ops pass through unchanged
             _ => result.push(op),
@@ -236,6 +346,8 @@ fn merge_adjacent_text(ops: Vec<Op>) -> Vec<Op> {
                     pending_text = Some(s);
                 }
             }
+            // Markers don't split text runs; they land before the merged text
+            marker @ Op::Marker(_) => result.push(marker),
             other => {
                 // Flush any pending text
                 if let Some(text) = pending_text.take() {
@@ -254,6 +366,67 @@ fn merge_adjacent_text(ops: Vec<Op>) -> Vec<Op> {
     result
 }
 
+/// Merge consecutive `Raster` ops of the same width into one.
+///
+/// Each raster op becomes its own run of commands, and the printer stops
+/// feeding between them, which leaves a visible band where a TTF banner
+/// meets an image. Grayscale rasters are left alone, so they are dithered
+/// only at codegen and previews still show them in gray.
+/// Codegen splits the result again at `max_chunk_rows` only.
+fn merge_adjacent_rasters(ops: Vec<Op>) -> Vec<Op> {
+    let mut result = Vec::with_capacity(ops.len());
+    // Index in `result` of the raster the current run merges into
+    let mut run: Option<usize> = None;
+    // Markers seen since the last raster, placed once we know whether the
+    // next one merges
+    let mut markers = Vec::new();
+
+    for op in ops {
+        match op {
+            Op::Raster {
+                width,
+                height,
+                data,
+            } => {
+                if let Some(i) = run
+                    && let Op::Raster {
+                        width: w,
+                        height: total,
+                        data: merged,
+                    } = &mut result[i]
+                    && *w == width
+                    && let Some(sum) = total.checked_add(height)
+                {
+                    *total = sum;
+                    merged.extend(data);
+                    // Markers don't split raster runs; they land before the
+                    // merged raster
+                    let count = markers.len();
+                    result.splice(i..i, markers.drain(..));
+                    run = Some(i + count);
+                    continue;
+                }
+                result.append(&mut markers);
+                run = Some(result.len());
+                result.push(Op::Raster {
+                    width,
+                    height,
+                    data,
+                });
+            }
+            Op::Marker(_) if run.is_some() => markers.push(op),
+            other => {
+                run = None;
+                result.append(&mut markers);
+                result.push(other);
+            }
+        }
+    }
+    result.append(&mut markers);
+
+    result
+}
+
 /// Remove empty Text("") ops which serve no purpose.
 fn remove_empty_text(ops: Vec<Op>) -> Vec<Op> {
     ops.into_iter()
@@ -294,12 +467,16 @@ fn remove_trailing_dead_styles(ops: Vec<Op>) -> Vec<Op> {
             | Op::SetAlign(_)
             | Op::SetFont(_)
             | Op::SetCodepage(_)
+            | Op::SetLineSpacing(_)
+            | Op::SetDensity(_)
+            | Op::SetSpeed(_)
+            | Op::SetWrap(_)
             | Op::SetAbsolutePosition(_)
             | Op::ResetStyle => {
                 dead_indices.push(i);
             }
-            // Feed and Newline don't use styles, keep scanning
-            Op::Feed { .. } | Op::Newline => continue,
+            // Feeds, Newline and markers don't use styles, keep scanning
+            Op::Feed { .. } | Op::FeedToMark | Op::Newline | Op::Marker(_) => continue,
             // Any content-producing op means earlier styles might be used
             _ => break,
         }
@@ -328,92 +505,151 @@ fn char_split_at(s: &str, n: usize) -> (&str, &str) {
 ///
 /// Handles existing `\n` by splitting on them first. Words longer than
 /// `max_chars` are force-broken at the character limit.
-fn word_wrap(text: &str, max_chars: usize) -> Vec<String> {
+pub(crate) fn word_wrap(text: &str, max_chars: usize) -> Vec<String> {
+    wrap_text(text, max_chars, max_chars, Wrap::default())
+}
+
+/// Split text into lines that fit within `max_chars` the way `wrap` says,
+/// starting on a line with room for `first_chars` more characters.
+///
+/// Each `\n`-separated paragraph is wrapped on its own; its continuation
+/// lines start with `wrap.hanging_indent` spaces (at most `max_chars - 1`).
+fn wrap_text(text: &str, first_chars: usize, max_chars: usize, wrap: Wrap) -> Vec<String> {
+    let indent = (wrap.hanging_indent as usize).min(max_chars.saturating_sub(1));
     let mut lines = Vec::new();
+    let mut room = first_chars.min(max_chars);
 
     for paragraph in text.split('\n') {
-        if paragraph.is_empty() {
-            lines.push(String::new());
-            continue;
+        let first = lines.len();
+        match wrap.mode {
+            WrapMode::None => lines.push(char_split_at(paragraph, room).0.to_string()),
+            WrapMode::Char => char_wrap(paragraph, room, max_chars - indent, &mut lines),
+            WrapMode::Word => word_wrap_paragraph(paragraph, room, max_chars, indent, &mut lines),
         }
+        for line in &mut lines[first + 1..] {
+            line.insert_str(0, &" ".repeat(indent));
+        }
+        room = max_chars;
+    }
+
+    lines
+}
 
-        let mut line = String::new();
-        let mut line_chars: usize = 0;
+/// Break a paragraph after `first_width` characters, then every
+/// `rest_width`.
+fn char_wrap(paragraph: &str, first_width: usize, rest_width: usize, lines: &mut Vec<String>) {
+    let mut width = first_width;
+    let mut remaining = paragraph;
+    while char_len(remaining) > width {
+        let (chunk, rest) = char_split_at(remaining, width);
+        lines.push(chunk.to_string());
+        remaining = rest;
+        width = rest_width;
+    }
+    lines.push(remaining.to_string());
+}
 
-        for word in paragraph.split(' ') {
-            let word_chars = char_len(word);
+/// Break a paragraph at spaces into lines of `max_chars` characters
+/// (`first_width` on the first line, `max_chars - indent` after it).
+///
+/// When the first line is already partly used (`first_width < max_chars`),
+/// its leading spaces are kept, and a word that doesn't fit in the rest of
+/// it starts the next line instead of being split.
+fn word_wrap_paragraph(
+    paragraph: &str,
+    first_width: usize,
+    max_chars: usize,
+    indent: usize,
+    lines: &mut Vec<String>,
+) {
+    if paragraph.is_empty() {
+        lines.push(String::new());
+        return;
+    }
 
-            if word.is_empty() {
-                // Consecutive spaces: add a space to current line
-                if line_chars > 0 && line_chars < max_chars {
-                    line.push(' ');
-                    line_chars += 1;
-                }
-                continue;
+    let start = lines.len();
+    let partial = first_width < max_chars;
+    let rest_width = max_chars - indent;
+    let mut width = first_width;
+    let mut line = String::new();
+    let mut line_chars: usize = 0;
+
+    for (i, word) in paragraph.split(' ').enumerate() {
+        let word_chars = char_len(word);
+        // A space separates each word from the one before, except at the
+        // start of a line
+        let continues_line = line_chars > 0 || (partial && lines.len() == start);
+        let gap = usize::from(i > 0 && continues_line);
+
+        if word.is_empty() {
+            // Consecutive spaces: add a space to current line
+            if gap > 0 && line_chars < width {
+                line.push(' ');
+                line_chars += 1;
             }
+            continue;
+        }
 
-            if line_chars == 0 {
-                // First word on line — force-break if too long
-                if word_chars <= max_chars {
-                    line.push_str(word);
-                    line_chars = word_chars;
-                } else {
-                    let mut remaining = word;
-                    while char_len(remaining) > max_chars {
-                        let (chunk, rest) = char_split_at(remaining, max_chars);
-                        lines.push(chunk.to_string());
-                        remaining = rest;
-                    }
-                    if !remaining.is_empty() {
-                        line.push_str(remaining);
-                        line_chars = char_len(remaining);
-                    }
-                }
-            } else if line_chars + 1 + word_chars <= max_chars {
-                // Word fits with a space
+        if line_chars + gap + word_chars <= width {
+            // Word fits on this line
+            if gap > 0 {
                 line.push(' ');
-                line.push_str(word);
-                line_chars += 1 + word_chars;
-            } else {
-                // Word doesn't fit — start new line
-                lines.push(std::mem::take(&mut line));
-                line_chars = 0;
-                if word_chars <= max_chars {
-                    line.push_str(word);
-                    line_chars = word_chars;
-                } else {
-                    let mut remaining = word;
-                    while char_len(remaining) > max_chars {
-                        let (chunk, rest) = char_split_at(remaining, max_chars);
-                        lines.push(chunk.to_string());
-                        remaining = rest;
-                    }
-                    if !remaining.is_empty() {
-                        line.push_str(remaining);
-                        line_chars = char_len(remaining);
-                    }
-                }
             }
+            line.push_str(word);
+            line_chars += gap + word_chars;
+            continue;
         }
-        lines.push(line);
-    }
 
-    lines
+        if continues_line {
+            // Word doesn't fit — start new line
+            lines.push(std::mem::take(&mut line));
+            line_chars = 0;
+            width = rest_width;
+        }
+
+        // First word on line — force-break if too long
+        let mut remaining = word;
+        while char_len(remaining) > width {
+            let (chunk, rest) = char_split_at(remaining, width);
+            lines.push(chunk.to_string());
+            remaining = rest;
+            width = rest_width;
+        }
+        if !remaining.is_empty() {
+            line.push_str(remaining);
+            line_chars = char_len(remaining);
+        }
+    }
+    lines.push(line);
 }
 
-/// Wrap long `Op::Text` ops at word boundaries, inserting `Op::Newline` between lines.
+/// Wrap long `Op::Text` ops as the current `Op::SetWrap` says (at word
+/// boundaries by default), inserting `Op::Newline` between lines.
 ///
-/// Tracks `StyleState` to calculate the correct `chars_per_line` for each text op.
-fn wrap_long_text(ops: Vec<Op>) -> Vec<Op> {
+/// Tracks `StyleState` to calculate the correct `chars_per_line` for each
+/// text op on a line of `print_width` dots.
+fn wrap_long_text(ops: Vec<Op>, print_width: usize) -> Vec<Op> {
     let mut result = Vec::with_capacity(ops.len());
     let mut state = StyleState::default();
+    // Dots of the current line already used by text, so text split into
+    // several ops (e.g. to change style mid-line) wraps as one line
+    let mut column = 0usize;
 
     for op in ops {
         match &op {
-            Op::Init | Op::ResetStyle => {
+            Op::Init => {
+                state = StyleState::default();
+                column = 0;
+                result.push(op);
+            }
+            Op::ResetStyle => {
                 state = StyleState::default();
                 result.push(op);
             }
+            Op::SetAbsolutePosition(pos) => {
+                column = *pos as usize;
+                result.push(op);
+            }
             Op::SetFont(f) => {
                 state.font = *f;
                 result.push(op);
@@ -427,16 +663,27 @@ fn wrap_long_text(ops: Vec<Op>) -> Vec<Op> {
                 state.expanded_width = *w;
                 result.push(op);
             }
+            Op::SetWrap(wrap) => {
+                state.wrap = *wrap;
+                result.push(op);
+            }
             Op::Text(text) => {
-                let max = state.chars_per_line();
+                let max = state.chars_per_line(print_width);
+                let room = print_width.saturating_sub(column) / state.char_dots();
                 // Only wrap if text could overflow (contains long content or \n)
-                if char_len(text) <= max && !text.contains('\n') {
+                if char_len(text) <= room && !text.contains('\n') {
+                    column += char_len(text) * state.char_dots();
                     result.push(op);
                 } else {
-                    let lines = word_wrap(text, max);
+                    let lines = wrap_text(text, room, max, state.wrap);
+                    let last = lines.len() - 1;
                     for (i, line) in lines.into_iter().enumerate() {
                         if i > 0 {
                             result.push(Op::Newline);
+                            column = 0;
+                        }
+                        if i == last {
+                            column += char_len(&line) * state.char_dots();
                         }
                         if !line.is_empty() {
                             result.push(Op::Text(line));
@@ -444,6 +691,21 @@ fn wrap_long_text(ops: Vec<Op>) -> Vec<Op> {
                     }
                 }
             }
+            // Anything printed below the text starts a new line
+            Op::Newline
+            | Op::Feed { .. }
+            | Op::FeedToMark
+            | Op::Cut { .. }
+            | Op::Raster { .. }
+            | Op::Band { .. }
+            | Op::GrayRaster { .. }
+            | Op::QrCode { .. }
+            | Op::Pdf417 { .. }
+            | Op::Barcode1D { .. }
+            | Op::NvPrint { .. } => {
+                column = 0;
+                result.push(op);
+            }
             _ => result.push(op),
         }
     }
@@ -454,7 +716,8 @@ fn wrap_long_text(ops: Vec<Op>) -> Vec<Op> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::protocol::text::Alignment;
+    use crate::protocol::text::{Alignment, Font};
+    use crate::render::dither;
 
     #[test]
     fn test_remove_redundant_init() {
@@ -823,7 +1086,7 @@ mod tests {
             Op::Text("The quick brown fox jumps over the lazy dog and keeps on running through the forest until it reaches the end of the line".into()),
             Op::Newline,
         ];
-        let result = wrap_long_text(ops);
+        let result = wrap_long_text(ops, 576);
         // Should be split into multiple Text + Newline ops
         let text_count = result.iter().filter(|op| matches!(op, Op::Text(_))).count();
         assert!(
@@ -844,6 +1107,92 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_wrap_modes() {
+        let wrap = |mode, hanging_indent| Wrap {
+            mode,
+            hanging_indent,
+        };
+        let text = "Espresso doppio extra hot\nTea";
+        assert_eq!(
+            wrap_text(text, 10, 10, wrap(WrapMode::Word, 2)),
+            vec!["Espresso", "  doppio", "  extra", "  hot", "Tea"]
+        );
+        assert_eq!(
+            wrap_text(text, 10, 10, wrap(WrapMode::Char, 0)),
+            vec!["Espresso d", "oppio extr", "a hot", "Tea"]
+        );
+        assert_eq!(
+            wrap_text(text, 10, 10, wrap(WrapMode::None, 4)),
+            vec!["Espresso d", "Tea"]
+        );
+        // Indents never leave a line without room
+        assert_eq!(
+            wrap_text("abcd", 2, 2, wrap(WrapMode::Char, 9)),
+            vec!["ab", " c", " d"]
+        );
+    }
+
+    #[test]
+    fn test_wrap_continues_partly_used_line() {
+        let no_wrap = Wrap::default();
+        assert_eq!(
+            wrap_text(" very important", 8, 20, no_wrap),
+            vec![" very", "important"]
+        );
+        assert_eq!(
+            wrap_text("important", 3, 20, no_wrap),
+            vec!["", "important"]
+        );
+
+        // Font A: 48 characters per line, split over three ops
+        let ops = vec![
+            Op::Text(format!("{} ", "a".repeat(39))),
+            Op::SetBold(true),
+            Op::Text("bold".into()),
+            Op::SetBold(false),
+            Op::Text(" and more".into()),
+            Op::Newline,
+        ];
+        let result = wrap_long_text(ops, 576);
+        assert_eq!(
+            &result[4..],
+            &[
+                Op::Text(" and".into()),
+                Op::Newline,
+                Op::Text("more".into()),
+                Op::Newline,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_wrap_long_text_follows_set_wrap() {
+        // Font B on 576 dots is 64 chars per line, double width 32
+        let long = "x".repeat(40);
+        let ops = vec![
+            Op::Init,
+            Op::SetFont(Font::B),
+            Op::SetExpandedWidth(1),
+            Op::SetWrap(Wrap {
+                mode: WrapMode::None,
+                hanging_indent: 0,
+            }),
+            Op::Text(long.clone()),
+            Op::SetWrap(Wrap::default()),
+            Op::SetExpandedWidth(0),
+            Op::Text(long.clone()),
+        ];
+        let texts: Vec<_> = wrap_long_text(ops, 576)
+            .into_iter()
+            .filter_map(|op| match op {
+                Op::Text(text) => Some(text.len()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(texts, vec![32, 40]);
+    }
+
     #[test]
     fn test_wrap_long_text_with_size() {
         // Size 2 = width_mult 1, so 48 / 2 = 24 chars per line
@@ -856,7 +1205,7 @@ mod tests {
             Op::Text("Continuous Low of 3 feels like -1".into()),
             Op::Newline,
         ];
-        let result = wrap_long_text(ops);
+        let result = wrap_long_text(ops, 576);
         let text_count = result.iter().filter(|op| matches!(op, Op::Text(_))).count();
         assert!(
             text_count > 1,
@@ -878,7 +1227,7 @@ mod tests {
     #[test]
     fn test_wrap_long_text_with_newline_in_text() {
         let ops = vec![Op::Init, Op::Text("Line one\nLine two".into()), Op::Newline];
-        let result = wrap_long_text(ops);
+        let result = wrap_long_text(ops, 576);
         // Should produce: Init, Text("Line one"), Newline, Text("Line two"), Newline
         assert_eq!(result.len(), 5);
         assert_eq!(result[0], Op::Init);
@@ -891,7 +1240,176 @@ mod tests {
     #[test]
     fn test_wrap_short_text_unchanged() {
         let ops = vec![Op::Init, Op::Text("Short text".into()), Op::Newline];
-        let result = wrap_long_text(ops.clone());
+        let result = wrap_long_text(ops.clone(), 576);
         assert_eq!(result, ops);
     }
+
+    #[test]
+    fn test_markers_transparent_to_toggle_collapse() {
+        let ops = vec![
+            Op::Init,
+            Op::SetBold(true),
+            Op::Text("A".into()),
+            Op::Newline,
+            Op::SetBold(false),
+            Op::Marker("next".into()),
+            Op::SetBold(true),
+            Op::Text("B".into()),
+            Op::Newline,
+        ];
+        let result = collapse_style_toggles(ops);
+        assert_eq!(
+            result
+                .iter()
+                .filter(|op| matches!(op, Op::SetBold(_)))
+                .count(),
+            1
+        );
+        assert!(result.contains(&Op::Marker("next".into())));
+    }
+
+    #[test]
+    fn test_optimize_level_none_preserves_ops() {
+        let ops = vec![
+            Op::Init,
+            Op::Init,
+            Op::SetBold(false),
+            Op::Text("".into()),
+            Op::Cut { partial: true },
+        ];
+        let program = Program { ops: ops.clone() }.optimize_with(OptimizeLevel::None);
+        assert_eq!(program.ops, ops);
+    }
+
+    #[test]
+    fn test_optimize_level_safe_keeps_toggles() {
+        let ops = vec![
+            Op::Init,
+            Op::SetBold(true),
+            Op::SetBold(false),
+            Op::SetBold(true),
+            Op::Text("A".into()),
+            Op::Text("B".into()),
+        ];
+        let safe = Program { ops: ops.clone() }.optimize_with(OptimizeLevel::Safe);
+        // Nothing is redundant against the running state, and text is not merged
+        assert_eq!(safe.ops, ops);
+
+        let aggressive = Program { ops }.optimize();
+        assert_eq!(
+            aggressive.ops,
+            vec![Op::Init, Op::SetBold(true), Op::Text("AB".into())]
+        );
+    }
+
+    #[test]
+    fn test_merge_adjacent_rasters() {
+        let gray = Op::GrayRaster {
+            width: 16,
+            height: 2,
+            data: vec![255; 32],
+            dither: dither::DitheringAlgorithm::FloydSteinberg,
+        };
+        let ops = vec![
+            Op::Raster {
+                width: 16,
+                height: 1,
+                data: vec![0xAA, 0x55],
+            },
+            Op::Marker("[1] Image".into()),
+            gray.clone(),
+            Op::Raster {
+                width: 8,
+                height: 1,
+                data: vec![0x0F],
+            },
+            Op::Newline,
+            Op::Raster {
+                width: 8,
+                height: 1,
+                data: vec![0xF0],
+            },
+        ];
+        assert_eq!(
+            merge_adjacent_rasters(ops),
+            vec![
+                Op::Raster {
+                    width: 16,
+                    height: 1,
+                    data: vec![0xAA, 0x55],
+                },
+                Op::Marker("[1] Image".into()),
+                // Grayscale stays as it is, to be dithered at codegen
+                gray.clone(),
+                Op::Raster {
+                    width: 8,
+                    height: 1,
+                    data: vec![0x0F],
+                },
+                Op::Newline,
+                Op::Raster {
+                    width: 8,
+                    height: 1,
+                    data: vec![0xF0],
+                },
+            ]
+        );
+
+        // Markers inside a run land before the merged raster
+        let ops = vec![
+            Op::Marker("[0] Banner".into()),
+            Op::Raster {
+                width: 8,
+                height: 1,
+                data: vec![0xAA],
+            },
+            Op::Marker("[1] Image".into()),
+            Op::Raster {
+                width: 8,
+                height: 1,
+                data: vec![0x55],
+            },
+        ];
+        assert_eq!(
+            merge_adjacent_rasters(ops),
+            vec![
+                Op::Marker("[0] Banner".into()),
+                Op::Marker("[1] Image".into()),
+                Op::Raster {
+                    width: 8,
+                    height: 2,
+                    data: vec![0xAA, 0x55],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_merge_adjacent_rasters_same_bytes() {
+        let raster = |height: u16| Op::Raster {
+            width: 576,
+            height,
+            data: vec![0x81; 72 * height as usize],
+        };
+        let ops = vec![Op::Init, raster(200), raster(200), raster(100)];
+        let merged = Program { ops: ops.clone() }.optimize();
+        assert_eq!(merged.ops, vec![Op::Init, raster(500)]);
+
+        // Split at the chunk limit instead of at each original raster:
+        // one ESC GS S header per 256 rows
+        let headers = |bytes: Vec<u8>| bytes.windows(3).filter(|w| w == b"\x1b\x1dS").count();
+        assert_eq!(headers(Program { ops }.to_bytes()), 3);
+        assert_eq!(headers(merged.to_bytes()), 2);
+    }
+
+    #[test]
+    fn test_optimize_level_parse() {
+        assert_eq!("none".parse::<OptimizeLevel>(), Ok(OptimizeLevel::None));
+        assert_eq!("SAFE".parse::<OptimizeLevel>(), Ok(OptimizeLevel::Safe));
+        assert_eq!(
+            "aggressive".parse::<OptimizeLevel>(),
+            Ok(OptimizeLevel::Aggressive)
+        );
+        assert!("fast".parse::<OptimizeLevel>().is_err());
+    }
 }
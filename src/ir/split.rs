@@ -86,6 +86,13 @@ impl Program {
     ///
     /// For band mode, the chunk size will be rounded down to a multiple of 24.
     pub fn split_for_long_print_with_chunk_size(self, chunk_rows: usize) -> Vec<Program> {
+        // Splitting works on packed rows, so grayscale graphics are dithered first
+        let program = self.dither_graphics();
+        program.split_packed(chunk_rows)
+    }
+
+    /// Split a program whose graphics are already packed 1-bit data.
+    fn split_packed(self, chunk_rows: usize) -> Vec<Program> {
         // Find graphics operations and trailing operations
         let (graphics_idx, graphics_op) = match self.find_splittable_graphics(chunk_rows) {
             Some(result) => result,
@@ -232,7 +239,7 @@ impl Program {
     fn find_graphics_width_bytes(&self) -> Option<usize> {
         for op in &self.ops {
             match op {
-                Op::Raster { width, .. } => {
+                Op::Raster { width, .. } | Op::GrayRaster { width, .. } => {
                     return Some(width.div_ceil(8) as usize);
                 }
                 Op::Band { width_bytes, .. } => {
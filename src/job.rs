@@ -0,0 +1,160 @@
+//! # Print Job IDs
+//!
+//! Every print job gets a [`JobId`] so a physical receipt can be traced
+//! back to its job record. IDs are [ULIDs](https://github.com/ulid/spec):
+//! 26 characters of Crockford base32 that start with the creation time in
+//! milliseconds, so they sort chronologically and stay readable when
+//! printed in small type.
+//!
+//! ```
+//! use estrella::job::JobId;
+//!
+//! let id = JobId::from_parts(1_700_000_000_000, 42);
+//! assert_eq!(id.to_string(), "01HF7YAT00000000000000001A");
+//! assert_eq!(id.to_string().parse::<JobId>(), Ok(id));
+//! assert_eq!(id.timestamp_ms(), 1_700_000_000_000);
+//! ```
+
+use std::fmt;
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use rand::RngExt;
+use serde::{Deserialize, Serialize};
+
+/// Crockford base32 alphabet (no I, L, O, U).
+const ALPHABET: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+
+/// Length of the text form.
+const ENCODED_LEN: usize = 26;
+
+/// Bits of randomness after the 48-bit timestamp.
+const RANDOM_BITS: u32 = 80;
+
+/// A print job identifier (ULID).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct JobId(u128);
+
+impl JobId {
+    /// A new ID for the current time.
+    pub fn new() -> Self {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+        Self::from_parts(now, rand::rng().random())
+    }
+
+    /// Build an ID from a Unix timestamp in milliseconds (48 bits used)
+    /// and random bits (80 bits used).
+    pub fn from_parts(timestamp_ms: u64, random: u128) -> Self {
+        let timestamp = (timestamp_ms as u128) & ((1 << 48) - 1);
+        Self((timestamp << RANDOM_BITS) | (random & ((1 << RANDOM_BITS) - 1)))
+    }
+
+    /// Creation time as a Unix timestamp in milliseconds.
+    pub fn timestamp_ms(&self) -> u64 {
+        (self.0 >> RANDOM_BITS) as u64
+    }
+}
+
+impl Default for JobId {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Display for JobId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut out = [0u8; ENCODED_LEN];
+        for (i, c) in out.iter_mut().enumerate() {
+            let shift = 5 * (ENCODED_LEN - 1 - i);
+            *c = ALPHABET[((self.0 >> shift) & 0x1f) as usize];
+        }
+        // The alphabet is ASCII
+        f.write_str(std::str::from_utf8(&out).unwrap())
+    }
+}
+
+impl FromStr for JobId {
+    type Err = String;
+
+    /// Parse the 26-character text form. Case-insensitive; `I`/`L` read
+    /// as 1 and `O` as 0, as Crockford base32 specifies.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.len() != ENCODED_LEN {
+            return Err(format!(
+                "Invalid job ID '{}': expected {} characters",
+                s, ENCODED_LEN
+            ));
+        }
+        let mut value: u128 = 0;
+        for (i, c) in s.chars().enumerate() {
+            let c = match c.to_ascii_uppercase() {
+                'I' | 'L' => '1',
+                'O' => '0',
+                c => c,
+            };
+            let digit = ALPHABET
+                .iter()
+                .position(|&a| a as char == c)
+                .ok_or_else(|| format!("Invalid job ID '{}': bad character '{}'", s, c))?;
+            // 26 × 5 = 130 bits: the first character only carries 3
+            if i == 0 && digit > 7 {
+                return Err(format!("Invalid job ID '{}': out of range", s));
+            }
+            value = (value << 5) | digit as u128;
+        }
+        Ok(Self(value))
+    }
+}
+
+impl Serialize for JobId {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for JobId {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip_and_order() {
+        let earlier = JobId::from_parts(1_000, u128::MAX);
+        let later = JobId::from_parts(1_001, 0);
+        assert!(earlier < later);
+        assert!(earlier.to_string() < later.to_string());
+
+        for id in [earlier, later, JobId::new()] {
+            let text = id.to_string();
+            assert_eq!(text.len(), ENCODED_LEN);
+            assert_eq!(text.parse::<JobId>().unwrap(), id);
+            assert_eq!(text.to_lowercase().parse::<JobId>().unwrap(), id);
+        }
+    }
+
+    #[test]
+    fn test_rejects_invalid() {
+        assert!("".parse::<JobId>().is_err());
+        assert!("01HF7YAT0000000000000000A".parse::<JobId>().is_err());
+        assert!("01HF7YAT0000000000000000AU".parse::<JobId>().is_err());
+        assert!("81HF7YAT0000000000000000AA".parse::<JobId>().is_err());
+    }
+
+    #[test]
+    fn test_serde_as_string() {
+        let id = JobId::from_parts(1_700_000_000_000, 7);
+        let json = serde_json::to_string(&id).unwrap();
+        assert_eq!(json, format!("\"{}\"", id));
+        assert_eq!(serde_json::from_str::<JobId>(&json).unwrap(), id);
+    }
+}
@@ -6,15 +6,16 @@
 //! - **Protocol implementation**: StarPRNT command builders
 //! - **Visual patterns**: Ripple, waves, and calibration patterns
 //! - **Dithering**: Bayer 8x8 ordered dithering for grayscale conversion
-//! - **Transport**: Bluetooth RFCOMM communication
+//! - **Transport**: Bluetooth RFCOMM and TCP backends behind one [`Transport`] trait
 //!
 //! ## Quick Start
 //!
 //! ```no_run
+//! # #[cfg(feature = "patterns")] {
 //! use estrella::{
 //!     protocol::{commands, graphics},
 //!     render::patterns::{self, Ripple},
-//!     transport::BluetoothTransport,
+//!     transport::{BluetoothTransport, Transport},
 //!     printer::PrinterConfig,
 //! };
 //!
@@ -45,6 +46,7 @@
 //!
 //! // Send to printer
 //! transport.write_all(&data)?;
+//! # }
 //!
 //! # Ok::<(), estrella::error::EstrellaError>(())
 //! ```
@@ -57,6 +59,7 @@
 //! | [`render`] | Dithering and pattern generation |
 //! | [`transport`] | Communication backends |
 //! | [`printer`] | Printer configurations |
+//! | [`units`] | Millimeters, dots and feed units |
 //! | [`error`] | Error types |
 //!
 //! ## Supported Printers
@@ -67,21 +70,34 @@
 //! Other Star printers using StarPRNT protocol should work with
 //! appropriate configuration adjustments.
 
+#[cfg(feature = "patterns")]
 pub mod art;
 pub mod document;
 pub mod error;
 pub mod ir;
+pub mod job;
 pub mod logos;
+pub mod metrics;
+pub mod output;
 pub mod preview;
 pub mod printer;
 pub mod protocol;
 pub mod receipt;
 pub mod render;
+#[cfg(feature = "server")]
 pub mod server;
 pub mod shader;
+pub mod snapshot;
 pub mod transport;
+#[cfg(feature = "tui")]
+pub mod tui;
+pub mod units;
 
 // Re-exports for convenience
 pub use error::EstrellaError;
 pub use printer::PrinterConfig;
-pub use transport::BluetoothTransport;
+pub use transport::{BluetoothTransport, Transport};
+
+#[cfg(feature = "snapshot")]
+#[doc(hidden)]
+pub use insta as __insta;
@@ -21,9 +21,11 @@
 //! }
 //! ```
 
+#[cfg(feature = "patterns")]
 pub mod ripple;
 pub mod star;
 
+#[cfg(feature = "patterns")]
 pub use ripple::RippleLogo;
 pub use star::Star;
 
@@ -66,6 +68,7 @@ impl Logo {
 
 /// All registered logos.
 static LOGOS: &[Logo] = &[
+    #[cfg(feature = "patterns")]
     Logo::new("A0", "ripple", RippleLogo::raster),
     Logo::new("A1", "star", Star::raster),
 ];
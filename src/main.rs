@@ -14,36 +14,70 @@
 //! # Print with custom height
 //! estrella print --height 1000 waves
 //!
+//! # Print an animation strip: 8 frames of a pattern in motion
+//! estrella print ripple --frames 8 --frame-gap 5mm
+//!
 //! # Save pattern as PNG (patterns only)
 //! estrella print --png output.png ripple
 //!
+//! # Preview in the terminal (sixel or half blocks), e.g. over SSH
+//! estrella print --preview-term receipt
+//!
 //! # Print a demo receipt
 //! estrella print receipt
 //!
 //! # Print full receipt with barcodes
 //! estrella print receipt-full
 //!
+//! # Print a test page exercising every feature
+//! estrella print demo-all
+//!
+//! # Print a poster: pattern band, big title, details and a QR code
+//! estrella poster --pattern riley --title "PARTY" --subtitle "Sat 9PM" --qr https://example.com
+//!
+//! # Print a pattern with its recipe as a QR code, then print it again from a scan
+//! estrella print mycelium --params-qr
+//! estrella reprint "estrella:mycelium?seed=1234567&..."
+//!
+//! # Show the IR ops and StarPRNT bytes of a receipt without printing
+//! estrella dump receipt
+//!
 //! # Store a logo in printer's NV memory
 //! estrella logo store --key A0 logo.png
 //!
 //! # Delete a stored logo
 //! estrella logo delete --key A0
+//!
+//! # Save a document as a template on a running server, then print it
+//! estrella template save order order.json
+//! estrella template print order --var name=Ada --var table=4
 //! ```
 
 use clap::{Parser, Subcommand};
 use std::io::{self, Write};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use estrella::{
-    EstrellaError, document, logos, preview,
+    EstrellaError, document,
+    document::batch::CutPolicy,
+    ir::OptimizeLevel,
+    logos, preview,
+    preview::terminal::TerminalGraphics,
     printer::PrinterConfig,
-    protocol::{commands, nv_graphics},
+    protocol::{
+        commands::{self, PrintSpeed},
+        nv_graphics,
+    },
     receipt,
+    render::animation,
     render::dither,
     render::patterns,
+    render::quality::PrintQuality,
+    render::recipe::Recipe,
     render::weave::{BlendCurve, Weave},
     server,
-    transport::BluetoothTransport,
+    transport::{self, Transport},
+    units::{self, Length},
 };
 
 /// Estrella - Thermal receipt printer utility
@@ -51,6 +85,10 @@ use estrella::{
 #[command(name = "estrella")]
 #[command(author, version, about, long_about = None)]
 struct Cli {
+    /// Load every NAME.expr formula file in this directory as a pattern
+    #[arg(long, global = true, value_name = "DIR")]
+    pattern_dir: Option<PathBuf>,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -70,7 +108,12 @@ enum Commands {
         #[arg(long, value_name = "FILE")]
         png: Option<PathBuf>,
 
-        /// Printer device path
+        /// Show the preview in the terminal instead of printing
+        /// (auto, sixel, blocks; auto uses sixel when the terminal supports it)
+        #[arg(long, value_name = "MODE", num_args = 0..=1, default_missing_value = "auto")]
+        preview_term: Option<TerminalGraphics>,
+
+        /// Printer device path, or tcp://host[:port] for a network printer
         #[arg(long, default_value = "/dev/rfcomm0")]
         device: String,
 
@@ -78,14 +121,29 @@ enum Commands {
         #[arg(long)]
         height: Option<usize>,
 
-        /// Pattern length in millimeters (e.g., "15mm" or "62.5mm").
+        /// Pattern length (e.g., "15mm", "62.5mm", or "120" dots).
         /// Overrides --height if both are specified.
         #[arg(long, value_name = "LENGTH")]
-        length: Option<String>,
+        length: Option<Length>,
 
-        /// Print width in dots
-        #[arg(long, default_value = "576")]
-        width: usize,
+        /// Print width in dots (defaults to the pattern's width, up to the printer's)
+        #[arg(long)]
+        width: Option<usize>,
+
+        /// Print an animation strip: this many frames of the pattern, each
+        /// --height (or --length) tall, stepping through one loop of its motion
+        #[arg(long, value_name = "N")]
+        frames: Option<usize>,
+
+        /// Blank paper between animation frames (e.g., "5mm")
+        #[arg(long, value_name = "LENGTH", default_value = "5mm")]
+        frame_gap: Length,
+
+        /// Printer model (tsp650ii, tsp100, tsp700ii, mc-print2, mc-print3, sm-l200)
+        /// or paper width for other printers (58mm, 80mm, 112mm).
+        /// Sets the print width and which cuts and graphics commands are sent.
+        #[arg(long, default_value = "tsp650ii")]
+        printer: PrinterConfig,
 
         /// Skip printing title header
         #[arg(long)]
@@ -100,15 +158,37 @@ enum Commands {
         #[arg(long)]
         raster: bool,
 
-        /// Dithering algorithm (bayer, floyd-steinberg, atkinson, jarvis)
+        /// Dithering algorithm (bayer, blue-noise, floyd-steinberg, atkinson, jarvis, auto),
+        /// optionally with options: fs:serpentine,gamma=1.8,levels=0.1-0.9
         #[arg(long, default_value = "floyd-steinberg")]
         dither: String,
 
+        /// Pattern quality preset (draft, normal, high). Draft samples every
+        /// second row and dithers with Bayer; high averages 2x2 samples per
+        /// dot and sends in smaller, paced chunks.
+        #[arg(long, default_value = "normal")]
+        quality: PrintQuality,
+
+        /// Print density from -3 (lightest) to 3 (darkest). Lower it for
+        /// large dark patterns that fade halfway down the print.
+        #[arg(long, allow_hyphen_values = true, value_parser = clap::value_parser!(i8).range(-3..=3))]
+        density: Option<i8>,
+
+        /// Print speed (high, medium, low). Slower prints darker.
+        #[arg(long)]
+        speed: Option<PrintSpeed>,
+
         /// Use golden (deterministic) parameters instead of randomized ones.
         /// Useful for golden tests and reproducible output.
         #[arg(long)]
         golden: bool,
 
+        /// Seed for the randomized parameters. The seed of every random
+        /// print is shown in its parameter footer, so passing it back
+        /// reproduces the print.
+        #[arg(long, conflicts_with = "golden")]
+        seed: Option<u64>,
+
         /// Set a pattern parameter (can be used multiple times).
         /// Format: name=value (e.g., --param scale=8.0 --param gamma=1.5)
         #[arg(long = "param", value_name = "NAME=VALUE")]
@@ -122,6 +202,49 @@ enum Commands {
         /// By default, randomized patterns show their parameters for reproducibility.
         #[arg(long)]
         no_params: bool,
+
+        /// Also print the pattern name, seed and parameters as a QR code,
+        /// which `estrella reprint` turns back into the same print
+        #[arg(long, conflicts_with = "no_params")]
+        params_qr: bool,
+
+        /// Optimizer level (none, safe, aggressive).
+        /// Use "none" to send ops verbatim when debugging layout issues.
+        #[arg(long, default_value = "aggressive")]
+        optimize: OptimizeLevel,
+
+        /// First code for `print barcodes` (leading zeros are kept)
+        #[arg(long, default_value = "1")]
+        start: String,
+
+        /// Number of labels for `print barcodes`
+        #[arg(long, default_value = "1")]
+        count: usize,
+
+        /// Barcode format for `print barcodes` (code39, code128, ean13, upca, itf).
+        /// Overrides the format of barcodes in --template.
+        #[arg(long)]
+        format: Option<String>,
+
+        /// Document JSON used for each label in `print barcodes`.
+        /// `{{code}}`, `{{index}}` and `{{count}}` are filled in per label.
+        #[arg(long, value_name = "FILE")]
+        template: Option<PathBuf>,
+
+        /// Read codes for `print barcodes` from a CSV file instead of counting.
+        /// Uses the `code` column (or the first one); every column is a variable.
+        #[arg(long, value_name = "FILE")]
+        csv: Option<PathBuf>,
+
+        /// Document JSON for the stub half of each `print tickets` ticket
+        /// (--template sets the body half)
+        #[arg(long, value_name = "FILE")]
+        stub: Option<PathBuf>,
+
+        /// Cuts between items of `all`, `barcodes` and `tickets`: full,
+        /// partial, none (a dashed separator instead) or every:N
+        #[arg(long, value_name = "POLICY", default_value = "partial")]
+        cut: CutPolicy,
     },
 
     /// Manage logos stored in printer's NV (non-volatile) memory
@@ -130,15 +253,181 @@ enum Commands {
         action: LogoAction,
     },
 
+    /// Save and print named document templates on an estrella server
+    Template {
+        #[command(subcommand)]
+        action: TemplateAction,
+    },
+
+    /// Browse, preview and print patterns, receipts and documents in the terminal
+    #[cfg(feature = "tui")]
+    Tui {
+        /// Printer device path, or tcp://host[:port] for a network printer
+        #[arg(long, default_value = "/dev/rfcomm0")]
+        device: String,
+
+        /// Printer model or paper width (see `estrella print --help`).
+        /// Previews and prints use its print width.
+        #[arg(long, default_value = "tsp650ii")]
+        printer: PrinterConfig,
+
+        /// Directory of JSON documents to list (e.g. DIR/documents for the server's --data-dir)
+        #[arg(long, value_name = "DIR")]
+        documents_dir: Option<PathBuf>,
+    },
+
     /// Start HTTP server for web-based printing
     Serve {
         /// Address and port to bind to
         #[arg(long, default_value = "0.0.0.0:8080")]
         listen: String,
 
-        /// Printer device path
+        /// Printer device path, tcp://host[:port] for a network printer,
+        /// or "none" to serve previews without a printer
         #[arg(long, default_value = "/dev/rfcomm0")]
         device: String,
+
+        /// Printer model (tsp650ii, tsp100, tsp700ii, mc-print2, mc-print3, sm-l200)
+        /// or paper width for other printers (58mm, 80mm, 112mm).
+        /// Previews, layout and prints use its print width (384 dots on 58mm paper).
+        #[arg(long, default_value = "tsp650ii")]
+        printer: PrinterConfig,
+
+        /// Print a status receipt (hostname, IP, QR to the web UI) on startup.
+        /// Handy for headless deployments that need to reveal their address.
+        #[arg(long)]
+        startup_print: bool,
+
+        /// Self-hosted URL shortener endpoint for long QR code links.
+        /// Receives POST {"url": "..."} and returns the short URL.
+        #[arg(long, value_name = "URL")]
+        shortener_url: Option<String>,
+
+        /// Shorten QR code links longer than this many characters
+        #[arg(long, default_value = "100")]
+        shorten_threshold: usize,
+
+        /// Shell command run as a document hook before every preview/print
+        /// (can be used multiple times). Receives the document JSON on stdin
+        /// and must print the transformed document JSON.
+        #[arg(long = "hook", value_name = "COMMAND")]
+        hooks: Vec<String>,
+
+        /// Seconds a hook command may run before it is killed and the
+        /// print fails
+        #[arg(long, value_name = "SECS", default_value = "10")]
+        hook_timeout: u64,
+
+        /// Directory for saved state, e.g. documents saved by edit-link prints
+        /// (in DIR/documents), so a receipt's QR code can reopen its document
+        /// after a restart (memory only if unset)
+        #[arg(long, value_name = "DIR", alias = "documents-dir")]
+        data_dir: Option<PathBuf>,
+
+        /// Sqlite database for saved state, instead of one JSON file per
+        /// record in --data-dir
+        #[cfg(feature = "sqlite")]
+        #[arg(long, value_name = "FILE", conflicts_with = "data_dir")]
+        database: Option<PathBuf>,
+
+        /// Dithering for photos and image components that don't choose one
+        #[arg(long, default_value = "floyd-steinberg")]
+        photo_dither: dither::DitheringAlgorithm,
+
+        /// Dithering for patterns, weaves, textures and compositions that don't choose one
+        #[arg(long, default_value = "bayer")]
+        pattern_dither: dither::DitheringAlgorithm,
+
+        /// Latitude and longitude (e.g. 52.52,13.40) for {{sunrise}} and
+        /// {{sunset}} in documents that don't set a location
+        #[arg(long, value_name = "LAT,LON")]
+        location: Option<document::Location>,
+
+        /// Directory that file:// images in documents may be read from
+        /// (can be used multiple times; local images are refused without one)
+        #[arg(long = "image-dir", value_name = "DIR")]
+        image_dirs: Vec<PathBuf>,
+
+        /// Largest file:// or data: image accepted, in bytes
+        #[arg(long, default_value_t = document::DEFAULT_MAX_IMAGE_BYTES)]
+        max_image_bytes: usize,
+
+        /// Local time window (e.g. 22:00-07:00) when prints are held and
+        /// sent once it ends; requests with ?urgent=true print anyway
+        #[arg(long, value_name = "HH:MM-HH:MM")]
+        quiet_hours: Option<server::QuietHours>,
+
+        /// Also act as an IPP printer at ipp://HOST:PORT/ipp/print, so
+        /// desktops can add it and print text, images and PWG raster
+        #[arg(long)]
+        ipp: bool,
+
+        #[cfg(feature = "mqtt")]
+        #[command(flatten)]
+        mqtt: MqttArgs,
+    },
+
+    /// Normalize and pretty-print JSON documents in place
+    Fmt {
+        /// Document files to format
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+
+        /// Collapse components to shorthand form ({"text": "hi"})
+        #[arg(long)]
+        compact: bool,
+
+        /// Don't write anything; fail if any file isn't formatted
+        #[arg(long)]
+        check: bool,
+    },
+
+    /// Compile a pattern, receipt or JSON document and list its IR ops and
+    /// StarPRNT bytes, without touching the printer
+    Dump {
+        /// Pattern or receipt name, or a JSON document file
+        target: String,
+
+        /// Printer model (tsp650ii, tsp100, tsp700ii, mc-print2, mc-print3, sm-l200)
+        /// or paper width for other printers (58mm, 80mm, 112mm)
+        #[arg(long, default_value = "tsp650ii")]
+        printer: PrinterConfig,
+
+        /// Optimizer level (none, safe, aggressive).
+        /// Defaults to the document's own level, or aggressive.
+        #[arg(long)]
+        optimize: Option<OptimizeLevel>,
+
+        /// Pattern height in rows (defaults to pattern's recommended height).
+        /// Patterns always use their golden parameters, so dumps can be diffed.
+        #[arg(long)]
+        height: Option<usize>,
+
+        /// Also write the raw bytes to this file (e.g. to compare with `cmp`)
+        #[arg(long, value_name = "FILE")]
+        bytes: Option<PathBuf>,
+    },
+
+    /// Show a JSON document on an e-paper panel, or save it as a panel-sized BMP
+    Display {
+        /// JSON document to render
+        document: PathBuf,
+
+        /// Panel size (waveshare-2in9, waveshare-4in2, waveshare-7in5, inkplate-6)
+        #[arg(long, default_value = "waveshare-7in5")]
+        panel: String,
+
+        /// Mount the panel in portrait orientation (better for receipts)
+        #[arg(long)]
+        portrait: bool,
+
+        /// Save as a 1-bit BMP
+        #[arg(long, value_name = "FILE")]
+        bmp: Option<PathBuf>,
+
+        /// Write the packed framebuffer (1 = white) to this device, FIFO or file
+        #[arg(long, value_name = "PATH")]
+        device: Option<PathBuf>,
     },
 
     /// Blend multiple patterns together with crossfade transitions (like a DJ mix)
@@ -147,13 +436,13 @@ enum Commands {
         #[arg(required = true)]
         patterns: Vec<String>,
 
-        /// Total length in millimeters (e.g., "500mm")
+        /// Total length (e.g., "500mm")
         #[arg(long, value_name = "LENGTH", default_value = "200mm")]
-        length: String,
+        length: Length,
 
-        /// Crossfade transition length in millimeters (e.g., "30mm")
+        /// Crossfade transition length (e.g., "30mm")
         #[arg(long, value_name = "LENGTH", default_value = "30mm")]
-        crossfade: String,
+        crossfade: Length,
 
         /// Blend curve: linear, smooth, ease-in, ease-out
         #[arg(long, default_value = "smooth")]
@@ -163,23 +452,154 @@ enum Commands {
         #[arg(long, value_name = "FILE")]
         png: Option<PathBuf>,
 
-        /// Printer device path
+        /// Printer device path, or tcp://host[:port] for a network printer
         #[arg(long, default_value = "/dev/rfcomm0")]
         device: String,
 
-        /// Print width in dots
-        #[arg(long, default_value = "576")]
-        width: usize,
+        /// Printer model or paper width (see `estrella print --help`)
+        #[arg(long, default_value = "tsp650ii")]
+        printer: PrinterConfig,
+
+        /// Print width in dots (defaults to the printer's print width)
+        #[arg(long)]
+        width: Option<usize>,
 
         /// Use golden (deterministic) parameters instead of randomized ones
         #[arg(long)]
         golden: bool,
 
-        /// Dithering algorithm (bayer, floyd-steinberg, atkinson, jarvis)
+        /// Dithering algorithm (bayer, blue-noise, floyd-steinberg, atkinson, jarvis, auto),
+        /// optionally with options: fs:serpentine,gamma=1.8,levels=0.1-0.9
         #[arg(long, default_value = "floyd-steinberg")]
         dither: String,
     },
 
+    /// Print a poster: a pattern band, a big title, details and a QR code footer
+    Poster {
+        /// Headline, printed as large as the paper allows
+        #[arg(long)]
+        title: String,
+
+        /// Pattern for the header band
+        #[arg(long, default_value = "riley")]
+        pattern: String,
+
+        /// Line under the title
+        #[arg(long)]
+        subtitle: Option<String>,
+
+        /// Detail line under the subtitle (can be used multiple times)
+        #[arg(long = "detail", value_name = "TEXT")]
+        details: Vec<String>,
+
+        /// URL (or any text) for a QR code footer
+        #[arg(long, value_name = "URL")]
+        qr: Option<String>,
+
+        /// Height of the pattern band (e.g., "40mm")
+        #[arg(long, value_name = "LENGTH", default_value = "40mm")]
+        band: Length,
+
+        /// Set a pattern parameter (can be used multiple times).
+        /// Format: name=value (e.g., --param scale=8.0)
+        #[arg(long = "param", value_name = "NAME=VALUE")]
+        params: Vec<String>,
+
+        /// Use golden (deterministic) pattern parameters instead of randomized ones
+        #[arg(long)]
+        golden: bool,
+
+        /// Seed for the randomized pattern parameters
+        #[arg(long, conflicts_with = "golden")]
+        seed: Option<u64>,
+
+        /// Output to PNG file instead of printing
+        #[arg(long, value_name = "FILE")]
+        png: Option<PathBuf>,
+
+        /// Show the preview in the terminal instead of printing
+        /// (auto, sixel, blocks; auto uses sixel when the terminal supports it)
+        #[arg(long, value_name = "MODE", num_args = 0..=1, default_missing_value = "auto")]
+        preview_term: Option<TerminalGraphics>,
+
+        /// Printer device path, or tcp://host[:port] for a network printer
+        #[arg(long, default_value = "/dev/rfcomm0")]
+        device: String,
+
+        /// Printer model or paper width (see `estrella print --help`)
+        #[arg(long, default_value = "tsp650ii")]
+        printer: PrinterConfig,
+    },
+
+    /// Print the pages of a PDF, scaled to the paper and dithered
+    #[cfg(feature = "pdf")]
+    PrintPdf {
+        /// The PDF file
+        file: PathBuf,
+
+        /// Dithering algorithm: floyd-steinberg (default), bayer, atkinson,
+        /// jarvis, blue-noise, none or auto
+        #[arg(long)]
+        dither: Option<String>,
+
+        /// Print whole pages instead of trimming their blank margins
+        #[arg(long)]
+        keep_margins: bool,
+
+        /// Password of an encrypted PDF
+        #[arg(long)]
+        password: Option<String>,
+
+        /// Cuts between pages: full, partial, none (a dashed separator
+        /// instead) or every:N
+        #[arg(long, value_name = "POLICY", default_value = "partial")]
+        cut: CutPolicy,
+
+        /// Output to PNG file instead of printing
+        #[arg(long, value_name = "FILE")]
+        png: Option<PathBuf>,
+
+        /// Show the preview in the terminal instead of printing
+        /// (auto, sixel, blocks; auto uses sixel when the terminal supports it)
+        #[arg(long, value_name = "MODE", num_args = 0..=1, default_missing_value = "auto")]
+        preview_term: Option<TerminalGraphics>,
+
+        /// Printer device path, or tcp://host[:port] for a network printer
+        #[arg(long, default_value = "/dev/rfcomm0")]
+        device: String,
+
+        /// Printer model or paper width (see `estrella print --help`)
+        #[arg(long, default_value = "tsp650ii")]
+        printer: PrinterConfig,
+    },
+
+    /// Print a pattern again from the QR code of `print --params-qr`
+    Reprint {
+        /// The scanned QR code text (estrella:PATTERN?seed=N&...)
+        payload: String,
+
+        /// Pattern height in rows (defaults to pattern's recommended height)
+        #[arg(long)]
+        height: Option<usize>,
+
+        /// Output to PNG file instead of printing
+        #[arg(long, value_name = "FILE")]
+        png: Option<PathBuf>,
+
+        /// Show the preview in the terminal instead of printing
+        /// (auto, sixel, blocks; auto uses sixel when the terminal supports it)
+        #[arg(long, value_name = "MODE", num_args = 0..=1, default_missing_value = "auto")]
+        preview_term: Option<TerminalGraphics>,
+
+        /// Printer device path, or tcp://host[:port] for a network printer
+        #[arg(long, default_value = "/dev/rfcomm0")]
+        device: String,
+
+        /// Printer model or paper width (see `estrella print --help`)
+        #[arg(long, default_value = "tsp650ii")]
+        printer: PrinterConfig,
+    },
+
     /// Set up RFCOMM device for a Bluetooth MAC address (requires root)
     SetupRfcomm {
         /// Bluetooth MAC address (e.g., 00:11:22:33:44:55)
@@ -206,7 +626,7 @@ enum LogoAction {
 
     /// Sync registry logos to the printer's NV memory
     Sync {
-        /// Printer device path
+        /// Printer device path, or tcp://host[:port] for a network printer
         #[arg(long, default_value = "/dev/rfcomm0")]
         device: String,
 
@@ -238,7 +658,7 @@ enum LogoAction {
         #[arg(long, default_value = "A0")]
         key: String,
 
-        /// Printer device path
+        /// Printer device path, or tcp://host[:port] for a network printer
         #[arg(long, default_value = "/dev/rfcomm0")]
         device: String,
 
@@ -253,14 +673,14 @@ enum LogoAction {
         #[arg(long, default_value = "A0")]
         key: String,
 
-        /// Printer device path
+        /// Printer device path, or tcp://host[:port] for a network printer
         #[arg(long, default_value = "/dev/rfcomm0")]
         device: String,
     },
 
     /// Delete ALL logos from the printer's NV memory
     DeleteAll {
-        /// Printer device path
+        /// Printer device path, or tcp://host[:port] for a network printer
         #[arg(long, default_value = "/dev/rfcomm0")]
         device: String,
 
@@ -270,6 +690,93 @@ enum LogoAction {
     },
 }
 
+/// `serve` options for printing through an MQTT broker.
+#[cfg(feature = "mqtt")]
+#[derive(clap::Args, Debug)]
+struct MqttArgs {
+    /// MQTT broker (host[:port]) to print documents published to
+    /// TOPIC/print from, and report job results and printer status to
+    #[arg(long = "mqtt", value_name = "HOST[:PORT]")]
+    broker: Option<String>,
+
+    /// Prefix of the MQTT topics
+    #[arg(long, default_value = "estrella")]
+    mqtt_topic: String,
+
+    /// MQTT client id, unique on the broker
+    #[arg(long, default_value = "estrella")]
+    mqtt_client_id: String,
+
+    /// MQTT user name
+    #[arg(long)]
+    mqtt_username: Option<String>,
+
+    /// MQTT password (defaults to $ESTRELLA_MQTT_PASSWORD)
+    #[arg(long)]
+    mqtt_password: Option<String>,
+}
+
+#[cfg(feature = "mqtt")]
+impl MqttArgs {
+    fn config(self) -> Option<server::mqtt::MqttConfig> {
+        Some(server::mqtt::MqttConfig {
+            broker: self.broker?,
+            topic: self.mqtt_topic,
+            client_id: self.mqtt_client_id,
+            username: self.mqtt_username,
+            password: self
+                .mqtt_password
+                .or_else(|| std::env::var("ESTRELLA_MQTT_PASSWORD").ok()),
+        })
+    }
+}
+
+#[derive(Subcommand, Debug)]
+enum TemplateAction {
+    /// List the server's templates
+    List {
+        /// Server URL
+        #[arg(long, default_value = "http://localhost:8080")]
+        server: String,
+    },
+
+    /// Save a document JSON file as a template
+    Save {
+        /// Template name (letters, digits, '-' and '_')
+        name: String,
+
+        /// Document JSON with {{variable}} placeholders
+        file: PathBuf,
+
+        /// Server URL
+        #[arg(long, default_value = "http://localhost:8080")]
+        server: String,
+    },
+
+    /// Print a saved template with the given variables
+    Print {
+        /// Template name
+        name: String,
+
+        /// Set a variable (can be used multiple times).
+        /// Format: name=value (e.g., --var name=Ada --var table=4)
+        #[arg(long = "var", value_name = "NAME=VALUE")]
+        vars: Vec<String>,
+
+        /// Read variables from a JSON object file (--var values override it)
+        #[arg(long, value_name = "FILE")]
+        vars_file: Option<PathBuf>,
+
+        /// Print right away, even during the server's quiet hours
+        #[arg(long)]
+        urgent: bool,
+
+        /// Server URL
+        #[arg(long, default_value = "http://localhost:8080")]
+        server: String,
+    },
+}
+
 fn main() {
     if let Err(e) = run() {
         eprintln!("Error: {}", e);
@@ -280,23 +787,44 @@ fn main() {
 fn run() -> Result<(), EstrellaError> {
     let cli = Cli::parse();
 
+    if let Some(dir) = &cli.pattern_dir {
+        estrella::art::registry::load_formula_dir(dir).map_err(EstrellaError::Pattern)?;
+    }
+
     match cli.command {
         Commands::Print {
             pattern,
             list,
             png,
+            preview_term,
             device,
             height,
             length,
             width,
+            frames,
+            frame_gap,
+            printer,
             no_title,
             band,
             raster,
             dither,
+            quality,
+            density,
+            speed,
             golden,
+            seed,
             params,
             list_params,
             no_params,
+            params_qr,
+            optimize,
+            start,
+            count,
+            format,
+            template,
+            csv,
+            stub,
+            cut,
         } => {
             // List patterns if --list flag or no pattern specified
             if list || pattern.is_none() {
@@ -309,7 +837,10 @@ fn run() -> Result<(), EstrellaError> {
                     println!("  {}", name);
                 }
                 println!("\nSpecial:");
-                println!("  all  - Print all patterns and receipts");
+                println!("  all       - Print all patterns and receipts");
+                println!(
+                    "  barcodes  - Print a batch of barcode labels (--start/--count or --csv)"
+                );
                 return Ok(());
             }
 
@@ -339,81 +870,119 @@ fn run() -> Result<(), EstrellaError> {
             // Handle "all" - print all patterns and receipts
             if name == "all" {
                 println!("Printing all patterns and receipts...\n");
+                let receipts = receipt::list_receipts();
+                let pattern_names = patterns::list_patterns();
+                let total = receipts.len() + pattern_names.len();
 
                 // Print all receipts first
-                for receipt_name in receipt::list_receipts() {
+                for (i, receipt_name) in receipts.iter().enumerate() {
                     println!("Printing receipt: {}", receipt_name);
-                    let receipt_data = receipt::by_name(receipt_name).unwrap();
-                    print_raw_to_device(&device, &receipt_data)?;
+                    let mut doc = receipt::document_by_name(receipt_name).unwrap();
+                    doc.optimize = optimize;
+                    doc.density = density;
+                    doc.speed = speed;
+                    let mut program = doc.print_program_with_config(&printer)?;
+                    cut.finish(&mut program, i, total, &printer);
+                    print_program_to_device(&device, &program, &printer)?;
                 }
 
                 // Then print all patterns
-                for pattern_name in patterns::list_patterns() {
+                for (i, pattern_name) in pattern_names.into_iter().enumerate() {
                     println!("Printing pattern: {}", pattern_name);
 
                     // Get pattern impl - randomized by default unless --golden
-                    let pattern_impl = if golden {
-                        patterns::by_name_golden(pattern_name).unwrap()
-                    } else {
-                        patterns::by_name_random(pattern_name).unwrap()
+                    let pattern_seed =
+                        (!golden).then(|| seed.unwrap_or_else(patterns::random_seed));
+                    let pattern_impl = match pattern_seed {
+                        Some(seed) => patterns::by_name_seeded(pattern_name, seed).unwrap(),
+                        None => patterns::by_name_golden(pattern_name).unwrap(),
                     };
 
                     let (default_width, default_height) = pattern_impl.default_dimensions();
-                    let pattern_width = if width != 576 { width } else { default_width };
-                    let pattern_height = if let Some(ref len) = length {
-                        parse_length_mm(len)?
+                    let pattern_width =
+                        width.unwrap_or(default_width.min(printer.width_dots as usize));
+                    let pattern_height = if let Some(len) = length {
+                        positive_dots(len, &printer)?
                     } else {
                         height.unwrap_or(default_height)
                     };
 
-                    // Parse dithering algorithm
-                    let dither_algo = match dither.to_lowercase().as_str() {
-                        "none" | "threshold" => dither::DitheringAlgorithm::None,
-                        "bayer" => dither::DitheringAlgorithm::Bayer,
-                        "floyd-steinberg" | "floyd_steinberg" | "fs" => {
-                            dither::DitheringAlgorithm::FloydSteinberg
-                        }
-                        "atkinson" => dither::DitheringAlgorithm::Atkinson,
-                        "jarvis" | "jjn" => dither::DitheringAlgorithm::Jarvis,
-                        _ => {
-                            return Err(EstrellaError::Pattern(format!(
-                                "Unknown dithering algorithm '{}'. Use 'none', 'bayer', 'floyd-steinberg', 'atkinson', or 'jarvis'",
-                                dither
-                            )));
-                        }
-                    };
+                    // Parse dithering algorithm and options (e.g. fs:serpentine)
+                    let dither_spec: dither::DitherSpec =
+                        dither.parse().map_err(EstrellaError::Pattern)?;
 
                     let program = build_pattern_program(
                         pattern_impl.as_ref(),
+                        &printer,
                         pattern_width,
                         pattern_height,
-                        dither_algo,
+                        &dither_spec,
+                        quality,
                         !no_title,
                         band,
-                        !no_params && !golden,
+                        (!no_params && !golden).then_some(ParamsFooter {
+                            seed: pattern_seed,
+                            qr: params_qr,
+                        }),
                     );
-                    let print_data = program
-                        .optimize()
-                        .to_bytes_with_config(&PrinterConfig::TSP650II);
-                    print_raw_to_device(&device, &print_data)?;
+                    let mut program = program.optimize_with_config(optimize, &printer);
+                    apply_head_settings(&mut program, density, speed);
+                    cut.finish(&mut program, receipts.len() + i, total, &printer);
+                    print_program_to_device(&quality.device(&device), &program, &printer)?;
                 }
 
                 println!("\nAll patterns and receipts printed successfully!");
                 return Ok(());
             }
 
+            if name == "barcodes" {
+                return print_barcode_batch(
+                    &start,
+                    count,
+                    format.as_deref(),
+                    template.as_ref(),
+                    csv.as_ref(),
+                    optimize,
+                    cut,
+                    png.as_ref(),
+                    preview_term,
+                    &device,
+                    &printer,
+                );
+            }
+
+            if name == "tickets" {
+                return print_ticket_batch(
+                    &start,
+                    count,
+                    template.as_ref(),
+                    stub.as_ref(),
+                    optimize,
+                    cut,
+                    png.as_ref(),
+                    preview_term,
+                    &device,
+                    &printer,
+                );
+            }
+
             // Check if it's a receipt template
             if receipt::is_receipt(name) {
                 if raster {
                     // Raster mode: render as full-page raster (no margins)
-                    return print_as_raster(name, png.as_ref(), &device);
+                    return print_as_raster(name, png.as_ref(), &device, &printer);
                 }
 
+                let mut doc = receipt::document_by_name(name).unwrap();
+                doc.optimize = optimize;
+                doc.density = density;
+                doc.speed = speed;
+
                 if let Some(png_path) = png {
                     // Render receipt to PNG preview
                     println!("Generating {} receipt preview...", name);
-                    let program = receipt::program_by_name(name).unwrap();
-                    let png_bytes = program.to_preview_png().map_err(|e| {
+                    let program = doc.compile_with_config(&printer);
+                    let png_bytes = program.to_preview_png_with_config(&printer).map_err(|e| {
                         EstrellaError::Image(format!("Failed to render preview: {}", e))
                     })?;
                     std::fs::write(&png_path, &png_bytes)
@@ -422,30 +991,33 @@ fn run() -> Result<(), EstrellaError> {
                     return Ok(());
                 }
 
+                if let Some(mode) = preview_term {
+                    return preview_in_terminal(&doc.compile_with_config(&printer), mode);
+                }
+
                 println!("Printing {} receipt...", name);
-                let receipt_data = receipt::by_name(name).unwrap();
-                print_raw_to_device(&device, &receipt_data)?;
+                print_program_to_device(
+                    &device,
+                    &doc.print_program_with_config(&printer)?,
+                    &printer,
+                )?;
                 println!("Printed successfully!");
                 return Ok(());
             }
 
             // It's a visual pattern
             // Get pattern impl - randomized by default unless --golden
-            let mut pattern_impl = if golden {
-                patterns::by_name_golden(name).ok_or_else(|| {
-                    EstrellaError::Pattern(format!(
-                        "Unknown pattern or receipt '{}'. Run without arguments to see available options.",
-                        name
-                    ))
-                })?
-            } else {
-                patterns::by_name_random(name).ok_or_else(|| {
-                    EstrellaError::Pattern(format!(
-                        "Unknown pattern or receipt '{}'. Run without arguments to see available options.",
-                        name
-                    ))
-                })?
-            };
+            let seed = (!golden).then(|| seed.unwrap_or_else(patterns::random_seed));
+            let mut pattern_impl = match seed {
+                Some(seed) => patterns::by_name_seeded(name, seed),
+                None => patterns::by_name_golden(name),
+            }
+            .ok_or_else(|| {
+                EstrellaError::Pattern(format!(
+                    "Unknown pattern or receipt '{}'. Run without arguments to see available options.",
+                    name
+                ))
+            })?;
 
             // Apply any --param overrides
             for param_str in &params {
@@ -463,64 +1035,87 @@ fn run() -> Result<(), EstrellaError> {
 
             // Use pattern's default dimensions if user didn't specify
             let (default_width, default_height) = pattern_impl.default_dimensions();
-            let width = if width != 576 { width } else { default_width };
-            let height = if let Some(ref len) = length {
-                parse_length_mm(len)?
+            let width = width.unwrap_or(default_width.min(printer.width_dots as usize));
+            let height = if let Some(len) = length {
+                positive_dots(len, &printer)?
             } else {
                 height.unwrap_or(default_height)
             };
 
             let params_desc = pattern_impl.params_description();
-            if !params_desc.is_empty() && !golden {
+            if let Some(seed) = seed {
+                let params_desc = if params_desc.is_empty() {
+                    String::new()
+                } else {
+                    format!(" and params: {}", params_desc)
+                };
                 println!(
-                    "Generating {} pattern ({}x{}) with params: {}...",
-                    name, width, height, params_desc
+                    "Generating {} pattern ({}x{}) with seed {}{}...",
+                    name, width, height, seed, params_desc
                 );
             } else {
                 println!("Generating {} pattern ({}x{})...", name, width, height);
             }
 
-            // Parse dithering algorithm
-            let dither_algo = match dither.to_lowercase().as_str() {
-                "none" | "threshold" => dither::DitheringAlgorithm::None,
-                "bayer" => dither::DitheringAlgorithm::Bayer,
-                "floyd-steinberg" | "floyd_steinberg" | "fs" => {
-                    dither::DitheringAlgorithm::FloydSteinberg
-                }
-                "atkinson" => dither::DitheringAlgorithm::Atkinson,
-                "jarvis" | "jjn" => dither::DitheringAlgorithm::Jarvis,
-                _ => {
-                    return Err(EstrellaError::Pattern(format!(
-                        "Unknown dithering algorithm '{}'. Use 'none', 'bayer', 'floyd-steinberg', 'atkinson', or 'jarvis'",
-                        dither
-                    )));
+            // Parse dithering algorithm and options (e.g. fs:serpentine)
+            let dither_spec: dither::DitherSpec = dither.parse().map_err(EstrellaError::Pattern)?;
+
+            // Build pattern program (or an animation strip of it)
+            let footer = (!no_params && !golden).then_some(ParamsFooter {
+                seed,
+                qr: params_qr,
+            });
+            let mut program = match frames {
+                Some(frames) => {
+                    if !(1..=animation::MAX_FRAMES).contains(&frames) {
+                        return Err(EstrellaError::Pattern(format!(
+                            "--frames must be between 1 and {}",
+                            animation::MAX_FRAMES
+                        )));
+                    }
+                    build_animation_program(
+                        pattern_impl.as_ref(),
+                        &printer,
+                        width,
+                        height,
+                        frames,
+                        positive_mm(frame_gap, &printer)?,
+                        &dither_spec,
+                        quality,
+                        !no_title,
+                        footer,
+                    )
                 }
+                None => build_pattern_program(
+                    pattern_impl.as_ref(),
+                    &printer,
+                    width,
+                    height,
+                    &dither_spec,
+                    quality,
+                    !no_title,
+                    band,
+                    footer,
+                ),
             };
-
-            // Build pattern program
-            let program = build_pattern_program(
-                pattern_impl.as_ref(),
-                width,
-                height,
-                dither_algo,
-                !no_title,
-                band,
-                !no_params && !golden,
-            );
+            apply_head_settings(&mut program, density, speed);
 
             // Output to PNG or printer
             if let Some(png_path) = png {
-                let png_bytes = program.to_preview_png().map_err(|e| {
+                let png_bytes = program.to_preview_png_with_config(&printer).map_err(|e| {
                     EstrellaError::Image(format!("Failed to render preview: {}", e))
                 })?;
                 std::fs::write(&png_path, &png_bytes)
                     .map_err(|e| EstrellaError::Image(format!("Failed to write PNG: {}", e)))?;
                 println!("Saved to {}", png_path.display());
+            } else if let Some(mode) = preview_term {
+                preview_in_terminal(&program, mode)?;
             } else {
-                let print_data = program
-                    .optimize()
-                    .to_bytes_with_config(&PrinterConfig::TSP650II);
-                print_raw_to_device(&device, &print_data)?;
+                print_program_to_device(
+                    &quality.device(&device),
+                    &program.optimize_with_config(optimize, &printer),
+                    &printer,
+                )?;
                 println!("Printed successfully!");
             }
         }
@@ -551,10 +1146,111 @@ fn run() -> Result<(), EstrellaError> {
             }
         },
 
-        Commands::Serve { listen, device } => {
+        Commands::Template { action } => match action {
+            TemplateAction::List { server } => {
+                template_list(&server)?;
+            }
+            TemplateAction::Save { name, file, server } => {
+                template_save(&server, &name, &file)?;
+            }
+            TemplateAction::Print {
+                name,
+                vars,
+                vars_file,
+                urgent,
+                server,
+            } => {
+                template_print(&server, &name, &vars, vars_file.as_deref(), urgent)?;
+            }
+        },
+
+        Commands::Fmt {
+            files,
+            compact,
+            check,
+        } => {
+            format_documents(&files, compact, check)?;
+        }
+        Commands::Dump {
+            target,
+            printer,
+            optimize,
+            height,
+            bytes,
+        } => {
+            dump_target(&target, &printer, optimize, height, bytes.as_deref())?;
+        }
+
+        Commands::Display {
+            document,
+            panel,
+            portrait,
+            bmp,
+            device,
+        } => {
+            display_document(&document, &panel, portrait, bmp, device)?;
+        }
+        #[cfg(feature = "tui")]
+        Commands::Tui {
+            device,
+            printer,
+            documents_dir,
+        } => {
+            estrella::tui::run(device, printer, documents_dir)?;
+        }
+        Commands::Serve {
+            listen,
+            device,
+            printer,
+            startup_print,
+            shortener_url,
+            shorten_threshold,
+            hooks,
+            hook_timeout,
+            data_dir,
+            #[cfg(feature = "sqlite")]
+            database,
+            photo_dither,
+            pattern_dither,
+            location,
+            image_dirs,
+            max_image_bytes,
+            quiet_hours,
+            ipp,
+            #[cfg(feature = "mqtt")]
+            mqtt,
+        } => {
+            let storage = data_dir.map_or(server::StorageConfig::Memory, |dir| {
+                server::StorageConfig::Directory(dir)
+            });
+            #[cfg(feature = "sqlite")]
+            let storage = database.map_or(storage, server::StorageConfig::Sqlite);
             let config = server::ServerConfig {
                 device_path: device,
+                open_transport: transport::open_async,
+                printer,
                 listen_addr: listen,
+                startup_print,
+                shortener: shortener_url.map(|endpoint| document::ShortenerConfig {
+                    endpoint,
+                    threshold: shorten_threshold,
+                }),
+                hook_commands: hooks,
+                hook_timeout: std::time::Duration::from_secs(hook_timeout),
+                storage,
+                dither: server::DitherDefaults {
+                    photo: photo_dither,
+                    pattern: pattern_dither,
+                },
+                location,
+                image_sources: document::ImageSources {
+                    allowed_dirs: image_dirs,
+                    max_bytes: max_image_bytes,
+                },
+                quiet_hours,
+                ipp,
+                #[cfg(feature = "mqtt")]
+                mqtt: mqtt.config(),
             };
 
             // Create tokio runtime and run the server
@@ -572,23 +1268,158 @@ fn run() -> Result<(), EstrellaError> {
             curve,
             png,
             device,
+            printer,
             width,
             golden,
             dither,
         } => {
             weave_patterns(
                 &pattern_names,
-                &length,
-                &crossfade,
+                length,
+                crossfade,
                 &curve,
                 png.as_ref(),
                 &device,
-                width,
+                &printer,
+                width.unwrap_or(printer.width_dots as usize),
                 golden,
                 &dither,
             )?;
         }
 
+        Commands::Poster {
+            title,
+            pattern,
+            subtitle,
+            details,
+            qr,
+            band,
+            params,
+            golden,
+            seed,
+            png,
+            preview_term,
+            device,
+            printer,
+        } => {
+            let seed = (!golden).then(|| seed.unwrap_or_else(patterns::random_seed));
+            let poster = document::poster::Poster {
+                params: pattern_params(&pattern, &params, seed)?,
+                seed,
+                band_mm: positive_mm(band, &printer)?,
+                subtitle,
+                details,
+                qr,
+                ..document::poster::Poster::new(pattern, title)
+            };
+            let doc = poster.document();
+
+            if let Some(png_path) = png {
+                let png_bytes = doc
+                    .compile_with_config(&printer)
+                    .to_preview_png_with_config(&printer)
+                    .map_err(|e| {
+                        EstrellaError::Image(format!("Failed to render preview: {}", e))
+                    })?;
+                std::fs::write(&png_path, &png_bytes)
+                    .map_err(|e| EstrellaError::Image(format!("Failed to write PNG: {}", e)))?;
+                println!("Saved to {}", png_path.display());
+            } else if let Some(mode) = preview_term {
+                preview_in_terminal(&doc.compile_with_config(&printer), mode)?;
+            } else {
+                println!("Printing poster...");
+                print_program_to_device(
+                    &device,
+                    &doc.print_program_with_config(&printer)?,
+                    &printer,
+                )?;
+                println!("Printed successfully!");
+            }
+        }
+
+        #[cfg(feature = "pdf")]
+        Commands::PrintPdf {
+            file,
+            dither,
+            keep_margins,
+            password,
+            cut,
+            png,
+            preview_term,
+            device,
+            printer,
+        } => {
+            let options = document::pdf::PdfOptions {
+                dither,
+                keep_margins,
+                password,
+            };
+            let docs = document::pdf::documents(&std::fs::read(&file)?, &options, &printer)?;
+            let program = document::batch::batch_program_with_cuts(&docs, cut, &printer)?;
+
+            if let Some(png_path) = png {
+                let png_bytes = program.to_preview_png_with_config(&printer).map_err(|e| {
+                    EstrellaError::Image(format!("Failed to render preview: {}", e))
+                })?;
+                std::fs::write(&png_path, &png_bytes)
+                    .map_err(|e| EstrellaError::Image(format!("Failed to write PNG: {}", e)))?;
+                println!("Saved {} pages to {}", docs.len(), png_path.display());
+            } else if let Some(mode) = preview_term {
+                preview_in_terminal(&program, mode)?;
+            } else {
+                println!("Printing {} pages...", docs.len());
+                print_program_to_device(&device, &program, &printer)?;
+                println!("Printed successfully!");
+            }
+        }
+
+        Commands::Reprint {
+            payload,
+            height,
+            png,
+            preview_term,
+            device,
+            printer,
+        } => {
+            let recipe: Recipe = payload.parse().map_err(EstrellaError::Pattern)?;
+            let pattern_impl = recipe.pattern().map_err(EstrellaError::Pattern)?;
+            let (default_width, default_height) = pattern_impl.default_dimensions();
+            // The recipe goes under the reprint too, so it can be reprinted again
+            let program = build_pattern_program(
+                pattern_impl.as_ref(),
+                &printer,
+                default_width.min(printer.width_dots as usize),
+                height.unwrap_or(default_height),
+                &dither::DitherSpec::default(),
+                PrintQuality::Normal,
+                true,
+                false,
+                Some(ParamsFooter {
+                    seed: recipe.seed,
+                    qr: true,
+                }),
+            );
+
+            if let Some(png_path) = png {
+                let png_bytes = program.to_preview_png_with_config(&printer).map_err(|e| {
+                    EstrellaError::Image(format!("Failed to render preview: {}", e))
+                })?;
+                std::fs::write(&png_path, &png_bytes)
+                    .map_err(|e| EstrellaError::Image(format!("Failed to write PNG: {}", e)))?;
+                println!("Saved to {}", png_path.display());
+            } else if let Some(mode) = preview_term {
+                preview_in_terminal(&program, mode)?;
+            } else {
+                println!("Reprinting {}...", recipe.pattern);
+                print_program_to_device(
+                    &device,
+                    &program.optimize_with_config(OptimizeLevel::Aggressive, &printer),
+                    &printer,
+                )?;
+                println!("Printed successfully!");
+            }
+        }
+
         Commands::SetupRfcomm {
             mac,
             channel,
@@ -602,33 +1433,69 @@ fn run() -> Result<(), EstrellaError> {
     Ok(())
 }
 
+/// Parameters for a pattern component: randomized from `seed` (golden
+/// without one), then the `name=value` overrides. Every value is checked
+/// against the pattern.
+fn pattern_params(
+    name: &str,
+    overrides: &[String],
+    seed: Option<u64>,
+) -> Result<std::collections::HashMap<String, String>, EstrellaError> {
+    let pattern_impl = match seed {
+        Some(seed) => patterns::by_name_seeded(name, seed),
+        None => patterns::by_name_golden(name),
+    };
+    let mut pattern_impl = pattern_impl.ok_or_else(|| {
+        EstrellaError::Pattern(format!(
+            "Unknown pattern '{}'. Run `estrella print` to see available options.",
+            name
+        ))
+    })?;
+    for param_str in overrides {
+        let (key, value) = param_str.split_once('=').ok_or_else(|| {
+            EstrellaError::Pattern(format!(
+                "Invalid param format '{}'. Use name=value (e.g., --param scale=8.0)",
+                param_str
+            ))
+        })?;
+        pattern_impl
+            .set_param(key, value)
+            .map_err(EstrellaError::Pattern)?;
+    }
+    Ok(pattern_impl
+        .list_params()
+        .into_iter()
+        .map(|(key, value)| (key.to_string(), value))
+        .collect())
+}
+
 /// Build an IR Program for a pattern with optional title and params display.
+#[allow(clippy::too_many_arguments)]
 fn build_pattern_program(
     pattern_impl: &dyn patterns::Pattern,
+    printer: &PrinterConfig,
     width: usize,
     height: usize,
-    dither_algo: dither::DitheringAlgorithm,
+    dither_spec: &dither::DitherSpec,
+    quality: PrintQuality,
     show_title: bool,
     band_mode: bool,
-    show_params: bool,
+    footer: Option<ParamsFooter>,
 ) -> estrella::ir::Program {
     use estrella::ir::{Op, Program};
 
-    let raster_data = patterns::render(pattern_impl, width, height, dither_algo);
+    let raster_data = quality.render(
+        width,
+        height,
+        |x, y, w, h| pattern_impl.intensity(x, y, w, h),
+        dither_spec.algorithm,
+        &dither_spec.options,
+    );
+    let ctx = document::EmitContext::new(printer);
     let mut program = Program::with_init();
 
     if show_title {
-        let title = document::Text {
-            content: pattern_impl.name().to_string(),
-            center: true,
-            bold: true,
-            size: [3, 2],
-            ..Default::default()
-        };
-        title.emit(&mut program.ops);
-        program.push(Op::Newline);
-        let divider = document::Divider::default();
-        divider.emit(&mut program.ops);
+        emit_pattern_title(&ctx, &mut program, pattern_impl);
     }
 
     if band_mode {
@@ -644,25 +1511,68 @@ fn build_pattern_program(
         });
     }
 
-    if show_params {
-        let divider = document::Divider::default();
-        divider.emit(&mut program.ops);
-        let params_list = pattern_impl.list_params();
-        if !params_list.is_empty() {
-            let params_text = params_list
-                .iter()
-                .map(|(k, v)| format!("{}={}", k, v))
-                .collect::<Vec<_>>()
-                .join(", ");
-            let text = document::Text {
-                content: params_text,
-                center: true,
-                size: [0, 0],
-                ..Default::default()
-            };
-            text.emit(&mut program.ops);
-            program.push(Op::Newline);
+    if let Some(footer) = footer {
+        emit_pattern_params(&ctx, &mut program, pattern_impl, footer);
+    }
+
+    program.push(Op::Feed { units: 24 });
+    program.push(Op::Cut { partial: true });
+    program
+}
+
+/// Build an IR Program for an animation strip: `frames` frames of the
+/// pattern, each captioned with its frame number and time, `gap_mm` apart.
+#[allow(clippy::too_many_arguments)]
+fn build_animation_program(
+    pattern_impl: &dyn patterns::Pattern,
+    printer: &PrinterConfig,
+    width: usize,
+    frame_height: usize,
+    frames: usize,
+    gap_mm: f32,
+    dither_spec: &dither::DitherSpec,
+    quality: PrintQuality,
+    show_title: bool,
+    footer: Option<ParamsFooter>,
+) -> estrella::ir::Program {
+    use estrella::ir::{Op, Program};
+
+    let ctx = document::EmitContext::new(printer);
+    let mut program = Program::with_init();
+
+    if show_title {
+        emit_pattern_title(&ctx, &mut program, pattern_impl);
+    }
+
+    let strip = animation::render_strip_with_quality(
+        pattern_impl,
+        width,
+        frame_height,
+        frames,
+        dither_spec.algorithm,
+        &dither_spec.options,
+        quality,
+    );
+    let gap_units = units::mm_to_feed_units(gap_mm);
+    for frame in strip {
+        if frame.index > 0 && gap_units > 0 {
+            program.push(Op::Feed { units: gap_units });
         }
+        let caption = document::Text {
+            content: frame.label(),
+            center: true,
+            ..Default::default()
+        };
+        caption.emit(&ctx, &mut program.ops);
+        program.push(Op::Raster {
+            width: width as u16,
+            height: frame_height as u16,
+            data: frame.data,
+        });
+    }
+
+    if let Some(footer) = footer {
+        emit_pattern_params(&ctx, &mut program, pattern_impl, footer);
     }
 
     program.push(Op::Feed { units: 24 });
@@ -670,11 +1580,490 @@ fn build_pattern_program(
     program
 }
 
+/// Put `--density` and `--speed` right after the program's Init.
+fn apply_head_settings(
+    program: &mut estrella::ir::Program,
+    density: Option<i8>,
+    speed: Option<PrintSpeed>,
+) {
+    use estrella::ir::Op;
+
+    let settings = density
+        .map(Op::SetDensity)
+        .into_iter()
+        .chain(speed.map(Op::SetSpeed));
+    program.ops.splice(1..1, settings);
+}
+
+/// The pattern's name in large bold type, with a divider below.
+fn emit_pattern_title(
+    ctx: &document::EmitContext,
+    program: &mut estrella::ir::Program,
+    pattern_impl: &dyn patterns::Pattern,
+) {
+    let title = document::Text {
+        content: pattern_impl.name().to_string(),
+        center: true,
+        bold: true,
+        size: [3, 2],
+        ..Default::default()
+    };
+    title.emit(ctx, &mut program.ops);
+    program.push(estrella::ir::Op::Newline);
+    let divider = document::Divider::default();
+    divider.emit(ctx, &mut program.ops);
+}
+
+/// What the footer under a pattern shows.
+#[derive(Debug, Clone, Copy)]
+struct ParamsFooter {
+    /// Seed the parameters were randomized from.
+    seed: Option<u64>,
+    /// Add the recipe as a QR code, for `estrella reprint`.
+    qr: bool,
+}
+
+/// A divider, the seed and the pattern's parameter values, for reproducing
+/// a print.
+fn emit_pattern_params(
+    ctx: &document::EmitContext,
+    program: &mut estrella::ir::Program,
+    pattern_impl: &dyn patterns::Pattern,
+    footer: ParamsFooter,
+) {
+    let divider = document::Divider::default();
+    divider.emit(ctx, &mut program.ops);
+    let params_list = footer
+        .seed
+        .map(|seed| ("seed", seed.to_string()))
+        .into_iter()
+        .chain(pattern_impl.list_params())
+        .collect::<Vec<_>>();
+    if !params_list.is_empty() {
+        let params_text = params_list
+            .iter()
+            .map(|(k, v)| format!("{}={}", k, v))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let text = document::Text {
+            content: params_text,
+            center: true,
+            size: [0, 0],
+            ..Default::default()
+        };
+        text.emit(ctx, &mut program.ops);
+        program.push(estrella::ir::Op::Newline);
+    }
+    if footer.qr {
+        // Low error correction keeps the symbol small enough to scan
+        let qr = document::QrCode {
+            error_level: Some("L".into()),
+            ..document::QrCode::new(Recipe::of(pattern_impl, footer.seed).payload())
+        };
+        qr.emit(ctx, &mut program.ops);
+    }
+}
+
+/// Draw a program's preview in the terminal (sixel or half blocks).
+fn preview_in_terminal(
+    program: &estrella::ir::Program,
+    mode: TerminalGraphics,
+) -> Result<(), EstrellaError> {
+    let raw = preview::render_raw(program)
+        .map_err(|e| EstrellaError::Image(format!("Failed to render preview: {}", e)))?;
+    let columns = std::env::var("COLUMNS")
+        .ok()
+        .and_then(|c| c.parse().ok())
+        .unwrap_or(80);
+    let mut stdout = io::stdout().lock();
+    stdout.write_all(mode.render(&raw, columns).as_bytes())?;
+    stdout.flush()?;
+    Ok(())
+}
+
+/// Print a batch of barcode labels from a template (or the default label).
+#[allow(clippy::too_many_arguments)]
+fn print_barcode_batch(
+    start: &str,
+    count: usize,
+    format: Option<&str>,
+    template: Option<&PathBuf>,
+    csv: Option<&PathBuf>,
+    optimize: OptimizeLevel,
+    cut: CutPolicy,
+    png: Option<&PathBuf>,
+    preview_term: Option<TerminalGraphics>,
+    device: &str,
+    printer: &PrinterConfig,
+) -> Result<(), EstrellaError> {
+    use document::batch;
+
+    let rows = match csv {
+        Some(path) => batch::csv_rows(&std::fs::read_to_string(path)?)?,
+        None => batch::sequential_rows(start, count)?,
+    };
+    let mut template = match template {
+        Some(path) => read_template(path)?,
+        None => batch::default_template(format.unwrap_or("code128")),
+    };
+    template.optimize = optimize;
+
+    let docs = batch::documents(&template, &rows, format);
+    let program = batch::batch_program_with_cuts(&docs, cut, printer)?;
+
+    if let Some(png_path) = png {
+        let png_bytes = program
+            .to_preview_png_with_config(printer)
+            .map_err(|e| EstrellaError::Image(format!("Failed to render preview: {}", e)))?;
+        std::fs::write(png_path, &png_bytes)
+            .map_err(|e| EstrellaError::Image(format!("Failed to write PNG: {}", e)))?;
+        println!("Saved {} labels to {}", docs.len(), png_path.display());
+        return Ok(());
+    }
+    if let Some(mode) = preview_term {
+        return preview_in_terminal(&program, mode);
+    }
+
+    println!("Printing {} barcode labels...", docs.len());
+    print_program_to_device(device, &program, printer)?;
+    println!("Printed successfully!");
+    Ok(())
+}
+
+/// Print numbered raffle tickets, body and stub, as one job with progress.
+#[allow(clippy::too_many_arguments)]
+fn print_ticket_batch(
+    start: &str,
+    count: usize,
+    body: Option<&PathBuf>,
+    stub: Option<&PathBuf>,
+    optimize: OptimizeLevel,
+    cut: CutPolicy,
+    png: Option<&PathBuf>,
+    preview_term: Option<TerminalGraphics>,
+    device: &str,
+    printer: &PrinterConfig,
+) -> Result<(), EstrellaError> {
+    use document::batch;
+
+    let rows = batch::sequential_rows(start, count)?;
+    let mut body = match body {
+        Some(path) => read_template(path)?,
+        None => batch::ticket_body_template(),
+    };
+    let mut stub = match stub {
+        Some(path) => read_template(path)?,
+        None => batch::ticket_stub_template(),
+    };
+    body.optimize = optimize;
+    stub.optimize = optimize;
+    let docs = batch::ticket_documents(&body, &stub, &rows);
+    // Each ticket is one item: its body always tears off the stub
+    let tickets = docs
+        .chunks(2)
+        .map(|ticket| batch::batch_program_with_config(ticket, printer))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if let Some(png_path) = png {
+        let png_bytes = batch::join_programs(tickets, cut, printer)
+            .to_preview_png_with_config(printer)
+            .map_err(|e| EstrellaError::Image(format!("Failed to render preview: {}", e)))?;
+        std::fs::write(png_path, &png_bytes)
+            .map_err(|e| EstrellaError::Image(format!("Failed to write PNG: {}", e)))?;
+        println!("Saved {} tickets to {}", rows.len(), png_path.display());
+        return Ok(());
+    }
+    if let Some(mode) = preview_term {
+        return preview_in_terminal(&batch::join_programs(tickets, cut, printer), mode);
+    }
+
+    // One connection for the whole batch; each ticket is drained before the
+    // next is sent, so the counter tracks the paper.
+    let mut transport = transport::open(device)?;
+    let count = tickets.len();
+    for (i, mut ticket) in tickets.into_iter().enumerate() {
+        cut.finish(&mut ticket, i, count, printer);
+        transport.send_program(&ticket, printer)?;
+        print!("\rPrinting tickets... {}/{}", i + 1, rows.len());
+        io::stdout().flush()?;
+    }
+    println!("\nPrinted successfully!");
+    Ok(())
+}
+
+/// Load a document JSON file used as a batch template.
+fn read_template(path: &Path) -> Result<document::Document, EstrellaError> {
+    serde_json::from_str(&std::fs::read_to_string(path)?).map_err(|e| {
+        EstrellaError::InvalidCommand(format!("Invalid template {}: {}", path.display(), e))
+    })
+}
+
+/// Send a request to an estrella server and return its JSON answer.
+/// Error answers become an error with the server's message.
+fn server_request(request: reqwest::RequestBuilder) -> Result<serde_json::Value, EstrellaError> {
+    let runtime = tokio::runtime::Runtime::new()
+        .map_err(|e| EstrellaError::Transport(format!("Failed to create tokio runtime: {}", e)))?;
+    runtime.block_on(async {
+        let response = request
+            .send()
+            .await
+            .map_err(|e| EstrellaError::Transport(format!("Server request failed: {}", e)))?;
+        let status = response.status();
+        let body = response
+            .text()
+            .await
+            .map_err(|e| EstrellaError::Transport(format!("Server request failed: {}", e)))?;
+        let json: serde_json::Value = serde_json::from_str(&body).unwrap_or_default();
+        if status.is_success() {
+            return Ok(json);
+        }
+        let message = json["error"].as_str().unwrap_or(body.trim());
+        Err(EstrellaError::Transport(format!(
+            "Server answered {}: {}",
+            status, message
+        )))
+    })
+}
+
+/// A POST request with `body` as JSON.
+fn post_json(url: &str, body: &serde_json::Value) -> reqwest::RequestBuilder {
+    reqwest::Client::new()
+        .post(url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .body(body.to_string())
+}
+
+/// `/api/templates` on `server`, plus `path`.
+fn templates_url(server: &str, path: &str) -> String {
+    format!("{}/api/templates{}", server.trim_end_matches('/'), path)
+}
+
+fn template_list(server: &str) -> Result<(), EstrellaError> {
+    let names = server_request(reqwest::Client::new().get(templates_url(server, "")))?;
+    for name in names.as_array().into_iter().flatten() {
+        if let Some(name) = name.as_str() {
+            println!("{}", name);
+        }
+    }
+    Ok(())
+}
+
+fn template_save(server: &str, name: &str, file: &Path) -> Result<(), EstrellaError> {
+    let document: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(file)?)
+        .map_err(|e| {
+            EstrellaError::InvalidCommand(format!("Invalid document {}: {}", file.display(), e))
+        })?;
+    let url = templates_url(server, &format!("/{}", name));
+    let answer = server_request(post_json(&url, &document))?;
+    for warning in answer["warnings"].as_array().into_iter().flatten() {
+        eprintln!("Warning: {}", warning.as_str().unwrap_or_default());
+    }
+    println!("Saved template '{}'", name);
+    Ok(())
+}
+
+fn template_print(
+    server: &str,
+    name: &str,
+    vars: &[String],
+    vars_file: Option<&Path>,
+    urgent: bool,
+) -> Result<(), EstrellaError> {
+    let mut variables = match vars_file {
+        Some(path) => match serde_json::from_str(&std::fs::read_to_string(path)?) {
+            Ok(serde_json::Value::Object(variables)) => variables,
+            _ => {
+                return Err(EstrellaError::InvalidCommand(format!(
+                    "{} must hold a JSON object of variables",
+                    path.display()
+                )));
+            }
+        },
+        None => serde_json::Map::new(),
+    };
+    for var in vars {
+        let (key, value) = var.split_once('=').ok_or_else(|| {
+            EstrellaError::InvalidCommand(format!(
+                "Invalid variable '{}'. Use name=value (e.g., --var name=Ada)",
+                var
+            ))
+        })?;
+        variables.insert(key.to_string(), value.into());
+    }
+
+    let url = templates_url(server, &format!("/{}/print", name));
+    let body = serde_json::json!({ "variables": variables });
+    let answer = server_request(post_json(&url, &body).query(&[("urgent", urgent)]))?;
+    println!(
+        "{} (job {})",
+        answer["message"].as_str().unwrap_or("Printed"),
+        answer["job_id"].as_str().unwrap_or("?")
+    );
+    Ok(())
+}
+
+/// Format JSON documents in place, or report unformatted ones with `check`.
+fn format_documents(files: &[PathBuf], compact: bool, check: bool) -> Result<(), EstrellaError> {
+    let mut unformatted = Vec::new();
+    for path in files {
+        let input = std::fs::read_to_string(path)?;
+        let formatted = document::format::format_document(&input, compact)
+            .map_err(|e| EstrellaError::InvalidCommand(format!("{}: {}", path.display(), e)))?;
+        for warning in &formatted.warnings {
+            eprintln!("{}: {}", path.display(), warning);
+        }
+        if formatted.output == input {
+            continue;
+        }
+        if check {
+            println!("Would reformat {}", path.display());
+            unformatted.push(path);
+        } else {
+            std::fs::write(path, &formatted.output)?;
+            println!("Formatted {}", path.display());
+        }
+    }
+
+    if !unformatted.is_empty() {
+        return Err(EstrellaError::InvalidCommand(format!(
+            "{} file(s) need formatting",
+            unformatted.len()
+        )));
+    }
+    Ok(())
+}
+
+/// Compile a pattern, receipt or document file and print its op listing
+/// and a hexdump of its bytes.
+fn dump_target(
+    target: &str,
+    printer: &PrinterConfig,
+    optimize: Option<OptimizeLevel>,
+    height: Option<usize>,
+    bytes_path: Option<&Path>,
+) -> Result<(), EstrellaError> {
+    use estrella::ir::analyze::{disassemble, hexdump};
+
+    let path = Path::new(target);
+    let program = if target.ends_with(".json") || path.is_file() {
+        let mut doc = read_template(path)?;
+        if let Some(level) = optimize {
+            doc.optimize = level;
+        }
+        doc.print_program_with_config(printer)?
+    } else if let Some(mut doc) = receipt::document_by_name(target) {
+        doc.optimize = optimize.unwrap_or_default();
+        doc.print_program_with_config(printer)?
+    } else {
+        let pattern_impl = patterns::by_name_golden(target).ok_or_else(|| {
+            EstrellaError::Pattern(format!(
+                "Unknown pattern, receipt or file '{}'. Run `estrella print` to see available options.",
+                target
+            ))
+        })?;
+        let (default_width, default_height) = pattern_impl.default_dimensions();
+        build_pattern_program(
+            pattern_impl.as_ref(),
+            printer,
+            default_width.min(printer.width_dots as usize),
+            height.unwrap_or(default_height),
+            &dither::DitherSpec::default(),
+            PrintQuality::Normal,
+            true,
+            false,
+            None,
+        )
+        .optimize_with_config(optimize.unwrap_or_default(), printer)
+    };
+    let bytes = program.to_bytes_with_config(printer);
+
+    // The listing is usually piped into `less`, `head` or `diff`
+    let mut stdout = io::stdout().lock();
+    let listed = writeln!(
+        stdout,
+        "; {} for {}: {} ops, {} bytes",
+        target,
+        printer.name,
+        program.ops.len(),
+        bytes.len()
+    )
+    .and_then(|()| writeln!(stdout, "{}", disassemble(&program)))
+    .and_then(|()| write!(stdout, "{}", hexdump(&bytes)));
+    match listed {
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => {}
+        other => other?,
+    }
+
+    if let Some(bytes_path) = bytes_path {
+        std::fs::write(bytes_path, &bytes)?;
+        eprintln!("Wrote {} bytes to {}", bytes.len(), bytes_path.display());
+    }
+    Ok(())
+}
+
+/// Render a JSON document for an e-paper panel and save or send it.
+fn display_document(
+    path: &Path,
+    panel: &str,
+    portrait: bool,
+    bmp: Option<PathBuf>,
+    device: Option<PathBuf>,
+) -> Result<(), EstrellaError> {
+    use estrella::output::{BmpFile, EpaperFrame, EpaperPanel, OutputTarget};
+
+    let mut panel = EpaperPanel::by_name(panel).ok_or_else(|| {
+        let names: Vec<&str> = EpaperPanel::ALL.iter().map(|p| p.name).collect();
+        EstrellaError::InvalidCommand(format!(
+            "Unknown panel '{}'. Available: {}",
+            panel,
+            names.join(", ")
+        ))
+    })?;
+    if portrait {
+        panel = panel.portrait();
+    }
+    if bmp.is_none() && device.is_none() {
+        return Err(EstrellaError::InvalidCommand(
+            "Specify --bmp FILE and/or --device PATH".into(),
+        ));
+    }
+
+    let doc = read_template(path)?;
+    if let Some(bmp) = bmp {
+        BmpFile::new(&bmp, panel).show(&doc)?;
+        println!(
+            "Saved {}x{} BMP to {}",
+            panel.width,
+            panel.height,
+            bmp.display()
+        );
+    }
+    if let Some(device) = device {
+        EpaperFrame::new(&device, panel).show(&doc)?;
+        println!("Sent frame to {}", device.display());
+    }
+    Ok(())
+}
+
+/// Stream a compiled program to the printer device
+fn print_program_to_device(
+    device: &str,
+    program: &estrella::ir::Program,
+    config: &PrinterConfig,
+) -> Result<(), EstrellaError> {
+    let mut printer = transport::open(device)?;
+    printer.send_program(program, config)
+}
+
 /// Print raw command data to the printer device
 fn print_raw_to_device(device: &str, data: &[u8]) -> Result<(), EstrellaError> {
-    let mut transport = BluetoothTransport::open(device)?;
-    transport.write_all(data)?;
-    Ok(())
+    send_raw(transport::open(device)?.as_mut(), data)
+}
+
+/// Write raw command data to an open printer and wait for it to go out.
+fn send_raw(printer: &mut dyn Transport, data: &[u8]) -> Result<(), EstrellaError> {
+    printer.write_all(data)?;
+    printer.flush()
 }
 
 /// Set up RFCOMM device for a Bluetooth MAC address.
@@ -730,27 +2119,18 @@ fn setup_rfcomm_command(
     }
 }
 
-/// Parse a length string like "15mm" or "62.5mm" and convert to height in dots.
-fn parse_length_mm(length: &str) -> Result<usize, EstrellaError> {
-    let length = length.trim().to_lowercase();
-    let mm_str = length.strip_suffix("mm").ok_or_else(|| {
-        EstrellaError::Pattern(format!(
-            "Invalid length format '{}'. Use format like '15mm' or '62.5mm'",
-            length
-        ))
-    })?;
-    let mm: f32 = mm_str.parse().map_err(|_| {
-        EstrellaError::Pattern(format!(
-            "Invalid length value '{}'. Use format like '15mm' or '62.5mm'",
-            length
-        ))
-    })?;
-    if mm <= 0.0 {
-        return Err(EstrellaError::Pattern(
-            "Length must be positive".to_string(),
-        ));
-    }
-    Ok(PrinterConfig::TSP650II.mm_to_dots(mm) as usize)
+/// A length given on the command line (print height, band, gap), in dots.
+fn positive_dots(length: Length, printer: &PrinterConfig) -> Result<usize, EstrellaError> {
+    length
+        .to_positive_dots(printer)
+        .map_err(EstrellaError::Pattern)
+}
+
+/// A length given on the command line, in millimeters.
+fn positive_mm(length: Length, printer: &PrinterConfig) -> Result<f32, EstrellaError> {
+    length
+        .to_positive_mm(printer)
+        .map_err(EstrellaError::Pattern)
 }
 
 /// Print a receipt as a full-page raster (no margins, 576px wide).
@@ -761,17 +2141,21 @@ fn print_as_raster(
     name: &str,
     png_path: Option<&PathBuf>,
     device: &str,
+    printer: &PrinterConfig,
 ) -> Result<(), EstrellaError> {
     use image::{GrayImage, Luma};
 
-    println!("Rendering {} as raster (576px, no margins)...", name);
+    println!(
+        "Rendering {} as raster ({}px, no margins)...",
+        name, printer.width_dots
+    );
 
     // Get the program for this receipt
     let program = receipt::program_by_name(name)
         .ok_or_else(|| EstrellaError::Pattern(format!("Unknown receipt '{}'", name)))?;
 
     // Render to raw pixel buffer (no margins)
-    let raw = preview::render_raw(&program)
+    let raw = preview::render_raw_with_config(&program, printer)
         .map_err(|e| EstrellaError::Image(format!("Failed to render: {}", e)))?;
 
     println!(
@@ -818,9 +2202,8 @@ fn print_as_raster(
         program.push(Op::Feed { units: 24 }); // 6mm
         program.push(Op::Cut { partial: false });
 
-        // Compile to bytes (chunking happens here)
-        let print_data = program.to_bytes();
-        print_raw_to_device(device, &print_data)?;
+        // Stream to the printer (codegen handles chunking)
+        print_program_to_device(device, &program, printer)?;
 
         println!("Printed successfully!");
     }
@@ -868,6 +2251,7 @@ fn logo_sync(device: &str, key: Option<&str>) -> Result<(), EstrellaError> {
         return Ok(());
     }
 
+    let mut printer = transport::open(device)?;
     for logo in logos_to_sync {
         let raster = logo.raster();
         let cmd = nv_graphics::define(logo.key, raster.width, raster.height, &raster.data)
@@ -889,7 +2273,7 @@ fn logo_sync(device: &str, key: Option<&str>) -> Result<(), EstrellaError> {
 
         let mut data = commands::init();
         data.extend(cmd);
-        print_raw_to_device(device, &data)?;
+        send_raw(printer.as_mut(), &data)?;
     }
 
     println!("Sync complete!");
@@ -1095,11 +2479,12 @@ fn logo_delete_all(device: &str, force: bool) -> Result<(), EstrellaError> {
 #[allow(clippy::too_many_arguments)]
 fn weave_patterns(
     pattern_names: &[String],
-    length: &str,
-    crossfade: &str,
+    length: Length,
+    crossfade: Length,
     curve: &str,
     png_path: Option<&PathBuf>,
     device: &str,
+    printer: &PrinterConfig,
     width: usize,
     golden: bool,
     dither_name: &str,
@@ -1113,8 +2498,8 @@ fn weave_patterns(
     }
 
     // Parse length and crossfade
-    let height = parse_length_mm(length)?;
-    let crossfade_pixels = parse_length_mm(crossfade)?;
+    let height = positive_dots(length, printer)?;
+    let crossfade_pixels = positive_dots(crossfade, printer)?;
 
     // Parse blend curve
     let blend_curve = BlendCurve::from_str(curve).ok_or_else(|| {
@@ -1124,20 +2509,8 @@ fn weave_patterns(
         ))
     })?;
 
-    // Parse dithering algorithm
-    let dither_algo = match dither_name.to_lowercase().as_str() {
-        "none" | "threshold" => dither::DitheringAlgorithm::None,
-        "bayer" => dither::DitheringAlgorithm::Bayer,
-        "floyd-steinberg" | "floyd_steinberg" | "fs" => dither::DitheringAlgorithm::FloydSteinberg,
-        "atkinson" => dither::DitheringAlgorithm::Atkinson,
-        "jarvis" | "jjn" => dither::DitheringAlgorithm::Jarvis,
-        _ => {
-            return Err(EstrellaError::Pattern(format!(
-                "Unknown dithering algorithm '{}'. Use 'none', 'bayer', 'floyd-steinberg', 'atkinson', or 'jarvis'",
-                dither_name
-            )));
-        }
-    };
+    // Parse dithering algorithm and options (e.g. fs:serpentine)
+    let dither_spec: dither::DitherSpec = dither_name.parse().map_err(EstrellaError::Pattern)?;
 
     // Load patterns
     let mut pattern_impls: Vec<Box<dyn patterns::Pattern>> = Vec::new();
@@ -1174,11 +2547,12 @@ fn weave_patterns(
     println!("  Patterns: {}", pattern_names.join(" -> "));
 
     // Render using the dithering module's generate_raster
-    let raster_data = dither::generate_raster(
+    let raster_data = dither::generate_raster_with_options(
         width,
         height,
         |x, y, w, h| weave.intensity(x, y, w, h),
-        dither_algo,
+        dither_spec.algorithm,
+        &dither_spec.options,
     );
     let width_bytes = width.div_ceil(8);
 
@@ -1213,8 +2587,7 @@ fn weave_patterns(
         program.push(Op::Feed { units: 24 }); // 6mm
         program.push(Op::Cut { partial: false });
 
-        let print_data = program.to_bytes();
-        print_raw_to_device(device, &print_data)?;
+        print_program_to_device(device, &program, printer)?;
         println!("Printed successfully!");
     }
 
@@ -0,0 +1,263 @@
+//! # Stage Timings
+//!
+//! How long compiling, optimizing, code generation and preview rendering
+//! take, as Prometheus histograms. Each stage is broken down by what it
+//! was working on, so a slow pattern or optimizer pass stands out:
+//!
+//! | Metric | Label | Timed per |
+//! |--------|-------|-----------|
+//! | `estrella_compile_duration_seconds` | `component` | component emitted |
+//! | `estrella_optimize_duration_seconds` | `pass` | optimizer pass |
+//! | `estrella_codegen_duration_seconds` | `op` | op written as bytes |
+//! | `estrella_preview_duration_seconds` | `op` | op drawn in a preview |
+//!
+//! Recording is off until [`enable`] is called (`estrella serve` does, and
+//! serves [`render`] on `/metrics`), so the CLI pays one atomic load per
+//! timed step.
+//!
+//! ```
+//! use estrella::metrics::{Registry, Stage};
+//! use std::time::Duration;
+//!
+//! let registry = Registry::new();
+//! registry.observe(Stage::Codegen, "raster", Duration::from_millis(3));
+//! assert!(registry.render().contains(
+//!     r#"estrella_codegen_duration_seconds_bucket{op="raster",le="0.005"} 1"#
+//! ));
+//! ```
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+/// Upper bounds of the histogram buckets, in seconds: 10µs to 5s. Most
+/// ops take microseconds; pattern renders and big rasters take seconds.
+pub const BUCKETS: [f64; 12] = [
+    0.000_01, 0.000_05, 0.000_1, 0.000_5, 0.001, 0.005, 0.01, 0.05, 0.1, 0.5, 1.0, 5.0,
+];
+
+/// A timed stage of turning a document into paper (or pixels).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Stage {
+    /// Emitting IR ops, per component type.
+    Compile,
+    /// Optimizing the IR, per pass.
+    Optimize,
+    /// Writing StarPRNT bytes, per op type.
+    Codegen,
+    /// Drawing a preview, per op type.
+    Preview,
+}
+
+impl Stage {
+    /// All stages, in pipeline order.
+    pub const ALL: [Stage; 4] = [
+        Stage::Compile,
+        Stage::Optimize,
+        Stage::Codegen,
+        Stage::Preview,
+    ];
+
+    /// Prometheus metric name.
+    pub fn metric(self) -> &'static str {
+        match self {
+            Stage::Compile => "estrella_compile_duration_seconds",
+            Stage::Optimize => "estrella_optimize_duration_seconds",
+            Stage::Codegen => "estrella_codegen_duration_seconds",
+            Stage::Preview => "estrella_preview_duration_seconds",
+        }
+    }
+
+    /// Name of the label that breaks the stage down.
+    pub fn label(self) -> &'static str {
+        match self {
+            Stage::Compile => "component",
+            Stage::Optimize => "pass",
+            Stage::Codegen | Stage::Preview => "op",
+        }
+    }
+
+    fn help(self) -> &'static str {
+        match self {
+            Stage::Compile => "Time to emit IR ops for one component.",
+            Stage::Optimize => "Time for one optimizer pass over a program.",
+            Stage::Codegen => "Time to write one op as StarPRNT bytes.",
+            Stage::Preview => "Time to draw one op in a preview.",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct Histogram {
+    /// Observations per bucket (not cumulative); the last is `+Inf`.
+    buckets: [u64; BUCKETS.len() + 1],
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn observe(&mut self, seconds: f64) {
+        let bucket = BUCKETS
+            .iter()
+            .position(|&bound| seconds <= bound)
+            .unwrap_or(BUCKETS.len());
+        self.buckets[bucket] += 1;
+        self.sum += seconds;
+        self.count += 1;
+    }
+}
+
+/// A set of stage histograms.
+#[derive(Debug, Default)]
+pub struct Registry {
+    histograms: Mutex<BTreeMap<(Stage, &'static str), Histogram>>,
+}
+
+impl Registry {
+    /// An empty registry.
+    pub const fn new() -> Self {
+        Registry {
+            histograms: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Record one step of `stage`, labelled `label`, taking `elapsed`.
+    pub fn observe(&self, stage: Stage, label: &'static str, elapsed: Duration) {
+        let mut histograms = self.histograms.lock().unwrap_or_else(|e| e.into_inner());
+        histograms
+            .entry((stage, label))
+            .or_default()
+            .observe(elapsed.as_secs_f64());
+    }
+
+    /// Everything recorded so far, in the Prometheus text format.
+    pub fn render(&self) -> String {
+        let histograms = self.histograms.lock().unwrap_or_else(|e| e.into_inner());
+        let mut out = String::new();
+        for stage in Stage::ALL {
+            let metric = stage.metric();
+            let _ = writeln!(out, "# HELP {} {}", metric, stage.help());
+            let _ = writeln!(out, "# TYPE {} histogram", metric);
+            for ((_, label), histogram) in histograms
+                .range((stage, "")..)
+                .take_while(|((s, _), _)| *s == stage)
+            {
+                let label = format!("{}=\"{}\"", stage.label(), escape(label));
+                let mut cumulative = 0;
+                for (bound, count) in BUCKETS.iter().zip(&histogram.buckets) {
+                    cumulative += count;
+                    let _ = writeln!(
+                        out,
+                        "{}_bucket{{{},le=\"{}\"}} {}",
+                        metric, label, bound, cumulative
+                    );
+                }
+                let _ = writeln!(
+                    out,
+                    "{}_bucket{{{},le=\"+Inf\"}} {}",
+                    metric, label, histogram.count
+                );
+                let _ = writeln!(out, "{}_sum{{{}}} {}", metric, label, histogram.sum);
+                let _ = writeln!(out, "{}_count{{{}}} {}", metric, label, histogram.count);
+            }
+        }
+        out
+    }
+}
+
+/// Escape a label value: backslash, double quote and newline.
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static GLOBAL: Registry = Registry::new();
+
+/// Start recording into the process-wide registry.
+pub fn enable() {
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+/// Whether [`enable`] has been called.
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Run `f`, recording how long it took if recording is enabled.
+pub fn timed<T>(stage: Stage, label: &'static str, f: impl FnOnce() -> T) -> T {
+    if !is_enabled() {
+        return f();
+    }
+    let start = Instant::now();
+    let result = f();
+    GLOBAL.observe(stage, label, start.elapsed());
+    result
+}
+
+/// The process-wide registry in the Prometheus text format.
+pub fn render() -> String {
+    GLOBAL.render()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_buckets_are_cumulative() {
+        let registry = Registry::new();
+        registry.observe(Stage::Preview, "text", Duration::from_micros(5));
+        registry.observe(Stage::Preview, "text", Duration::from_millis(2));
+        registry.observe(Stage::Preview, "text", Duration::from_secs(10));
+
+        let text = registry.render();
+        let line = |le: &str| {
+            format!(
+                "estrella_preview_duration_seconds_bucket{{op=\"text\",le=\"{}\"}}",
+                le
+            )
+        };
+        assert!(text.contains(&format!("{} 1\n", line("0.00001"))));
+        assert!(text.contains(&format!("{} 1\n", line("0.001"))));
+        assert!(text.contains(&format!("{} 2\n", line("0.005"))));
+        assert!(text.contains(&format!("{} 2\n", line("5"))));
+        assert!(text.contains(&format!("{} 3\n", line("+Inf"))));
+        assert!(text.contains("estrella_preview_duration_seconds_count{op=\"text\"} 3\n"));
+    }
+
+    #[test]
+    fn test_render_groups_by_stage() {
+        let registry = Registry::new();
+        registry.observe(Stage::Optimize, "merge_adjacent_text", Duration::ZERO);
+        registry.observe(Stage::Compile, "Line \"Item\"", Duration::ZERO);
+        registry.observe(Stage::Codegen, "raster", Duration::ZERO);
+
+        let text = registry.render();
+        // Every stage is declared, even with nothing recorded yet
+        for stage in Stage::ALL {
+            assert!(text.contains(&format!("# TYPE {} histogram\n", stage.metric())));
+        }
+        assert!(
+            text.contains(
+                r#"estrella_compile_duration_seconds_count{component="Line \"Item\""} 1"#
+            )
+        );
+        assert!(
+            text.contains(
+                r#"estrella_optimize_duration_seconds_count{pass="merge_adjacent_text"} 1"#
+            )
+        );
+        // Series sit under their own metric's TYPE line
+        let codegen = text.find("# TYPE estrella_codegen").unwrap();
+        let preview = text.find("# TYPE estrella_preview").unwrap();
+        let raster = text
+            .find("estrella_codegen_duration_seconds_count{op=\"raster\"}")
+            .unwrap();
+        assert!(codegen < raster && raster < preview);
+    }
+}
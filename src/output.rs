@@ -0,0 +1,304 @@
+//! # Output Targets
+//!
+//! Documents normally end up on the thermal printer, but the bitmap the
+//! printer would print (see [`Document::to_raster`]) can drive any 1-bit
+//! display. An [`OutputTarget`] lays the document out at its own width,
+//! through the same layout and dithering pipeline, and presents the result.
+//!
+//! ## Available Targets
+//!
+//! - [`BmpFile`]: a 1-bit BMP sized for an e-paper panel
+//! - [`EpaperFrame`]: the panel's packed framebuffer, written to a device
+//!   node, FIFO or file read by the panel driver
+//!
+//! ## Example
+//!
+//! ```no_run
+//! use estrella::document::{Component, Document, Text};
+//! use estrella::output::{BmpFile, EpaperPanel, OutputTarget};
+//!
+//! # fn example() -> Result<(), estrella::EstrellaError> {
+//! let mut doc = Document::new();
+//! doc.push(Component::Text(Text::new("Soup of the day: lentil")));
+//!
+//! let panel = EpaperPanel::by_name("waveshare-7in5").unwrap().portrait();
+//! BmpFile::new("dashboard.bmp", panel).show(&doc)?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::path::PathBuf;
+
+use crate::EstrellaError;
+use crate::document::Document;
+use crate::preview::RawRaster;
+use crate::printer::PrinterConfig;
+
+/// Something a rendered document can be shown on.
+pub trait OutputTarget {
+    /// Printer model the document is laid out for (print width, DPI).
+    fn config(&self) -> PrinterConfig;
+
+    /// Present a raster rendered at [`OutputTarget::config`].
+    fn present(&mut self, raster: &RawRaster) -> Result<(), EstrellaError>;
+
+    /// Render a document and present it.
+    fn show(&mut self, doc: &Document) -> Result<(), EstrellaError> {
+        let raster = doc
+            .to_raster(&self.config())
+            .map_err(|e| EstrellaError::Image(format!("Render failed: {}", e)))?;
+        self.present(&raster)
+    }
+}
+
+// ============================================================================
+// PANELS
+// ============================================================================
+
+/// Size and resolution of an e-paper panel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EpaperPanel {
+    /// Panel name, as accepted by [`EpaperPanel::by_name`]
+    pub name: &'static str,
+    /// Width in pixels (the document's print width)
+    pub width: u16,
+    /// Height in pixels (longer documents are cropped)
+    pub height: u16,
+    /// Approximate resolution in dots per inch
+    pub dpi: u16,
+}
+
+impl EpaperPanel {
+    /// Waveshare 2.9" (296×128).
+    pub const WAVESHARE_2IN9: Self = Self::new("waveshare-2in9", 296, 128, 112);
+    /// Waveshare 4.2" (400×300).
+    pub const WAVESHARE_4IN2: Self = Self::new("waveshare-4in2", 400, 300, 120);
+    /// Waveshare 7.5" V2 (800×480).
+    pub const WAVESHARE_7IN5: Self = Self::new("waveshare-7in5", 800, 480, 125);
+    /// Inkplate 6 (800×600).
+    pub const INKPLATE_6: Self = Self::new("inkplate-6", 800, 600, 167);
+
+    /// All built-in panels.
+    pub const ALL: &[Self] = &[
+        Self::WAVESHARE_2IN9,
+        Self::WAVESHARE_4IN2,
+        Self::WAVESHARE_7IN5,
+        Self::INKPLATE_6,
+    ];
+
+    const fn new(name: &'static str, width: u16, height: u16, dpi: u16) -> Self {
+        Self {
+            name,
+            width,
+            height,
+            dpi,
+        }
+    }
+
+    /// Look up a built-in panel by name.
+    pub fn by_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|panel| panel.name == name)
+    }
+
+    /// The same panel mounted on its side, which suits tall receipts.
+    pub fn portrait(self) -> Self {
+        Self {
+            width: self.height.min(self.width),
+            height: self.height.max(self.width),
+            ..self
+        }
+    }
+
+    /// Printer config that lays documents out at the panel's width.
+    pub fn printer_config(&self) -> PrinterConfig {
+        PrinterConfig {
+            name: self.name,
+            width_dots: self.width,
+            width_bytes: self.width.div_ceil(8),
+            dpi: self.dpi,
+            ..PrinterConfig::TSP650II
+        }
+    }
+
+    /// Crop or pad a raster to exactly the panel size (white padding).
+    pub fn fit(&self, raster: &RawRaster) -> RawRaster {
+        let (width, height) = (self.width as usize, self.height as usize);
+        let width_bytes = width.div_ceil(8);
+        let src_bytes = raster.width.div_ceil(8);
+        let mut data = vec![0u8; width_bytes * height];
+
+        for y in 0..height.min(raster.height) {
+            let src = &raster.data[y * src_bytes..(y + 1) * src_bytes];
+            for x in 0..width.min(raster.width) {
+                if src[x / 8] & (0x80 >> (x % 8)) != 0 {
+                    data[y * width_bytes + x / 8] |= 0x80 >> (x % 8);
+                }
+            }
+        }
+
+        RawRaster {
+            width,
+            height,
+            data,
+        }
+    }
+}
+
+// ============================================================================
+// TARGETS
+// ============================================================================
+
+/// Save documents as 1-bit BMP files sized for a panel.
+pub struct BmpFile {
+    path: PathBuf,
+    panel: EpaperPanel,
+}
+
+impl BmpFile {
+    pub fn new(path: impl Into<PathBuf>, panel: EpaperPanel) -> Self {
+        Self {
+            path: path.into(),
+            panel,
+        }
+    }
+}
+
+impl OutputTarget for BmpFile {
+    fn config(&self) -> PrinterConfig {
+        self.panel.printer_config()
+    }
+
+    fn present(&mut self, raster: &RawRaster) -> Result<(), EstrellaError> {
+        std::fs::write(&self.path, encode_bmp(&self.panel.fit(raster)))?;
+        Ok(())
+    }
+}
+
+/// Write a panel's packed framebuffer to a device or file.
+///
+/// Rows are MSB-first, one bit per pixel. Most e-paper controllers use
+/// 1 = white, so the raster's 1 = black is inverted by default.
+pub struct EpaperFrame {
+    path: PathBuf,
+    panel: EpaperPanel,
+    /// Send 1 = black instead of the usual 1 = white.
+    pub black_is_one: bool,
+}
+
+impl EpaperFrame {
+    pub fn new(path: impl Into<PathBuf>, panel: EpaperPanel) -> Self {
+        Self {
+            path: path.into(),
+            panel,
+            black_is_one: false,
+        }
+    }
+
+    /// The framebuffer bytes for a raster.
+    pub fn frame(&self, raster: &RawRaster) -> Vec<u8> {
+        let mut data = self.panel.fit(raster).data;
+        if !self.black_is_one {
+            data.iter_mut().for_each(|b| *b = !*b);
+        }
+        data
+    }
+}
+
+impl OutputTarget for EpaperFrame {
+    fn config(&self) -> PrinterConfig {
+        self.panel.printer_config()
+    }
+
+    fn present(&mut self, raster: &RawRaster) -> Result<(), EstrellaError> {
+        std::fs::write(&self.path, self.frame(raster))?;
+        Ok(())
+    }
+}
+
+/// Encode a raster as a 1-bit BMP (black and white palette).
+pub fn encode_bmp(raster: &RawRaster) -> Vec<u8> {
+    const HEADER_SIZE: u32 = 14 + 40 + 8;
+
+    let src_bytes = raster.width.div_ceil(8);
+    // BMP rows are padded to 4 bytes and stored bottom-up
+    let row_bytes = src_bytes.div_ceil(4) * 4;
+    let image_size = (row_bytes * raster.height) as u32;
+
+    let mut out = Vec::with_capacity((HEADER_SIZE + image_size) as usize);
+    out.extend_from_slice(b"BM");
+    out.extend_from_slice(&(HEADER_SIZE + image_size).to_le_bytes());
+    out.extend_from_slice(&[0; 4]);
+    out.extend_from_slice(&HEADER_SIZE.to_le_bytes());
+
+    out.extend_from_slice(&40u32.to_le_bytes());
+    out.extend_from_slice(&(raster.width as i32).to_le_bytes());
+    out.extend_from_slice(&(raster.height as i32).to_le_bytes());
+    out.extend_from_slice(&1u16.to_le_bytes()); // planes
+    out.extend_from_slice(&1u16.to_le_bytes()); // bits per pixel
+    out.extend_from_slice(&0u32.to_le_bytes()); // no compression
+    out.extend_from_slice(&image_size.to_le_bytes());
+    out.extend_from_slice(&[0; 8]); // resolution (unspecified)
+    out.extend_from_slice(&2u32.to_le_bytes()); // palette entries
+    out.extend_from_slice(&0u32.to_le_bytes());
+
+    // Palette: index 0 = white, 1 = black, matching the raster's bits
+    out.extend_from_slice(&[0xFF, 0xFF, 0xFF, 0x00, 0x00, 0x00, 0x00, 0x00]);
+
+    for y in (0..raster.height).rev() {
+        out.extend_from_slice(&raster.data[y * src_bytes..(y + 1) * src_bytes]);
+        out.resize(out.len() + row_bytes - src_bytes, 0);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document::{Component, Text};
+
+    #[test]
+    fn test_panel_fit_and_portrait() {
+        let panel = EpaperPanel::by_name("waveshare-4in2").unwrap().portrait();
+        assert_eq!((panel.width, panel.height), (300, 400));
+
+        let mut doc = Document::new();
+        doc.push(Component::Text(Text::new("EINK")));
+        let raster = doc.to_raster(&panel.printer_config()).unwrap();
+        assert_eq!(raster.width, 300);
+
+        let fitted = panel.fit(&raster);
+        assert_eq!((fitted.width, fitted.height), (300, 400));
+        assert_eq!(fitted.data.len(), 38 * 400);
+        assert_eq!(&fitted.data[..38], &raster.data[..38]);
+    }
+
+    #[test]
+    fn test_bmp_layout() {
+        let raster = RawRaster {
+            width: 10,
+            height: 2,
+            data: vec![0x80, 0x00, 0x00, 0x40],
+        };
+        let bmp = encode_bmp(&raster);
+        assert_eq!(&bmp[..2], b"BM");
+        assert_eq!(bmp.len(), 62 + 4 * 2);
+        // Bottom row first
+        assert_eq!(&bmp[62..66], &[0x00, 0x40, 0, 0]);
+        assert_eq!(&bmp[66..70], &[0x80, 0x00, 0, 0]);
+    }
+
+    #[test]
+    fn test_epaper_frame_inverts_by_default() {
+        let panel = EpaperPanel::WAVESHARE_2IN9;
+        let frame = EpaperFrame::new("/dev/null", panel);
+        let raster = RawRaster {
+            width: 296,
+            height: 1,
+            data: vec![0xFF; 37],
+        };
+        let data = frame.frame(&raster);
+        assert_eq!(data.len(), 37 * 128);
+        assert_eq!(data[0], 0x00);
+        assert_eq!(data[37], 0xFF);
+    }
+}
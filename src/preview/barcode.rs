@@ -1,10 +1,144 @@
 //! Barcode encoding for preview rendering.
 //!
-//! Uses the barcoders crate for Code 39 and Code 128 encoding.
+//! Uses the barcoders crate for Code 39 and Code 128. EAN-13, UPC-A, ITF,
+//! Code 93 and NW-7 are encoded here from their symbology tables.
+//!
+//! Each module is drawn as several dots, matching the module width the
+//! printer uses for that symbology (see [`module_width`]), so previews have
+//! the same size as the printed barcode. Like the printer, the preview
+//! draws the human-readable (HRI) text in Font A under the bars. The same
+//! rendering backs the raster fallback for printing barcodes as graphics
+//! (see [`raster`]).
 
 use barcoders::sym::code39::Code39;
 use barcoders::sym::code128::Code128;
 
+use super::font::{FontMetrics, generate_glyph};
+use crate::ir::BarcodeKind;
+use crate::protocol::barcode::barcode1d::HriPosition;
+use crate::protocol::text::Font;
+
+/// Blank dots between the bars and the HRI text.
+pub const HRI_GAP: usize = 4;
+
+/// Dots per module, matching the `ModuleWidth` codegen uses for each kind.
+pub fn module_width(kind: BarcodeKind) -> usize {
+    // ModuleWidth::Dots2 is 1, Dots3 is 2, ...
+    kind.module_width() as usize + 1
+}
+
+/// Encode data as barcode bars for any supported symbology.
+/// Returns a Vec<bool> where true = bar (black), false = space (white).
+/// Empty if the data can't be encoded.
+pub fn encode(kind: BarcodeKind, data: &str) -> Vec<bool> {
+    match kind {
+        BarcodeKind::Code39 => encode_code39(data),
+        BarcodeKind::Code128 => encode_code128(data),
+        BarcodeKind::Ean13 => encode_ean13(data),
+        BarcodeKind::UpcA => encode_upca(data),
+        BarcodeKind::Itf => encode_itf(data),
+        BarcodeKind::Code93 => encode_code93(data),
+        BarcodeKind::Nw7 => encode_nw7(data),
+    }
+}
+
+/// The human-readable text printed with a barcode.
+///
+/// EAN-13 and UPC-A show all digits, including a check digit the printer
+/// calculated; other symbologies show the data as given.
+pub fn hri_text(kind: BarcodeKind, data: &str) -> String {
+    let complete = match kind {
+        BarcodeKind::Ean13 => ean_digits(data, 13),
+        BarcodeKind::UpcA => ean_digits(data, 12),
+        _ => None,
+    };
+    match complete {
+        Some(digits) => digits.iter().map(|d| (b'0' + d) as char).collect(),
+        None => data.to_string(),
+    }
+}
+
+/// A barcode rendered to packed 1-bit raster data (MSB first, 1 = black).
+#[derive(Debug, Clone)]
+pub struct BarcodeRaster {
+    pub width: usize,
+    pub height: usize,
+    pub data: Vec<u8>,
+}
+
+/// Render a barcode with bars `bar_height` rows tall, followed by its HRI
+/// text unless `hri` is [`HriPosition::None`]. The printer has no HRI
+/// above the bars, so every other position draws it below.
+///
+/// Returns `None` if the data can't be encoded.
+pub fn raster(
+    kind: BarcodeKind,
+    data: &str,
+    bar_height: usize,
+    hri: HriPosition,
+) -> Option<BarcodeRaster> {
+    let bars = encode(kind, data);
+    if bars.is_empty() {
+        return None;
+    }
+
+    let text: Vec<char> = match hri {
+        HriPosition::None => Vec::new(),
+        _ => hri_text(kind, data).chars().collect(),
+    };
+    let metrics = FontMetrics::FONT_A;
+    let text_width = text.len() * metrics.char_width;
+    let width = bars.len().max(text_width);
+    let height = if text.is_empty() {
+        bar_height
+    } else {
+        bar_height + HRI_GAP + metrics.char_height
+    };
+
+    let width_bytes = width.div_ceil(8);
+    let mut data = vec![0u8; width_bytes * height];
+    let mut set = |x: usize, y: usize| data[y * width_bytes + x / 8] |= 0x80 >> (x % 8);
+
+    // Bars and text are each centered
+    let bars_x = (width - bars.len()) / 2;
+    for (x, _) in bars.iter().enumerate().filter(|(_, bar)| **bar) {
+        for y in 0..bar_height {
+            set(bars_x + x, y);
+        }
+    }
+    let text_x = (width - text_width) / 2;
+    let text_y = bar_height + HRI_GAP;
+    for (i, &ch) in text.iter().enumerate() {
+        let glyph = generate_glyph(Font::A, ch);
+        for gy in 0..metrics.char_height {
+            for gx in 0..metrics.char_width {
+                if glyph.get(gy * metrics.char_width + gx) == Some(&1) {
+                    set(text_x + i * metrics.char_width + gx, text_y + gy);
+                }
+            }
+        }
+    }
+
+    Some(BarcodeRaster {
+        width,
+        height,
+        data,
+    })
+}
+
+/// Expand modules (true = bar) to `scale` dots each.
+fn scale_modules(modules: impl IntoIterator<Item = bool>, scale: usize) -> Vec<bool> {
+    modules
+        .into_iter()
+        .flat_map(|is_bar| std::iter::repeat_n(is_bar, scale))
+        .collect()
+}
+
+/// Append a pattern written as a string of `'1'` (bar) and `'0'` (space).
+fn push_pattern(modules: &mut Vec<bool>, pattern: &str) {
+    modules.extend(pattern.bytes().map(|b| b == b'1'));
+}
+
 /// Encode data as Code 39 barcode bars.
 /// Returns a Vec<bool> where true = bar (black), false = space (white).
 pub fn encode_code39(data: &str) -> Vec<bool> {
@@ -14,18 +148,10 @@ pub fn encode_code39(data: &str) -> Vec<bool> {
     };
 
     let encoded = barcode.encode();
-
-    // Scale up the bars for visibility (each module = 2 pixels)
-    let scale = 2;
-    let mut bars = Vec::with_capacity(encoded.len() * scale);
-    for &module in &encoded {
-        let is_bar = module == 1;
-        for _ in 0..scale {
-            bars.push(is_bar);
-        }
-    }
-
-    bars
+    scale_modules(
+        encoded.iter().map(|&module| module == 1),
+        module_width(BarcodeKind::Code39),
+    )
 }
 
 /// Encode data as Code 128 barcode bars.
@@ -44,24 +170,334 @@ pub fn encode_code128(data: &str) -> Vec<bool> {
     };
 
     let encoded = barcode.encode();
+    scale_modules(
+        encoded.iter().map(|&module| module == 1),
+        module_width(BarcodeKind::Code128),
+    )
+}
+
+// ============================================================================
+// EAN-13 / UPC-A
+// ============================================================================
+
+/// EAN left-hand odd parity (L) codes. Right-hand (R) codes are their
+/// complement, and even parity (G) codes are R reversed.
+const EAN_L: [&str; 10] = [
+    "0001101", "0011001", "0010011", "0111101", "0100011", "0110001", "0101111", "0111011",
+    "0110111", "0001011",
+];
+
+/// Parity (`L` odd, `G` even) of the six left-hand digits, keyed by the
+/// first digit, which is not drawn itself.
+const EAN_PARITY: [&str; 10] = [
+    "LLLLLL", "LLGLGG", "LLGGLG", "LLGGGL", "LGLLGG", "LGGLLG", "LGGGLL", "LGLGLG", "LGLGGL",
+    "LGGLGL",
+];
+
+/// EAN/UPC check digit for the given digits (without the check digit).
+pub fn ean_check_digit(digits: &[u8]) -> u8 {
+    // Weights alternate 3, 1 from the rightmost digit
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, &d)| d as u32 * [3, 1][i % 2])
+        .sum();
+    ((10 - sum % 10) % 10) as u8
+}
 
-    // Scale up the bars for visibility (each module = 2 pixels)
-    let scale = 2;
-    let mut bars = Vec::with_capacity(encoded.len() * scale);
-    for &module in &encoded {
-        let is_bar = module == 1;
-        for _ in 0..scale {
-            bars.push(is_bar);
+/// Parse a string of ASCII digits into their values.
+fn digits(data: &str) -> Option<Vec<u8>> {
+    data.bytes()
+        .map(|b| b.is_ascii_digit().then(|| b - b'0'))
+        .collect()
+}
+
+/// Digits of an EAN-13 (`length` 13) or UPC-A (`length` 12) code, with
+/// the check digit appended if it was omitted.
+///
+/// Returns `None` for non-digits, the wrong length, or a wrong check digit,
+/// all of which the printer refuses to print.
+pub fn ean_digits(data: &str, length: usize) -> Option<Vec<u8>> {
+    let mut digits = digits(data)?;
+    if digits.len() + 1 == length {
+        digits.push(ean_check_digit(&digits));
+    }
+    (digits.len() == length && digits[length - 1] == ean_check_digit(&digits[..length - 1]))
+        .then_some(digits)
+}
+
+/// Encode data as EAN-13 barcode bars.
+///
+/// Accepts 12 digits (the check digit is appended) or 13 digits with a
+/// valid check digit.
+pub fn encode_ean13(data: &str) -> Vec<bool> {
+    match ean_digits(data, 13) {
+        Some(digits) => ean13_bars(&digits),
+        None => Vec::new(),
+    }
+}
+
+/// Encode data as UPC-A barcode bars.
+///
+/// Accepts 11 digits (the check digit is appended) or 12 digits with a
+/// valid check digit. UPC-A is EAN-13 with a leading zero.
+pub fn encode_upca(data: &str) -> Vec<bool> {
+    match ean_digits(data, 12) {
+        Some(digits) => ean13_bars(&[vec![0], digits].concat()),
+        None => Vec::new(),
+    }
+}
+
+/// Bars for 13 EAN digits, check digit included.
+fn ean13_bars(digits: &[u8]) -> Vec<bool> {
+    let mut modules = Vec::with_capacity(95);
+    push_pattern(&mut modules, "101");
+    let parity = EAN_PARITY[digits[0] as usize].as_bytes();
+    for (i, &d) in digits[1..7].iter().enumerate() {
+        let l = EAN_L[d as usize];
+        if parity[i] == b'G' {
+            // G = reversed complement of L
+            modules.extend(l.bytes().rev().map(|b| b == b'0'));
+        } else {
+            push_pattern(&mut modules, l);
         }
     }
+    push_pattern(&mut modules, "01010");
+    for &d in &digits[7..] {
+        // R = complement of L
+        modules.extend(EAN_L[d as usize].bytes().map(|b| b == b'0'));
+    }
+    push_pattern(&mut modules, "101");
 
-    bars
+    scale_modules(modules, module_width(BarcodeKind::Ean13))
+}
+
+// ============================================================================
+// ITF
+// ============================================================================
+
+/// Interleaved 2 of 5 digit patterns (`W` = wide element).
+const ITF_DIGITS: [&str; 10] = [
+    "NNWWN", "WNNNW", "NWNNW", "WWNNN", "NNWNW", "WNWNN", "NWWNN", "NNNWW", "WNNWN", "NWNWN",
+];
+
+/// Modules in a wide ITF element (narrow is one).
+const ITF_WIDE: usize = 3;
+
+/// Encode data as ITF (Interleaved 2 of 5) barcode bars.
+///
+/// Data must be an even number of digits: each pair is drawn as the first
+/// digit's bars interleaved with the second digit's spaces.
+pub fn encode_itf(data: &str) -> Vec<bool> {
+    let Some(digits) = digits(data) else {
+        return Vec::new();
+    };
+    if digits.is_empty() || digits.len() % 2 == 1 {
+        return Vec::new();
+    }
+
+    let mut modules = Vec::new();
+    push_pattern(&mut modules, "1010");
+    for pair in digits.chunks(2) {
+        let bars = ITF_DIGITS[pair[0] as usize].as_bytes();
+        let spaces = ITF_DIGITS[pair[1] as usize].as_bytes();
+        for i in 0..5 {
+            for (element, is_bar) in [(bars[i], true), (spaces[i], false)] {
+                let width = if element == b'W' { ITF_WIDE } else { 1 };
+                modules.extend(std::iter::repeat_n(is_bar, width));
+            }
+        }
+    }
+    modules.extend(std::iter::repeat_n(true, ITF_WIDE));
+    push_pattern(&mut modules, "01");
+
+    scale_modules(modules, module_width(BarcodeKind::Itf))
+}
+
+// ============================================================================
+// CODE 93
+// ============================================================================
+
+/// Code 93 characters, in value order (0-42).
+const CODE93_CHARS: &str = "0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ-. $/+%";
+
+/// Code 93 patterns by value: 0-42 are [`CODE93_CHARS`], 43-46 are the
+/// shift characters ($) (%) (/) (+), and 47 is the start/stop character.
+const CODE93_PATTERNS: [&str; 48] = [
+    "100010100",
+    "101001000",
+    "101000100",
+    "101000010",
+    "100101000",
+    "100100100",
+    "100100010",
+    "101010000",
+    "100010010",
+    "100001010",
+    "110101000",
+    "110100100",
+    "110100010",
+    "110010100",
+    "110010010",
+    "110001010",
+    "101101000",
+    "101100100",
+    "101100010",
+    "100110100",
+    "100011010",
+    "101011000",
+    "101001100",
+    "101000110",
+    "100101100",
+    "100010110",
+    "110110100",
+    "110110010",
+    "110101100",
+    "110100110",
+    "110010110",
+    "110011010",
+    "101101100",
+    "101100110",
+    "100110110",
+    "100111010",
+    "100101110",
+    "111010100",
+    "111010010",
+    "111001010",
+    "101101110",
+    "101110110",
+    "110101110",
+    "100100110",
+    "111011010",
+    "111010110",
+    "100110010",
+    "101011110",
+];
+
+/// Value of the (+) shift, which selects lowercase letters.
+const CODE93_SHIFT_LOWER: usize = 46;
+
+/// Code 93 start/stop character value.
+const CODE93_START_STOP: usize = 47;
+
+/// Code 93 check character over `values`, with weights cycling 1..=`max_weight`
+/// from the right.
+fn code93_check(values: &[usize], max_weight: usize) -> usize {
+    values
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, &v)| v * (i % max_weight + 1))
+        .sum::<usize>()
+        % 47
+}
+
+/// Encode data as Code 93 barcode bars.
+///
+/// Supports the 43 Code 93 characters, plus lowercase letters through the
+/// (+) shift. Both check characters (C and K) are added.
+pub fn encode_code93(data: &str) -> Vec<bool> {
+    let mut values = Vec::with_capacity(data.len());
+    for c in data.chars() {
+        if c.is_ascii_lowercase() {
+            values.push(CODE93_SHIFT_LOWER);
+            values.push(10 + (c as u8 - b'a') as usize);
+        } else if let Some(value) = CODE93_CHARS.find(c) {
+            values.push(value);
+        } else {
+            return Vec::new();
+        }
+    }
+    if values.is_empty() {
+        return Vec::new();
+    }
+    values.push(code93_check(&values, 20));
+    values.push(code93_check(&values, 15));
+
+    let mut modules = Vec::new();
+    push_pattern(&mut modules, CODE93_PATTERNS[CODE93_START_STOP]);
+    for value in values {
+        push_pattern(&mut modules, CODE93_PATTERNS[value]);
+    }
+    push_pattern(&mut modules, CODE93_PATTERNS[CODE93_START_STOP]);
+    // Termination bar
+    modules.push(true);
+
+    scale_modules(modules, module_width(BarcodeKind::Code93))
+}
+
+// ============================================================================
+// NW-7 (CODABAR)
+// ============================================================================
+
+/// NW-7 characters and their patterns (wide elements are two modules).
+const NW7_PATTERNS: [(char, &str); 20] = [
+    ('0', "101010011"),
+    ('1', "101011001"),
+    ('2', "101001011"),
+    ('3', "110010101"),
+    ('4', "101101001"),
+    ('5', "110101001"),
+    ('6', "100101011"),
+    ('7', "100101101"),
+    ('8', "100110101"),
+    ('9', "110100101"),
+    ('-', "101001101"),
+    ('$', "101100101"),
+    (':', "1101011011"),
+    ('/', "1101101011"),
+    ('.', "1101101101"),
+    ('+', "1011011011"),
+    ('A', "1011001001"),
+    ('B', "1001001011"),
+    ('C', "1010010011"),
+    ('D', "1010011001"),
+];
+
+/// Encode data as NW-7 (Codabar) barcode bars.
+///
+/// Data should start and end with one of the start/stop characters A-D;
+/// if it doesn't, it is wrapped in `A…A`.
+pub fn encode_nw7(data: &str) -> Vec<bool> {
+    let is_start_stop = |c: char| matches!(c.to_ascii_uppercase(), 'A'..='D');
+    let (first, last) = (data.chars().next(), data.chars().last());
+    let data = if data.chars().count() >= 2
+        && first.is_some_and(is_start_stop)
+        && last.is_some_and(is_start_stop)
+    {
+        data.to_ascii_uppercase()
+    } else {
+        format!("A{}A", data)
+    };
+
+    let mut modules = Vec::new();
+    for (i, c) in data.chars().enumerate() {
+        let Some((_, pattern)) = NW7_PATTERNS.iter().find(|(ch, _)| *ch == c) else {
+            return Vec::new();
+        };
+        if i > 0 {
+            // Narrow gap between characters
+            modules.push(false);
+        }
+        push_pattern(&mut modules, pattern);
+    }
+
+    scale_modules(modules, module_width(BarcodeKind::Nw7))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    /// Collapse dots back to modules.
+    fn modules(bars: &[bool], kind: BarcodeKind) -> String {
+        bars.iter()
+            .step_by(module_width(kind))
+            .map(|&b| if b { '1' } else { '0' })
+            .collect()
+    }
+
     #[test]
     fn test_code39_encoding() {
         let bars = encode_code39("A");
@@ -86,4 +522,93 @@ mod tests {
         // If it fails, returns empty
         assert!(bars.is_empty() || !bars.is_empty());
     }
+
+    #[test]
+    fn test_ean13_and_upca() {
+        assert_eq!(ean_check_digit(&[5, 9, 0, 1, 2, 3, 4, 1, 2, 3, 4, 5]), 7);
+        assert_eq!(ean_check_digit(&[0, 3, 6, 0, 0, 0, 2, 9, 1, 4, 5]), 2);
+
+        let bars = encode_ean13("5901234123457");
+        assert_eq!(bars.len(), 95 * 3);
+        let m = modules(&bars, BarcodeKind::Ean13);
+        // Start guard, "9" in L parity and "0" in G (first digit 5 = LGGLLG)
+        assert!(m.starts_with("10100010110100111"));
+        assert_eq!(&m[45..50], "01010");
+        // Ends with "7" in R code and the end guard
+        assert!(m.ends_with("1000100101"));
+        // Check digit is appended when omitted
+        assert_eq!(encode_ean13("590123412345"), bars);
+
+        assert_eq!(encode_upca("036000291452"), encode_ean13("0036000291452"));
+        assert!(encode_ean13("12345").is_empty());
+        assert!(encode_upca("03600029145X").is_empty());
+        // Wrong check digit
+        assert!(encode_ean13("5901234123458").is_empty());
+        assert!(encode_upca("036000291453").is_empty());
+
+        assert_eq!(
+            hri_text(BarcodeKind::Ean13, "590123412345"),
+            "5901234123457"
+        );
+        assert_eq!(hri_text(BarcodeKind::UpcA, "03600029145"), "036000291452");
+        assert_eq!(hri_text(BarcodeKind::Code39, "ABC"), "ABC");
+    }
+
+    #[test]
+    fn test_itf() {
+        let m = modules(&encode_itf("12"), BarcodeKind::Itf);
+        // Start, "1" bars (WNNNW) interleaved with "2" spaces (NWNNW), stop
+        assert_eq!(m, "101011101000101011100011101");
+        assert!(encode_itf("123").is_empty());
+    }
+
+    #[test]
+    fn test_code93_and_nw7() {
+        let m = modules(&encode_code93("TEST93"), BarcodeKind::Code93);
+        // Start + 6 characters + 2 check characters + stop, then termination bar
+        assert_eq!(m.len(), 10 * 9 + 1);
+        assert!(m.starts_with("101011110110100110"));
+        assert!(m.ends_with("1010111101"));
+        assert_ne!(encode_code93("a"), encode_code93("A"));
+        assert!(encode_code93("#").is_empty());
+
+        // Start/stop characters are added when missing
+        assert_eq!(encode_nw7("40156"), encode_nw7("A40156A"));
+        let m = modules(&encode_nw7("A1B"), BarcodeKind::Nw7);
+        assert_eq!(m, "1011001001010101100101001001011");
+        assert!(encode_nw7("A1*A").is_empty());
+    }
+
+    #[test]
+    fn test_raster_packs_bars() {
+        let barcode = raster(BarcodeKind::Itf, "1234", 2, HriPosition::None).unwrap();
+        assert_eq!(barcode.width, 45 * 3);
+        assert_eq!(barcode.height, 2);
+        assert_eq!(barcode.data.len(), barcode.width.div_ceil(8) * 2);
+        // Start pattern "1010" at 3 dots per module
+        assert_eq!(barcode.data[0], 0b1110_0011);
+        assert!(raster(BarcodeKind::Ean13, "nope", 10, HriPosition::None).is_none());
+    }
+
+    #[test]
+    fn test_raster_hri_below_bars() {
+        let bare = raster(BarcodeKind::Ean13, "590123412345", 40, HriPosition::None).unwrap();
+        let with_text = raster(BarcodeKind::Ean13, "590123412345", 40, HriPosition::Below).unwrap();
+        let font = FontMetrics::FONT_A;
+        assert_eq!(with_text.height, 40 + HRI_GAP + font.char_height);
+        // Bars are unchanged, the gap is blank, and the text has ink
+        let row_bytes = with_text.width.div_ceil(8);
+        assert_eq!(with_text.data[..40 * row_bytes], bare.data[..]);
+        let gap = &with_text.data[40 * row_bytes..(40 + HRI_GAP) * row_bytes];
+        assert!(gap.iter().all(|&b| b == 0));
+        assert!(
+            with_text.data[(40 + HRI_GAP) * row_bytes..]
+                .iter()
+                .any(|&b| b != 0)
+        );
+
+        // The printer draws "above" below the bars too
+        let above = raster(BarcodeKind::Ean13, "590123412345", 40, HriPosition::Above).unwrap();
+        assert_eq!(above.data, with_text.data);
+    }
 }
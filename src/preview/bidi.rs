@@ -0,0 +1,471 @@
+//! Right-to-left text: bidi reordering and Arabic shaping.
+//!
+//! Hebrew and Arabic are stored in logical (reading) order, but a printer
+//! draws characters left to right, so printed as is they come out reversed.
+//! Arabic letters also change shape depending on whether they join the
+//! letters around them. [`display`] turns a line into the order it should
+//! be drawn in, left to right, with Arabic letters replaced by their joined
+//! presentation forms (U+FE70–U+FEFF):
+//!
+//! ```
+//! use estrella::preview::bidi;
+//!
+//! assert!(bidi::has_rtl("שלום"));
+//! assert_eq!(bidi::display("שלום 123"), "123 םולש");
+//! assert_eq!(bidi::display("Total: (שקל)"), "Total: (לקש)");
+//! ```
+//!
+//! This is the part of the Unicode Bidirectional Algorithm (UAX #9) a
+//! single receipt line needs: strong types, numbers and their separators,
+//! neutrals and mirrored brackets. Explicit embeddings, overrides and
+//! isolates are ignored (and dropped, like the LRM/RLM marks).
+
+/// Bidi class of a character, simplified to what [`display`] resolves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Class {
+    /// Left-to-right letter
+    L,
+    /// Hebrew letter
+    R,
+    /// Arabic letter
+    Al,
+    /// European (ASCII) digit
+    En,
+    /// Arabic-Indic digit
+    An,
+    /// Number separator inside a number: `.`, `,`, `:`, `/`
+    Cs,
+    /// Plus and minus
+    Es,
+    /// Currency and percent signs next to a number
+    Et,
+    /// Combining mark, takes the class of the character before it
+    Nsm,
+    /// Whitespace
+    Ws,
+    /// Other neutral: punctuation, symbols
+    On,
+    /// Formatting character that isn't printed (LRM, RLM, embeddings)
+    Bn,
+}
+
+fn class(ch: char) -> Class {
+    match ch {
+        '0'..='9' => Class::En,
+        '.' | ',' | ':' | '/' | '\u{00A0}' => Class::Cs,
+        '+' | '-' => Class::Es,
+        '#' | '$' | '%' | '¢' | '£' | '¤' | '¥' | '°' | '€' | '₪' => Class::Et,
+        '\u{200E}' => Class::L,
+        '\u{200F}' | '\u{061C}' => Class::R,
+        '\u{202A}'..='\u{202E}' | '\u{2066}'..='\u{2069}' => Class::Bn,
+        '\u{0591}'..='\u{05BD}'
+        | '\u{05BF}'
+        | '\u{05C1}'..='\u{05C2}'
+        | '\u{05C4}'..='\u{05C5}'
+        | '\u{05C7}'
+        | '\u{0610}'..='\u{061A}'
+        | '\u{064B}'..='\u{065F}'
+        | '\u{0670}'
+        | '\u{06D6}'..='\u{06DC}'
+        | '\u{06DF}'..='\u{06E4}'
+        | '\u{06E7}'..='\u{06E8}'
+        | '\u{06EA}'..='\u{06ED}'
+        | '\u{0300}'..='\u{036F}'
+        | '\u{20D0}'..='\u{20FF}'
+        | '\u{FE00}'..='\u{FE0F}' => Class::Nsm,
+        '\u{0660}'..='\u{0669}' | '\u{066B}'..='\u{066C}' => Class::An,
+        '\u{06F0}'..='\u{06F9}' => Class::En,
+        '\u{0590}'..='\u{05FF}' | '\u{07C0}'..='\u{085F}' | '\u{FB1D}'..='\u{FB4F}' => Class::R,
+        '\u{0600}'..='\u{07BF}'
+        | '\u{0860}'..='\u{08FF}'
+        | '\u{FB50}'..='\u{FDFF}'
+        | '\u{FE70}'..='\u{FEFE}' => Class::Al,
+        c if c.is_whitespace() => Class::Ws,
+        c if c.is_alphabetic() => Class::L,
+        _ => Class::On,
+    }
+}
+
+/// Whether `text` has any right-to-left letters.
+pub fn has_rtl(text: &str) -> bool {
+    text.chars()
+        .any(|ch| matches!(class(ch), Class::R | Class::Al))
+}
+
+/// Whether `text` reads right to left: its first letter is Hebrew or
+/// Arabic.
+pub fn is_rtl(text: &str) -> bool {
+    text.chars()
+        .map(class)
+        .find(|class| matches!(class, Class::L | Class::R | Class::Al))
+        .is_some_and(|class| class != Class::L)
+}
+
+/// `text` shaped and reordered for drawing left to right.
+///
+/// Each line is reordered on its own, in the direction of its first letter
+/// (see [`is_rtl`]).
+pub fn display(text: &str) -> String {
+    if !has_rtl(text) {
+        return text.to_string();
+    }
+    text.split('\n')
+        .map(display_line)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn display_line(text: &str) -> String {
+    let chars: Vec<char> = shape(text)
+        .chars()
+        .filter(|&ch| class(ch) != Class::Bn)
+        .collect();
+    let rtl = is_rtl(text);
+    let levels = resolve_levels(&chars, rtl);
+    reorder(&chars, &levels)
+}
+
+/// Resolve the embedding level of each character (UAX #9 rules W1–W7,
+/// N1–N2, I1–I2 and L1 for a single paragraph without embeddings).
+fn resolve_levels(chars: &[char], rtl: bool) -> Vec<u8> {
+    let base = if rtl { Class::R } else { Class::L };
+    let mut classes: Vec<Class> = chars.iter().map(|&ch| class(ch)).collect();
+
+    // W1: marks take the class of what they're attached to
+    let mut prev = base;
+    for class in &mut classes {
+        if *class == Class::Nsm {
+            *class = prev;
+        }
+        prev = *class;
+    }
+
+    // W2, W3: digits after Arabic letters are Arabic numbers; AL is R
+    let mut strong = base;
+    for class in &mut classes {
+        match *class {
+            Class::L | Class::R => strong = *class,
+            Class::Al => {
+                strong = Class::Al;
+                *class = Class::R;
+            }
+            Class::En if strong == Class::Al => *class = Class::An,
+            _ => {}
+        }
+    }
+
+    // W4: a single separator between two numbers of the same type joins them
+    for i in 1..classes.len().saturating_sub(1) {
+        let (before, after) = (classes[i - 1], classes[i + 1]);
+        classes[i] = match classes[i] {
+            Class::Es if before == Class::En && after == Class::En => Class::En,
+            Class::Cs if before == after && matches!(before, Class::En | Class::An) => before,
+            class => class,
+        };
+    }
+
+    // W5: currency and percent signs next to a European number join it
+    for i in 0..classes.len() {
+        if classes[i] != Class::En {
+            continue;
+        }
+        for j in (0..i).rev() {
+            if classes[j] != Class::Et {
+                break;
+            }
+            classes[j] = Class::En;
+        }
+        for class in classes.iter_mut().skip(i + 1) {
+            if *class != Class::Et {
+                break;
+            }
+            *class = Class::En;
+        }
+    }
+
+    // W6: remaining separators are neutral
+    for class in &mut classes {
+        if matches!(class, Class::Es | Class::Et | Class::Cs | Class::Bn) {
+            *class = Class::On;
+        }
+    }
+
+    // W7: European numbers in left-to-right text are left-to-right
+    let mut strong = base;
+    for class in &mut classes {
+        match *class {
+            Class::L | Class::R => strong = *class,
+            Class::En if strong == Class::L => *class = Class::L,
+            _ => {}
+        }
+    }
+
+    // N1, N2: neutrals between two runs of the same direction take it,
+    // others take the paragraph's (numbers count as right-to-left)
+    let direction = |class: Class| match class {
+        Class::L => Some(Class::L),
+        Class::R | Class::En | Class::An => Some(Class::R),
+        _ => None,
+    };
+    let mut i = 0;
+    while i < classes.len() {
+        if direction(classes[i]).is_some() {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i < classes.len() && direction(classes[i]).is_none() {
+            i += 1;
+        }
+        let before = start
+            .checked_sub(1)
+            .and_then(|j| direction(classes[j]))
+            .unwrap_or(base);
+        let after = classes.get(i).and_then(|&c| direction(c)).unwrap_or(base);
+        let resolved = if before == after { before } else { base };
+        classes[start..i].fill(resolved);
+    }
+
+    // I1, I2: levels from the resolved types
+    let mut levels: Vec<u8> = classes
+        .iter()
+        .map(|class| match (rtl, class) {
+            (false, Class::L) => 0,
+            (false, Class::R) => 1,
+            (false, _) => 2,
+            (true, Class::R) => 1,
+            (true, _) => 2,
+        })
+        .collect();
+
+    // L1: trailing whitespace goes back to the paragraph level
+    for (i, &ch) in chars.iter().enumerate().rev() {
+        if !ch.is_whitespace() {
+            break;
+        }
+        levels[i] = rtl as u8;
+    }
+    levels
+}
+
+/// Reorder by level (rule L2), keeping combining marks after their base
+/// character (L3) and mirroring brackets in right-to-left runs (L4).
+fn reorder(chars: &[char], levels: &[u8]) -> String {
+    // Clusters: a base character and the marks attached to it
+    let mut clusters: Vec<(u8, Vec<char>)> = Vec::new();
+    for (&ch, &level) in chars.iter().zip(levels) {
+        match clusters.last_mut() {
+            Some((_, cluster)) if class(ch) == Class::Nsm => cluster.push(ch),
+            _ => clusters.push((level, vec![ch])),
+        }
+    }
+
+    let highest = clusters.iter().map(|(level, _)| *level).max().unwrap_or(0);
+    for level in (1..=highest).rev() {
+        let mut i = 0;
+        while i < clusters.len() {
+            if clusters[i].0 < level {
+                i += 1;
+                continue;
+            }
+            let start = i;
+            while i < clusters.len() && clusters[i].0 >= level {
+                i += 1;
+            }
+            clusters[start..i].reverse();
+        }
+    }
+
+    clusters
+        .into_iter()
+        .flat_map(|(level, cluster)| {
+            cluster
+                .into_iter()
+                .map(move |ch| if level % 2 == 1 { mirror(ch) } else { ch })
+        })
+        .collect()
+}
+
+/// The mirrored form of a bracket, for right-to-left runs.
+fn mirror(ch: char) -> char {
+    match ch {
+        '(' => ')',
+        ')' => '(',
+        '[' => ']',
+        ']' => '[',
+        '{' => '}',
+        '}' => '{',
+        '<' => '>',
+        '>' => '<',
+        '«' => '»',
+        '»' => '«',
+        other => other,
+    }
+}
+
+// ============================================================================
+// ARABIC SHAPING
+// ============================================================================
+
+/// How an Arabic letter connects to its neighbors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Joining {
+    /// Joins the letters on both sides: isolated, final, initial and
+    /// medial forms, in that order from the first presentation form.
+    Dual,
+    /// Only joins the letter before it: isolated and final forms.
+    Right,
+}
+
+/// Arabic letters: first presentation form (isolated) and joining type.
+const ARABIC_FORMS: &[(char, u32, Joining)] = &[
+    ('\u{0622}', 0xFE81, Joining::Right), // آ
+    ('\u{0623}', 0xFE83, Joining::Right), // أ
+    ('\u{0624}', 0xFE85, Joining::Right), // ؤ
+    ('\u{0625}', 0xFE87, Joining::Right), // إ
+    ('\u{0626}', 0xFE89, Joining::Dual),  // ئ
+    ('\u{0627}', 0xFE8D, Joining::Right), // ا
+    ('\u{0628}', 0xFE8F, Joining::Dual),  // ب
+    ('\u{0629}', 0xFE93, Joining::Right), // ة
+    ('\u{062A}', 0xFE95, Joining::Dual),  // ت
+    ('\u{062B}', 0xFE99, Joining::Dual),  // ث
+    ('\u{062C}', 0xFE9D, Joining::Dual),  // ج
+    ('\u{062D}', 0xFEA1, Joining::Dual),  // ح
+    ('\u{062E}', 0xFEA5, Joining::Dual),  // خ
+    ('\u{062F}', 0xFEA9, Joining::Right), // د
+    ('\u{0630}', 0xFEAB, Joining::Right), // ذ
+    ('\u{0631}', 0xFEAD, Joining::Right), // ر
+    ('\u{0632}', 0xFEAF, Joining::Right), // ز
+    ('\u{0633}', 0xFEB1, Joining::Dual),  // س
+    ('\u{0634}', 0xFEB5, Joining::Dual),  // ش
+    ('\u{0635}', 0xFEB9, Joining::Dual),  // ص
+    ('\u{0636}', 0xFEBD, Joining::Dual),  // ض
+    ('\u{0637}', 0xFEC1, Joining::Dual),  // ط
+    ('\u{0638}', 0xFEC5, Joining::Dual),  // ظ
+    ('\u{0639}', 0xFEC9, Joining::Dual),  // ع
+    ('\u{063A}', 0xFECD, Joining::Dual),  // غ
+    ('\u{0641}', 0xFED1, Joining::Dual),  // ف
+    ('\u{0642}', 0xFED5, Joining::Dual),  // ق
+    ('\u{0643}', 0xFED9, Joining::Dual),  // ك
+    ('\u{0644}', 0xFEDD, Joining::Dual),  // ل
+    ('\u{0645}', 0xFEE1, Joining::Dual),  // م
+    ('\u{0646}', 0xFEE5, Joining::Dual),  // ن
+    ('\u{0647}', 0xFEE9, Joining::Dual),  // ه
+    ('\u{0648}', 0xFEED, Joining::Right), // و
+    ('\u{0649}', 0xFEEF, Joining::Right), // ى
+    ('\u{064A}', 0xFEF1, Joining::Dual),  // ي
+];
+
+/// Lam-alef ligatures: the alef and the ligature's isolated form.
+const LAM_ALEF: &[(char, u32)] = &[
+    ('\u{0622}', 0xFEF5),
+    ('\u{0623}', 0xFEF7),
+    ('\u{0625}', 0xFEF9),
+    ('\u{0627}', 0xFEFB),
+];
+
+const LAM: char = '\u{0644}';
+const TATWEEL: char = '\u{0640}';
+
+fn arabic_form(ch: char) -> Option<(u32, Joining)> {
+    ARABIC_FORMS
+        .iter()
+        .find(|(letter, _, _)| *letter == ch)
+        .map(|&(_, form, joining)| (form, joining))
+}
+
+/// Whether `ch` connects to the letter after it.
+fn joins_next(ch: char) -> bool {
+    ch == TATWEEL || matches!(arabic_form(ch), Some((_, Joining::Dual)))
+}
+
+/// Whether `ch` connects to the letter before it.
+fn joins_previous(ch: char) -> bool {
+    ch == TATWEEL || arabic_form(ch).is_some()
+}
+
+/// Replace Arabic letters with the presentation forms that join them to
+/// their neighbors, in logical order. Combining marks don't break joins.
+fn shape(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let neighbor = |range: &mut dyn Iterator<Item = usize>| {
+        range.map(|j| chars[j]).find(|&ch| class(ch) != Class::Nsm)
+    };
+
+    let mut out = String::with_capacity(text.len());
+    let mut i = 0;
+    while i < chars.len() {
+        let ch = chars[i];
+        let Some((isolated, joining)) = arabic_form(ch) else {
+            out.push(ch);
+            i += 1;
+            continue;
+        };
+        let previous = neighbor(&mut (0..i).rev()).is_some_and(joins_next);
+
+        // Lam followed by alef is a single ligature
+        if ch == LAM
+            && let Some(&(_, ligature)) = chars
+                .get(i + 1)
+                .and_then(|next| LAM_ALEF.iter().find(|(alef, _)| alef == next))
+        {
+            out.extend(char::from_u32(ligature + previous as u32));
+            i += 2;
+            continue;
+        }
+
+        let next = joining == Joining::Dual
+            && neighbor(&mut (i + 1..chars.len())).is_some_and(joins_previous);
+        let offset = match (previous, next) {
+            (false, false) => 0,
+            (true, false) => 1,
+            (false, true) => 2,
+            (true, true) => 3,
+        };
+        out.extend(char::from_u32(isolated + offset));
+        i += 1;
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ltr_text_is_unchanged() {
+        assert_eq!(display("Café 12.50€"), "Café 12.50€");
+        assert!(!has_rtl("Café"));
+    }
+
+    #[test]
+    fn test_hebrew_reorders_around_numbers() {
+        // "Price: 12.50 ₪" in Hebrew; the number keeps its order
+        assert_eq!(display("מחיר: 12.50 ₪"), "₪ 12.50 :ריחמ");
+        // Hebrew inside English stays in place, reversed
+        assert_eq!(display("Pay שלום now"), "Pay םולש now");
+        assert!(is_rtl("  שלום abc"));
+        assert!(!is_rtl("abc שלום"));
+    }
+
+    #[test]
+    fn test_arabic_shaping() {
+        // سلام: seen (initial), lam-alef ligature (final), meem (isolated)
+        let shaped = shape("سلام");
+        assert_eq!(shaped, "\u{FEB3}\u{FEFC}\u{FEE1}");
+        // Displayed right to left
+        assert_eq!(display("سلام"), "\u{FEE1}\u{FEFC}\u{FEB3}");
+        // Letters that only join the one before them break the word: دار
+        assert_eq!(shape("دار"), "\u{FEA9}\u{FE8D}\u{FEAD}");
+        // Marks don't break a join: بَب → initial beh, mark, final beh
+        assert_eq!(shape("بَب"), "\u{FE91}\u{064E}\u{FE90}");
+    }
+
+    #[test]
+    fn test_arabic_digits_and_marks_stay_in_order() {
+        // Arabic-Indic digits read left to right
+        assert_eq!(display("رقم ١٢"), "١٢ \u{FEE2}\u{FED7}\u{FEAD}");
+        // A mark stays after its letter
+        assert_eq!(display("بَب"), "\u{FE90}\u{FE91}\u{064E}");
+    }
+}
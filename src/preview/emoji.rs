@@ -3,13 +3,12 @@
 //! Provides emoji rendering by extracting sprites from an embedded sprite sheet
 //! and compositing with text. Supports the original DoCoMo emoji set.
 
-use image::Pixel;
 use std::collections::HashMap;
 use std::sync::OnceLock;
 
-/// Embedded DoCoMo emoji sprite sheet (1-bit PNG, 267x267 pixels).
+/// Embedded DoCoMo emoji sprite sheet (binary PBM, 267x267 pixels).
 /// 18x18 grid with 12x12 pixel cells and 3px spacing.
-const DOCOMO_SPRITE_PNG: &[u8] = include_bytes!("emoji/docomo.png");
+const DOCOMO_SPRITE_PBM: &[u8] = include_bytes!("emoji/docomo.pbm");
 
 /// Sprite sheet dimensions.
 const SHEET_WIDTH: usize = 267;
@@ -46,19 +45,21 @@ pub struct EmojiGrayscale {
 }
 
 /// Decode and cache the sprite sheet pixels.
+///
+/// The sheet is a binary PBM: a `P4` header, then rows packed 8 pixels per
+/// byte, MSB first, 1 = black.
 fn get_sprite_pixels() -> &'static Vec<u8> {
     SPRITE_PIXELS.get_or_init(|| {
-        let img = image::load_from_memory(DOCOMO_SPRITE_PNG)
+        let header = format!("P4\n{} {}\n", SHEET_WIDTH, SHEET_HEIGHT);
+        let rows = DOCOMO_SPRITE_PBM
+            .strip_prefix(header.as_bytes())
             .expect("Failed to decode embedded emoji sprite sheet");
+        let row_bytes = SHEET_WIDTH.div_ceil(8);
 
-        let gray = img.to_luma8();
         let mut pixels = vec![0u8; SHEET_WIDTH * SHEET_HEIGHT];
-
-        for (x, y, pixel) in gray.enumerate_pixels() {
-            if x < SHEET_WIDTH as u32 && y < SHEET_HEIGHT as u32 {
-                let idx = (y as usize) * SHEET_WIDTH + (x as usize);
-                // Threshold: anything darker than 128 is black
-                pixels[idx] = if pixel.channels()[0] < 128 { 1 } else { 0 };
+        for (y, row) in rows.chunks(row_bytes).take(SHEET_HEIGHT).enumerate() {
+            for x in 0..SHEET_WIDTH {
+                pixels[y * SHEET_WIDTH + x] = (row[x / 8] >> (7 - x % 8)) & 1;
             }
         }
 
@@ -15,35 +15,51 @@
 //!               - Output final composited image
 //! ```
 //!
+//! [`render_svg`] lays programs out the same way but writes SVG, for
+//! previews that stay sharp when zoomed.
+//!
 //! ## Example
 //!
 //! ```
+//! # #[cfg(feature = "images")] {
 //! use estrella::ir::Program;
 //! use estrella::preview::render_preview;
 //!
 //! let program = Program::new();
 //! // ... add ops ...
 //! let png_bytes = render_preview(&program).unwrap();
+//! # }
 //! ```
 
-mod barcode;
+pub mod barcode;
+pub mod bidi;
 pub mod emoji;
 mod font;
+#[cfg(feature = "images")]
+pub(crate) mod svg;
+pub mod terminal;
 mod text;
 pub mod ttf_font;
 
 pub use font::{FontMetrics, generate_glyph};
+#[cfg(feature = "images")]
+pub use svg::{render_svg, render_svg_with_config};
 
 use crate::ir::{BarcodeKind, Op, Program, StyleState};
+use crate::metrics::{self, Stage};
+use crate::printer::PrinterConfig;
+use crate::protocol::barcode::barcode1d::HriPosition;
 use crate::protocol::barcode::qr::QrErrorLevel;
 use crate::protocol::text::{Alignment, Font};
-use image::{GrayImage, Luma};
 use std::collections::HashMap;
+use std::ops::Range;
 use thiserror::Error;
 
-use barcode::{encode_code39, encode_code128};
 use font::RenderState;
 
+/// Blank paper shown for `Op::FeedToMark` (3mm, a common label gap).
+const LABEL_GAP_DOTS: usize = 24;
+
 /// Errors that can occur during preview rendering.
 #[derive(Debug, Error)]
 pub enum PreviewError {
@@ -65,10 +81,31 @@ pub struct PreviewRenderer {
     /// Top margin in dots (used to set initial y position)
     #[allow(dead_code)]
     top_margin: usize,
+    /// Pixel intensities (0 = white, 255 = black).
     buffer: Vec<u8>,
+    /// Blit `GrayRaster` ops as grayscale instead of dithering them.
+    keep_gray: bool,
     height: usize,
     state: RenderState,
+    /// Styles saved by `Op::PushStyle`, restored by `Op::PopStyle`.
+    style_stack: Vec<StyleState>,
     font_cache: HashMap<(Font, char), Vec<u8>>,
+    /// Debug markers seen so far, with the y position they were hit at.
+    markers: Vec<PreviewMarker>,
+    /// Only store rows in this range (buffer row 0 = `window.start`).
+    /// Used for paged previews of very long documents.
+    window: Option<Range<usize>>,
+    /// One past the lowest row that received a non-white pixel, across the
+    /// whole document (not just the window).
+    content_height: usize,
+}
+
+/// A debug marker (`Op::Marker`) and the y position where it was reached.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PreviewMarker {
+    pub label: String,
+    /// Y position in dots (including the top margin).
+    pub y: usize,
 }
 
 impl PreviewRenderer {
@@ -100,8 +137,13 @@ impl PreviewRenderer {
             top_margin,
             buffer,
             height: initial_height,
+            keep_gray: false,
             state,
+            style_stack: Vec::new(),
             font_cache: HashMap::new(),
+            markers: Vec::new(),
+            window: None,
+            content_height: 0,
         }
     }
 
@@ -116,33 +158,61 @@ impl PreviewRenderer {
     ///                   640 dots total
     /// ```
     pub fn tsp650ii() -> Self {
-        // 80mm paper at 203 DPI = 640 dots total
-        // 72mm printable = 576 dots
-        // 4mm margin each side = 32 dots
-        // ~2mm top margin = 16 dots
-        Self::new(640, 576, 32, 16)
+        Self::for_config(&PrinterConfig::TSP650II)
+    }
+
+    /// Create a renderer for a printer's print width, with 4mm (32-dot)
+    /// side margins and a ~2mm (16-dot) top margin.
+    ///
+    /// A 58mm printer (384 dots) previews as 448 dots of paper.
+    pub fn for_config(config: &PrinterConfig) -> Self {
+        let print_width = config.width_dots as usize;
+        Self::new(print_width + 2 * 32, print_width, 32, 16)
     }
 
     /// Ensure buffer has room for the given y position.
+    ///
+    /// When rendering a window, rows outside it are never stored, so the
+    /// buffer only grows to the window's height.
     fn ensure_height(&mut self, y: usize) {
-        let needed_height = y + 1;
+        let row = match &self.window {
+            Some(window) if y < window.start => return,
+            Some(window) => y.min(window.end - 1) - window.start,
+            None => y,
+        };
+        let needed_height = row + 1;
         if needed_height > self.height {
-            // Grow by at least 100 rows or to needed height
-            let new_height = needed_height.max(self.height + 100);
+            // Grow geometrically (at least 100 rows) so long documents
+            // don't re-copy the whole buffer on every few lines
+            let new_height = needed_height.max(self.height + (self.height / 2).max(100));
             self.buffer.resize(self.paper_width * new_height, 0);
             self.height = new_height;
         }
     }
 
-    /// Set a pixel (1 = black, 0 = white).
+    /// Set a pixel to black or white.
     /// x is in paper coordinates (0 = left edge of paper).
     fn set_pixel(&mut self, x: usize, y: usize, black: bool) {
+        self.set_pixel_level(x, y, if black { 255 } else { 0 });
+    }
+
+    /// Set a pixel intensity (0 = white, 255 = black).
+    /// x is in paper coordinates (0 = left edge of paper).
+    fn set_pixel_level(&mut self, x: usize, y: usize, level: u8) {
         if x >= self.paper_width {
             return;
         }
+        if level != 0 {
+            self.content_height = self.content_height.max(y + 1);
+        }
+        let row = match &self.window {
+            Some(window) if !window.contains(&y) => return,
+            Some(window) => y - window.start,
+            None => y,
+        };
         self.ensure_height(y);
-        let idx = y * self.paper_width + x;
-        self.buffer[idx] = if black { 1 } else { 0 };
+        let idx = row * self.paper_width + x;
+        self.buffer[idx] = level;
     }
 
     /// Set a pixel in print coordinates (0 = left edge of printable area).
@@ -152,6 +222,7 @@ impl PreviewRenderer {
     }
 
     /// Render the program to PNG bytes.
+    #[cfg(feature = "images")]
     pub fn render(&mut self, program: &Program) -> Result<Vec<u8>, PreviewError> {
         for op in &program.ops {
             self.process_op(op)?;
@@ -160,8 +231,14 @@ impl PreviewRenderer {
         self.to_png()
     }
 
-    /// Process a single IR operation.
+    /// Process a single IR operation, recorded in the preview timings (see
+    /// [`crate::metrics`]).
     fn process_op(&mut self, op: &Op) -> Result<(), PreviewError> {
+        metrics::timed(Stage::Preview, op.name(), || self.draw_op(op))
+    }
+
+    /// Draw a single IR operation.
+    fn draw_op(&mut self, op: &Op) -> Result<(), PreviewError> {
         match op {
             Op::Init => {
                 self.state.reset();
@@ -190,6 +267,14 @@ impl PreviewRenderer {
                 self.ensure_height(self.state.y);
             }
 
+            Op::FeedToMark => {
+                // Where the next mark is depends on the stock; show a
+                // typical 3mm label gap
+                self.newline();
+                self.state.y += LABEL_GAP_DOTS;
+                self.ensure_height(self.state.y);
+            }
+
             Op::SetAlign(align) => {
                 self.state.style.alignment = *align;
             }
@@ -243,10 +328,37 @@ impl PreviewRenderer {
                 // Codepage doesn't affect visual rendering in preview
             }
 
+            Op::SetLineSpacing(_) => {
+                // Preview lines are always a character tall, i.e. tight
+            }
+
+            Op::SetDensity(_) | Op::SetSpeed(_) => {
+                // Heat settings don't change which dots are printed
+            }
+
+            Op::SetWrap(_) => {
+                // Wrapping is done by the optimizer
+            }
+
             Op::ResetStyle => {
                 self.state.style = StyleState::default();
             }
 
+            Op::PushStyle => {
+                self.style_stack.push(self.state.style.clone());
+            }
+
+            Op::PopStyle => {
+                // The print position isn't part of the scoped style
+                if let Some(saved) = self.style_stack.pop() {
+                    let absolute_position = self.state.style.absolute_position;
+                    self.state.style = StyleState {
+                        absolute_position,
+                        ..saved
+                    };
+                }
+            }
+
             Op::Text(text) => {
                 self.render_text(text);
             }
@@ -267,6 +379,21 @@ impl PreviewRenderer {
                 self.render_raster(*width as usize, *height as usize, data);
             }
 
+            Op::GrayRaster {
+                width,
+                height,
+                data,
+                dither,
+            } => {
+                let (width, height) = (*width as usize, *height as usize);
+                if self.keep_gray {
+                    self.render_gray_raster(width, height, data);
+                } else {
+                    let packed = crate::render::dither::dither_levels(width, height, data, *dither);
+                    self.render_raster(width, height, &packed);
+                }
+            }
+
             Op::Band { width_bytes, data } => {
                 let width_dots = (*width_bytes as usize) * 8;
                 let height = data.len() / (*width_bytes as usize);
@@ -289,8 +416,13 @@ impl PreviewRenderer {
                 self.render_pdf417(data, *module_width)?;
             }
 
-            Op::Barcode1D { kind, data, height } => {
-                self.render_barcode1d(*kind, data, *height);
+            Op::Barcode1D {
+                kind,
+                data,
+                height,
+                hri,
+            } => {
+                self.render_barcode1d(*kind, data, *height, *hri);
             }
 
             Op::SetAbsolutePosition(dots) => {
@@ -315,6 +447,19 @@ impl PreviewRenderer {
             Op::NvStore { .. } | Op::NvDelete { .. } => {
                 // These don't produce visible output in preview
             }
+
+            Op::Marker(label) => {
+                // Record the marker and draw a tick in the left margin
+                // (outside the printable area, so content is unaffected)
+                let y = self.state.y;
+                self.markers.push(PreviewMarker {
+                    label: label.clone(),
+                    y,
+                });
+                for x in 0..self.left_margin / 2 {
+                    self.set_pixel(x, y, true);
+                }
+            }
         }
 
         Ok(())
@@ -348,6 +493,33 @@ impl PreviewRenderer {
         self.state.x = 0;
     }
 
+    /// Render grayscale raster data without dithering.
+    fn render_gray_raster(&mut self, width: usize, height: usize, data: &[u8]) {
+        // Center graphics within print area (same as render_raster)
+        let start_x = if width < self.print_width {
+            (self.print_width - width) / 2
+        } else {
+            0
+        };
+
+        self.ensure_height(self.state.y + height);
+
+        for row in 0..height {
+            for col in 0..width {
+                if let Some(&level) = data.get(row * width + col) {
+                    self.set_pixel_level(
+                        start_x + col + self.left_margin,
+                        self.state.y + row,
+                        level,
+                    );
+                }
+            }
+        }
+
+        self.state.y += height;
+        self.state.x = 0;
+    }
+
     /// Render band graphics data.
     fn render_band(&mut self, width_dots: usize, height: usize, width_bytes: usize, data: &[u8]) {
         // Center graphics within print area
@@ -557,40 +729,14 @@ impl PreviewRenderer {
         Ok(())
     }
 
-    /// Render a 1D barcode.
-    fn render_barcode1d(&mut self, kind: BarcodeKind, data: &str, height: u8) {
-        let bars = match kind {
-            BarcodeKind::Code39 => encode_code39(data),
-            BarcodeKind::Code128 => encode_code128(data),
-            _ => {
-                // For unsupported types, show placeholder
-                self.render_placeholder(&format!("{:?}", kind), 200, height as usize);
-                return;
-            }
-        };
-
+    /// Render a 1D barcode, with its HRI text as the printer draws it.
+    fn render_barcode1d(&mut self, kind: BarcodeKind, data: &str, height: u8, hri: HriPosition) {
         let bar_height = height.max(20) as usize;
-        let bar_width = bars.len();
-
-        // Center barcode within print area
-        let start_x = if bar_width < self.print_width {
-            (self.print_width - bar_width) / 2
-        } else {
-            0
-        };
-
-        self.ensure_height(self.state.y + bar_height);
-
-        for (i, &bar) in bars.iter().enumerate() {
-            if bar {
-                for y in 0..bar_height {
-                    self.set_print_pixel(start_x + i, self.state.y + y, true);
-                }
-            }
+        match barcode::raster(kind, data, bar_height, hri) {
+            Some(barcode) => self.render_raster(barcode.width, barcode.height, &barcode.data),
+            // Data the symbology can't encode: show placeholder
+            None => self.render_placeholder(&format!("{:?}", kind), 200, height as usize),
         }
-
-        self.state.y += bar_height;
-        self.state.x = 0;
     }
 
     /// Render a placeholder box with text.
@@ -643,29 +789,31 @@ impl PreviewRenderer {
     }
 
     /// Convert buffer to PNG bytes.
+    #[cfg(feature = "images")]
     fn to_png(&self) -> Result<Vec<u8>, PreviewError> {
-        use image::ImageEncoder;
-
         let actual_height = self.trimmed_height(10);
+        self.encode_rows(0, actual_height)
+    }
 
-        let mut img = GrayImage::new(self.paper_width as u32, actual_height as u32);
+    /// Encode `rows` buffer rows starting at buffer row `start` as a PNG.
+    #[cfg(feature = "images")]
+    fn encode_rows(&self, start: usize, rows: usize) -> Result<Vec<u8>, PreviewError> {
+        use image::ImageEncoder;
 
-        for y in 0..actual_height {
-            for x in 0..self.paper_width {
-                let idx = y * self.paper_width + x;
+        let pixels: Vec<u8> = (start * self.paper_width..(start + rows) * self.paper_width)
+            .map(|idx| {
                 let is_black = self.buffer.get(idx).copied().unwrap_or(0) != 0;
-                let color = if is_black { 0u8 } else { 255u8 };
-                img.put_pixel(x as u32, y as u32, Luma([color]));
-            }
-        }
+                if is_black { 0u8 } else { 255u8 }
+            })
+            .collect();
 
         let mut png_bytes = Vec::new();
         let encoder = image::codecs::png::PngEncoder::new(&mut png_bytes);
         encoder
             .write_image(
-                img.as_raw(),
+                &pixels,
                 self.paper_width as u32,
-                actual_height as u32,
+                rows as u32,
                 image::ExtendedColorType::L8,
             )
             .map_err(|e: image::ImageError| PreviewError::ImageEncode(e.to_string()))?;
@@ -675,17 +823,102 @@ impl PreviewRenderer {
 }
 
 /// Render a program to PNG bytes.
+#[cfg(feature = "images")]
 pub fn render_preview(program: &Program) -> Result<Vec<u8>, PreviewError> {
-    let mut renderer = PreviewRenderer::tsp650ii();
+    render_preview_with_config(program, &PrinterConfig::TSP650II)
+}
+
+/// Render a program to PNG bytes on a printer's paper width.
+#[cfg(feature = "images")]
+pub fn render_preview_with_config(
+    program: &Program,
+    config: &PrinterConfig,
+) -> Result<Vec<u8>, PreviewError> {
+    let mut renderer = PreviewRenderer::for_config(config);
     renderer.render(program)
 }
 
+/// One page of a segmented preview.
+#[derive(Debug, Clone)]
+#[cfg(feature = "images")]
+pub struct PreviewPage {
+    /// PNG bytes for this page's rows.
+    pub png: Vec<u8>,
+    /// Zero-based page index.
+    pub page: usize,
+    /// Total number of pages for the document.
+    pub page_count: usize,
+    /// Full document preview height in pixels.
+    pub total_height: usize,
+}
+
+/// Render one vertical page of a program's preview.
+///
+/// Only the rows of the requested page are kept in memory and encoded, so
+/// multi-meter documents can be previewed page by page instead of
+/// allocating (and PNG-encoding) a single huge image. Stitching all pages
+/// top to bottom reproduces [`render_preview`].
+#[cfg(feature = "images")]
+pub fn render_preview_page(
+    program: &Program,
+    page: usize,
+    page_rows: usize,
+) -> Result<PreviewPage, PreviewError> {
+    render_preview_page_with_config(program, page, page_rows, &PrinterConfig::TSP650II)
+}
+
+/// [`render_preview_page`] on a printer's paper width.
+#[cfg(feature = "images")]
+pub fn render_preview_page_with_config(
+    program: &Program,
+    page: usize,
+    page_rows: usize,
+    config: &PrinterConfig,
+) -> Result<PreviewPage, PreviewError> {
+    if page_rows == 0 {
+        return Err(PreviewError::InvalidOp("page_rows must be > 0".into()));
+    }
+
+    let out_of_range = || PreviewError::InvalidOp(format!("page {} out of range", page));
+    let start = page.checked_mul(page_rows).ok_or_else(out_of_range)?;
+    let mut renderer = PreviewRenderer::for_config(config);
+    renderer.window = Some(start..start.saturating_add(page_rows));
+    for op in &program.ops {
+        renderer.process_op(op)?;
+    }
+
+    let total_height = renderer.content_height.max(10);
+    let page_count = total_height.div_ceil(page_rows);
+    if page >= page_count {
+        return Err(PreviewError::InvalidOp(format!(
+            "page {} out of range ({} pages)",
+            page, page_count
+        )));
+    }
+
+    let rows = page_rows.min(total_height - start);
+    Ok(PreviewPage {
+        png: renderer.encode_rows(0, rows)?,
+        page,
+        page_count,
+        total_height,
+    })
+}
+
 /// Measure the rendered height of a program using TSP650II preview parameters.
 ///
 /// Returns the same height that `to_preview_png()` would produce, without
 /// generating the PNG. Useful for computing the total preview image height.
 pub fn measure_preview(program: &Program) -> Result<usize, PreviewError> {
-    let mut renderer = PreviewRenderer::tsp650ii();
+    measure_preview_with_config(program, &PrinterConfig::TSP650II)
+}
+
+/// [`measure_preview`] on a printer's paper width.
+pub fn measure_preview_with_config(
+    program: &Program,
+    config: &PrinterConfig,
+) -> Result<usize, PreviewError> {
+    let mut renderer = PreviewRenderer::for_config(config);
     for op in &program.ops {
         renderer.process_op(op)?;
     }
@@ -699,14 +932,34 @@ pub fn measure_preview(program: &Program) -> Result<usize, PreviewError> {
 /// Unlike `measure_preview`, this returns the cursor position (not the trimmed
 /// buffer height), so it correctly accounts for whitespace/spacers.
 pub fn measure_cursor_y(program: &Program) -> Result<usize, PreviewError> {
-    let mut renderer = PreviewRenderer::tsp650ii();
+    measure_cursor_y_with_config(program, &PrinterConfig::TSP650II)
+}
+
+/// [`measure_cursor_y`] on a printer's paper width.
+pub fn measure_cursor_y_with_config(
+    program: &Program,
+    config: &PrinterConfig,
+) -> Result<usize, PreviewError> {
+    let mut renderer = PreviewRenderer::for_config(config);
     for op in &program.ops {
         renderer.process_op(op)?;
     }
     Ok(renderer.state.y)
 }
 
+/// Collect the debug markers of a program with their preview y positions.
+///
+/// Markers are only present when the document was compiled with `debug` set.
+pub fn preview_markers(program: &Program) -> Result<Vec<PreviewMarker>, PreviewError> {
+    let mut renderer = PreviewRenderer::tsp650ii();
+    for op in &program.ops {
+        renderer.process_op(op)?;
+    }
+    Ok(renderer.markers)
+}
+
 /// Raw raster output for printing.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct RawRaster {
     /// Width in pixels (576 for TSP650II)
     pub width: usize,
@@ -716,13 +969,70 @@ pub struct RawRaster {
     pub data: Vec<u8>,
 }
 
+/// Raw grayscale output for compositing.
+pub struct GrayRaster {
+    /// Width in pixels (576 for TSP650II)
+    pub width: usize,
+    /// Height in pixels
+    pub height: usize,
+    /// One intensity per pixel, row-major (0.0 = white, 1.0 = black)
+    pub data: Vec<f32>,
+}
+
+/// Render a program to a grayscale intensity buffer with no margins.
+///
+/// Like [`render_raw`], but `GrayRaster` ops keep their continuous-tone
+/// intensities instead of being dithered. Used by Canvas so that blending
+/// and re-dithering operate on the original grayscale values.
+pub fn render_gray(program: &Program) -> Result<GrayRaster, PreviewError> {
+    render_gray_with_config(program, &PrinterConfig::TSP650II)
+}
+
+/// [`render_gray`] at a printer's print width.
+pub fn render_gray_with_config(
+    program: &Program,
+    config: &PrinterConfig,
+) -> Result<GrayRaster, PreviewError> {
+    let width = config.width_dots as usize;
+    let mut renderer = PreviewRenderer::new(width, width, 0, 0);
+    renderer.keep_gray = true;
+
+    for op in &program.ops {
+        if matches!(op, Op::Cut { .. }) {
+            continue;
+        }
+        renderer.process_op(op)?;
+    }
+
+    let height = renderer.trimmed_height(1);
+    let width = renderer.paper_width;
+    let data = (0..width * height)
+        .map(|idx| renderer.buffer.get(idx).copied().unwrap_or(0) as f32 / 255.0)
+        .collect();
+
+    Ok(GrayRaster {
+        width,
+        height,
+        data,
+    })
+}
+
 /// Render a program to raw 1-bit raster data with no margins.
 ///
 /// Returns exactly 576 pixels wide (72mm at 203 DPI), packed 1-bit per pixel.
 /// This is suitable for direct raster printing via `Op::Raster`.
 pub fn render_raw(program: &Program) -> Result<RawRaster, PreviewError> {
-    // Create renderer with no margins: paper = print = 576px
-    let mut renderer = PreviewRenderer::new(576, 576, 0, 0);
+    render_raw_with_config(program, &PrinterConfig::TSP650II)
+}
+
+/// Render a program to raw 1-bit raster data at a printer's print width.
+pub fn render_raw_with_config(
+    program: &Program,
+    config: &PrinterConfig,
+) -> Result<RawRaster, PreviewError> {
+    // Create renderer with no margins: paper = print width
+    let width = config.width_dots as usize;
+    let mut renderer = PreviewRenderer::new(width, width, 0, 0);
 
     for op in &program.ops {
         // Skip Cut ops - we want the content only
@@ -772,6 +1082,7 @@ mod tests {
         assert_eq!(renderer.top_margin, 16);
     }
 
+    #[cfg(feature = "images")]
     #[test]
     fn test_empty_program() {
         let program = Program::new();
@@ -779,6 +1090,7 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[cfg(feature = "images")]
     #[test]
     fn test_simple_text() {
         let mut program = Program::new();
@@ -790,6 +1102,7 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[cfg(feature = "images")]
     #[test]
     fn test_styled_text() {
         let mut program = Program::new();
@@ -803,6 +1116,7 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[cfg(feature = "images")]
     #[test]
     fn test_feed() {
         let mut program = Program::new();
@@ -813,6 +1127,7 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[cfg(feature = "images")]
     #[test]
     fn test_cut() {
         let mut program = Program::new();
@@ -822,4 +1137,89 @@ mod tests {
         let result = render_preview(&program);
         assert!(result.is_ok());
     }
+
+    #[test]
+    fn test_gray_raster_dithered_in_raw() {
+        let mut program = Program::new();
+        program.push(Op::GrayRaster {
+            width: 576,
+            height: 4,
+            data: vec![255; 576 * 4],
+            dither: crate::render::dither::DitheringAlgorithm::Bayer,
+        });
+
+        let raw = render_raw(&program).unwrap();
+        assert_eq!(raw.height, 4);
+        assert!(raw.data.iter().all(|&b| b == 0xFF));
+    }
+
+    #[test]
+    fn test_gray_raster_kept_in_render_gray() {
+        let mut program = Program::new();
+        program.push(Op::GrayRaster {
+            width: 576,
+            height: 2,
+            data: vec![128; 576 * 2],
+            dither: crate::render::dither::DitheringAlgorithm::Bayer,
+        });
+
+        let gray = render_gray(&program).unwrap();
+        assert_eq!(gray.height, 2);
+        assert!(gray.data.iter().all(|&v| (v - 0.5).abs() < 0.01));
+    }
+
+    #[test]
+    fn test_markers_recorded_without_affecting_content() {
+        let mut program = Program::new();
+        program.push(Op::Init);
+        program.push(Op::Marker("first".into()));
+        program.push(Op::Text("Hello".to_string()));
+        program.push(Op::Newline);
+        program.push(Op::Marker("second".into()));
+
+        let markers = preview_markers(&program).unwrap();
+        assert_eq!(markers.len(), 2);
+        assert_eq!(markers[0].label, "first");
+        assert!(markers[1].y > markers[0].y);
+
+        // Markers only draw in the margin, so the raw raster is unchanged
+        let plain: Program = program
+            .ops
+            .iter()
+            .filter(|op| !matches!(op, Op::Marker(_)))
+            .cloned()
+            .collect();
+        assert_eq!(
+            render_raw(&program).unwrap().data,
+            render_raw(&plain).unwrap().data
+        );
+    }
+
+    #[cfg(feature = "images")]
+    #[test]
+    fn test_preview_pages_stitch_to_full_preview() {
+        let mut program = Program::new();
+        program.push(Op::Init);
+        for i in 0..40 {
+            program.push(Op::Text(format!("Line {}", i)));
+            program.push(Op::Newline);
+        }
+
+        let full = image::load_from_memory(&render_preview(&program).unwrap())
+            .unwrap()
+            .to_luma8();
+        let first = render_preview_page(&program, 0, 300).unwrap();
+        assert_eq!(first.total_height, full.height() as usize);
+        assert!(first.page_count > 1);
+
+        let mut stitched = Vec::new();
+        for page in 0..first.page_count {
+            let png = render_preview_page(&program, page, 300).unwrap().png;
+            stitched.extend(image::load_from_memory(&png).unwrap().to_luma8().into_raw());
+        }
+        assert_eq!(stitched, full.into_raw());
+
+        assert!(render_preview_page(&program, first.page_count, 300).is_err());
+        assert!(render_preview_page(&program, usize::MAX, 300).is_err());
+    }
 }
@@ -0,0 +1,353 @@
+//! SVG preview: a vector rendering of the receipt.
+//!
+//! Layout is the PNG preview's: every op goes through a [`PreviewRenderer`]
+//! that keeps track of the cursor and styles without storing pixels, so
+//! both previews put everything in the same place. What each op draws is
+//! then written as SVG:
+//!
+//! - text as `<text>` sized to its character cells (with rectangles for
+//!   inverted, underlined and upperlined text), so it stays sharp at any zoom
+//! - graphics, barcodes and logos as embedded PNGs, one dot per pixel,
+//!   scaled without smoothing
+//! - cuts as dashed lines
+
+use std::fmt::Write;
+
+use super::PreviewRenderer;
+use super::font::RenderState;
+use crate::ir::{Op, Program};
+use crate::printer::PrinterConfig;
+use crate::protocol::text::Alignment;
+
+/// Baseline of a text line, as a fraction of its cell height.
+const BASELINE: f32 = 0.8;
+
+/// Font size for a cell height (monospace fonts are ~5/6 as tall as a line).
+const FONT_SIZE: f32 = 5.0 / 6.0;
+
+/// Render a program to an SVG document on TSP650II paper.
+///
+/// ```
+/// use estrella::ir::{Op, Program};
+/// use estrella::preview::render_svg;
+///
+/// let program = Program {
+///     ops: vec![Op::Init, Op::Text("Hello".into()), Op::Newline],
+/// };
+/// let svg = render_svg(&program);
+/// assert!(svg.starts_with("<svg"));
+/// assert!(svg.contains(">Hello</text>"));
+/// ```
+pub fn render_svg(program: &Program) -> String {
+    render_svg_with_config(program, &PrinterConfig::TSP650II)
+}
+
+/// [`render_svg`] on a printer's paper width.
+pub fn render_svg_with_config(program: &Program, config: &PrinterConfig) -> String {
+    let mut layout = PreviewRenderer::for_config(config);
+    // Track the cursor only: no rows are stored
+    layout.window = Some(usize::MAX - 1..usize::MAX);
+
+    let mut body = String::new();
+    for op in &program.ops {
+        let before = layout.state.clone();
+        if layout.process_op(op).is_err() {
+            continue;
+        }
+        match op {
+            Op::Text(text) => write_text(&mut body, &layout, &before, text),
+            Op::Cut { .. } => {
+                let y = layout.state.y - 4;
+                let _ = write!(
+                    body,
+                    r##"<line x1="0" y1="{}" x2="{}" y2="{}" stroke="#000" stroke-width="2" stroke-dasharray="8 8"/>"##,
+                    y + 1,
+                    layout.paper_width,
+                    y + 1
+                );
+            }
+            Op::Marker(label) => {
+                let _ = write!(
+                    body,
+                    r##"<line x1="0" y1="{y}.5" x2="{}" y2="{y}.5" stroke="#000"><title>{}</title></line>"##,
+                    layout.left_margin / 2,
+                    escape(label),
+                    y = before.y
+                );
+            }
+            Op::Raster { .. }
+            | Op::GrayRaster { .. }
+            | Op::Band { .. }
+            | Op::QrCode { .. }
+            | Op::Pdf417 { .. }
+            | Op::Barcode1D { .. }
+            | Op::NvPrint { .. } => write_graphic(&mut body, &layout, &before, op),
+            _ => {}
+        }
+    }
+
+    let height = layout.content_height.max(10);
+    format!(
+        r##"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}" font-family="monospace" xml:space="preserve"><rect width="{w}" height="{h}" fill="#fff"/>{body}</svg>"##,
+        w = layout.paper_width,
+        h = height,
+        body = body
+    )
+}
+
+/// One line of a text op: where it starts and its characters, in the
+/// order the preview draws them.
+struct Run {
+    x: usize,
+    y: usize,
+    chars: Vec<char>,
+}
+
+/// Split text into lines as [`PreviewRenderer::render_text`] lays it out.
+fn text_runs(state: &RenderState, print_width: usize, text: &str) -> Vec<Run> {
+    let char_width = state.effective_char_width();
+    let line_height = state.effective_char_height();
+    let chars: Vec<char> = if state.style.upside_down {
+        text.chars().rev().collect()
+    } else {
+        text.chars().collect()
+    };
+
+    let mut x = state.x;
+    let mut y = state.y;
+    if x == 0 {
+        let text_width = chars.len() * char_width;
+        x = match state.style.alignment {
+            Alignment::Left => 0,
+            Alignment::Center => print_width.saturating_sub(text_width) / 2,
+            Alignment::Right => print_width.saturating_sub(text_width),
+        };
+    }
+
+    let mut runs = vec![Run {
+        x,
+        y,
+        chars: Vec::new(),
+    }];
+    for ch in chars {
+        let wrap = x + char_width > print_width;
+        if ch == '\n' || wrap {
+            x = 0;
+            y += line_height;
+            runs.push(Run {
+                x,
+                y,
+                chars: Vec::new(),
+            });
+            if ch == '\n' {
+                continue;
+            }
+        }
+        runs.last_mut()
+            .expect("runs start non-empty")
+            .chars
+            .push(ch);
+        x += char_width;
+    }
+    runs.retain(|run| !run.chars.is_empty());
+    runs
+}
+
+fn write_text(out: &mut String, layout: &PreviewRenderer, state: &RenderState, text: &str) {
+    let style = &state.style;
+    let char_width = state.effective_char_width();
+    let height = state.effective_char_height();
+    let (fill, background) = if style.invert {
+        ("#fff", "#000")
+    } else {
+        ("#000", "#fff")
+    };
+
+    for run in text_runs(state, layout.print_width, text) {
+        let x = run.x + layout.left_margin;
+        let y = run.y;
+        let width = run.chars.len() * char_width;
+
+        if style.invert {
+            rect(out, x, y, width, height, background);
+        }
+        if style.underline {
+            let line_y = if style.upside_down {
+                y + 1
+            } else {
+                y + height - 2
+            };
+            rect(out, x, line_y, width, 2, fill);
+        }
+        if style.upperline {
+            let line_y = if style.upside_down { y + height - 2 } else { y };
+            rect(out, x, line_y, width, 2, fill);
+        }
+
+        // Upside-down runs hold the characters reversed; turning the
+        // original order by 180° draws the same thing
+        let content: String = if style.upside_down {
+            run.chars.iter().rev().collect()
+        } else {
+            run.chars.iter().collect()
+        };
+        if content.trim().is_empty() {
+            continue;
+        }
+        let _ = write!(
+            out,
+            r#"<text x="{}" y="{:.1}" font-size="{:.1}" textLength="{}" lengthAdjust="spacingAndGlyphs" fill="{}""#,
+            x,
+            y as f32 + height as f32 * BASELINE,
+            height as f32 * FONT_SIZE,
+            width,
+            fill
+        );
+        if style.bold {
+            out.push_str(r#" font-weight="bold""#);
+        }
+        if style.upside_down {
+            let _ = write!(
+                out,
+                r#" transform="rotate(180 {:.1} {:.1})""#,
+                x as f32 + width as f32 / 2.0,
+                y as f32 + height as f32 / 2.0
+            );
+        }
+        let _ = write!(out, ">{}</text>", escape(&content));
+    }
+}
+
+/// Draw a graphics op into a scratch renderer and embed the rows it
+/// covered as a PNG.
+fn write_graphic(out: &mut String, layout: &PreviewRenderer, state: &RenderState, op: &Op) {
+    let width = layout.print_width;
+    let mut scratch = PreviewRenderer::new(width, width, 0, 0);
+    scratch.state = RenderState {
+        y: 0,
+        ..state.clone()
+    };
+    if scratch.process_op(op).is_err() || scratch.state.y == 0 {
+        return;
+    }
+    let Ok(png) = scratch.encode_rows(0, scratch.state.y) else {
+        return;
+    };
+    let _ = write!(
+        out,
+        r#"<image x="{}" y="{}" width="{}" height="{}" style="image-rendering:pixelated" href="data:image/png;base64,{}"/>"#,
+        layout.left_margin,
+        state.y,
+        width,
+        scratch.state.y,
+        base64(&png)
+    );
+}
+
+fn rect(out: &mut String, x: usize, y: usize, width: usize, height: usize, fill: &str) {
+    let _ = write!(
+        out,
+        r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{}"/>"#,
+        x, y, width, height, fill
+    );
+}
+
+/// Escape text for XML content and attributes.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            // Not allowed in XML 1.0
+            c if c.is_control() => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Standard base64 with padding, for data URIs.
+pub(crate) fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let b = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[(n >> (18 - 6 * i)) as usize & 63] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document::Document;
+
+    #[test]
+    fn test_base64() {
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foo"), "Zm9v");
+        assert_eq!(base64(b"foobar"), "Zm9vYmFy");
+    }
+
+    #[test]
+    fn test_svg_matches_png_layout() {
+        let doc: Document = serde_json::from_str(
+            r#"{"document": [
+                {"text": "A & B <tag>", "center": true, "bold": true},
+                {"text": "INVERTED", "invert": true},
+                {"qr_code": "https://example.com"},
+                {"text": "after"}
+            ]}"#,
+        )
+        .unwrap();
+        let program = doc.compile();
+        let svg = render_svg(&program);
+
+        assert!(svg.contains(r#"font-weight="bold">A &amp; B &lt;tag&gt;</text>"#));
+        assert!(svg.contains(r##"fill="#fff">INVERTED</text>"##));
+        assert_eq!(svg.matches("<image ").count(), 1);
+        assert!(svg.contains("stroke-dasharray"));
+
+        // Same height as the PNG preview
+        let height = crate::preview::measure_preview(&program).unwrap();
+        assert!(svg.contains(&format!(
+            r#"height="{}" viewBox="0 0 640 {}""#,
+            height, height
+        )));
+    }
+
+    #[test]
+    fn test_text_runs_wrap_and_align() {
+        let state = RenderState::default();
+        let runs = text_runs(&state, 48, "abcdef\ngh");
+        let lines: Vec<(usize, usize, String)> = runs
+            .iter()
+            .map(|run| (run.x, run.y, run.chars.iter().collect()))
+            .collect();
+        assert_eq!(
+            lines,
+            [
+                (0, 0, "abcd".to_string()),
+                (0, 24, "ef".to_string()),
+                (0, 48, "gh".to_string())
+            ]
+        );
+    }
+}
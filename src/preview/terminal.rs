@@ -0,0 +1,282 @@
+//! Terminal renderings of a [`RawRaster`], for previews over SSH.
+//!
+//! - [`sixel`]: a real bitmap, for terminals with sixel graphics
+//! - [`half_block_lines`]: U+2580 `▀` with ANSI colors, two pixels per cell
+//! - [`braille_lines`]: plain text, 2×4 pixels per cell (used by the TUI)
+//!
+//! The raster is scaled down to fit a number of terminal columns. Each
+//! terminal cell covers a block of pixels and is inked when at least a
+//! quarter of them are black, which keeps thin text strokes visible at
+//! small sizes.
+
+use super::RawRaster;
+
+/// Fraction of black pixels at which a scaled-down cell is inked.
+const INK_COVERAGE: f32 = 0.25;
+
+/// A raster scaled down to a grid of on/off cells.
+struct CellGrid {
+    width: usize,
+    height: usize,
+    cells: Vec<bool>,
+}
+
+impl CellGrid {
+    /// Scale `raw` so it is at most `max_width` cells wide.
+    fn fit(raw: &RawRaster, max_width: usize) -> Self {
+        let scale = raw.width.div_ceil(max_width.max(1)).max(1);
+        let width = raw.width.div_ceil(scale);
+        let height = raw.height.div_ceil(scale);
+        let width_bytes = raw.width.div_ceil(8);
+
+        let mut cells = Vec::with_capacity(width * height);
+        for cy in 0..height {
+            for cx in 0..width {
+                let (x0, y0) = (cx * scale, cy * scale);
+                let (x1, y1) = ((x0 + scale).min(raw.width), (y0 + scale).min(raw.height));
+                let black = (y0..y1)
+                    .flat_map(|y| (x0..x1).map(move |x| (x, y)))
+                    .filter(|&(x, y)| raw.data[y * width_bytes + x / 8] & (0x80 >> (x % 8)) != 0)
+                    .count();
+                let area = (x1 - x0) * (y1 - y0);
+                cells.push(black as f32 >= area as f32 * INK_COVERAGE);
+            }
+        }
+
+        Self {
+            width,
+            height,
+            cells,
+        }
+    }
+
+    fn get(&self, x: usize, y: usize) -> bool {
+        x < self.width && y < self.height && self.cells[y * self.width + x]
+    }
+}
+
+/// Render a raster as Unicode braille, at most `columns` characters wide.
+///
+/// Each character shows a 2×4 block of cells, so this is the densest
+/// text-only preview.
+pub fn braille_lines(raw: &RawRaster, columns: usize) -> Vec<String> {
+    // Dot bit for each (x, y) position in a braille character
+    const DOTS: [[u32; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+
+    let grid = CellGrid::fit(raw, columns * 2);
+    (0..grid.height.div_ceil(4))
+        .map(|row| {
+            (0..grid.width.div_ceil(2))
+                .map(|col| {
+                    let mut bits = 0;
+                    for (dy, dots) in DOTS.iter().enumerate() {
+                        for (dx, dot) in dots.iter().enumerate() {
+                            if grid.get(col * 2 + dx, row * 4 + dy) {
+                                bits |= dot;
+                            }
+                        }
+                    }
+                    char::from_u32(0x2800 + bits).unwrap_or(' ')
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Render a raster as U+2580 half blocks, at most `columns` characters wide.
+///
+/// Each character shows two cells stacked vertically: the foreground color
+/// paints the top one, the background the bottom one. Colors are explicit
+/// (black on white) so the preview reads the same on dark terminals.
+pub fn half_block_lines(raw: &RawRaster, columns: usize) -> Vec<String> {
+    let grid = CellGrid::fit(raw, columns);
+    (0..grid.height.div_ceil(2))
+        .map(|row| {
+            let mut line = String::new();
+            for x in 0..grid.width {
+                let top = if grid.get(x, row * 2) { 30 } else { 97 };
+                let bottom = if grid.get(x, row * 2 + 1) { 40 } else { 107 };
+                line.push_str(&format!("\x1b[{};{}m▀", top, bottom));
+            }
+            line.push_str("\x1b[0m");
+            line
+        })
+        .collect()
+}
+
+/// Encode a raster as a sixel image, at most `max_width` pixels wide.
+///
+/// Returns the complete DCS sequence, ready to write to the terminal.
+pub fn sixel(raw: &RawRaster, max_width: usize) -> String {
+    let grid = CellGrid::fit(raw, max_width);
+    // Two-color palette: 0 = white, 1 = black (RGB in percent)
+    let mut out = format!(
+        "\x1bP0;1q\"1;1;{};{}#0;2;100;100;100#1;2;0;0;0",
+        grid.width, grid.height
+    );
+
+    for band in 0..grid.height.div_ceil(6) {
+        for (color, black) in [(0, false), (1, true)] {
+            if color > 0 {
+                out.push('$');
+            }
+            out.push_str(&format!("#{}", color));
+            let sixels = (0..grid.width).map(|x| {
+                let bits = (0..6)
+                    .filter(|dy| {
+                        let y = band * 6 + dy;
+                        y < grid.height && grid.get(x, y) == black
+                    })
+                    .fold(0u8, |bits, dy| bits | 1 << dy);
+                (b'?' + bits) as char
+            });
+            push_runs(&mut out, sixels);
+        }
+        out.push('-');
+    }
+
+    out.push_str("\x1b\\");
+    out
+}
+
+/// Append sixel characters, run-length encoding repeats (`!<count><char>`).
+fn push_runs(out: &mut String, sixels: impl Iterator<Item = char>) {
+    let mut run: Option<(char, usize)> = None;
+    let flush = |out: &mut String, (c, n): (char, usize)| {
+        if n > 3 {
+            out.push_str(&format!("!{}{}", n, c));
+        } else {
+            (0..n).for_each(|_| out.push(c));
+        }
+    };
+    for c in sixels {
+        run = match run {
+            Some((prev, n)) if prev == c => Some((prev, n + 1)),
+            Some(prev) => {
+                flush(out, prev);
+                Some((c, 1))
+            }
+            None => Some((c, 1)),
+        };
+    }
+    if let Some(last) = run {
+        flush(out, last);
+    }
+}
+
+/// How to draw a preview in the terminal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TerminalGraphics {
+    /// Sixel if the terminal looks capable, half blocks otherwise.
+    Auto,
+    Sixel,
+    HalfBlocks,
+}
+
+impl TerminalGraphics {
+    /// Resolve [`TerminalGraphics::Auto`] from the environment.
+    ///
+    /// Sixel support can't be queried without putting the terminal in raw
+    /// mode, so this goes by `TERM`/`TERM_PROGRAM` for terminals known to
+    /// support it. Set `ESTRELLA_SIXEL=1` (or `0`) to override.
+    pub fn resolve(self) -> Self {
+        if self != Self::Auto {
+            return self;
+        }
+        let var = |name: &str| std::env::var(name).unwrap_or_default();
+        let sixel = match var("ESTRELLA_SIXEL").as_str() {
+            "1" => true,
+            "0" => false,
+            _ => {
+                let term = var("TERM");
+                term.contains("sixel")
+                    || ["foot", "mlterm", "yaft", "contour"]
+                        .iter()
+                        .any(|t| term.starts_with(t))
+                    || ["WezTerm", "iTerm.app", "mintty"].contains(&var("TERM_PROGRAM").as_str())
+            }
+        };
+        if sixel { Self::Sixel } else { Self::HalfBlocks }
+    }
+
+    /// Render a raster for this mode, fitting `columns` terminal columns.
+    pub fn render(self, raw: &RawRaster, columns: usize) -> String {
+        match self.resolve() {
+            // Sixel is in pixels; terminal cells are usually ~10px wide
+            Self::Sixel => sixel(raw, columns * 10) + "\n",
+            _ => half_block_lines(raw, columns)
+                .into_iter()
+                .map(|line| line + "\n")
+                .collect(),
+        }
+    }
+}
+
+impl std::str::FromStr for TerminalGraphics {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "auto" => Ok(Self::Auto),
+            "sixel" => Ok(Self::Sixel),
+            "blocks" | "half-blocks" | "unicode" => Ok(Self::HalfBlocks),
+            _ => Err(format!(
+                "Unknown terminal preview '{}'. Use 'auto', 'sixel', or 'blocks'",
+                s
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_braille_scales_to_columns() {
+        // 16×8 raster with the top-left 8×4 block black
+        let mut data = vec![0u8; 2 * 8];
+        for row in data.chunks_mut(2).take(4) {
+            row[0] = 0xFF;
+        }
+        let raw = RawRaster {
+            width: 16,
+            height: 8,
+            data,
+        };
+
+        // Full size: 8 columns × 2 lines, solid block in the top-left 4 chars
+        let lines = braille_lines(&raw, 8);
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].chars().count(), 8);
+        assert!(lines[0].starts_with("⣿⣿⣿⣿⠀"));
+        assert!(lines[1].chars().all(|c| c == '⠀'));
+
+        // Half size: each cell averages 2×2 pixels
+        let lines = braille_lines(&raw, 4);
+        assert_eq!(lines, ["⠛⠛⠀⠀"]);
+    }
+
+    #[test]
+    fn test_half_blocks_and_sixel() {
+        // 2×2 raster: left column black
+        let raw = RawRaster {
+            width: 2,
+            height: 2,
+            data: vec![0x80, 0x80],
+        };
+
+        let lines = half_block_lines(&raw, 2);
+        assert_eq!(lines, ["\x1b[30;40m▀\x1b[97;107m▀\x1b[0m"]);
+
+        let image = sixel(&raw, 2);
+        assert!(image.starts_with("\x1bP0;1q\"1;1;2;2"));
+        // Band of two rows: white is only in column 1, black only in column 0
+        assert!(image.contains("#0?B$#1B?-"));
+        assert!(image.ends_with("\x1b\\"));
+
+        let mut runs = String::new();
+        push_runs(&mut runs, "AAAAAB".chars());
+        assert_eq!(runs, "!5AB");
+    }
+}
@@ -2,13 +2,34 @@
 //!
 //! Renders text to an anti-aliased f32 grayscale buffer using ab_glyph.
 //! Used by Text and Banner components with `font: "ibm"` to produce
-//! raster output with smooth edges (which then gets dithered).
+//! raster output with smooth edges (which then gets dithered), and for
+//! characters no printer code page has (CJK, symbols).
+//!
+//! IBM Plex only covers Latin scripts. Characters it lacks are drawn with
+//! a fallback font: the file named by `ESTRELLA_FALLBACK_FONT`, else the
+//! first of a few common system fonts (Noto CJK, Droid Sans Fallback,
+//! DejaVu Sans) that is installed.
 
-use ab_glyph::{Font, FontArc, ScaleFont};
+use ab_glyph::{Font, FontArc, GlyphId, ScaleFont};
 use std::sync::OnceLock;
 
 static IBM_PLEX_REGULAR: OnceLock<FontArc> = OnceLock::new();
 static IBM_PLEX_BOLD: OnceLock<FontArc> = OnceLock::new();
+static FALLBACK: OnceLock<Option<FontArc>> = OnceLock::new();
+
+/// Environment variable naming a TTF/OTF file for the fallback font.
+pub const FALLBACK_FONT_VAR: &str = "ESTRELLA_FALLBACK_FONT";
+
+/// System fonts tried (in order) when `ESTRELLA_FALLBACK_FONT` is unset.
+const FALLBACK_FONT_PATHS: &[&str] = &[
+    "/usr/share/fonts/opentype/noto/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/noto-cjk/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/google-noto-cjk/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/truetype/droid/DroidSansFallbackFull.ttf",
+    "/usr/share/fonts/truetype/dejavu/DejaVuSans.ttf",
+    "/usr/share/fonts/TTF/DejaVuSans.ttf",
+    "/System/Library/Fonts/Supplemental/Arial Unicode.ttf",
+];
 
 fn ibm_plex_regular() -> &'static FontArc {
     IBM_PLEX_REGULAR.get_or_init(|| {
@@ -24,6 +45,33 @@ fn ibm_plex_bold() -> &'static FontArc {
     })
 }
 
+/// The fallback font, loaded on first use. `None` if none was found.
+fn fallback_font() -> Option<&'static FontArc> {
+    FALLBACK
+        .get_or_init(|| {
+            let configured = std::env::var(FALLBACK_FONT_VAR).ok();
+            configured
+                .iter()
+                .map(String::as_str)
+                .chain(FALLBACK_FONT_PATHS.iter().copied())
+                .find_map(|path| FontArc::try_from_vec(std::fs::read(path).ok()?).ok())
+        })
+        .as_ref()
+}
+
+/// The font to draw `ch` with: `primary` if it has the glyph, else the
+/// fallback font if that does, else `primary`'s missing-glyph box.
+fn font_for(primary: &FontArc, ch: char) -> (&FontArc, GlyphId) {
+    let glyph_id = primary.glyph_id(ch);
+    if glyph_id.0 != 0 {
+        return (primary, glyph_id);
+    }
+    match fallback_font().map(|font| (font, font.glyph_id(ch))) {
+        Some((font, id)) if id.0 != 0 => (font, id),
+        _ => (primary, glyph_id),
+    }
+}
+
 /// Rendered TTF text as an anti-aliased grayscale buffer.
 pub struct TtfRender {
     pub width: usize,
@@ -76,10 +124,10 @@ pub fn render_ttf_text(
     let mut caret_x = 0.0f32;
 
     for ch in text.chars() {
-        let glyph_id = font.glyph_id(ch);
-        let advance = scaled.h_advance(glyph_id);
+        let (glyph_font, glyph_id) = font_for(font, ch);
+        let advance = glyph_font.as_scaled(pixel_height).h_advance(glyph_id);
 
-        glyphs.push((glyph_id, caret_x));
+        glyphs.push((glyph_font, glyph_id, caret_x));
         caret_x += advance;
     }
 
@@ -96,11 +144,11 @@ pub fn render_ttf_text(
     let mut data = vec![0.0f32; width * height];
 
     // Rasterize each glyph
-    for &(glyph_id, glyph_x) in &glyphs {
+    for &(glyph_font, glyph_id, glyph_x) in &glyphs {
         let glyph =
             glyph_id.with_scale_and_position(pixel_height, ab_glyph::point(glyph_x, baseline_y));
 
-        if let Some(outlined) = font.outline_glyph(glyph) {
+        if let Some(outlined) = glyph_font.outline_glyph(glyph) {
             let bounds = outlined.px_bounds();
             outlined.draw(|px, py, coverage| {
                 let x = px as i32 + bounds.min.x as i32;
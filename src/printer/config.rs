@@ -4,9 +4,17 @@
 //!
 //! ## Supported Printers
 //!
-//! | Model | Width (dots) | Resolution | Band Height |
-//! |-------|--------------|------------|-------------|
-//! | TSP650II | 576 | 203 DPI | 24 rows |
+//! | Model | Paper | Width (dots) | Resolution | Cutter | NV Graphics |
+//! |-------|-------|--------------|------------|--------|-------------|
+//! | TSP650II | 80mm | 576 | 203 DPI | full/partial | yes |
+//! | TSP100IV | 80mm | 576 | 203 DPI | full/partial | yes |
+//! | TSP700II | 80mm | 576 | 203 DPI | full/partial | yes |
+//! | mC-Print2 | 58mm | 384 | 203 DPI | full/partial | yes |
+//! | mC-Print3 | 80mm | 576 | 203 DPI | full/partial | yes |
+//! | SM-L200 | 58mm | 384 | 203 DPI | none (tear bar) | yes |
+//!
+//! Printers that aren't listed can be targeted by paper width: `"58mm"`,
+//! `"80mm"` and `"112mm"` name generic presets (see [`PaperSize`]).
 //!
 //! ## Usage
 //!
@@ -17,14 +25,24 @@
 //! println!("Print width: {} dots ({} bytes)",
 //!          config.width_dots,
 //!          config.width_bytes);
+//!
+//! let mobile = PrinterConfig::by_name("sm-l200").unwrap();
+//! assert_eq!(mobile.width_dots, 384);
+//! assert!(!mobile.cutter);
 //! ```
 
+use std::str::FromStr;
+
+use super::{Media, PaperSize};
+use crate::units;
+
 /// # Printer Configuration
 ///
 /// Defines the hardware characteristics of a thermal printer.
 ///
 /// ## Physical Properties
 ///
+/// - **paper**: Width of the paper roll
 /// - **width_dots**: Maximum printable width in dots (pixels)
 /// - **width_bytes**: Width in bytes (width_dots / 8)
 /// - **dpi**: Resolution in dots per inch
@@ -34,6 +52,17 @@
 ///
 /// - **max_chunk_rows**: Maximum rows per raster command over Bluetooth
 ///
+/// ## Features
+///
+/// - **cutter** / **partial_cut**: cuts the printer can make; codegen
+///   turns unsupported cuts into the closest one it can do
+/// - **nv_graphics**: logos stored in non-volatile memory can be printed
+///
+/// ## Media
+///
+/// - **media**: receipt roll or black mark label stock; on labels, cuts
+///   happen at the next mark (see [`Media`])
+///
 /// ## Calculations
 ///
 /// ```text
@@ -49,6 +78,9 @@ pub struct PrinterConfig {
     /// Printer model name
     pub name: &'static str,
 
+    /// Paper roll width
+    pub paper: PaperSize,
+
     /// Maximum print width in dots (pixels)
     pub width_dots: u16,
 
@@ -63,6 +95,21 @@ pub struct PrinterConfig {
 
     /// Maximum rows per raster chunk (for Bluetooth buffer limits)
     pub max_chunk_rows: u16,
+
+    /// Pitch of pre-scored label stock in mm (None for continuous roll)
+    pub label_pitch_mm: Option<f32>,
+
+    /// Paper loaded: receipt roll or black mark labels
+    pub media: Media,
+
+    /// Has an auto-cutter (otherwise cuts feed the paper to the tear bar)
+    pub cutter: bool,
+
+    /// The cutter can leave a hinge (otherwise partial cuts are full cuts)
+    pub partial_cut: bool,
+
+    /// Supports printing logos stored in NV memory
+    pub nv_graphics: bool,
 }
 
 impl PrinterConfig {
@@ -88,13 +135,160 @@ impl PrinterConfig {
     /// ```
     pub const TSP650II: Self = Self {
         name: "Star TSP650II",
+        paper: PaperSize::Mm80,
         width_dots: 576,
         width_bytes: 72,
         dpi: 203,
         band_height: 24,
         max_chunk_rows: 256,
+        label_pitch_mm: None,
+        media: Media::Receipt,
+        cutter: true,
+        partial_cut: true,
+        nv_graphics: true,
+    };
+
+    /// # Star TSP100IV Configuration
+    ///
+    /// 80mm USB/LAN receipt printer, same print area as the TSP650II.
+    pub const TSP100: Self = Self {
+        name: "Star TSP100IV",
+        ..Self::TSP650II
+    };
+
+    /// # Star TSP700II Configuration
+    ///
+    /// 80mm receipt and label printer, same print area as the TSP650II.
+    pub const TSP700II: Self = Self {
+        name: "Star TSP700II",
+        ..Self::TSP650II
+    };
+
+    /// # Star mC-Print2 Configuration
+    ///
+    /// Compact 58mm printer with auto-cutter.
+    ///
+    /// ## Print Area
+    ///
+    /// ```text
+    /// ├─ 5mm ─┼── 48mm printable ──┼─ 5mm ─┤
+    /// │       │      384 dots      │       │
+    /// ```
+    pub const MC_PRINT2: Self = Self {
+        name: "Star mC-Print2",
+        paper: PaperSize::Mm58,
+        width_dots: 384,
+        width_bytes: 48,
+        ..Self::TSP650II
     };
 
+    /// # Star mC-Print3 Configuration
+    ///
+    /// 80mm printer (58mm with the paper guide fitted; use
+    /// [`PrinterConfig::MC_PRINT2`] for that).
+    pub const MC_PRINT3: Self = Self {
+        name: "Star mC-Print3",
+        ..Self::TSP650II
+    };
+
+    /// # Star SM-L200 Configuration
+    ///
+    /// 58mm battery-powered Bluetooth printer. It has no cutter (receipts
+    /// are torn off) and a small receive buffer, so raster graphics go out
+    /// in shorter chunks.
+    pub const SM_L200: Self = Self {
+        name: "Star SM-L200",
+        paper: PaperSize::Mm58,
+        width_dots: 384,
+        width_bytes: 48,
+        max_chunk_rows: 64,
+        cutter: false,
+        partial_cut: false,
+        ..Self::TSP650II
+    };
+
+    /// # Generic 58mm Printer
+    ///
+    /// Any 203 DPI StarPRNT printer on 58mm paper (48mm printable).
+    pub const PAPER_58MM: Self = Self {
+        name: "58mm paper",
+        paper: PaperSize::Mm58,
+        width_dots: 384,
+        width_bytes: 48,
+        ..Self::TSP650II
+    };
+
+    /// # Generic 80mm Printer
+    ///
+    /// Any 203 DPI StarPRNT printer on 80mm paper (72mm printable).
+    pub const PAPER_80MM: Self = Self {
+        name: "80mm paper",
+        ..Self::TSP650II
+    };
+
+    /// # Generic 112mm Printer
+    ///
+    /// Any 203 DPI StarPRNT printer on 112mm paper (104mm printable), such
+    /// as the TSP800II.
+    pub const PAPER_112MM: Self = Self {
+        name: "112mm paper",
+        paper: PaperSize::Mm112,
+        width_dots: 832,
+        width_bytes: 104,
+        ..Self::TSP650II
+    };
+
+    /// All built-in printer configs.
+    pub const ALL: &[Self] = &[
+        Self::TSP650II,
+        Self::TSP100,
+        Self::TSP700II,
+        Self::MC_PRINT2,
+        Self::MC_PRINT3,
+        Self::SM_L200,
+    ];
+
+    /// Look up a built-in config by model name.
+    ///
+    /// Case, punctuation and the "Star" prefix are ignored, so `"tsp700ii"`,
+    /// `"mC-Print2"` and `"Star SM-L200"` all work, and `"tsp100"` finds
+    /// the TSP100IV. A paper width (`"58mm"`) gives the generic preset for
+    /// that paper.
+    ///
+    /// ```
+    /// use estrella::printer::PrinterConfig;
+    ///
+    /// let config = PrinterConfig::by_name("tsp700ii").unwrap();
+    /// assert_eq!(config.name, "Star TSP700II");
+    /// assert_eq!(PrinterConfig::by_name("58mm").unwrap().width_dots, 384);
+    /// assert!(PrinterConfig::by_name("tsp999").is_none());
+    /// ```
+    pub fn by_name(name: &str) -> Option<Self> {
+        let key = model_key(name);
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|config| {
+                let model = model_key(config.name);
+                model == key || model.strip_suffix("iv") == Some(key.as_str())
+            })
+            .or_else(|| name.parse::<PaperSize>().ok().map(PaperSize::printer))
+    }
+
+    /// This printer with `media` loaded.
+    ///
+    /// ```
+    /// use estrella::printer::{Media, PrinterConfig};
+    ///
+    /// let labels = PrinterConfig::TSP700II.with_media(Media::Label);
+    /// assert_eq!(labels.media, Media::Label);
+    /// let parsed: PrinterConfig = "tsp700ii:label".parse().unwrap();
+    /// assert_eq!(parsed.media, Media::Label);
+    /// ```
+    pub const fn with_media(self, media: Media) -> Self {
+        Self { media, ..self }
+    }
+
     /// Calculate dots per millimeter
     ///
     /// ## Example
@@ -107,7 +301,7 @@ impl PrinterConfig {
     /// ```
     #[inline]
     pub fn dots_per_mm(&self) -> f32 {
-        self.dpi as f32 / 25.4
+        units::dots_per_mm(self.dpi)
     }
 
     /// Calculate print width in millimeters
@@ -116,16 +310,23 @@ impl PrinterConfig {
         self.width_dots as f32 / self.dots_per_mm()
     }
 
+    /// Paper roll width in millimeters (wider than [`Self::width_mm`],
+    /// which is the printable part)
+    #[inline]
+    pub fn paper_width_mm(&self) -> f32 {
+        self.paper.width_mm()
+    }
+
     /// Convert millimeters to dots
     #[inline]
     pub fn mm_to_dots(&self, mm: f32) -> u16 {
-        (mm * self.dots_per_mm()).round() as u16
+        units::mm_to_dots(mm, self.dpi)
     }
 
     /// Convert dots to millimeters
     #[inline]
     pub fn dots_to_mm(&self, dots: u16) -> f32 {
-        dots as f32 / self.dots_per_mm()
+        units::dots_to_mm(dots.into(), self.dpi)
     }
 }
 
@@ -135,6 +336,39 @@ impl Default for PrinterConfig {
     }
 }
 
+impl FromStr for PrinterConfig {
+    type Err = String;
+
+    /// Parse a model or paper width (see [`PrinterConfig::by_name`]),
+    /// optionally followed by the media, e.g. `"tsp700ii:label"`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some((name, media)) = s.rsplit_once(':') {
+            return Ok(name.parse::<Self>()?.with_media(media.parse()?));
+        }
+        Self::by_name(s).ok_or_else(|| {
+            let known: Vec<String> = Self::ALL.iter().map(|c| model_key(c.name)).collect();
+            format!(
+                "Unknown printer '{}'. Use one of: {}, or a paper width (58mm, 80mm, 112mm)",
+                s,
+                known.join(", ")
+            )
+        })
+    }
+}
+
+/// Normalized model name: lowercase alphanumerics without the "star" prefix.
+fn model_key(name: &str) -> String {
+    let key: String = name
+        .chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|c| c.to_ascii_lowercase())
+        .collect();
+    match key.strip_prefix("star") {
+        Some(rest) if !rest.is_empty() => rest.to_string(),
+        _ => key,
+    }
+}
+
 // ============================================================================
 // TESTS
 // ============================================================================
@@ -183,6 +417,37 @@ mod tests {
         assert!((mm - 10.0).abs() < 0.5);
     }
 
+    #[test]
+    fn test_by_name() {
+        for config in PrinterConfig::ALL {
+            assert_eq!(
+                PrinterConfig::by_name(config.name).unwrap().name,
+                config.name
+            );
+            assert_eq!(config.width_dots, config.width_bytes * 8);
+        }
+        assert_eq!(
+            PrinterConfig::by_name("TSP100").unwrap().name,
+            "Star TSP100IV"
+        );
+        assert_eq!(
+            PrinterConfig::by_name("tsp100iv").unwrap().name,
+            "Star TSP100IV"
+        );
+        assert_eq!(PrinterConfig::by_name("mc-print2").unwrap().width_dots, 384);
+        assert_eq!(
+            "sm_l200".parse::<PrinterConfig>().unwrap().max_chunk_rows,
+            64
+        );
+        assert!("tsp999".parse::<PrinterConfig>().is_err());
+        assert!("tsp999:label".parse::<PrinterConfig>().is_err());
+        assert!("tsp700ii:sticker".parse::<PrinterConfig>().is_err());
+        assert_eq!(
+            "112mm".parse::<PrinterConfig>().unwrap().name,
+            "112mm paper"
+        );
+    }
+
     #[test]
     fn test_default_is_tsp650ii() {
         let default = PrinterConfig::default();
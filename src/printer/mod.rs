@@ -5,7 +5,10 @@
 //! ## Modules
 //!
 //! - [`config`]: Printer hardware specifications
+//! - [`paper`]: Paper roll widths, printable areas and media types
 
 pub mod config;
+pub mod paper;
 
 pub use config::PrinterConfig;
+pub use paper::{Media, PaperSize};
@@ -0,0 +1,184 @@
+//! # Paper Sizes
+//!
+//! Thermal receipt rolls come in three common widths. The print head never
+//! reaches the edge of the roll, so each width has a smaller printable area
+//! in the middle:
+//!
+//! | Paper | Printable | Dots at 203 DPI |
+//! |-------|-----------|-----------------|
+//! | 58mm | 48mm | 384 |
+//! | 80mm | 72mm | 576 |
+//! | 112mm | 104mm | 832 |
+//!
+//! Documents sized in millimeters (`width_mm`, `height_mm`, `"12mm"`
+//! positions) are converted with the target printer's DPI, so they keep
+//! their physical size on any printer.
+//!
+//! ```
+//! use estrella::printer::{PaperSize, PrinterConfig};
+//!
+//! let paper: PaperSize = "58mm".parse().unwrap();
+//! assert_eq!(paper.printable_dots(203), 384);
+//!
+//! // A generic printer for a paper width, for models estrella doesn't know
+//! let config = PrinterConfig::by_name("112mm").unwrap();
+//! assert_eq!(config.width_dots, 832);
+//! ```
+//!
+//! Besides plain receipt rolls, Star label printers take die-cut label stock
+//! with a black mark between labels ([`Media::Label`]). On it, cuts and
+//! tear-offs happen at the marks rather than wherever the receipt ends.
+
+use std::fmt;
+use std::str::FromStr;
+
+use super::PrinterConfig;
+
+/// Width of a thermal paper roll.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PaperSize {
+    /// 58mm (2¼") rolls: mobile and compact printers.
+    Mm58,
+    /// 80mm (3⅛") rolls: the standard receipt width.
+    Mm80,
+    /// 112mm (4⅜") rolls: wide kitchen and label printers.
+    Mm112,
+}
+
+impl PaperSize {
+    /// All paper sizes, narrowest first.
+    pub const ALL: &[Self] = &[Self::Mm58, Self::Mm80, Self::Mm112];
+
+    /// Width of the roll in millimeters.
+    pub fn width_mm(self) -> f32 {
+        match self {
+            Self::Mm58 => 58.0,
+            Self::Mm80 => 80.0,
+            Self::Mm112 => 112.0,
+        }
+    }
+
+    /// Width the print head covers, in millimeters.
+    pub fn printable_mm(self) -> f32 {
+        match self {
+            Self::Mm58 => 48.0,
+            Self::Mm80 => 72.0,
+            Self::Mm112 => 104.0,
+        }
+    }
+
+    /// Unprintable margin on each side, in millimeters.
+    pub fn margin_mm(self) -> f32 {
+        (self.width_mm() - self.printable_mm()) / 2.0
+    }
+
+    /// Printable width in dots at `dpi`, rounded to whole bytes as raster
+    /// rows require.
+    pub fn printable_dots(self, dpi: u16) -> u16 {
+        let dots = self.printable_mm() * crate::units::dots_per_mm(dpi);
+        (dots / 8.0).round() as u16 * 8
+    }
+
+    /// Generic 203 DPI StarPRNT printer for this paper.
+    pub fn printer(self) -> PrinterConfig {
+        match self {
+            Self::Mm58 => PrinterConfig::PAPER_58MM,
+            Self::Mm80 => PrinterConfig::PAPER_80MM,
+            Self::Mm112 => PrinterConfig::PAPER_112MM,
+        }
+    }
+}
+
+impl fmt::Display for PaperSize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}mm", self.width_mm())
+    }
+}
+
+impl FromStr for PaperSize {
+    type Err = String;
+
+    /// Parse `"58mm"`, `"80mm"` or `"112mm"` (the unit is optional).
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let width = s.trim().to_ascii_lowercase();
+        let width = width.strip_suffix("mm").unwrap_or(&width).trim();
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|paper| width.parse::<f32>() == Ok(paper.width_mm()))
+            .ok_or_else(|| format!("Unknown paper size '{}'. Use one of: 58mm, 80mm, 112mm", s))
+    }
+}
+
+/// What's loaded in the printer.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, Default, serde::Serialize, serde::Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum Media {
+    /// Continuous receipt roll.
+    #[default]
+    Receipt,
+    /// Label stock with black marks between labels. Codegen turns the
+    /// black mark sensor on and feeds to the next mark before each cut.
+    Label,
+}
+
+impl fmt::Display for Media {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Receipt => write!(f, "receipt"),
+            Self::Label => write!(f, "label"),
+        }
+    }
+}
+
+impl FromStr for Media {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "receipt" | "roll" => Ok(Self::Receipt),
+            "label" | "labels" | "black-mark" => Ok(Self::Label),
+            _ => Err(format!("Unknown media '{}'. Use 'receipt' or 'label'", s)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_printable_dots() {
+        assert_eq!(PaperSize::Mm58.printable_dots(203), 384);
+        assert_eq!(PaperSize::Mm80.printable_dots(203), 576);
+        assert_eq!(PaperSize::Mm112.printable_dots(203), 832);
+        assert_eq!(PaperSize::Mm80.printable_dots(300), 848);
+        assert_eq!(PaperSize::Mm58.margin_mm(), 5.0);
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!("58mm".parse::<PaperSize>(), Ok(PaperSize::Mm58));
+        assert_eq!(" 80MM ".parse::<PaperSize>(), Ok(PaperSize::Mm80));
+        assert_eq!("112".parse::<PaperSize>(), Ok(PaperSize::Mm112));
+        assert!("76mm".parse::<PaperSize>().is_err());
+        for paper in PaperSize::ALL {
+            assert_eq!(paper.to_string().parse::<PaperSize>(), Ok(*paper));
+        }
+        assert_eq!("Labels".parse::<Media>(), Ok(Media::Label));
+        assert_eq!(Media::Receipt.to_string().parse(), Ok(Media::Receipt));
+        assert!("sticker".parse::<Media>().is_err());
+    }
+
+    #[test]
+    fn test_presets_match_paper() {
+        for paper in PaperSize::ALL {
+            let config = paper.printer();
+            assert_eq!(config.paper, *paper);
+            assert_eq!(config.width_dots, paper.printable_dots(config.dpi));
+            assert_eq!(config.width_dots, config.width_bytes * 8);
+        }
+    }
+}
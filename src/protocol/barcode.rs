@@ -103,7 +103,8 @@ pub mod barcode1d {
     }
 
     /// HRI (Human Readable Interpretation) position
-    #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Serialize, serde::Deserialize)]
+    #[serde(rename_all = "snake_case")]
     pub enum HriPosition {
         /// No HRI text printed
         None = 0,
@@ -389,6 +390,52 @@ pub mod barcode1d {
             ModuleWidth::Dots3,
         )
     }
+
+    /// # Print Code93 Barcode
+    ///
+    /// Code93 encodes the same characters as Code39 in less space, with two
+    /// check characters added by the printer.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use estrella::protocol::barcode::barcode1d;
+    ///
+    /// let cmd = barcode1d::code93(b"TEST93", 80);
+    /// ```
+    pub fn code93(data: &[u8], height: u8) -> Vec<u8> {
+        barcode(
+            BarcodeType::Code93,
+            data,
+            height,
+            HriPosition::Below,
+            HriFont::FontA,
+            ModuleWidth::Dots2,
+        )
+    }
+
+    /// # Print NW-7 (Codabar) Barcode
+    ///
+    /// NW-7 encodes digits and `-$:/.+`, framed by start/stop characters
+    /// A-D. Common on library and shipping labels.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use estrella::protocol::barcode::barcode1d;
+    ///
+    /// let cmd = barcode1d::nw7(b"A40156B", 80);
+    /// ```
+    pub fn nw7(data: &[u8], height: u8) -> Vec<u8> {
+        barcode(
+            BarcodeType::Nw7,
+            data,
+            height,
+            HriPosition::Below,
+            HriFont::FontA,
+            ModuleWidth::Dots3,
+        )
+    }
 }
 
 // ============================================================================
@@ -599,8 +646,10 @@ pub mod qr {
         let nh = ((len >> 8) & 0xFF) as u8;
 
         // m = 0 for AUTO mode (automatic data type analysis)
+        // Data beyond what nL/nH can describe is dropped, so the length
+        // header always matches the payload.
         let mut cmd = vec![ESC, GS, b'y', b'D', b'1', 0, nl, nh];
-        cmd.extend_from_slice(data);
+        cmd.extend_from_slice(&data[..len as usize]);
         cmd
     }
 
@@ -850,8 +899,9 @@ pub mod pdf417 {
         let nl = (len & 0xFF) as u8;
         let nh = ((len >> 8) & 0xFF) as u8;
 
+        // Truncate with the length header (see qr::set_data)
         let mut cmd = vec![ESC, GS, b'x', b'D', nl, nh];
-        cmd.extend_from_slice(data);
+        cmd.extend_from_slice(&data[..len as usize]);
         cmd
     }
 
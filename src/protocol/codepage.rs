@@ -0,0 +1,356 @@
+//! # International Code Pages
+//!
+//! Converts Unicode text to the single-byte code pages StarPRNT printers
+//! print, switching code page mid-text when a character needs it.
+//!
+//! **Command:** ESC GS t n (see [`super::text::codepage`])
+//!
+//! Every code page shares ASCII (0x00–0x7F) and differs in the upper half:
+//!
+//! | n | Code page | Covers |
+//! |---|-----------|--------|
+//! | 1 | CP437 | US English, Spanish/German accents, box drawing, blocks |
+//! | 2 | Katakana | Half-width Japanese katakana (U+FF61–U+FF9F) |
+//! | 3 | CP858 | Western European: CP850 with € in place of `ı` |
+//! | 4 | CP852 | Central European (Polish, Czech, Hungarian, ...) |
+//!
+//! The printer has no CP850 page of its own: CP850 text prints on CP858,
+//! which only differs at 0xD5. [`CP850`] is kept for decoding bytes from
+//! systems that use it.
+//!
+//! [`Encoder`] tracks the page the printer is on. Text the current page
+//! can print stays on it; a character it can't print switches to the first
+//! page in [`AUTO`] that has it, so box drawing and `Łódź` can share a
+//! line. Characters no page has become `?` (with a warning on stderr).
+//!
+//! ```
+//! use estrella::protocol::codepage::Encoder;
+//!
+//! let mut encoder = Encoder::default(); // CP437
+//! let runs = encoder.runs("─ 5€ ─");
+//! assert_eq!(runs[0].text, "─ 5");
+//! assert_eq!(runs[1].page, 3); // € is only on CP858
+//! assert_eq!(runs[1].text, "€ ─"); // CP858 has box drawing too
+//! assert_eq!(encoder.page(), 3);
+//! ```
+
+use super::cp437;
+pub use super::text::CodePage;
+
+/// Code pages [`Encoder`] switches to, in order of preference.
+pub const AUTO: &[CodePage] = &[
+    CodePage::Cp437,
+    CodePage::Cp858,
+    CodePage::Cp852,
+    CodePage::Katakana,
+];
+
+/// Whether `ch` prints as text, i.e. some code page in [`AUTO`] has it.
+///
+/// ```
+/// use estrella::protocol::codepage;
+///
+/// assert!(codepage::is_printable('Ł'));
+/// assert!(!codepage::is_printable('寿'));
+/// ```
+pub fn is_printable(ch: char) -> bool {
+    AUTO.iter().any(|page| page.encode_char(ch).is_some())
+}
+
+/// Upper-half entry for bytes a code page leaves undefined here.
+const UNDEFINED: char = '\u{FFFD}';
+
+/// CP850 (Multilingual Latin-1) upper half, indexed by `byte - 0x80`.
+pub const CP850: [char; 128] = [
+    // 0x80
+    'Ç', 'ü', 'é', 'â', 'ä', 'à', 'å', 'ç', 'ê', 'ë', 'è', 'ï', 'î', 'ì', 'Ä', 'Å',
+    // 0x90
+    'É', 'æ', 'Æ', 'ô', 'ö', 'ò', 'û', 'ù', 'ÿ', 'Ö', 'Ü', 'ø', '£', 'Ø', '×', 'ƒ',
+    // 0xA0
+    'á', 'í', 'ó', 'ú', 'ñ', 'Ñ', 'ª', 'º', '¿', '®', '¬', '½', '¼', '¡', '«', '»',
+    // 0xB0
+    '░', '▒', '▓', '│', '┤', 'Á', 'Â', 'À', '©', '╣', '║', '╗', '╝', '¢', '¥', '┐',
+    // 0xC0
+    '└', '┴', '┬', '├', '─', '┼', 'ã', 'Ã', '╚', '╔', '╩', '╦', '╠', '═', '╬', '¤',
+    // 0xD0
+    'ð', 'Ð', 'Ê', 'Ë', 'È', 'ı', 'Í', 'Î', 'Ï', '┘', '┌', '█', '▄', '¦', 'Ì', '▀',
+    // 0xE0
+    'Ó', 'ß', 'Ô', 'Ò', 'õ', 'Õ', 'µ', 'þ', 'Þ', 'Ú', 'Û', 'Ù', 'ý', 'Ý', '¯', '´',
+    // 0xF0
+    '\u{00AD}', '±', '‗', '¾', '¶', '§', '÷', '¸', '°', '¨', '·', '¹', '³', '²', '■', '\u{00A0}',
+];
+
+/// CP858 upper half: [`CP850`] with the euro sign at 0xD5.
+pub const CP858: [char; 128] = {
+    let mut table = CP850;
+    table[0xD5 - 0x80] = '€';
+    table
+};
+
+/// CP852 (Central European) upper half, indexed by `byte - 0x80`.
+pub const CP852: [char; 128] = [
+    // 0x80
+    'Ç', 'ü', 'é', 'â', 'ä', 'ů', 'ć', 'ç', 'ł', 'ë', 'Ő', 'ő', 'î', 'Ź', 'Ä', 'Ć',
+    // 0x90
+    'É', 'Ĺ', 'ĺ', 'ô', 'ö', 'Ľ', 'ľ', 'Ś', 'ś', 'Ö', 'Ü', 'Ť', 'ť', 'Ł', '×', 'č',
+    // 0xA0
+    'á', 'í', 'ó', 'ú', 'Ą', 'ą', 'Ž', 'ž', 'Ę', 'ę', '¬', 'ź', 'Č', 'ş', '«', '»',
+    // 0xB0
+    '░', '▒', '▓', '│', '┤', 'Á', 'Â', 'Ě', 'Ş', '╣', '║', '╗', '╝', 'Ż', 'ż', '┐',
+    // 0xC0
+    '└', '┴', '┬', '├', '─', '┼', 'Ă', 'ă', '╚', '╔', '╩', '╦', '╠', '═', '╬', '¤',
+    // 0xD0
+    'đ', 'Đ', 'Ď', 'Ë', 'ď', 'Ň', 'Í', 'Î', 'ě', '┘', '┌', '█', '▄', 'Ţ', 'Ů', '▀',
+    // 0xE0
+    'Ó', 'ß', 'Ô', 'Ń', 'ń', 'ň', 'Š', 'š', 'Ŕ', 'Ú', 'ŕ', 'Ű', 'ý', 'Ý', 'ţ', '´',
+    // 0xF0
+    '\u{00AD}', '˝', '˛', 'ˇ', '˘', '§', '÷', '¸', '°', '¨', '˙', 'ű', 'Ř', 'ř', '■', '\u{00A0}',
+];
+
+/// Katakana upper half: JIS X 0201 half-width katakana at 0xA1–0xDF.
+///
+/// The printer's graphic characters around them have no exact Unicode
+/// counterpart and are left undefined.
+pub const KATAKANA: [char; 128] = {
+    let mut table = [UNDEFINED; 128];
+    let mut byte = 0xA1;
+    while byte <= 0xDF {
+        table[byte - 0x80] = match char::from_u32(0xFF61 + (byte - 0xA1) as u32) {
+            Some(ch) => ch,
+            None => UNDEFINED,
+        };
+        byte += 1;
+    }
+    table
+};
+
+impl CodePage {
+    /// The code page selected by `ESC GS t n`, if it's one of these.
+    pub fn from_u8(n: u8) -> Option<Self> {
+        [
+            Self::Cp437,
+            Self::Katakana,
+            Self::Cp858,
+            Self::Cp852,
+            Self::Cp860,
+            Self::Cp861,
+            Self::Cp863,
+            Self::Cp865,
+            Self::Cp866,
+            Self::Cp1252,
+        ]
+        .into_iter()
+        .find(|page| *page as u8 == n)
+    }
+
+    /// The characters at 0x80–0xFF, for the pages estrella can transcode.
+    pub fn upper_half(self) -> Option<&'static [char; 128]> {
+        match self {
+            Self::Cp437 => Some(&cp437::UPPER_HALF),
+            Self::Katakana => Some(&KATAKANA),
+            Self::Cp858 => Some(&CP858),
+            Self::Cp852 => Some(&CP852),
+            _ => None,
+        }
+    }
+
+    /// The byte for `ch` on this page, if it has one.
+    pub fn encode_char(self, ch: char) -> Option<u8> {
+        if (ch as u32) < 0x80 {
+            return Some(ch as u8);
+        }
+        if ch == UNDEFINED {
+            return None;
+        }
+        let index = self.upper_half()?.iter().position(|&c| c == ch)?;
+        Some(0x80 + index as u8)
+    }
+}
+
+/// A stretch of text printed on one code page.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Run<'a> {
+    /// Code page number (`ESC GS t n`)
+    pub page: u8,
+    /// Whether the printer has to switch to `page` before this run
+    pub switch: bool,
+    pub text: &'a str,
+}
+
+/// Transcodes text for the printer, tracking the active code page.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Encoder {
+    page: u8,
+}
+
+impl Default for Encoder {
+    /// Starts on CP437, which documents select right after `Init`.
+    fn default() -> Self {
+        Self::new(CodePage::Cp437 as u8)
+    }
+}
+
+impl Encoder {
+    /// An encoder for a printer on code page `page`.
+    pub fn new(page: u8) -> Self {
+        Self { page }
+    }
+
+    /// The code page the printer is on.
+    pub fn page(&self) -> u8 {
+        self.page
+    }
+
+    /// Track an explicit code page change (`Op::SetCodepage`).
+    pub fn set_page(&mut self, page: u8) {
+        self.page = page;
+    }
+
+    /// Split `text` into runs by the code page each one prints on,
+    /// switching pages only for characters the current one lacks.
+    pub fn runs<'a>(&mut self, text: &'a str) -> Vec<Run<'a>> {
+        let mut runs = Vec::new();
+        let mut start = 0;
+        let mut switch = false;
+        for (i, ch) in text.char_indices() {
+            if self.can_encode(ch) {
+                continue;
+            }
+            let Some(page) = AUTO.iter().find(|page| page.encode_char(ch).is_some()) else {
+                continue; // printed as '?' on the current page
+            };
+            if i > start {
+                runs.push(Run {
+                    page: self.page,
+                    switch,
+                    text: &text[start..i],
+                });
+            }
+            self.page = *page as u8;
+            start = i;
+            switch = true;
+        }
+        if start < text.len() {
+            runs.push(Run {
+                page: self.page,
+                switch,
+                text: &text[start..],
+            });
+        }
+        runs
+    }
+
+    /// Encode `text`, with `ESC GS t n` wherever the code page changes.
+    pub fn encode(&mut self, text: &str) -> Vec<u8> {
+        let mut out = Vec::with_capacity(text.len());
+        for run in self.runs(text) {
+            if run.switch {
+                out.extend(super::text::codepage_raw(run.page));
+            }
+            out.extend(encode(run.text, run.page));
+        }
+        out
+    }
+
+    fn can_encode(&self, ch: char) -> bool {
+        match CodePage::from_u8(self.page) {
+            Some(page) => page.encode_char(ch).is_some(),
+            None => ascii(ch).is_some(),
+        }
+    }
+}
+
+/// Encode `text` on code page `page`, without switching pages.
+///
+/// Characters the page lacks are replaced with `?` and a warning is
+/// printed to stderr.
+pub fn encode(text: &str, page: u8) -> Vec<u8> {
+    let page = CodePage::from_u8(page).filter(|page| page.upper_half().is_some());
+    text.chars()
+        .map(|ch| {
+            page.map_or_else(|| ascii(ch), |page| page.encode_char(ch))
+                .unwrap_or_else(|| {
+                    eprintln!(
+                        "codepage: unmapped character '{}' (U+{:04X}), replacing with '?'",
+                        ch, ch as u32
+                    );
+                    b'?'
+                })
+        })
+        .collect()
+}
+
+/// Decode bytes printed on code page `page`.
+///
+/// Pages estrella has no table for decode as CP437, like the printer's
+/// default; undefined bytes become U+FFFD.
+pub fn decode(bytes: &[u8], page: u8) -> String {
+    match CodePage::from_u8(page).and_then(CodePage::upper_half) {
+        Some(table) => bytes
+            .iter()
+            .map(|&b| {
+                if b < 0x80 {
+                    b as char
+                } else {
+                    table[(b - 0x80) as usize]
+                }
+            })
+            .collect(),
+        None => cp437::decode(bytes),
+    }
+}
+
+/// `ch` if it's ASCII: a page without a table could have anything in its
+/// upper half.
+fn ascii(ch: char) -> Option<u8> {
+    ((ch as u32) < 0x80).then_some(ch as u8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tables_round_trip() {
+        for page in AUTO {
+            let all: Vec<u8> = (0x20..=0xFF).filter(|&b| b != 0x7F).collect();
+            let text = decode(&all, *page as u8);
+            let defined: Vec<u8> = all
+                .iter()
+                .copied()
+                .zip(text.chars())
+                .filter(|(_, ch)| *ch != UNDEFINED)
+                .map(|(b, _)| b)
+                .collect();
+            let text: String = text.chars().filter(|ch| *ch != UNDEFINED).collect();
+            assert_eq!(encode(&text, *page as u8), defined, "{:?}", page);
+        }
+        assert_eq!(decode(&[0xD5], 3), "€");
+        assert_eq!(CP850[0xD5 - 0x80], 'ı');
+        assert_eq!(decode(&[0xB1], 2), "ｱ");
+    }
+
+    #[test]
+    fn test_switches_only_when_needed() {
+        let mut encoder = Encoder::default();
+        let runs = encoder.runs("Año: Łódź ─ ﾗｰﾒﾝ ok");
+        let pages: Vec<(u8, &str)> = runs.iter().map(|run| (run.page, run.text)).collect();
+        assert_eq!(pages, vec![(1, "Año: "), (4, "Łódź ─ "), (2, "ﾗｰﾒﾝ ok")]);
+        assert!(!runs[0].switch && runs[1].switch);
+
+        // Already on a page that has everything: no switch
+        let mut encoder = Encoder::new(4);
+        assert_eq!(encoder.encode("Łódź"), vec![0x9D, 0xA2, 0x64, 0xAB]);
+    }
+
+    #[test]
+    fn test_unmapped_stays_on_page() {
+        let mut encoder = Encoder::default();
+        assert_eq!(encoder.encode("a★b"), b"a?b");
+        assert_eq!(encoder.page(), 1);
+
+        // Unknown page: ASCII only, switch for anything else
+        let mut encoder = Encoder::new(200);
+        assert_eq!(encoder.encode("é"), vec![0x1B, 0x1D, 0x74, 1, 0x82]);
+    }
+}
@@ -63,7 +63,8 @@ pub const LF: u8 = 0x0A;
 
 /// FF (Form Feed) - Page eject in page mode
 ///
-/// In standard mode: prints buffer and feeds to top of next page
+/// In standard mode: prints buffer and feeds to top of next page (the next
+/// black mark on label media)
 /// In page mode: prints the composed page
 pub const FF: u8 = 0x0C;
 
@@ -303,8 +304,184 @@ pub fn feed_units(n: u8) -> Vec<u8> {
 /// ```
 #[inline]
 pub fn feed_mm(mm: f32) -> Vec<u8> {
-    let units = (mm * 4.0).round().clamp(0.0, 255.0) as u8;
-    feed_units(units)
+    feed_units(crate::units::mm_to_feed_units(mm))
+}
+
+// ============================================================================
+// LABEL MEDIA COMMANDS
+// ============================================================================
+
+/// # Black Mark Sensor (ESC RS m n)
+///
+/// Turns detection of the black marks printed on the back of label stock
+/// on or off. With it on, [`feed_to_mark`] stops at the next mark instead
+/// of feeding blindly.
+///
+/// ## Protocol Details
+///
+/// | Format  | Bytes |
+/// |---------|-------|
+/// | ASCII   | ESC RS m n |
+/// | Hex     | 1B 1E 6D n |
+/// | Decimal | 27 30 109 n |
+///
+/// ## Parameters
+///
+/// - `n = 0`: sensor off (continuous roll)
+/// - `n = 1`: sensor on (black mark media)
+///
+/// ## Example
+///
+/// ```
+/// use estrella::protocol::commands;
+///
+/// assert_eq!(commands::black_mark_sensor(true), vec![0x1B, 0x1E, 0x6D, 1]);
+/// ```
+///
+/// ## Reference
+///
+/// StarPRNT Command Spec Rev 4.10
+#[inline]
+pub fn black_mark_sensor(enabled: bool) -> Vec<u8> {
+    vec![ESC, RS, b'm', enabled as u8]
+}
+
+/// # Feed to Next Black Mark (FF)
+///
+/// Prints any pending data and feeds to the top of the next label, so the
+/// gap between labels lines up with the cutter and tear bar. Needs the
+/// [`black_mark_sensor`] on.
+///
+/// ## Protocol Details
+///
+/// | Format  | Bytes |
+/// |---------|-------|
+/// | ASCII   | FF    |
+/// | Hex     | 0C    |
+/// | Decimal | 12    |
+///
+/// ## Reference
+///
+/// StarPRNT Command Spec Rev 4.10
+#[inline]
+pub fn feed_to_mark() -> Vec<u8> {
+    vec![FF]
+}
+
+// ============================================================================
+// PRINT DENSITY AND SPEED
+// ============================================================================
+
+/// Lightest print density accepted by [`set_print_density`].
+pub const MIN_DENSITY: i8 = -3;
+
+/// Darkest print density accepted by [`set_print_density`].
+pub const MAX_DENSITY: i8 = 3;
+
+/// How fast the print head moves the paper. Slower speeds put more heat
+/// into each line, so prints come out darker.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Default, Hash, serde::Serialize, serde::Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum PrintSpeed {
+    /// The power-on default.
+    #[default]
+    High,
+    Medium,
+    Low,
+}
+
+/// # Set Print Density (ESC RS d n)
+///
+/// Sets how much heat the head applies, from `-3` (lightest) to `+3`
+/// (darkest); `0` is the printer's standard. Lower it for large, dark
+/// graphics, which otherwise overheat the head and fade halfway down.
+/// Values outside that range are clamped.
+///
+/// ## Protocol Details
+///
+/// | Format  | Bytes |
+/// |---------|-------|
+/// | ASCII   | ESC RS d n |
+/// | Hex     | 1B 1E 64 n |
+/// | Decimal | 27 30 100 n |
+///
+/// ## Parameters
+///
+/// - `n = 0`: +3 (darkest)
+/// - `n = 3`: standard
+/// - `n = 6`: -3 (lightest)
+///
+/// ## Example
+///
+/// ```
+/// use estrella::protocol::commands;
+///
+/// assert_eq!(commands::set_print_density(0), vec![0x1B, 0x1E, 0x64, 3]);
+/// assert_eq!(commands::set_print_density(-2), vec![0x1B, 0x1E, 0x64, 5]);
+/// ```
+///
+/// ## Reference
+///
+/// StarPRNT Command Spec Rev 4.10
+#[inline]
+pub fn set_print_density(level: i8) -> Vec<u8> {
+    let n = (MAX_DENSITY - level.clamp(MIN_DENSITY, MAX_DENSITY)) as u8;
+    vec![ESC, RS, b'd', n]
+}
+
+/// # Set Print Speed (ESC RS r n)
+///
+/// ## Protocol Details
+///
+/// | Format  | Bytes |
+/// |---------|-------|
+/// | ASCII   | ESC RS r n |
+/// | Hex     | 1B 1E 72 n |
+/// | Decimal | 27 30 114 n |
+///
+/// ## Parameters
+///
+/// - `n = 0`: high speed (default)
+/// - `n = 1`: medium speed
+/// - `n = 2`: low speed
+///
+/// ## Example
+///
+/// ```
+/// use estrella::protocol::commands::{self, PrintSpeed};
+///
+/// assert_eq!(commands::set_print_speed(PrintSpeed::Low), vec![0x1B, 0x1E, 0x72, 2]);
+/// ```
+///
+/// ## Reference
+///
+/// StarPRNT Command Spec Rev 4.10
+#[inline]
+pub fn set_print_speed(speed: PrintSpeed) -> Vec<u8> {
+    let n = match speed {
+        PrintSpeed::High => 0,
+        PrintSpeed::Medium => 1,
+        PrintSpeed::Low => 2,
+    };
+    vec![ESC, RS, b'r', n]
+}
+
+impl std::str::FromStr for PrintSpeed {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "high" | "fast" => Ok(Self::High),
+            "medium" | "mid" => Ok(Self::Medium),
+            "low" | "slow" => Ok(Self::Low),
+            _ => Err(format!(
+                "Unknown print speed '{}'. Use 'high', 'medium' or 'low'",
+                s
+            )),
+        }
+    }
 }
 
 // ============================================================================
@@ -386,6 +563,20 @@ mod tests {
         assert_eq!(feed_mm(-5.0), vec![0x1B, 0x4A, 0]);
     }
 
+    #[test]
+    fn test_label_media() {
+        assert_eq!(black_mark_sensor(false), vec![0x1B, 0x1E, 0x6D, 0x00]);
+        assert_eq!(feed_to_mark(), vec![0x0C]);
+    }
+
+    #[test]
+    fn test_print_density_clamps() {
+        assert_eq!(set_print_density(3), vec![0x1B, 0x1E, 0x64, 0]);
+        assert_eq!(set_print_density(-3), vec![0x1B, 0x1E, 0x64, 6]);
+        assert_eq!(set_print_density(-100), set_print_density(-3));
+        assert_eq!(set_print_density(100), set_print_density(3));
+    }
+
     #[test]
     fn test_u16_le() {
         assert_eq!(u16_le(0x0000), [0x00, 0x00]);
@@ -8,6 +8,10 @@
 //! The printer must be set to Code Page 437 (`ESC GS t 1`) for these bytes
 //! to render correctly. ASCII (U+0000–U+007F) passes through unchanged.
 //! Characters not in CP437 are replaced with `?` and a warning is printed.
+//! [`decode`] maps bytes back to Unicode.
+//!
+//! Codegen goes through [`super::codepage`], which switches to other code
+//! pages for characters CP437 lacks.
 
 /// Encode a Unicode string as CP437 bytes.
 ///
@@ -32,6 +36,45 @@ pub fn encode(s: &str) -> Vec<u8> {
     out
 }
 
+/// CP437 upper half (0x80–0xFF) as Unicode, indexed by `byte - 0x80`.
+///
+/// Inverse of [`unicode_to_cp437`]; used to decode printer bytes back to text.
+pub(crate) const UPPER_HALF: [char; 128] = [
+    // 0x80
+    'Ç', 'ü', 'é', 'â', 'ä', 'à', 'å', 'ç', 'ê', 'ë', 'è', 'ï', 'î', 'ì', 'Ä', 'Å',
+    // 0x90
+    'É', 'æ', 'Æ', 'ô', 'ö', 'ò', 'û', 'ù', 'ÿ', 'Ö', 'Ü', '¢', '£', '¥', '₧', 'ƒ',
+    // 0xA0
+    'á', 'í', 'ó', 'ú', 'ñ', 'Ñ', 'ª', 'º', '¿', '⌐', '¬', '½', '¼', '¡', '«', '»',
+    // 0xB0
+    '░', '▒', '▓', '│', '┤', '╡', '╢', '╖', '╕', '╣', '║', '╗', '╝', '╜', '╛', '┐',
+    // 0xC0
+    '└', '┴', '┬', '├', '─', '┼', '╞', '╟', '╚', '╔', '╩', '╦', '╠', '═', '╬', '╧',
+    // 0xD0
+    '╨', '╤', '╥', '╙', '╘', '╒', '╓', '╫', '╪', '┘', '┌', '█', '▄', '▌', '▐', '▀',
+    // 0xE0
+    'α', 'ß', 'Γ', 'π', 'Σ', 'σ', 'µ', 'τ', 'Φ', 'Θ', 'Ω', 'δ', '∞', 'φ', 'ε', '∩',
+    // 0xF0
+    '≡', '±', '≥', '≤', '⌠', '⌡', '÷', '≈', '°', '∙', '·', '√', 'ⁿ', '²', '■', '\u{00A0}',
+];
+
+/// Decode CP437 bytes to a Unicode string.
+///
+/// ASCII passes through unchanged and the upper half is the inverse of
+/// [`encode`]'s mapping, so `encode(&decode(b)) == b` for every byte.
+pub fn decode(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|&b| {
+            if b < 0x80 {
+                b as char
+            } else {
+                UPPER_HALF[(b - 0x80) as usize]
+            }
+        })
+        .collect()
+}
+
 /// Map a Unicode code point to its CP437 byte value (0x80–0xFF).
 ///
 /// Returns `None` if the character has no CP437 representation.
@@ -270,6 +313,13 @@ mod tests {
         assert_eq!(encode("★"), vec![b'?']);
     }
 
+    #[test]
+    fn test_decode_inverts_encode() {
+        let all: Vec<u8> = (0..=255).collect();
+        assert_eq!(encode(&decode(&all)), all);
+        assert_eq!(decode(&[0x43, 0x61, 0x66, 0x82]), "Café");
+    }
+
     #[test]
     fn test_mixed_ascii_and_extended() {
         // "Café" → C=0x43, a=0x61, f=0x66, é=0x82
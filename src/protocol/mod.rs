@@ -8,7 +8,9 @@
 //! - [`commands`]: Basic printer commands (init, cut, feed)
 //! - [`graphics`]: Bit image and raster graphics commands
 //! - [`text`]: Text styling (alignment, fonts, bold, underline, etc.)
+//! - [`codepage`]: International code pages and UTF-8 transcoding
 //! - [`barcode`]: QR codes and PDF417 barcodes
+//! - [`status`]: Automatic Status Back reports
 //!
 //! ## Usage Example
 //!
@@ -44,8 +46,10 @@
 //! by Star Micronics Co., Ltd.
 
 pub mod barcode;
+pub mod codepage;
 pub mod commands;
 pub mod cp437;
 pub mod graphics;
 pub mod nv_graphics;
+pub mod status;
 pub mod text;
@@ -0,0 +1,133 @@
+//! # Printer Status (Automatic Status Back)
+//!
+//! With Automatic Status Back (ASB) enabled, the printer sends a status
+//! report by itself whenever its state changes, without a request being
+//! written into the print data. Transports use it for flow control: a
+//! printer that goes offline mid-job (buffer full, cover opened, paper out)
+//! reports it, and the next report says when it is back.
+//!
+//! ## ASB Format
+//!
+//! | Byte | Contents |
+//! |------|----------|
+//! | 1 | Header: length in bits 1-3 and 5 (9 bytes = `0x23`) |
+//! | 2 | Header: version |
+//! | 3 | Bit 3 offline, bit 5 cover open |
+//! | 4-5 | Error flags |
+//! | 6 | Bit 3 paper empty |
+//! | 7+ | Model-specific |
+//!
+//! ## Reference
+//!
+//! StarPRNT Command Spec Rev 4.10, "Automatic Status Back"
+
+use super::commands::{ESC, RS};
+
+/// ASB condition bit: report online/offline changes.
+const ASB_ONLINE_OFFLINE: u8 = 0x02;
+
+/// Shortest report that carries the status bytes parsed here.
+const MIN_ASB_LEN: usize = 6;
+
+/// # Enable Automatic Status Back (ESC RS a n)
+///
+/// Asks the printer to report whenever it goes online or offline.
+///
+/// | Format  | Bytes       |
+/// |---------|-------------|
+/// | ASCII   | ESC RS a n  |
+/// | Hex     | 1B 1E 61 02 |
+#[inline]
+pub fn asb_enable() -> Vec<u8> {
+    vec![ESC, RS, b'a', ASB_ONLINE_OFFLINE]
+}
+
+/// One status report from the printer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AsbStatus {
+    /// Not accepting print data (buffer full, cover open, paper out, error).
+    pub offline: bool,
+    /// Printer cover is open.
+    pub cover_open: bool,
+    /// Out of paper.
+    pub paper_empty: bool,
+}
+
+impl AsbStatus {
+    /// Parse every complete report in `data`, oldest first. Bytes that
+    /// don't form a report are skipped.
+    ///
+    /// ```
+    /// use estrella::protocol::status::AsbStatus;
+    ///
+    /// let reports = AsbStatus::parse_all(&[
+    ///     0x23, 0x86, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // offline
+    ///     0x23, 0x86, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // back online
+    /// ]);
+    /// assert_eq!(reports.len(), 2);
+    /// assert!(reports[0].offline && !reports[1].offline);
+    /// ```
+    pub fn parse_all(data: &[u8]) -> Vec<Self> {
+        let mut reports = Vec::new();
+        let mut rest = data;
+        while let Some((&header, _)) = rest.split_first() {
+            let len = report_len(header);
+            if len < MIN_ASB_LEN || len > rest.len() {
+                rest = &rest[1..];
+                continue;
+            }
+            let report = &rest[..len];
+            reports.push(Self {
+                offline: report[2] & 0x08 != 0,
+                cover_open: report[2] & 0x20 != 0,
+                paper_empty: report[5] & 0x08 != 0,
+            });
+            rest = &rest[len..];
+        }
+        reports
+    }
+}
+
+/// Report length encoded in the first header byte (0 if it isn't a header:
+/// bit 0 set, bits 4 and 7 clear).
+fn report_len(header: u8) -> usize {
+    if header & 0x91 != 0x01 {
+        return 0;
+    }
+    let low = (header >> 1) & 0x07;
+    let high = (header >> 5) & 0x01;
+    ((high << 3) | low) as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_asb_enable() {
+        assert_eq!(asb_enable(), vec![0x1B, 0x1E, 0x61, 0x02]);
+    }
+
+    #[test]
+    fn test_report_len() {
+        assert_eq!(report_len(0x23), 9);
+        assert_eq!(report_len(0x0F), 7);
+        assert_eq!(report_len(0x00), 0);
+        assert_eq!(report_len(0x86), 0);
+    }
+
+    #[test]
+    fn test_parse_skips_partial_reports() {
+        let mut data = vec![0x00, 0x86];
+        data.extend([0x23, 0x86, 0x28, 0x00, 0x00, 0x08, 0x00, 0x00, 0x00]);
+        data.extend([0x23, 0x86, 0x00]);
+        assert_eq!(
+            AsbStatus::parse_all(&data),
+            [AsbStatus {
+                offline: true,
+                cover_open: true,
+                paper_empty: true,
+            }]
+        );
+    }
+}
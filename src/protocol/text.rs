@@ -156,6 +156,16 @@ pub enum Font {
     C = 2,
 }
 
+impl Font {
+    /// Character cell width in dots (12 for Font A, 9 for B and C).
+    pub fn char_width(self) -> usize {
+        match self {
+            Font::A => 12,
+            Font::B | Font::C => 9,
+        }
+    }
+}
+
 /// # Select Font (ESC RS F n)
 ///
 /// Selects the character font for subsequent text.
@@ -839,6 +849,60 @@ pub fn smoothing_off() -> Vec<u8> {
     vec![ESC, GS, b'b', 0]
 }
 
+// ============================================================================
+// LINE SPACING
+// ============================================================================
+
+/// How far each line feed advances the paper.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Default, Hash, serde::Serialize, serde::Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum LineSpacing {
+    /// 3mm: Font A lines with no gap between them.
+    Tight,
+    /// 4mm, the power-on default.
+    #[default]
+    Normal,
+}
+
+/// # Set Line Spacing (ESC z n)
+///
+/// Sets the line feed pitch used by every following `LF`.
+///
+/// ## Protocol Details
+///
+/// | Format  | Bytes |
+/// |---------|-------|
+/// | ASCII   | ESC z n |
+/// | Hex     | 1B 7A n |
+/// | Decimal | 27 122 n |
+///
+/// ## Parameters
+///
+/// - `n = 0`: 3mm
+/// - `n = 1`: 4mm (default)
+///
+/// ## Example
+///
+/// ```
+/// use estrella::protocol::text::{LineSpacing, line_spacing};
+///
+/// assert_eq!(line_spacing(LineSpacing::Tight), vec![0x1B, 0x7A, 0]);
+/// ```
+///
+/// ## Reference
+///
+/// StarPRNT Command Spec Rev 4.10, Section 2.3.5
+#[inline]
+pub fn line_spacing(spacing: LineSpacing) -> Vec<u8> {
+    let n = match spacing {
+        LineSpacing::Tight => 0,
+        LineSpacing::Normal => 1,
+    };
+    vec![ESC, b'z', n]
+}
+
 // ============================================================================
 // TEXT STYLE BUILDER
 // ============================================================================
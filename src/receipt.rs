@@ -13,7 +13,10 @@
 
 use chrono::Local;
 
-use crate::document::{Component, Document, Markdown};
+use crate::document::{
+    Banner, Barcode, BorderStyle, Canvas, CanvasElement, Component, Divider, Document, Header,
+    Image, Markdown, Pattern, Pdf417, Position, QrCode, ResolvedImage, Spacer, Text,
+};
 
 /// Fixed date used for golden tests (ensures reproducible output)
 pub const GOLDEN_TEST_DATE: &str = "2026-01-20";
@@ -44,12 +47,16 @@ pub fn current_datetime() -> String {
 /// - Upside-down text
 /// - Font selection
 pub fn demo_receipt() -> Vec<u8> {
-    demo_receipt_doc(&current_datetime()).build()
+    demo_receipt_doc(&current_datetime())
+        .build()
+        .expect("built-in receipts have no raster mode")
 }
 
 /// Generate a simple demo receipt with a fixed date (for golden tests).
 pub fn demo_receipt_golden() -> Vec<u8> {
-    demo_receipt_doc(GOLDEN_TEST_DATETIME).build()
+    demo_receipt_doc(GOLDEN_TEST_DATETIME)
+        .build()
+        .expect("built-in receipts have no raster mode")
 }
 
 /// JSON fixture for the demo receipt.
@@ -81,12 +88,16 @@ fn demo_receipt_doc(datetime: &str) -> Document {
 /// - QR code
 /// - PDF417 barcode
 pub fn full_receipt() -> Vec<u8> {
-    full_receipt_doc(&current_datetime()).build()
+    full_receipt_doc(&current_datetime())
+        .build()
+        .expect("built-in receipts have no raster mode")
 }
 
 /// Generate a full demo receipt with a fixed date (for golden tests).
 pub fn full_receipt_golden() -> Vec<u8> {
-    full_receipt_doc(GOLDEN_TEST_DATETIME).build()
+    full_receipt_doc(GOLDEN_TEST_DATETIME)
+        .build()
+        .expect("built-in receipts have no raster mode")
 }
 
 /// Build a full demo receipt Document with a specific datetime string.
@@ -105,12 +116,16 @@ fn full_receipt_doc(datetime: &str) -> Document {
 /// - Horizontal rules
 /// - Paragraphs and spacing
 pub fn markdown_demo() -> Vec<u8> {
-    markdown_demo_doc(&current_date()).build()
+    markdown_demo_doc(&current_date())
+        .build()
+        .expect("built-in receipts have no raster mode")
 }
 
 /// Generate a markdown demo receipt with a fixed date (for golden tests).
 pub fn markdown_demo_golden() -> Vec<u8> {
-    markdown_demo_doc(GOLDEN_TEST_DATE).build()
+    markdown_demo_doc(GOLDEN_TEST_DATE)
+        .build()
+        .expect("built-in receipts have no raster mode")
 }
 
 /// Build a markdown demo Document with a specific date.
@@ -162,6 +177,22 @@ Visit [our website](https://example.com) for more.
 
 ---
 
+## Tables and Quotes
+
+| Drink | Size | Price |
+|:------|:----:|------:|
+| Latte | L | $5.25 |
+| Tea | S | $3.00 |
+
+> Markdown from an assistant often quotes things, and
+> quoted lines wrap under the bar.
+
+```
+curl -d @receipt.json localhost:8080/api/json/print
+```
+
+---
+
 ## Receipt Example
 
 Date: {} | Order: #1234
@@ -191,13 +222,255 @@ Thank *you* for your purchase!
     }
 }
 
+/// Generate the "demo-all" test page with the current date/time.
+///
+/// One long receipt exercising every feature, each under a section label:
+/// - Text styles, fonts and sizes
+/// - Every component type
+/// - Every 1D barcode format, plus QR and PDF417
+/// - Every dithering mode, on the same gradient
+/// - A couple of patterns
+///
+/// Print it after setup as an acceptance test; it doubles as a printed
+/// reference of what the printer can do.
+pub fn demo_all() -> Vec<u8> {
+    demo_all_doc(&current_datetime())
+        .build()
+        .expect("built-in receipts have no raster mode")
+}
+
+/// Generate the "demo-all" test page with a fixed date (for golden tests).
+pub fn demo_all_golden() -> Vec<u8> {
+    demo_all_doc(GOLDEN_TEST_DATETIME)
+        .build()
+        .expect("built-in receipts have no raster mode")
+}
+
+/// 1D barcode formats with sample data valid for each.
+const DEMO_BARCODES: &[(&str, &str)] = &[
+    ("code39", "ESTRELLA"),
+    ("code128", "Estrella-128"),
+    ("code93", "STAR-93"),
+    ("ean13", "590123412345"),
+    ("upca", "03600029145"),
+    ("itf", "12345678"),
+    ("nw7", "A12345B"),
+];
+
+/// Dithering modes, in the order they are shown.
+const DEMO_DITHERS: &[&str] = &[
+    "none",
+    "bayer",
+    "floyd-steinberg",
+    "atkinson",
+    "jarvis",
+    "auto",
+];
+
+/// Patterns shown at the end of the test page.
+const DEMO_PATTERNS: &[&str] = &["estrella", "ripple"];
+
+/// Build the "demo-all" test page Document with a specific datetime string.
+fn demo_all_doc(datetime: &str) -> Document {
+    let mut doc = vec![
+        Component::Header(Header::new("ESTRELLA")),
+        Component::Text(Text {
+            content: "TEST PAGE".into(),
+            bold: true,
+            center: true,
+            ..Default::default()
+        }),
+        Component::Text(Text {
+            content: format!("v{} - {}", env!("CARGO_PKG_VERSION"), datetime),
+            center: true,
+            size: [0, 0],
+            ..Default::default()
+        }),
+    ];
+
+    section(&mut doc, "TEXT STYLES");
+    type Style = fn(&mut Text);
+    let styles: [(&str, Style); 9] = [
+        ("Bold", |t| t.bold = true),
+        ("Underline", |t| t.underline = true),
+        ("Upperline", |t| t.upperline = true),
+        ("Inverted", |t| t.invert = true),
+        ("Upside down", |t| t.upside_down = true),
+        ("Reduced", |t| t.reduced = true),
+        ("Centered", |t| t.center = true),
+        ("Right-aligned", |t| t.right = true),
+        ("IBM Plex Sans (raster)", |t| t.font = Some("ibm".into())),
+    ];
+    for (content, style) in styles {
+        let mut text = Text::new(content);
+        style(&mut text);
+        doc.push(Component::Text(text));
+    }
+
+    section(&mut doc, "FONT SIZES");
+    for size in [[0, 0], [1, 1], [2, 1], [1, 2], [2, 2], [3, 3]] {
+        let content = match size {
+            [0, 0] => "Font B".to_string(),
+            [h, w] => format!("Size {}x{}", h, w),
+        };
+        doc.push(Component::Text(Text {
+            content,
+            size,
+            ..Default::default()
+        }));
+    }
+
+    section(&mut doc, "COMPONENTS");
+    for component in Component::all_editor_defaults() {
+        caption(&mut doc, component.label());
+        doc.push(demo_component(component));
+    }
+
+    section(&mut doc, "BARCODES");
+    for (format, data) in DEMO_BARCODES {
+        caption(&mut doc, format);
+        doc.push(Component::Barcode(Barcode {
+            format: format.to_string(),
+            data: data.to_string(),
+            height: Some(60),
+            height_mm: None,
+            hri: true,
+            raster: false,
+        }));
+    }
+    caption(&mut doc, "qr_code");
+    doc.push(Component::QrCode(QrCode::new(
+        "https://github.com/eljojo/estrella",
+    )));
+    caption(&mut doc, "pdf417");
+    doc.push(Component::Pdf417(Pdf417::new("ESTRELLA TEST PAGE")));
+
+    section(&mut doc, "DITHERING");
+    for dither in DEMO_DITHERS {
+        caption(&mut doc, dither);
+        doc.push(Component::Image(Image {
+            dither: Some(dither.to_string()),
+            resolved_data: Some(gradient(576, 48)),
+            ..Default::default()
+        }));
+    }
+
+    section(&mut doc, "PATTERNS");
+    for name in DEMO_PATTERNS {
+        caption(&mut doc, name);
+        doc.push(Component::Pattern(Pattern {
+            name: name.to_string(),
+            height: Some(160),
+            ..Default::default()
+        }));
+    }
+
+    doc.push(Component::Spacer(Spacer::mm(2.0)));
+    doc.push(Component::Text(Text {
+        content: "END OF TEST PAGE".into(),
+        bold: true,
+        center: true,
+        ..Default::default()
+    }));
+
+    let mut doc = Document {
+        document: doc,
+        cut: true,
+        ..Default::default()
+    };
+    // Editor defaults use {{time}}; pin it so the golden page is reproducible
+    let time = datetime.get(11..16).unwrap_or_default();
+    doc.variables.insert("time".to_string(), time.to_string());
+    doc
+}
+
+/// Push a section label onto the test page.
+fn section(doc: &mut Vec<Component>, title: &str) {
+    doc.push(Component::Spacer(Spacer::mm(3.0)));
+    doc.push(Component::Banner(Banner {
+        content: title.into(),
+        border: BorderStyle::Heading,
+        ..Default::default()
+    }));
+}
+
+/// Push a small caption naming the sample below it.
+fn caption(doc: &mut Vec<Component>, text: &str) {
+    doc.push(Component::Text(Text {
+        content: text.into(),
+        size: [0, 0],
+        underline: true,
+        ..Default::default()
+    }));
+}
+
+/// Make an editor-default component printable on its own.
+///
+/// Images need no download, and the canvas gets something to draw.
+fn demo_component(component: Component) -> Component {
+    match component {
+        Component::Image(image) => Component::Image(Image {
+            resolved_data: Some(gradient(384, 96)),
+            ..image
+        }),
+        Component::Canvas(canvas) => Component::Canvas(Canvas {
+            elements: vec![
+                canvas_element(
+                    Position::dots(0, 0),
+                    Component::Pattern(Pattern {
+                        name: "estrella".into(),
+                        height: Some(100),
+                        ..Default::default()
+                    }),
+                ),
+                canvas_element(
+                    Position::dots(0, 30),
+                    Component::Text(Text {
+                        content: "CANVAS".into(),
+                        bold: true,
+                        invert: true,
+                        center: true,
+                        size: [2, 2],
+                        ..Default::default()
+                    }),
+                ),
+            ],
+            ..canvas
+        }),
+        other => other,
+    }
+}
+
+/// A canvas element at `position`, with no effects.
+fn canvas_element(position: Position, component: Component) -> CanvasElement {
+    CanvasElement {
+        component,
+        position: Some(position),
+        blend_mode: Default::default(),
+        opacity: 1.0,
+        rotate: 0.0,
+        scale: [1.0, 1.0],
+        clip: None,
+    }
+}
+
+/// A left-to-right white-to-black ramp, for showing off dithering.
+fn gradient(width: u16, height: u16) -> ResolvedImage {
+    let row: Vec<f32> = (0..width).map(|x| x as f32 / (width - 1) as f32).collect();
+    ResolvedImage {
+        intensities: row.repeat(height as usize),
+        width,
+        height,
+    }
+}
+
 // ============================================================================
 // LOOKUP FUNCTIONS
 // ============================================================================
 
 /// List available receipt templates
 pub fn list_receipts() -> &'static [&'static str] {
-    &["receipt", "receipt-full", "markdown"]
+    &["receipt", "receipt-full", "markdown", "demo-all"]
 }
 
 /// Get receipt data by name
@@ -206,26 +479,69 @@ pub fn by_name(name: &str) -> Option<Vec<u8>> {
         "receipt" => Some(demo_receipt()),
         "receipt-full" | "receipt_full" => Some(full_receipt()),
         "markdown" => Some(markdown_demo()),
+        "demo-all" | "demo_all" => Some(demo_all()),
         _ => None,
     }
 }
 
-/// Get receipt IR Program by name (uses current date for live preview).
-pub fn program_by_name(name: &str) -> Option<crate::ir::Program> {
+/// Build the compact status receipt printed when the server boots.
+///
+/// Shows the hostname, IP address, version and a QR code linking to the
+/// web UI, so headless deployments reveal their address on paper.
+pub fn status_receipt_doc(hostname: &str, ip: &str, url: &str) -> Document {
+    Document {
+        document: vec![
+            Component::Header(Header {
+                content: "ESTRELLA".into(),
+                variant: Some("small".into()),
+            }),
+            Component::Text(Text {
+                content: format!("v{}", env!("CARGO_PKG_VERSION")),
+                center: true,
+                ..Default::default()
+            }),
+            Component::Divider(Divider::default()),
+            Component::Text(Text::new(format!("Host: {}", hostname))),
+            Component::Text(Text::new(format!("IP:   {}", ip))),
+            Component::Text(Text::new(format!("Up:   {}", current_datetime()))),
+            Component::Spacer(Spacer::mm(2.0)),
+            Component::QrCode(QrCode::new(url)),
+            Component::Text(Text {
+                content: url.to_string(),
+                center: true,
+                ..Default::default()
+            }),
+            Component::Spacer(Spacer::mm(4.0)),
+        ],
+        cut: true,
+        interpolate: false,
+        ..Default::default()
+    }
+}
+
+/// Get receipt Document by name (uses current date).
+pub fn document_by_name(name: &str) -> Option<Document> {
     match name.to_lowercase().as_str() {
-        "receipt" => Some(demo_receipt_doc(&current_datetime()).compile()),
-        "receipt-full" | "receipt_full" => Some(full_receipt_doc(&current_datetime()).compile()),
-        "markdown" => Some(markdown_demo_doc(&current_date()).compile()),
+        "receipt" => Some(demo_receipt_doc(&current_datetime())),
+        "receipt-full" | "receipt_full" => Some(full_receipt_doc(&current_datetime())),
+        "markdown" => Some(markdown_demo_doc(&current_date())),
+        "demo-all" | "demo_all" => Some(demo_all_doc(&current_datetime())),
         _ => None,
     }
 }
 
+/// Get receipt IR Program by name (uses current date for live preview).
+pub fn program_by_name(name: &str) -> Option<crate::ir::Program> {
+    document_by_name(name).map(|doc| doc.compile())
+}
+
 /// Get receipt IR Program by name with fixed date (for golden tests).
 pub fn program_by_name_golden(name: &str) -> Option<crate::ir::Program> {
     match name.to_lowercase().as_str() {
         "receipt" => Some(demo_receipt_doc(GOLDEN_TEST_DATETIME).compile()),
         "receipt-full" | "receipt_full" => Some(full_receipt_doc(GOLDEN_TEST_DATETIME).compile()),
         "markdown" => Some(markdown_demo_doc(GOLDEN_TEST_DATE).compile()),
+        "demo-all" | "demo_all" => Some(demo_all_doc(GOLDEN_TEST_DATETIME).compile()),
         _ => None,
     }
 }
@@ -234,7 +550,7 @@ pub fn program_by_name_golden(name: &str) -> Option<crate::ir::Program> {
 pub fn is_receipt(name: &str) -> bool {
     matches!(
         name.to_lowercase().as_str(),
-        "receipt" | "receipt-full" | "receipt_full" | "markdown"
+        "receipt" | "receipt-full" | "receipt_full" | "markdown" | "demo-all" | "demo_all"
     )
 }
 
@@ -266,11 +582,58 @@ mod tests {
         assert!(data.windows(4).any(|w| w == qr_print));
     }
 
+    #[test]
+    fn test_status_receipt_has_qr() {
+        let ir = status_receipt_doc("pi", "192.168.1.20", "http://192.168.1.20:8080/").compile();
+        assert!(ir.ops.iter().any(|op| matches!(
+            op,
+            crate::ir::Op::QrCode { data, .. } if data == "http://192.168.1.20:8080/"
+        )));
+        assert!(
+            ir.ops
+                .iter()
+                .any(|op| matches!(op, crate::ir::Op::Text(s) if s.contains("192.168.1.20")))
+        );
+    }
+
+    #[test]
+    fn test_demo_all_covers_every_component() {
+        let doc = document_by_name("demo-all").unwrap();
+        let ops = doc.compile().ops;
+        let texts: Vec<&str> = ops
+            .iter()
+            .filter_map(|op| match op {
+                crate::ir::Op::Text(s) => Some(s.as_str()),
+                _ => None,
+            })
+            .collect();
+
+        for component in Component::all_editor_defaults() {
+            assert!(
+                texts.contains(&component.label()),
+                "demo-all has no {} sample",
+                component.label()
+            );
+        }
+        for dither in DEMO_DITHERS {
+            assert!(texts.contains(dither));
+        }
+
+        let barcodes = ops
+            .iter()
+            .filter(|op| matches!(op, crate::ir::Op::Barcode1D { .. }))
+            .count();
+        // Every format, plus the Barcode component sample
+        assert_eq!(barcodes, DEMO_BARCODES.len() + 1);
+        assert!(matches!(ops.last(), Some(crate::ir::Op::Cut { .. })));
+    }
+
     #[test]
     fn test_list_receipts() {
         let receipts = list_receipts();
         assert!(receipts.contains(&"receipt"));
         assert!(receipts.contains(&"receipt-full"));
+        assert!(receipts.contains(&"demo-all"));
     }
 
     #[test]
@@ -0,0 +1,140 @@
+//! Brightness and contrast, shared by the photo page and image components.
+//!
+//! Both take -100 to 100, with 0 leaving the image unchanged. Thermal
+//! paper prints darker than a screen shows, so photos usually want a little
+//! more brightness.
+
+use image::DynamicImage;
+
+/// Apply brightness and contrast if needed, otherwise return a clone.
+pub fn apply_brightness_contrast_if_needed(
+    img: &DynamicImage,
+    brightness: i32,
+    contrast: i32,
+) -> DynamicImage {
+    if brightness != 0 || contrast != 0 {
+        apply_brightness_contrast(img, brightness, contrast)
+    } else {
+        img.clone()
+    }
+}
+
+/// Apply brightness and contrast adjustments to an image.
+pub fn apply_brightness_contrast(
+    img: &DynamicImage,
+    brightness: i32,
+    contrast: i32,
+) -> DynamicImage {
+    let mut rgba = img.to_rgba8();
+
+    // Brightness: -100 to 100 maps to -255 to 255 offset
+    let brightness_offset = (brightness as f32 / 100.0) * 255.0;
+
+    // Contrast: -100 to 100 maps to factor
+    // At -100: factor = 0 (all gray)
+    // At 0: factor = 1 (no change)
+    // At 100: factor = 2 (max contrast)
+    let contrast_factor = 1.0 + (contrast as f32 / 100.0);
+
+    for pixel in rgba.pixels_mut() {
+        for c in 0..3 {
+            // Skip alpha channel
+            let val = pixel[c] as f32;
+            // Apply contrast (around midpoint 128)
+            let val = (val - 128.0) * contrast_factor + 128.0;
+            // Apply brightness
+            let val = val + brightness_offset;
+            // Clamp to valid range
+            pixel[c] = val.clamp(0.0, 255.0) as u8;
+        }
+    }
+
+    DynamicImage::ImageRgba8(rgba)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgb, RgbImage};
+
+    fn create_test_image(width: u32, height: u32) -> DynamicImage {
+        let mut img = RgbImage::new(width, height);
+        // Create a simple gradient to verify transformations
+        for y in 0..height {
+            for x in 0..width {
+                img.put_pixel(x, y, Rgb([x as u8, y as u8, 128]));
+            }
+        }
+        DynamicImage::ImageRgb8(img)
+    }
+
+    #[test]
+    fn test_brightness_increase() {
+        let img = create_test_image(10, 10);
+        let original_pixel = img.to_rgba8().get_pixel(5, 5).0;
+
+        let brightened = apply_brightness_contrast(&img, 50, 0);
+        let bright_pixel = brightened.to_rgba8().get_pixel(5, 5).0;
+
+        // Brightness increase should make pixels brighter (higher values)
+        assert!(
+            bright_pixel[0] > original_pixel[0] || bright_pixel[0] == 255,
+            "Red channel should be brighter: {} vs {}",
+            bright_pixel[0],
+            original_pixel[0]
+        );
+    }
+
+    #[test]
+    fn test_brightness_decrease() {
+        let img = create_test_image(10, 10);
+        let original_pixel = img.to_rgba8().get_pixel(5, 5).0;
+
+        let darkened = apply_brightness_contrast(&img, -50, 0);
+        let dark_pixel = darkened.to_rgba8().get_pixel(5, 5).0;
+
+        // Brightness decrease should make pixels darker (lower values)
+        assert!(
+            dark_pixel[0] < original_pixel[0] || dark_pixel[0] == 0,
+            "Red channel should be darker: {} vs {}",
+            dark_pixel[0],
+            original_pixel[0]
+        );
+    }
+
+    #[test]
+    fn test_contrast_increase() {
+        // Create a gray image
+        let mut img = RgbImage::new(10, 10);
+        for y in 0..10 {
+            for x in 0..10 {
+                img.put_pixel(x, y, Rgb([100, 100, 100])); // Mid-gray-ish
+            }
+        }
+        let img = DynamicImage::ImageRgb8(img);
+
+        let contrasted = apply_brightness_contrast(&img, 0, 50);
+        let pixel = contrasted.to_rgba8().get_pixel(5, 5).0;
+
+        // With contrast increase, values below 128 should go lower
+        // Original: 100, after contrast (factor 1.5): (100-128)*1.5+128 = 86
+        assert!(
+            pixel[0] < 100,
+            "Expected contrast to move 100 away from 128, got {}",
+            pixel[0]
+        );
+    }
+
+    #[test]
+    fn test_no_change_when_zero() {
+        let img = create_test_image(10, 10);
+        let original_pixel = img.to_rgba8().get_pixel(5, 5).0;
+
+        let unchanged = apply_brightness_contrast_if_needed(&img, 0, 0);
+        let new_pixel = unchanged.to_rgba8().get_pixel(5, 5).0;
+
+        assert_eq!(original_pixel[0], new_pixel[0]);
+        assert_eq!(original_pixel[1], new_pixel[1]);
+        assert_eq!(original_pixel[2], new_pixel[2]);
+    }
+}
@@ -0,0 +1,200 @@
+//! Transparency: what prints behind the see-through parts of an image.
+//!
+//! Paper can't be transparent, so images with an alpha channel are laid
+//! over a [`Background`] before dithering. Without that, a transparent
+//! pixel prints as whatever color it happens to store, usually black.
+//!
+//! | `background` | Behind transparent pixels |
+//! |--------------|---------------------------|
+//! | `"white"` (default) | paper |
+//! | `"black"` | solid ink |
+//! | `0.25` or `"25%"` | a flat shade, as ink coverage |
+//! | `"ripple"` | a pattern, by name, drawn at the image's size |
+
+#[cfg(feature = "images")]
+use image::DynamicImage;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+#[cfg(feature = "patterns")]
+use crate::art;
+
+/// What shows through transparent pixels.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(try_from = "BackgroundRepr", into = "BackgroundRepr")]
+pub enum Background {
+    /// Bare paper.
+    #[default]
+    White,
+    /// Solid ink.
+    Black,
+    /// A flat gray: ink coverage from 0.0 (white) to 1.0 (black).
+    Shade(f32),
+    /// A pattern by name (see [`crate::art::PATTERNS`]).
+    Pattern(String),
+}
+
+impl fmt::Display for Background {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Background::White => write!(f, "white"),
+            Background::Black => write!(f, "black"),
+            Background::Shade(shade) => write!(f, "{}", shade),
+            Background::Pattern(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+impl FromStr for Background {
+    type Err = String;
+
+    /// Parse `white`, `black`, a shade (`0.25` or `25%`) or a pattern name.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        match s.to_lowercase().as_str() {
+            "white" => return Ok(Background::White),
+            "black" => return Ok(Background::Black),
+            _ => {}
+        }
+        let shade = match s.strip_suffix('%') {
+            Some(percent) => percent.trim().parse::<f32>().ok().map(|p| p / 100.0),
+            None => s.parse::<f32>().ok(),
+        };
+        if let Some(shade) = shade {
+            return Background::shade(shade);
+        }
+        #[cfg(feature = "patterns")]
+        if art::by_name(s).is_some() {
+            return Ok(Background::Pattern(s.to_string()));
+        }
+        Err(format!(
+            "Invalid background '{}'. Use white, black, a shade (0.0-1.0 or 0-100%) or a pattern name",
+            s
+        ))
+    }
+}
+
+impl Background {
+    /// Checked shade: ink coverage within 0.0..=1.0.
+    pub fn shade(shade: f32) -> Result<Self, String> {
+        if !(0.0..=1.0).contains(&shade) {
+            return Err(format!("Background shade {} is out of range", shade));
+        }
+        Ok(Background::Shade(shade))
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum BackgroundRepr {
+    Shade(f32),
+    Text(String),
+}
+
+impl TryFrom<BackgroundRepr> for Background {
+    type Error = String;
+
+    fn try_from(repr: BackgroundRepr) -> Result<Self, Self::Error> {
+        match repr {
+            BackgroundRepr::Shade(shade) => Background::shade(shade),
+            BackgroundRepr::Text(s) => s.parse(),
+        }
+    }
+}
+
+impl From<Background> for BackgroundRepr {
+    fn from(background: Background) -> Self {
+        match background {
+            Background::Shade(shade) => BackgroundRepr::Shade(shade),
+            other => BackgroundRepr::Text(other.to_string()),
+        }
+    }
+}
+
+/// Ink intensities of `image` laid over `background`, one per pixel,
+/// row-major (0.0 = white, 1.0 = black).
+///
+/// Images without an alpha channel are converted to grayscale as they are.
+/// A pattern background prints as paper without the `patterns` feature.
+#[cfg(feature = "images")]
+pub fn intensities(image: &DynamicImage, background: &Background) -> Vec<f32> {
+    if !image.color().has_alpha() {
+        return image
+            .to_luma8()
+            .pixels()
+            .map(|pixel| 1.0 - pixel[0] as f32 / 255.0)
+            .collect();
+    }
+
+    let pattern: Box<dyn Fn(usize, usize) -> f32> = match background {
+        #[cfg(feature = "patterns")]
+        Background::Pattern(name) => match art::by_name(name) {
+            Some(pattern) => {
+                let (width, height) = (image.width() as usize, image.height() as usize);
+                Box::new(move |x, y| pattern.intensity(x, y, width, height))
+            }
+            None => Box::new(|_, _| 0.0),
+        },
+        _ => Box::new(|_, _| 0.0),
+    };
+    image
+        .to_luma_alpha8()
+        .enumerate_pixels()
+        .map(|(x, y, pixel)| {
+            let ink = 1.0 - pixel[0] as f32 / 255.0;
+            let alpha = pixel[1] as f32 / 255.0;
+            let behind = match background {
+                Background::White => 0.0,
+                Background::Black => 1.0,
+                Background::Shade(shade) => *shade,
+                Background::Pattern(_) => pattern(x as usize, y as usize),
+            };
+            ink * alpha + behind * (1.0 - alpha)
+        })
+        .collect()
+}
+
+#[cfg(all(test, feature = "patterns"))]
+mod tests {
+    use super::*;
+    use image::{LumaA, RgbaImage};
+
+    /// Left pixel: opaque white. Right pixel: fully transparent black.
+    fn half_transparent() -> DynamicImage {
+        let mut image = RgbaImage::new(2, 1);
+        image.put_pixel(0, 0, image::Rgba([255, 255, 255, 255]));
+        image.put_pixel(1, 0, image::Rgba([0, 0, 0, 0]));
+        DynamicImage::ImageRgba8(image)
+    }
+
+    #[test]
+    fn test_transparent_pixels_show_the_background() {
+        let image = half_transparent();
+        assert_eq!(intensities(&image, &Background::White), [0.0, 0.0]);
+        assert_eq!(intensities(&image, &Background::Black), [0.0, 1.0]);
+        assert_eq!(intensities(&image, &Background::Shade(0.25)), [0.0, 0.25]);
+
+        // Half-transparent black over white is mid gray
+        let gray = DynamicImage::ImageLumaA8(image::ImageBuffer::from_pixel(1, 1, LumaA([0, 128])));
+        let ink = intensities(&gray, &Background::White)[0];
+        assert!((ink - 0.5).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_parse_background() {
+        assert_eq!("white".parse(), Ok(Background::White));
+        assert_eq!("25%".parse(), Ok(Background::Shade(0.25)));
+        assert_eq!("ripple".parse(), Ok(Background::Pattern("ripple".into())));
+        assert!("1.5".parse::<Background>().is_err());
+        assert!("plaid".parse::<Background>().is_err());
+
+        let json = |s: &str| serde_json::from_str::<Background>(s);
+        assert_eq!(json("0.5").unwrap(), Background::Shade(0.5));
+        assert_eq!(json(r#""black""#).unwrap(), Background::Black);
+        assert_eq!(
+            serde_json::to_string(&Background::Pattern("waves".into())).unwrap(),
+            r#""waves""#
+        );
+    }
+}
@@ -0,0 +1,168 @@
+//! Animation strips: a pattern at several moments, stacked like a flipbook.
+//!
+//! Patterns that move implement [`Pattern::intensity_at_time`], where `t`
+//! runs from 0.0 to 1.0 over one loop. Frame `i` of `n` is drawn at
+//! `t = i / n`, so the strip ends one step before it would repeat its
+//! first frame. Patterns that don't move print the same frame `n` times.
+//!
+//! ```
+//! use estrella::render::animation;
+//! use estrella::render::dither::{DitherOptions, DitheringAlgorithm};
+//! use estrella::render::patterns::Ripple;
+//!
+//! let frames = animation::render_strip(
+//!     &Ripple::default(),
+//!     64,
+//!     32,
+//!     4,
+//!     DitheringAlgorithm::Bayer,
+//!     &DitherOptions::default(),
+//! );
+//! assert_eq!(frames.len(), 4);
+//! assert_eq!(frames[1].label(), "frame 2/4  t=0.25");
+//! ```
+
+use super::dither::{self, DitherOptions, DitheringAlgorithm};
+use super::patterns::Pattern;
+use super::quality::PrintQuality;
+
+/// Most frames in one strip.
+pub const MAX_FRAMES: usize = 64;
+
+/// One rendered frame of a strip.
+#[derive(Debug, Clone)]
+pub struct Frame {
+    /// Zero-based frame number.
+    pub index: usize,
+    /// Frames in the strip.
+    pub count: usize,
+    /// Time the frame was drawn at (0.0-1.0).
+    pub t: f32,
+    /// Packed 1-bit raster data, `width.div_ceil(8) * height` bytes.
+    pub data: Vec<u8>,
+}
+
+impl Frame {
+    /// Caption printed above the frame, e.g. `frame 2/8  t=0.13`.
+    pub fn label(&self) -> String {
+        format!("frame {}/{}  t={:.2}", self.index + 1, self.count, self.t)
+    }
+}
+
+/// Time of frame `index` in a strip of `count` frames.
+pub fn frame_time(index: usize, count: usize) -> f32 {
+    index as f32 / count.max(1) as f32
+}
+
+/// Render `pattern` at time `t` to grayscale intensities.
+pub fn render_gray_at(pattern: &dyn Pattern, width: usize, height: usize, t: f32) -> Vec<f32> {
+    dither::generate_intensities(width, height, |x, y, w, h| {
+        pattern.intensity_at_time(x, y, w, h, t)
+    })
+}
+
+/// Render `count` frames of `pattern`, each `width` × `frame_height`.
+///
+/// `count` is clamped to 1..=[`MAX_FRAMES`].
+pub fn render_strip(
+    pattern: &dyn Pattern,
+    width: usize,
+    frame_height: usize,
+    count: usize,
+    algorithm: DitheringAlgorithm,
+    options: &DitherOptions,
+) -> Vec<Frame> {
+    render_strip_with_quality(
+        pattern,
+        width,
+        frame_height,
+        count,
+        algorithm,
+        options,
+        PrintQuality::Normal,
+    )
+}
+
+/// Like [`render_strip`], with every frame rendered at `quality`.
+pub fn render_strip_with_quality(
+    pattern: &dyn Pattern,
+    width: usize,
+    frame_height: usize,
+    count: usize,
+    algorithm: DitheringAlgorithm,
+    options: &DitherOptions,
+    quality: PrintQuality,
+) -> Vec<Frame> {
+    let count = count.clamp(1, MAX_FRAMES);
+    (0..count)
+        .map(|index| {
+            let t = frame_time(index, count);
+            let data = quality.render(
+                width,
+                frame_height,
+                |x, y, w, h| pattern.intensity_at_time(x, y, w, h, t),
+                algorithm,
+                options,
+            );
+            Frame {
+                index,
+                count,
+                t,
+                data,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::patterns::{self, Calibration};
+
+    #[test]
+    fn test_frame_times_loop() {
+        let times: Vec<f32> = (0..4).map(|i| frame_time(i, 4)).collect();
+        assert_eq!(times, [0.0, 0.25, 0.5, 0.75]);
+        assert_eq!(frame_time(0, 0), 0.0);
+    }
+
+    #[test]
+    fn test_moving_patterns_change_between_frames() {
+        for name in ["ripple", "waves", "plasma"] {
+            let pattern = patterns::by_name_golden(name).unwrap();
+            // t = 0 is the static pattern; a whole loop comes back to it
+            let still = patterns::render_gray(pattern.as_ref(), 48, 24);
+            assert_eq!(
+                render_gray_at(pattern.as_ref(), 48, 24, 0.0),
+                still,
+                "{}",
+                name
+            );
+            let moved = render_gray_at(pattern.as_ref(), 48, 24, 0.25);
+            assert_ne!(moved, still, "{}", name);
+            let looped = render_gray_at(pattern.as_ref(), 48, 24, 1.0);
+            let drift = looped
+                .iter()
+                .zip(&still)
+                .map(|(a, b)| (a - b).abs())
+                .fold(0.0, f32::max);
+            assert!(drift < 0.01, "{} drifts by {}", name, drift);
+        }
+    }
+
+    #[test]
+    fn test_static_patterns_repeat() {
+        let frames = render_strip(
+            &Calibration::default(),
+            32,
+            16,
+            3,
+            DitheringAlgorithm::Bayer,
+            &DitherOptions::default(),
+        );
+        assert_eq!(frames.len(), 3);
+        assert_eq!(frames[0].data.len(), 4 * 16);
+        assert!(frames.iter().all(|frame| frame.data == frames[0].data));
+        assert_eq!(frames[2].label(), "frame 3/3  t=0.67");
+    }
+}
@@ -0,0 +1,500 @@
+//! Blue-noise threshold matrix for ordered dithering.
+//!
+//! A 64×64 rank matrix generated with Ulichney's void-and-cluster method
+//! (Gaussian filter, σ = 1.5, toroidal). Thresholding against it spreads
+//! the dots of any gray level as evenly as possible with no repeating
+//! structure, so smooth gradients print without Bayer's crosshatch.
+//!
+//! Like Bayer it's a per-pixel lookup: deterministic, parallel, no error
+//! to carry between pixels.
+
+/// Size of [`BLUE_NOISE64`] on each side.
+pub const SIZE: usize = 64;
+
+/// Void-and-cluster ranks 0..4096, each exactly once. Lower ranks turn
+/// black first as intensity rises.
+pub const BLUE_NOISE64: [[u16; SIZE]; SIZE] = [
+    [
+        764, 3917, 2831, 2091, 1654, 414, 3150, 706, 2251, 1557, 4059, 684, 3085, 286, 1578, 917,
+        404, 2128, 3457, 659, 2494, 1110, 2818, 1711, 3175, 2596, 1431, 288, 1999, 1179, 3716,
+        1829, 257, 2024, 1127, 2236, 810, 1314, 2446, 548, 3212, 216, 985, 1691, 3193, 2451, 1808,
+        616, 3652, 185, 1725, 719, 3757, 3164, 2680, 1478, 1827, 4001, 2588, 3592, 3065, 242, 3702,
+        3158,
+    ],
+    [
+        2640, 1326, 217, 635, 3436, 2521, 1363, 3783, 2, 990, 2445, 3310, 1313, 2309, 2952, 1965,
+        3139, 4079, 291, 1497, 3628, 155, 2016, 577, 3483, 1135, 2964, 4069, 3203, 573, 3423, 2256,
+        2675, 3769, 1646, 3120, 3932, 1833, 3606, 1584, 2019, 3822, 2843, 2182, 415, 3419, 1079,
+        2820, 2211, 3391, 3057, 2395, 1547, 931, 517, 3444, 2994, 2238, 372, 1400, 1935, 978, 2245,
+        361,
+    ],
+    [
+        1860, 3644, 2289, 2942, 3883, 1045, 2325, 1820, 3050, 3552, 1934, 394, 1764, 3944, 631,
+        3566, 1193, 1647, 2868, 1021, 2219, 3024, 4005, 940, 2313, 82, 1957, 877, 1716, 2508, 1491,
+        986, 620, 3231, 107, 2492, 487, 2998, 153, 2730, 1187, 738, 1492, 3662, 1231, 2000, 3812,
+        1502, 450, 1951, 1124, 305, 3478, 1896, 2495, 10, 1243, 914, 3233, 634, 3816, 2781, 1529,
+        3389,
+    ],
+    [
+        547, 3230, 908, 1447, 1900, 269, 3322, 538, 1260, 2834, 821, 2636, 3453, 1010, 2691, 51,
+        2353, 698, 2523, 3374, 1729, 489, 1351, 2682, 3616, 1537, 3767, 2762, 386, 3820, 1, 2812,
+        4029, 1359, 875, 3510, 1246, 2155, 1032, 4014, 3142, 2517, 13, 3010, 664, 2683, 131, 3146,
+        3603, 790, 2614, 4090, 1266, 2946, 3854, 2126, 3666, 2832, 1635, 3429, 2390, 105, 4049,
+        1071,
+    ],
+    [
+        2556, 1693, 54, 3701, 2613, 760, 2795, 4006, 2081, 168, 3847, 1429, 264, 2135, 1506, 3210,
+        1823, 3631, 234, 3911, 850, 2410, 3319, 1835, 340, 3033, 615, 2349, 1332, 3049, 2129, 3354,
+        1673, 2408, 2038, 2823, 1600, 3708, 661, 2298, 440, 1890, 3437, 2271, 4023, 1745, 927,
+        2421, 1325, 2900, 1658, 557, 2217, 241, 842, 1465, 586, 2524, 279, 2080, 1286, 735, 2987,
+        2149,
+    ],
+    [
+        1309, 3955, 3064, 2013, 1181, 3495, 1586, 1001, 2528, 1682, 3191, 2297, 2935, 3692, 522,
+        3873, 1087, 3001, 1425, 1982, 3144, 23, 3832, 776, 1205, 2157, 3282, 1057, 3546, 1831, 787,
+        1176, 220, 3066, 539, 3945, 262, 2607, 3283, 1674, 3542, 1294, 841, 1568, 273, 2917, 2156,
+        3928, 343, 2036, 3745, 3220, 2746, 1733, 3382, 3100, 1913, 4030, 1097, 3720, 2700, 1868,
+        3605, 390,
+    ],
+    [
+        2797, 722, 2307, 500, 2913, 135, 2208, 3264, 345, 3658, 588, 903, 1185, 1864, 2502, 824,
+        2721, 2181, 478, 987, 2793, 1545, 2092, 2943, 2604, 3967, 1758, 247, 2667, 525, 3923, 2617,
+        3705, 1813, 1058, 3199, 799, 1958, 1222, 110, 2771, 3778, 2563, 3222, 1053, 3485, 1438,
+        695, 3388, 1078, 211, 1365, 711, 3937, 1073, 2392, 133, 2908, 1754, 528, 3161, 968, 1544,
+        3343,
+    ],
+    [
+        1916, 1118, 3541, 1493, 3919, 1788, 3748, 668, 1458, 2714, 1921, 4051, 3114, 100, 3480,
+        1666, 205, 3988, 3286, 2539, 3765, 1183, 535, 3580, 186, 1473, 732, 3690, 2049, 3241, 1556,
+        2229, 387, 3491, 2706, 1518, 2315, 3611, 2958, 947, 2176, 645, 324, 2039, 3843, 510, 2465,
+        1776, 2659, 3074, 2312, 3544, 2537, 2046, 455, 3590, 1334, 865, 3523, 2474, 60, 3927, 2247,
+        225,
+    ],
+    [
+        3804, 3205, 175, 2454, 941, 2598, 1214, 3043, 2338, 3505, 1272, 393, 2561, 1414, 2276,
+        3195, 1233, 1877, 673, 1616, 274, 3211, 2417, 1889, 1004, 3432, 2265, 2882, 1382, 159,
+        1018, 3006, 756, 1310, 2087, 188, 3862, 399, 1719, 3362, 4041, 1346, 3099, 1712, 2735,
+        1274, 3170, 36, 3802, 1548, 828, 1851, 79, 3055, 1576, 2689, 2158, 3208, 1530, 2112, 1307,
+        2922, 644, 2631,
+    ],
+    [
+        909, 1698, 2127, 3095, 632, 3425, 301, 2034, 844, 28, 2923, 2079, 3569, 687, 3823, 371,
+        2808, 3650, 2380, 3516, 2138, 798, 4022, 1588, 3147, 2531, 417, 1129, 4009, 2462, 3446,
+        1897, 2546, 4075, 3280, 951, 2891, 1378, 2532, 473, 1908, 2468, 3561, 867, 261, 2243, 3681,
+        1017, 2071, 523, 4034, 2734, 1217, 3695, 924, 3838, 660, 236, 3971, 785, 3378, 1780, 3581,
+        1401,
+    ],
+    [
+        2894, 429, 4084, 1261, 1936, 2829, 1541, 3949, 3201, 1757, 3727, 970, 1644, 2852, 1125,
+        2141, 837, 1436, 148, 1037, 2921, 1375, 2652, 70, 648, 3827, 1955, 3111, 813, 1768, 574,
+        3766, 44, 1662, 601, 2403, 1874, 3448, 740, 3188, 1131, 48, 1528, 2960, 4000, 1836, 580,
+        2901, 2535, 1347, 2999, 373, 3412, 1977, 297, 2345, 2968, 1927, 2766, 451, 2599, 1091, 348,
+        2366,
+    ],
+    [
+        3484, 749, 2555, 3531, 90, 3784, 549, 2433, 1075, 2663, 626, 2391, 3273, 438, 1862, 3107,
+        4086, 2506, 3299, 1938, 3835, 480, 3492, 2052, 2873, 1244, 1550, 112, 3597, 2814, 1450,
+        2305, 3073, 1177, 2799, 3712, 132, 1084, 3881, 2055, 2687, 3781, 688, 2365, 1101, 3318,
+        1514, 3506, 313, 3648, 1747, 861, 2439, 1419, 3262, 1159, 1679, 3513, 1259, 3672, 2216,
+        3089, 3947, 1959,
+    ],
+    [
+        1224, 3046, 1603, 979, 2300, 1316, 1870, 3298, 267, 1461, 4010, 157, 1380, 3872, 2632, 95,
+        1629, 622, 2838, 405, 1608, 2352, 1190, 3266, 880, 2373, 3404, 2616, 2190, 351, 3293, 1006,
+        516, 3502, 2132, 1397, 3015, 2294, 1579, 244, 3058, 1769, 3405, 1993, 200, 2642, 835, 1967,
+        2318, 1108, 3279, 2104, 3895, 653, 2615, 4076, 26, 876, 2443, 1631, 198, 866, 1523, 39,
+    ],
+    [
+        2399, 2044, 245, 3217, 2777, 3640, 795, 2909, 2114, 3439, 1844, 3048, 2183, 846, 3496,
+        1268, 3358, 2227, 1152, 3677, 3101, 772, 3907, 228, 1731, 4067, 408, 1034, 3810, 1308,
+        2054, 3999, 2610, 1812, 306, 851, 4011, 589, 2621, 3551, 918, 1312, 363, 2878, 3867, 1303,
+        3098, 117, 3997, 625, 2564, 136, 1580, 2925, 447, 1806, 3096, 3392, 511, 3915, 2003, 3305,
+        2718, 3704,
+    ],
+    [
+        551, 3450, 3959, 689, 1972, 360, 1633, 3889, 1213, 693, 2765, 1033, 3680, 524, 2008, 2478,
+        937, 3774, 1837, 9, 2533, 1969, 1495, 2647, 3007, 621, 1974, 2911, 1707, 708, 2989, 102,
+        1520, 3654, 3181, 2712, 1727, 3240, 1234, 436, 2341, 4093, 2574, 1590, 568, 2130, 3698,
+        1619, 2750, 1398, 3025, 3747, 1171, 3602, 2048, 1035, 2280, 1501, 2841, 1107, 2584, 418,
+        1763, 998,
+    ],
+    [
+        2821, 1370, 1739, 2476, 1122, 3393, 2653, 72, 2376, 3627, 341, 2460, 1671, 2969, 1445, 280,
+        3019, 463, 2736, 1336, 3469, 575, 3591, 1062, 2136, 3671, 1418, 3348, 303, 2503, 3452, 945,
+        2347, 572, 1156, 2274, 5, 2004, 3813, 1650, 3004, 1922, 777, 3558, 3261, 999, 2472, 427,
+        3360, 921, 1815, 366, 2241, 770, 3331, 2720, 323, 3764, 696, 2177, 3522, 1291, 4052, 3145,
+    ],
+    [
+        2206, 848, 410, 2962, 3811, 1443, 2131, 949, 3180, 1732, 1362, 3290, 35, 4033, 2723, 3570,
+        1744, 3921, 2122, 859, 2936, 2264, 382, 3184, 63, 2538, 811, 2261, 3972, 1843, 1393, 3776,
+        1914, 2785, 3897, 1475, 3620, 958, 2805, 702, 3474, 184, 1186, 2302, 73, 1893, 2972, 771,
+        2178, 3803, 2412, 3226, 2809, 1690, 113, 3956, 1317, 3027, 1849, 89, 2954, 728, 1964, 145,
+    ],
+    [
+        2625, 3882, 3314, 2174, 212, 658, 3008, 4054, 475, 2011, 3831, 652, 2260, 1109, 753, 2207,
+        1202, 669, 3204, 1571, 4053, 1145, 1821, 3860, 1639, 1251, 3126, 174, 1157, 2854, 448,
+        3138, 191, 3337, 808, 375, 2575, 3396, 287, 2101, 1416, 2672, 3738, 3070, 1519, 4015, 1319,
+        3608, 1686, 0, 1249, 596, 3512, 1392, 2505, 1939, 812, 2419, 3409, 1627, 3829, 2456, 1494,
+        3571,
+    ],
+    [
+        1174, 1589, 1895, 1030, 2778, 3610, 1802, 1196, 2481, 2889, 895, 2650, 3521, 1932, 3172,
+        142, 2609, 3454, 352, 2469, 122, 3346, 2716, 640, 2857, 3754, 1865, 2641, 3688, 761, 2187,
+        2580, 1120, 1689, 2094, 3082, 1598, 1175, 2389, 3975, 3194, 495, 2002, 870, 2786, 633,
+        2577, 320, 3143, 2729, 4031, 2037, 1014, 3770, 494, 3136, 3572, 362, 1052, 2708, 491, 948,
+        3075, 364,
+    ],
+    [
+        641, 3169, 12, 3741, 1496, 2364, 302, 3477, 1526, 154, 3197, 1624, 400, 1352, 3884, 1678,
+        3732, 1441, 1968, 2875, 826, 2062, 1320, 2329, 966, 347, 3462, 564, 1562, 3326, 1315, 3951,
+        579, 3576, 2422, 4062, 531, 2988, 1767, 765, 1111, 2493, 1622, 3851, 266, 2225, 3428, 1068,
+        1966, 840, 1524, 2970, 300, 2218, 2753, 1197, 1601, 2254, 3742, 1428, 3285, 2308, 3936,
+        2086,
+    ],
+    [
+        3509, 2333, 2646, 766, 3379, 1126, 2709, 736, 3798, 2169, 1136, 3978, 2490, 3054, 584,
+        2375, 886, 3000, 1096, 3799, 1620, 3507, 456, 3948, 3251, 2166, 1102, 2428, 1989, 3002,
+        337, 1876, 2880, 1404, 69, 967, 1956, 3442, 195, 2742, 3669, 109, 3489, 1287, 3225, 1854,
+        1435, 3900, 2427, 3549, 180, 2550, 3373, 1723, 878, 4065, 30, 2973, 737, 2029, 137, 1212,
+        1709, 2819,
+    ],
+    [
+        1432, 996, 4080, 2010, 471, 2213, 3227, 1875, 3016, 515, 3399, 1952, 97, 1054, 2775, 1997,
+        227, 3350, 512, 2337, 194, 2932, 2520, 1467, 118, 1680, 2830, 4083, 29, 971, 3746, 2340,
+        818, 3277, 2671, 3713, 1335, 2324, 3866, 1455, 2191, 1795, 2827, 599, 2450, 916, 2893, 355,
+        650, 3077, 1863, 1165, 3861, 662, 3253, 2041, 2582, 3459, 1741, 3986, 2903, 3574, 781, 233,
+    ],
+    [
+        3017, 1774, 276, 3116, 1582, 3850, 87, 972, 2594, 1383, 2402, 767, 3501, 1566, 3243, 3684,
+        1324, 2591, 3968, 1781, 1208, 3699, 857, 1941, 3012, 3773, 752, 1348, 3395, 2557, 1513,
+        3476, 251, 1753, 2148, 605, 2886, 368, 897, 3115, 543, 3366, 981, 2090, 4002, 50, 3664,
+        1713, 2210, 1349, 3723, 457, 2369, 1468, 2845, 413, 1345, 1024, 556, 2525, 381, 2350, 1950,
+        3744,
+    ],
+    [
+        567, 2273, 3645, 868, 2783, 1228, 3493, 1655, 4038, 322, 1756, 2859, 3855, 2239, 327, 778,
+        1667, 2133, 705, 2788, 3185, 2171, 532, 3367, 1173, 2316, 437, 1790, 2963, 654, 2042, 1169,
+        3094, 3922, 1038, 3538, 1642, 3353, 2022, 2516, 1273, 3807, 307, 3005, 1499, 3336, 2540,
+        1085, 3236, 2651, 802, 2123, 3031, 151, 3573, 1762, 3729, 2220, 3275, 1489, 944, 3133,
+        1276, 2571,
+    ],
+    [
+        1100, 3284, 1358, 2430, 1912, 614, 2368, 2874, 717, 3555, 3182, 1095, 482, 1296, 2624,
+        3929, 2915, 78, 3518, 1016, 312, 1572, 4004, 2643, 299, 3515, 2717, 3710, 2188, 187, 3994,
+        2644, 504, 1462, 2426, 218, 2639, 1170, 4036, 21, 1636, 2658, 2259, 1140, 1881, 754, 2066,
+        526, 4082, 94, 3465, 1621, 3966, 1114, 2448, 892, 2701, 124, 2879, 3868, 1810, 3430, 46,
+        3996,
+    ],
+    [
+        2093, 169, 2866, 389, 3926, 3059, 213, 2139, 1353, 1991, 20, 2698, 2120, 3329, 1789, 988,
+        2295, 3271, 1508, 1973, 3646, 2449, 1318, 1807, 894, 2025, 1507, 794, 1204, 3256, 1714,
+        893, 2896, 1984, 3750, 768, 3249, 1878, 692, 2975, 3641, 769, 3267, 3909, 196, 3090, 3596,
+        1288, 2861, 1907, 1005, 2541, 613, 1887, 3400, 474, 3938, 1918, 1216, 439, 2196, 677, 2804,
+        1640,
+    ],
+    [
+        775, 3824, 1683, 3500, 803, 1503, 3313, 3782, 982, 2485, 3877, 1426, 748, 3623, 173, 3076,
+        546, 1245, 4043, 2725, 581, 3078, 14, 3308, 2916, 3912, 134, 3045, 2415, 3796, 336, 2306,
+        3604, 64, 3063, 1386, 2330, 409, 3499, 2200, 1211, 1929, 461, 1459, 2358, 2697, 1623, 346,
+        2323, 1439, 3315, 256, 3697, 1338, 2237, 2992, 1522, 694, 3508, 2611, 3709, 1421, 2378,
+        3562,
+    ],
+    [
+        2984, 2590, 1139, 2144, 2515, 1077, 1819, 416, 2957, 604, 3349, 1884, 2892, 2386, 1551,
+        3821, 1920, 2545, 253, 896, 2258, 1134, 3857, 656, 2356, 1090, 3435, 1879, 527, 2763, 1525,
+        3364, 1230, 1792, 1019, 2690, 3869, 1567, 952, 2740, 204, 2480, 2931, 3447, 638, 1028,
+        3876, 3163, 686, 3808, 2938, 2168, 2679, 3152, 45, 1041, 2500, 3122, 2068, 965, 259, 3023,
+        1042, 435,
+    ],
+    [
+        1341, 1930, 582, 3327, 42, 4028, 2733, 2275, 3653, 1715, 1172, 252, 4094, 462, 1184, 2737,
+        807, 3398, 1660, 2981, 3455, 1766, 2072, 2764, 1591, 419, 2246, 1407, 3209, 1050, 2107,
+        704, 2562, 4073, 590, 3463, 160, 2089, 3092, 3715, 1410, 4016, 939, 1734, 3719, 2137, 161,
+        1850, 2453, 922, 370, 1199, 1609, 819, 4060, 1718, 3642, 182, 1330, 3301, 1752, 3887, 2031,
+        3347,
+    ],
+    [
+        4013, 278, 3731, 2833, 1613, 3176, 741, 1304, 125, 2570, 3041, 2230, 1002, 3276, 2020,
+        3587, 43, 2197, 3879, 1366, 403, 3718, 240, 1285, 3598, 2876, 4055, 831, 3584, 250, 3878,
+        3091, 378, 2240, 2997, 1904, 1293, 2595, 611, 1760, 420, 3200, 2067, 56, 2622, 1376, 2835,
+        3402, 1510, 3593, 2032, 3890, 530, 3338, 1979, 2811, 583, 2290, 3990, 2743, 718, 2530, 88,
+        1626,
+    ],
+    [
+        2780, 2435, 789, 1374, 2314, 458, 2017, 3533, 1554, 3940, 729, 3629, 1746, 2815, 674, 1442,
+        3102, 1066, 537, 2507, 2858, 806, 3157, 2470, 904, 1947, 96, 2634, 1755, 2498, 1247, 1641,
+        3528, 919, 1466, 329, 3206, 3989, 1116, 3421, 2388, 721, 2761, 1160, 3321, 481, 4044, 1105,
+        258, 2657, 3255, 1787, 2855, 2344, 292, 1166, 3390, 879, 1899, 384, 1460, 3179, 2270, 905,
+    ],
+    [
+        1189, 3244, 2100, 3494, 1031, 3846, 2888, 942, 3237, 2085, 442, 1457, 2471, 165, 3788,
+        2322, 2654, 1742, 3272, 1998, 1154, 1688, 2186, 3904, 533, 3377, 1194, 3117, 550, 2056,
+        2941, 19, 2674, 1970, 3833, 2440, 874, 2160, 71, 2890, 1946, 3841, 1559, 3622, 2299, 1805,
+        750, 2179, 3072, 657, 1321, 76, 1056, 3679, 1454, 3842, 2518, 1577, 2907, 3609, 1051, 3785,
+        534, 3511,
+    ],
+    [
+        146, 1740, 490, 2976, 162, 2579, 1710, 295, 2455, 1132, 2864, 3186, 881, 3424, 1225, 426,
+        3970, 853, 3624, 335, 4068, 3363, 58, 1472, 2953, 1705, 2228, 3795, 1482, 3637, 820, 3981,
+        1112, 3365, 608, 2910, 3700, 1701, 3539, 1430, 902, 226, 3003, 383, 956, 3135, 2600, 3730,
+        1632, 2396, 3983, 2145, 3134, 734, 2696, 2134, 465, 3166, 8, 2096, 2619, 1779, 2914, 1980,
+    ],
+    [
+        2401, 3618, 1486, 4057, 1917, 1277, 3739, 655, 3472, 1861, 92, 3711, 2175, 1882, 2920,
+        1602, 2117, 127, 2798, 1490, 2296, 672, 2684, 1025, 3559, 338, 2782, 969, 158, 3309, 2335,
+        1872, 421, 2287, 1661, 202, 1258, 667, 2648, 2277, 3224, 1297, 2519, 2012, 3894, 1464, 98,
+        1206, 453, 3438, 929, 2592, 1670, 3488, 172, 1777, 1023, 3913, 1262, 3294, 751, 238, 1357,
+        3874,
+    ],
+    [
+        587, 2816, 1080, 2486, 834, 3278, 2124, 2955, 1474, 4040, 2693, 1328, 486, 3891, 747, 3356,
+        1098, 3155, 2406, 997, 3039, 1828, 3753, 2075, 2434, 720, 4021, 2006, 2536, 636, 1301,
+        2745, 3250, 1381, 2635, 3460, 2009, 3119, 271, 3659, 541, 4085, 1676, 3408, 565, 2772,
+        3490, 2098, 2906, 1885, 224, 3787, 497, 1344, 3051, 3725, 2822, 607, 2411, 1555, 4095,
+        2248, 3333, 885,
+    ],
+    [
+        3108, 2078, 263, 3397, 521, 2773, 22, 983, 2278, 391, 805, 2393, 1706, 2767, 192, 2547,
+        3752, 1761, 646, 3497, 285, 1263, 3167, 171, 1390, 3268, 1155, 1618, 3536, 3106, 1750, 209,
+        3649, 699, 4058, 975, 2377, 3780, 1848, 1065, 2125, 2732, 7, 1117, 2339, 847, 1694, 4012,
+        1009, 3269, 1469, 2862, 2014, 2482, 845, 2231, 1394, 1940, 3554, 315, 3013, 1083, 2649,
+        1653,
+    ],
+    [
+        3942, 1203, 3737, 2221, 1685, 3941, 1361, 3661, 3165, 1804, 3340, 3643, 1074, 3173, 1409,
+        2255, 452, 1322, 4003, 1985, 2748, 3899, 598, 1724, 3678, 2626, 235, 2872, 446, 910, 3954,
+        2991, 1072, 2113, 2895, 52, 1483, 815, 2884, 1595, 3359, 746, 3068, 1937, 3714, 3306, 265,
+        2483, 544, 2288, 757, 3550, 1043, 3964, 354, 3411, 147, 3123, 854, 2757, 1770, 520, 3586,
+        93,
+    ],
+    [
+        1942, 715, 1517, 3056, 928, 2572, 2028, 627, 2466, 1182, 2849, 126, 2084, 3950, 926, 3663,
+        1933, 2956, 11, 2437, 1533, 887, 2192, 2934, 943, 1886, 2203, 3779, 1264, 2424, 2064, 1476,
+        2479, 432, 1721, 3385, 2573, 508, 3962, 321, 2499, 1270, 3817, 1485, 379, 2665, 1218, 3168,
+        1549, 3771, 2620, 27, 1751, 3192, 1275, 2662, 1668, 3995, 2110, 1201, 3775, 2195, 1387,
+        2464,
+    ],
+    [
+        3468, 2847, 2510, 116, 3577, 369, 3270, 1630, 243, 3980, 1560, 710, 2597, 425, 1663, 2851,
+        724, 3418, 1055, 3149, 467, 3578, 2511, 3335, 401, 3934, 691, 3221, 1794, 3632, 81, 663,
+        3818, 3214, 1210, 3892, 1910, 3093, 1340, 2033, 3614, 181, 2268, 2853, 962, 2150, 1778,
+        3594, 183, 1954, 1241, 3383, 2374, 485, 2047, 3735, 1076, 553, 2549, 55, 3355, 743, 3042,
+        349,
+    ],
+    [
+        1697, 468, 3345, 1371, 1923, 1104, 3806, 2673, 3011, 923, 2223, 3567, 1867, 3030, 3475,
+        164, 2630, 1446, 2262, 3758, 1832, 1271, 120, 1599, 1150, 2810, 1481, 167, 2581, 816, 2867,
+        3482, 1845, 2722, 759, 342, 2267, 973, 3525, 2705, 855, 1798, 3274, 594, 3924, 3118, 651,
+        2768, 1047, 3018, 4035, 675, 2836, 1484, 3009, 742, 2404, 3295, 2959, 1574, 1960, 2707,
+        4008, 1039,
+    ],
+    [
+        3694, 2021, 873, 4064, 2865, 2321, 763, 1337, 1953, 3717, 484, 3159, 1329, 817, 2351, 1158,
+        2097, 3961, 290, 755, 2655, 3022, 4092, 2058, 3540, 2371, 3097, 1026, 3371, 1536, 2164,
+        1088, 311, 2326, 1532, 2930, 3676, 143, 1659, 529, 3036, 4018, 1092, 1649, 2432, 67, 1342,
+        3749, 2082, 433, 1672, 2193, 993, 3819, 115, 3443, 1825, 294, 1283, 3905, 964, 221, 1512,
+        2253,
+    ],
+    [
+        2902, 1180, 2372, 223, 1539, 3130, 422, 3417, 152, 2379, 1089, 2668, 33, 4025, 1607, 3625,
+        407, 3067, 1692, 3520, 2152, 1003, 552, 2605, 792, 283, 1842, 2250, 3674, 431, 4047, 3160,
+        1368, 3403, 3786, 1824, 1192, 2628, 3357, 2317, 1256, 2070, 334, 2800, 3633, 1987, 3304,
+        2360, 827, 2664, 3560, 222, 3334, 1905, 2629, 1355, 2263, 3691, 629, 2172, 3466, 2512,
+        3232, 610,
+    ],
+    [
+        15, 3840, 2760, 3289, 619, 2119, 3943, 2566, 1487, 3258, 1696, 3826, 1943, 2496, 555, 3234,
+        1883, 959, 2791, 1373, 207, 3323, 1784, 3724, 1384, 3263, 3969, 570, 1292, 2751, 1924, 716,
+        2606, 16, 938, 514, 3153, 1978, 758, 3849, 40, 2559, 3427, 1420, 542, 989, 1615, 281, 3852,
+        1215, 3124, 1498, 2452, 585, 1099, 4074, 814, 2789, 1669, 3081, 445, 1775, 1298, 3547,
+    ],
+    [
+        1926, 1415, 793, 1791, 3683, 1130, 1735, 889, 3601, 571, 2899, 316, 3376, 1027, 2860, 1343,
+        2567, 3896, 506, 2362, 3801, 1521, 2848, 59, 2005, 2727, 913, 1681, 2967, 114, 2431, 1606,
+        3853, 2060, 2844, 2416, 4066, 246, 1463, 2807, 1638, 3687, 832, 2235, 3148, 4077, 2488,
+        2974, 1869, 518, 2301, 891, 3925, 2856, 3252, 1765, 156, 3524, 2400, 936, 3761, 2747, 830,
+        2413,
+    ],
+    [
+        513, 3406, 2215, 304, 2463, 2840, 49, 3053, 2266, 1252, 2059, 838, 1470, 2205, 3763, 176,
+        783, 2074, 3461, 1168, 2996, 628, 2212, 1198, 3464, 459, 2381, 3178, 3825, 995, 3557, 377,
+        3110, 1200, 3543, 1592, 1059, 2244, 3470, 963, 3131, 507, 1894, 2877, 177, 1306, 703, 3369,
+        1433, 2790, 3619, 2030, 38, 1395, 454, 2105, 3113, 1451, 317, 1250, 2061, 144, 4046, 3028,
+    ],
+    [
+        1648, 2678, 3985, 954, 3324, 1396, 3837, 1909, 339, 4088, 2586, 3706, 2993, 469, 1818,
+        3288, 1596, 3047, 77, 1822, 883, 2529, 4024, 3104, 974, 3759, 1453, 237, 1856, 2173, 1402,
+        2813, 833, 1841, 275, 630, 3722, 2944, 476, 2461, 2053, 1178, 3910, 1563, 3473, 2688, 2111,
+        80, 3946, 1060, 357, 3311, 1782, 3660, 2385, 3809, 980, 2542, 3939, 3312, 2977, 1504, 2184,
+        1103,
+    ],
+    [
+        3733, 178, 1284, 2978, 1976, 479, 839, 2637, 3316, 1012, 1583, 86, 3456, 2444, 984, 4039,
+        2304, 1280, 2695, 3864, 3216, 239, 1857, 374, 1628, 2143, 2638, 804, 3449, 2685, 647, 3914,
+        2303, 3401, 2522, 3196, 1992, 1331, 1811, 3998, 201, 3344, 2603, 330, 1029, 1858, 3668,
+        907, 2249, 2576, 1581, 2982, 773, 2741, 1229, 681, 2966, 1643, 493, 1880, 700, 3595, 350,
+        3238,
+    ],
+    [
+        1990, 2477, 682, 1594, 3479, 2361, 3630, 1477, 643, 2355, 3177, 1903, 733, 1388, 2711, 289,
+        690, 3607, 424, 2189, 1534, 1240, 3613, 2342, 2951, 600, 3213, 4017, 1219, 402, 3129, 1637,
+        84, 1253, 3974, 915, 2731, 68, 3582, 860, 2824, 1412, 707, 2320, 3800, 483, 2948, 1651,
+        3189, 623, 3755, 1137, 2121, 296, 3503, 1944, 128, 3422, 2214, 2826, 1142, 2354, 2702, 898,
+    ],
+    [
+        1527, 3151, 3656, 2656, 385, 1123, 3079, 1840, 3918, 282, 2754, 1188, 3935, 2102, 3527,
+        2971, 1892, 3259, 957, 2587, 578, 3370, 2774, 786, 3903, 1367, 25, 2001, 1687, 2438, 3689,
+        1022, 2986, 2115, 444, 1704, 2281, 680, 3128, 1617, 2202, 3685, 3245, 1677, 3105, 2458,
+        1377, 272, 3519, 1931, 119, 2441, 4056, 3080, 1500, 2623, 3982, 1327, 871, 3728, 61, 1738,
+        3930, 496,
+    ],
+    [
+        2165, 992, 57, 1800, 3898, 2103, 123, 2703, 1300, 2146, 3384, 540, 2883, 170, 1656, 1143,
+        2409, 1427, 3721, 1759, 3976, 2073, 108, 1146, 1928, 3534, 2504, 2929, 863, 3415, 203,
+        1906, 2612, 3564, 1411, 2850, 3736, 3381, 1267, 2560, 492, 1094, 4, 2108, 1195, 779, 4037,
+        2694, 1086, 2887, 1364, 3352, 1737, 606, 1008, 2151, 443, 2924, 2382, 1531, 3372, 2949,
+        1295, 3434,
+    ],
+    [
+        2863, 4070, 2346, 1238, 2898, 856, 3342, 593, 3670, 932, 1625, 3600, 2331, 906, 3740, 499,
+        3103, 6, 2806, 344, 1061, 3040, 1569, 2669, 3297, 325, 1046, 3848, 472, 2233, 1237, 4042,
+        536, 829, 3162, 140, 991, 1925, 231, 2961, 3902, 1785, 2792, 3839, 397, 3351, 1830, 2222,
+        679, 3908, 2083, 862, 255, 2699, 3726, 3260, 1799, 701, 3844, 309, 1996, 676, 2513, 230,
+    ],
+    [
+        788, 1456, 3296, 617, 3565, 1515, 2293, 1748, 2918, 2487, 37, 1962, 1339, 3132, 2666, 2199,
+        3933, 849, 1975, 3320, 2497, 731, 3815, 2282, 624, 1717, 2194, 1389, 3292, 2726, 1593,
+        3086, 2383, 1772, 3858, 2527, 1546, 4087, 2332, 890, 2057, 3375, 727, 2334, 1511, 2633,
+        111, 3693, 1605, 376, 2534, 3121, 3834, 2279, 1255, 3, 2467, 3487, 1220, 2752, 3174, 1036,
+        3532, 1915,
+    ],
+    [
+        3751, 428, 2686, 1994, 318, 2558, 3991, 1064, 411, 3859, 3265, 745, 4050, 314, 1839, 1082,
+        1535, 3458, 2319, 1191, 1657, 3481, 388, 1302, 3675, 2779, 3156, 697, 1866, 3760, 365, 912,
+        3471, 284, 1164, 2040, 576, 3071, 392, 3599, 1406, 260, 1226, 2965, 3639, 934, 3215, 1254,
+        2995, 3445, 1048, 1444, 1902, 762, 3431, 1575, 2985, 930, 1736, 2234, 503, 3957, 1612,
+        2328,
+    ],
+    [
+        2905, 1855, 1015, 3792, 3034, 1311, 163, 3498, 2007, 1236, 1540, 2796, 2418, 3380, 670,
+        3626, 219, 2869, 603, 4089, 129, 2759, 2035, 3087, 920, 254, 4020, 2548, 138, 1093, 2026,
+        2842, 1440, 2252, 3247, 2755, 3526, 1281, 2583, 1700, 3235, 2660, 4026, 1853, 563, 2063,
+        2414, 470, 1809, 2242, 139, 3579, 2904, 353, 2749, 2076, 566, 4091, 150, 3634, 1437, 2661,
+        75, 1162,
+    ],
+    [
+        308, 3171, 1538, 2407, 744, 1816, 3223, 2704, 569, 3109, 2224, 249, 1113, 1585, 2162, 3026,
+        2473, 1898, 1369, 3069, 1803, 796, 3880, 1505, 2405, 1971, 1151, 1561, 3617, 3127, 2489,
+        3931, 591, 3638, 888, 193, 1801, 791, 3772, 2185, 545, 961, 2269, 91, 3420, 1149, 3984,
+        2846, 780, 3893, 2645, 618, 1703, 3992, 1013, 3667, 1354, 3219, 2601, 1948, 3084, 774,
+        3281, 3657,
+    ],
+    [
+        2589, 683, 3979, 17, 3407, 2204, 911, 1573, 2394, 3635, 864, 3814, 2945, 464, 3952, 1235,
+        800, 3830, 396, 2423, 3330, 1115, 2593, 166, 3548, 3228, 558, 2933, 2106, 730, 1305, 65,
+        1888, 2950, 1597, 3977, 2384, 2979, 32, 1153, 2870, 3563, 1448, 3061, 1645, 2739, 179,
+        1543, 3303, 1333, 2027, 3242, 1207, 2209, 2526, 199, 1817, 2284, 1070, 333, 1265, 2398,
+        1665, 2018,
+    ],
+    [
+        1323, 3535, 2140, 1167, 2770, 3682, 406, 4081, 1147, 74, 1702, 1988, 3426, 2602, 1783, 47,
+        3291, 2776, 1007, 3655, 2180, 501, 2937, 1847, 726, 1350, 3836, 2429, 331, 3433, 1728,
+        3287, 2618, 1128, 2159, 519, 1399, 3361, 1919, 3953, 1614, 326, 2457, 836, 3845, 1983, 666,
+        3686, 2291, 277, 950, 3756, 66, 3112, 723, 2927, 3451, 488, 2803, 3886, 3339, 559, 4027,
+        935,
+    ],
+    [
+        2370, 434, 2939, 1699, 597, 1372, 3020, 1871, 3440, 2881, 2509, 609, 1360, 899, 3588, 2363,
+        1434, 2142, 1743, 106, 1553, 3529, 1278, 3960, 2292, 2744, 18, 1838, 1011, 4078, 2285, 872,
+        3856, 270, 3198, 3665, 2677, 1044, 678, 2552, 3137, 2023, 3703, 509, 1269, 3254, 2553,
+        1067, 3032, 1786, 2825, 2475, 1424, 1949, 3888, 1610, 1227, 3768, 858, 1695, 2147, 2724,
+        130, 3021,
+    ],
+    [
+        1558, 3387, 953, 3920, 2045, 2551, 141, 2232, 725, 1449, 3885, 3141, 189, 2069, 3083, 380,
+        3790, 637, 3014, 3993, 869, 2442, 3125, 328, 1020, 3410, 1509, 3183, 2828, 1422, 412, 3035,
+        1542, 2436, 823, 1826, 149, 2257, 3556, 268, 1248, 884, 3317, 2681, 2226, 232, 1720, 3530,
+        477, 4072, 709, 3328, 449, 3514, 1063, 319, 2447, 2050, 3154, 210, 3615, 1379, 1891, 3793,
+    ],
+    [
+        298, 1961, 2676, 197, 3202, 3789, 1106, 3302, 2670, 367, 994, 2283, 3696, 2728, 1611, 1138,
+        2627, 3414, 1223, 1911, 2802, 214, 2043, 1587, 3743, 2116, 797, 3707, 562, 2578, 3647,
+        1981, 602, 3441, 1279, 2817, 4061, 1356, 3038, 2109, 3875, 1552, 62, 1796, 2980, 3973, 843,
+        2153, 1480, 2397, 1221, 2095, 1652, 2871, 2198, 2710, 3394, 649, 1479, 2565, 1081, 612,
+        3246, 801,
+    ],
+    [
+        2543, 1242, 3589, 1488, 822, 1797, 502, 1564, 3958, 1963, 3467, 1722, 1239, 466, 4032, 825,
+        1986, 248, 2343, 554, 3585, 1141, 3325, 639, 2715, 395, 2491, 1901, 1209, 2161, 208, 1148,
+        2885, 3777, 2099, 423, 3140, 714, 1726, 460, 2784, 2357, 3762, 1040, 560, 1417, 3386, 2719,
+        24, 2919, 3575, 229, 3865, 592, 900, 4048, 53, 1873, 2940, 3963, 2310, 3504, 2794, 2170,
+    ],
+    [
+        4071, 2926, 642, 2286, 3029, 2484, 3553, 2947, 809, 2387, 121, 2912, 739, 3207, 2201, 3486,
+        2897, 1664, 3906, 3060, 1516, 2585, 4045, 1814, 3037, 1289, 3307, 99, 3916, 3062, 3368,
+        1771, 2327, 41, 1634, 976, 2348, 3621, 2608, 1119, 3332, 665, 3088, 2088, 3583, 2367, 398,
+        1161, 3791, 1773, 852, 3187, 2544, 1408, 3044, 1730, 1257, 3651, 882, 430, 1423, 101, 1708,
+        1000,
+    ],
+    [
+        3416, 34, 1834, 3734, 293, 1290, 2065, 215, 1163, 3248, 1413, 3797, 2501, 1793, 85, 1403,
+        595, 3300, 977, 31, 2163, 784, 359, 2359, 960, 3870, 1684, 2839, 925, 1565, 713, 4007, 901,
+        2568, 3218, 3871, 1471, 1945, 83, 3965, 1452, 1852, 332, 1282, 2801, 1604, 3052, 1995,
+        3239, 505, 2311, 1144, 1859, 3545, 356, 2420, 3190, 2167, 2769, 3341, 2077, 3863, 2990,
+        498,
+    ],
+    [
+        1570, 2425, 1121, 3257, 946, 4019, 2713, 1749, 3636, 2758, 441, 2051, 1069, 3612, 2738,
+        3828, 2459, 1299, 2756, 1846, 3805, 3229, 1405, 3673, 206, 2154, 671, 3568, 2336, 2692,
+        358, 2983, 1385, 3537, 561, 2787, 310, 3413, 2928, 933, 2272, 3517, 2569, 4063, 782, 103,
+        3901, 955, 2554, 1391, 3987, 2837, 104, 2118, 1049, 3794, 685, 190, 1133, 1675, 712, 2514,
+        1232, 2015,
+    ],
+];
+
+/// Threshold for a pixel position, in (0, 1); tiles every 64 pixels.
+#[inline]
+pub fn threshold(x: usize, y: usize) -> f32 {
+    (BLUE_NOISE64[y % SIZE][x % SIZE] as f32 + 0.5) / (SIZE * SIZE) as f32
+}
+
+/// Whether to print a dot at (x, y) for `intensity` (0.0 = white,
+/// 1.0 = black).
+#[inline]
+pub fn should_print(x: usize, y: usize, intensity: f32) -> bool {
+    intensity > threshold(x, y)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matrix_is_a_permutation() {
+        let mut seen = vec![false; SIZE * SIZE];
+        for &rank in BLUE_NOISE64.iter().flatten() {
+            assert!(!seen[rank as usize], "duplicate rank {}", rank);
+            seen[rank as usize] = true;
+        }
+        assert!(seen.iter().all(|&s| s));
+    }
+
+    #[test]
+    fn test_dots_spread_evenly() {
+        // At 50% gray, every 8x8 block gets close to its share of 32 dots
+        for by in 0..SIZE / 8 {
+            for bx in 0..SIZE / 8 {
+                let dots = (0..64)
+                    .filter(|i| should_print(bx * 8 + i % 8, by * 8 + i / 8, 0.5))
+                    .count();
+                assert!(
+                    (26..=38).contains(&dots),
+                    "block ({}, {}): {}",
+                    bx,
+                    by,
+                    dots
+                );
+            }
+        }
+    }
+}
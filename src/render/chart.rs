@@ -43,14 +43,14 @@ const BAR_GAP: usize = 2;
 
 /// Grayscale intensity buffer for chart rendering.
 /// 0.0 = white (no print), 1.0 = black (print).
-struct Canvas {
-    buf: Vec<f32>,
-    width: usize,
-    height: usize,
+pub(super) struct Canvas {
+    pub(super) buf: Vec<f32>,
+    pub(super) width: usize,
+    pub(super) height: usize,
 }
 
 impl Canvas {
-    fn new(width: usize, height: usize) -> Self {
+    pub(super) fn new(width: usize, height: usize) -> Self {
         Self {
             buf: vec![0.0; width * height],
             width,
@@ -65,7 +65,7 @@ impl Canvas {
 
     /// Set pixel intensity, taking the maximum of existing and new value.
     #[inline]
-    fn blend(&mut self, x: usize, y: usize, intensity: f32) {
+    pub(super) fn blend(&mut self, x: usize, y: usize, intensity: f32) {
         if self.in_bounds(x, y) {
             let idx = y * self.width + x;
             self.buf[idx] = self.buf[idx].max(intensity);
@@ -76,6 +76,8 @@ impl Canvas {
 // ============================================================================
 // DRAWING PRIMITIVES
 // ============================================================================
+//
+// Shared with the other raster visualizations in `render` (e.g. `gauge`).
 
 fn draw_hline(
     canvas: &mut Canvas,
@@ -155,7 +157,13 @@ fn draw_filled_rect(
     }
 }
 
-fn draw_filled_circle(canvas: &mut Canvas, cx: f32, cy: f32, radius: f32, intensity: f32) {
+pub(super) fn draw_filled_circle(
+    canvas: &mut Canvas,
+    cx: f32,
+    cy: f32,
+    radius: f32,
+    intensity: f32,
+) {
     let r_ceil = radius.ceil() as i32 + 1;
     let cxi = cx as i32;
     let cyi = cy as i32;
@@ -181,7 +189,7 @@ fn draw_filled_circle(canvas: &mut Canvas, cx: f32, cy: f32, radius: f32, intens
     }
 }
 
-fn draw_line_thick(
+pub(super) fn draw_line_thick(
     canvas: &mut Canvas,
     x1: f32,
     y1: f32,
@@ -395,7 +403,7 @@ fn compute_nice_ticks(min: f64, max: f64, target_count: usize) -> Vec<f64> {
     ticks
 }
 
-fn format_number(v: f64) -> String {
+pub(super) fn format_number(v: f64) -> String {
     if (v - v.round()).abs() < 1e-9 {
         format!("{}", v as i64)
     } else {
@@ -494,6 +502,19 @@ fn map_data_points(values: &[f64], y_min: f64, y_max: f64, layout: &Layout) -> V
 /// Returns `(raster_data, width, height)` where raster_data is packed 1-bit
 /// data suitable for `Op::Raster`.
 pub fn render(chart: &Chart, width: usize, dithering: DitheringAlgorithm) -> (Vec<u8>, u16, u16) {
+    let (gray, w, h) = render_gray(chart, width);
+    if gray.is_empty() {
+        return (Vec::new(), 0, 0);
+    }
+    let raster = dither::dither_intensities(w as usize, h as usize, &gray, dithering);
+    (raster, w, h)
+}
+
+/// Render a chart to a grayscale intensity buffer.
+///
+/// Returns `(intensities, width, height)` with one value per pixel
+/// (0.0 = white, 1.0 = black), suitable for `Op::GrayRaster`.
+pub fn render_gray(chart: &Chart, width: usize) -> (Vec<f32>, u16, u16) {
     let total_height = chart.height.unwrap_or(200);
     let n = chart.values.len();
 
@@ -652,15 +673,7 @@ pub fn render(chart: &Chart, width: usize, dithering: DitheringAlgorithm) -> (Ve
         ChartStyle::Dot => draw_dot_style(&mut canvas, &points),
     }
 
-    // Dither to 1-bit raster
-    let raster = dither::generate_raster(
-        width,
-        total_height,
-        |x, y, _w, _h| canvas.buf[y * width + x],
-        dithering,
-    );
-
-    (raster, width as u16, total_height as u16)
+    (canvas.buf, width as u16, total_height as u16)
 }
 
 // ============================================================================
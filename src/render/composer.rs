@@ -1,6 +1,9 @@
-//! Blend modes for layer compositing.
+//! Blend modes and layer transforms for compositing.
 //!
-//! Used by the Canvas component to composite elements with different blend modes.
+//! Used by the Canvas component to composite elements with different blend
+//! modes, after rotating and scaling them with [`transform`]. `mask` and
+//! `knockout` turn a layer (e.g. a `text_mask` headline) into a stencil for
+//! the layers below it.
 
 use crate::shader::{blend_add, blend_difference, blend_multiply, blend_overlay, blend_screen};
 use serde::{Deserialize, Serialize};
@@ -26,6 +29,11 @@ pub enum BlendMode {
     Min,
     /// Max - takes the lighter of two values.
     Max,
+    /// Mask - keeps what's below only where this layer is inked. On a
+    /// canvas, everything outside the layer's box is cleared too.
+    Mask,
+    /// Knockout - clears what's below wherever this layer is inked.
+    Knockout,
 }
 
 impl BlendMode {
@@ -43,10 +51,80 @@ impl BlendMode {
             BlendMode::Difference => blend_difference(base, blend),
             BlendMode::Min => base.min(blend),
             BlendMode::Max => base.max(blend),
+            BlendMode::Mask => blend_multiply(base, blend),
+            BlendMode::Knockout => base * (1.0 - blend),
         }
     }
 }
 
+/// Rotate (degrees, clockwise) and scale an intensity buffer about its center.
+///
+/// Returns the transformed buffer and its size, which is the bounding box
+/// of the transformed layer. Uses inverse mapping with bilinear sampling;
+/// when shrinking, each output pixel averages several samples so fine
+/// detail is filtered instead of aliased. Uncovered pixels are white.
+pub fn transform(
+    data: &[f32],
+    width: usize,
+    height: usize,
+    rotate_deg: f32,
+    scale_x: f32,
+    scale_y: f32,
+) -> (Vec<f32>, usize, usize) {
+    if width == 0 || height == 0 || scale_x <= 0.0 || scale_y <= 0.0 {
+        return (Vec::new(), 0, 0);
+    }
+
+    let (sin, cos) = rotate_deg.to_radians().sin_cos();
+    let (half_w, half_h) = (width as f32 * scale_x / 2.0, height as f32 * scale_y / 2.0);
+    let out_w = (2.0 * (half_w * cos.abs() + half_h * sin.abs()))
+        .round()
+        .max(1.0) as usize;
+    let out_h = (2.0 * (half_w * sin.abs() + half_h * cos.abs()))
+        .round()
+        .max(1.0) as usize;
+
+    // Samples per axis: more when shrinking
+    let samples = (1.0 / scale_x.min(scale_y)).ceil().clamp(1.0, 4.0) as usize;
+    let step = 1.0 / samples as f32;
+
+    let sample = |sx: f32, sy: f32| -> f32 {
+        // Bilinear lookup in source pixel space (pixel centers at +0.5)
+        let (fx, fy) = (sx - 0.5, sy - 0.5);
+        let (x0, y0) = (fx.floor(), fy.floor());
+        let (tx, ty) = (fx - x0, fy - y0);
+        let at = |x: f32, y: f32| -> f32 {
+            if x < 0.0 || y < 0.0 || x >= width as f32 || y >= height as f32 {
+                0.0
+            } else {
+                data[y as usize * width + x as usize]
+            }
+        };
+        let top = at(x0, y0) * (1.0 - tx) + at(x0 + 1.0, y0) * tx;
+        let bottom = at(x0, y0 + 1.0) * (1.0 - tx) + at(x0 + 1.0, y0 + 1.0) * tx;
+        top * (1.0 - ty) + bottom * ty
+    };
+
+    let (out_cx, out_cy) = (out_w as f32 / 2.0, out_h as f32 / 2.0);
+    let (src_cx, src_cy) = (width as f32 / 2.0, height as f32 / 2.0);
+    let data = crate::render::dither::generate_intensities(out_w, out_h, |x, y, _, _| {
+        let mut sum = 0.0;
+        for j in 0..samples {
+            for i in 0..samples {
+                let dx = x as f32 + (i as f32 + 0.5) * step - out_cx;
+                let dy = y as f32 + (j as f32 + 0.5) * step - out_cy;
+                // Inverse rotation, then inverse scale
+                let rx = dx * cos + dy * sin;
+                let ry = -dx * sin + dy * cos;
+                sum += sample(rx / scale_x + src_cx, ry / scale_y + src_cy);
+            }
+        }
+        sum / (samples * samples) as f32
+    });
+
+    (data, out_w, out_h)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -85,4 +163,36 @@ mod tests {
         assert!((BlendMode::Min.apply(0.3, 0.7) - 0.3).abs() < 1e-6);
         assert!((BlendMode::Max.apply(0.3, 0.7) - 0.7).abs() < 1e-6);
     }
+
+    #[test]
+    fn test_blend_mode_mask_and_knockout() {
+        // Through the glyph the pattern shows; around it, paper
+        assert!((BlendMode::Mask.apply(0.6, 1.0) - 0.6).abs() < 1e-6);
+        assert_eq!(BlendMode::Mask.apply(0.6, 0.0), 0.0);
+        assert_eq!(BlendMode::Knockout.apply(0.6, 1.0), 0.0);
+        assert!((BlendMode::Knockout.apply(0.6, 0.0) - 0.6).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_transform_identity_and_scale() {
+        let data: Vec<f32> = (0..12).map(|i| (i % 2) as f32).collect();
+        let (out, w, h) = transform(&data, 4, 3, 0.0, 1.0, 1.0);
+        assert_eq!((w, h), (4, 3));
+        assert_eq!(out, data);
+
+        let (_, w, h) = transform(&data, 4, 3, 0.0, 2.0, 0.5);
+        assert_eq!((w, h), (8, 2));
+    }
+
+    #[test]
+    fn test_transform_rotate_quarter_turn() {
+        // Black left column of a 4x2 layer becomes the top row after 90° clockwise
+        let mut data = vec![0.0f32; 8];
+        data[0] = 1.0;
+        data[4] = 1.0;
+        let (out, w, h) = transform(&data, 4, 2, 90.0, 1.0, 1.0);
+        assert_eq!((w, h), (2, 4));
+        assert!(out[0] > 0.9 && out[1] > 0.9);
+        assert!(out[2..].iter().all(|&v| v < 0.1));
+    }
 }
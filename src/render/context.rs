@@ -5,11 +5,14 @@
 //! an image) use the context to access shared infrastructure like HTTP clients
 //! and caches, keeping callers unaware of what happens behind the scenes.
 
+use image::DynamicImage;
 use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
 use std::sync::Arc;
+use std::time::Instant;
 use tokio::sync::RwLock;
 
-use crate::server::{CachedIntensity, IntensityCacheKey, PhotoSession};
+use super::exif::PhotoInfo;
 
 /// Shared resources available to patterns during `prepare()`.
 ///
@@ -80,3 +83,137 @@ impl RenderContext {
         buffer
     }
 }
+
+/// Cache key for rendered intensity buffers.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct IntensityCacheKey {
+    /// Pattern name.
+    pub pattern: String,
+    /// Hash of pattern parameters (deterministic).
+    pub params_hash: u64,
+    /// Layer width in pixels.
+    pub width: usize,
+    /// Layer height in pixels.
+    pub height: usize,
+}
+
+impl IntensityCacheKey {
+    /// Create a new cache key from layer parameters.
+    pub fn new(
+        pattern: &str,
+        params: &HashMap<String, String>,
+        width: usize,
+        height: usize,
+    ) -> Self {
+        Self {
+            pattern: pattern.to_string(),
+            params_hash: hash_params(params),
+            width,
+            height,
+        }
+    }
+}
+
+/// Hash pattern parameters deterministically.
+fn hash_params(params: &HashMap<String, String>) -> u64 {
+    use std::collections::hash_map::DefaultHasher;
+
+    // Sort keys for deterministic ordering
+    let mut sorted: Vec<_> = params.iter().collect();
+    sorted.sort_by_key(|(k, _)| *k);
+
+    let mut hasher = DefaultHasher::new();
+    for (k, v) in sorted {
+        k.hash(&mut hasher);
+        v.hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+/// Cached intensity buffer (quantized to u8 + gzip compressed).
+pub struct CachedIntensity {
+    /// Compressed intensity data (quantized u8 values, gzip compressed).
+    compressed: Vec<u8>,
+    /// Original uncompressed size (for allocation hint).
+    uncompressed_size: usize,
+    /// Last time this cache entry was accessed.
+    pub last_accessed: Instant,
+}
+
+impl CachedIntensity {
+    /// Create from f32 intensities.
+    /// Quantizes to u8 and compresses with gzip for memory savings.
+    pub fn new(intensity: &[f32]) -> Self {
+        use flate2::Compression;
+        use flate2::write::GzEncoder;
+        use std::io::Write;
+
+        let uncompressed_size = intensity.len();
+
+        // Quantize f32 -> u8
+        let quantized: Vec<u8> = intensity
+            .iter()
+            .map(|&v| (v.clamp(0.0, 1.0) * 255.0).round() as u8)
+            .collect();
+
+        // Compress with gzip (fast compression level for speed)
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::fast());
+        encoder.write_all(&quantized).expect("compression failed");
+        let compressed = encoder.finish().expect("compression finish failed");
+
+        Self {
+            compressed,
+            uncompressed_size,
+            last_accessed: Instant::now(),
+        }
+    }
+
+    /// Decompress and dequantize back to f32 intensities.
+    pub fn intensity(&self) -> Vec<f32> {
+        use flate2::read::GzDecoder;
+        use std::io::Read;
+
+        let mut decoder = GzDecoder::new(&self.compressed[..]);
+        let mut quantized = Vec::with_capacity(self.uncompressed_size);
+        decoder
+            .read_to_end(&mut quantized)
+            .expect("decompression failed");
+
+        quantized.iter().map(|&v| v as f32 / 255.0).collect()
+    }
+
+    /// Update last_accessed time.
+    pub fn touch(&mut self) {
+        self.last_accessed = Instant::now();
+    }
+}
+
+/// A photo session storing an uploaded image.
+pub struct PhotoSession {
+    /// The decoded image
+    pub image: DynamicImage,
+    /// EXIF details read when the image was decoded
+    pub info: PhotoInfo,
+    /// Last accessed time (for expiration)
+    pub last_accessed: Instant,
+}
+
+impl PhotoSession {
+    pub fn new(image: DynamicImage) -> Self {
+        Self::with_info(image, PhotoInfo::default())
+    }
+
+    /// A session that keeps the photo's EXIF details for captions.
+    pub fn with_info(image: DynamicImage, info: PhotoInfo) -> Self {
+        Self {
+            image,
+            info,
+            last_accessed: Instant::now(),
+        }
+    }
+
+    /// Touch the session to update last_accessed time.
+    pub fn touch(&mut self) {
+        self.last_accessed = Instant::now();
+    }
+}
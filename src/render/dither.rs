@@ -61,6 +61,14 @@
 //! - **Good patterns**: Produces visually pleasing halftone screens
 //! - **Thermal-friendly**: Works well with thermal printer characteristics
 //!
+//! ## Blue-Noise Ordered Dithering
+//!
+//! [`DitheringAlgorithm::BlueNoise`] works exactly like Bayer, but looks up
+//! thresholds in a 64×64 void-and-cluster matrix ([`super::blue_noise`])
+//! instead of the 8×8 Bayer one. Dots at every gray level are spread
+//! evenly with no regular structure, so smooth gradients (plasma, ripple)
+//! print as fine grain instead of a visible crosshatch.
+//!
 //! ## Floyd-Steinberg Error Diffusion
 //!
 //! Floyd-Steinberg dithering uses error diffusion to distribute quantization
@@ -121,9 +129,27 @@
 //! | Method | Speed | Quality | Artifacts | Best For |
 //! |--------|-------|---------|-----------|----------|
 //! | Bayer | Fast | Good | Regular pattern | Text, graphics, patterns |
+//! | Blue noise | Fast | Better | Fine grain, no crosshatch | Smooth patterns and gradients |
 //! | Floyd-Steinberg | Medium | Better | Occasional worms | Photos, continuous tones |
 //! | Atkinson | Medium | Good | Higher contrast | Retro look, line art |
 //! | Jarvis | Slower | Best | Smoothest | High-quality photos |
+//! | Auto | Slowest | Per region | — | Mixed content (logo + photo) |
+//!
+//! ## Adaptive Dithering
+//!
+//! [`DitheringAlgorithm::Auto`] classifies the buffer in 64×64 tiles
+//! (see [`classify_region`]) and dithers each with the algorithm that suits
+//! it: thresholding for line art and text, ordered dithering for smooth
+//! gradients, error diffusion for photos. Each chosen algorithm runs over
+//! the whole buffer, so diffusion flows across tile edges without seams.
+//!
+//! ## Serpentine Scanning and Tone Curves
+//!
+//! [`DitherOptions`] adjusts any algorithm per call: `serpentine` runs the
+//! error-diffusion dithers right-to-left on every other row, which breaks
+//! up directional artifacts on large patterns, and `gamma`/`levels` reshape
+//! intensities before dithering. On the command line they follow the
+//! algorithm name as a [`DitherSpec`]: `fs:serpentine,gamma=1.8`.
 //!
 //! ## Usage Example
 //!
@@ -141,6 +167,8 @@
 //! }, DitheringAlgorithm::FloydSteinberg);
 //! ```
 
+use super::blue_noise;
+
 /// Dithering algorithm selection.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 #[serde(rename_all = "snake_case")]
@@ -158,6 +186,196 @@ pub enum DitheringAlgorithm {
     Atkinson,
     /// Jarvis-Judice-Ninke dithering (smoother gradients, larger diffusion)
     Jarvis,
+    /// Blue-noise ordered dithering (fast like Bayer, without the crosshatch)
+    #[serde(alias = "blue-noise")]
+    BlueNoise,
+    /// Pick per region from the content: threshold, Bayer or Floyd-Steinberg
+    Auto,
+}
+
+impl DitheringAlgorithm {
+    /// Canonical name, as accepted by [`str::parse`].
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::Bayer => "bayer",
+            Self::FloydSteinberg => "floyd-steinberg",
+            Self::Atkinson => "atkinson",
+            Self::Jarvis => "jarvis",
+            Self::BlueNoise => "blue-noise",
+            Self::Auto => "auto",
+        }
+    }
+}
+
+impl std::str::FromStr for DitheringAlgorithm {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "none" | "threshold" => Ok(Self::None),
+            "bayer" => Ok(Self::Bayer),
+            "floyd-steinberg" | "floyd_steinberg" | "fs" => Ok(Self::FloydSteinberg),
+            "atkinson" => Ok(Self::Atkinson),
+            "jarvis" | "jjn" => Ok(Self::Jarvis),
+            "blue-noise" | "blue_noise" | "bluenoise" | "blue" => Ok(Self::BlueNoise),
+            "auto" | "adaptive" => Ok(Self::Auto),
+            _ => Err(format!(
+                "Unknown dithering algorithm '{}'. Use 'none', 'bayer', 'blue-noise', 'floyd-steinberg', 'atkinson', 'jarvis', or 'auto'",
+                s
+            )),
+        }
+    }
+}
+
+// ============================================================================
+// OPTIONS
+// ============================================================================
+
+/// Per-call tweaks on top of a [`DitheringAlgorithm`].
+///
+/// The default changes nothing: left-to-right scanning, no tone curve.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DitherOptions {
+    /// Scan error-diffusion rows alternately left-to-right and
+    /// right-to-left. Breaks up the directional streaks plain scanning
+    /// leaves on large smooth areas. Ignored by Bayer and threshold.
+    pub serpentine: bool,
+    /// Tone curve applied to intensities before dithering: values above
+    /// 1.0 lighten midtones, values below darken them.
+    pub gamma: f32,
+    /// Input levels `(low, high)`: intensities at or below `low` print as
+    /// paper, at or above `high` as solid black, stretched in between.
+    pub levels: (f32, f32),
+}
+
+impl Default for DitherOptions {
+    fn default() -> Self {
+        Self {
+            serpentine: false,
+            gamma: 1.0,
+            levels: (0.0, 1.0),
+        }
+    }
+}
+
+impl DitherOptions {
+    /// Apply levels, then gamma, to one intensity.
+    pub fn adjust(&self, intensity: f32) -> f32 {
+        if !self.has_tone_curve() {
+            return intensity;
+        }
+        let (low, high) = self.levels;
+        let stretched = ((intensity - low) / (high - low)).clamp(0.0, 1.0);
+        stretched.powf(self.gamma)
+    }
+
+    fn has_tone_curve(&self) -> bool {
+        self.gamma != 1.0 || self.levels != (0.0, 1.0)
+    }
+
+    /// Apply one `name` or `name=value` option from a [`DitherSpec`].
+    fn set(&mut self, option: &str) -> Result<(), String> {
+        let (name, value) = match option.split_once('=') {
+            Some((name, value)) => (name.trim(), Some(value.trim())),
+            None => (option.trim(), None),
+        };
+        let number = |value: &str| {
+            value
+                .parse::<f32>()
+                .map_err(|_| format!("Invalid number '{}' for dither option '{}'", value, name))
+        };
+        match (name.to_lowercase().as_str(), value) {
+            ("serpentine" | "snake", None) => self.serpentine = true,
+            ("gamma", Some(value)) => {
+                let gamma = number(value)?;
+                if !(gamma > 0.0 && gamma.is_finite()) {
+                    return Err(format!("Gamma must be positive, got {}", value));
+                }
+                self.gamma = gamma;
+            }
+            ("levels", Some(value)) => {
+                let (low, high) = value
+                    .split_once('-')
+                    .ok_or_else(|| format!("Levels must look like 0.1-0.9, got '{}'", value))?;
+                let (low, high) = (number(low)?, number(high)?);
+                if !(0.0 <= low && low < high && high <= 1.0) {
+                    return Err(format!(
+                        "Levels must satisfy 0 <= low < high <= 1, got {}",
+                        value
+                    ));
+                }
+                self.levels = (low, high);
+            }
+            _ => {
+                return Err(format!(
+                    "Unknown dither option '{}'. Use 'serpentine', 'gamma=<n>' or 'levels=<low>-<high>'",
+                    option
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// An algorithm with its options, as written on the command line:
+/// `algorithm[:option,option...]`.
+///
+/// ```
+/// use estrella::render::dither::{DitherSpec, DitheringAlgorithm};
+///
+/// let spec: DitherSpec = "fs:serpentine,gamma=1.8".parse().unwrap();
+/// assert_eq!(spec.algorithm, DitheringAlgorithm::FloydSteinberg);
+/// assert!(spec.options.serpentine);
+/// assert_eq!(spec.options.gamma, 1.8);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct DitherSpec {
+    pub algorithm: DitheringAlgorithm,
+    pub options: DitherOptions,
+}
+
+impl std::str::FromStr for DitherSpec {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (algorithm, options) = match s.split_once(':') {
+            Some((algorithm, options)) => (algorithm, Some(options)),
+            None => (s, None),
+        };
+        let mut spec = DitherSpec {
+            algorithm: algorithm.trim().parse()?,
+            options: DitherOptions::default(),
+        };
+        for option in options.into_iter().flat_map(|o| o.split(',')) {
+            if !option.trim().is_empty() {
+                spec.options.set(option)?;
+            }
+        }
+        Ok(spec)
+    }
+}
+
+impl std::fmt::Display for DitherSpec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let defaults = DitherOptions::default();
+        let mut options = Vec::new();
+        if self.options.serpentine {
+            options.push("serpentine".to_string());
+        }
+        if self.options.gamma != defaults.gamma {
+            options.push(format!("gamma={}", self.options.gamma));
+        }
+        if self.options.levels != defaults.levels {
+            let (low, high) = self.options.levels;
+            options.push(format!("levels={}-{}", low, high));
+        }
+        write!(f, "{}", self.algorithm.as_str())?;
+        if !options.is_empty() {
+            write!(f, ":{}", options.join(","))?;
+        }
+        Ok(())
+    }
 }
 
 // ============================================================================
@@ -329,20 +547,224 @@ pub fn generate_raster<F>(
     intensity_fn: F,
     algorithm: DitheringAlgorithm,
 ) -> Vec<u8>
+where
+    F: Fn(usize, usize, usize, usize) -> f32 + Sync,
+{
+    generate_raster_with_options(
+        width,
+        height,
+        intensity_fn,
+        algorithm,
+        &DitherOptions::default(),
+    )
+}
+
+/// Like [`generate_raster`], with serpentine scanning and a tone curve
+/// from [`DitherOptions`].
+///
+/// ```
+/// use estrella::render::dither::{generate_raster_with_options, DitherOptions, DitheringAlgorithm};
+///
+/// let options = DitherOptions { serpentine: true, gamma: 1.8, ..Default::default() };
+/// let data = generate_raster_with_options(64, 100, |x, _y, w, _h| {
+///     x as f32 / w as f32
+/// }, DitheringAlgorithm::FloydSteinberg, &options);
+///
+/// assert_eq!(data.len(), 8 * 100);
+/// ```
+pub fn generate_raster_with_options<F>(
+    width: usize,
+    height: usize,
+    intensity_fn: F,
+    algorithm: DitheringAlgorithm,
+    options: &DitherOptions,
+) -> Vec<u8>
+where
+    F: Fn(usize, usize, usize, usize) -> f32 + Sync,
+{
+    if options.has_tone_curve() {
+        let options = *options;
+        let adjusted = move |x, y, w, h| options.adjust(intensity_fn(x, y, w, h));
+        return dither_with_serpentine(width, height, adjusted, algorithm, options.serpentine);
+    }
+    dither_with_serpentine(width, height, intensity_fn, algorithm, options.serpentine)
+}
+
+fn dither_with_serpentine<F>(
+    width: usize,
+    height: usize,
+    intensity_fn: F,
+    algorithm: DitheringAlgorithm,
+    serpentine: bool,
+) -> Vec<u8>
 where
     F: Fn(usize, usize, usize, usize) -> f32 + Sync,
 {
     match algorithm {
         DitheringAlgorithm::None => generate_raster_threshold(width, height, intensity_fn),
-        DitheringAlgorithm::Bayer => generate_raster_bayer(width, height, intensity_fn),
+        DitheringAlgorithm::Bayer => {
+            generate_raster_ordered(width, height, intensity_fn, should_print)
+        }
+        DitheringAlgorithm::BlueNoise => {
+            generate_raster_ordered(width, height, intensity_fn, blue_noise::should_print)
+        }
         DitheringAlgorithm::FloydSteinberg => {
-            generate_raster_floyd_steinberg(width, height, intensity_fn)
+            generate_raster_floyd_steinberg(width, height, intensity_fn, serpentine)
+        }
+        DitheringAlgorithm::Atkinson => {
+            generate_raster_atkinson(width, height, intensity_fn, serpentine)
+        }
+        DitheringAlgorithm::Jarvis => {
+            generate_raster_jarvis(width, height, intensity_fn, serpentine)
+        }
+        DitheringAlgorithm::Auto => {
+            let intensities = generate_intensities(width, height, intensity_fn);
+            dither_adaptive_impl(width, height, &intensities, serpentine)
         }
-        DitheringAlgorithm::Atkinson => generate_raster_atkinson(width, height, intensity_fn),
-        DitheringAlgorithm::Jarvis => generate_raster_jarvis(width, height, intensity_fn),
     }
 }
 
+/// Sample an intensity function into a grayscale buffer without dithering.
+///
+/// Returns one f32 per pixel (0.0 = white, 1.0 = black), row-major.
+/// The result can be carried through the IR as `Op::GrayRaster` (see
+/// [`to_levels`]) and dithered later.
+pub fn generate_intensities<F>(width: usize, height: usize, intensity_fn: F) -> Vec<f32>
+where
+    F: Fn(usize, usize, usize, usize) -> f32 + Sync,
+{
+    use super::par::*;
+
+    (0..height)
+        .into_par_iter()
+        .flat_map_iter(|y| (0..width).map(move |x| (x, y)))
+        .map(|(x, y)| intensity_fn(x, y, width, height))
+        .collect()
+}
+
+/// Like [`generate_intensities`], but only evaluates every `row_step`-th
+/// row and repeats it below — a cheap low-resolution draft with the same
+/// dimensions. A `row_step` of 0 or 1 evaluates every row.
+pub fn generate_intensities_draft<F>(
+    width: usize,
+    height: usize,
+    row_step: usize,
+    intensity_fn: F,
+) -> Vec<f32>
+where
+    F: Fn(usize, usize, usize, usize) -> f32 + Sync,
+{
+    use super::par::*;
+
+    if row_step <= 1 {
+        return generate_intensities(width, height, intensity_fn);
+    }
+
+    let mut data = vec![0.0f32; width * height];
+    data.par_chunks_mut(width * row_step)
+        .enumerate()
+        .for_each(|(i, block)| {
+            let y = i * row_step;
+            let (first, rest) = block.split_at_mut(width);
+            for (x, value) in first.iter_mut().enumerate() {
+                *value = intensity_fn(x, y, width, height);
+            }
+            for row in rest.chunks_mut(width) {
+                row.copy_from_slice(first);
+            }
+        });
+    data
+}
+
+/// Quantize an intensity (0.0 = white, 1.0 = black) to an 8-bit gray
+/// level (0 = white, 255 = black).
+pub fn level(intensity: f32) -> u8 {
+    (intensity.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+/// Quantize a grayscale intensity buffer to the 8-bit gray levels
+/// `Op::GrayRaster` carries.
+pub fn to_levels(intensities: &[f32]) -> Vec<u8> {
+    intensities
+        .iter()
+        .map(|&intensity| level(intensity))
+        .collect()
+}
+
+/// Dither 8-bit gray levels (0 = white, 255 = black, row-major) to packed
+/// 1-bit raster data, as [`dither_intensities`] does for intensities.
+///
+/// ```
+/// use estrella::render::dither::{dither_levels, DitheringAlgorithm};
+///
+/// let data = dither_levels(16, 2, &[255; 16 * 2], DitheringAlgorithm::Bayer);
+/// assert_eq!(data, vec![0xFF; 4]);
+/// ```
+pub fn dither_levels(
+    width: usize,
+    height: usize,
+    levels: &[u8],
+    algorithm: DitheringAlgorithm,
+) -> Vec<u8> {
+    generate_raster_with_options(
+        width,
+        height,
+        |x, y, w, _h| {
+            levels
+                .get(y * w + x)
+                .map_or(0.0, |&level| f32::from(level) / 255.0)
+        },
+        algorithm,
+        &DitherOptions::default(),
+    )
+}
+
+/// Dither a grayscale intensity buffer to packed 1-bit raster data.
+///
+/// `intensities` is row-major, one value per pixel (0.0 = white, 1.0 = black).
+/// Missing pixels (short buffers) are treated as white.
+///
+/// ## Example
+///
+/// ```
+/// use estrella::render::dither::{dither_intensities, DitheringAlgorithm};
+///
+/// let gray = vec![1.0f32; 16 * 2];
+/// let data = dither_intensities(16, 2, &gray, DitheringAlgorithm::Bayer);
+/// assert_eq!(data, vec![0xFF; 4]);
+/// ```
+pub fn dither_intensities(
+    width: usize,
+    height: usize,
+    intensities: &[f32],
+    algorithm: DitheringAlgorithm,
+) -> Vec<u8> {
+    dither_intensities_with_options(
+        width,
+        height,
+        intensities,
+        algorithm,
+        &DitherOptions::default(),
+    )
+}
+
+/// Like [`dither_intensities`], with [`DitherOptions`].
+pub fn dither_intensities_with_options(
+    width: usize,
+    height: usize,
+    intensities: &[f32],
+    algorithm: DitheringAlgorithm,
+    options: &DitherOptions,
+) -> Vec<u8> {
+    generate_raster_with_options(
+        width,
+        height,
+        |x, y, w, _h| intensities.get(y * w + x).copied().unwrap_or(0.0),
+        algorithm,
+        options,
+    )
+}
+
 // ============================================================================
 // SIMPLE THRESHOLD (NO DITHERING)
 // ============================================================================
@@ -381,13 +803,19 @@ where
 // BAYER 8x8 ORDERED DITHERING
 // ============================================================================
 
-/// Generate a dithered raster using Bayer ordered dithering.
+/// Generate a dithered raster with an ordered (threshold matrix) dither:
+/// Bayer's [`should_print`] or [`blue_noise::should_print`].
 /// Uses parallel processing for large images (height > 100).
-fn generate_raster_bayer<F>(width: usize, height: usize, intensity_fn: F) -> Vec<u8>
+fn generate_raster_ordered<F>(
+    width: usize,
+    height: usize,
+    intensity_fn: F,
+    should_print: fn(usize, usize, f32) -> bool,
+) -> Vec<u8>
 where
     F: Fn(usize, usize, usize, usize) -> f32 + Sync,
 {
-    use rayon::prelude::*;
+    use super::par::*;
 
     let width_bytes = width.div_ceil(8);
 
@@ -444,69 +872,22 @@ where
 /// ```
 ///
 /// Where X is the current pixel being processed.
-#[allow(clippy::needless_range_loop)]
-fn generate_raster_floyd_steinberg<F>(width: usize, height: usize, intensity_fn: F) -> Vec<u8>
+fn generate_raster_floyd_steinberg<F>(
+    width: usize,
+    height: usize,
+    intensity_fn: F,
+    serpentine: bool,
+) -> Vec<u8>
 where
     F: Fn(usize, usize, usize, usize) -> f32,
 {
-    let width_bytes = width.div_ceil(8);
-    let mut data = Vec::with_capacity(width_bytes * height);
-
-    // Buffer to accumulate errors - current row and next row
-    // We use f32 to track fractional intensity values with accumulated error
-    // curr_row holds accumulated error from previous row, we add base intensity to it
-    let mut curr_row = vec![0.0f32; width];
-    let mut next_row = vec![0.0f32; width];
-
-    for y in 0..height {
-        // Add base intensity to accumulated error for current row
-        for x in 0..width {
-            curr_row[x] += intensity_fn(x, y, width, height);
-        }
-
-        // Process current row left-to-right
-        let mut row_pixels = Vec::with_capacity(width);
-        for x in 0..width {
-            // Get intensity with accumulated error, clamped to valid range
-            let intensity = curr_row[x].clamp(0.0, 1.0);
-
-            // Threshold at 0.5
-            let output = if intensity >= 0.5 { 1.0 } else { 0.0 };
-            row_pixels.push(output > 0.5);
-
-            // Calculate quantization error
-            let error = intensity - output;
-
-            // Distribute error to neighbors (if they exist)
-            // Right: 7/16
-            if x + 1 < width {
-                curr_row[x + 1] += error * (7.0 / 16.0);
-            }
-
-            // Bottom-left: 3/16
-            if x > 0 {
-                next_row[x - 1] += error * (3.0 / 16.0);
-            }
-
-            // Bottom: 5/16
-            next_row[x] += error * (5.0 / 16.0);
-
-            // Bottom-right: 1/16
-            if x + 1 < width {
-                next_row[x + 1] += error * (1.0 / 16.0);
-            }
-        }
-
-        // Pack the row into bytes and add to data
-        data.extend(pack_row(&row_pixels));
-
-        // Swap buffers: next_row (with accumulated error) becomes curr_row
-        std::mem::swap(&mut curr_row, &mut next_row);
-        // Clear next_row (old curr_row) for accumulating errors for row y+2
-        next_row.fill(0.0);
-    }
-
-    data
+    const KERNEL: Kernel = &[
+        (1, 0, 7.0 / 16.0),
+        (-1, 1, 3.0 / 16.0),
+        (0, 1, 5.0 / 16.0),
+        (1, 1, 1.0 / 16.0),
+    ];
+    generate_raster_diffusion(width, height, intensity_fn, KERNEL, serpentine)
 }
 
 // ============================================================================
@@ -528,81 +909,24 @@ where
 /// ```
 ///
 /// Note: 2/8 of the error is intentionally discarded, creating higher contrast.
-#[allow(clippy::needless_range_loop)]
-fn generate_raster_atkinson<F>(width: usize, height: usize, intensity_fn: F) -> Vec<u8>
+fn generate_raster_atkinson<F>(
+    width: usize,
+    height: usize,
+    intensity_fn: F,
+    serpentine: bool,
+) -> Vec<u8>
 where
     F: Fn(usize, usize, usize, usize) -> f32,
 {
-    let width_bytes = width.div_ceil(8);
-    let mut data = Vec::with_capacity(width_bytes * height);
-
-    // Buffer to accumulate errors - we need current row and two rows ahead
-    // curr_row holds accumulated error from previous rows, we add base intensity to it
-    let mut curr_row = vec![0.0f32; width];
-    let mut next_row = vec![0.0f32; width];
-    let mut next_next_row = vec![0.0f32; width];
-
-    for y in 0..height {
-        // Add base intensity to accumulated error for current row
-        for x in 0..width {
-            curr_row[x] += intensity_fn(x, y, width, height);
-        }
-
-        // Process current row left-to-right
-        let mut row_pixels = Vec::with_capacity(width);
-        for x in 0..width {
-            // Get intensity with accumulated error from previous pixels
-            let intensity = curr_row[x].clamp(0.0, 1.0);
-
-            // Threshold at 0.5
-            let output = if intensity >= 0.5 { 1.0 } else { 0.0 };
-            row_pixels.push(output > 0.5);
-
-            // Calculate quantization error
-            let error = intensity - output;
-            let diffused = error / 8.0; // Each neighbor gets 1/8
-
-            // Distribute error to neighbors (if they exist)
-            // Atkinson only distributes 6/8 of the error, 2/8 is lost
-
-            // Right: 1/8
-            if x + 1 < width {
-                curr_row[x + 1] += diffused;
-            }
-
-            // Right+1: 1/8
-            if x + 2 < width {
-                curr_row[x + 2] += diffused;
-            }
-
-            // Bottom-left: 1/8
-            if x > 0 {
-                next_row[x - 1] += diffused;
-            }
-
-            // Bottom: 1/8
-            next_row[x] += diffused;
-
-            // Bottom-right: 1/8
-            if x + 1 < width {
-                next_row[x + 1] += diffused;
-            }
-
-            // Two rows down, center: 1/8
-            next_next_row[x] += diffused;
-        }
-
-        // Pack the row into bytes and add to data
-        data.extend(pack_row(&row_pixels));
-
-        // Rotate buffers
-        std::mem::swap(&mut curr_row, &mut next_row);
-        std::mem::swap(&mut next_row, &mut next_next_row);
-        // Clear the furthest row for the next iteration
-        next_next_row.fill(0.0);
-    }
-
-    data
+    const KERNEL: Kernel = &[
+        (1, 0, 1.0 / 8.0),
+        (2, 0, 1.0 / 8.0),
+        (-1, 1, 1.0 / 8.0),
+        (0, 1, 1.0 / 8.0),
+        (1, 1, 1.0 / 8.0),
+        (0, 2, 1.0 / 8.0),
+    ];
+    generate_raster_diffusion(width, height, intensity_fn, KERNEL, serpentine)
 }
 
 // ============================================================================
@@ -622,91 +946,219 @@ where
 ///   3/48  5/48  7/48  5/48  3/48
 ///   1/48  3/48  5/48  3/48  1/48
 /// ```
-#[allow(clippy::needless_range_loop)]
-fn generate_raster_jarvis<F>(width: usize, height: usize, intensity_fn: F) -> Vec<u8>
+fn generate_raster_jarvis<F>(
+    width: usize,
+    height: usize,
+    intensity_fn: F,
+    serpentine: bool,
+) -> Vec<u8>
+where
+    F: Fn(usize, usize, usize, usize) -> f32,
+{
+    const KERNEL: Kernel = &[
+        (1, 0, 7.0 / 48.0),
+        (2, 0, 5.0 / 48.0),
+        (-2, 1, 3.0 / 48.0),
+        (-1, 1, 5.0 / 48.0),
+        (0, 1, 7.0 / 48.0),
+        (1, 1, 5.0 / 48.0),
+        (2, 1, 3.0 / 48.0),
+        (-2, 2, 1.0 / 48.0),
+        (-1, 2, 3.0 / 48.0),
+        (0, 2, 5.0 / 48.0),
+        (1, 2, 3.0 / 48.0),
+        (2, 2, 1.0 / 48.0),
+    ];
+    generate_raster_diffusion(width, height, intensity_fn, KERNEL, serpentine)
+}
+
+// ============================================================================
+// ERROR DIFFUSION
+// ============================================================================
+
+/// Error diffusion weights as `(dx, dy, weight)` from the current pixel,
+/// written for a left-to-right scan.
+type Kernel = &'static [(isize, usize, f32)];
+
+/// Threshold each pixel at 0.5 and push the quantization error onto the
+/// unprocessed neighbors given by `kernel`.
+///
+/// With `serpentine`, odd rows run right-to-left with the kernel mirrored,
+/// so error doesn't always drift the same way and long diagonal "worms"
+/// break up.
+fn generate_raster_diffusion<F>(
+    width: usize,
+    height: usize,
+    intensity_fn: F,
+    kernel: Kernel,
+    serpentine: bool,
+) -> Vec<u8>
 where
     F: Fn(usize, usize, usize, usize) -> f32,
 {
     let width_bytes = width.div_ceil(8);
     let mut data = Vec::with_capacity(width_bytes * height);
 
-    // Buffer to accumulate errors - we need current row and two rows ahead
-    // curr_row holds accumulated error from previous rows, we add base intensity to it
-    let mut curr_row = vec![0.0f32; width];
-    let mut next_row = vec![0.0f32; width];
-    let mut next_next_row = vec![0.0f32; width];
+    // rows[0] is the current row; rows[dy] accumulates error for row y + dy
+    let depth = kernel.iter().map(|&(_, dy, _)| dy).max().unwrap_or(0) + 1;
+    let mut rows = vec![vec![0.0f32; width]; depth];
+    let mut row_pixels = vec![false; width];
 
     for y in 0..height {
         // Add base intensity to accumulated error for current row
-        for x in 0..width {
-            curr_row[x] += intensity_fn(x, y, width, height);
+        for (x, value) in rows[0].iter_mut().enumerate() {
+            *value += intensity_fn(x, y, width, height);
         }
 
-        // Process current row left-to-right
-        let mut row_pixels = Vec::with_capacity(width);
-        for x in 0..width {
-            // Get intensity with accumulated error from previous pixels
-            let intensity = curr_row[x].clamp(0.0, 1.0);
+        let reverse = serpentine && y % 2 == 1;
+        for i in 0..width {
+            let x = if reverse { width - 1 - i } else { i };
+
+            // Get intensity with accumulated error, clamped to valid range
+            let intensity = rows[0][x].clamp(0.0, 1.0);
 
             // Threshold at 0.5
             let output = if intensity >= 0.5 { 1.0 } else { 0.0 };
-            row_pixels.push(output > 0.5);
+            row_pixels[x] = output > 0.5;
 
-            // Calculate quantization error
+            // Distribute the quantization error to neighbors (if they exist)
             let error = intensity - output;
+            for &(dx, dy, weight) in kernel {
+                let dx = if reverse { -dx } else { dx };
+                if let Some(nx) = x.checked_add_signed(dx)
+                    && nx < width
+                {
+                    rows[dy][nx] += error * weight;
+                }
+            }
+        }
 
-            // Distribute error to neighbors using Jarvis-Judice-Ninke coefficients
-            // Total = 48, all coefficients sum to 48
+        // Pack the row into bytes and add to data
+        data.extend(pack_row(&row_pixels));
 
-            // Current row: X, +1, +2
-            if x + 1 < width {
-                curr_row[x + 1] += error * (7.0 / 48.0);
-            }
-            if x + 2 < width {
-                curr_row[x + 2] += error * (5.0 / 48.0);
-            }
+        // Rotate buffers and clear the furthest row for the next iteration
+        rows.rotate_left(1);
+        if let Some(last) = rows.last_mut() {
+            last.fill(0.0);
+        }
+    }
 
-            // Next row: -2, -1, 0, +1, +2
-            if x >= 2 {
-                next_row[x - 2] += error * (3.0 / 48.0);
-            }
-            if x >= 1 {
-                next_row[x - 1] += error * (5.0 / 48.0);
-            }
-            next_row[x] += error * (7.0 / 48.0);
-            if x + 1 < width {
-                next_row[x + 1] += error * (5.0 / 48.0);
-            }
-            if x + 2 < width {
-                next_row[x + 2] += error * (3.0 / 48.0);
-            }
+    data
+}
 
-            // Row after next: -2, -1, 0, +1, +2
-            if x >= 2 {
-                next_next_row[x - 2] += error * (1.0 / 48.0);
-            }
-            if x >= 1 {
-                next_next_row[x - 1] += error * (3.0 / 48.0);
-            }
-            next_next_row[x] += error * (5.0 / 48.0);
-            if x + 1 < width {
-                next_next_row[x + 1] += error * (3.0 / 48.0);
+// ============================================================================
+// ADAPTIVE DITHERING
+// ============================================================================
+
+/// Tile size for adaptive classification. A multiple of 8, so every packed
+/// byte belongs to a single tile.
+const ADAPTIVE_TILE: usize = 64;
+
+/// Intensity step between neighbors that counts as an edge.
+const EDGE_STEP: f32 = 0.08;
+
+/// Pick a dithering algorithm for a region of a grayscale buffer.
+///
+/// Looks at the histogram and edge density of the pixels in
+/// `x0..x1` × `y0..y1`:
+///
+/// - almost every pixel near pure black or white (line art, text, blank
+///   paper) → [`DitheringAlgorithm::None`], which keeps strokes crisp
+/// - mid-tones with few edges (flat fills, smooth gradients, patterns) →
+///   [`DitheringAlgorithm::Bayer`], which renders them without worms
+/// - mid-tones with busy detail (photos) → [`DitheringAlgorithm::FloydSteinberg`]
+pub fn classify_region(
+    intensities: &[f32],
+    width: usize,
+    (x0, y0): (usize, usize),
+    (x1, y1): (usize, usize),
+) -> DitheringAlgorithm {
+    let at = |x: usize, y: usize| intensities.get(y * width + x).copied().unwrap_or(0.0);
+
+    let (mut pixels, mut binary, mut pairs, mut edges) = (0usize, 0usize, 0usize, 0usize);
+    for y in y0..y1 {
+        for x in x0..x1 {
+            let v = at(x, y);
+            pixels += 1;
+            if !(0.1..0.9).contains(&v) {
+                binary += 1;
             }
-            if x + 2 < width {
-                next_next_row[x + 2] += error * (1.0 / 48.0);
+            for (nx, ny) in [(x + 1, y), (x, y + 1)] {
+                if nx < x1 && ny < y1 {
+                    pairs += 1;
+                    if (at(nx, ny) - v).abs() > EDGE_STEP {
+                        edges += 1;
+                    }
+                }
             }
         }
+    }
 
-        // Pack the row into bytes and add to data
-        data.extend(pack_row(&row_pixels));
+    if binary as f32 >= pixels as f32 * 0.9 {
+        DitheringAlgorithm::None
+    } else if edges as f32 <= pairs as f32 * 0.02 {
+        DitheringAlgorithm::Bayer
+    } else {
+        DitheringAlgorithm::FloydSteinberg
+    }
+}
 
-        // Rotate buffers
-        std::mem::swap(&mut curr_row, &mut next_row);
-        std::mem::swap(&mut next_row, &mut next_next_row);
-        // Clear the furthest row for the next iteration
-        next_next_row.fill(0.0);
+/// Dither a grayscale buffer, choosing the algorithm per 64×64 tile with
+/// [`classify_region`].
+pub fn dither_adaptive(width: usize, height: usize, intensities: &[f32]) -> Vec<u8> {
+    dither_adaptive_impl(width, height, intensities, false)
+}
+
+fn dither_adaptive_impl(
+    width: usize,
+    height: usize,
+    intensities: &[f32],
+    serpentine: bool,
+) -> Vec<u8> {
+    let tiles_x = width.div_ceil(ADAPTIVE_TILE);
+    let tiles_y = height.div_ceil(ADAPTIVE_TILE);
+    let choices: Vec<DitheringAlgorithm> = (0..tiles_y)
+        .flat_map(|ty| (0..tiles_x).map(move |tx| (tx, ty)))
+        .map(|(tx, ty)| {
+            let (x0, y0) = (tx * ADAPTIVE_TILE, ty * ADAPTIVE_TILE);
+            let x1 = (x0 + ADAPTIVE_TILE).min(width);
+            let y1 = (y0 + ADAPTIVE_TILE).min(height);
+            classify_region(intensities, width, (x0, y0), (x1, y1))
+        })
+        .collect();
+
+    // Dither the whole buffer once per algorithm in use
+    let mut rasters: Vec<(DitheringAlgorithm, Vec<u8>)> = Vec::new();
+    for &algorithm in &choices {
+        if !rasters.iter().any(|(a, _)| *a == algorithm) {
+            rasters.push((
+                algorithm,
+                dither_with_serpentine(
+                    width,
+                    height,
+                    |x, y, w, _h| intensities.get(y * w + x).copied().unwrap_or(0.0),
+                    algorithm,
+                    serpentine,
+                ),
+            ));
+        }
+    }
+    if rasters.len() <= 1 {
+        return rasters
+            .pop()
+            .map(|(_, data)| data)
+            .unwrap_or_else(|| vec![0; width.div_ceil(8) * height]);
     }
 
+    let width_bytes = width.div_ceil(8);
+    let mut data = vec![0u8; width_bytes * height];
+    for (i, byte) in data.iter_mut().enumerate() {
+        let (y, bx) = (i / width_bytes, i % width_bytes);
+        let choice = choices[(y / ADAPTIVE_TILE) * tiles_x + bx * 8 / ADAPTIVE_TILE];
+        if let Some((_, raster)) = rasters.iter().find(|(a, _)| *a == choice) {
+            *byte = raster[i];
+        }
+    }
     data
 }
 
@@ -862,6 +1314,73 @@ mod tests {
         assert!(data.iter().all(|&b| b == 0x00));
     }
 
+    #[test]
+    fn test_dither_intensities_matches_generate_raster() {
+        let gradient = |x: usize, _y: usize, w: usize, _h: usize| x as f32 / w as f32;
+        let gray = generate_intensities(64, 4, gradient);
+        assert_eq!(gray.len(), 64 * 4);
+        for algo in [
+            DitheringAlgorithm::Bayer,
+            DitheringAlgorithm::FloydSteinberg,
+            DitheringAlgorithm::Atkinson,
+        ] {
+            assert_eq!(
+                dither_intensities(64, 4, &gray, algo),
+                generate_raster(64, 4, gradient, algo)
+            );
+        }
+    }
+
+    #[test]
+    fn test_classify_region() {
+        let (w, h) = (64, 64);
+        let classify = |gray: &[f32]| classify_region(gray, w, (0, 0), (w, h));
+
+        // Black and white stripes: line art
+        let stripes: Vec<f32> = (0..w * h).map(|i| ((i % w) / 4 % 2) as f32).collect();
+        assert_eq!(classify(&stripes), DitheringAlgorithm::None);
+
+        // Smooth gradient
+        let gradient = generate_intensities(w, h, |x, _, w, _| x as f32 / w as f32);
+        assert_eq!(classify(&gradient), DitheringAlgorithm::Bayer);
+
+        // Noisy mid-tones, like a photo
+        let mut seed = 1u32;
+        let noise: Vec<f32> = (0..w * h)
+            .map(|_| {
+                seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                0.2 + 0.6 * (seed >> 8) as f32 / (1u32 << 24) as f32
+            })
+            .collect();
+        assert_eq!(classify(&noise), DitheringAlgorithm::FloydSteinberg);
+    }
+
+    #[test]
+    fn test_adaptive_mixes_algorithms_per_tile() {
+        // Left tile: near-black block on white (line art); right: gradient
+        let (w, h) = (128, 64);
+        let gray = generate_intensities(w, h, |x, _, _, _| match x {
+            0..32 => 0.95,
+            32..64 => 0.0,
+            _ => (x - 64) as f32 / 64.0,
+        });
+        let data = dither_adaptive(w, h, &gray);
+        let threshold = dither_intensities(w, h, &gray, DitheringAlgorithm::None);
+        let bayer = dither_intensities(w, h, &gray, DitheringAlgorithm::Bayer);
+        for row in 0..h {
+            let i = row * 16;
+            assert_eq!(data[i..i + 8], threshold[i..i + 8]);
+            assert_eq!(data[i + 8..i + 16], bayer[i + 8..i + 16]);
+        }
+        // Thresholding keeps the block solid, where Bayer would speckle it
+        assert_eq!(data[..4], [0xFF; 4]);
+        assert_ne!(bayer[..64 * 16], threshold[..64 * 16]);
+        assert_eq!(
+            generate_raster(w, h, |x, y, w, _| gray[y * w + x], DitheringAlgorithm::Auto),
+            data
+        );
+    }
+
     #[test]
     fn test_floyd_steinberg_dimensions() {
         let data = generate_raster(
@@ -980,4 +1499,125 @@ mod tests {
         // Right side should be mostly black (high intensity)
         assert!(data[7] > 0x7F); // Last byte should have many bits set
     }
+
+    #[test]
+    fn test_draft_intensities_repeat_sampled_rows() {
+        let f = |x: usize, y: usize, _w: usize, _h: usize| (x + y) as f32 / 100.0;
+        let full = generate_intensities(8, 10, f);
+        let draft = generate_intensities_draft(8, 10, 4, f);
+        assert_eq!(draft.len(), full.len());
+        for y in 0..10 {
+            let sampled = y / 4 * 4;
+            assert_eq!(
+                draft[y * 8..(y + 1) * 8],
+                full[sampled * 8..(sampled + 1) * 8]
+            );
+        }
+        assert_eq!(generate_intensities_draft(8, 10, 1, f), full);
+    }
+
+    #[test]
+    fn test_serpentine_diffusion() {
+        let gray = |_: usize, _: usize, _: usize, _: usize| 0.3;
+        let ones = |data: &[u8]| data.iter().map(|b| b.count_ones()).sum::<u32>();
+        let options = DitherOptions {
+            serpentine: true,
+            ..Default::default()
+        };
+        for algo in [
+            DitheringAlgorithm::FloydSteinberg,
+            DitheringAlgorithm::Atkinson,
+            DitheringAlgorithm::Jarvis,
+        ] {
+            let plain = generate_raster(64, 64, gray, algo);
+            let snake = generate_raster_with_options(64, 64, gray, algo, &options);
+            assert_ne!(plain, snake, "{:?} should scan differently", algo);
+            // Same ink overall, within a few percent
+            let (a, b) = (ones(&plain) as f32, ones(&snake) as f32);
+            assert!((a - b).abs() / a < 0.05, "{:?}: {} vs {}", algo, a, b);
+        }
+
+        // First row is always left-to-right
+        let plain = generate_raster(64, 2, gray, DitheringAlgorithm::FloydSteinberg);
+        let snake =
+            generate_raster_with_options(64, 2, gray, DitheringAlgorithm::FloydSteinberg, &options);
+        assert_eq!(plain[..8], snake[..8]);
+    }
+
+    #[test]
+    fn test_tone_curve() {
+        let options = |gamma, levels| DitherOptions {
+            gamma,
+            levels,
+            ..Default::default()
+        };
+        assert_eq!(options(1.0, (0.0, 1.0)).adjust(0.3), 0.3);
+        assert_eq!(options(1.0, (0.2, 0.6)).adjust(0.1), 0.0);
+        assert_eq!(options(1.0, (0.2, 0.6)).adjust(0.7), 1.0);
+        assert!((options(1.0, (0.2, 0.6)).adjust(0.4) - 0.5).abs() < 1e-6);
+        assert!((options(2.0, (0.0, 1.0)).adjust(0.5) - 0.25).abs() < 1e-6);
+
+        // Gamma above 1 prints fewer dots in the midtones
+        let gray = |_: usize, _: usize, _: usize, _: usize| 0.5;
+        let ones = |data: Vec<u8>| data.iter().map(|b| b.count_ones()).sum::<u32>();
+        let plain = ones(generate_raster(64, 8, gray, DitheringAlgorithm::Bayer));
+        let light = ones(generate_raster_with_options(
+            64,
+            8,
+            gray,
+            DitheringAlgorithm::Bayer,
+            &options(2.0, (0.0, 1.0)),
+        ));
+        assert!(light < plain);
+    }
+
+    #[test]
+    fn test_parse_dither_spec() {
+        let spec: DitherSpec = "jarvis".parse().unwrap();
+        assert_eq!(spec.algorithm, DitheringAlgorithm::Jarvis);
+        assert_eq!(spec.options, DitherOptions::default());
+
+        let spec: DitherSpec = "fs:serpentine,gamma=1.5,levels=0.1-0.9".parse().unwrap();
+        assert_eq!(spec.algorithm, DitheringAlgorithm::FloydSteinberg);
+        assert!(spec.options.serpentine);
+        assert_eq!(spec.options.gamma, 1.5);
+        assert_eq!(spec.options.levels, (0.1, 0.9));
+        assert_eq!(
+            spec.to_string(),
+            "floyd-steinberg:serpentine,gamma=1.5,levels=0.1-0.9"
+        );
+        assert_eq!(spec.to_string().parse::<DitherSpec>().unwrap(), spec);
+
+        assert!("fs:wobbly".parse::<DitherSpec>().is_err());
+        assert!("fs:gamma=0".parse::<DitherSpec>().is_err());
+        assert!("fs:levels=0.8-0.2".parse::<DitherSpec>().is_err());
+        assert!("plaid:serpentine".parse::<DitherSpec>().is_err());
+    }
+
+    #[test]
+    fn test_blue_noise() {
+        assert_eq!(
+            "blue-noise".parse::<DitheringAlgorithm>(),
+            Ok(DitheringAlgorithm::BlueNoise)
+        );
+        assert_eq!(
+            serde_json::from_str::<DitheringAlgorithm>(r#""blue-noise""#).unwrap(),
+            DitheringAlgorithm::BlueNoise
+        );
+
+        let black = generate_raster(64, 64, |_, _, _, _| 1.0, DitheringAlgorithm::BlueNoise);
+        assert!(black.iter().all(|&b| b == 0xFF));
+        let white = generate_raster(64, 64, |_, _, _, _| 0.0, DitheringAlgorithm::BlueNoise);
+        assert!(white.iter().all(|&b| b == 0x00));
+
+        // Ink tracks intensity across a 64-dot tile
+        let ones = |level: f32| {
+            generate_raster(64, 64, |_, _, _, _| level, DitheringAlgorithm::BlueNoise)
+                .iter()
+                .map(|b| b.count_ones())
+                .sum::<u32>()
+        };
+        assert_eq!(ones(0.25), 1024);
+        assert_eq!(ones(0.5), 2048);
+    }
 }
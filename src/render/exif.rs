@@ -0,0 +1,326 @@
+//! EXIF: which way up a photo goes, and when and with what it was taken.
+//!
+//! Phones store photos in sensor orientation and record the real one in an
+//! EXIF tag, so a portrait shot decoded naively comes out sideways.
+//! [`decode`] applies that tag right after decoding, before any scaling,
+//! and picks out the fields a caption can show ([`PhotoInfo`]).
+
+use image::{DynamicImage, ImageDecoder, ImageReader, ImageResult};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::io::Cursor;
+use std::str::FromStr;
+
+/// EXIF fields worth printing under a photo.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct PhotoInfo {
+    /// When the photo was taken, as `YYYY-MM-DD HH:MM`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub date_taken: Option<String>,
+    /// Camera make and model, e.g. `Apple iPhone 13`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub camera: Option<String>,
+}
+
+/// A [`PhotoInfo`] field that can go in a caption.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CaptionField {
+    /// Date taken
+    Date,
+    /// Camera make and model
+    Camera,
+}
+
+impl fmt::Display for CaptionField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CaptionField::Date => write!(f, "date"),
+            CaptionField::Camera => write!(f, "camera"),
+        }
+    }
+}
+
+impl FromStr for CaptionField {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "date" => Ok(CaptionField::Date),
+            "camera" => Ok(CaptionField::Camera),
+            other => Err(format!(
+                "Invalid caption field '{}'. Valid options: date, camera",
+                other
+            )),
+        }
+    }
+}
+
+impl PhotoInfo {
+    /// One caption line with the requested fields that are present, in the
+    /// order given. `None` if none of them are.
+    pub fn caption(&self, fields: &[CaptionField]) -> Option<String> {
+        let parts: Vec<&str> = fields
+            .iter()
+            .filter_map(|field| match field {
+                CaptionField::Date => self.date_taken.as_deref(),
+                CaptionField::Camera => self.camera.as_deref(),
+            })
+            .collect();
+        (!parts.is_empty()).then(|| parts.join(" | "))
+    }
+}
+
+/// Decode an image upright, following its EXIF orientation.
+pub fn decode(bytes: &[u8]) -> ImageResult<DynamicImage> {
+    decode_with_info(bytes).map(|(image, _)| image)
+}
+
+/// Decode an image upright and read its [`PhotoInfo`].
+///
+/// Formats without EXIF decode as they are, with an empty `PhotoInfo`.
+pub fn decode_with_info(bytes: &[u8]) -> ImageResult<(DynamicImage, PhotoInfo)> {
+    let mut decoder = ImageReader::new(Cursor::new(bytes))
+        .with_guessed_format()?
+        .into_decoder()?;
+    let exif = decoder.exif_metadata().ok().flatten();
+    let orientation = decoder.orientation().ok();
+    let mut image = DynamicImage::from_decoder(decoder)?;
+    if let Some(orientation) = orientation {
+        image.apply_orientation(orientation);
+    }
+    let info = exif.as_deref().map(parse).unwrap_or_default();
+    Ok((image, info))
+}
+
+const TAG_MAKE: u16 = 0x010F;
+const TAG_MODEL: u16 = 0x0110;
+const TAG_DATE_TIME: u16 = 0x0132;
+const TAG_EXIF_IFD: u16 = 0x8769;
+const TAG_DATE_TIME_ORIGINAL: u16 = 0x9003;
+
+/// Read [`PhotoInfo`] from a raw EXIF block (a TIFF header and IFDs,
+/// optionally behind JPEG's `Exif\0\0` marker). Missing or malformed
+/// fields are left out.
+pub fn parse(exif: &[u8]) -> PhotoInfo {
+    let data = exif.strip_prefix(b"Exif\0\0").unwrap_or(exif);
+    let Some(tiff) = Tiff::new(data) else {
+        return PhotoInfo::default();
+    };
+    let Some(ifd0) = tiff.u32(4) else {
+        return PhotoInfo::default();
+    };
+
+    let make = tiff.ascii(ifd0, TAG_MAKE);
+    let model = tiff.ascii(ifd0, TAG_MODEL);
+    let original = tiff
+        .long(ifd0, TAG_EXIF_IFD)
+        .and_then(|exif_ifd| tiff.ascii(exif_ifd, TAG_DATE_TIME_ORIGINAL));
+    let date = original.or_else(|| tiff.ascii(ifd0, TAG_DATE_TIME));
+
+    PhotoInfo {
+        date_taken: date.and_then(|date| format_date(&date)),
+        camera: camera_name(make, model),
+    }
+}
+
+/// `2024:06:01 14:32:10` → `2024-06-01 14:32`. Unset dates (all zeros or
+/// blank) are dropped; anything else unusual is kept as written.
+fn format_date(date: &str) -> Option<String> {
+    if date.chars().all(|c| matches!(c, '0' | ':' | ' ')) {
+        return None;
+    }
+    let bytes = date.as_bytes();
+    let standard = bytes.len() >= 16
+        && bytes[4] == b':'
+        && bytes[7] == b':'
+        && bytes[10] == b' '
+        && bytes[13] == b':';
+    if !standard {
+        return Some(date.to_string());
+    }
+    Some(format!(
+        "{}-{}-{} {}",
+        &date[..4],
+        &date[5..7],
+        &date[8..10],
+        &date[11..16]
+    ))
+}
+
+/// Combine make and model, skipping the make when the model already
+/// starts with it (`Canon` + `Canon EOS R6`).
+fn camera_name(make: Option<String>, model: Option<String>) -> Option<String> {
+    match (make, model) {
+        (Some(make), Some(model)) => {
+            if model.to_lowercase().starts_with(&make.to_lowercase()) {
+                Some(model)
+            } else {
+                Some(format!("{} {}", make, model))
+            }
+        }
+        (make, model) => make.or(model),
+    }
+}
+
+/// Just enough TIFF to read ASCII and LONG tags out of an IFD.
+struct Tiff<'a> {
+    data: &'a [u8],
+    big_endian: bool,
+}
+
+impl<'a> Tiff<'a> {
+    const ASCII: u16 = 2;
+    const LONG: u16 = 4;
+
+    fn new(data: &'a [u8]) -> Option<Self> {
+        let big_endian = match data.get(..2)? {
+            b"II" => false,
+            b"MM" => true,
+            _ => return None,
+        };
+        let tiff = Tiff { data, big_endian };
+        (tiff.u16(2)? == 42).then_some(tiff)
+    }
+
+    fn u16(&self, offset: usize) -> Option<u16> {
+        let bytes: [u8; 2] = self.data.get(offset..offset + 2)?.try_into().ok()?;
+        Some(if self.big_endian {
+            u16::from_be_bytes(bytes)
+        } else {
+            u16::from_le_bytes(bytes)
+        })
+    }
+
+    fn u32(&self, offset: usize) -> Option<usize> {
+        let bytes: [u8; 4] = self.data.get(offset..offset + 4)?.try_into().ok()?;
+        let value = if self.big_endian {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        };
+        Some(value as usize)
+    }
+
+    /// Offset of the 12-byte entry for `tag` in the IFD at `ifd`.
+    fn entry(&self, ifd: usize, tag: u16) -> Option<usize> {
+        let count = self.u16(ifd)? as usize;
+        (0..count)
+            .map(|i| ifd + 2 + i * 12)
+            .find(|&entry| self.u16(entry) == Some(tag))
+    }
+
+    fn long(&self, ifd: usize, tag: u16) -> Option<usize> {
+        let entry = self.entry(ifd, tag)?;
+        (self.u16(entry + 2)? == Self::LONG).then(|| self.u32(entry + 8))?
+    }
+
+    fn ascii(&self, ifd: usize, tag: u16) -> Option<String> {
+        let entry = self.entry(ifd, tag)?;
+        if self.u16(entry + 2)? != Self::ASCII {
+            return None;
+        }
+        let len = self.u32(entry + 4)?;
+        // Values of up to 4 bytes are stored in the entry itself
+        let start = if len <= 4 {
+            entry + 8
+        } else {
+            self.u32(entry + 8)?
+        };
+        let raw = self.data.get(start..start.checked_add(len)?)?;
+        let raw = raw.split(|&b| b == 0).next().unwrap_or(raw);
+        let text = String::from_utf8_lossy(raw).trim().to_string();
+        (!text.is_empty()).then_some(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{ImageEncoder, RgbImage, codecs::png::PngEncoder};
+
+    /// A little-endian EXIF block: make, model, orientation and an Exif IFD
+    /// holding the date taken.
+    fn sample_exif(orientation: u16) -> Vec<u8> {
+        fn entry(out: &mut Vec<u8>, tag: u16, kind: u16, count: u32, value: u32) {
+            out.extend(tag.to_le_bytes());
+            out.extend(kind.to_le_bytes());
+            out.extend(count.to_le_bytes());
+            out.extend(value.to_le_bytes());
+        }
+        let make = b"Apple\0";
+        let model = b"iPhone 13\0";
+        let date = b"2024:06:01 14:32:10\0";
+        // Header (8) + IFD0 with 4 entries (54) + Exif IFD with 1 entry (18)
+        let exif_ifd = 8 + 54;
+        let data = exif_ifd + 18;
+
+        let mut out = b"II".to_vec();
+        out.extend(42u16.to_le_bytes());
+        out.extend(8u32.to_le_bytes());
+        out.extend(4u16.to_le_bytes());
+        entry(&mut out, TAG_MAKE, 2, 6, data);
+        entry(&mut out, TAG_MODEL, 2, 10, data + 6);
+        entry(&mut out, 0x0112, 3, 1, orientation as u32);
+        entry(&mut out, TAG_EXIF_IFD, 4, 1, exif_ifd);
+        out.extend(0u32.to_le_bytes());
+        out.extend(1u16.to_le_bytes());
+        entry(&mut out, TAG_DATE_TIME_ORIGINAL, 2, 20, data + 16);
+        out.extend(0u32.to_le_bytes());
+        out.extend(make);
+        out.extend(model);
+        out.extend(date);
+        out
+    }
+
+    #[test]
+    fn test_parse_exif_fields() {
+        let info = parse(&sample_exif(1));
+        assert_eq!(info.date_taken.as_deref(), Some("2024-06-01 14:32"));
+        assert_eq!(info.camera.as_deref(), Some("Apple iPhone 13"));
+
+        // JPEG APP1 marker prefix is accepted
+        let mut jpeg = b"Exif\0\0".to_vec();
+        jpeg.extend(sample_exif(1));
+        assert_eq!(parse(&jpeg), info);
+
+        assert_eq!(parse(b"not exif"), PhotoInfo::default());
+        assert_eq!(parse(&sample_exif(1)[..40]).date_taken, None);
+    }
+
+    #[test]
+    fn test_caption() {
+        let info = parse(&sample_exif(1));
+        assert_eq!(
+            info.caption(&[CaptionField::Camera, CaptionField::Date])
+                .as_deref(),
+            Some("Apple iPhone 13 | 2024-06-01 14:32")
+        );
+        assert_eq!(PhotoInfo::default().caption(&[CaptionField::Date]), None);
+        assert_eq!(
+            camera_name(Some("Canon".into()), Some("Canon EOS R6".into())).as_deref(),
+            Some("Canon EOS R6")
+        );
+        assert_eq!(format_date("0000:00:00 00:00:00"), None);
+    }
+
+    #[test]
+    fn test_decode_applies_orientation() {
+        // 4x2 landscape pixels tagged "rotate 90° clockwise" come out 2x4
+        let mut png = Vec::new();
+        let mut encoder = PngEncoder::new(&mut png);
+        encoder.set_exif_metadata(sample_exif(6)).unwrap();
+        encoder
+            .write_image(
+                RgbImage::new(4, 2).as_raw(),
+                4,
+                2,
+                image::ExtendedColorType::Rgb8,
+            )
+            .unwrap();
+
+        let (image, info) = decode_with_info(&png).unwrap();
+        assert_eq!((image.width(), image.height()), (2, 4));
+        assert_eq!(info.camera.as_deref(), Some("Apple iPhone 13"));
+    }
+}
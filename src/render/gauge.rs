@@ -0,0 +1,216 @@
+//! Gauge (radial dial) rendering for thermal receipt printers.
+//!
+//! Draws a single value as a dial: a thick arc filled up to the value,
+//! labeled ticks, a needle and a large readout. Shapes use the chart
+//! drawing primitives; text uses the TTF path (IBM Plex Sans), so the
+//! result is a grayscale buffer meant to be dithered.
+//!
+//! Angles are measured clockwise from straight up, so the dial is
+//! symmetric around the vertical axis.
+
+use super::chart::{Canvas, draw_filled_circle, draw_line_thick, format_number};
+use crate::document::types::Gauge;
+use crate::preview::ttf_font::{self, TtfRender};
+
+// ============================================================================
+// CONSTANTS
+// ============================================================================
+
+const DEFAULT_SIZE: usize = 320;
+const MIN_SIZE: usize = 96;
+const DEFAULT_SWEEP: f64 = 240.0;
+const DEFAULT_TICKS: usize = 5;
+
+const TOP_PAD: f32 = 4.0;
+const BOTTOM_PAD: f32 = 4.0;
+
+const ARC_THICKNESS: f32 = 14.0;
+const ARC_TRACK_INTENSITY: f32 = 0.3;
+
+const TICK_GAP: f32 = 4.0; // between arc and tick
+const TICK_LENGTH: f32 = 12.0;
+const TICK_THICKNESS: f32 = 3.0;
+const TICK_LABEL_PX: f32 = 20.0;
+const TICK_LABEL_GAP: f32 = 16.0; // between tick end and label center
+
+const NEEDLE_THICKNESS: f32 = 5.0;
+const HUB_RADIUS: f32 = 9.0;
+
+const VALUE_PX: f32 = 48.0;
+const VALUE_GAP: f32 = 12.0; // between hub and readout
+const LABEL_PX: f32 = 24.0;
+const LABEL_GAP: f32 = 6.0; // between dial and caption
+
+const FONT: &str = "ibm";
+
+// ============================================================================
+// RENDERING
+// ============================================================================
+
+/// Render a gauge to a grayscale intensity buffer `width` dots wide.
+///
+/// Returns `(intensities, width, height)` with one value per pixel
+/// (0.0 = white, 1.0 = black), suitable for `Op::GrayRaster`.
+pub fn render_gray(gauge: &Gauge, width: usize) -> (Vec<f32>, u16, u16) {
+    let size = gauge.size.unwrap_or(DEFAULT_SIZE).clamp(MIN_SIZE, width);
+    let radius = size as f32 / 2.0;
+    let half_sweep =
+        (gauge.sweep.unwrap_or(DEFAULT_SWEEP).clamp(30.0, 360.0) / 2.0).to_radians() as f32;
+    let ticks = gauge.ticks.unwrap_or(DEFAULT_TICKS).clamp(1, 20);
+    let max = gauge.max.unwrap_or(100.0);
+
+    let readout = format!(
+        "{}{}",
+        format_number(gauge.value),
+        gauge.unit.as_deref().unwrap_or("")
+    );
+    let readout = ttf_font::render_ttf_text(&readout, FONT, true, VALUE_PX, width);
+    let caption = gauge
+        .label
+        .as_deref()
+        .filter(|l| !l.is_empty())
+        .map(|l| ttf_font::render_ttf_text(l, FONT, false, LABEL_PX, width));
+
+    // Vertical layout: dial, readout under the hub, then the caption
+    let cx = width as f32 / 2.0;
+    let cy = TOP_PAD + radius;
+    let readout_top = cy + HUB_RADIUS + VALUE_GAP;
+    let arc_bottom = cy + radius * (-half_sweep.cos()).max(0.0);
+    let dial_bottom = arc_bottom.max(readout_top + readout.height as f32);
+    let caption_top = dial_bottom + LABEL_GAP;
+    let height = caption_top + caption.as_ref().map_or(0.0, |c| c.height as f32) + BOTTOM_PAD;
+
+    let mut canvas = Canvas::new(width, height.ceil() as usize);
+    let angle_at = |fraction: f32| -half_sweep + fraction * 2.0 * half_sweep;
+    let point_at = |angle: f32, r: f32| (cx + r * angle.sin(), cy - r * angle.cos());
+    let value_angle = angle_at(gauge.fraction() as f32);
+
+    // Arc: solid up to the value, light track after it
+    let inner = radius - ARC_THICKNESS;
+    for y in 0..(cy + radius).ceil() as usize {
+        for x in 0..width {
+            let dx = x as f32 + 0.5 - cx;
+            let dy = y as f32 + 0.5 - cy;
+            let dist = (dx * dx + dy * dy).sqrt();
+            let angle = dx.atan2(-dy);
+            if dist < inner || dist > radius || angle.abs() > half_sweep {
+                continue;
+            }
+            let intensity = if angle <= value_angle {
+                1.0
+            } else {
+                ARC_TRACK_INTENSITY
+            };
+            canvas.blend(x, y, intensity);
+        }
+    }
+
+    // Ticks with labels. A full circle would put the last tick on the first.
+    let tick_outer = inner - TICK_GAP;
+    let tick_inner = tick_outer - TICK_LENGTH;
+    let last_tick = if half_sweep * 2.0 >= std::f32::consts::TAU {
+        ticks - 1
+    } else {
+        ticks
+    };
+    for i in 0..=last_tick {
+        let fraction = i as f32 / ticks as f32;
+        let angle = angle_at(fraction);
+        let (x1, y1) = point_at(angle, tick_outer);
+        let (x2, y2) = point_at(angle, tick_inner);
+        draw_line_thick(&mut canvas, x1, y1, x2, y2, TICK_THICKNESS, 1.0);
+
+        let value = gauge.min + (max - gauge.min) * fraction as f64;
+        let label =
+            ttf_font::render_ttf_text(&format_number(value), FONT, false, TICK_LABEL_PX, width);
+        let (lx, ly) = point_at(angle, tick_inner - TICK_LABEL_GAP);
+        blit_centered(&mut canvas, &label, lx, ly - label.height as f32 / 2.0);
+    }
+
+    // Needle and hub
+    let (nx, ny) = point_at(value_angle, tick_outer);
+    draw_line_thick(&mut canvas, cx, cy, nx, ny, NEEDLE_THICKNESS, 1.0);
+    draw_filled_circle(&mut canvas, cx, cy, HUB_RADIUS, 1.0);
+
+    blit_centered(&mut canvas, &readout, cx, readout_top);
+    if let Some(caption) = &caption {
+        blit_centered(&mut canvas, caption, cx, caption_top);
+    }
+
+    let height = canvas.height as u16;
+    (canvas.buf, width as u16, height)
+}
+
+/// Draw TTF text horizontally centered on `center_x`, starting at row `top`.
+fn blit_centered(canvas: &mut Canvas, text: &TtfRender, center_x: f32, top: f32) {
+    let left = (center_x - text.width as f32 / 2.0).round().max(0.0) as usize;
+    let top = top.round().max(0.0) as usize;
+    for ty in 0..text.height {
+        for tx in 0..text.width {
+            canvas.blend(left + tx, top + ty, text.data[ty * text.width + tx]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pixel(data: &[f32], width: usize, x: f32, y: f32) -> f32 {
+        data[y as usize * width + x as usize]
+    }
+
+    #[test]
+    fn test_needle_points_at_value() {
+        // Half-circle dial at 50%: the needle points straight up
+        let gauge = Gauge {
+            value: 50.0,
+            sweep: Some(180.0),
+            size: Some(200),
+            ..Default::default()
+        };
+        let (data, width, height) = render_gray(&gauge, 300);
+        let (width, height) = (width as usize, height as usize);
+        assert_eq!(data.len(), width * height);
+
+        // Hub center is at (150, TOP_PAD + 100); the needle runs up from it,
+        // not down toward the readout
+        assert_eq!(pixel(&data, width, 150.0, TOP_PAD + 60.0), 1.0);
+        assert_eq!(pixel(&data, width, 150.0, TOP_PAD + 115.0), 0.0);
+    }
+
+    #[test]
+    fn test_arc_fills_up_to_value() {
+        let gauge = Gauge {
+            value: 25.0,
+            sweep: Some(180.0),
+            size: Some(200),
+            ..Default::default()
+        };
+        let (data, width, _) = render_gray(&gauge, 300);
+        let width = width as usize;
+        let mid = ARC_THICKNESS / 2.0;
+
+        // 25% of a half circle is 45° left of vertical: the top of the arc is
+        // past the value (light track), its left end before it (solid)
+        assert_eq!(
+            pixel(&data, width, 150.0, TOP_PAD + mid),
+            ARC_TRACK_INTENSITY
+        );
+        assert_eq!(pixel(&data, width, mid + 50.0, TOP_PAD + 98.0), 1.0);
+    }
+
+    #[test]
+    fn test_fraction_clamps() {
+        let gauge = |value, max| Gauge {
+            value,
+            min: 10.0,
+            max: Some(max),
+            ..Default::default()
+        };
+        assert_eq!(gauge(15.0, 20.0).fraction(), 0.5);
+        assert_eq!(gauge(50.0, 20.0).fraction(), 1.0);
+        assert_eq!(gauge(0.0, 20.0).fraction(), 0.0);
+        assert_eq!(gauge(15.0, 10.0).fraction(), 0.0);
+    }
+}
@@ -0,0 +1,309 @@
+//! Halftone screening: the newspaper look.
+//!
+//! Dithering hides its dots; a halftone screen shows them off. The image is
+//! cut into a rotated grid of cells and each cell prints one shape sized by
+//! the ink it covers, at a fixed number of lines per inch (LPI).
+//!
+//! | Screen | Shape | Default angle |
+//! |--------|-------|---------------|
+//! | `dot` | clustered round dots | 45° |
+//! | `line` | horizontal lines of varying width | 0° |
+//! | `diagonal` | lines at an angle | 45° |
+//!
+//! A spec is written `screen[:lpi[:angle]]`, e.g. `dot`, `dot:30` or
+//! `line:20:15`. The 576-dot printer runs at 203 DPI, so the default
+//! 25 LPI gives cells about 8 dots across.
+
+use super::par::*;
+use serde::{Deserialize, Serialize};
+use std::f32::consts::PI;
+use std::fmt;
+use std::str::FromStr;
+
+use super::dither::pack_row;
+
+/// Shape printed in each halftone cell.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Screen {
+    /// Round dots that grow until they merge.
+    Dot,
+    /// Straight lines that thicken.
+    Line,
+    /// Lines at 45° unless told otherwise.
+    Diagonal,
+}
+
+impl Screen {
+    /// Angle used when a spec doesn't give one, in degrees.
+    pub fn default_angle(self) -> f32 {
+        match self {
+            Screen::Dot | Screen::Diagonal => 45.0,
+            Screen::Line => 0.0,
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Screen::Dot => "dot",
+            Screen::Line => "line",
+            Screen::Diagonal => "diagonal",
+        }
+    }
+}
+
+impl FromStr for Screen {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "dot" | "dots" => Ok(Screen::Dot),
+            "line" | "lines" => Ok(Screen::Line),
+            "diagonal" | "diag" => Ok(Screen::Diagonal),
+            other => Err(format!(
+                "Invalid halftone screen '{}'. Valid options: dot, line, diagonal",
+                other
+            )),
+        }
+    }
+}
+
+/// A halftone screen at a given ruling and angle.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "HalftoneRepr", into = "HalftoneRepr")]
+pub struct Halftone {
+    /// Cell shape.
+    pub screen: Screen,
+    /// Lines (cells) per inch.
+    pub lpi: f32,
+    /// Screen rotation in degrees.
+    pub angle: f32,
+}
+
+impl Halftone {
+    /// Default screen ruling.
+    pub const DEFAULT_LPI: f32 = 25.0;
+
+    /// `screen` at the default ruling and its default angle.
+    pub fn new(screen: Screen) -> Self {
+        Halftone {
+            screen,
+            lpi: Self::DEFAULT_LPI,
+            angle: screen.default_angle(),
+        }
+    }
+
+    /// Checked constructor: LPI must be positive and finite.
+    pub fn with_options(
+        screen: Screen,
+        lpi: Option<f32>,
+        angle: Option<f32>,
+    ) -> Result<Self, String> {
+        let lpi = lpi.unwrap_or(Self::DEFAULT_LPI);
+        if !(lpi.is_finite() && lpi > 0.0) {
+            return Err(format!("Halftone LPI {} must be positive", lpi));
+        }
+        let angle = angle.unwrap_or_else(|| screen.default_angle());
+        if !angle.is_finite() {
+            return Err(format!("Halftone angle {} is not a number", angle));
+        }
+        Ok(Halftone { screen, lpi, angle })
+    }
+
+    /// Whether the pixel at (`x`, `y`) prints for ink `intensity`
+    /// (0.0 = white, 1.0 = black) at `dpi` dots per inch.
+    pub fn should_print(&self, x: usize, y: usize, intensity: f32, dpi: u16) -> bool {
+        let cell = (dpi as f32 / self.lpi).max(1.0);
+        let (sin, cos) = self.angle.to_radians().sin_cos();
+        // Sample pixel centers, in cell units along the rotated axes
+        let (px, py) = (x as f32 + 0.5, y as f32 + 0.5);
+        let u = (px * cos + py * sin) / cell;
+        let v = (py * cos - px * sin) / cell;
+        // Offsets from the cell center, in -0.5..0.5
+        let fu = u - u.floor() - 0.5;
+        let fv = v - v.floor() - 0.5;
+
+        let threshold = match self.screen {
+            Screen::Dot => dot_coverage(fu.hypot(fv)),
+            Screen::Line | Screen::Diagonal => 2.0 * fv.abs(),
+        };
+        intensity > threshold
+    }
+}
+
+/// Fraction of a unit cell covered by a centered dot of radius `r`. Using
+/// it as the threshold makes a dot's area match the ink it stands for.
+fn dot_coverage(r: f32) -> f32 {
+    let area = PI * r * r;
+    if r <= 0.5 {
+        return area;
+    }
+    // Past 0.5 the circle spills over the cell's four edges; subtract the
+    // four circular segments outside.
+    let segment = r * r * (0.5 / r).acos() - 0.5 * (r * r - 0.25).sqrt();
+    (area - 4.0 * segment).min(1.0)
+}
+
+impl fmt::Display for Halftone {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.screen.as_str(), self.lpi, self.angle)
+    }
+}
+
+impl FromStr for Halftone {
+    type Err = String;
+
+    /// Parse `screen[:lpi[:angle]]`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.trim().split(':');
+        let screen: Screen = parts.next().unwrap_or_default().parse()?;
+        let number = |part: Option<&str>, what: &str| {
+            part.map(|p| {
+                p.trim()
+                    .parse::<f32>()
+                    .map_err(|_| format!("Invalid halftone {} '{}' in '{}'", what, p, s))
+            })
+            .transpose()
+        };
+        let lpi = number(parts.next(), "LPI")?;
+        let angle = number(parts.next(), "angle")?;
+        if parts.next().is_some() {
+            return Err(format!(
+                "Invalid halftone '{}'. Use screen[:lpi[:angle]], e.g. dot:25:45",
+                s
+            ));
+        }
+        Halftone::with_options(screen, lpi, angle)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum HalftoneRepr {
+    Text(String),
+    Fields {
+        screen: Screen,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        lpi: Option<f32>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        angle: Option<f32>,
+    },
+}
+
+impl TryFrom<HalftoneRepr> for Halftone {
+    type Error = String;
+
+    fn try_from(repr: HalftoneRepr) -> Result<Self, Self::Error> {
+        match repr {
+            HalftoneRepr::Text(s) => s.parse(),
+            HalftoneRepr::Fields { screen, lpi, angle } => {
+                Halftone::with_options(screen, lpi, angle)
+            }
+        }
+    }
+}
+
+impl From<Halftone> for HalftoneRepr {
+    fn from(halftone: Halftone) -> Self {
+        HalftoneRepr::Text(halftone.to_string())
+    }
+}
+
+/// Screen row-major `intensities` into packed 1-bit raster data, ready for
+/// a raster command.
+pub fn screen(
+    width: usize,
+    height: usize,
+    intensities: &[f32],
+    halftone: &Halftone,
+    dpi: u16,
+) -> Vec<u8> {
+    (0..height)
+        .into_par_iter()
+        .flat_map_iter(|y| {
+            let row: Vec<bool> = (0..width)
+                .map(|x| halftone.should_print(x, y, intensities[y * width + x], dpi))
+                .collect();
+            pack_row(&row)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn coverage(halftone: &Halftone, intensity: f32) -> f32 {
+        let size = 96;
+        let data = screen(size, size, &vec![intensity; size * size], halftone, 203);
+        let black: u32 = data.iter().map(|b| b.count_ones()).sum();
+        black as f32 / (size * size) as f32
+    }
+
+    #[test]
+    fn test_coverage_tracks_intensity() {
+        for spec in ["dot", "line", "diagonal:20:30"] {
+            let halftone: Halftone = spec.parse().unwrap();
+            assert_eq!(coverage(&halftone, 0.0), 0.0, "{}", spec);
+            assert_eq!(coverage(&halftone, 1.0), 1.0, "{}", spec);
+            for level in [0.25, 0.5, 0.75] {
+                let got = coverage(&halftone, level);
+                assert!((got - level).abs() < 0.08, "{} at {}: {}", spec, level, got);
+            }
+        }
+    }
+
+    #[test]
+    fn test_dots_cluster() {
+        // A light gray as one dot per cell: neighbours of a black pixel are
+        // mostly black, unlike dithering's scattered single dots.
+        let halftone = Halftone::new(Screen::Dot);
+        let (w, h) = (64, 64);
+        let on = |x, y| halftone.should_print(x, y, 0.2, 203);
+        let (mut black, mut clustered) = (0, 0);
+        for y in 1..h - 1 {
+            for x in 1..w - 1 {
+                if on(x, y) {
+                    black += 1;
+                    if on(x + 1, y) || on(x - 1, y) {
+                        clustered += 1;
+                    }
+                }
+            }
+        }
+        assert!(black > 0);
+        assert!(clustered * 10 >= black * 9);
+    }
+
+    #[test]
+    fn test_dot_coverage_is_monotonic() {
+        let max_r = std::f32::consts::FRAC_1_SQRT_2;
+        let samples: Vec<f32> = (0..=100)
+            .map(|i| dot_coverage(max_r * i as f32 / 100.0))
+            .collect();
+        assert!(samples.windows(2).all(|w| w[1] >= w[0]));
+        assert!((samples[100] - 1.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_parse_halftone() {
+        assert_eq!("dot".parse(), Ok(Halftone::new(Screen::Dot)));
+        let line: Halftone = "line:20:15".parse().unwrap();
+        assert_eq!(
+            (line.screen, line.lpi, line.angle),
+            (Screen::Line, 20.0, 15.0)
+        );
+        assert_eq!("diagonal:30".parse::<Halftone>().unwrap().angle, 45.0);
+        assert!("dot:0".parse::<Halftone>().is_err());
+        assert!("dot:fine".parse::<Halftone>().is_err());
+        assert!("stars".parse::<Halftone>().is_err());
+
+        let json = |s: &str| serde_json::from_str::<Halftone>(s);
+        assert_eq!(json(r#""line:20:15""#).unwrap(), line);
+        assert_eq!(
+            json(r#"{"screen": "line", "lpi": 20, "angle": 15}"#).unwrap(),
+            line
+        );
+        assert_eq!(serde_json::to_string(&line).unwrap(), r#""line:20:15""#);
+    }
+}
@@ -4,13 +4,22 @@
 //!
 //! ## Modules
 //!
+//! - [`adjust`]: Brightness and contrast for photos
+//! - [`alpha`]: Backgrounds for transparent images
+//! - [`animation`]: Flipbook strips of a pattern over time
+//! - [`blue_noise`]: 64x64 blue-noise threshold matrix
 //! - [`dither`]: Bayer 8x8 ordered dithering for binary conversion
+//! - [`exif`]: Photo orientation and capture details
+//! - [`halftone`]: Dot and line screens at a set LPI and angle
 //! - [`patterns`]: Visual patterns (ripple, waves, calibration)
+//! - [`quality`]: Draft, normal and high quality presets for patterns
+//! - [`recipe`]: Pattern settings as a one-line payload, for reprinting
 //! - [`weave`]: Pattern blending with crossfade transitions
 //!
 //! ## Usage Example
 //!
 //! ```
+//! # #[cfg(feature = "patterns")] {
 //! use estrella::render::patterns::{self, Pattern, Ripple};
 //! use estrella::render::dither::DitheringAlgorithm;
 //!
@@ -21,16 +30,36 @@
 //! let raster_data = patterns::render(&ripple, 576, 500, DitheringAlgorithm::Bayer);
 //!
 //! // raster_data is ready to send via graphics::raster() command
+//! # }
 //! ```
 
+#[cfg(feature = "images")]
 use image::{GrayImage, Luma};
+#[cfg(feature = "images")]
 use std::io::Cursor;
 
+#[cfg(feature = "images")]
+pub mod adjust;
+pub mod alpha;
+#[cfg(feature = "patterns")]
+pub mod animation;
+pub mod blue_noise;
 pub mod chart;
 pub mod composer;
+#[cfg(feature = "images")]
 pub mod context;
 pub mod dither;
+#[cfg(feature = "images")]
+pub mod exif;
+pub mod gauge;
+pub mod halftone;
+mod par;
+#[cfg(feature = "patterns")]
 pub mod patterns;
+pub mod quality;
+#[cfg(feature = "patterns")]
+pub mod recipe;
+#[cfg(feature = "patterns")]
 pub mod weave;
 
 /// Convert packed 1-bit raster data to PNG bytes.
@@ -45,6 +74,7 @@ pub mod weave;
 ///
 /// # Returns
 /// PNG-encoded image bytes, or an error message.
+#[cfg(feature = "images")]
 pub fn raster_to_png(width: usize, height: usize, raster_data: &[u8]) -> Result<Vec<u8>, String> {
     let width_bytes = width.div_ceil(8);
 
@@ -0,0 +1,50 @@
+//! Parallel iteration for the renderers.
+//!
+//! With the `parallel` feature this is `rayon::prelude`. Without it, the
+//! same method names run on plain iterators, one row at a time, so the
+//! renderers read the same either way.
+
+#[cfg(feature = "parallel")]
+pub use rayon::prelude::*;
+
+#[cfg(not(feature = "parallel"))]
+pub use sequential::*;
+
+#[cfg(not(feature = "parallel"))]
+mod sequential {
+    use std::iter::FlatMap;
+    use std::slice::ChunksMut;
+
+    /// `into_par_iter()` for anything iterable.
+    pub trait IntoParallelIterator: IntoIterator + Sized {
+        fn into_par_iter(self) -> Self::IntoIter {
+            self.into_iter()
+        }
+    }
+
+    impl<I: IntoIterator> IntoParallelIterator for I {}
+
+    /// `flat_map_iter()` for any iterator.
+    pub trait ParallelIterator: Iterator + Sized {
+        fn flat_map_iter<U, F>(self, f: F) -> FlatMap<Self, U, F>
+        where
+            U: IntoIterator,
+            F: FnMut(Self::Item) -> U,
+        {
+            self.flat_map(f)
+        }
+    }
+
+    impl<I: Iterator> ParallelIterator for I {}
+
+    /// `par_chunks_mut()` for slices.
+    pub trait ParallelSliceMut<T> {
+        fn par_chunks_mut(&mut self, chunk_size: usize) -> ChunksMut<'_, T>;
+    }
+
+    impl<T> ParallelSliceMut<T> for [T] {
+        fn par_chunks_mut(&mut self, chunk_size: usize) -> ChunksMut<'_, T> {
+            self.chunks_mut(chunk_size)
+        }
+    }
+}
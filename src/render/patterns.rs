@@ -15,6 +15,8 @@ pub use art::Pattern;
 pub use art::by_name;
 pub use art::by_name_golden;
 pub use art::by_name_random;
+pub use art::{Category, PATTERN_ENTRIES, PatternEntry, category};
+pub use art::{PatternRng, by_name_seeded, by_name_with_rng, random_seed};
 // Classic patterns
 pub use art::calibration::Calibration;
 pub use art::crystal::Crystal;
@@ -57,9 +59,12 @@ pub use art::stipple::Stipple;
 pub use art::weave::Weave;
 pub use art::woodgrain::Woodgrain;
 
-/// List all available pattern names.
-pub fn list_patterns() -> &'static [&'static str] {
-    PATTERNS
+/// List all available pattern names: the built-ins, then any added
+/// through [`art::registry`].
+pub fn list_patterns() -> Vec<&'static str> {
+    let mut names = PATTERNS.to_vec();
+    names.extend(art::registry::names());
+    names
 }
 
 /// Render a pattern to a byte array suitable for raster graphics.
@@ -72,17 +77,57 @@ pub fn render(
     height: usize,
     algorithm: dither::DitheringAlgorithm,
 ) -> Vec<u8> {
-    dither::generate_raster(
+    render_with_options(
+        pattern,
+        width,
+        height,
+        algorithm,
+        &dither::DitherOptions::default(),
+    )
+}
+
+/// Like [`render`], with serpentine scanning or a tone curve
+/// (see [`dither::DitherOptions`]).
+pub fn render_with_options(
+    pattern: &dyn Pattern,
+    width: usize,
+    height: usize,
+    algorithm: dither::DitheringAlgorithm,
+    options: &dither::DitherOptions,
+) -> Vec<u8> {
+    dither::generate_raster_with_options(
         width,
         height,
         |x, y, w, h| pattern.intensity(x, y, w, h),
         algorithm,
+        options,
     )
 }
 
+/// Render a pattern to a grayscale intensity buffer (no dithering).
+///
+/// Returns one value per pixel (0.0 = white, 1.0 = black), suitable for
+/// `Op::GrayRaster`.
+pub fn render_gray(pattern: &dyn Pattern, width: usize, height: usize) -> Vec<f32> {
+    dither::generate_intensities(width, height, |x, y, w, h| pattern.intensity(x, y, w, h))
+}
+
+/// Render a low-resolution draft: every `row_step`-th row, repeated below.
+pub fn render_gray_draft(
+    pattern: &dyn Pattern,
+    width: usize,
+    height: usize,
+    row_step: usize,
+) -> Vec<f32> {
+    dither::generate_intensities_draft(width, height, row_step, |x, y, w, h| {
+        pattern.intensity(x, y, w, h)
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use rand::SeedableRng;
 
     #[test]
     fn test_list_patterns() {
@@ -95,7 +140,8 @@ mod tests {
         assert!(patterns.contains(&"corrupt_barcode"));
         assert!(patterns.contains(&"voronoi"));
         assert!(patterns.contains(&"weave"));
-        assert_eq!(patterns.len(), 38);
+        assert!(patterns.contains(&"expr"));
+        assert_eq!(PATTERNS.len(), 39);
     }
 
     #[test]
@@ -107,10 +153,58 @@ mod tests {
         assert!(by_name("unknown").is_none());
     }
 
+    #[test]
+    fn test_pattern_entries() {
+        for entry in PATTERN_ENTRIES {
+            // Each constructor builds the pattern it is listed as
+            assert_eq!((entry.golden_fn)().name(), entry.name);
+            let mut rng = PatternRng::seed_from_u64(1);
+            assert_eq!((entry.random_fn)(&mut rng).name(), entry.name);
+        }
+        assert_eq!(category("riley_check"), Some(Category::OpArt));
+        assert_eq!(category("demo"), Some(Category::Diagnostic));
+        assert_eq!(category("unknown"), None);
+        // Every category has at least one pattern
+        for &category in Category::ALL {
+            assert!(PATTERN_ENTRIES.iter().any(|e| e.category == category));
+        }
+    }
+
+    #[test]
+    fn test_seeded_params_are_stable() {
+        // Seeds in printed footers and recipe QR codes must keep giving
+        // these values
+        let params = by_name_seeded("ripple", 42).unwrap().list_params();
+        let expected = [
+            ("center_x", "0.55"),
+            ("center_y", "0.37"),
+            ("scale", "7.6"),
+            ("drift", "139"),
+            ("wobble_mix", "0.17"),
+            ("gamma", "1.37"),
+            ("border", "6.0"),
+        ];
+        assert_eq!(
+            params,
+            expected.map(|(name, value)| (name, value.to_string()))
+        );
+    }
+
     #[test]
     fn test_render() {
         let ripple = Ripple::golden();
         let data = render(&ripple, 576, 100, dither::DitheringAlgorithm::Bayer);
         assert_eq!(data.len(), 72 * 100); // 576/8 = 72 bytes per row
     }
+
+    #[test]
+    fn test_render_gray_dithers_to_render() {
+        let ripple = Ripple::golden();
+        let gray = render_gray(&ripple, 576, 50);
+        assert_eq!(gray.len(), 576 * 50);
+        assert_eq!(
+            dither::dither_intensities(576, 50, &gray, dither::DitheringAlgorithm::Bayer),
+            render(&ripple, 576, 50, dither::DitheringAlgorithm::Bayer)
+        );
+    }
 }
@@ -0,0 +1,250 @@
+//! Print quality presets for patterns.
+//!
+//! One setting picks how a pattern is sampled, dithered and sent, so
+//! speed and quality are traded together instead of flag by flag:
+//!
+//! | Preset | Resolution | Dithering | Sending |
+//! |--------|------------|-----------|---------|
+//! | `draft` | every second row, repeated below | Bayer | as configured |
+//! | `normal` (default) | every row | as requested | as configured |
+//! | `high` | 2×2 samples averaged per dot | as requested | small, paced chunks |
+//!
+//! ```
+//! use estrella::render::dither::{DitherOptions, DitheringAlgorithm};
+//! use estrella::render::quality::PrintQuality;
+//!
+//! let quality: PrintQuality = "draft".parse().unwrap();
+//! assert_eq!(quality.algorithm(DitheringAlgorithm::Atkinson), DitheringAlgorithm::Bayer);
+//!
+//! // Rows are computed in pairs
+//! let gray = quality.intensities(4, 6, |_x, y, _w, _h| y as f32 / 6.0);
+//! assert_eq!(gray[0..4], gray[4..8]);
+//! assert_ne!(gray[4..8], gray[8..12]);
+//!
+//! let data = quality.render(64, 10, |x, _y, w, _h| x as f32 / w as f32,
+//!     DitheringAlgorithm::Atkinson, &DitherOptions::default());
+//! assert_eq!(data.len(), 8 * 10);
+//!
+//! assert_eq!(
+//!     PrintQuality::High.device("/dev/rfcomm0"),
+//!     "/dev/rfcomm0?chunk_size=1024&chunk_delay_ms=20&flow_control=true"
+//! );
+//! ```
+
+use std::borrow::Cow;
+
+use super::dither::{self, DitherOptions, DitheringAlgorithm};
+
+/// Rows computed per repeated block in `draft`.
+pub const DRAFT_ROW_STEP: usize = 2;
+
+/// Samples averaged per dot along each axis in `high`.
+pub const HIGH_SAMPLES: usize = 2;
+
+/// Device options (see [`crate::transport::bluetooth`]) added in `high`:
+/// smaller chunks, a pause after each, and flow control.
+pub const HIGH_PACING: &str = "chunk_size=1024&chunk_delay_ms=20&flow_control=true";
+
+/// Speed/quality preset for printing patterns.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PrintQuality {
+    /// Half the vertical resolution and Bayer dithering, for quick tests.
+    Draft,
+    /// Full resolution with the requested dithering.
+    #[default]
+    Normal,
+    /// Anti-aliased sampling and slower, paced sending.
+    High,
+}
+
+impl PrintQuality {
+    /// Canonical name, as accepted by [`str::parse`].
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Draft => "draft",
+            Self::Normal => "normal",
+            Self::High => "high",
+        }
+    }
+
+    /// Rows computed per repeated block (1 = every row).
+    pub fn row_step(self) -> usize {
+        match self {
+            Self::Draft => DRAFT_ROW_STEP,
+            Self::Normal | Self::High => 1,
+        }
+    }
+
+    /// The dithering algorithm to use when `requested` was asked for.
+    pub fn algorithm(self, requested: DitheringAlgorithm) -> DitheringAlgorithm {
+        match self {
+            Self::Draft => DitheringAlgorithm::Bayer,
+            Self::Normal | Self::High => requested,
+        }
+    }
+
+    /// `device` with this preset's write pacing added. Devices that already
+    /// carry options, and network printers, are left as they are.
+    pub fn device(self, device: &str) -> Cow<'_, str> {
+        let paced = self == Self::High
+            && !device.contains('?')
+            && !device.starts_with(crate::transport::tcp::SCHEME);
+        if paced {
+            Cow::Owned(format!("{}?{}", device, HIGH_PACING))
+        } else {
+            Cow::Borrowed(device)
+        }
+    }
+
+    /// Sample `intensity_fn` at this quality, without dithering.
+    pub fn intensities<F>(self, width: usize, height: usize, intensity_fn: F) -> Vec<f32>
+    where
+        F: Fn(usize, usize, usize, usize) -> f32 + Sync,
+    {
+        match self {
+            Self::Draft => {
+                dither::generate_intensities_draft(width, height, DRAFT_ROW_STEP, intensity_fn)
+            }
+            Self::Normal => dither::generate_intensities(width, height, intensity_fn),
+            Self::High => dither::generate_intensities(width, height, |x, y, w, h| {
+                supersample(&intensity_fn, x, y, w, h)
+            }),
+        }
+    }
+
+    /// Sample and dither `intensity_fn` at this quality, returning packed
+    /// 1-bit raster data like [`dither::generate_raster_with_options`].
+    pub fn render<F>(
+        self,
+        width: usize,
+        height: usize,
+        intensity_fn: F,
+        algorithm: DitheringAlgorithm,
+        options: &DitherOptions,
+    ) -> Vec<u8>
+    where
+        F: Fn(usize, usize, usize, usize) -> f32 + Sync,
+    {
+        let algorithm = self.algorithm(algorithm);
+        if self == Self::Normal {
+            // Dither straight from the pattern, without a grayscale buffer
+            return dither::generate_raster_with_options(
+                width,
+                height,
+                intensity_fn,
+                algorithm,
+                options,
+            );
+        }
+        let gray = self.intensities(width, height, intensity_fn);
+        dither::dither_intensities_with_options(width, height, &gray, algorithm, options)
+    }
+}
+
+/// Average of the [`HIGH_SAMPLES`]² samples starting at `(x, y)`, staying
+/// inside the image.
+fn supersample<F>(intensity_fn: &F, x: usize, y: usize, width: usize, height: usize) -> f32
+where
+    F: Fn(usize, usize, usize, usize) -> f32,
+{
+    let mut sum = 0.0;
+    for dy in 0..HIGH_SAMPLES {
+        for dx in 0..HIGH_SAMPLES {
+            let sx = (x + dx).min(width.saturating_sub(1));
+            let sy = (y + dy).min(height.saturating_sub(1));
+            sum += intensity_fn(sx, sy, width, height);
+        }
+    }
+    sum / (HIGH_SAMPLES * HIGH_SAMPLES) as f32
+}
+
+impl std::str::FromStr for PrintQuality {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "draft" | "fast" => Ok(Self::Draft),
+            "normal" => Ok(Self::Normal),
+            "high" | "best" => Ok(Self::High),
+            _ => Err(format!(
+                "Unknown quality '{}'. Use 'draft', 'normal' or 'high'",
+                s
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for PrintQuality {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gradient(x: usize, _y: usize, w: usize, _h: usize) -> f32 {
+        x as f32 / w as f32
+    }
+
+    #[test]
+    fn test_normal_matches_plain_render() {
+        let options = DitherOptions::default();
+        let expected = dither::generate_raster_with_options(
+            64,
+            20,
+            gradient,
+            DitheringAlgorithm::FloydSteinberg,
+            &options,
+        );
+        let data = PrintQuality::Normal.render(
+            64,
+            20,
+            gradient,
+            DitheringAlgorithm::FloydSteinberg,
+            &options,
+        );
+        assert_eq!(data, expected);
+    }
+
+    #[test]
+    fn test_draft_samples_half_the_rows() {
+        let calls = std::sync::atomic::AtomicUsize::new(0);
+        let gray = PrintQuality::Draft.intensities(8, 10, |x, y, w, h| {
+            calls.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            gradient(x, y, w, h)
+        });
+        assert_eq!(gray.len(), 80);
+        assert_eq!(calls.into_inner(), 40);
+    }
+
+    #[test]
+    fn test_high_averages_samples() {
+        // A one-pixel checkerboard averages out to flat gray
+        let gray = PrintQuality::High.intensities(8, 8, |x, y, _w, _h| ((x + y) % 2) as f32);
+        assert!(gray[..7].iter().all(|&v| v == 0.5));
+        // The last column repeats its edge sample
+        assert_eq!(gray[7], 0.5);
+    }
+
+    #[test]
+    fn test_device_pacing() {
+        assert_eq!(PrintQuality::Normal.device("/dev/rfcomm0"), "/dev/rfcomm0");
+        assert_eq!(
+            PrintQuality::High.device("/dev/rfcomm0?chunk_size=512"),
+            "/dev/rfcomm0?chunk_size=512"
+        );
+        assert_eq!(
+            PrintQuality::High.device("tcp://10.0.0.5"),
+            "tcp://10.0.0.5"
+        );
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!("HIGH".parse::<PrintQuality>(), Ok(PrintQuality::High));
+        assert_eq!(PrintQuality::Draft.to_string(), "draft");
+        assert!("ultra".parse::<PrintQuality>().is_err());
+    }
+}
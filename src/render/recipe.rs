@@ -0,0 +1,185 @@
+//! Pattern recipes: everything needed to print a pattern again.
+//!
+//! A recipe is the pattern name, the seed its parameters were randomized
+//! from and the parameter values, packed into one short line that fits in
+//! a QR code:
+//!
+//! ```text
+//! estrella:ripple?seed=1234567&scale=6.5&center_x=0.5
+//! ```
+//!
+//! `estrella print --params-qr` prints it under a pattern, and
+//! `estrella reprint PAYLOAD` prints the pattern it describes.
+//!
+//! ```
+//! use estrella::render::patterns;
+//! use estrella::render::recipe::Recipe;
+//!
+//! let pattern = patterns::by_name_seeded("waves", 42).unwrap();
+//! let payload = Recipe::of(pattern.as_ref(), Some(42)).payload();
+//! assert!(payload.starts_with("estrella:waves?seed=42&"));
+//!
+//! let again = payload.parse::<Recipe>().unwrap().pattern().unwrap();
+//! assert_eq!(again.list_params(), pattern.list_params());
+//! ```
+
+use std::fmt::Write;
+
+use super::patterns::{self, Pattern};
+use crate::document::resolve::percent_decode;
+
+/// Prefix of every recipe payload.
+pub const SCHEME: &str = "estrella:";
+
+/// A pattern and the settings it was printed with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Recipe {
+    pub pattern: String,
+    /// Seed of the randomized parameters; golden parameters when unset.
+    pub seed: Option<u64>,
+    /// Parameter values, applied on top of the seeded (or golden) ones.
+    pub params: Vec<(String, String)>,
+}
+
+impl Recipe {
+    /// The recipe for `pattern`, randomized from `seed`.
+    pub fn of(pattern: &dyn Pattern, seed: Option<u64>) -> Self {
+        Self {
+            pattern: pattern.name().to_string(),
+            seed,
+            params: pattern
+                .list_params()
+                .into_iter()
+                .map(|(name, value)| (name.to_string(), value))
+                .collect(),
+        }
+    }
+
+    /// The recipe as one line, for a QR code.
+    pub fn payload(&self) -> String {
+        let mut out = format!("{}{}", SCHEME, escape(&self.pattern));
+        let seed = self.seed.map(|seed| ("seed".to_string(), seed.to_string()));
+        for (i, (name, value)) in seed.iter().chain(&self.params).enumerate() {
+            out.push(if i == 0 { '?' } else { '&' });
+            let _ = write!(out, "{}={}", escape(name), escape(value));
+        }
+        out
+    }
+
+    /// Build the pattern this recipe describes.
+    pub fn pattern(&self) -> Result<Box<dyn Pattern>, String> {
+        let pattern = match self.seed {
+            Some(seed) => patterns::by_name_seeded(&self.pattern, seed),
+            None => patterns::by_name_golden(&self.pattern),
+        };
+        let mut pattern = pattern.ok_or_else(|| format!("Unknown pattern '{}'", self.pattern))?;
+        for (name, value) in &self.params {
+            pattern.set_param(name, value)?;
+        }
+        Ok(pattern)
+    }
+}
+
+impl std::str::FromStr for Recipe {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let rest = s
+            .trim()
+            .strip_prefix(SCHEME)
+            .ok_or_else(|| format!("Not a pattern recipe (expected '{}...')", SCHEME))?;
+        let (pattern, query) = rest.split_once('?').unwrap_or((rest, ""));
+        if pattern.is_empty() {
+            return Err("Pattern recipe has no pattern name".into());
+        }
+
+        let mut recipe = Recipe {
+            pattern: percent_decode(pattern),
+            seed: None,
+            params: Vec::new(),
+        };
+        for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+            let (name, value) = pair
+                .split_once('=')
+                .ok_or_else(|| format!("Invalid recipe parameter '{}'", pair))?;
+            let (name, value) = (percent_decode(name), percent_decode(value));
+            if name == "seed" {
+                let seed = value
+                    .parse()
+                    .map_err(|_| format!("Invalid recipe seed '{}'", value))?;
+                recipe.seed = Some(seed);
+            } else {
+                recipe.params.push((name, value));
+            }
+        }
+        Ok(recipe)
+    }
+}
+
+/// `text` with the characters that would break the payload `%XX`-escaped.
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for byte in text.bytes() {
+        if byte.is_ascii_graphic() && !b"%&=?#".contains(&byte) {
+            out.push(byte as char);
+        } else {
+            let _ = write!(out, "%{:02X}", byte);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_payload_round_trip() {
+        let recipe = Recipe {
+            pattern: "expr".into(),
+            seed: Some(7),
+            params: vec![("formula".into(), "u > 0.5 & v=1 % 2".into())],
+        };
+        let payload = recipe.payload();
+        assert_eq!(
+            payload,
+            "estrella:expr?seed=7&formula=u%20>%200.5%20%26%20v%3D1%20%25%202"
+        );
+        assert_eq!(payload.parse(), Ok(recipe));
+    }
+
+    #[test]
+    fn test_recipe_rebuilds_pattern() {
+        let original = patterns::by_name_seeded("riley", 99).unwrap();
+        let recipe: Recipe = Recipe::of(original.as_ref(), Some(99))
+            .payload()
+            .parse()
+            .unwrap();
+        let copy = recipe.pattern().unwrap();
+        assert_eq!(copy.list_params(), original.list_params());
+        assert_eq!(
+            copy.intensity(10, 20, 576, 200),
+            original.intensity(10, 20, 576, 200)
+        );
+
+        let golden: Recipe = "estrella:ripple".parse().unwrap();
+        assert_eq!(golden.seed, None);
+        assert!(golden.pattern().is_ok());
+
+        for name in patterns::PATTERNS {
+            let pattern = patterns::by_name_golden(name).unwrap();
+            let recipe = Recipe::of(pattern.as_ref(), None);
+            assert!(recipe.pattern().is_ok(), "{} doesn't reprint", name);
+        }
+    }
+
+    #[test]
+    fn test_bad_payloads() {
+        assert!("ripple?seed=1".parse::<Recipe>().is_err());
+        assert!("estrella:".parse::<Recipe>().is_err());
+        assert!("estrella:ripple?seed=x".parse::<Recipe>().is_err());
+        assert!("estrella:ripple?scale".parse::<Recipe>().is_err());
+        let unknown: Recipe = "estrella:nope".parse().unwrap();
+        assert!(unknown.pattern().is_err());
+    }
+}
@@ -0,0 +1,61 @@
+//! HTML API handler: print an HTML page, such as a receipt a service sends
+//! by email.
+//!
+//! `POST /api/html/print` takes the HTML as the raw request body and prints
+//! it as one `html` component (see [`crate::document::Html`]) through the
+//! same path as `POST /api/json/print`, so images are fetched and hooks run.
+
+use axum::{
+    Json,
+    extract::{Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+use serde::Deserialize;
+use std::sync::Arc;
+
+use super::super::state::AppState;
+use super::PrintFlags;
+use super::json_api::{self, PrintQuery};
+
+/// Query parameters for `/api/html/print`.
+#[derive(Debug, Default, Deserialize)]
+pub struct HtmlQuery {
+    /// Print a link's URL after its text.
+    #[serde(default)]
+    pub show_urls: bool,
+    /// Cut after printing (default: true).
+    #[serde(default = "default_true")]
+    pub cut: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// Handle POST /api/html/print - print the HTML in the request body.
+///
+/// Takes the query parameters of `POST /api/json/print` too, and answers
+/// the same way. `{{...}}` in the HTML prints as it is.
+pub async fn print(
+    State(state): State<Arc<AppState>>,
+    Query(html): Query<HtmlQuery>,
+    query: Query<PrintQuery>,
+    flags: Query<PrintFlags>,
+    headers: HeaderMap,
+    body: String,
+) -> Response {
+    if body.trim().is_empty() {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({"success": false, "error": "Request body must be HTML"})),
+        )
+            .into_response();
+    }
+    let document = serde_json::json!({
+        "document": [{"type": "html", "content": body, "show_urls": html.show_urls}],
+        "cut": html.cut,
+        "interpolate": false,
+    });
+    json_api::print(State(state), query, flags, headers, Json(document)).await
+}
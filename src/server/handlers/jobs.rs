@@ -0,0 +1,165 @@
+//! Print job history and reprint handlers.
+//!
+//! Every print gets a job ID (see [`crate::job`]) that is returned in the
+//! response, logged, and optionally stamped on the receipt. These endpoints
+//! look jobs up by that ID, and list or release jobs held for quiet hours.
+
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+    http::StatusCode,
+};
+use std::sync::Arc;
+
+use crate::job::JobId;
+
+use super::super::state::{AppState, HeldJob, JobRecord};
+use super::{PrintFlags, PrintJob, print_job, release_held_jobs};
+
+type ApiError = (StatusCode, Json<serde_json::Value>);
+
+fn error(status: StatusCode, message: String) -> ApiError {
+    (
+        status,
+        Json(serde_json::json!({"success": false, "error": message})),
+    )
+}
+
+/// Look up a recorded job by its ID.
+async fn find(state: &AppState, id: &str) -> Result<JobRecord, ApiError> {
+    let id: JobId = id.parse().map_err(|e| error(StatusCode::BAD_REQUEST, e))?;
+    state
+        .jobs
+        .get(&id)
+        .await
+        .ok_or_else(|| error(StatusCode::NOT_FOUND, format!("Unknown job {}", id)))
+}
+
+/// Handle GET /api/jobs - recent print jobs, newest first.
+pub async fn list(State(state): State<Arc<AppState>>) -> Json<Vec<JobRecord>> {
+    Json(state.jobs.list().await)
+}
+
+/// Handle GET /api/jobs/:id - one print job.
+pub async fn get(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Json<JobRecord>, ApiError> {
+    find(&state, &id).await.map(Json)
+}
+
+/// Handle POST /api/jobs/:id/reprint - send a recorded job again.
+///
+/// The exact programs are resent, so a stamped receipt carries the original
+/// job ID; the reprint itself is recorded as a new job.
+pub async fn reprint(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Query(flags): Query<PrintFlags>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let original = find(&state, &id).await?;
+    if original.programs.is_empty() {
+        return Err(error(
+            StatusCode::CONFLICT,
+            format!("Job {} has nothing to reprint", original.id),
+        ));
+    }
+
+    let job = PrintJob {
+        id: JobId::new(),
+        source: "reprint".into(),
+        summary: format!("Reprint of {} ({})", original.id, original.summary),
+        reprint_of: Some(original.id),
+        document: original.document.clone(),
+        urgent: flags.urgent,
+    };
+    let job_id = job.id;
+    let programs = original.programs.clone();
+    let outcome = print_job(&state, job, move || Ok(programs.to_vec()))
+        .await
+        .map_err(|e| {
+            error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Print failed: {}", e),
+            )
+        })?;
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "message": outcome.message(format!("Job {} reprinted", original.id)),
+        "held_until": outcome.held_until(),
+        "preview": outcome.preview(),
+        "job_id": job_id,
+    })))
+}
+
+/// Handle POST /api/jobs/:id/gift-receipt - print a recorded job again as
+/// a gift receipt.
+///
+/// The job's document is recompiled with prices redacted (see
+/// [`crate::document::Document::as_gift_receipt`]); it keeps the original
+/// job ID for the stamp. Only jobs printed from a document can be
+/// redacted.
+pub async fn gift_receipt(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Query(flags): Query<PrintFlags>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let original = find(&state, &id).await?;
+    let Some(document) = original.document.clone() else {
+        return Err(error(
+            StatusCode::CONFLICT,
+            format!("Job {} has no document to redact", original.id),
+        ));
+    };
+
+    let job = PrintJob {
+        id: JobId::new(),
+        source: "gift_receipt".into(),
+        summary: format!("Gift receipt for {} ({})", original.id, original.summary),
+        reprint_of: Some(original.id),
+        document: Some(document.clone()),
+        urgent: flags.urgent,
+    };
+    let job_id = job.id;
+    let printer = state.config.printer;
+    let outcome = print_job(&state, job, move || {
+        Ok(vec![
+            document
+                .as_gift_receipt()
+                .print_program_with_config(&printer)?,
+        ])
+    })
+    .await
+    .map_err(|e| {
+        error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Print failed: {}", e),
+        )
+    })?;
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "message": outcome.message(format!("Gift receipt for job {} printed", original.id)),
+        "held_until": outcome.held_until(),
+        "preview": outcome.preview(),
+        "job_id": job_id,
+    })))
+}
+
+/// Handle GET /api/jobs/held - jobs waiting for quiet hours to end,
+/// oldest first.
+pub async fn held(State(state): State<Arc<AppState>>) -> Json<Vec<HeldJob>> {
+    Json(state.held.list().await)
+}
+
+/// Handle POST /api/jobs/held/release - print all held jobs now, without
+/// waiting for quiet hours to end.
+pub async fn release(State(state): State<Arc<AppState>>) -> Json<serde_json::Value> {
+    let released = release_held_jobs(&state).await;
+    Json(serde_json::json!({
+        "success": true,
+        "message": format!("Released {} held job(s)", released),
+        "released": released,
+    }))
+}
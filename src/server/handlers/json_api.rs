@@ -4,28 +4,236 @@
 
 use axum::{
     Json,
-    extract::{Path, State},
-    http::{StatusCode, header},
+    extract::{Path, Query, State},
+    http::{HeaderMap, HeaderName, HeaderValue, StatusCode, header},
     response::{Html, IntoResponse, Response},
 };
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
 use crate::document::canvas::ElementLayout;
-use crate::document::{self, Component, Document, ImageResolver};
+use crate::document::{
+    self, Component, Document, EmitContext, ImageResolver, OnError, QrCode, compat,
+};
+use crate::ir::analyze::marker_ranges;
 use crate::ir::{Op, Program};
-use crate::preview::{measure_cursor_y, measure_preview};
-use crate::transport::BluetoothTransport;
+use crate::job::JobId;
+use crate::preview::{
+    PreviewError, measure_cursor_y_with_config, measure_preview_with_config,
+    render_preview_page_with_config, render_svg_with_config,
+};
+use crate::units;
 
 use super::super::state::AppState;
+use super::{PrintFlags, PrintJob, print_job, run_hooks};
+
+/// Parse a request body of any supported wire-format version.
+///
+/// Returns the upgraded document and the deprecation warnings to send back.
+fn parse_document(
+    value: serde_json::Value,
+) -> Result<(Document, Vec<String>), (StatusCode, String)> {
+    compat::parse_document(value)
+        .map(|upgraded| (upgraded.document, upgraded.warnings))
+        .map_err(|e| {
+            (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                format!("Invalid document: {}", e),
+            )
+        })
+}
+
+/// One `X-Deprecation` header per warning, so clients on an old payload
+/// shape find out before it stops being accepted.
+fn deprecation_headers(warnings: &[String]) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    for warning in warnings {
+        if let Ok(value) = HeaderValue::from_str(warning) {
+            headers.append(HeaderName::from_static("x-deprecation"), value);
+        }
+    }
+    headers
+}
+
+/// Rows computed per repeated block in draft previews.
+const DRAFT_ROW_STEP: usize = 4;
+
+/// Query parameters for the preview endpoint.
+#[derive(Deserialize)]
+pub struct PreviewQuery {
+    /// `"full"` (default) or `"draft"`: patterns and canvases computed at a
+    /// quarter of the vertical resolution, for live feedback while editing.
+    quality: Option<String>,
+}
 
 /// Handle POST /api/json/preview - render JSON document as PNG.
+///
+/// `?quality=draft` trades vertical resolution for speed; request a full
+/// render once the user stops editing.
 pub async fn preview(
     State(state): State<Arc<AppState>>,
-    Json(mut doc): Json<Document>,
+    Query(query): Query<PreviewQuery>,
+    Json(body): Json<serde_json::Value>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let (mut doc, warnings) = parse_document(body)?;
+    let quality = apply_quality(&mut doc, query.quality.as_deref())?;
+    let program = prepare_preview(&state, doc).await?;
+    let png_bytes = program
+        .to_preview_png_with_config(&state.config.printer)
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Preview render failed: {}", e),
+            )
+        })?;
+
+    Ok((
+        deprecation_headers(&warnings),
+        [
+            (header::CONTENT_TYPE, "image/png".to_string()),
+            (
+                HeaderName::from_static("x-preview-quality"),
+                quality.to_string(),
+            ),
+        ],
+        png_bytes,
+    ))
+}
+
+/// Handle POST /api/json/preview.svg - render JSON document as SVG.
+///
+/// Same layout and `?quality` as [`preview`], with text as vector glyphs so
+/// the editor can zoom in without pixelation.
+pub async fn preview_svg(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<PreviewQuery>,
+    Json(body): Json<serde_json::Value>,
 ) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let (mut doc, warnings) = parse_document(body)?;
+    let quality = apply_quality(&mut doc, query.quality.as_deref())?;
+    let program = prepare_preview(&state, doc).await?;
+    let svg = render_svg_with_config(&program, &state.config.printer);
+
+    Ok((
+        deprecation_headers(&warnings),
+        [
+            (header::CONTENT_TYPE, "image/svg+xml".to_string()),
+            (
+                HeaderName::from_static("x-preview-quality"),
+                quality.to_string(),
+            ),
+        ],
+        svg,
+    ))
+}
+
+/// Set up the document for the requested preview quality, returning its name.
+fn apply_quality<'a>(
+    doc: &mut Document,
+    quality: Option<&'a str>,
+) -> Result<&'a str, (StatusCode, String)> {
+    let quality = quality.unwrap_or("full");
+    match quality {
+        "full" => {}
+        "draft" => doc.set_preview_row_step(DRAFT_ROW_STEP),
+        other => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                format!("Unknown quality '{}'. Use 'full' or 'draft'", other),
+            ));
+        }
+    }
+    Ok(quality)
+}
+
+/// Default page height for paged previews (~25cm of paper at 203 DPI).
+const DEFAULT_PAGE_ROWS: usize = 2000;
+
+/// Tallest page served (~1m of paper); larger `rows` are clamped to it.
+const MAX_PAGE_ROWS: usize = 8000;
+
+/// Query parameters for the paged preview endpoint.
+#[derive(Deserialize)]
+pub struct PageQuery {
+    /// Rows per page (defaults to [`DEFAULT_PAGE_ROWS`], at most
+    /// [`MAX_PAGE_ROWS`]).
+    rows: Option<usize>,
+    /// As for [`PreviewQuery`], so the editor can page draft previews too.
+    quality: Option<String>,
+}
+
+/// Handle POST /api/json/preview/page/:page - render one vertical page of a
+/// document preview.
+///
+/// Long documents can be fetched page by page instead of as one huge PNG.
+/// The `X-Page-Count` and `X-Total-Height` headers tell the client how many
+/// pages to request.
+pub async fn preview_page(
+    State(state): State<Arc<AppState>>,
+    Path(page): Path<usize>,
+    Query(query): Query<PageQuery>,
+    Json(body): Json<serde_json::Value>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let (mut doc, warnings) = parse_document(body)?;
+    let quality = apply_quality(&mut doc, query.quality.as_deref())?;
+    let program = prepare_preview(&state, doc).await?;
+    let rows = query.rows.unwrap_or(DEFAULT_PAGE_ROWS).min(MAX_PAGE_ROWS);
+    let result = render_preview_page_with_config(&program, page, rows, &state.config.printer)
+        .map_err(|e| match e {
+            PreviewError::InvalidOp(msg) => (StatusCode::BAD_REQUEST, msg),
+            e => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Preview render failed: {}", e),
+            ),
+        })?;
+
+    Ok((
+        deprecation_headers(&warnings),
+        [
+            (header::CONTENT_TYPE, "image/png".to_string()),
+            (
+                HeaderName::from_static("x-page-count"),
+                result.page_count.to_string(),
+            ),
+            (
+                HeaderName::from_static("x-total-height"),
+                result.total_height.to_string(),
+            ),
+            (
+                HeaderName::from_static("x-preview-quality"),
+                quality.to_string(),
+            ),
+        ],
+        result.png,
+    ))
+}
+
+/// Run hooks and resolve images, then compile for preview.
+///
+/// Links aren't shortened: previews run on every edit, and the shortener
+/// only sees documents that are printed or compiled.
+async fn prepare_preview(state: &AppState, doc: Document) -> Result<Program, (StatusCode, String)> {
+    let doc = prepare_document(state, doc).await?;
+    Ok(doc.compile_with_config(&state.config.printer))
+}
+
+/// Run hooks, apply the server defaults and resolve images: everything a
+/// print does before shortening links and compiling.
+async fn prepare_document(
+    state: &AppState,
+    doc: Document,
+) -> Result<Document, (StatusCode, String)> {
+    let mut doc = run_hooks(state, doc)
+        .await
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+    state.config.apply_defaults(&mut doc);
+    doc.expand_templates()
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+
     // Resolve images from URLs before compilation
-    let resolver = ImageResolver::new(state.photo_sessions.clone());
+    let resolver = ImageResolver::new(state.photo_sessions.clone())
+        .with_printer(&state.config.printer)
+        .with_sources(state.config.image_sources.clone());
     resolver.resolve(&mut doc).await.map_err(|e| {
         (
             StatusCode::BAD_REQUEST,
@@ -33,15 +241,79 @@ pub async fn preview(
         )
     })?;
 
-    let program = doc.compile();
-    let png_bytes = program.to_preview_png().map_err(|e| {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            format!("Preview render failed: {}", e),
-        )
-    })?;
+    Ok(doc)
+}
+
+/// Ops emitted by one top-level component, for [`compile`].
+///
+/// Components are those of the compiled document: after template
+/// expansion, station routing, gift receipt redaction and `on_error` skips,
+/// so indices can differ from the request's.
+#[derive(Serialize)]
+struct ComponentOps {
+    index: usize,
+    component: String,
+    /// Op range `[start, end)`, starting at the component's marker.
+    ops: [usize; 2],
+}
+
+/// Handle POST /api/json/compile - compile a document without printing.
+///
+/// Returns the optimized IR (as [`Program::to_json`] writes it), the
+/// number of bytes that would be sent, the estimated paper length, the
+/// ops each component emitted, deprecation warnings and the components
+/// that can't be printed. Hooks, images and link shortening run as for a
+/// print, so CI pipelines can validate payloads against a running server.
+pub async fn compile(
+    State(state): State<Arc<AppState>>,
+    Json(body): Json<serde_json::Value>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let (doc, warnings) = parse_document(body)?;
+    let mut doc = prepare_document(&state, doc).await?;
+    shorten_links(&state, &mut doc).await;
+    let component_errors = doc.component_errors();
 
-    Ok(([(header::CONTENT_TYPE, "image/png")], png_bytes))
+    // Markers map ops back to components and emit no bytes
+    doc.debug = true;
+    let printer = &state.config.printer;
+    let program = doc.compile_with_config(printer);
+    let bytes = if doc.raster {
+        doc.print_program_with_config(printer)
+            .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?
+            .to_bytes_with_config(printer)
+            .len()
+    } else {
+        program.to_bytes_with_config(printer).len()
+    };
+    let height = measure_preview_with_config(&program, printer).ok();
+
+    let components: Vec<ComponentOps> = marker_ranges(&program)
+        .into_iter()
+        .filter_map(|(label, range)| {
+            let (index, component) = label.strip_prefix('[')?.split_once("] ")?;
+            Some(ComponentOps {
+                index: index.parse().ok()?,
+                component: component.to_string(),
+                ops: [range.start, range.end],
+            })
+        })
+        .collect();
+
+    Ok((
+        deprecation_headers(&warnings),
+        Json(serde_json::json!({
+            "success": component_errors.is_empty() || doc.on_error != OnError::Fail,
+            "printer": printer.name,
+            "ir": program.ops,
+            "op_count": program.ops.len(),
+            "bytes": bytes,
+            "height_dots": height,
+            "length_mm": height.map(|dots| units::dots_to_mm(dots as u32, printer.dpi)),
+            "components": components,
+            "warnings": warnings,
+            "component_errors": component_errors,
+        })),
+    ))
 }
 
 /// Request body for canvas-layout endpoint.
@@ -65,6 +337,7 @@ pub struct CanvasLayoutResponse {
 
 /// Handle POST /api/json/canvas-layout - compute element bounding boxes for canvas overlay.
 pub async fn canvas_layout(
+    State(state): State<Arc<AppState>>,
     Json(req): Json<CanvasLayoutRequest>,
 ) -> Result<Json<CanvasLayoutResponse>, (StatusCode, String)> {
     let canvas_component = req.document.get(req.canvas_index).ok_or((
@@ -82,25 +355,28 @@ pub async fn canvas_layout(
         }
     };
 
-    let layout = canvas.compute_layout();
+    let printer = &state.config.printer;
+    let layout = canvas.compute_layout_with_config(printer);
 
     // Compute Y offset using cursor position (where the canvas starts in the
     // preview image), and document height using trimmed buffer height (matching
     // the actual preview PNG dimensions).
+    let ctx = EmitContext::new(printer);
     let mut prefix_ops = vec![Op::Init, Op::SetCodepage(1)];
     for comp in &req.document[..req.canvas_index] {
-        comp.emit(&mut prefix_ops);
+        comp.emit(&ctx, &mut prefix_ops);
     }
-    let y_offset = measure_cursor_y(&Program { ops: prefix_ops }).unwrap_or(0);
+    let y_offset = measure_cursor_y_with_config(&Program { ops: prefix_ops }, printer).unwrap_or(0);
 
     let mut all_ops = vec![Op::Init, Op::SetCodepage(1)];
     for comp in &req.document {
-        comp.emit(&mut all_ops);
+        comp.emit(&ctx, &mut all_ops);
     }
     if req.cut {
         all_ops.push(Op::Cut { partial: true });
     }
-    let document_height = measure_preview(&Program { ops: all_ops }).unwrap_or(0);
+    let document_height =
+        measure_preview_with_config(&Program { ops: all_ops }, printer).unwrap_or(0);
 
     Ok(Json(CanvasLayoutResponse {
         width: layout.width,
@@ -111,10 +387,74 @@ pub async fn canvas_layout(
     }))
 }
 
+/// Query parameters for the print endpoint.
+#[derive(Default, Deserialize)]
+pub struct PrintQuery {
+    /// Save the document and end the receipt with a small QR code that
+    /// reopens it in the web editor.
+    #[serde(default)]
+    edit_link: bool,
+}
+
 /// Handle POST /api/json/print - print JSON document to device.
-pub async fn print(State(state): State<Arc<AppState>>, Json(mut doc): Json<Document>) -> Response {
+///
+/// With `?edit_link=true` the document is saved (as sent, before hooks) and
+/// the response includes its `document_id`.
+pub async fn print(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<PrintQuery>,
+    Query(flags): Query<PrintFlags>,
+    request_headers: HeaderMap,
+    Json(body): Json<serde_json::Value>,
+) -> Response {
+    let (doc, warnings) = match parse_document(body) {
+        Ok(parsed) => parsed,
+        Err(e) => return e.into_response(),
+    };
+    let headers = deprecation_headers(&warnings);
+
+    let document_id = if query.edit_link {
+        Some(state.documents.save(&doc).await)
+    } else {
+        None
+    };
+
+    let mut doc = match run_hooks(&state, doc).await {
+        Ok(doc) => doc,
+        Err(e) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Html(format!(r#"{{"success": false, "error": "{}"}}"#, e)),
+            )
+                .into_response();
+        }
+    };
+    state.config.apply_defaults(&mut doc);
+    if let Err(e) = doc.expand_templates() {
+        return (
+            StatusCode::BAD_REQUEST,
+            headers,
+            Html(serde_json::json!({"success": false, "error": e.to_string()}).to_string()),
+        )
+            .into_response();
+    }
+    let job_id = JobId::new();
+    doc.job_id = Some(job_id);
+
+    if let Some(id) = &document_id {
+        let url = edit_link_url(&request_headers, &state, id);
+        doc.document.push(Component::QrCode(QrCode {
+            data: url,
+            cell_size: Some(3),
+            error_level: Some("L".into()),
+            align: None,
+        }));
+    }
+
     // Resolve images from URLs before compilation
-    let resolver = ImageResolver::new(state.photo_sessions.clone());
+    let resolver = ImageResolver::new(state.photo_sessions.clone())
+        .with_printer(&state.config.printer)
+        .with_sources(state.config.image_sources.clone());
     if let Err(e) = resolver.resolve(&mut doc).await {
         return (
             StatusCode::BAD_REQUEST,
@@ -126,46 +466,111 @@ pub async fn print(State(state): State<Arc<AppState>>, Json(mut doc): Json<Docum
             .into_response();
     }
 
+    shorten_links(&state, &mut doc).await;
+
+    let component_errors = doc.component_errors();
+    if doc.on_error == OnError::Fail && !component_errors.is_empty() {
+        let messages: Vec<String> = component_errors.iter().map(|e| e.to_string()).collect();
+        return (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            headers,
+            Html(
+                serde_json::json!({
+                    "success": false,
+                    "error": format!("Cannot print {}", messages.join("; ")),
+                    "component_errors": component_errors,
+                })
+                .to_string(),
+            ),
+        )
+            .into_response();
+    }
+    for error in &component_errors {
+        eprintln!("[json] Printing without {}", error);
+    }
+
     match serde_json::to_string_pretty(&doc) {
         Ok(json) => eprintln!("=== JSON Print ===\n{}\n==================", json),
         Err(e) => eprintln!("(failed to serialize document for logging: {})", e),
     }
 
-    let print_data = doc.build();
-    let device_path = state.config.device_path.clone();
-
-    let print_result = tokio::task::spawn_blocking(move || {
-        let mut transport = BluetoothTransport::open(&device_path)?;
-        transport.write_all(&print_data)?;
-        Ok::<_, crate::EstrellaError>(())
+    let job = PrintJob {
+        id: job_id,
+        source: "json".into(),
+        summary: format!("{} components", doc.document.len()),
+        reprint_of: None,
+        document: Some(Arc::new(doc.clone())),
+        urgent: flags.urgent,
+    };
+    let printer = state.config.printer;
+    let print_result = print_job(&state, job, move || {
+        Ok(vec![doc.print_program_with_config(&printer)?])
     })
     .await;
 
     match print_result {
-        Ok(Ok(())) => (
+        Ok(outcome) => (
             StatusCode::OK,
-            Html(r#"{"success": true, "message": "Document printed successfully"}"#.to_string()),
-        )
-            .into_response(),
-        Ok(Err(e)) => (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Html(format!(
-                r#"{{"success": false, "error": "Print failed: {}"}}"#,
-                e
-            )),
+            headers,
+            Html(
+                serde_json::json!({
+                    "success": true,
+                    "message": outcome.message("Document printed successfully"),
+                    "held_until": outcome.held_until(),
+                    "preview": outcome.preview(),
+                    "warnings": warnings,
+                    "component_errors": component_errors,
+                    "document_id": document_id,
+                    "job_id": job_id,
+                })
+                .to_string(),
+            ),
         )
             .into_response(),
         Err(e) => (
             StatusCode::INTERNAL_SERVER_ERROR,
-            Html(format!(
-                r#"{{"success": false, "error": "Task error: {}"}}"#,
-                e
-            )),
+            Html(
+                serde_json::json!({
+                    "success": false,
+                    "error": format!("Print failed: {}", e),
+                    "job_id": job_id,
+                })
+                .to_string(),
+            ),
         )
             .into_response(),
     }
 }
 
+/// Editor URL for a saved document, on the host the request came in on.
+fn edit_link_url(headers: &HeaderMap, state: &AppState, id: &str) -> String {
+    let header_str = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+    let host = header_str(header::HOST.as_str()).unwrap_or(&state.config.listen_addr);
+    let scheme = header_str("x-forwarded-proto").unwrap_or("http");
+    format!("{}://{}/?doc={}", scheme, host, id)
+}
+
+/// Handle GET /api/json/documents/:id - return a document saved by an
+/// edit-link print, for the web editor to reopen.
+pub async fn saved_document(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Json<Document>, StatusCode> {
+    state
+        .documents
+        .load(&id)
+        .await
+        .map(Json)
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+/// Shorten long QR code links if the server has a shortener configured.
+async fn shorten_links(state: &AppState, doc: &mut Document) {
+    if let Some(shortener) = &state.shortener {
+        shortener.resolve(doc).await;
+    }
+}
+
 /// Handle GET /api/json/component/:type/default - return a default component by type name.
 pub async fn component_default(
     Path(type_name): Path<String>,
@@ -0,0 +1,13 @@
+//! Prometheus metrics endpoint.
+
+use axum::{http::header, response::IntoResponse};
+
+use crate::metrics;
+
+/// GET /metrics - Stage timing histograms in the Prometheus text format.
+pub async fn metrics() -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        metrics::render(),
+    )
+}
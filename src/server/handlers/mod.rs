@@ -1,7 +1,549 @@
 //! HTTP handlers for the server.
 
+#[cfg(feature = "html")]
+pub mod html;
+pub mod jobs;
 pub mod json_api;
+pub mod metrics;
 pub mod patterns;
+#[cfg(feature = "pdf")]
+pub mod pdf;
 pub mod photo;
 pub mod receipt;
+pub mod schedules;
+pub mod templates;
 pub mod weave;
+
+use axum::body::to_bytes;
+use axum::response::Response;
+use chrono::NaiveDateTime;
+use serde::Deserialize;
+use std::borrow::Cow;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::EstrellaError;
+use crate::document::{Document, hooks};
+use crate::ir::Program;
+use crate::job::JobId;
+use crate::preview::svg;
+use crate::transport::{AsyncTransport, JOB_DELAY};
+
+use super::state::{AppState, HeldJob, JobRecord};
+
+/// Apply the server's document hooks.
+///
+/// Runs on the blocking pool since command hooks spawn processes.
+pub(super) async fn run_hooks(state: &AppState, doc: Document) -> Result<Document, EstrellaError> {
+    if state.hooks.is_empty() {
+        return Ok(doc);
+    }
+    let hook_list = state.hooks.clone();
+    tokio::task::spawn_blocking(move || {
+        let mut doc = doc;
+        hooks::apply_hooks(&hook_list, &mut doc)?;
+        Ok(doc)
+    })
+    .await
+    .map_err(|e| EstrellaError::Hook(format!("hook task failed: {}", e)))?
+}
+
+/// The JSON body of a handler's response, for callers that print without
+/// an HTTP request (schedules, MQTT). A body that isn't JSON comes back as
+/// `{"success": false, "error": body}`.
+pub(super) async fn response_json(response: Response) -> serde_json::Value {
+    let body = to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap_or_default();
+    serde_json::from_slice(&body).unwrap_or_else(|_| {
+        serde_json::json!({
+            "success": false,
+            "error": String::from_utf8_lossy(&body),
+        })
+    })
+}
+
+/// A print job for [`print_job`].
+pub(super) struct PrintJob {
+    pub id: JobId,
+    /// Handler name, used in logs and the job history.
+    pub source: Cow<'static, str>,
+    /// Short description for the job history.
+    pub summary: String,
+    /// Set when reprinting an earlier job.
+    pub reprint_of: Option<JobId>,
+    /// The document printed, if the job came from one.
+    pub document: Option<Arc<Document>>,
+    /// Print even during quiet hours.
+    pub urgent: bool,
+}
+
+/// Query flags accepted by every print endpoint.
+#[derive(Debug, Default, Deserialize)]
+pub struct PrintFlags {
+    /// `?urgent=true` prints right away, even during quiet hours.
+    #[serde(default)]
+    pub urgent: bool,
+}
+
+/// What [`print_job`] did with a job.
+pub(super) enum JobOutcome {
+    /// Sent to the printer.
+    Printed,
+    /// Compiled and held for quiet hours; prints when they end.
+    Held { until: NaiveDateTime },
+    /// Compiled but not sent, since no printer is configured; `png` is
+    /// what would have printed.
+    Previewed { png: Vec<u8> },
+}
+
+impl JobOutcome {
+    /// `printed` for a printed job, or when a held one will print.
+    pub fn message(&self, printed: impl Into<String>) -> String {
+        match self {
+            JobOutcome::Printed => printed.into(),
+            JobOutcome::Held { until } => format!(
+                "Quiet hours: held until {}. Pass ?urgent=true to print now",
+                until.format("%H:%M")
+            ),
+            JobOutcome::Previewed { .. } => {
+                "No printer configured: returning a preview instead".to_string()
+            }
+        }
+    }
+
+    /// Local time a held job will print.
+    pub fn held_until(&self) -> Option<String> {
+        match self {
+            JobOutcome::Held { until } => Some(until.format("%Y-%m-%d %H:%M").to_string()),
+            _ => None,
+        }
+    }
+
+    /// The preview of a job that wasn't printed, as a PNG data URI.
+    pub fn preview(&self) -> Option<String> {
+        match self {
+            JobOutcome::Previewed { png } => {
+                Some(format!("data:image/png;base64,{}", svg::base64(png)))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Build a print job on the blocking pool, send it, then record it in the
+/// job history.
+///
+/// During quiet hours a job that isn't `urgent` is built but not sent:
+/// it waits in [`AppState::held`] until [`release_held_jobs`] prints it.
+/// Without a printer (`--device none`) every job is built and answered
+/// with its preview.
+///
+/// `build` returns the programs to send, one per independent printer job
+/// (e.g. from [`Program::split_for_long_print`]). They are sent on a task
+/// of their own with an async transport; if the request is dropped (the
+/// client disconnected), the program being sent still finishes, so the
+/// printer stops on a command boundary, and the rest are skipped. The job
+/// is recorded as cancelled.
+pub(super) async fn print_job<F>(
+    state: &Arc<AppState>,
+    job: PrintJob,
+    build: F,
+) -> Result<JobOutcome, EstrellaError>
+where
+    F: FnOnce() -> Result<Vec<Program>, EstrellaError> + Send + 'static,
+{
+    println!("[{}] Job {}: {}", job.source, job.id, job.summary);
+    if !state.config.has_printer() {
+        return preview_job(state, job, build).await;
+    }
+    let now = chrono::Local::now().naive_local();
+    if !job.urgent
+        && let Some(until) = state.config.quiet_hours.and_then(|q| q.held_until(now))
+    {
+        hold_job(state, job, build, now, until).await?;
+        return Ok(JobOutcome::Held { until });
+    }
+
+    let cancel = CancelOnDrop(Arc::new(AtomicBool::new(false)));
+    let task = tokio::spawn(run_job(
+        Arc::clone(state),
+        job,
+        build,
+        Arc::clone(&cancel.0),
+    ));
+    let result = task
+        .await
+        .map_err(|e| EstrellaError::Transport(format!("Task error: {}", e)))
+        .and_then(|result| result);
+    drop(cancel);
+    result.map(|()| JobOutcome::Printed)
+}
+
+/// Print documents that need no further resolving (e.g. pages converted
+/// from an upload, with their images already decoded) as one job.
+///
+/// Each document is compiled with the server's defaults into a program of
+/// its own, so every page is cut.
+pub(super) async fn print_documents(
+    state: &Arc<AppState>,
+    source: &'static str,
+    summary: String,
+    mut docs: Vec<Document>,
+    urgent: bool,
+) -> Result<(JobId, JobOutcome), EstrellaError> {
+    let id = JobId::new();
+    for doc in &mut docs {
+        state.config.apply_defaults(doc);
+        doc.job_id = Some(id);
+    }
+    let job = PrintJob {
+        id,
+        source: source.into(),
+        summary,
+        reprint_of: None,
+        document: match &docs[..] {
+            [doc] => Some(Arc::new(doc.clone())),
+            _ => None,
+        },
+        urgent,
+    };
+    let printer = state.config.printer;
+    let outcome = print_job(state, job, move || {
+        docs.iter()
+            .map(|doc| doc.print_program_with_config(&printer))
+            .collect()
+    })
+    .await?;
+    Ok((id, outcome))
+}
+
+/// Build a job and render what it would print, then record it in the job
+/// history as if it had printed.
+async fn preview_job<F>(
+    state: &AppState,
+    job: PrintJob,
+    build: F,
+) -> Result<JobOutcome, EstrellaError>
+where
+    F: FnOnce() -> Result<Vec<Program>, EstrellaError> + Send + 'static,
+{
+    let printer = state.config.printer;
+    let (programs, png) = tokio::task::spawn_blocking(move || {
+        let programs = build()?;
+        let whole = Program {
+            ops: programs
+                .iter()
+                .flat_map(|p| p.ops.iter().cloned())
+                .collect(),
+        };
+        let png = whole
+            .to_preview_png_with_config(&printer)
+            .map_err(|e| EstrellaError::Image(format!("Failed to render preview: {}", e)))?;
+        Ok::<_, EstrellaError>((programs, png))
+    })
+    .await
+    .map_err(|e| EstrellaError::Transport(format!("Task error: {}", e)))??;
+    record_job(state, job, programs, &Ok(())).await;
+    Ok(JobOutcome::Previewed { png })
+}
+
+/// Build a job and queue it until quiet hours end.
+async fn hold_job<F>(
+    state: &AppState,
+    job: PrintJob,
+    build: F,
+    now: NaiveDateTime,
+    until: NaiveDateTime,
+) -> Result<(), EstrellaError>
+where
+    F: FnOnce() -> Result<Vec<Program>, EstrellaError> + Send + 'static,
+{
+    let programs = tokio::task::spawn_blocking(build)
+        .await
+        .map_err(|e| EstrellaError::Transport(format!("Task error: {}", e)))??;
+    println!(
+        "[{}] Job {} held for quiet hours until {}",
+        job.source,
+        job.id,
+        until.format("%H:%M")
+    );
+    state
+        .held
+        .push(HeldJob {
+            id: job.id,
+            source: job.source,
+            summary: job.summary,
+            held_at: now.format("%Y-%m-%d %H:%M:%S").to_string(),
+            held_until: until.format("%Y-%m-%d %H:%M").to_string(),
+            reprint_of: job.reprint_of,
+            programs,
+            document: job.document,
+        })
+        .await;
+    Ok(())
+}
+
+/// Print every job held for quiet hours, oldest first, and record each in
+/// the job history. Returns how many were released.
+pub(super) async fn release_held_jobs(state: &AppState) -> usize {
+    let held = state.held.take().await;
+    let count = held.len();
+    for job in held {
+        let result = send_programs(state, &job.programs, &AtomicBool::new(false)).await;
+        let print_job = PrintJob {
+            id: job.id,
+            source: job.source,
+            summary: job.summary,
+            reprint_of: job.reprint_of,
+            document: job.document,
+            urgent: false,
+        };
+        record_job(state, print_job, job.programs, &result).await;
+    }
+    count
+}
+
+/// Sets its flag when dropped, which [`run_job`] reads as "the client went
+/// away" unless the job has already finished.
+struct CancelOnDrop(Arc<AtomicBool>);
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Body of [`print_job`], independent of the request.
+async fn run_job<F>(
+    state: Arc<AppState>,
+    job: PrintJob,
+    build: F,
+    cancelled: Arc<AtomicBool>,
+) -> Result<(), EstrellaError>
+where
+    F: FnOnce() -> Result<Vec<Program>, EstrellaError> + Send + 'static,
+{
+    let outcome = tokio::task::spawn_blocking(build)
+        .await
+        .map_err(|e| EstrellaError::Transport(format!("Task error: {}", e)))
+        .and_then(|programs| programs);
+
+    let (programs, result) = match outcome {
+        Ok(programs) => {
+            let sent = send_programs(&state, &programs, &cancelled).await;
+            (programs, sent)
+        }
+        Err(e) => (Vec::new(), Err(e)),
+    };
+    record_job(&state, job, programs, &result).await;
+    result
+}
+
+/// Log how a job went and add it to the job history.
+async fn record_job(
+    state: &AppState,
+    job: PrintJob,
+    programs: Vec<Program>,
+    result: &Result<(), EstrellaError>,
+) {
+    match result {
+        Ok(()) if !state.config.has_printer() => {
+            println!("[{}] Job {} previewed (no printer)", job.source, job.id)
+        }
+        Ok(()) => println!("[{}] Job {} printed", job.source, job.id),
+        Err(e) => eprintln!("[{}] Job {} failed: {}", job.source, job.id, e),
+    }
+    state
+        .jobs
+        .record(JobRecord {
+            id: job.id,
+            source: job.source,
+            summary: job.summary,
+            printed_at: chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+            error: result.as_ref().err().map(|e| e.to_string()),
+            reprint_of: job.reprint_of,
+            document: job.document,
+            programs: Arc::new(programs),
+        })
+        .await;
+}
+
+/// Open the printer and send `programs` like
+/// [`AsyncTransport::send_programs`], stopping between programs once
+/// `cancelled` is set.
+async fn send_programs(
+    state: &AppState,
+    programs: &[Program],
+    cancelled: &AtomicBool,
+) -> Result<(), EstrellaError> {
+    let config = state.config.printer;
+    let mut printer = state.config.open_printer().await?;
+    for (i, program) in programs.iter().enumerate() {
+        if cancelled.load(Ordering::Relaxed) {
+            return Err(EstrellaError::Transport(format!(
+                "Cancelled: client disconnected after {} of {} parts",
+                i,
+                programs.len()
+            )));
+        }
+        printer.send_program(program, &config).await?;
+        if i + 1 < programs.len() {
+            printer.flush().await?;
+            tokio::time::sleep(JOB_DELAY).await;
+        }
+    }
+    printer.flush().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::Op;
+    use crate::printer::PrinterConfig;
+    use crate::server::ServerConfig;
+    use crate::transport::BoxFuture;
+
+    fn unreachable_printer(
+        device: &str,
+    ) -> BoxFuture<'_, Result<Box<dyn AsyncTransport>, EstrellaError>> {
+        panic!("opened printer {} in preview-only mode", device)
+    }
+
+    #[tokio::test]
+    async fn test_no_printer_returns_preview() {
+        let state = Arc::new(
+            AppState::new(
+                ServerConfig {
+                    device_path: ServerConfig::NO_PRINTER.to_string(),
+                    open_transport: unreachable_printer,
+                    printer: PrinterConfig::TSP650II,
+                    listen_addr: "127.0.0.1:0".to_string(),
+                    startup_print: true,
+                    shortener: None,
+                    hook_commands: Vec::new(),
+                    hook_timeout: crate::document::CommandHook::DEFAULT_TIMEOUT,
+                    storage: Default::default(),
+                    dither: Default::default(),
+                    location: None,
+                    image_sources: Default::default(),
+                    quiet_hours: "00:00-23:59".parse().ok(),
+                    ipp: false,
+                    #[cfg(feature = "mqtt")]
+                    mqtt: None,
+                },
+                Vec::new(),
+            )
+            .unwrap(),
+        );
+        let job = PrintJob {
+            id: JobId::new(),
+            source: "test".into(),
+            summary: "hello".to_string(),
+            reprint_of: None,
+            document: None,
+            urgent: false,
+        };
+        let id = job.id;
+        let outcome = print_job(&state, job, || {
+            let mut program = Program::with_init();
+            program.push(Op::Text("Hello".to_string()));
+            program.push(Op::Newline);
+            Ok(vec![program.clone(), program])
+        })
+        .await
+        .unwrap();
+
+        // Not held for quiet hours either: there's nothing to wait for
+        assert!(outcome.held_until().is_none());
+        assert!(
+            outcome
+                .message("printed")
+                .starts_with("No printer configured")
+        );
+        let preview = outcome.preview().unwrap();
+        assert!(preview.starts_with("data:image/png;base64,iVBORw0KGgo"));
+
+        let record = state.jobs.get(&id).await.unwrap();
+        assert_eq!(record.error, None);
+        assert_eq!(record.programs.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_jobs_survive_restart() {
+        let dir = std::env::temp_dir().join(format!("estrella-jobs-{}", std::process::id()));
+        let open = || {
+            AppState::new(
+                ServerConfig {
+                    device_path: "/dev/usb/lp0".to_string(),
+                    open_transport: unreachable_printer,
+                    printer: PrinterConfig::TSP650II,
+                    listen_addr: "127.0.0.1:0".to_string(),
+                    startup_print: false,
+                    shortener: None,
+                    hook_commands: Vec::new(),
+                    hook_timeout: crate::document::CommandHook::DEFAULT_TIMEOUT,
+                    storage: crate::server::StorageConfig::Directory(dir.clone()),
+                    dither: Default::default(),
+                    location: None,
+                    image_sources: Default::default(),
+                    quiet_hours: "00:00-23:59".parse().ok(),
+                    ipp: false,
+                    #[cfg(feature = "mqtt")]
+                    mqtt: None,
+                },
+                Vec::new(),
+            )
+            .unwrap()
+        };
+        let mut program = Program::with_init();
+        program.push(Op::Text("Hello".to_string()));
+
+        let state = Arc::new(open());
+        let printed = JobId::new();
+        record_job(
+            &state,
+            PrintJob {
+                id: printed,
+                source: "test".into(),
+                summary: "printed".to_string(),
+                reprint_of: None,
+                document: Some(Arc::new(Document::default())),
+                urgent: false,
+            },
+            vec![program.clone()],
+            &Ok(()),
+        )
+        .await;
+        let held = PrintJob {
+            id: JobId::new(),
+            source: "test".into(),
+            summary: "held".to_string(),
+            reprint_of: Some(printed),
+            document: None,
+            urgent: false,
+        };
+        let held_id = held.id;
+        let job_program = program.clone();
+        let outcome = print_job(&state, held, move || Ok(vec![job_program]))
+            .await
+            .unwrap();
+        assert!(outcome.held_until().is_some());
+        drop(state);
+
+        let state = open();
+        let record = state.jobs.get(&printed).await.unwrap();
+        assert_eq!(record.source, "test");
+        assert_eq!(*record.programs, [program.clone()]);
+        assert!(record.document.is_some());
+        let held = state.held.take().await;
+        assert_eq!(held.len(), 1);
+        assert_eq!(held[0].id, held_id);
+        assert_eq!(held[0].reprint_of, Some(printed));
+        assert_eq!(held[0].programs, [program]);
+
+        // Released jobs are gone for good
+        assert!(open().held.list().await.is_empty());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -11,28 +11,52 @@ use serde::{Deserialize, Serialize};
 use std::{collections::HashMap, io::Cursor, sync::Arc};
 
 use crate::{
-    art::ParamSpec,
-    printer::PrinterConfig,
-    render::{context::RenderContext, dither, patterns},
-    transport::BluetoothTransport,
+    art::{Category, ParamSpec},
+    job::JobId,
+    render::{context::RenderContext, patterns},
 };
 
 use super::super::state::AppState;
+use super::{PrintFlags, PrintJob, print_job};
 
 /// Pattern information returned by the API.
 #[derive(Debug, Serialize)]
 pub struct PatternInfo {
     pub name: String,
+    pub category: Option<Category>,
+    /// Seed the params were randomized from (randomize endpoint only).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
     pub params: HashMap<String, String>,
     pub specs: Vec<ParamSpec>,
 }
 
+/// Query parameters for the randomize endpoint.
+#[derive(Debug, Deserialize)]
+pub struct RandomizeQuery {
+    /// Seed to randomize from (a fresh one if absent).
+    #[serde(default)]
+    pub seed: Option<u64>,
+}
+
+/// A group of patterns returned by the categories endpoint.
+#[derive(Debug, Serialize)]
+pub struct PatternCategory {
+    pub category: Category,
+    pub label: &'static str,
+    pub patterns: Vec<&'static str>,
+}
+
 /// Query parameters for preview endpoint.
 #[derive(Debug, Deserialize)]
 pub struct PreviewQuery {
     pub length_mm: f32,
-    #[serde(default = "default_dither")]
-    pub dither: String,
+    /// Dithering algorithm (server's pattern default if absent)
+    #[serde(default)]
+    pub dither: Option<String>,
+    /// Randomize from this seed before applying params (golden if absent)
+    #[serde(default)]
+    pub seed: Option<u64>,
     /// Mode is accepted but not used for preview (only affects printing).
     #[serde(default = "default_mode")]
     #[allow(dead_code)]
@@ -41,10 +65,6 @@ pub struct PreviewQuery {
     pub params: HashMap<String, String>,
 }
 
-fn default_dither() -> String {
-    "floyd-steinberg".to_string()
-}
-
 fn default_mode() -> String {
     "raster".to_string()
 }
@@ -57,10 +77,15 @@ fn default_true() -> bool {
 #[derive(Debug, Deserialize)]
 pub struct PatternPrintForm {
     pub length_mm: f32,
-    #[serde(default = "default_dither")]
-    pub dither: String,
+    /// Dithering algorithm (server's pattern default if absent)
+    #[serde(default)]
+    pub dither: Option<String>,
     #[serde(default = "default_mode")]
     pub mode: String,
+    /// Randomize from this seed before applying params (golden if absent).
+    /// Printed in the details footer so the print can be made again.
+    #[serde(default)]
+    pub seed: Option<u64>,
     #[serde(default)]
     pub params: HashMap<String, String>,
     #[serde(default = "default_true")]
@@ -71,7 +96,26 @@ pub struct PatternPrintForm {
 
 /// GET /api/patterns - List all pattern names.
 pub async fn list() -> Json<Vec<&'static str>> {
-    Json(patterns::list_patterns().to_vec())
+    Json(patterns::list_patterns())
+}
+
+/// GET /api/patterns/categories - Pattern names grouped by category.
+pub async fn categories() -> Json<Vec<PatternCategory>> {
+    let names = patterns::list_patterns();
+    let groups = Category::ALL
+        .iter()
+        .map(|&category| PatternCategory {
+            category,
+            label: category.label(),
+            patterns: names
+                .iter()
+                .copied()
+                .filter(|name| patterns::category(name) == Some(category))
+                .collect(),
+        })
+        .filter(|group| !group.patterns.is_empty())
+        .collect();
+    Json(groups)
 }
 
 /// GET /api/patterns/:name/params - Get golden default params for a pattern.
@@ -88,14 +132,23 @@ pub async fn params(Path(name): Path<String>) -> Result<Json<PatternInfo>, Statu
 
     Ok(Json(PatternInfo {
         name: pattern.name().to_string(),
+        category: patterns::category(&name),
+        seed: None,
         params,
         specs,
     }))
 }
 
 /// POST /api/patterns/:name/randomize - Get randomized params for a pattern.
-pub async fn randomize(Path(name): Path<String>) -> Result<Json<PatternInfo>, StatusCode> {
-    let pattern = patterns::by_name_random(&name).ok_or(StatusCode::NOT_FOUND)?;
+///
+/// `?seed=N` gives the same params every time; without it a fresh seed is
+/// picked and returned.
+pub async fn randomize(
+    Path(name): Path<String>,
+    Query(query): Query<RandomizeQuery>,
+) -> Result<Json<PatternInfo>, StatusCode> {
+    let seed = query.seed.unwrap_or_else(patterns::random_seed);
+    let pattern = patterns::by_name_seeded(&name, seed).ok_or(StatusCode::NOT_FOUND)?;
 
     let params: HashMap<String, String> = pattern
         .list_params()
@@ -107,25 +160,35 @@ pub async fn randomize(Path(name): Path<String>) -> Result<Json<PatternInfo>, St
 
     Ok(Json(PatternInfo {
         name: pattern.name().to_string(),
+        category: patterns::category(&name),
+        seed: Some(seed),
         params,
         specs,
     }))
 }
 
+/// A pattern randomized from `seed`, or with golden params without one.
+fn lookup(name: &str, seed: Option<u64>) -> Option<Box<dyn patterns::Pattern>> {
+    match seed {
+        Some(seed) => patterns::by_name_seeded(name, seed),
+        None => patterns::by_name_golden(name),
+    }
+}
+
 /// GET /api/patterns/:name/preview - Generate PNG preview.
 pub async fn preview(
     State(state): State<Arc<AppState>>,
     Path(name): Path<String>,
     Query(query): Query<PreviewQuery>,
 ) -> Result<impl IntoResponse, (StatusCode, String)> {
-    let mut pattern = patterns::by_name_golden(&name).ok_or((
+    let mut pattern = lookup(&name, query.seed).ok_or((
         StatusCode::NOT_FOUND,
         format!("Pattern '{}' not found", name),
     ))?;
 
     // Apply custom params (skip the known query params)
     for (param_name, param_value) in &query.params {
-        if param_name != "length_mm" && param_name != "dither" && param_name != "mode" {
+        if !["length_mm", "dither", "mode", "seed"].contains(&param_name.as_str()) {
             pattern
                 .set_param(param_name, param_value)
                 .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid param: {}", e)))?;
@@ -133,7 +196,7 @@ pub async fn preview(
     }
 
     // Calculate dimensions
-    let config = PrinterConfig::TSP650II;
+    let config = state.config.printer;
     let width = config.width_dots as usize;
     let height = config.mm_to_dots(query.length_mm) as usize;
 
@@ -157,13 +220,7 @@ pub async fn preview(
         .map_err(|e| (StatusCode::BAD_REQUEST, format!("Prepare failed: {}", e)))?;
 
     // Parse dithering algorithm
-    let dither_algo = match query.dither.to_lowercase().as_str() {
-        "none" | "threshold" => dither::DitheringAlgorithm::None,
-        "floyd-steinberg" | "floyd_steinberg" | "fs" => dither::DitheringAlgorithm::FloydSteinberg,
-        "atkinson" => dither::DitheringAlgorithm::Atkinson,
-        "jarvis" | "jjn" => dither::DitheringAlgorithm::Jarvis,
-        _ => dither::DitheringAlgorithm::Bayer,
-    };
+    let dither_algo = state.config.dither.pattern_or(query.dither.as_deref());
 
     // Render pattern
     let raster_data = patterns::render(pattern.as_ref(), width, height, dither_algo);
@@ -197,9 +254,10 @@ pub async fn preview(
 pub async fn print(
     State(state): State<Arc<AppState>>,
     Path(name): Path<String>,
+    Query(flags): Query<PrintFlags>,
     Json(form): Json<PatternPrintForm>,
 ) -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)> {
-    let mut pattern = patterns::by_name_golden(&name).ok_or_else(|| {
+    let mut pattern = lookup(&name, form.seed).ok_or_else(|| {
         (
             StatusCode::NOT_FOUND,
             Json(serde_json::json!({"success": false, "error": "Pattern not found"})),
@@ -217,7 +275,7 @@ pub async fn print(
     }
 
     // Calculate dimensions
-    let config = PrinterConfig::TSP650II;
+    let config = state.config.printer;
     let width = config.width_dots as usize;
     let height = config.mm_to_dots(form.length_mm) as usize;
 
@@ -241,21 +299,16 @@ pub async fn print(
     })?;
 
     // Parse dithering algorithm
-    let dither_algo = match form.dither.to_lowercase().as_str() {
-        "none" | "threshold" => dither::DitheringAlgorithm::None,
-        "floyd-steinberg" | "floyd_steinberg" | "fs" => dither::DitheringAlgorithm::FloydSteinberg,
-        "atkinson" => dither::DitheringAlgorithm::Atkinson,
-        "jarvis" | "jjn" => dither::DitheringAlgorithm::Jarvis,
-        _ => dither::DitheringAlgorithm::Bayer,
-    };
+    let dither_algo = state.config.dither.pattern_or(form.dither.as_deref());
 
     // Render pattern
     let raster_data = patterns::render(pattern.as_ref(), width, height, dither_algo);
 
     // Build print command based on mode
-    use crate::document::{Divider, Text};
+    use crate::document::{Divider, EmitContext, Text};
     use crate::ir::{Op, Program};
 
+    let emit_ctx = EmitContext::new(&config);
     let mut program = Program::new();
     program.push(Op::Init);
 
@@ -270,14 +323,14 @@ pub async fn print(
             ..Default::default()
         };
         let mut title_ops = Vec::new();
-        title.emit(&mut title_ops);
+        title.emit(&emit_ctx, &mut title_ops);
         program.extend(title_ops);
         program.push(Op::Newline);
 
         // Divider
         let divider = Divider::default();
         let mut divider_ops = Vec::new();
-        divider.emit(&mut divider_ops);
+        divider.emit(&emit_ctx, &mut divider_ops);
         program.extend(divider_ops);
     }
 
@@ -298,15 +351,17 @@ pub async fn print(
     if form.print_details {
         let divider = Divider::default();
         let mut divider_ops = Vec::new();
-        divider.emit(&mut divider_ops);
+        divider.emit(&emit_ctx, &mut divider_ops);
         program.extend(divider_ops);
 
-        // Parameters
+        // Parameters, after the seed they were randomized from
         let params_list = pattern.list_params();
-        if !params_list.is_empty() {
-            let params_text = params_list
-                .iter()
-                .map(|(k, v)| format!("{}={}", k, v))
+        if !params_list.is_empty() || form.seed.is_some() {
+            let params_text = form
+                .seed
+                .map(|seed| format!("seed={}", seed))
+                .into_iter()
+                .chain(params_list.iter().map(|(k, v)| format!("{}={}", k, v)))
                 .collect::<Vec<_>>()
                 .join(", ");
             let params = Text {
@@ -316,7 +371,7 @@ pub async fn print(
                 ..Default::default()
             };
             let mut params_ops = Vec::new();
-            params.emit(&mut params_ops);
+            params.emit(&emit_ctx, &mut params_ops);
             program.extend(params_ops);
             program.push(Op::Newline);
         }
@@ -329,35 +384,37 @@ pub async fn print(
     }
 
     // Split for long print and send to printer
-    let device_path = state.config.device_path.clone();
-    let pattern_name = name.clone();
-
-    println!(
-        "[patterns] Print request: pattern={}, {}x{} pixels, mode={}",
-        pattern_name, width, height, form.mode
-    );
+    let job = PrintJob {
+        id: JobId::new(),
+        source: "patterns".into(),
+        summary: format!(
+            "pattern={}, {}x{} pixels, mode={}",
+            name, width, height, form.mode
+        ),
+        reprint_of: None,
+        document: None,
+        urgent: flags.urgent,
+    };
+    let job_id = job.id;
 
-    let print_result = tokio::task::spawn_blocking(move || {
+    let print_result = print_job(&state, job, move || {
         let programs = program.split_for_long_print();
         println!("[patterns] Split into {} program(s)", programs.len());
-        let mut transport = BluetoothTransport::open(&device_path)?;
-        transport.send_programs(&programs)?;
-        Ok::<_, crate::EstrellaError>(())
+        Ok(programs)
     })
     .await;
 
     match print_result {
-        Ok(Ok(())) => Ok(Json(serde_json::json!({
+        Ok(outcome) => Ok(Json(serde_json::json!({
             "success": true,
-            "message": format!("Pattern '{}' printed successfully", name)
+            "message": outcome.message(format!("Pattern '{}' printed successfully", name)),
+            "held_until": outcome.held_until(),
+            "preview": outcome.preview(),
+            "job_id": job_id,
         }))),
-        Ok(Err(e)) => Err((
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(serde_json::json!({"success": false, "error": format!("Print failed: {}", e)})),
-        )),
         Err(e) => Err((
             StatusCode::INTERNAL_SERVER_ERROR,
-            Json(serde_json::json!({"success": false, "error": format!("Task error: {}", e)})),
+            Json(serde_json::json!({"success": false, "error": format!("Print failed: {}", e)})),
         )),
     }
 }
@@ -0,0 +1,84 @@
+//! PDF API handler: print the pages of an uploaded PDF.
+//!
+//! `POST /api/pdf/print` takes the PDF as the raw request body. Each page
+//! becomes an image document (see [`crate::document::pdf`]) and is cut
+//! after printing.
+
+use axum::{
+    Json,
+    body::Bytes,
+    extract::{Query, State},
+    http::StatusCode,
+};
+use serde::Deserialize;
+use std::sync::Arc;
+
+use crate::EstrellaError;
+use crate::document::pdf::{self, PdfOptions};
+
+use super::super::state::AppState;
+use super::{PrintFlags, print_documents};
+
+type ApiError = (StatusCode, Json<serde_json::Value>);
+
+/// Query parameters for `/api/pdf/print`.
+#[derive(Debug, Default, Deserialize)]
+pub struct PdfQuery {
+    /// Dithering algorithm for the pages (the image default when omitted).
+    #[serde(default)]
+    pub dither: Option<String>,
+    /// Print whole pages instead of trimming their blank margins.
+    #[serde(default)]
+    pub keep_margins: bool,
+    /// Password of an encrypted PDF.
+    #[serde(default)]
+    pub password: Option<String>,
+}
+
+/// Print every page of the PDF in the request body, one cut per page.
+pub async fn print(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<PdfQuery>,
+    Query(flags): Query<PrintFlags>,
+    body: Bytes,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    if body.is_empty() {
+        return Err(bad_request("Request body must be a PDF".into()));
+    }
+    let options = PdfOptions {
+        dither: query.dither,
+        keep_margins: query.keep_margins,
+        password: query.password,
+    };
+    let printer = state.config.printer;
+    // PDFium renders synchronously, and pages are big
+    let docs = tokio::task::spawn_blocking(move || pdf::documents(&body, &options, &printer))
+        .await
+        .map_err(|e| EstrellaError::Transport(format!("Task error: {}", e)))
+        .and_then(|result| result)
+        .map_err(|e| bad_request(e.to_string()))?;
+
+    let pages = docs.len();
+    let summary = format!("PDF, {} page{}", pages, if pages == 1 { "" } else { "s" });
+    match print_documents(&state, "pdf", summary, docs, flags.urgent).await {
+        Ok((job_id, outcome)) => Ok(Json(serde_json::json!({
+            "success": true,
+            "message": outcome.message(format!("Printed {} PDF page(s)", pages)),
+            "pages": pages,
+            "held_until": outcome.held_until(),
+            "preview": outcome.preview(),
+            "job_id": job_id,
+        }))),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({"success": false, "error": format!("Print failed: {}", e)})),
+        )),
+    }
+}
+
+fn bad_request(error: String) -> ApiError {
+    (
+        StatusCode::BAD_REQUEST,
+        Json(serde_json::json!({"success": false, "error": error})),
+    )
+}
@@ -16,15 +16,19 @@ use std::{sync::Arc, time::Instant};
 use uuid::Uuid;
 
 use crate::{
-    printer::PrinterConfig,
+    job::JobId,
     render::{
-        self,
+        self, adjust,
+        alpha::{self, Background},
+        context::PhotoSession,
         dither::{self, DitheringAlgorithm},
+        exif::{self, CaptionField, PhotoInfo},
+        halftone::{self, Halftone},
     },
-    transport::BluetoothTransport,
 };
 
-use super::super::state::{AppState, PhotoSession, SESSION_EXPIRATION_SECS};
+use super::super::state::{AppState, SESSION_EXPIRATION_SECS};
+use super::{PrintFlags, PrintJob, print_job};
 
 /// Response from upload endpoint.
 #[derive(Debug, Serialize)]
@@ -35,6 +39,8 @@ pub struct UploadResponse {
     pub height: u32,
     /// True if the image is already binary (1-bit black/white)
     pub is_binary: bool,
+    /// Date taken and camera, when the photo has EXIF
+    pub exif: PhotoInfo,
 }
 
 /// Query parameters for preview endpoint.
@@ -42,16 +48,19 @@ pub struct UploadResponse {
 pub struct PreviewQuery {
     #[serde(default)]
     pub rotation: i32,
-    #[serde(default = "default_dither")]
-    pub dither: String,
+    /// Dithering algorithm (server's photo default if absent)
+    #[serde(default)]
+    pub dither: Option<String>,
     #[serde(default)]
     pub brightness: i32,
     #[serde(default)]
     pub contrast: i32,
-}
-
-fn default_dither() -> String {
-    "floyd-steinberg".to_string()
+    /// What prints behind transparent pixels (white if absent)
+    #[serde(default)]
+    pub background: Option<Background>,
+    /// Halftone screen, e.g. `dot:25:45` (replaces dithering when set)
+    #[serde(default)]
+    pub halftone: Option<Halftone>,
 }
 
 fn default_mode() -> String {
@@ -67,16 +76,26 @@ fn default_true() -> bool {
 pub struct PrintRequest {
     #[serde(default)]
     pub rotation: i32,
-    #[serde(default = "default_dither")]
-    pub dither: String,
+    /// Dithering algorithm (server's photo default if absent)
+    #[serde(default)]
+    pub dither: Option<String>,
     #[serde(default)]
     pub brightness: i32,
     #[serde(default)]
     pub contrast: i32,
+    /// What prints behind transparent pixels (white if absent)
+    #[serde(default)]
+    pub background: Option<Background>,
+    /// Halftone screen, e.g. `dot:25:45` (replaces dithering when set)
+    #[serde(default)]
+    pub halftone: Option<Halftone>,
     #[serde(default = "default_mode")]
     pub mode: String,
     #[serde(default = "default_true")]
     pub cut: bool,
+    /// EXIF fields to print under the photo, e.g. `["date", "camera"]`
+    #[serde(default)]
+    pub caption: Vec<CaptionField>,
 }
 
 /// POST /api/photo/upload - Upload an image file.
@@ -113,19 +132,22 @@ pub async fn upload(
     let image_bytes =
         image_data.ok_or((StatusCode::BAD_REQUEST, "No image field found".to_string()))?;
 
-    // Decode the image (try HEIC first if it looks like HEIC, otherwise use image crate)
-    let img = if is_heic(&image_bytes)
+    // Decode the image (try HEIC first if it looks like HEIC, otherwise use
+    // image crate). Either way it comes out upright: libheif applies HEIF's
+    // rotation, and the image crate path applies EXIF orientation.
+    let (img, info) = if is_heic(&image_bytes)
         || filename.to_lowercase().ends_with(".heic")
         || filename.to_lowercase().ends_with(".heif")
     {
-        decode_heic(&image_bytes).map_err(|e| {
+        let img = decode_heic(&image_bytes).map_err(|e| {
             (
                 StatusCode::BAD_REQUEST,
                 format!("Failed to decode HEIC: {}", e),
             )
-        })?
+        })?;
+        (img, PhotoInfo::default())
     } else {
-        image::load_from_memory(&image_bytes).map_err(|e| {
+        exif::decode_with_info(&image_bytes).map_err(|e| {
             (
                 StatusCode::BAD_REQUEST,
                 format!("Failed to decode image: {}", e),
@@ -155,7 +177,7 @@ pub async fn upload(
 
     // Generate session ID and store
     let session_id = Uuid::new_v4();
-    let session = PhotoSession::new(img);
+    let session = PhotoSession::with_info(img, info.clone());
 
     {
         let mut sessions = state.photo_sessions.write().await;
@@ -168,6 +190,7 @@ pub async fn upload(
         width,
         height,
         is_binary,
+        exif: info,
     }))
 }
 
@@ -196,11 +219,25 @@ pub async fn preview(
     let rotation = query.rotation;
     let brightness = query.brightness;
     let contrast = query.contrast;
-    let dither_algo = parse_dither(&query.dither);
+    let screening = Screening::new(
+        state.config.dither.photo_or(query.dither.as_deref()),
+        query.halftone,
+        state.config.printer.dpi,
+    );
+    let background = query.background.unwrap_or_default();
+    let target_width = state.config.printer.width_dots as u32;
 
     // Move CPU-intensive work to blocking thread pool
     let png_bytes = tokio::task::spawn_blocking(move || {
-        generate_preview_png(source_image, rotation, brightness, contrast, dither_algo)
+        generate_preview_png(
+            source_image,
+            target_width,
+            rotation,
+            brightness,
+            contrast,
+            screening,
+            &background,
+        )
     })
     .await
     .map_err(|e| {
@@ -217,13 +254,12 @@ pub async fn preview(
 /// Prepare image for printing: rotate, resize to printer width, adjust brightness/contrast.
 fn prepare_for_print(
     source_image: DynamicImage,
+    target_width: u32,
     rotation: i32,
     brightness: i32,
     contrast: i32,
     filter: FilterType,
 ) -> DynamicImage {
-    let target_width = PrinterConfig::TSP650II.width_dots as u32;
-
     // Rotate first to get correct orientation
     let rotated = match rotation % 360 {
         90 | -270 => source_image.rotate90(),
@@ -232,33 +268,55 @@ fn prepare_for_print(
         _ => source_image,
     };
 
-    // Resize to target width (576px)
+    // Resize to target width (the print width)
     let aspect_ratio = rotated.height() as f32 / rotated.width() as f32;
     let target_height = (target_width as f32 * aspect_ratio).round() as u32;
     let resized = rotated.resize(target_width, target_height, filter);
 
     // Apply brightness/contrast
-    apply_brightness_contrast_if_needed(&resized, brightness, contrast)
+    adjust::apply_brightness_contrast_if_needed(&resized, brightness, contrast)
+}
+
+/// How gray turns into black and white dots.
+#[derive(Debug, Clone, Copy)]
+enum Screening {
+    Dither(DitheringAlgorithm),
+    /// A halftone screen, and the printer DPI its LPI is measured against
+    Halftone(Halftone, u16),
+}
+
+impl Screening {
+    /// A halftone screen when one is requested, else dithering.
+    fn new(dither: DitheringAlgorithm, halftone: Option<Halftone>, dpi: u16) -> Self {
+        match halftone {
+            Some(halftone) => Screening::Halftone(halftone, dpi),
+            None => Screening::Dither(dither),
+        }
+    }
 }
 
-/// Generate dithered raster data from a grayscale image.
+/// Generate dithered (or halftoned) raster data from an image, laying
+/// transparent pixels over `background`.
 fn generate_dithered_raster(
     img: &DynamicImage,
-    dither_algo: DitheringAlgorithm,
+    screening: Screening,
+    background: &Background,
 ) -> (usize, usize, Vec<u8>) {
     let width = img.width() as usize;
     let height = img.height() as usize;
-    let grayscale = img.to_luma8();
-
-    let raster_data = dither::generate_raster(
-        width,
-        height,
-        |x, y, _w, _h| {
-            let pixel = grayscale.get_pixel(x as u32, y as u32);
-            1.0 - (pixel[0] as f32 / 255.0)
-        },
-        dither_algo,
-    );
+    let intensities = alpha::intensities(img, background);
+
+    let raster_data = match screening {
+        Screening::Dither(dither_algo) => dither::generate_raster(
+            width,
+            height,
+            |x, y, w, _h| intensities[y * w + x],
+            dither_algo,
+        ),
+        Screening::Halftone(halftone, dpi) => {
+            halftone::screen(width, height, &intensities, &halftone, dpi)
+        }
+    };
 
     (width, height, raster_data)
 }
@@ -266,50 +324,57 @@ fn generate_dithered_raster(
 /// Generate a dithered preview PNG (runs on blocking thread pool).
 fn generate_preview_png(
     source_image: DynamicImage,
+    target_width: u32,
     rotation: i32,
     brightness: i32,
     contrast: i32,
-    dither_algo: DitheringAlgorithm,
+    screening: Screening,
+    background: &Background,
 ) -> Result<Vec<u8>, String> {
     // Use Triangle filter for speed in preview
     let processed = prepare_for_print(
         source_image,
+        target_width,
         rotation,
         brightness,
         contrast,
         FilterType::Triangle,
     );
-    let (width, height, raster_data) = generate_dithered_raster(&processed, dither_algo);
+    let (width, height, raster_data) = generate_dithered_raster(&processed, screening, background);
     render::raster_to_png(width, height, &raster_data)
 }
 
 /// Generate raster data for printing (runs on blocking thread pool).
 fn generate_print_raster(
     source_image: DynamicImage,
+    target_width: u32,
     rotation: i32,
     brightness: i32,
     contrast: i32,
-    dither_algo: DitheringAlgorithm,
+    screening: Screening,
+    background: &Background,
 ) -> (usize, usize, Vec<u8>) {
     // Use Lanczos3 for print quality
     let processed = prepare_for_print(
         source_image,
+        target_width,
         rotation,
         brightness,
         contrast,
         FilterType::Lanczos3,
     );
-    generate_dithered_raster(&processed, dither_algo)
+    generate_dithered_raster(&processed, screening, background)
 }
 
 /// POST /api/photo/:id/print - Print the uploaded image.
 pub async fn print(
     State(state): State<Arc<AppState>>,
     Path(id): Path<String>,
+    Query(flags): Query<PrintFlags>,
     Json(req): Json<PrintRequest>,
 ) -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)> {
     // Get the image from session (minimize lock time)
-    let source_image = {
+    let (source_image, info) = {
         let mut sessions = state.photo_sessions.write().await;
         let session = sessions.get_mut(&id).ok_or((
             StatusCode::NOT_FOUND,
@@ -320,26 +385,50 @@ pub async fn print(
         session.touch();
 
         // Clone the image to release the lock quickly
-        session.image.clone()
+        (session.image.clone(), session.info.clone())
     };
 
     // Parse parameters
     let rotation = req.rotation;
     let brightness = req.brightness;
     let contrast = req.contrast;
-    let dither_algo = parse_dither(&req.dither);
+    let screening = Screening::new(
+        state.config.dither.photo_or(req.dither.as_deref()),
+        req.halftone,
+        state.config.printer.dpi,
+    );
+    let background = req.background.unwrap_or_default();
+    let target_width = state.config.printer.width_dots as u32;
     let mode = req.mode.clone();
     let cut = req.cut;
-    let device_path = state.config.device_path.clone();
+    let caption = info.caption(&req.caption);
+
+    let job = PrintJob {
+        id: JobId::new(),
+        source: "photo".into(),
+        summary: format!("photo {}, mode={}", id, mode),
+        reprint_of: None,
+        document: None,
+        urgent: flags.urgent,
+    };
+    let job_id = job.id;
 
     // Move all CPU-intensive work to blocking thread pool
-    let print_result = tokio::task::spawn_blocking(move || {
+    let print_result = print_job(&state, job, move || {
         // Generate raster data
-        let (width, height, raster_data) =
-            generate_print_raster(source_image, rotation, brightness, contrast, dither_algo);
+        let (width, height, raster_data) = generate_print_raster(
+            source_image,
+            target_width,
+            rotation,
+            brightness,
+            contrast,
+            screening,
+            &background,
+        );
 
         // Build print command
         use crate::ir::{Op, Program};
+        use crate::protocol::text::{Alignment, Font};
 
         let mut program = Program::new();
         program.push(Op::Init);
@@ -357,95 +446,46 @@ pub async fn print(
             });
         }
 
+        if let Some(caption) = caption {
+            program.push(Op::SetAlign(Alignment::Center));
+            program.push(Op::SetFont(Font::B));
+            program.push(Op::Text(caption));
+            program.push(Op::Newline);
+            program.push(Op::ResetStyle);
+        }
+
         program.push(Op::Feed { units: 24 }); // 6mm
 
         if cut {
             program.push(Op::Cut { partial: false });
         }
 
-        // Split for long print and send to printer
+        // Split for long print
         println!(
             "[photo] Print request: {}x{} pixels, mode={}",
             width, height, mode
         );
         let programs = program.split_for_long_print();
         println!("[photo] Split into {} program(s)", programs.len());
-        let mut transport = BluetoothTransport::open(&device_path)?;
-        transport.send_programs(&programs)?;
-        Ok::<_, crate::EstrellaError>(())
+        Ok(programs)
     })
     .await;
 
     match print_result {
-        Ok(Ok(())) => Ok(Json(serde_json::json!({
+        Ok(outcome) => Ok(Json(serde_json::json!({
             "success": true,
-            "message": "Photo printed successfully"
+            "message": outcome.message("Photo printed successfully"),
+            "held_until": outcome.held_until(),
+            "preview": outcome.preview(),
+            "job_id": job_id,
         }))),
-        Ok(Err(e)) => Err((
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(serde_json::json!({"success": false, "error": format!("Print failed: {}", e)})),
-        )),
         Err(e) => Err((
             StatusCode::INTERNAL_SERVER_ERROR,
-            Json(serde_json::json!({"success": false, "error": format!("Task error: {}", e)})),
+            Json(serde_json::json!({"success": false, "error": format!("Print failed: {}", e)})),
         )),
     }
 }
 
-/// Apply brightness and contrast if needed, otherwise return a clone.
-fn apply_brightness_contrast_if_needed(
-    img: &DynamicImage,
-    brightness: i32,
-    contrast: i32,
-) -> DynamicImage {
-    if brightness != 0 || contrast != 0 {
-        apply_brightness_contrast(img, brightness, contrast)
-    } else {
-        img.clone()
-    }
-}
-
-/// Apply brightness and contrast adjustments to an image.
-fn apply_brightness_contrast(img: &DynamicImage, brightness: i32, contrast: i32) -> DynamicImage {
-    let mut rgba = img.to_rgba8();
-
-    // Brightness: -100 to 100 maps to -255 to 255 offset
-    let brightness_offset = (brightness as f32 / 100.0) * 255.0;
-
-    // Contrast: -100 to 100 maps to factor
-    // At -100: factor = 0 (all gray)
-    // At 0: factor = 1 (no change)
-    // At 100: factor = 2 (max contrast)
-    let contrast_factor = 1.0 + (contrast as f32 / 100.0);
-
-    for pixel in rgba.pixels_mut() {
-        for c in 0..3 {
-            // Skip alpha channel
-            let val = pixel[c] as f32;
-            // Apply contrast (around midpoint 128)
-            let val = (val - 128.0) * contrast_factor + 128.0;
-            // Apply brightness
-            let val = val + brightness_offset;
-            // Clamp to valid range
-            pixel[c] = val.clamp(0.0, 255.0) as u8;
-        }
-    }
-
-    DynamicImage::ImageRgba8(rgba)
-}
-
-/// Parse dithering algorithm from string.
-fn parse_dither(dither: &str) -> DitheringAlgorithm {
-    match dither.to_lowercase().as_str() {
-        "none" | "threshold" => DitheringAlgorithm::None,
-        "floyd-steinberg" | "floyd_steinberg" | "fs" => DitheringAlgorithm::FloydSteinberg,
-        "atkinson" => DitheringAlgorithm::Atkinson,
-        "jarvis" | "jjn" => DitheringAlgorithm::Jarvis,
-        "bayer" => DitheringAlgorithm::Bayer,
-        _ => DitheringAlgorithm::FloydSteinberg,
-    }
-}
-
 /// Clean up expired photo sessions.
 async fn cleanup_expired_sessions(state: &AppState) {
     let now = Instant::now();
@@ -601,74 +641,4 @@ mod tests {
         assert_eq!(rotated.width(), 50);
         assert_eq!(rotated.height(), 100);
     }
-
-    #[test]
-    fn test_brightness_increase() {
-        let img = create_test_image(10, 10);
-        let original_pixel = img.to_rgba8().get_pixel(5, 5).0;
-
-        let brightened = apply_brightness_contrast(&img, 50, 0);
-        let bright_pixel = brightened.to_rgba8().get_pixel(5, 5).0;
-
-        // Brightness increase should make pixels brighter (higher values)
-        assert!(
-            bright_pixel[0] > original_pixel[0] || bright_pixel[0] == 255,
-            "Red channel should be brighter: {} vs {}",
-            bright_pixel[0],
-            original_pixel[0]
-        );
-    }
-
-    #[test]
-    fn test_brightness_decrease() {
-        let img = create_test_image(10, 10);
-        let original_pixel = img.to_rgba8().get_pixel(5, 5).0;
-
-        let darkened = apply_brightness_contrast(&img, -50, 0);
-        let dark_pixel = darkened.to_rgba8().get_pixel(5, 5).0;
-
-        // Brightness decrease should make pixels darker (lower values)
-        assert!(
-            dark_pixel[0] < original_pixel[0] || dark_pixel[0] == 0,
-            "Red channel should be darker: {} vs {}",
-            dark_pixel[0],
-            original_pixel[0]
-        );
-    }
-
-    #[test]
-    fn test_contrast_increase() {
-        // Create a gray image
-        let mut img = RgbImage::new(10, 10);
-        for y in 0..10 {
-            for x in 0..10 {
-                img.put_pixel(x, y, Rgb([100, 100, 100])); // Mid-gray-ish
-            }
-        }
-        let img = DynamicImage::ImageRgb8(img);
-
-        let contrasted = apply_brightness_contrast(&img, 0, 50);
-        let pixel = contrasted.to_rgba8().get_pixel(5, 5).0;
-
-        // With contrast increase, values below 128 should go lower
-        // Original: 100, after contrast (factor 1.5): (100-128)*1.5+128 = 86
-        assert!(
-            pixel[0] < 100,
-            "Expected contrast to move 100 away from 128, got {}",
-            pixel[0]
-        );
-    }
-
-    #[test]
-    fn test_no_change_when_zero() {
-        let img = create_test_image(10, 10);
-        let original_pixel = img.to_rgba8().get_pixel(5, 5).0;
-
-        let unchanged = apply_brightness_contrast_if_needed(&img, 0, 0);
-        let new_pixel = unchanged.to_rgba8().get_pixel(5, 5).0;
-
-        assert_eq!(original_pixel[0], new_pixel[0]);
-        assert_eq!(original_pixel[1], new_pixel[1]);
-        assert_eq!(original_pixel[2], new_pixel[2]);
-    }
 }
@@ -2,7 +2,7 @@
 
 use axum::{
     Json,
-    extract::State,
+    extract::{Query, State},
     http::{StatusCode, header},
     response::{Html, IntoResponse, Response},
 };
@@ -11,12 +11,12 @@ use std::sync::Arc;
 
 use crate::{
     document::{Component, Divider, Document, Markdown, Spacer, Text},
-    ir::Program,
+    job::JobId,
     receipt::current_datetime,
-    transport::BluetoothTransport,
 };
 
 use super::super::state::AppState;
+use super::{JobOutcome, PrintFlags, PrintJob, print_job, run_hooks};
 
 fn default_true() -> bool {
     true
@@ -38,29 +38,49 @@ pub struct ReceiptForm {
 }
 
 /// Handle POST /api/receipt/print - print the receipt.
-pub async fn print(State(state): State<Arc<AppState>>, Json(form): Json<ReceiptForm>) -> Response {
+pub async fn print(
+    State(state): State<Arc<AppState>>,
+    Query(flags): Query<PrintFlags>,
+    Json(form): Json<ReceiptForm>,
+) -> Response {
     // Validate input
     if form.body.trim().is_empty() {
         return error_response("Body cannot be empty");
     }
 
     // Build the receipt data
-    let receipt_data = build_receipt(&form).to_bytes();
+    let mut doc = match run_hooks(&state, build_receipt(&form)).await {
+        Ok(doc) => doc,
+        Err(e) => return error_response(&e.to_string()),
+    };
+    let job_id = JobId::new();
+    doc.job_id = Some(job_id);
+    let job = PrintJob {
+        id: job_id,
+        source: "receipt".into(),
+        summary: form
+            .title
+            .clone()
+            .unwrap_or_else(|| "(no title)".to_string()),
+        reprint_of: None,
+        document: Some(Arc::new(doc.clone())),
+        urgent: flags.urgent,
+    };
 
     // Print to device (blocking operation, run in separate thread)
-    let device_path = state.config.device_path.clone();
-    let print_result =
-        tokio::task::spawn_blocking(move || print_to_device(&device_path, &receipt_data)).await;
-
-    match print_result {
-        Ok(Ok(())) => success_response(&form),
-        Ok(Err(e)) => error_response(&format!("Print failed: {}", e)),
-        Err(e) => error_response(&format!("Task error: {}", e)),
+    let printer = state.config.printer;
+    match print_job(&state, job, move || {
+        Ok(vec![doc.print_program_with_config(&printer)?])
+    })
+    .await
+    {
+        Ok(outcome) => success_response(&form, &job_id, &outcome),
+        Err(e) => error_response(&format!("Print failed: {}", e)),
     }
 }
 
-/// Build receipt program from form data.
-fn build_receipt(form: &ReceiptForm) -> Program {
+/// Build receipt document from form data.
+fn build_receipt(form: &ReceiptForm) -> Document {
     let mut components = Vec::new();
 
     // Add title if provided
@@ -100,18 +120,10 @@ fn build_receipt(form: &ReceiptForm) -> Program {
         interpolate: false,
         ..Default::default()
     }
-    .compile()
-}
-
-/// Print to the physical device.
-fn print_to_device(device_path: &str, data: &[u8]) -> Result<(), crate::EstrellaError> {
-    let mut transport = BluetoothTransport::open(device_path)?;
-    transport.write_all(data)?;
-    Ok(())
 }
 
 /// Generate success response JSON.
-fn success_response(form: &ReceiptForm) -> Response {
+fn success_response(form: &ReceiptForm, job_id: &JobId, outcome: &JobOutcome) -> Response {
     let title_text = form
         .title
         .as_ref()
@@ -120,10 +132,16 @@ fn success_response(form: &ReceiptForm) -> Response {
 
     (
         StatusCode::OK,
-        Html(format!(
-            r#"{{"success": true, "message": "Receipt {} printed successfully"}}"#,
-            title_text
-        )),
+        Html(
+            serde_json::json!({
+                "success": true,
+                "message": outcome.message(format!("Receipt {} printed successfully", title_text)),
+                "held_until": outcome.held_until(),
+                "preview": outcome.preview(),
+                "job_id": job_id,
+            })
+            .to_string(),
+        ),
     )
         .into_response()
 }
@@ -138,18 +156,29 @@ fn error_response(error_msg: &str) -> Response {
 }
 
 /// Handle POST /api/receipt/preview - generate PNG preview.
-pub async fn preview(Json(form): Json<ReceiptForm>) -> impl IntoResponse {
+pub async fn preview(
+    State(state): State<Arc<AppState>>,
+    Json(form): Json<ReceiptForm>,
+) -> impl IntoResponse {
     if form.body.trim().is_empty() {
         return Err((StatusCode::BAD_REQUEST, "Body cannot be empty".to_string()));
     }
 
+    let doc = run_hooks(&state, build_receipt(&form))
+        .await
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+
     // Build the receipt program and render to PNG
-    let png_bytes = build_receipt(&form).to_preview_png().map_err(|e| {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            format!("Failed to render preview: {}", e),
-        )
-    })?;
+    let printer = &state.config.printer;
+    let png_bytes = doc
+        .compile_with_config(printer)
+        .to_preview_png_with_config(printer)
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to render preview: {}", e),
+            )
+        })?;
 
     Ok(([(header::CONTENT_TYPE, "image/png")], png_bytes))
 }
@@ -0,0 +1,178 @@
+//! Scheduled print handlers.
+//!
+//! Schedules print a saved template on a cron expression (see
+//! [`crate::server::Schedule`]). These endpoints create, list, change and
+//! remove them; the server's scheduler task prints them with [`run`].
+
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+};
+use std::io;
+use std::sync::Arc;
+
+use super::super::schedule::{RunOutcome, Schedule};
+use super::super::state::AppState;
+use super::json_api::PrintQuery;
+use super::templates::{self, TemplatePrintRequest};
+use super::{PrintFlags, response_json};
+
+type ApiError = (StatusCode, Json<serde_json::Value>);
+
+fn error(status: StatusCode, message: String) -> ApiError {
+    (
+        status,
+        Json(serde_json::json!({"success": false, "error": message})),
+    )
+}
+
+fn storage_error(e: io::Error) -> ApiError {
+    match e.kind() {
+        io::ErrorKind::InvalidInput => error(StatusCode::NOT_FOUND, "Unknown schedule".into()),
+        _ => error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Schedule storage failed: {}", e),
+        ),
+    }
+}
+
+/// Look up a schedule by id.
+async fn find(state: &AppState, id: &str) -> Result<Schedule, ApiError> {
+    state
+        .schedules
+        .load(id)
+        .await
+        .map_err(storage_error)?
+        .ok_or_else(|| error(StatusCode::NOT_FOUND, format!("Unknown schedule {}", id)))
+}
+
+/// Parse a schedule from a request body and check its template exists.
+/// Its next run is counted from now.
+async fn parse(state: &AppState, body: serde_json::Value) -> Result<Schedule, ApiError> {
+    let mut schedule: Schedule = serde_json::from_value(body)
+        .map_err(|e| error(StatusCode::BAD_REQUEST, format!("Invalid schedule: {}", e)))?;
+    if !matches!(state.templates.load(&schedule.template).await, Ok(Some(_))) {
+        return Err(error(
+            StatusCode::BAD_REQUEST,
+            format!("Unknown template '{}'", schedule.template),
+        ));
+    }
+    schedule.last_run = None;
+    schedule.start(chrono::Local::now().naive_local());
+    Ok(schedule)
+}
+
+/// Handle GET /api/schedules - all schedules.
+pub async fn list(State(state): State<Arc<AppState>>) -> Result<Json<Vec<Schedule>>, ApiError> {
+    state
+        .schedules
+        .list()
+        .await
+        .map(Json)
+        .map_err(storage_error)
+}
+
+/// Handle POST /api/schedules - add a schedule.
+///
+/// The body needs `cron` and `template`; `variables`, `missed` ("skip" or
+/// "catch_up") and `enabled` are optional. The response is the schedule
+/// with its `id` and `next_run`.
+pub async fn create(
+    State(state): State<Arc<AppState>>,
+    Json(body): Json<serde_json::Value>,
+) -> Result<(StatusCode, Json<Schedule>), ApiError> {
+    let mut schedule = parse(&state, body).await?;
+    state
+        .schedules
+        .create(&mut schedule)
+        .await
+        .map_err(storage_error)?;
+    println!(
+        "[schedule] {}: '{}' at '{}'",
+        schedule.id, schedule.template, schedule.cron
+    );
+    Ok((StatusCode::CREATED, Json(schedule)))
+}
+
+/// Handle GET /api/schedules/:id - one schedule.
+pub async fn get(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Json<Schedule>, ApiError> {
+    find(&state, &id).await.map(Json)
+}
+
+/// Handle PUT /api/schedules/:id - replace a schedule's settings.
+///
+/// Takes the same body as creating one. Runs are counted afresh from now;
+/// the last print is kept.
+pub async fn update(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Json(body): Json<serde_json::Value>,
+) -> Result<Json<Schedule>, ApiError> {
+    let existing = find(&state, &id).await?;
+    let mut schedule = parse(&state, body).await?;
+    schedule.id = existing.id;
+    schedule.last_run = existing.last_run;
+    state
+        .schedules
+        .save(&schedule)
+        .await
+        .map_err(storage_error)?;
+    Ok(Json(schedule))
+}
+
+/// Handle DELETE /api/schedules/:id - remove a schedule.
+pub async fn delete(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    if !state.schedules.delete(&id).await.map_err(storage_error)? {
+        return Err(error(
+            StatusCode::NOT_FOUND,
+            format!("Unknown schedule {}", id),
+        ));
+    }
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "message": format!("Schedule {} deleted", id),
+    })))
+}
+
+/// Print a schedule's template, as `POST /api/templates/:name/print`
+/// would.
+///
+/// Server errors (the printer can't be reached) can be retried; anything
+/// else, like a template that is gone or no longer compiles, can't.
+pub async fn run(state: &Arc<AppState>, schedule: &Schedule) -> RunOutcome {
+    println!(
+        "[schedule] {}: printing '{}'",
+        schedule.id, schedule.template
+    );
+    let response = templates::print(
+        State(state.clone()),
+        Path(schedule.template.clone()),
+        Query(PrintQuery::default()),
+        Query(PrintFlags::default()),
+        HeaderMap::new(),
+        Json(TemplatePrintRequest {
+            variables: schedule.variables.clone(),
+        }),
+    )
+    .await;
+    let status = response.status();
+    if status.is_success() {
+        return RunOutcome::Printed;
+    }
+    let error = response_json(response).await["error"]
+        .as_str()
+        .unwrap_or("print failed")
+        .to_string();
+    eprintln!("[schedule] {}: print failed: {}", schedule.id, error);
+    RunOutcome::Failed {
+        error,
+        retry: status.is_server_error(),
+    }
+}
@@ -0,0 +1,211 @@
+//! Named document templates.
+//!
+//! A template is a document with `{{variable}}` placeholders, saved once
+//! under a name. Printing it only takes the variables, which are merged
+//! over the template's own before the document goes through the same path
+//! as `POST /api/json/print`.
+
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+use serde::Deserialize;
+use std::io;
+use std::sync::Arc;
+
+use crate::document::{Document, compat};
+
+use super::super::state::AppState;
+use super::PrintFlags;
+use super::json_api::{self, PrintQuery};
+
+type ApiError = (StatusCode, Json<serde_json::Value>);
+
+fn error(status: StatusCode, message: String) -> ApiError {
+    (
+        status,
+        Json(serde_json::json!({"success": false, "error": message})),
+    )
+}
+
+/// A storage error: a bad name is the caller's mistake, anything else the
+/// server's.
+fn storage_error(name: &str, e: io::Error) -> ApiError {
+    match e.kind() {
+        io::ErrorKind::InvalidInput => error(
+            StatusCode::BAD_REQUEST,
+            format!(
+                "Invalid template name '{}': use letters, digits, '-' and '_'",
+                name
+            ),
+        ),
+        _ => error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Template storage failed: {}", e),
+        ),
+    }
+}
+
+/// Look up a template by name.
+async fn find(state: &AppState, name: &str) -> Result<Document, ApiError> {
+    state
+        .templates
+        .load(name)
+        .await
+        .map_err(|e| storage_error(name, e))?
+        .ok_or_else(|| {
+            error(
+                StatusCode::NOT_FOUND,
+                format!("Unknown template '{}'", name),
+            )
+        })
+}
+
+/// Handle GET /api/templates - names of the saved templates.
+pub async fn list(State(state): State<Arc<AppState>>) -> Result<Json<Vec<String>>, ApiError> {
+    state
+        .templates
+        .names()
+        .await
+        .map(Json)
+        .map_err(|e| storage_error("", e))
+}
+
+/// Handle GET /api/templates/:name - one template.
+pub async fn get(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+) -> Result<Json<Document>, ApiError> {
+    find(&state, &name).await.map(Json)
+}
+
+/// Handle POST /api/templates/:name - save a document as a template,
+/// replacing any with the same name.
+///
+/// The body is any document the print endpoint accepts; older wire formats
+/// are upgraded before saving, and the rewrites come back as `warnings`.
+pub async fn save(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+    Json(body): Json<serde_json::Value>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let upgraded = compat::parse_document(body).map_err(|e| {
+        error(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            format!("Invalid document: {}", e),
+        )
+    })?;
+    state
+        .templates
+        .save(&name, &upgraded.document)
+        .await
+        .map_err(|e| storage_error(&name, e))?;
+    println!("[templates] Saved template '{}'", name);
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "message": format!("Template '{}' saved", name),
+        "warnings": upgraded.warnings,
+    })))
+}
+
+/// Handle DELETE /api/templates/:name - remove a template.
+pub async fn delete(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let existed = state
+        .templates
+        .delete(&name)
+        .await
+        .map_err(|e| storage_error(&name, e))?;
+    if !existed {
+        return Err(error(
+            StatusCode::NOT_FOUND,
+            format!("Unknown template '{}'", name),
+        ));
+    }
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "message": format!("Template '{}' deleted", name),
+    })))
+}
+
+/// Request body for printing a template.
+#[derive(Debug, Default, Deserialize)]
+pub struct TemplatePrintRequest {
+    /// Values for the template's placeholders; any JSON, like a document's
+    /// `variables`.
+    #[serde(default)]
+    pub variables: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Handle POST /api/templates/:name/print - print a template with the
+/// given `variables`.
+///
+/// Takes the same query parameters as `POST /api/json/print` and answers
+/// the same way.
+pub async fn print(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+    query: Query<PrintQuery>,
+    flags: Query<PrintFlags>,
+    headers: HeaderMap,
+    Json(request): Json<TemplatePrintRequest>,
+) -> Response {
+    let template = match find(&state, &name).await {
+        Ok(template) => template,
+        Err(e) => return e.into_response(),
+    };
+    let body = match with_variables(&template, request.variables) {
+        Ok(body) => body,
+        Err(e) => return e.into_response(),
+    };
+    json_api::print(State(state), query, flags, headers, Json(body)).await
+}
+
+/// The template as a print request body, with `variables` merged over its
+/// own.
+fn with_variables(
+    template: &Document,
+    variables: serde_json::Map<String, serde_json::Value>,
+) -> Result<serde_json::Value, ApiError> {
+    let mut body = serde_json::to_value(template).map_err(|e| {
+        error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to serialize template: {}", e),
+        )
+    })?;
+    if let Some(fields) = body.as_object_mut() {
+        let merged = fields
+            .entry("variables")
+            .or_insert_with(|| serde_json::Value::Object(Default::default()));
+        if let Some(merged) = merged.as_object_mut() {
+            merged.extend(variables);
+        }
+    }
+    Ok(body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_variables_override_template() {
+        let template: Document = serde_json::from_value(serde_json::json!({
+            "document": [{"type": "text", "content": "Hi {{name}}, table {{table}}"}],
+            "variables": {"name": "guest", "table": "4"}
+        }))
+        .unwrap();
+        let variables = serde_json::json!({"name": "Ada", "items": [{"name": "Latte"}]});
+        let body = with_variables(&template, variables.as_object().unwrap().clone()).unwrap();
+
+        let doc = compat::parse_document(body).unwrap().document;
+        assert_eq!(doc.variables["name"], "Ada");
+        assert_eq!(doc.variables["table"], "4");
+        // Non-string values stay JSON, for `repeat`
+        assert_eq!(doc.variables["items"], r#"[{"name":"Latte"}]"#);
+    }
+}
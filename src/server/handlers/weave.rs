@@ -2,7 +2,7 @@
 
 use axum::{
     Json,
-    extract::State,
+    extract::{Query, State},
     http::{StatusCode, header},
     response::IntoResponse,
 };
@@ -11,17 +11,17 @@ use serde::Deserialize;
 use std::{collections::HashMap, io::Cursor, sync::Arc};
 
 use crate::{
-    printer::PrinterConfig,
+    job::JobId,
     render::{
         context::RenderContext,
         dither,
         patterns::{self, Pattern},
         weave::{BlendCurve, Weave},
     },
-    transport::BluetoothTransport,
 };
 
 use super::super::state::AppState;
+use super::{PrintFlags, PrintJob, print_job};
 
 // Available curves: "linear", "smooth", "ease-in", "ease-out"
 // Hardcoded in frontend - see BlendCurve in src/render/weave.rs for reference
@@ -46,8 +46,9 @@ pub struct WeaveRequest {
     pub crossfade_mm: f32,
     #[serde(default = "default_curve")]
     pub curve: String,
-    #[serde(default = "default_dither")]
-    pub dither: String,
+    /// Dithering algorithm (server's pattern default if absent)
+    #[serde(default)]
+    pub dither: Option<String>,
     #[serde(default = "default_mode")]
     pub mode: String,
     pub patterns: Vec<WeavePatternEntry>,
@@ -65,10 +66,6 @@ fn default_curve() -> String {
     "smooth".to_string()
 }
 
-fn default_dither() -> String {
-    "floyd-steinberg".to_string()
-}
-
 fn default_mode() -> String {
     "raster".to_string()
 }
@@ -100,7 +97,7 @@ pub async fn preview(
     );
 
     // Calculate dimensions (needed for prepare)
-    let config = PrinterConfig::TSP650II;
+    let config = state.config.printer;
     let width = config.width_dots as usize;
     let height = config.mm_to_dots(req.length_mm) as usize;
 
@@ -134,13 +131,7 @@ pub async fn preview(
     let blend_curve = BlendCurve::from_str(&req.curve).unwrap_or(BlendCurve::Smooth);
 
     // Parse dithering algorithm
-    let dither_algo = match req.dither.to_lowercase().as_str() {
-        "none" | "threshold" => dither::DitheringAlgorithm::None,
-        "floyd-steinberg" | "floyd_steinberg" | "fs" => dither::DitheringAlgorithm::FloydSteinberg,
-        "atkinson" => dither::DitheringAlgorithm::Atkinson,
-        "jarvis" | "jjn" => dither::DitheringAlgorithm::Jarvis,
-        _ => dither::DitheringAlgorithm::Bayer,
-    };
+    let dither_algo = state.config.dither.pattern_or(req.dither.as_deref());
 
     // Create the weave
     let pattern_refs: Vec<&dyn Pattern> = pattern_impls.iter().map(|p| p.as_ref()).collect();
@@ -184,6 +175,7 @@ pub async fn preview(
 /// POST /api/weave/print - Print the blended patterns.
 pub async fn print(
     State(state): State<Arc<AppState>>,
+    Query(flags): Query<PrintFlags>,
     Json(req): Json<WeaveRequest>,
 ) -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)> {
     if req.patterns.len() < 2 {
@@ -208,7 +200,7 @@ pub async fn print(
     );
 
     // Calculate dimensions (needed for prepare)
-    let config = PrinterConfig::TSP650II;
+    let config = state.config.printer;
     let width = config.width_dots as usize;
     let height = config.mm_to_dots(req.length_mm) as usize;
     let crossfade_pixels = config.mm_to_dots(req.crossfade_mm) as usize;
@@ -246,13 +238,7 @@ pub async fn print(
     let blend_curve = BlendCurve::from_str(&req.curve).unwrap_or(BlendCurve::Smooth);
 
     // Parse dithering algorithm
-    let dither_algo = match req.dither.to_lowercase().as_str() {
-        "none" | "threshold" => dither::DitheringAlgorithm::None,
-        "floyd-steinberg" | "floyd_steinberg" | "fs" => dither::DitheringAlgorithm::FloydSteinberg,
-        "atkinson" => dither::DitheringAlgorithm::Atkinson,
-        "jarvis" | "jjn" => dither::DitheringAlgorithm::Jarvis,
-        _ => dither::DitheringAlgorithm::Bayer,
-    };
+    let dither_algo = state.config.dither.pattern_or(req.dither.as_deref());
 
     // Create the weave
     let pattern_refs: Vec<&dyn Pattern> = pattern_impls.iter().map(|p| p.as_ref()).collect();
@@ -269,9 +255,10 @@ pub async fn print(
     );
 
     // Build print command based on mode
-    use crate::document::{Divider, Text};
+    use crate::document::{Divider, EmitContext, Text};
     use crate::ir::{Op, Program};
 
+    let emit_ctx = EmitContext::new(&config);
     let mut program = Program::new();
     program.push(Op::Init);
 
@@ -296,7 +283,7 @@ pub async fn print(
     if req.print_details {
         let divider = Divider::default();
         let mut divider_ops = Vec::new();
-        divider.emit(&mut divider_ops);
+        divider.emit(&emit_ctx, &mut divider_ops);
         program.extend(divider_ops);
 
         // Each pattern with its params
@@ -318,7 +305,7 @@ pub async fn print(
                 ..Default::default()
             };
             let mut text_ops = Vec::new();
-            text.emit(&mut text_ops);
+            text.emit(&emit_ctx, &mut text_ops);
             program.extend(text_ops);
             program.push(Op::Newline);
         }
@@ -331,37 +318,40 @@ pub async fn print(
     }
 
     // Split for long print and send to printer
-    let device_path = state.config.device_path.clone();
-
-    println!(
-        "[weave] Print request: {} patterns, {}x{} pixels, mode={}",
-        pattern_names.len(),
-        width,
-        height,
-        req.mode
-    );
+    let job = PrintJob {
+        id: JobId::new(),
+        source: "weave".into(),
+        summary: format!(
+            "{} patterns, {}x{} pixels, mode={}",
+            pattern_names.len(),
+            width,
+            height,
+            req.mode
+        ),
+        reprint_of: None,
+        document: None,
+        urgent: flags.urgent,
+    };
+    let job_id = job.id;
 
-    let print_result = tokio::task::spawn_blocking(move || {
+    let print_result = print_job(&state, job, move || {
         let programs = program.split_for_long_print();
         println!("[weave] Split into {} program(s)", programs.len());
-        let mut transport = BluetoothTransport::open(&device_path)?;
-        transport.send_programs(&programs)?;
-        Ok::<_, crate::EstrellaError>(())
+        Ok(programs)
     })
     .await;
 
     match print_result {
-        Ok(Ok(())) => Ok(Json(serde_json::json!({
+        Ok(outcome) => Ok(Json(serde_json::json!({
             "success": true,
-            "message": format!("Weave printed: {}", pattern_list)
+            "message": outcome.message(format!("Weave printed: {}", pattern_list)),
+            "held_until": outcome.held_until(),
+            "preview": outcome.preview(),
+            "job_id": job_id,
         }))),
-        Ok(Err(e)) => Err((
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(serde_json::json!({"success": false, "error": format!("Print failed: {}", e)})),
-        )),
         Err(e) => Err((
             StatusCode::INTERNAL_SERVER_ERROR,
-            Json(serde_json::json!({"success": false, "error": format!("Task error: {}", e)})),
+            Json(serde_json::json!({"success": false, "error": format!("Print failed: {}", e)})),
         )),
     }
 }
@@ -0,0 +1,1331 @@
+//! IPP: a printer desktops can add from their print dialogs.
+//!
+//! With `serve --ipp` the server also answers the Internet Printing
+//! Protocol at `ipp://HOST:PORT/ipp/print`, so macOS, Linux (CUPS) and
+//! Windows can add it as a printer by URI and print from any app. What
+//! arrives is converted to documents and printed like any other job:
+//!
+//! | Format | Printed as |
+//! |--------|------------|
+//! | `text/plain` | A `text` component per page (pages split at form feeds) |
+//! | `image/png`, `image/jpeg` | An `image` component, fit to the print width |
+//! | `image/pwg-raster` | An `image` per page, 1:1 when sent at the printer's resolution |
+//! | `application/pdf` | An `image` per page, margins trimmed (with the `pdf` feature) |
+//! | `application/octet-stream` | Whichever of the above the data looks like |
+//!
+//! Every page is cut. Jobs show up in the job history (source `ipp`),
+//! respect quiet hours and, without a printer, are only previewed.
+//!
+//! Only the operations a print dialog needs are spoken: Print-Job,
+//! Validate-Job, Get-Printer-Attributes, Get-Jobs, Get-Job-Attributes and
+//! Cancel-Job (which can't stop a job already sent). There is no DNS-SD
+//! announcement, so the printer is added by its URI.
+
+use axum::body::Bytes;
+use axum::extract::State;
+use axum::http::{HeaderMap, StatusCode, header};
+use axum::response::{IntoResponse, Response};
+use image::{DynamicImage, GrayImage, imageops};
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::RwLock;
+
+use crate::document::{Component, Document, Image, Text};
+use crate::job::JobId;
+use crate::printer::PrinterConfig;
+use crate::render::exif;
+
+use super::handlers::print_documents;
+use super::state::{AppState, JOB_HISTORY_LEN};
+use message::{Attribute, Message, Value};
+
+/// Path of the printer, as in `ipp://HOST:PORT/ipp/print`.
+pub const PATH: &str = "/ipp/print";
+
+// Operations
+const PRINT_JOB: u16 = 0x0002;
+const VALIDATE_JOB: u16 = 0x0004;
+const CANCEL_JOB: u16 = 0x0008;
+const GET_JOB_ATTRIBUTES: u16 = 0x0009;
+const GET_JOBS: u16 = 0x000A;
+const GET_PRINTER_ATTRIBUTES: u16 = 0x000B;
+
+// Status codes
+const OK: u16 = 0x0000;
+const BAD_REQUEST: u16 = 0x0400;
+const NOT_POSSIBLE: u16 = 0x0404;
+const NOT_FOUND: u16 = 0x0406;
+const DOCUMENT_FORMAT_NOT_SUPPORTED: u16 = 0x040A;
+const COMPRESSION_NOT_SUPPORTED: u16 = 0x040F;
+const DOCUMENT_FORMAT_ERROR: u16 = 0x0411;
+const INTERNAL_ERROR: u16 = 0x0500;
+const OPERATION_NOT_SUPPORTED: u16 = 0x0501;
+const VERSION_NOT_SUPPORTED: u16 = 0x0503;
+const DEVICE_ERROR: u16 = 0x0504;
+
+// Job states
+const PENDING_HELD: i32 = 4;
+const CANCELED: i32 = 7;
+const ABORTED: i32 = 8;
+const COMPLETED: i32 = 9;
+
+/// `document-format-supported`, default first.
+const FORMATS: &[&str] = &[
+    "application/octet-stream",
+    "text/plain",
+    "image/png",
+    "image/jpeg",
+    "image/pwg-raster",
+    #[cfg(feature = "pdf")]
+    "application/pdf",
+];
+
+/// Kinds of document a job can carry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Text,
+    Image,
+    PwgRaster,
+    #[cfg(feature = "pdf")]
+    Pdf,
+}
+
+impl Format {
+    /// The format of a `document-format` MIME type, ignoring parameters
+    /// such as `charset`. `application/octet-stream` is told from `data`.
+    fn detect(mime: &str, data: &[u8]) -> Option<Self> {
+        let mime = mime.split(';').next().unwrap_or_default().trim();
+        match mime.to_ascii_lowercase().as_str() {
+            "text/plain" => Some(Self::Text),
+            "image/png" | "image/jpeg" => Some(Self::Image),
+            "image/pwg-raster" => Some(Self::PwgRaster),
+            #[cfg(feature = "pdf")]
+            "application/pdf" => Some(Self::Pdf),
+            "application/octet-stream" => Some(Self::sniff(data)),
+            _ => None,
+        }
+    }
+
+    /// The format `data` looks like, plain text when nothing else fits.
+    fn sniff(data: &[u8]) -> Self {
+        if data.starts_with(raster::SYNC) {
+            return Self::PwgRaster;
+        }
+        #[cfg(feature = "pdf")]
+        if data.starts_with(b"%PDF") {
+            return Self::Pdf;
+        }
+        if image::guess_format(data).is_ok() {
+            Self::Image
+        } else {
+            Self::Text
+        }
+    }
+
+    /// Convert a job's data into documents, one per page.
+    fn documents(self, data: &[u8], printer: &PrinterConfig) -> Result<Vec<Document>, String> {
+        let docs = match self {
+            Self::Text => text_documents(&String::from_utf8_lossy(data)),
+            Self::Image => {
+                let image =
+                    exif::decode(data).map_err(|e| format!("Failed to decode image: {}", e))?;
+                vec![image_document(image, None, printer)]
+            }
+            Self::PwgRaster => raster::decode(data)?
+                .into_iter()
+                .map(|page| raster_document(page, printer))
+                .collect(),
+            #[cfg(feature = "pdf")]
+            Self::Pdf => crate::document::pdf::documents(data, &Default::default(), printer)
+                .map_err(|e| e.to_string())?,
+        };
+        if docs.is_empty() {
+            return Err("Nothing to print".to_string());
+        }
+        Ok(docs)
+    }
+}
+
+/// A document per page of plain text, pages split at form feeds.
+fn text_documents(text: &str) -> Vec<Document> {
+    text.replace("\r\n", "\n")
+        .replace('\t', "    ")
+        .split('\x0c')
+        .map(|page| page.trim_start_matches('\n').trim_end())
+        .filter(|page| !page.is_empty())
+        .map(|page| Document {
+            document: vec![Component::Text(Text::new(page))],
+            // Braces in a text file aren't placeholders
+            interpolate: false,
+            ..Document::default()
+        })
+        .collect()
+}
+
+/// A document printing `image` at `width` dots (the print width if `None`).
+fn image_document(image: DynamicImage, width: Option<usize>, printer: &PrinterConfig) -> Document {
+    let mut component = Image {
+        width,
+        ..Image::default()
+    };
+    component.resolve_source(image, printer);
+    Document {
+        document: vec![Component::Image(component)],
+        ..Document::default()
+    }
+}
+
+/// A document printing one raster page without its blank end.
+///
+/// Pages sent at the printer's resolution print 1:1, cropped to the
+/// printable width around the center (the media's margins are blank).
+/// Others are scaled to the print width.
+fn raster_document(page: raster::Page, printer: &PrinterConfig) -> Document {
+    let mut image = page.image;
+    let height = inked_height(&image);
+    let print_width = printer.width_dots as u32;
+    if page.dpi.abs_diff(printer.dpi as u32) > 1 {
+        let image = imageops::crop_imm(&image, 0, 0, image.width(), height).to_image();
+        return image_document(DynamicImage::ImageLuma8(image), None, printer);
+    }
+    let left = image.width().saturating_sub(print_width) / 2;
+    let width = image.width().min(print_width);
+    let image = imageops::crop(&mut image, left, 0, width, height).to_image();
+    image_document(
+        DynamicImage::ImageLuma8(image),
+        Some(width as usize),
+        printer,
+    )
+}
+
+/// Rows down to the last one with ink (at least one), so a page from a
+/// dialog set to a long paper size doesn't feed blank paper.
+fn inked_height(image: &GrayImage) -> u32 {
+    image
+        .rows()
+        .rposition(|row| row.into_iter().any(|pixel| pixel.0[0] < 128))
+        .map_or(1, |last| last as u32 + 1)
+}
+
+/// Jobs received over IPP, numbered for clients that follow them by
+/// `job-id`. How each went is looked up in the job history.
+#[derive(Default)]
+pub struct IppJobs {
+    jobs: RwLock<VecDeque<IppJob>>,
+    last_number: AtomicI32,
+}
+
+#[derive(Debug, Clone)]
+struct IppJob {
+    /// The IPP `job-id`.
+    number: i32,
+    id: JobId,
+    name: String,
+    user: String,
+    /// Unix time it arrived.
+    created_at: u64,
+}
+
+impl IppJobs {
+    /// Number and remember a job, forgetting the oldest beyond
+    /// [`JOB_HISTORY_LEN`].
+    async fn add(&self, id: JobId, name: String, user: String) -> IppJob {
+        let job = IppJob {
+            number: self.last_number.fetch_add(1, Ordering::Relaxed) + 1,
+            id,
+            name,
+            user,
+            created_at: unix_now(),
+        };
+        let mut jobs = self.jobs.write().await;
+        if jobs.len() == JOB_HISTORY_LEN {
+            jobs.pop_front();
+        }
+        jobs.push_back(job.clone());
+        job
+    }
+
+    async fn get(&self, number: i32) -> Option<IppJob> {
+        let jobs = self.jobs.read().await;
+        jobs.iter().find(|job| job.number == number).cloned()
+    }
+
+    /// All remembered jobs, newest first.
+    async fn list(&self) -> Vec<IppJob> {
+        self.jobs.read().await.iter().rev().cloned().collect()
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// POST /ipp/print - Answer an IPP request.
+pub async fn handle(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    let (request, data_start) = match Message::parse(&body) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                format!("Invalid IPP request: {}", e),
+            )
+                .into_response();
+        }
+    };
+    let host = headers
+        .get(header::HOST)
+        .and_then(|host| host.to_str().ok())
+        .unwrap_or(&state.config.listen_addr)
+        .to_string();
+
+    let response = if !matches!(request.version.0, 1 | 2) {
+        Message::error(
+            &request,
+            VERSION_NOT_SUPPORTED,
+            "IPP 1.1 and 2.0 are supported",
+        )
+    } else {
+        match request.code {
+            PRINT_JOB => print_job(&state, &request, body.slice(data_start..), &host).await,
+            VALIDATE_JOB => match job_format(&request, &body[data_start..]) {
+                Ok(_) => Message::response(&request, OK),
+                Err(error) => error,
+            },
+            GET_PRINTER_ATTRIBUTES => get_printer_attributes(&state, &request, &host).await,
+            GET_JOBS => get_jobs(&state, &request, &host).await,
+            GET_JOB_ATTRIBUTES => match find_job(&state, &request).await {
+                Ok(job) => {
+                    let mut response = Message::response(&request, OK);
+                    add_job(&mut response, &state, &job, &host).await;
+                    response
+                }
+                Err(error) => error,
+            },
+            CANCEL_JOB => match find_job(&state, &request).await {
+                Ok(job) => Message::error(
+                    &request,
+                    NOT_POSSIBLE,
+                    &format!("Job {} was already sent to the printer", job.number),
+                ),
+                Err(error) => error,
+            },
+            _ => Message::error(&request, OPERATION_NOT_SUPPORTED, "Operation not supported"),
+        }
+    };
+    (
+        [(header::CONTENT_TYPE, "application/ipp")],
+        response.encode(),
+    )
+        .into_response()
+}
+
+/// The format of a job's document, or the error to answer with.
+fn job_format(request: &Message, data: &[u8]) -> Result<Format, Message> {
+    if let Some(compression) = request.string("compression")
+        && compression != "none"
+    {
+        return Err(Message::error(
+            request,
+            COMPRESSION_NOT_SUPPORTED,
+            &format!("Compression {} is not supported", compression),
+        ));
+    }
+    let mime = request
+        .string("document-format")
+        .unwrap_or("application/octet-stream");
+    Format::detect(mime, data).ok_or_else(|| {
+        Message::error(
+            request,
+            DOCUMENT_FORMAT_NOT_SUPPORTED,
+            &format!("Document format {} is not supported", mime),
+        )
+    })
+}
+
+/// Print-Job: convert the document and print it.
+async fn print_job(state: &Arc<AppState>, request: &Message, data: Bytes, host: &str) -> Message {
+    let format = match job_format(request, &data) {
+        Ok(format) => format,
+        Err(error) => return error,
+    };
+    let name = request.string("job-name").unwrap_or("Untitled").to_string();
+    let user = request
+        .string("requesting-user-name")
+        .unwrap_or("anonymous")
+        .to_string();
+
+    let printer = state.config.printer;
+    let docs = match tokio::task::spawn_blocking(move || format.documents(&data, &printer)).await {
+        Ok(Ok(docs)) => docs,
+        Ok(Err(e)) => return Message::error(request, DOCUMENT_FORMAT_ERROR, &e),
+        Err(e) => {
+            return Message::error(request, INTERNAL_ERROR, &format!("Task error: {}", e));
+        }
+    };
+    let pages = docs.len();
+    let summary = format!(
+        "\"{}\" from {} ({} page{})",
+        name,
+        user,
+        pages,
+        if pages == 1 { "" } else { "s" }
+    );
+
+    match print_documents(state, "ipp", summary, docs, false).await {
+        Ok((id, _)) => {
+            let job = state.ipp_jobs.add(id, name, user).await;
+            let mut response = Message::response(request, OK);
+            add_job(&mut response, state, &job, host).await;
+            response
+        }
+        Err(e) => Message::error(request, DEVICE_ERROR, &e.to_string()),
+    }
+}
+
+/// The job a Get-Job-Attributes or Cancel-Job request names by `job-id`
+/// or `job-uri`, or the error to answer with.
+async fn find_job(state: &AppState, request: &Message) -> Result<IppJob, Message> {
+    let number = request
+        .attribute("job-id")
+        .and_then(Attribute::integer)
+        .or_else(|| {
+            let uri = request.string("job-uri")?;
+            uri.rsplit('/').next()?.parse().ok()
+        })
+        .ok_or_else(|| Message::error(request, BAD_REQUEST, "Missing job-id"))?;
+    state
+        .ipp_jobs
+        .get(number)
+        .await
+        .ok_or_else(|| Message::error(request, NOT_FOUND, &format!("No job {}", number)))
+}
+
+/// Get-Jobs: `which-jobs` (`not-completed` by default, `completed` or
+/// `all`), newest first.
+async fn get_jobs(state: &AppState, request: &Message, host: &str) -> Message {
+    let which = request.string("which-jobs").unwrap_or("not-completed");
+    let mut response = Message::response(request, OK);
+    for job in state.ipp_jobs.list().await {
+        let (job_state, _, _) = job_state(state, &job).await;
+        let completed = job_state >= CANCELED;
+        if which == "all" || (which == "completed") == completed {
+            response.start_group(message::JOB);
+            add_job(&mut response, state, &job, host).await;
+        }
+    }
+    response
+}
+
+/// `job-state`, `job-state-reasons` and `job-state-message`: held for
+/// quiet hours, or printed or failed as the job history says.
+async fn job_state(state: &AppState, job: &IppJob) -> (i32, &'static str, String) {
+    if let Some(held) = state.held.list().await.iter().find(|h| h.id == job.id) {
+        return (
+            PENDING_HELD,
+            "job-hold-until-specified",
+            format!("Held for quiet hours until {}", held.held_until),
+        );
+    }
+    match state
+        .jobs
+        .get(&job.id)
+        .await
+        .and_then(|record| record.error)
+    {
+        Some(error) => (ABORTED, "aborted-by-system", error),
+        None => (
+            COMPLETED,
+            "job-completed-successfully",
+            format!("Printed as job {}", job.id),
+        ),
+    }
+}
+
+/// Add a job's attributes to the response's last group.
+async fn add_job(response: &mut Message, state: &AppState, job: &IppJob, host: &str) {
+    let (job_state, reason, message) = job_state(state, job).await;
+    let printer_uri = format!("ipp://{}{}", host, PATH);
+    let created = job.created_at.saturating_sub(state.boot_time).max(1);
+    let attributes = [
+        ("job-id", Value::integer(job.number)),
+        (
+            "job-uri",
+            Value::string(message::URI, &format!("{}/{}", printer_uri, job.number)),
+        ),
+        ("job-printer-uri", Value::string(message::URI, &printer_uri)),
+        ("job-name", Value::string(message::NAME, &job.name)),
+        (
+            "job-originating-user-name",
+            Value::string(message::NAME, &job.user),
+        ),
+        ("job-state", Value::enumeration(job_state)),
+        ("job-state-reasons", Value::keyword(reason)),
+        ("job-state-message", Value::string(message::TEXT, &message)),
+        ("time-at-creation", Value::integer(created as i32)),
+    ];
+    for (name, value) in attributes {
+        response.add(message::JOB, name, vec![value]);
+    }
+}
+
+/// Get-Printer-Attributes: the ones in `requested-attributes`, or all.
+async fn get_printer_attributes(state: &AppState, request: &Message, host: &str) -> Message {
+    let requested: Vec<&str> = request
+        .attribute("requested-attributes")
+        .map(Attribute::strings)
+        .unwrap_or_default();
+    let all = requested.is_empty()
+        || requested
+            .iter()
+            .any(|name| matches!(*name, "all" | "printer-description" | "job-template"));
+
+    let mut response = Message::response(request, OK);
+    for (name, values) in printer_attributes(state, host).await {
+        if all || requested.contains(&name) {
+            response.add(message::PRINTER, name, values);
+        }
+    }
+    response
+}
+
+/// Everything the printer says about itself.
+async fn printer_attributes(state: &AppState, host: &str) -> Vec<(&'static str, Vec<Value>)> {
+    use message::{CHARSET, LANGUAGE, MIME, NAME, TEXT, URI};
+
+    let printer = &state.config.printer;
+    let now = chrono::Local::now().naive_local();
+    let status = if !state.config.has_printer() {
+        "No printer configured: jobs are only previewed".to_string()
+    } else if let Some(until) = state.config.quiet_hours.and_then(|q| q.held_until(now)) {
+        format!("Quiet hours: jobs are held until {}", until.format("%H:%M"))
+    } else {
+        "Ready".to_string()
+    };
+    let paper = printer.paper_width_mm();
+    let margin = ((paper - printer.width_mm()) / 2.0 * 100.0).max(0.0) as i32;
+    let media = format!("om_receipt-{}mm_{}x297mm", paper, paper);
+    let dpi = printer.dpi as i32;
+    let up_time = unix_now().saturating_sub(state.boot_time).max(1) as i32;
+    let keywords = |words: &[&str]| words.iter().map(|w| Value::keyword(w)).collect();
+
+    vec![
+        (
+            "printer-uri-supported",
+            vec![Value::string(URI, &format!("ipp://{}{}", host, PATH))],
+        ),
+        ("uri-security-supported", keywords(&["none"])),
+        ("uri-authentication-supported", keywords(&["none"])),
+        ("printer-name", vec![Value::string(NAME, "estrella")]),
+        ("printer-info", vec![Value::string(TEXT, printer.name)]),
+        (
+            "printer-make-and-model",
+            vec![Value::string(TEXT, &format!("Star {}", printer.name))],
+        ),
+        (
+            "printer-more-info",
+            vec![Value::string(URI, &format!("http://{}/", host))],
+        ),
+        ("printer-state", vec![Value::enumeration(3)]),
+        ("printer-state-reasons", keywords(&["none"])),
+        ("printer-state-message", vec![Value::string(TEXT, &status)]),
+        ("printer-is-accepting-jobs", vec![Value::boolean(true)]),
+        (
+            "queued-job-count",
+            vec![Value::integer(state.held.list().await.len() as i32)],
+        ),
+        ("printer-up-time", vec![Value::integer(up_time)]),
+        ("ipp-versions-supported", keywords(&["1.1", "2.0"])),
+        (
+            "operations-supported",
+            [
+                PRINT_JOB,
+                VALIDATE_JOB,
+                CANCEL_JOB,
+                GET_JOB_ATTRIBUTES,
+                GET_JOBS,
+                GET_PRINTER_ATTRIBUTES,
+            ]
+            .iter()
+            .map(|&op| Value::enumeration(op as i32))
+            .collect(),
+        ),
+        ("charset-configured", vec![Value::string(CHARSET, "utf-8")]),
+        ("charset-supported", vec![Value::string(CHARSET, "utf-8")]),
+        (
+            "natural-language-configured",
+            vec![Value::string(LANGUAGE, "en")],
+        ),
+        (
+            "generated-natural-language-supported",
+            vec![Value::string(LANGUAGE, "en")],
+        ),
+        (
+            "document-format-default",
+            vec![Value::string(MIME, FORMATS[0])],
+        ),
+        (
+            "document-format-supported",
+            FORMATS.iter().map(|f| Value::string(MIME, f)).collect(),
+        ),
+        ("compression-supported", keywords(&["none"])),
+        ("pdl-override-supported", keywords(&["not-attempted"])),
+        (
+            "multiple-document-jobs-supported",
+            vec![Value::boolean(false)],
+        ),
+        (
+            "which-jobs-supported",
+            keywords(&["completed", "not-completed", "all"]),
+        ),
+        ("color-supported", vec![Value::boolean(false)]),
+        ("print-color-mode-default", keywords(&["monochrome"])),
+        ("print-color-mode-supported", keywords(&["monochrome"])),
+        ("sides-default", keywords(&["one-sided"])),
+        ("sides-supported", keywords(&["one-sided"])),
+        ("media-default", vec![Value::keyword(&media)]),
+        ("media-ready", vec![Value::keyword(&media)]),
+        (
+            "media-supported",
+            vec![
+                Value::keyword(&media),
+                Value::keyword(&format!("roll_max_{}x3000mm", paper)),
+                Value::keyword(&format!("roll_min_{}x25mm", paper)),
+            ],
+        ),
+        ("media-left-margin-supported", vec![Value::integer(margin)]),
+        ("media-right-margin-supported", vec![Value::integer(margin)]),
+        ("media-top-margin-supported", vec![Value::integer(0)]),
+        ("media-bottom-margin-supported", vec![Value::integer(0)]),
+        ("printer-resolution-default", vec![Value::resolution(dpi)]),
+        ("printer-resolution-supported", vec![Value::resolution(dpi)]),
+        (
+            "pwg-raster-document-resolution-supported",
+            vec![Value::resolution(dpi)],
+        ),
+        (
+            "pwg-raster-document-type-supported",
+            keywords(&["black_1", "sgray_8", "srgb_8"]),
+        ),
+        ("pwg-raster-document-sheet-back", keywords(&["normal"])),
+    ]
+}
+
+/// IPP messages (RFC 8010): a header, attribute groups, then the document.
+mod message {
+    /// Delimiter tags starting each attribute group.
+    pub const OPERATION: u8 = 0x01;
+    pub const JOB: u8 = 0x02;
+    const END: u8 = 0x03;
+    pub const PRINTER: u8 = 0x04;
+
+    // Value tags
+    const INTEGER: u8 = 0x21;
+    const BOOLEAN: u8 = 0x22;
+    const ENUM: u8 = 0x23;
+    const RESOLUTION: u8 = 0x32;
+    pub const TEXT: u8 = 0x41;
+    pub const NAME: u8 = 0x42;
+    pub const KEYWORD: u8 = 0x44;
+    pub const URI: u8 = 0x45;
+    pub const CHARSET: u8 = 0x47;
+    pub const LANGUAGE: u8 = 0x48;
+    pub const MIME: u8 = 0x49;
+
+    /// An IPP request or response.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct Message {
+        /// Major and minor protocol version.
+        pub version: (u8, u8),
+        /// Operation of a request, status of a response.
+        pub code: u16,
+        pub request_id: u32,
+        pub groups: Vec<Group>,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct Group {
+        pub tag: u8,
+        pub attributes: Vec<Attribute>,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct Attribute {
+        pub name: String,
+        pub values: Vec<Value>,
+    }
+
+    /// One value, kept as its tag and raw bytes.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct Value {
+        pub tag: u8,
+        pub bytes: Vec<u8>,
+    }
+
+    impl Value {
+        pub fn integer(n: i32) -> Self {
+            Self {
+                tag: INTEGER,
+                bytes: n.to_be_bytes().to_vec(),
+            }
+        }
+
+        pub fn enumeration(n: i32) -> Self {
+            Self {
+                tag: ENUM,
+                bytes: n.to_be_bytes().to_vec(),
+            }
+        }
+
+        pub fn boolean(b: bool) -> Self {
+            Self {
+                tag: BOOLEAN,
+                bytes: vec![u8::from(b)],
+            }
+        }
+
+        pub fn keyword(s: &str) -> Self {
+            Self::string(KEYWORD, s)
+        }
+
+        /// A text-like value: `TEXT`, `NAME`, `URI`, `MIME`, ...
+        pub fn string(tag: u8, s: &str) -> Self {
+            Self {
+                tag,
+                bytes: s.as_bytes().to_vec(),
+            }
+        }
+
+        /// The same resolution across and down, in dots per inch.
+        pub fn resolution(dpi: i32) -> Self {
+            let mut bytes = dpi.to_be_bytes().to_vec();
+            bytes.extend(dpi.to_be_bytes());
+            bytes.push(3);
+            Self {
+                tag: RESOLUTION,
+                bytes,
+            }
+        }
+
+        pub fn as_str(&self) -> Option<&str> {
+            std::str::from_utf8(&self.bytes).ok()
+        }
+
+        pub fn as_integer(&self) -> Option<i32> {
+            self.bytes
+                .as_slice()
+                .try_into()
+                .ok()
+                .map(i32::from_be_bytes)
+        }
+    }
+
+    impl Attribute {
+        /// The first value as a string.
+        pub fn string(&self) -> Option<&str> {
+            self.values.first()?.as_str()
+        }
+
+        /// The first value as an integer or enum.
+        pub fn integer(&self) -> Option<i32> {
+            self.values.first()?.as_integer()
+        }
+
+        /// Every value that is a string.
+        pub fn strings(&self) -> Vec<&str> {
+            self.values.iter().filter_map(Value::as_str).collect()
+        }
+    }
+
+    impl Message {
+        /// A response to `request` with `status` and the required
+        /// operation attributes.
+        pub fn response(request: &Message, status: u16) -> Self {
+            let mut response = Self {
+                version: if request.version.0 >= 2 {
+                    (2, 0)
+                } else {
+                    (1, 1)
+                },
+                code: status,
+                request_id: request.request_id,
+                groups: Vec::new(),
+            };
+            response.add(
+                OPERATION,
+                "attributes-charset",
+                vec![Value::string(CHARSET, "utf-8")],
+            );
+            response.add(
+                OPERATION,
+                "attributes-natural-language",
+                vec![Value::string(LANGUAGE, "en")],
+            );
+            response
+        }
+
+        /// A response with `status` explained by `message`.
+        pub fn error(request: &Message, status: u16, message: &str) -> Self {
+            let mut response = Self::response(request, status);
+            response.add(
+                OPERATION,
+                "status-message",
+                vec![Value::string(TEXT, message)],
+            );
+            response
+        }
+
+        /// An attribute in any group.
+        pub fn attribute(&self, name: &str) -> Option<&Attribute> {
+            self.groups
+                .iter()
+                .flat_map(|group| &group.attributes)
+                .find(|attribute| attribute.name == name)
+        }
+
+        /// The first value of an attribute, as a string.
+        pub fn string(&self, name: &str) -> Option<&str> {
+            self.attribute(name)?.string()
+        }
+
+        /// Start a new group, even if the last one has the same tag (e.g.
+        /// one job group per job).
+        pub fn start_group(&mut self, tag: u8) {
+            self.groups.push(Group {
+                tag,
+                attributes: Vec::new(),
+            });
+        }
+
+        /// Add an attribute to the last group if it has tag `group`, or to
+        /// a new one.
+        pub fn add(&mut self, group: u8, name: &str, values: Vec<Value>) {
+            if self.groups.last().map(|g| g.tag) != Some(group) {
+                self.start_group(group);
+            }
+            if let Some(last) = self.groups.last_mut() {
+                last.attributes.push(Attribute {
+                    name: name.to_string(),
+                    values,
+                });
+            }
+        }
+
+        /// Parse a message, returning it and where the document data
+        /// after it starts.
+        pub fn parse(body: &[u8]) -> Result<(Self, usize), String> {
+            let mut reader = Reader { body, pos: 0 };
+            let version = (reader.u8()?, reader.u8()?);
+            let code = u16::from_be_bytes([reader.u8()?, reader.u8()?]);
+            let request_id =
+                u32::from_be_bytes([reader.u8()?, reader.u8()?, reader.u8()?, reader.u8()?]);
+            let mut groups: Vec<Group> = Vec::new();
+            loop {
+                match reader.u8()? {
+                    END => break,
+                    tag @ 0x00..=0x0F => groups.push(Group {
+                        tag,
+                        attributes: Vec::new(),
+                    }),
+                    tag => {
+                        let name = reader.field()?;
+                        let value = Value {
+                            tag,
+                            bytes: reader.field()?.to_vec(),
+                        };
+                        let group = groups.last_mut().ok_or("attribute outside of a group")?;
+                        // An empty name adds a value to the last attribute
+                        if name.is_empty() {
+                            group
+                                .attributes
+                                .last_mut()
+                                .ok_or("value without an attribute")?
+                                .values
+                                .push(value);
+                        } else {
+                            group.attributes.push(Attribute {
+                                name: String::from_utf8_lossy(name).into_owned(),
+                                values: vec![value],
+                            });
+                        }
+                    }
+                }
+            }
+            let message = Self {
+                version,
+                code,
+                request_id,
+                groups,
+            };
+            Ok((message, reader.pos))
+        }
+
+        pub fn encode(&self) -> Vec<u8> {
+            let mut out = vec![self.version.0, self.version.1];
+            out.extend(self.code.to_be_bytes());
+            out.extend(self.request_id.to_be_bytes());
+            for group in &self.groups {
+                out.push(group.tag);
+                for attribute in &group.attributes {
+                    for (i, value) in attribute.values.iter().enumerate() {
+                        out.push(value.tag);
+                        let name = if i == 0 {
+                            attribute.name.as_bytes()
+                        } else {
+                            b""
+                        };
+                        push_field(&mut out, name);
+                        push_field(&mut out, &value.bytes);
+                    }
+                }
+            }
+            out.push(END);
+            out
+        }
+    }
+
+    fn push_field(out: &mut Vec<u8>, bytes: &[u8]) {
+        out.extend((bytes.len() as u16).to_be_bytes());
+        out.extend(bytes);
+    }
+
+    struct Reader<'a> {
+        body: &'a [u8],
+        pos: usize,
+    }
+
+    impl<'a> Reader<'a> {
+        fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+            let bytes = self
+                .body
+                .get(self.pos..self.pos + len)
+                .ok_or("message ends early")?;
+            self.pos += len;
+            Ok(bytes)
+        }
+
+        fn u8(&mut self) -> Result<u8, String> {
+            Ok(self.take(1)?[0])
+        }
+
+        /// A length-prefixed name or value.
+        fn field(&mut self) -> Result<&'a [u8], String> {
+            let len = u16::from_be_bytes([self.u8()?, self.u8()?]);
+            self.take(len as usize)
+        }
+    }
+}
+
+/// PWG raster (`image/pwg-raster`, PWG 5102.4), as sent by CUPS and IPP
+/// Everywhere clients.
+mod raster {
+    use image::{GrayImage, Luma};
+
+    /// Start of every PWG raster stream.
+    pub const SYNC: &[u8] = b"RaS2";
+
+    /// Length of each page's header.
+    const HEADER_LEN: usize = 1796;
+
+    /// Largest page accepted, in bytes of pixel data.
+    const MAX_PAGE_BYTES: usize = 64 * 1024 * 1024;
+
+    // Color spaces
+    const GRAY: u32 = 0;
+    const RGB: u32 = 1;
+    const BLACK: u32 = 3;
+    const SGRAY: u32 = 18;
+    const SRGB: u32 = 19;
+
+    /// One decoded page.
+    pub struct Page {
+        /// Luminance, 255 for white paper.
+        pub image: GrayImage,
+        /// Horizontal resolution it was rendered at.
+        pub dpi: u32,
+    }
+
+    /// Decode every page of a stream.
+    pub fn decode(data: &[u8]) -> Result<Vec<Page>, String> {
+        let mut rest = data.strip_prefix(SYNC).ok_or("not a PWG raster stream")?;
+        let mut pages = Vec::new();
+        while !rest.is_empty() {
+            let (page, used) =
+                decode_page(rest).map_err(|e| format!("page {}: {}", pages.len() + 1, e))?;
+            pages.push(page);
+            rest = &rest[used..];
+        }
+        Ok(pages)
+    }
+
+    /// Decode the page at the start of `data`, returning it and how many
+    /// bytes it took.
+    fn decode_page(data: &[u8]) -> Result<(Page, usize), String> {
+        let header = data.get(..HEADER_LEN).ok_or("header ends early")?;
+        let field = |offset: usize| {
+            u32::from_be_bytes([
+                header[offset],
+                header[offset + 1],
+                header[offset + 2],
+                header[offset + 3],
+            ])
+        };
+        let dpi = field(276);
+        let width = field(372) as usize;
+        let height = field(376) as usize;
+        let bits_per_pixel = field(388);
+        let bytes_per_line = field(392) as usize;
+        let color_space = field(400);
+
+        let channels = match (color_space, bits_per_pixel) {
+            (GRAY | SGRAY | BLACK, 1 | 8) => 1,
+            (RGB | SRGB, 24) => 3,
+            _ => {
+                return Err(format!(
+                    "{} bits per pixel in color space {} is not supported",
+                    bits_per_pixel, color_space
+                ));
+            }
+        };
+        if width == 0
+            || height == 0
+            || bytes_per_line != (width * bits_per_pixel as usize).div_ceil(8)
+            || bytes_per_line.saturating_mul(height) > MAX_PAGE_BYTES
+        {
+            return Err(format!("bad page size {}x{}", width, height));
+        }
+
+        // Runs count whole pixels, or bytes of 8 pixels at 1 bit
+        let unit = (bits_per_pixel as usize / 8).max(1);
+        let white = if color_space == BLACK { 0x00 } else { 0xFF };
+        let mut pos = HEADER_LEN;
+        let mut take = |len: usize| -> Result<&[u8], String> {
+            let bytes = data.get(pos..pos + len).ok_or("data ends early")?;
+            pos += len;
+            Ok(bytes)
+        };
+        let mut pixels = Vec::with_capacity(bytes_per_line * height);
+        let mut line = vec![0u8; bytes_per_line];
+        while pixels.len() < bytes_per_line * height {
+            let copies = take(1)?[0] as usize + 1;
+            let mut x = 0;
+            while x < bytes_per_line {
+                let control = take(1)?[0];
+                let run = match control {
+                    // The rest of the line is blank
+                    128 => bytes_per_line - x,
+                    0..=127 => (control as usize + 1) * unit,
+                    _ => (257 - control as usize) * unit,
+                };
+                let target = line.get_mut(x..x + run).ok_or("run overflows the line")?;
+                match control {
+                    128 => target.fill(white),
+                    0..=127 => {
+                        let pixel = take(unit)?;
+                        for chunk in target.chunks_mut(unit) {
+                            chunk.copy_from_slice(pixel);
+                        }
+                    }
+                    _ => target.copy_from_slice(take(run)?),
+                }
+                x += run;
+            }
+            let remaining = height - pixels.len() / bytes_per_line;
+            for _ in 0..copies.min(remaining) {
+                pixels.extend_from_slice(&line);
+            }
+        }
+
+        let luma = |x: usize, y: usize| -> u8 {
+            let row = &pixels[y * bytes_per_line..(y + 1) * bytes_per_line];
+            match (bits_per_pixel, channels) {
+                (1, _) => {
+                    let set = row[x / 8] & (0x80 >> (x % 8)) != 0;
+                    // Set bits are ink in black, paper in gray
+                    if set == (color_space == BLACK) {
+                        0
+                    } else {
+                        255
+                    }
+                }
+                (_, 1) if color_space == BLACK => 255 - row[x],
+                (_, 1) => row[x],
+                _ => {
+                    let [r, g, b] = [row[x * 3], row[x * 3 + 1], row[x * 3 + 2]].map(u32::from);
+                    ((r * 299 + g * 587 + b * 114) / 1000) as u8
+                }
+            }
+        };
+        let image = GrayImage::from_fn(width as u32, height as u32, |x, y| {
+            Luma([luma(x as usize, y as usize)])
+        });
+        Ok((Page { image, dpi }, pos))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::ServerConfig;
+
+    /// A request with operation attributes, as a client sends it.
+    fn request(operation: u16, attributes: &[(u8, &str, &str)]) -> Vec<u8> {
+        let mut message = Message {
+            version: (2, 0),
+            code: operation,
+            request_id: 7,
+            groups: Vec::new(),
+        };
+        for &(tag, name, value) in attributes {
+            message.add(message::OPERATION, name, vec![Value::string(tag, value)]);
+        }
+        message.encode()
+    }
+
+    /// A one-page PWG raster stream in `black_1`.
+    fn raster_stream(width: u32, height: u32, lines: &[u8]) -> Vec<u8> {
+        let mut header = vec![0u8; 1796];
+        for (offset, value) in [
+            (276, 203),
+            (280, 203),
+            (372, width),
+            (376, height),
+            (384, 1),
+            (388, 1),
+            (392, width.div_ceil(8)),
+            (400, 3),
+        ] {
+            header[offset..offset + 4].copy_from_slice(&u32::to_be_bytes(value));
+        }
+        let mut stream = raster::SYNC.to_vec();
+        stream.extend(header);
+        stream.extend(lines);
+        stream
+    }
+
+    #[test]
+    fn test_message_round_trip() {
+        let mut message = Message::response(
+            &Message {
+                version: (1, 1),
+                code: GET_PRINTER_ATTRIBUTES,
+                request_id: 42,
+                groups: Vec::new(),
+            },
+            OK,
+        );
+        message.add(
+            message::PRINTER,
+            "document-format-supported",
+            vec![
+                Value::string(message::MIME, "text/plain"),
+                Value::string(message::MIME, "image/png"),
+            ],
+        );
+        message.add(
+            message::PRINTER,
+            "printer-state",
+            vec![Value::enumeration(3)],
+        );
+        let mut bytes = message.encode();
+        assert_eq!(&bytes[..8], &[1, 1, 0, 0, 0, 0, 0, 42]);
+        bytes.extend(b"document");
+
+        let (parsed, data_start) = Message::parse(&bytes).unwrap();
+        assert_eq!(parsed, message);
+        assert_eq!(&bytes[data_start..], b"document");
+        assert_eq!(
+            parsed
+                .attribute("document-format-supported")
+                .unwrap()
+                .strings(),
+            ["text/plain", "image/png"]
+        );
+        assert_eq!(
+            parsed.attribute("printer-state").unwrap().integer(),
+            Some(3)
+        );
+    }
+
+    #[test]
+    fn test_truncated_message_is_an_error() {
+        let bytes = request(PRINT_JOB, &[(message::NAME, "job-name", "hello")]);
+        assert!(Message::parse(&bytes[..bytes.len() - 3]).is_err());
+    }
+
+    #[test]
+    fn test_format_detection() {
+        let png = b"\x89PNG\r\n\x1a\n";
+        assert_eq!(
+            Format::detect("text/plain; charset=utf-8", b""),
+            Some(Format::Text)
+        );
+        assert_eq!(
+            Format::detect("application/octet-stream", png),
+            Some(Format::Image)
+        );
+        assert_eq!(
+            Format::detect("application/octet-stream", b"RaS2..."),
+            Some(Format::PwgRaster)
+        );
+        assert_eq!(
+            Format::detect("application/octet-stream", b"hello"),
+            Some(Format::Text)
+        );
+        #[cfg(feature = "pdf")]
+        assert_eq!(
+            Format::detect("application/octet-stream", b"%PDF-1.7"),
+            Some(Format::Pdf)
+        );
+        assert_eq!(Format::detect("application/postscript", b""), None);
+    }
+
+    #[test]
+    fn test_text_pages_split_at_form_feeds() {
+        let docs = text_documents("Page one\r\n\tindented\r\n\x0c\n\nPage two\n\n\x0c  \n");
+        assert_eq!(docs.len(), 2);
+        let Component::Text(text) = &docs[0].document[0] else {
+            panic!("expected text");
+        };
+        assert_eq!(text.content, "Page one\n    indented");
+        assert!(!docs[0].interpolate);
+    }
+
+    #[test]
+    fn test_decode_pwg_raster() {
+        // Line 1: 8 black pixels then white. Line 2, twice: white, then
+        // 8 black pixels
+        let stream = raster_stream(16, 3, &[0x00, 0x00, 0xFF, 0x80, 0x01, 0xFF, 0x00, 0xFF]);
+        let pages = raster::decode(&stream).unwrap();
+        assert_eq!(pages.len(), 1);
+        let image = &pages[0].image;
+        assert_eq!(image.dimensions(), (16, 3));
+        assert_eq!(pages[0].dpi, 203);
+        assert_eq!(image.get_pixel(0, 0).0, [0]);
+        assert_eq!(image.get_pixel(8, 0).0, [255]);
+        for y in 1..3 {
+            assert_eq!(image.get_pixel(0, y).0, [255]);
+            assert_eq!(image.get_pixel(15, y).0, [0]);
+        }
+    }
+
+    #[test]
+    fn test_bad_pwg_raster_is_an_error() {
+        // A literal run of 3 bytes overflows a 2-byte line
+        let stream = raster_stream(16, 1, &[0x00, 0xFE, 0xFF, 0xFF, 0xFF]);
+        assert!(raster::decode(&stream).is_err());
+        assert!(raster::decode(b"RaS2").unwrap().is_empty());
+        assert!(raster::decode(b"%PDF-1.7").is_err());
+    }
+
+    #[test]
+    fn test_raster_page_is_cropped_to_printable_width() {
+        let printer = PrinterConfig::TSP650II;
+        // A full 80mm page with ink in the middle of the first rows only
+        let mut image = GrayImage::from_pixel(640, 400, image::Luma([255]));
+        for y in 0..10 {
+            image.put_pixel(320, y, image::Luma([0]));
+        }
+        let doc = raster_document(raster::Page { image, dpi: 203 }, &printer);
+        let Component::Image(image) = &doc.document[0] else {
+            panic!("expected image");
+        };
+        let resolved = image.resolved_data.as_ref().unwrap();
+        assert_eq!(resolved.width, printer.width_dots);
+        assert_eq!(resolved.height, 10);
+    }
+
+    #[tokio::test]
+    async fn test_print_job_without_printer() {
+        let state = Arc::new(
+            AppState::new(
+                ServerConfig {
+                    device_path: ServerConfig::NO_PRINTER.to_string(),
+                    open_transport: crate::transport::open_async,
+                    printer: PrinterConfig::TSP650II,
+                    listen_addr: "127.0.0.1:0".to_string(),
+                    startup_print: false,
+                    shortener: None,
+                    hook_commands: Vec::new(),
+                    hook_timeout: crate::document::CommandHook::DEFAULT_TIMEOUT,
+                    storage: Default::default(),
+                    dither: Default::default(),
+                    location: None,
+                    image_sources: Default::default(),
+                    quiet_hours: None,
+                    ipp: true,
+                    #[cfg(feature = "mqtt")]
+                    mqtt: None,
+                },
+                Vec::new(),
+            )
+            .unwrap(),
+        );
+        let mut headers = HeaderMap::new();
+        headers.insert(header::HOST, "printer.local:8080".parse().unwrap());
+        let send = |body: Vec<u8>| {
+            let state = state.clone();
+            let headers = headers.clone();
+            async move {
+                let response = handle(State(state), headers, Bytes::from(body)).await;
+                let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                    .await
+                    .unwrap();
+                Message::parse(&body).unwrap().0
+            }
+        };
+
+        let attributes = send(request(
+            GET_PRINTER_ATTRIBUTES,
+            &[(
+                message::KEYWORD,
+                "requested-attributes",
+                "printer-uri-supported",
+            )],
+        ))
+        .await;
+        assert_eq!(attributes.code, OK);
+        assert_eq!(
+            attributes.string("printer-uri-supported"),
+            Some("ipp://printer.local:8080/ipp/print")
+        );
+        assert!(attributes.attribute("printer-name").is_none());
+
+        let mut print = request(
+            PRINT_JOB,
+            &[
+                (message::NAME, "job-name", "notes.txt"),
+                (message::MIME, "document-format", "text/plain"),
+            ],
+        );
+        print.extend(b"Hello from the print dialog\n");
+        let printed = send(print).await;
+        assert_eq!(printed.code, OK);
+        assert_eq!(printed.attribute("job-id").unwrap().integer(), Some(1));
+        assert_eq!(
+            printed.attribute("job-state").unwrap().integer(),
+            Some(COMPLETED)
+        );
+        let record = &state.jobs.list().await[0];
+        assert_eq!(record.source, "ipp");
+        assert_eq!(record.summary, "\"notes.txt\" from anonymous (1 page)");
+
+        let unsupported = send(request(
+            PRINT_JOB,
+            &[(message::MIME, "document-format", "application/postscript")],
+        ))
+        .await;
+        assert_eq!(unsupported.code, DOCUMENT_FORMAT_NOT_SUPPORTED);
+
+        let missing = send(request(CANCEL_JOB, &[])).await;
+        assert_eq!(missing.code, BAD_REQUEST);
+    }
+}
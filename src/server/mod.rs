@@ -9,22 +9,69 @@
 //! ```
 //!
 //! Then open http://localhost:8080 in a browser to access the UI.
+//!
+//! Pass `--startup-print` to print a status receipt with the server's
+//! address and a QR code to the UI when it boots.
+//!
+//! With `--ipp`, desktops can add the server as a printer at
+//! `ipp://HOST:PORT/ipp/print` and print text, images and PWG raster from
+//! any app.
+//!
+//! With `--quiet-hours 22:00-07:00`, prints arriving at night wait until
+//! morning unless the request passes `?urgent=true`.
+//!
+//! Saved state goes through a pluggable [`Storage`] backend: memory by
+//! default, JSON files with `--data-dir`, or any backend a library user
+//! passes as [`StorageConfig::Custom`].
+//!
+//! `--device none` runs without a printer, e.g. for a demo or for working
+//! on templates away from the hardware: print endpoints compile the job
+//! and answer with its preview (a PNG data URI in `preview`) and a "no
+//! printer configured" message instead of a transport error.
+//!
+//! `GET /metrics` serves compile, optimize, codegen and preview timing
+//! histograms for Prometheus (see [`crate::metrics`]).
+//!
+//! Handlers answer bad input with a 4xx and a message, never a panic. Should
+//! one panic anyway, the request gets a 500 with the panic message and the
+//! server keeps running.
+
+#![deny(clippy::unwrap_used, clippy::expect_used)]
 
 mod handlers;
+mod ipp;
+#[cfg(feature = "mqtt")]
+pub mod mqtt;
+mod quiet;
+mod schedule;
 mod state;
 mod static_files;
+mod storage;
 
-pub use state::{CachedIntensity, IntensityCacheKey, PhotoSession, ServerConfig};
+pub use crate::render::context::{CachedIntensity, IntensityCacheKey, PhotoSession};
+pub use quiet::QuietHours;
+pub use schedule::{Cron, Missed, Schedule};
+pub use state::{DitherDefaults, ServerConfig};
+pub use storage::{FileStorage, MemoryStorage, Storage, StorageConfig, check_key};
 
 use axum::{
-    Router,
+    Json, Router,
     extract::DefaultBodyLimit,
+    http::StatusCode,
+    response::{IntoResponse, Response},
     routing::{get, post},
 };
+use std::any::Any;
+use std::net::{IpAddr, UdpSocket};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
+use tower_http::catch_panic::CatchPanicLayer;
 
+use crate::document::DocumentHook;
 use crate::error::EstrellaError;
+use crate::metrics;
+use crate::receipt;
+use schedule::{Due, RunOutcome};
 use state::{AppState, SESSION_EXPIRATION_SECS};
 
 /// Start the HTTP server.
@@ -32,12 +79,28 @@ use state::{AppState, SESSION_EXPIRATION_SECS};
 /// ## Example
 ///
 /// ```no_run
+/// use estrella::printer::PrinterConfig;
 /// use estrella::server::{serve, ServerConfig};
+/// use estrella::transport;
 ///
 /// # async fn example() -> Result<(), estrella::error::EstrellaError> {
 /// let config = ServerConfig {
 ///     device_path: "/dev/rfcomm0".to_string(),
+///     open_transport: transport::open_async,
+///     printer: PrinterConfig::TSP650II,
 ///     listen_addr: "0.0.0.0:8080".to_string(),
+///     startup_print: false,
+///     shortener: None,
+///     hook_commands: Vec::new(),
+///     hook_timeout: std::time::Duration::from_secs(10),
+///     storage: Default::default(),
+///     dither: Default::default(),
+///     location: None,
+///     image_sources: Default::default(),
+///     quiet_hours: None,
+///     ipp: false,
+/// #   #[cfg(feature = "mqtt")]
+/// #   mqtt: None,
 /// };
 ///
 /// serve(config).await?;
@@ -45,31 +108,104 @@ use state::{AppState, SESSION_EXPIRATION_SECS};
 /// # }
 /// ```
 pub async fn serve(config: ServerConfig) -> Result<(), EstrellaError> {
-    let app_state = Arc::new(AppState::new(config.clone()));
+    serve_with_hooks(config, Vec::new()).await
+}
+
+/// Start the HTTP server with Rust document hooks.
+///
+/// Each hook transforms incoming documents before they are compiled (e.g.
+/// appending a footer or enforcing a theme). They run before any
+/// `config.hook_commands`.
+pub async fn serve_with_hooks(
+    config: ServerConfig,
+    hooks: Vec<Box<dyn DocumentHook>>,
+) -> Result<(), EstrellaError> {
+    let app_state = Arc::new(AppState::new(config.clone(), hooks)?);
+    metrics::enable();
 
     // Spawn background cache cleanup task
     tokio::spawn(cleanup_caches(app_state.clone()));
+    tokio::spawn(run_schedules(app_state.clone()));
+    #[cfg(feature = "mqtt")]
+    if let Some(mqtt) = config.mqtt.clone() {
+        tokio::spawn(mqtt::run(app_state.clone(), mqtt));
+    }
+    if let Some(quiet_hours) = config.quiet_hours {
+        tokio::spawn(release_after_quiet_hours(app_state.clone(), quiet_hours));
+    }
 
-    let app = Router::new()
+    let mut routes = Router::new()
         // Frontend
         .route("/", get(static_files::index_handler))
         .route("/assets/{*path}", get(static_files::asset_handler))
+        // Prometheus
+        .route("/metrics", get(handlers::metrics::metrics))
         // JSON API
         .route("/api/json/preview", post(handlers::json_api::preview))
+        .route(
+            "/api/json/preview.svg",
+            post(handlers::json_api::preview_svg),
+        )
+        .route(
+            "/api/json/preview/page/{page}",
+            post(handlers::json_api::preview_page),
+        )
+        .route("/api/json/compile", post(handlers::json_api::compile))
         .route("/api/json/print", post(handlers::json_api::print))
         .route(
             "/api/json/canvas-layout",
             post(handlers::json_api::canvas_layout),
         )
+        .route(
+            "/api/json/documents/{id}",
+            get(handlers::json_api::saved_document),
+        )
         .route(
             "/api/json/component/{type}/default",
             get(handlers::json_api::component_default),
         )
+        // Templates
+        .route("/api/templates", get(handlers::templates::list))
+        .route(
+            "/api/templates/{name}",
+            get(handlers::templates::get)
+                .post(handlers::templates::save)
+                .delete(handlers::templates::delete),
+        )
+        .route(
+            "/api/templates/{name}/print",
+            post(handlers::templates::print),
+        )
+        // Schedules
+        .route(
+            "/api/schedules",
+            get(handlers::schedules::list).post(handlers::schedules::create),
+        )
+        .route(
+            "/api/schedules/{id}",
+            get(handlers::schedules::get)
+                .put(handlers::schedules::update)
+                .delete(handlers::schedules::delete),
+        )
+        // Job history
+        .route("/api/jobs", get(handlers::jobs::list))
+        .route("/api/jobs/held", get(handlers::jobs::held))
+        .route("/api/jobs/held/release", post(handlers::jobs::release))
+        .route("/api/jobs/{id}", get(handlers::jobs::get))
+        .route("/api/jobs/{id}/reprint", post(handlers::jobs::reprint))
+        .route(
+            "/api/jobs/{id}/gift-receipt",
+            post(handlers::jobs::gift_receipt),
+        )
         // Receipt API
         .route("/api/receipt/print", post(handlers::receipt::print))
         .route("/api/receipt/preview", post(handlers::receipt::preview))
         // Pattern API
         .route("/api/patterns", get(handlers::patterns::list))
+        .route(
+            "/api/patterns/categories",
+            get(handlers::patterns::categories),
+        )
         .route(
             "/api/patterns/{name}/params",
             get(handlers::patterns::params),
@@ -95,12 +231,48 @@ pub async fn serve(config: ServerConfig) -> Result<(), EstrellaError> {
             post(handlers::photo::upload).layer(DefaultBodyLimit::max(50 * 1024 * 1024)),
         )
         .route("/api/photo/{id}/preview", get(handlers::photo::preview))
-        .route("/api/photo/{id}/print", post(handlers::photo::print))
+        .route("/api/photo/{id}/print", post(handlers::photo::print));
+    #[cfg(feature = "html")]
+    {
+        // HTML may carry its images as data URIs
+        routes = routes.route(
+            "/api/html/print",
+            post(handlers::html::print).layer(DefaultBodyLimit::max(
+                crate::document::DEFAULT_MAX_IMAGE_BYTES,
+            )),
+        );
+    }
+    #[cfg(feature = "pdf")]
+    {
+        routes = routes.route(
+            "/api/pdf/print",
+            post(handlers::pdf::print).layer(DefaultBodyLimit::max(50 * 1024 * 1024)),
+        );
+    }
+    if config.ipp {
+        // Rasterized pages are big
+        routes = routes.route(
+            ipp::PATH,
+            post(ipp::handle).layer(DefaultBodyLimit::max(50 * 1024 * 1024)),
+        );
+    }
+    let app = routes
+        .layer(CatchPanicLayer::custom(panic_response))
         .with_state(app_state);
 
     println!("Estrella HTTP server starting...");
     println!("Listening on: {}", config.listen_addr);
-    println!("Printer device: {}", config.device_path);
+    if config.has_printer() {
+        println!(
+            "Printer device: {} ({})",
+            config.device_path, config.printer.name
+        );
+    } else {
+        println!(
+            "No printer configured: print requests return a preview ({})",
+            config.printer.name
+        );
+    }
     println!();
     println!(
         "Open http://{}/ in your browser to print",
@@ -114,6 +286,29 @@ pub async fn serve(config: ServerConfig) -> Result<(), EstrellaError> {
             EstrellaError::Transport(format!("Failed to bind to {}: {}", config.listen_addr, e))
         })?;
 
+    if let Some(quiet_hours) = config.quiet_hours {
+        println!("Quiet hours: {} (urgent jobs print anyway)", quiet_hours);
+    }
+    if config.ipp {
+        println!(
+            "IPP printer: ipp://{}{} (add it by URI in the OS printer settings)",
+            config.listen_addr,
+            ipp::PATH
+        );
+    }
+
+    if config.startup_print
+        && config.has_printer()
+        && let Ok(addr) = listener.local_addr()
+    {
+        let ip = if addr.ip().is_unspecified() {
+            local_ip()
+        } else {
+            Some(addr.ip())
+        };
+        tokio::spawn(print_startup_receipt(config.clone(), ip, addr.port()));
+    }
+
     axum::serve(listener, app)
         .await
         .map_err(|e| EstrellaError::Transport(format!("Server error: {}", e)))?;
@@ -121,6 +316,130 @@ pub async fn serve(config: ServerConfig) -> Result<(), EstrellaError> {
     Ok(())
 }
 
+/// Print the startup status receipt. Failures are logged, never fatal.
+async fn print_startup_receipt(config: ServerConfig, ip: Option<IpAddr>, port: u16) {
+    let now = chrono::Local::now().naive_local();
+    if let Some(until) = config.quiet_hours.and_then(|q| q.held_until(now)) {
+        println!(
+            "[startup] Skipped status receipt: quiet hours until {}",
+            until.format("%H:%M")
+        );
+        return;
+    }
+    let hostname = hostname().unwrap_or_else(|| "unknown".to_string());
+    let ip = ip.map_or_else(|| "unknown".to_string(), |ip| ip.to_string());
+    let url = format!("http://{}:{}/", ip, port);
+    let program =
+        receipt::status_receipt_doc(&hostname, &ip, &url).compile_with_config(&config.printer);
+
+    let print_result = async {
+        let mut transport = config.open_printer().await?;
+        transport.send_program(&program, &config.printer).await?;
+        transport.flush().await
+    }
+    .await;
+
+    match print_result {
+        Ok(()) => println!("[startup] Printed status receipt ({})", url),
+        Err(e) => eprintln!("[startup] Status receipt failed: {}", e),
+    }
+}
+
+/// Hostname of this machine.
+fn hostname() -> Option<String> {
+    let mut buf = [0u8; 256];
+    let result = unsafe { libc::gethostname(buf.as_mut_ptr() as *mut libc::c_char, buf.len()) };
+    if result != 0 {
+        return None;
+    }
+    let len = buf.iter().position(|&b| b == 0).unwrap_or(buf.len());
+    String::from_utf8(buf[..len].to_vec()).ok()
+}
+
+/// Best-guess LAN address: the source IP the OS would use for outbound traffic.
+///
+/// Connecting a UDP socket sends no packets; it only selects a route.
+fn local_ip() -> Option<IpAddr> {
+    let socket = UdpSocket::bind("0.0.0.0:0").ok()?;
+    socket.connect("8.8.8.8:80").ok()?;
+    socket.local_addr().ok().map(|addr| addr.ip())
+}
+
+/// Answer a request whose handler panicked with a 500 and the panic message,
+/// instead of dropping the connection.
+fn panic_response(panic: Box<dyn Any + Send + 'static>) -> Response {
+    let message = panic
+        .downcast_ref::<String>()
+        .map(String::as_str)
+        .or_else(|| panic.downcast_ref::<&str>().copied())
+        .unwrap_or("unknown panic");
+    eprintln!("[server] Handler panicked: {}", message);
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(serde_json::json!({
+            "success": false,
+            "error": format!("Internal error: {}", message),
+        })),
+    )
+        .into_response()
+}
+
+/// Background task that prints the jobs held for quiet hours once they end.
+async fn release_after_quiet_hours(state: Arc<AppState>, quiet_hours: QuietHours) {
+    let mut interval = tokio::time::interval(Duration::from_secs(60));
+
+    loop {
+        interval.tick().await;
+        let now = chrono::Local::now().naive_local();
+        if quiet_hours.contains(now.time()) {
+            continue;
+        }
+        let released = handlers::release_held_jobs(&state).await;
+        if released > 0 {
+            println!("[quiet] Quiet hours over, printed {} held job(s)", released);
+        }
+    }
+}
+
+/// Background task printing scheduled templates when they are due.
+async fn run_schedules(state: Arc<AppState>) {
+    let mut interval = tokio::time::interval(Duration::from_secs(30));
+
+    loop {
+        interval.tick().await;
+        let schedules = match state.schedules.list().await {
+            Ok(schedules) => schedules,
+            Err(e) => {
+                eprintln!("[schedule] Failed to load schedules: {}", e);
+                continue;
+            }
+        };
+        for mut schedule in schedules {
+            let now = chrono::Local::now().naive_local();
+            let outcome = match schedule.due(now) {
+                Due::Wait => continue,
+                Due::Skip => {
+                    println!(
+                        "[schedule] {}: skipping the run missed at {}",
+                        schedule.id,
+                        schedule.next_run.unwrap_or(now).format("%Y-%m-%d %H:%M")
+                    );
+                    RunOutcome::Skipped
+                }
+                Due::Print => handlers::schedules::run(&state, &schedule).await,
+            };
+            // Deleted while it printed
+            if !matches!(state.schedules.load(&schedule.id).await, Ok(Some(_))) {
+                continue;
+            }
+            schedule.finish(now, outcome);
+            if let Err(e) = state.schedules.save(&schedule).await {
+                eprintln!("[schedule] Failed to save schedule {}: {}", schedule.id, e);
+            }
+        }
+    }
+}
+
 /// Background task to clean up expired cache entries.
 async fn cleanup_caches(state: Arc<AppState>) {
     let mut interval = tokio::time::interval(Duration::from_secs(60));
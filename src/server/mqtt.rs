@@ -0,0 +1,507 @@
+//! MQTT: print documents published to a topic, and report on the printer.
+//!
+//! With `serve --mqtt broker:1883` the server connects to an MQTT broker
+//! as a client, so Home Assistant and other automations can print without
+//! reaching the HTTP API. Topics hang off one prefix (`estrella` by
+//! default):
+//!
+//! | Topic | Direction | Payload |
+//! |-------|-----------|---------|
+//! | `estrella/print` | in | A document, as for `POST /api/json/print` |
+//! | `estrella/print/result` | out | The print response for each document received |
+//! | `estrella/jobs` | out | Every recorded job (from MQTT, HTTP or schedules) |
+//! | `estrella/server` | out, retained | `online`, `has_printer`, `held_jobs` and the `last_job`; `{"online": false}` once the server is gone |
+//!
+//! Only the parts of MQTT 3.1.1 this needs are spoken: QoS 0 publishing, a
+//! subscription, keep-alive pings and a last will for the server topic.
+//! The connection is retried with backoff for as long as the server runs,
+//! and dropped when the broker goes quiet for longer than the keep-alive.
+//!
+//! The server topic describes the server, not the printer: whether a
+//! printer is configured, not whether it has paper.
+
+use axum::Json;
+use axum::extract::{Query, State};
+use axum::http::HeaderMap;
+use std::io;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::sync::{broadcast, mpsc};
+
+use crate::error::EstrellaError;
+
+use super::handlers::json_api::{self, PrintQuery};
+use super::handlers::{PrintFlags, response_json};
+use super::state::{AppState, JobRecord};
+
+/// Port used when the broker address doesn't name one.
+pub const DEFAULT_PORT: u16 = 1883;
+
+/// Keep-alive agreed with the broker; pings go out at half of it.
+const KEEP_ALIVE: Duration = Duration::from_secs(60);
+
+/// Silence from the broker after which the connection counts as dead (the
+/// same grace MQTT gives brokers for their clients).
+const BROKER_TIMEOUT: Duration = Duration::from_secs(KEEP_ALIVE.as_secs() * 3 / 2);
+
+/// Longest wait between reconnection attempts.
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Largest packet accepted from the broker. Documents should link their
+/// images by URL rather than inline them.
+const MAX_PACKET_LEN: usize = 16 * 1024;
+
+/// How to reach the broker and which topics to use.
+#[derive(Debug, Clone)]
+pub struct MqttConfig {
+    /// Broker address: `host`, `host:port` or `mqtt://host[:port]`.
+    pub broker: String,
+    /// Prefix of every topic, e.g. `estrella` for `estrella/print`.
+    pub topic: String,
+    /// Client id; must be unique on the broker.
+    pub client_id: String,
+    pub username: Option<String>,
+    pub password: Option<String>,
+}
+
+impl MqttConfig {
+    /// `host:port` to connect to.
+    pub fn address(&self) -> String {
+        let host = self.broker.strip_prefix("mqtt://").unwrap_or(&self.broker);
+        let host = host.trim_end_matches('/');
+        match host.rsplit_once(':') {
+            Some((_, port)) if port.parse::<u16>().is_ok() => host.to_string(),
+            _ => format!("{}:{}", host, DEFAULT_PORT),
+        }
+    }
+
+    fn topic(&self, name: &str) -> String {
+        format!("{}/{}", self.topic.trim_end_matches('/'), name)
+    }
+}
+
+/// Stay connected to the broker, reconnecting with backoff when the
+/// connection drops.
+pub async fn run(state: Arc<AppState>, config: MqttConfig) {
+    let mut backoff = Duration::from_secs(1);
+    loop {
+        let started = Instant::now();
+        match session(&state, &config).await {
+            Ok(()) => return,
+            Err(e) => eprintln!(
+                "[mqtt] {}: {}; reconnecting in {}s",
+                config.address(),
+                e,
+                backoff.as_secs()
+            ),
+        }
+        // A connection that lasted starts the backoff over
+        if started.elapsed() > MAX_BACKOFF {
+            backoff = Duration::from_secs(1);
+        }
+        tokio::time::sleep(backoff).await;
+        backoff = (backoff * 2).min(MAX_BACKOFF);
+    }
+}
+
+/// One connection: subscribe, then print what arrives and publish what
+/// happens until the connection fails.
+async fn session(state: &Arc<AppState>, config: &MqttConfig) -> Result<(), EstrellaError> {
+    let print_topic = config.topic("print");
+    let server_topic = config.topic("server");
+    let stream = TcpStream::connect(config.address()).await?;
+    let (mut reader, mut writer) = stream.into_split();
+
+    let offline = serde_json::json!({"online": false}).to_string();
+    writer
+        .write_all(&packet::connect(config, &server_topic, offline.as_bytes()))
+        .await?;
+    match packet::read(&mut reader).await? {
+        packet::Incoming::ConnAck(0) => {}
+        packet::Incoming::ConnAck(code) => {
+            return Err(EstrellaError::Transport(format!(
+                "MQTT broker refused the connection ({})",
+                packet::connack_reason(code)
+            )));
+        }
+        _ => {
+            return Err(EstrellaError::Transport(
+                "MQTT broker didn't acknowledge the connection".to_string(),
+            ));
+        }
+    }
+    writer
+        .write_all(&packet::subscribe(1, &print_topic))
+        .await?;
+    println!(
+        "[mqtt] Connected to {}, printing from {}",
+        config.address(),
+        print_topic
+    );
+
+    // Reading isn't cancel-safe, so it gets a task of its own
+    let (incoming_tx, mut incoming) = mpsc::channel(16);
+    let read_task = AbortOnDrop(tokio::spawn(async move {
+        loop {
+            let packet = packet::read(&mut reader).await;
+            let failed = packet.is_err();
+            if incoming_tx.send(packet).await.is_err() || failed {
+                break;
+            }
+        }
+    }));
+    // Results of prints, published as they finish
+    let (results_tx, mut results) = mpsc::channel::<serde_json::Value>(16);
+    let mut jobs = state.jobs.subscribe();
+    let mut ping = tokio::time::interval(KEEP_ALIVE / 2);
+    let mut last_heard = Instant::now();
+
+    writer
+        .write_all(&packet::publish(
+            &server_topic,
+            &status(state, None).await,
+            true,
+        ))
+        .await?;
+    loop {
+        tokio::select! {
+            packet = incoming.recv() => match packet {
+                Some(Ok(packet::Incoming::Publish { topic, payload, packet_id })) => {
+                    last_heard = Instant::now();
+                    if let Some(id) = packet_id {
+                        writer.write_all(&packet::puback(id)).await?;
+                    }
+                    if topic == print_topic {
+                        let state = state.clone();
+                        let results_tx = results_tx.clone();
+                        tokio::spawn(async move {
+                            let _ = results_tx.send(print(state, &payload).await).await;
+                        });
+                    }
+                }
+                Some(Ok(_)) => last_heard = Instant::now(),
+                Some(Err(e)) => return Err(e.into()),
+                None => break,
+            },
+            Some(result) = results.recv() => {
+                let topic = config.topic("print/result");
+                writer.write_all(&packet::publish(&topic, result.to_string().as_bytes(), false)).await?;
+            }
+            job = jobs.recv() => match job {
+                Ok(job) => {
+                    let payload = serde_json::to_vec(&job).unwrap_or_default();
+                    writer.write_all(&packet::publish(&config.topic("jobs"), &payload, false)).await?;
+                    let status = status(state, Some(&job)).await;
+                    writer.write_all(&packet::publish(&server_topic, &status, true)).await?;
+                }
+                Err(broadcast::error::RecvError::Lagged(_)) => {}
+                Err(broadcast::error::RecvError::Closed) => break,
+            },
+            _ = ping.tick() => {
+                if last_heard.elapsed() > BROKER_TIMEOUT {
+                    return Err(EstrellaError::Transport(format!(
+                        "MQTT broker silent for {}s",
+                        last_heard.elapsed().as_secs()
+                    )));
+                }
+                writer.write_all(&packet::PINGREQ).await?;
+            }
+        }
+    }
+    drop(read_task);
+    Ok(())
+}
+
+/// Aborts a task when dropped, so a session's reader stops with it.
+struct AbortOnDrop(tokio::task::JoinHandle<()>);
+
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+/// The retained server message.
+async fn status(state: &AppState, last_job: Option<&JobRecord>) -> Vec<u8> {
+    let last_job = match last_job {
+        Some(job) => Some(job.clone()),
+        None => state.jobs.list().await.into_iter().next(),
+    };
+    serde_json::json!({
+        "online": true,
+        "has_printer": state.config.has_printer(),
+        "device": state.config.device_path,
+        "held_jobs": state.held.list().await.len(),
+        "last_job": last_job,
+    })
+    .to_string()
+    .into_bytes()
+}
+
+/// Print a document received on the print topic, exactly like
+/// `POST /api/json/print`, and return the response.
+async fn print(state: Arc<AppState>, payload: &[u8]) -> serde_json::Value {
+    let body = match serde_json::from_slice(payload) {
+        Ok(body) => body,
+        Err(e) => {
+            return serde_json::json!({
+                "success": false,
+                "error": format!("Invalid JSON: {}", e),
+            });
+        }
+    };
+    let response = json_api::print(
+        State(state),
+        Query(PrintQuery::default()),
+        Query(PrintFlags::default()),
+        HeaderMap::new(),
+        Json(body),
+    )
+    .await;
+    response_json(response).await
+}
+
+/// The MQTT 3.1.1 packets the client sends and understands.
+mod packet {
+    use super::*;
+
+    pub const PINGREQ: [u8; 2] = [0xC0, 0x00];
+
+    const CONNECT: u8 = 0x10;
+    const CONNACK: u8 = 0x20;
+    const PUBLISH: u8 = 0x30;
+    const PUBACK: u8 = 0x40;
+    const SUBSCRIBE: u8 = 0x82;
+
+    /// A packet from the broker.
+    #[derive(Debug, PartialEq, Eq)]
+    pub enum Incoming {
+        /// Connection accepted (0) or refused.
+        ConnAck(u8),
+        /// A message; `packet_id` is set for QoS 1, which needs a PUBACK.
+        Publish {
+            topic: String,
+            payload: Vec<u8>,
+            packet_id: Option<u16>,
+        },
+        /// Anything else (SUBACK, PINGRESP, ...).
+        Other(u8),
+    }
+
+    /// Why the broker refused a connection.
+    pub fn connack_reason(code: u8) -> &'static str {
+        match code {
+            1 => "unsupported protocol version",
+            2 => "client id rejected",
+            3 => "server unavailable",
+            4 => "bad user name or password",
+            5 => "not authorized",
+            _ => "unknown reason",
+        }
+    }
+
+    fn with_header(kind: u8, body: Vec<u8>) -> Vec<u8> {
+        let mut packet = vec![kind];
+        let mut len = body.len();
+        loop {
+            let byte = (len % 128) as u8;
+            len /= 128;
+            if len == 0 {
+                packet.push(byte);
+                break;
+            }
+            packet.push(byte | 0x80);
+        }
+        packet.extend(body);
+        packet
+    }
+
+    fn push_bytes(buf: &mut Vec<u8>, bytes: &[u8]) {
+        buf.extend((bytes.len() as u16).to_be_bytes());
+        buf.extend(bytes);
+    }
+
+    /// CONNECT with a clean session and a retained last will.
+    pub fn connect(config: &MqttConfig, will_topic: &str, will: &[u8]) -> Vec<u8> {
+        // Clean session, will flag, will retain
+        let mut flags = 0x02 | 0x04 | 0x20;
+        if config.username.is_some() {
+            flags |= 0x80;
+        }
+        if config.password.is_some() {
+            flags |= 0x40;
+        }
+        let mut body = Vec::new();
+        push_bytes(&mut body, b"MQTT");
+        body.push(4);
+        body.push(flags);
+        body.extend((KEEP_ALIVE.as_secs() as u16).to_be_bytes());
+        push_bytes(&mut body, config.client_id.as_bytes());
+        push_bytes(&mut body, will_topic.as_bytes());
+        push_bytes(&mut body, will);
+        if let Some(username) = &config.username {
+            push_bytes(&mut body, username.as_bytes());
+        }
+        if let Some(password) = &config.password {
+            push_bytes(&mut body, password.as_bytes());
+        }
+        with_header(CONNECT, body)
+    }
+
+    /// SUBSCRIBE to one topic at QoS 0.
+    pub fn subscribe(packet_id: u16, topic: &str) -> Vec<u8> {
+        let mut body = packet_id.to_be_bytes().to_vec();
+        push_bytes(&mut body, topic.as_bytes());
+        body.push(0);
+        with_header(SUBSCRIBE, body)
+    }
+
+    /// PUBLISH at QoS 0.
+    pub fn publish(topic: &str, payload: &[u8], retain: bool) -> Vec<u8> {
+        let mut body = Vec::new();
+        push_bytes(&mut body, topic.as_bytes());
+        body.extend(payload);
+        with_header(PUBLISH | u8::from(retain), body)
+    }
+
+    pub fn puback(packet_id: u16) -> Vec<u8> {
+        with_header(PUBACK, packet_id.to_be_bytes().to_vec())
+    }
+
+    /// Read one packet.
+    pub async fn read(reader: &mut (impl AsyncRead + Unpin)) -> io::Result<Incoming> {
+        let header = reader.read_u8().await?;
+        let mut len = 0usize;
+        for shift in 0..4 {
+            let byte = reader.read_u8().await?;
+            len |= ((byte & 0x7f) as usize) << (7 * shift);
+            if byte & 0x80 == 0 {
+                break;
+            }
+        }
+        if len > MAX_PACKET_LEN {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("MQTT packet of {} bytes is too large", len),
+            ));
+        }
+        let mut body = vec![0; len];
+        reader.read_exact(&mut body).await?;
+        parse(header, body)
+    }
+
+    fn parse(header: u8, body: Vec<u8>) -> io::Result<Incoming> {
+        let malformed = || io::Error::new(io::ErrorKind::InvalidData, "malformed MQTT packet");
+        match header & 0xF0 {
+            CONNACK => body
+                .get(1)
+                .map(|&code| Incoming::ConnAck(code))
+                .ok_or_else(malformed),
+            PUBLISH => {
+                let qos = (header >> 1) & 0x03;
+                let topic_len = match body.get(..2) {
+                    Some(len) => u16::from_be_bytes([len[0], len[1]]) as usize,
+                    None => return Err(malformed()),
+                };
+                let topic = body.get(2..2 + topic_len).ok_or_else(malformed)?;
+                let topic = String::from_utf8_lossy(topic).into_owned();
+                let mut rest = 2 + topic_len;
+                let packet_id = match qos {
+                    0 => None,
+                    _ => {
+                        let id = body.get(rest..rest + 2).ok_or_else(malformed)?;
+                        rest += 2;
+                        Some(u16::from_be_bytes([id[0], id[1]]))
+                    }
+                };
+                Ok(Incoming::Publish {
+                    topic,
+                    payload: body[rest..].to_vec(),
+                    packet_id,
+                })
+            }
+            kind => Ok(Incoming::Other(kind)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> MqttConfig {
+        MqttConfig {
+            broker: "mqtt://broker.local".to_string(),
+            topic: "estrella/".to_string(),
+            client_id: "estrella".to_string(),
+            username: Some("ha".to_string()),
+            password: Some("secret".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_address_and_topics() {
+        assert_eq!(config().address(), "broker.local:1883");
+        let config = MqttConfig {
+            broker: "10.0.0.2:1884".to_string(),
+            ..config()
+        };
+        assert_eq!(config.address(), "10.0.0.2:1884");
+        assert_eq!(config.topic("print"), "estrella/print");
+    }
+
+    #[test]
+    fn test_connect_packet() {
+        let connect = packet::connect(&config(), "estrella/server", b"{}");
+        assert_eq!(connect[0], 0x10);
+        // Remaining length, then "MQTT" level 4
+        assert_eq!(connect[1] as usize, connect.len() - 2);
+        assert_eq!(&connect[2..9], b"\x00\x04MQTT\x04");
+        // User name, password, will retain, will, clean session
+        assert_eq!(connect[9], 0xE6);
+        assert!(connect.ends_with(b"\x00\x02ha\x00\x06secret"));
+    }
+
+    #[tokio::test]
+    async fn test_publish_round_trip() {
+        // Long enough for a two-byte remaining length
+        let payload = vec![b'x'; 300];
+        let bytes = packet::publish("estrella/print", &payload, true);
+        assert_eq!(bytes[0], 0x31);
+        assert_eq!(&bytes[1..3], &[0xBC, 0x02]);
+
+        let incoming = packet::read(&mut bytes.as_slice()).await.unwrap();
+        assert_eq!(
+            incoming,
+            packet::Incoming::Publish {
+                topic: "estrella/print".to_string(),
+                payload,
+                packet_id: None,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_qos1_publish_has_packet_id() {
+        let bytes = [0x32, 0x07, 0x00, 0x01, b't', 0x00, 0x2A, b'h', b'i'];
+        let incoming = packet::read(&mut bytes.as_slice()).await.unwrap();
+        assert_eq!(
+            incoming,
+            packet::Incoming::Publish {
+                topic: "t".to_string(),
+                payload: b"hi".to_vec(),
+                packet_id: Some(42),
+            }
+        );
+        assert_eq!(packet::puback(42), [0x40, 0x02, 0x00, 0x2A]);
+    }
+
+    #[tokio::test]
+    async fn test_oversized_packet_is_refused() {
+        let bytes = packet::publish("estrella/print", &vec![b'x'; MAX_PACKET_LEN], false);
+        let err = packet::read(&mut bytes.as_slice()).await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,125 @@
+//! Quiet hours: a nightly window when prints wait instead of running.
+//!
+//! A receipt printer's cutter is loud. With quiet hours set, jobs arriving
+//! inside the window are compiled right away (so bad input still fails
+//! fast) but held until it ends, then printed in arrival order. Requests
+//! that really can't wait pass `?urgent=true`.
+
+use chrono::{Duration, NaiveDateTime, NaiveTime};
+use std::fmt;
+use std::str::FromStr;
+
+/// A daily window, e.g. `22:00-07:00`. It may wrap past midnight.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QuietHours {
+    /// When the window opens (inclusive).
+    pub start: NaiveTime,
+    /// When it closes (exclusive).
+    pub end: NaiveTime,
+}
+
+impl QuietHours {
+    /// Whether `time` falls inside the window.
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        if self.start <= self.end {
+            self.start <= time && time < self.end
+        } else {
+            time >= self.start || time < self.end
+        }
+    }
+
+    /// When the window around `now` closes: the next `end` after `now`.
+    pub fn end_after(&self, now: NaiveDateTime) -> NaiveDateTime {
+        let today = now.date().and_time(self.end);
+        if today > now {
+            today
+        } else {
+            today + Duration::days(1)
+        }
+    }
+
+    /// If `now` is inside the window, when it closes.
+    pub fn held_until(&self, now: NaiveDateTime) -> Option<NaiveDateTime> {
+        self.contains(now.time()).then(|| self.end_after(now))
+    }
+}
+
+impl fmt::Display for QuietHours {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}-{}",
+            self.start.format("%H:%M"),
+            self.end.format("%H:%M")
+        )
+    }
+}
+
+impl FromStr for QuietHours {
+    type Err = String;
+
+    /// Parse `HH:MM-HH:MM`, in local time.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            format!(
+                "Invalid quiet hours '{}'. Use HH:MM-HH:MM, e.g. 22:00-07:00",
+                s
+            )
+        };
+        let (start, end) = s.split_once('-').ok_or_else(invalid)?;
+        let time = |t: &str| NaiveTime::parse_from_str(t.trim(), "%H:%M").map_err(|_| invalid());
+        let hours = QuietHours {
+            start: time(start)?,
+            end: time(end)?,
+        };
+        if hours.start == hours.end {
+            return Err(format!(
+                "Invalid quiet hours '{}': start and end are the same",
+                s
+            ));
+        }
+        Ok(hours)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn at(day: u32, h: u32, m: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2026, 1, day)
+            .and_then(|d| d.and_hms_opt(h, m, 0))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_window_wrapping_midnight() {
+        let night: QuietHours = "22:00-07:00".parse().unwrap();
+        assert_eq!(night.to_string(), "22:00-07:00");
+        assert!(night.contains(at(1, 23, 30).time()));
+        assert!(night.contains(at(1, 3, 0).time()));
+        assert!(!night.contains(at(1, 7, 0).time()));
+        assert!(!night.contains(at(1, 12, 0).time()));
+
+        // Before midnight, the window ends tomorrow; after, later today
+        assert_eq!(night.held_until(at(1, 23, 30)), Some(at(2, 7, 0)));
+        assert_eq!(night.held_until(at(2, 3, 0)), Some(at(2, 7, 0)));
+        assert_eq!(night.held_until(at(2, 12, 0)), None);
+    }
+
+    #[test]
+    fn test_window_within_a_day() {
+        let nap: QuietHours = "13:00-15:30".parse().unwrap();
+        assert!(nap.contains(at(1, 14, 0).time()));
+        assert!(!nap.contains(at(1, 16, 0).time()));
+        assert_eq!(nap.held_until(at(1, 13, 0)), Some(at(1, 15, 30)));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!("22:00".parse::<QuietHours>().is_err());
+        assert!("25:00-07:00".parse::<QuietHours>().is_err());
+        assert!("07:00-07:00".parse::<QuietHours>().is_err());
+    }
+}
@@ -0,0 +1,451 @@
+//! Scheduled prints: saved templates printed on a cron schedule.
+//!
+//! A [`Schedule`] prints one template (see `/api/templates`) with its own
+//! variables whenever its [`Cron`] expression comes round, e.g. a daily
+//! agenda at `30 7 * * *`. Times are local, like quiet hours.
+//!
+//! When a run can't print on time — the printer is offline, or the server
+//! was down — the schedule's [`Missed`] policy decides: `skip` drops the
+//! run and waits for the next one, `catch_up` keeps retrying every minute
+//! until it prints. Several missed runs catch up as one print.
+
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, Timelike};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// A five-field cron expression: minute, hour, day of month, month and
+/// day of week.
+///
+/// Fields take `*`, numbers, ranges (`1-5`), lists (`1,15`) and steps
+/// (`*/15`, `9-17/2`). Months and days of the week can be named (`jan`,
+/// `mon-fri`); Sunday is `0` or `7`. As in cron, when both day fields are
+/// restricted a day matching either runs, but a field starting with `*`
+/// (`*/2`) doesn't count as restricted, so then a day must match both.
+/// `@hourly`, `@daily`, `@weekly` and `@monthly` are shorthands.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Cron {
+    expr: String,
+    minutes: u64,
+    hours: u32,
+    days: u32,
+    months: u16,
+    weekdays: u8,
+    /// Whether the day-of-month field starts with `*` (`*`, `*/2`).
+    any_day: bool,
+    /// Whether the day-of-week field starts with `*`.
+    any_weekday: bool,
+}
+
+const MONTHS: [&str; 12] = [
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+const WEEKDAYS: [&str; 7] = ["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+
+/// How far ahead [`Cron::next_after`] looks. Leap days can be eight years
+/// apart (2096 to 2104).
+const SEARCH_YEARS: i64 = 9;
+
+impl Cron {
+    /// The first time after `time` (to the minute) the expression matches,
+    /// or `None` if it never does.
+    pub fn next_after(&self, time: NaiveDateTime) -> Option<NaiveDateTime> {
+        let mut t = time.with_second(0)?.with_nanosecond(0)? + Duration::minutes(1);
+        let limit = time + Duration::days(366 * SEARCH_YEARS);
+        while t <= limit {
+            if !bit(self.months as u64, t.month()) {
+                let (year, month) = match t.month() {
+                    12 => (t.year() + 1, 1),
+                    month => (t.year(), month + 1),
+                };
+                t = NaiveDate::from_ymd_opt(year, month, 1)?.and_hms_opt(0, 0, 0)?;
+            } else if !self.day_matches(t.date()) {
+                t = t.date().succ_opt()?.and_hms_opt(0, 0, 0)?;
+            } else if !bit(self.hours as u64, t.hour()) {
+                t = t.date().and_hms_opt(t.hour(), 0, 0)? + Duration::hours(1);
+            } else if !bit(self.minutes, t.minute()) {
+                t += Duration::minutes(1);
+            } else {
+                return Some(t);
+            }
+        }
+        None
+    }
+
+    fn day_matches(&self, date: NaiveDate) -> bool {
+        let day = bit(self.days as u64, date.day());
+        let weekday = bit(self.weekdays as u64, date.weekday().num_days_from_sunday());
+        if self.any_day || self.any_weekday {
+            day && weekday
+        } else {
+            day || weekday
+        }
+    }
+}
+
+fn bit(set: u64, n: u32) -> bool {
+    set & (1 << n) != 0
+}
+
+/// Parse one field into a bit set of the values in `min..=max`. `names`
+/// are accepted for the values from `min` on.
+fn parse_field(field: &str, min: u32, max: u32, names: &[&str]) -> Result<u64, String> {
+    let value = |s: &str| -> Result<u32, String> {
+        let lower = s.to_ascii_lowercase();
+        if let Some(i) = names.iter().position(|name| *name == lower) {
+            return Ok(min + i as u32);
+        }
+        match s.parse::<u32>() {
+            Ok(n) if (min..=max).contains(&n) => Ok(n),
+            _ => Err(format!("'{}' is not a value from {} to {}", s, min, max)),
+        }
+    };
+    let mut set = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => match step.parse::<u32>() {
+                Ok(step) if step > 0 => (range, step),
+                _ => return Err(format!("'{}' is not a valid step", step)),
+            },
+            None => (part, 1),
+        };
+        let (start, end) = match range {
+            "*" => (min, max),
+            _ => match range.split_once('-') {
+                Some((start, end)) => (value(start)?, value(end)?),
+                // `5/15` runs from 5 to the end
+                None if step > 1 => (value(range)?, max),
+                None => {
+                    let n = value(range)?;
+                    (n, n)
+                }
+            },
+        };
+        if start > end {
+            return Err(format!("'{}' runs backwards", range));
+        }
+        for n in (start..=end).step_by(step as usize) {
+            set |= 1 << n;
+        }
+    }
+    Ok(set)
+}
+
+impl fmt::Display for Cron {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.expr)
+    }
+}
+
+impl FromStr for Cron {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let expr = s.trim();
+        let fields: Vec<&str> = match expr {
+            "@hourly" => vec!["0", "*", "*", "*", "*"],
+            "@daily" | "@midnight" => vec!["0", "0", "*", "*", "*"],
+            "@weekly" => vec!["0", "0", "*", "*", "0"],
+            "@monthly" => vec!["0", "0", "1", "*", "*"],
+            _ => expr.split_whitespace().collect(),
+        };
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            return Err(format!(
+                "Invalid schedule '{}'. Use five cron fields (minute hour day month weekday), e.g. '30 7 * * *'",
+                s
+            ));
+        };
+        let field = |text: &str, name: &str, min, max, names: &[&str]| {
+            parse_field(text, min, max, names)
+                .map_err(|e| format!("Invalid {} in schedule '{}': {}", name, s, e))
+        };
+        let weekdays = field(weekday, "day of week", 0, 7, &WEEKDAYS)?;
+        let cron = Cron {
+            expr: expr.to_string(),
+            minutes: field(minute, "minute", 0, 59, &[])?,
+            hours: field(hour, "hour", 0, 23, &[])? as u32,
+            days: field(day, "day of month", 1, 31, &[])? as u32,
+            months: field(month, "month", 1, 12, &MONTHS)? as u16,
+            // 7 is Sunday too
+            weekdays: ((weekdays | weekdays >> 7) & 0x7f) as u8,
+            any_day: day.starts_with('*'),
+            any_weekday: weekday.starts_with('*'),
+        };
+        let start = NaiveDate::from_ymd_opt(2000, 1, 1).and_then(|d| d.and_hms_opt(0, 0, 0));
+        if start.and_then(|start| cron.next_after(start)).is_none() {
+            return Err(format!("Schedule '{}' never runs", s));
+        }
+        Ok(cron)
+    }
+}
+
+impl TryFrom<String> for Cron {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<Cron> for String {
+    fn from(cron: Cron) -> Self {
+        cron.expr
+    }
+}
+
+/// What a schedule does about a run it couldn't print on time.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Missed {
+    /// Drop it and wait for the next run.
+    #[default]
+    Skip,
+    /// Keep retrying until it prints.
+    CatchUp,
+}
+
+/// How late a run may start before a `skip` schedule drops it, e.g.
+/// because the server was down at the time.
+pub const SKIP_AFTER: Duration = Duration::minutes(5);
+
+/// A template printed on a schedule.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Schedule {
+    /// Assigned by the server.
+    #[serde(default)]
+    pub id: String,
+    /// When to print.
+    pub cron: Cron,
+    /// Name of the saved template to print.
+    pub template: String,
+    /// Variables merged over the template's, as for
+    /// `POST /api/templates/{name}/print`.
+    #[serde(default)]
+    pub variables: serde_json::Map<String, serde_json::Value>,
+    /// What to do about runs that couldn't print on time.
+    #[serde(default)]
+    pub missed: Missed,
+    /// Paused schedules keep their settings but don't print.
+    #[serde(default = "enabled")]
+    pub enabled: bool,
+    /// Local time of the next run. Set by the server.
+    #[serde(default, with = "local_time")]
+    pub next_run: Option<NaiveDateTime>,
+    /// Local time of the last print. Set by the server.
+    #[serde(default, with = "local_time")]
+    pub last_run: Option<NaiveDateTime>,
+    /// Why the last attempt failed, if it did.
+    #[serde(default)]
+    pub last_error: Option<String>,
+}
+
+fn enabled() -> bool {
+    true
+}
+
+/// Local times as `2026-01-27 07:30:00`, like the job history's.
+mod local_time {
+    use chrono::NaiveDateTime;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    const FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+    pub fn serialize<S: Serializer>(
+        time: &Option<NaiveDateTime>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match time {
+            Some(time) => serializer.collect_str(&time.format(FORMAT)),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<NaiveDateTime>, D::Error> {
+        Option::<String>::deserialize(deserializer)?
+            .map(|s| NaiveDateTime::parse_from_str(&s, FORMAT).map_err(serde::de::Error::custom))
+            .transpose()
+    }
+}
+
+/// What to do with a schedule now.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Due {
+    /// Nothing yet.
+    Wait,
+    /// Print the template.
+    Print,
+    /// Drop a run that is too late for a `skip` schedule.
+    Skip,
+}
+
+/// How a run went.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RunOutcome {
+    Printed,
+    /// Printing failed. `retry` when trying again might help (the printer
+    /// was unreachable), not when the request itself is wrong.
+    Failed {
+        error: String,
+        retry: bool,
+    },
+    Skipped,
+}
+
+impl Schedule {
+    /// Start counting runs from `now`: the next one is the first after it.
+    pub fn start(&mut self, now: NaiveDateTime) {
+        self.next_run = self.cron.next_after(now);
+        self.last_error = None;
+    }
+
+    /// Whether the schedule should print at `now`.
+    pub fn due(&self, now: NaiveDateTime) -> Due {
+        match self.next_run {
+            Some(next) if self.enabled && next <= now => {
+                if self.missed == Missed::Skip && now - next > SKIP_AFTER {
+                    Due::Skip
+                } else {
+                    Due::Print
+                }
+            }
+            _ => Due::Wait,
+        }
+    }
+
+    /// Record how the run due at `now` went and pick the next one.
+    ///
+    /// A `catch_up` schedule whose print can be retried stays due, so it
+    /// is tried again on the next check; everything else moves on to the
+    /// first run after `now`.
+    pub fn finish(&mut self, now: NaiveDateTime, outcome: RunOutcome) {
+        match outcome {
+            RunOutcome::Printed => {
+                self.last_run = Some(now);
+                self.last_error = None;
+            }
+            RunOutcome::Failed { error, retry } => {
+                self.last_error = Some(error);
+                if retry && self.missed == Missed::CatchUp {
+                    return;
+                }
+            }
+            RunOutcome::Skipped => {}
+        }
+        self.next_run = self.cron.next_after(now);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(day: u32, h: u32, m: u32) -> NaiveDateTime {
+        // January 2026 starts on a Thursday
+        NaiveDate::from_ymd_opt(2026, 1, day)
+            .and_then(|d| d.and_hms_opt(h, m, 0))
+            .unwrap()
+    }
+
+    fn next(expr: &str, after: NaiveDateTime) -> Option<NaiveDateTime> {
+        expr.parse::<Cron>().unwrap().next_after(after)
+    }
+
+    #[test]
+    fn test_next_after() {
+        assert_eq!(next("30 7 * * *", at(1, 7, 0)), Some(at(1, 7, 30)));
+        assert_eq!(next("30 7 * * *", at(1, 7, 30)), Some(at(2, 7, 30)));
+        assert_eq!(next("*/15 9-10 * * *", at(1, 9, 50)), Some(at(1, 10, 0)));
+        // Weekdays only: Friday the 2nd, then Monday the 5th
+        assert_eq!(next("0 8 * * mon-fri", at(2, 9, 0)), Some(at(5, 8, 0)));
+        assert_eq!(next("0 8 * * 7", at(2, 9, 0)), Some(at(4, 8, 0)));
+        // Either day field matches when both are set: the 15th or a Monday
+        assert_eq!(next("0 0 15 * 1", at(6, 0, 0)), Some(at(12, 0, 0)));
+        assert_eq!(next("0 0 15 * 1", at(12, 0, 0)), Some(at(15, 0, 0)));
+        // Both when one starts with `*`: Mondays on odd days
+        assert_eq!(next("0 9 */2 * 1", at(1, 0, 0)), Some(at(5, 9, 0)));
+        assert_eq!(next("0 9 */2 * 1", at(5, 9, 0)), Some(at(19, 9, 0)));
+        assert_eq!(
+            next("@monthly", at(20, 0, 0)),
+            NaiveDate::from_ymd_opt(2026, 2, 1).and_then(|d| d.and_hms_opt(0, 0, 0))
+        );
+        assert_eq!(
+            next("0 12 29 feb *", at(1, 0, 0)),
+            NaiveDate::from_ymd_opt(2028, 2, 29).and_then(|d| d.and_hms_opt(12, 0, 0))
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!("30 7 * *".parse::<Cron>().is_err());
+        assert!("60 7 * * *".parse::<Cron>().is_err());
+        assert!("0 17-9 * * *".parse::<Cron>().is_err());
+        assert!("*/0 * * * *".parse::<Cron>().is_err());
+        assert!("0 0 31 feb *".parse::<Cron>().is_err());
+        assert_eq!(
+            "30 7 * * *".parse::<Cron>().unwrap().to_string(),
+            "30 7 * * *"
+        );
+    }
+
+    fn schedule(missed: Missed) -> Schedule {
+        let mut schedule: Schedule = serde_json::from_value(serde_json::json!({
+            "cron": "30 7 * * *",
+            "template": "agenda",
+            "missed": missed,
+        }))
+        .unwrap();
+        schedule.start(at(1, 12, 0));
+        schedule
+    }
+
+    #[test]
+    fn test_skip_missed_runs() {
+        let mut daily = schedule(Missed::Skip);
+        assert_eq!(daily.next_run, Some(at(2, 7, 30)));
+        assert_eq!(daily.due(at(2, 7, 29)), Due::Wait);
+        assert_eq!(daily.due(at(2, 7, 31)), Due::Print);
+
+        // The printer is offline: wait for tomorrow
+        let offline = RunOutcome::Failed {
+            error: "no printer".into(),
+            retry: true,
+        };
+        daily.finish(at(2, 7, 31), offline);
+        assert_eq!(daily.next_run, Some(at(3, 7, 30)));
+        assert_eq!(daily.last_error.as_deref(), Some("no printer"));
+
+        // The server was down at 7:30
+        assert_eq!(daily.due(at(3, 9, 0)), Due::Skip);
+        daily.finish(at(3, 9, 0), RunOutcome::Skipped);
+        assert_eq!(daily.next_run, Some(at(4, 7, 30)));
+    }
+
+    #[test]
+    fn test_catch_up_missed_runs() {
+        let mut daily = schedule(Missed::CatchUp);
+        let offline = RunOutcome::Failed {
+            error: "no printer".into(),
+            retry: true,
+        };
+        daily.finish(at(2, 7, 30), offline);
+        assert_eq!(daily.next_run, Some(at(2, 7, 30)));
+
+        // Two days later: one print for both runs
+        assert_eq!(daily.due(at(4, 9, 0)), Due::Print);
+        daily.finish(at(4, 9, 0), RunOutcome::Printed);
+        assert_eq!(daily.last_run, Some(at(4, 9, 0)));
+        assert_eq!(daily.last_error, None);
+        assert_eq!(daily.next_run, Some(at(5, 7, 30)));
+
+        // A broken template isn't retried
+        let broken = RunOutcome::Failed {
+            error: "Unknown template 'agenda'".into(),
+            retry: false,
+        };
+        daily.finish(at(5, 7, 30), broken);
+        assert_eq!(daily.next_run, Some(at(6, 7, 30)));
+    }
+}
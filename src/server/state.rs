@@ -1,145 +1,560 @@
 //! Server state and configuration.
 
-use image::DynamicImage;
-use std::collections::HashMap;
-use std::hash::{Hash, Hasher};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::{HashMap, VecDeque};
+use std::io;
 use std::sync::Arc;
-use std::time::{Instant, SystemTime, UNIX_EPOCH};
-use tokio::sync::RwLock;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::{OnceCell, RwLock, broadcast};
+
+use crate::document::{
+    CommandHook, Document, DocumentHook, ImageSources, Location, ShortenerConfig, UrlShortener,
+};
+use crate::error::EstrellaError;
+use crate::ir::Program;
+use crate::job::JobId;
+use crate::printer::PrinterConfig;
+use crate::render::context::{CachedIntensity, IntensityCacheKey, PhotoSession};
+use crate::render::dither::DitheringAlgorithm;
+use crate::transport::{AsyncTransport, OpenAsyncTransport};
+
+use super::ipp::IppJobs;
+use super::quiet::QuietHours;
+use super::schedule::Schedule;
+use super::storage::{Storage, StorageConfig, check_key};
 
 /// Server configuration.
 #[derive(Debug, Clone)]
 pub struct ServerConfig {
-    /// Path to the printer device (e.g., "/dev/rfcomm0")
+    /// Printer device: an RFCOMM path (e.g., "/dev/rfcomm0"),
+    /// `tcp://host[:port]` for a network printer, or
+    /// [`NO_PRINTER`](Self::NO_PRINTER) to run preview-only
     pub device_path: String,
+    /// Opens `device_path` for every print. Use
+    /// [`crate::transport::open_async`] unless prints should go through a
+    /// custom transport (see [`crate::transport::Blocking`])
+    pub open_transport: OpenAsyncTransport,
+    /// Printer model: print width, cutter and codegen options for every
+    /// preview and print
+    pub printer: PrinterConfig,
     /// Address to listen on (e.g., "0.0.0.0:8080")
     pub listen_addr: String,
+    /// Print a status receipt (hostname, IP, QR to the web UI) on startup
+    pub startup_print: bool,
+    /// Shorten long QR code links through this endpoint (disabled when `None`)
+    pub shortener: Option<ShortenerConfig>,
+    /// Shell commands run as document hooks before every preview/print
+    /// (see [`crate::document::hooks`])
+    pub hook_commands: Vec<String>,
+    /// How long a hook command may run before it is killed and the print fails
+    pub hook_timeout: Duration,
+    /// Where saved state (e.g. edit-link documents) is kept
+    pub storage: StorageConfig,
+    /// Dithering for requests and components that don't choose one
+    pub dither: DitherDefaults,
+    /// Location for `{{sunrise}}`/`{{sunset}}` in documents that don't set one
+    pub location: Option<Location>,
+    /// Local directories and size limit for `file://` and `data:` images
+    pub image_sources: ImageSources,
+    /// Nightly window when non-urgent jobs wait instead of printing
+    pub quiet_hours: Option<QuietHours>,
+    /// Accept jobs from desktop print dialogs over IPP at `/ipp/print`
+    pub ipp: bool,
+    /// MQTT broker to print from and report to (disabled when `None`)
+    #[cfg(feature = "mqtt")]
+    pub mqtt: Option<super::mqtt::MqttConfig>,
 }
 
-/// Cache key for rendered intensity buffers.
-#[derive(Clone, Debug, Eq, PartialEq, Hash)]
-pub struct IntensityCacheKey {
-    /// Pattern name.
-    pub pattern: String,
-    /// Hash of pattern parameters (deterministic).
-    pub params_hash: u64,
-    /// Layer width in pixels.
-    pub width: usize,
-    /// Layer height in pixels.
-    pub height: usize,
+impl ServerConfig {
+    /// `device_path` for a server without a printer. Print requests are
+    /// compiled and answered with their preview instead of being sent.
+    pub const NO_PRINTER: &str = "none";
+
+    /// Whether a printer is configured, i.e. `device_path` isn't
+    /// [`NO_PRINTER`](Self::NO_PRINTER).
+    pub fn has_printer(&self) -> bool {
+        !self.device_path.eq_ignore_ascii_case(Self::NO_PRINTER)
+    }
+
+    /// Open the configured printer.
+    pub async fn open_printer(&self) -> Result<Box<dyn AsyncTransport>, EstrellaError> {
+        if !self.has_printer() {
+            return Err(EstrellaError::Transport(
+                "No printer configured (--device none)".to_string(),
+            ));
+        }
+        (self.open_transport)(&self.device_path).await
+    }
+
+    /// Fill in what a document leaves to the server: dithering and location.
+    pub fn apply_defaults(&self, doc: &mut Document) {
+        self.dither.apply(doc);
+        if doc.location.is_none() {
+            doc.location = self.location;
+        }
+    }
 }
 
-impl IntensityCacheKey {
-    /// Create a new cache key from layer parameters.
-    pub fn new(
-        pattern: &str,
-        params: &HashMap<String, String>,
-        width: usize,
-        height: usize,
-    ) -> Self {
+/// Default dithering algorithms, by kind of content.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DitherDefaults {
+    /// Photos and `image` components (default: Floyd-Steinberg)
+    pub photo: DitheringAlgorithm,
+    /// Patterns, weaves, textures and compositions (default: Bayer)
+    pub pattern: DitheringAlgorithm,
+}
+
+impl Default for DitherDefaults {
+    fn default() -> Self {
         Self {
-            pattern: pattern.to_string(),
-            params_hash: hash_params(params),
-            width,
-            height,
+            photo: DitheringAlgorithm::FloydSteinberg,
+            pattern: DitheringAlgorithm::Bayer,
         }
     }
 }
 
-/// Hash pattern parameters deterministically.
-fn hash_params(params: &HashMap<String, String>) -> u64 {
-    use std::collections::hash_map::DefaultHasher;
+impl DitherDefaults {
+    /// The photo default, unless `requested` names a known algorithm.
+    pub fn photo_or(&self, requested: Option<&str>) -> DitheringAlgorithm {
+        requested.and_then(|s| s.parse().ok()).unwrap_or(self.photo)
+    }
 
-    // Sort keys for deterministic ordering
-    let mut sorted: Vec<_> = params.iter().collect();
-    sorted.sort_by_key(|(k, _)| *k);
+    /// The pattern default, unless `requested` names a known algorithm.
+    pub fn pattern_or(&self, requested: Option<&str>) -> DitheringAlgorithm {
+        requested
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(self.pattern)
+    }
 
-    let mut hasher = DefaultHasher::new();
-    for (k, v) in sorted {
-        k.hash(&mut hasher);
-        v.hash(&mut hasher);
+    /// Fill in a document's components that don't choose a dithering.
+    pub fn apply(&self, doc: &mut Document) {
+        doc.set_default_dither(self.photo, self.pattern);
     }
-    hasher.finish()
 }
 
-/// Cached intensity buffer (quantized to u8 + gzip compressed).
-pub struct CachedIntensity {
-    /// Compressed intensity data (quantized u8 values, gzip compressed).
-    compressed: Vec<u8>,
-    /// Original uncompressed size (for allocation hint).
-    uncompressed_size: usize,
-    /// Last time this cache entry was accessed.
-    pub last_accessed: Instant,
+/// Documents saved at print time, so the edit-link QR on a receipt can
+/// reopen them in the web UI.
+///
+/// Documents are kept in the `documents` collection of the server's
+/// [`Storage`], so with a persistent backend links keep working across
+/// restarts.
+pub struct DocumentStore {
+    storage: Arc<dyn Storage>,
 }
 
-impl CachedIntensity {
-    /// Create from f32 intensities.
-    /// Quantizes to u8 and compresses with gzip for memory savings.
-    pub fn new(intensity: &[f32]) -> Self {
-        use flate2::Compression;
-        use flate2::write::GzEncoder;
-        use std::io::Write;
+impl DocumentStore {
+    /// Storage collection holding saved documents.
+    pub const COLLECTION: &str = "documents";
 
-        let uncompressed_size = intensity.len();
+    pub fn new(storage: Arc<dyn Storage>) -> Self {
+        Self { storage }
+    }
 
-        // Quantize f32 -> u8
-        let quantized: Vec<u8> = intensity
-            .iter()
-            .map(|&v| (v.clamp(0.0, 1.0) * 255.0).round() as u8)
-            .collect();
+    /// Save a document and return its id.
+    pub async fn save(&self, doc: &Document) -> String {
+        let id = uuid::Uuid::new_v4().simple().to_string()[..10].to_string();
+        if let Err(e) = self.storage.put_json(Self::COLLECTION, &id, doc).await {
+            eprintln!("Failed to save document {}: {}", id, e);
+        }
+        id
+    }
+
+    /// Look up a saved document.
+    pub async fn load(&self, id: &str) -> Option<Document> {
+        // Ids are generated hex; anything else must not reach the storage
+        if id.is_empty() || !id.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+        self.storage
+            .get_json(Self::COLLECTION, id)
+            .await
+            .ok()
+            .flatten()
+    }
+}
+
+/// Named documents with `{{variable}}` placeholders, so callers can print
+/// by sending just the variables.
+///
+/// Templates are kept in the `templates` collection of the server's
+/// [`Storage`] under their name, so names follow its key rules: ASCII
+/// letters, digits, `-` and `_`.
+pub struct TemplateStore {
+    storage: Arc<dyn Storage>,
+}
+
+impl TemplateStore {
+    /// Storage collection holding templates.
+    pub const COLLECTION: &str = "templates";
+
+    pub fn new(storage: Arc<dyn Storage>) -> Self {
+        Self { storage }
+    }
+
+    /// Save a template, replacing any with the same name.
+    pub async fn save(&self, name: &str, doc: &Document) -> io::Result<()> {
+        check_key(name)?;
+        self.storage.put_json(Self::COLLECTION, name, doc).await
+    }
+
+    /// Look up a template by name.
+    pub async fn load(&self, name: &str) -> io::Result<Option<Document>> {
+        check_key(name)?;
+        self.storage.get_json(Self::COLLECTION, name).await
+    }
+
+    /// Remove a template. Returns whether it existed.
+    pub async fn delete(&self, name: &str) -> io::Result<bool> {
+        check_key(name)?;
+        self.storage.delete(Self::COLLECTION, name).await
+    }
+
+    /// Names of all saved templates, sorted.
+    pub async fn names(&self) -> io::Result<Vec<String>> {
+        self.storage.keys(Self::COLLECTION).await
+    }
+}
+
+/// Scheduled prints, kept in the `schedules` collection of the server's
+/// [`Storage`] under their id, with when they last and next run.
+pub struct ScheduleStore {
+    storage: Arc<dyn Storage>,
+}
+
+impl ScheduleStore {
+    /// Storage collection holding schedules.
+    pub const COLLECTION: &str = "schedules";
+
+    pub fn new(storage: Arc<dyn Storage>) -> Self {
+        Self { storage }
+    }
 
-        // Compress with gzip (fast compression level for speed)
-        let mut encoder = GzEncoder::new(Vec::new(), Compression::fast());
-        encoder.write_all(&quantized).expect("compression failed");
-        let compressed = encoder.finish().expect("compression finish failed");
+    /// Save a new schedule under a fresh id, which is set on it.
+    pub async fn create(&self, schedule: &mut Schedule) -> io::Result<()> {
+        schedule.id = uuid::Uuid::new_v4().simple().to_string()[..10].to_string();
+        self.save(schedule).await
+    }
+
+    /// Save a schedule under its id, replacing the previous version.
+    pub async fn save(&self, schedule: &Schedule) -> io::Result<()> {
+        self.storage
+            .put_json(Self::COLLECTION, &schedule.id, schedule)
+            .await
+    }
 
+    /// Look up a schedule by id.
+    pub async fn load(&self, id: &str) -> io::Result<Option<Schedule>> {
+        check_key(id)?;
+        self.storage.get_json(Self::COLLECTION, id).await
+    }
+
+    /// Remove a schedule. Returns whether it existed.
+    pub async fn delete(&self, id: &str) -> io::Result<bool> {
+        check_key(id)?;
+        self.storage.delete(Self::COLLECTION, id).await
+    }
+
+    /// All schedules, by id. Records that no longer parse are skipped.
+    pub async fn list(&self) -> io::Result<Vec<Schedule>> {
+        let mut schedules = Vec::new();
+        for id in self.storage.keys(Self::COLLECTION).await? {
+            match self.load(&id).await {
+                Ok(Some(schedule)) => schedules.push(schedule),
+                Ok(None) => {}
+                Err(e) => eprintln!("[schedule] Ignoring schedule {}: {}", id, e),
+            }
+        }
+        Ok(schedules)
+    }
+}
+
+/// Number of recent print jobs kept for the history and reprint endpoints.
+pub const JOB_HISTORY_LEN: usize = 20;
+
+/// A print job as recorded in the [`JobHistory`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobRecord {
+    pub id: JobId,
+    /// Handler that printed it: "json", "receipt", "pattern", ...
+    pub source: Cow<'static, str>,
+    /// Short human-readable description.
+    pub summary: String,
+    /// Local time the job was sent.
+    pub printed_at: String,
+    /// Why printing failed, if it did.
+    pub error: Option<String>,
+    /// The job this one reprinted.
+    pub reprint_of: Option<JobId>,
+    /// The programs sent, kept for reprints.
+    #[serde(skip)]
+    pub programs: Arc<Vec<Program>>,
+    /// The document printed, kept for gift receipts. Jobs that didn't come
+    /// from a document (photos, patterns) have none.
+    #[serde(skip)]
+    pub document: Option<Arc<Document>>,
+}
+
+/// The most recent print jobs, oldest first.
+///
+/// Jobs are kept in the `jobs` collection of the server's [`Storage`], so
+/// with a persistent backend the history and reprints survive restarts.
+/// They are read from it on first use and written through on every change.
+pub struct JobHistory {
+    storage: Arc<dyn Storage>,
+    jobs: OnceCell<RwLock<VecDeque<JobRecord>>>,
+    /// Every job as it is recorded, for integrations (e.g. MQTT).
+    events: broadcast::Sender<JobRecord>,
+}
+
+impl JobHistory {
+    /// Storage collection holding the job history.
+    pub const COLLECTION: &str = "jobs";
+
+    pub fn new(storage: Arc<dyn Storage>) -> Self {
         Self {
-            compressed,
-            uncompressed_size,
-            last_accessed: Instant::now(),
+            storage,
+            jobs: OnceCell::new(),
+            events: broadcast::channel(JOB_HISTORY_LEN).0,
         }
     }
 
-    /// Decompress and dequantize back to f32 intensities.
-    pub fn intensity(&self) -> Vec<f32> {
-        use flate2::read::GzDecoder;
-        use std::io::Read;
+    /// The jobs, loaded from storage the first time.
+    async fn jobs(&self) -> &RwLock<VecDeque<JobRecord>> {
+        self.jobs
+            .get_or_init(|| async {
+                let mut jobs: Vec<JobRecord> =
+                    load_jobs::<JobRecord>(&self.storage, Self::COLLECTION)
+                        .await
+                        .into_iter()
+                        .map(|(mut job, programs, document)| {
+                            job.programs = Arc::new(programs);
+                            job.document = document.map(Arc::new);
+                            job
+                        })
+                        .collect();
+                jobs.sort_by(|a, b| a.printed_at.cmp(&b.printed_at));
+                let excess = jobs.len().saturating_sub(JOB_HISTORY_LEN);
+                RwLock::new(jobs.into_iter().skip(excess).collect())
+            })
+            .await
+    }
+
+    /// Add a job, forgetting the oldest beyond [`JOB_HISTORY_LEN`].
+    pub async fn record(&self, job: JobRecord) {
+        let mut jobs = self.jobs().await.write().await;
+        while jobs.len() >= JOB_HISTORY_LEN {
+            let Some(oldest) = jobs.pop_front() else {
+                break;
+            };
+            delete_job(&self.storage, Self::COLLECTION, &oldest.id).await;
+        }
+        save_job(
+            &self.storage,
+            Self::COLLECTION,
+            &job.id,
+            &job,
+            &job.programs,
+            job.document.as_deref(),
+        )
+        .await;
+        jobs.push_back(job.clone());
+        // Nobody listening is fine
+        let _ = self.events.send(job);
+    }
 
-        let mut decoder = GzDecoder::new(&self.compressed[..]);
-        let mut quantized = Vec::with_capacity(self.uncompressed_size);
-        decoder
-            .read_to_end(&mut quantized)
-            .expect("decompression failed");
+    /// Receive every job recorded from now on.
+    #[cfg(feature = "mqtt")]
+    pub fn subscribe(&self) -> broadcast::Receiver<JobRecord> {
+        self.events.subscribe()
+    }
 
-        quantized.iter().map(|&v| v as f32 / 255.0).collect()
+    /// All recorded jobs, newest first.
+    pub async fn list(&self) -> Vec<JobRecord> {
+        self.jobs()
+            .await
+            .read()
+            .await
+            .iter()
+            .rev()
+            .cloned()
+            .collect()
     }
 
-    /// Update last_accessed time.
-    pub fn touch(&mut self) {
-        self.last_accessed = Instant::now();
+    pub async fn get(&self, id: &JobId) -> Option<JobRecord> {
+        self.jobs()
+            .await
+            .read()
+            .await
+            .iter()
+            .find(|j| j.id == *id)
+            .cloned()
     }
 }
 
-/// A photo session storing an uploaded image.
-pub struct PhotoSession {
-    /// The decoded image
-    pub image: DynamicImage,
-    /// Last accessed time (for expiration)
-    pub last_accessed: Instant,
+/// A compiled job waiting for quiet hours to end.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HeldJob {
+    pub id: JobId,
+    /// Handler that queued it: "json", "receipt", "pattern", ...
+    pub source: Cow<'static, str>,
+    /// Short human-readable description.
+    pub summary: String,
+    /// Local time the job arrived.
+    pub held_at: String,
+    /// Local time quiet hours end and the job prints.
+    pub held_until: String,
+    /// The job this one reprints.
+    pub reprint_of: Option<JobId>,
+    /// The programs to send.
+    #[serde(skip)]
+    pub programs: Vec<Program>,
+    /// The document printed, if the job came from one.
+    #[serde(skip)]
+    pub document: Option<Arc<Document>>,
 }
 
-impl PhotoSession {
-    pub fn new(image: DynamicImage) -> Self {
+/// Jobs held for quiet hours, in arrival order.
+///
+/// Kept in the `held` collection of the server's [`Storage`] like the
+/// [`JobHistory`], so with a persistent backend held jobs still print
+/// after a restart.
+pub struct HeldJobs {
+    storage: Arc<dyn Storage>,
+    jobs: OnceCell<RwLock<Vec<HeldJob>>>,
+}
+
+impl HeldJobs {
+    /// Storage collection holding the held jobs.
+    pub const COLLECTION: &str = "held";
+
+    pub fn new(storage: Arc<dyn Storage>) -> Self {
         Self {
-            image,
-            last_accessed: Instant::now(),
+            storage,
+            jobs: OnceCell::new(),
         }
     }
 
-    /// Touch the session to update last_accessed time.
-    pub fn touch(&mut self) {
-        self.last_accessed = Instant::now();
+    /// The jobs, loaded from storage the first time.
+    async fn jobs(&self) -> &RwLock<Vec<HeldJob>> {
+        self.jobs
+            .get_or_init(|| async {
+                let mut jobs: Vec<HeldJob> = load_jobs::<HeldJob>(&self.storage, Self::COLLECTION)
+                    .await
+                    .into_iter()
+                    .map(|(mut job, programs, document)| {
+                        job.programs = programs;
+                        job.document = document.map(Arc::new);
+                        job
+                    })
+                    .collect();
+                jobs.sort_by(|a, b| a.held_at.cmp(&b.held_at));
+                RwLock::new(jobs)
+            })
+            .await
     }
+
+    pub async fn push(&self, job: HeldJob) {
+        let mut jobs = self.jobs().await.write().await;
+        save_job(
+            &self.storage,
+            Self::COLLECTION,
+            &job.id,
+            &job,
+            &job.programs,
+            job.document.as_deref(),
+        )
+        .await;
+        jobs.push(job);
+    }
+
+    /// All held jobs, oldest first.
+    pub async fn list(&self) -> Vec<HeldJob> {
+        self.jobs().await.read().await.clone()
+    }
+
+    /// Remove and return all held jobs, oldest first.
+    pub async fn take(&self) -> Vec<HeldJob> {
+        let jobs = std::mem::take(&mut *self.jobs().await.write().await);
+        for job in &jobs {
+            delete_job(&self.storage, Self::COLLECTION, &job.id).await;
+        }
+        jobs
+    }
+}
+
+/// A job as written to [`Storage`]: its record plus the programs and
+/// document that the API leaves out.
+#[derive(Serialize)]
+struct StoredJobRef<'a, T> {
+    #[serde(flatten)]
+    job: &'a T,
+    programs: &'a [Program],
+    document: Option<&'a Document>,
+}
+
+/// A job as read back from [`Storage`].
+#[derive(Deserialize)]
+struct StoredJob<T> {
+    #[serde(flatten)]
+    job: T,
+    programs: Vec<Program>,
+    document: Option<Document>,
+}
+
+/// Save a job under its id. Failures are logged: the job still prints.
+async fn save_job<T: Serialize>(
+    storage: &Arc<dyn Storage>,
+    collection: &str,
+    id: &JobId,
+    job: &T,
+    programs: &[Program],
+    document: Option<&Document>,
+) {
+    let stored = StoredJobRef {
+        job,
+        programs,
+        document,
+    };
+    // Compact: programs hold raster data
+    let result = match serde_json::to_vec(&stored) {
+        Ok(bytes) => storage.put(collection, &id.to_string(), bytes).await,
+        Err(e) => Err(e.into()),
+    };
+    if let Err(e) = result {
+        eprintln!("Failed to save job {} in {}: {}", id, collection, e);
+    }
+}
+
+/// Remove a job. Failures are logged.
+async fn delete_job(storage: &Arc<dyn Storage>, collection: &str, id: &JobId) {
+    if let Err(e) = storage.delete(collection, &id.to_string()).await {
+        eprintln!("Failed to remove job {} from {}: {}", id, collection, e);
+    }
+}
+
+/// Every job in a collection. Records that no longer parse are skipped.
+async fn load_jobs<T: DeserializeOwned>(
+    storage: &Arc<dyn Storage>,
+    collection: &str,
+) -> Vec<(T, Vec<Program>, Option<Document>)> {
+    let keys = match storage.keys(collection).await {
+        Ok(keys) => keys,
+        Err(e) => {
+            eprintln!("Failed to load {}: {}", collection, e);
+            return Vec::new();
+        }
+    };
+    let mut jobs = Vec::new();
+    for key in keys {
+        match storage.get_json::<StoredJob<T>>(collection, &key).await {
+            Ok(Some(stored)) => jobs.push((stored.job, stored.programs, stored.document)),
+            Ok(None) => {}
+            Err(e) => eprintln!("Ignoring job {} in {}: {}", key, collection, e),
+        }
+    }
+    jobs
 }
 
 /// Application state shared across handlers.
@@ -151,20 +566,70 @@ pub struct AppState {
     pub photo_sessions: Arc<RwLock<HashMap<String, PhotoSession>>>,
     /// Cached intensity buffers for composer layers.
     pub intensity_cache: Arc<RwLock<HashMap<IntensityCacheKey, CachedIntensity>>>,
+    /// Document hooks applied before compile: library callbacks first,
+    /// then `config.hook_commands` in order.
+    pub hooks: Arc<Vec<Box<dyn DocumentHook>>>,
+    /// Documents saved by edit-link prints.
+    pub documents: DocumentStore,
+    /// Saved document templates.
+    pub templates: TemplateStore,
+    /// Scheduled template prints.
+    pub schedules: ScheduleStore,
+    /// Recent print jobs.
+    pub jobs: JobHistory,
+    /// Jobs waiting for quiet hours to end.
+    pub held: HeldJobs,
+    /// Jobs received over IPP, by IPP job number.
+    pub ipp_jobs: IppJobs,
+    /// Shortener for long QR code links, when `config.shortener` is set.
+    pub shortener: Option<UrlShortener>,
 }
 
 impl AppState {
-    pub fn new(config: ServerConfig) -> Self {
+    /// Set up the state for `config`.
+    ///
+    /// ## Errors
+    ///
+    /// Fails when the configured storage can't be opened or the shortener's
+    /// HTTP client can't be built.
+    pub fn new(
+        config: ServerConfig,
+        mut hooks: Vec<Box<dyn DocumentHook>>,
+    ) -> Result<Self, EstrellaError> {
         let boot_time = SystemTime::now()
             .duration_since(UNIX_EPOCH)
-            .unwrap()
+            .unwrap_or_default()
             .as_secs();
-        Self {
+        for command in &config.hook_commands {
+            hooks.push(Box::new(
+                CommandHook::new(command.clone()).with_timeout(config.hook_timeout),
+            ));
+        }
+        let storage = config.storage.open()?;
+        let documents = DocumentStore::new(storage.clone());
+        let templates = TemplateStore::new(storage.clone());
+        let schedules = ScheduleStore::new(storage.clone());
+        let jobs = JobHistory::new(storage.clone());
+        let held = HeldJobs::new(storage);
+        let shortener = config
+            .shortener
+            .clone()
+            .map(UrlShortener::new)
+            .transpose()?;
+        Ok(Self {
             config,
             boot_time,
             photo_sessions: Arc::new(RwLock::new(HashMap::new())),
             intensity_cache: Arc::new(RwLock::new(HashMap::new())),
-        }
+            hooks: Arc::new(hooks),
+            documents,
+            templates,
+            schedules,
+            jobs,
+            held,
+            ipp_jobs: IppJobs::default(),
+            shortener,
+        })
     }
 }
 
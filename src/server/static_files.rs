@@ -27,7 +27,8 @@ pub async fn index_handler(State(state): State<Arc<AppState>>) -> impl IntoRespo
                 .replace(".css\"", &format!(".css{}\"", cache_bust));
 
             // Inject component types as static data (avoids an API round-trip)
-            let types_json = serde_json::to_string(&document::component_types()).unwrap();
+            let types_json = serde_json::to_string(&document::component_types())
+                .unwrap_or_else(|_| "[]".to_string());
             let script = format!(
                 "<script>window.__COMPONENT_TYPES={}</script></head>",
                 types_json
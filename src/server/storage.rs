@@ -0,0 +1,431 @@
+//! Where the server keeps what it saves.
+//!
+//! Everything the server persists goes through one [`Storage`] trait:
+//! named collections of JSON records, each under a short key. Saved
+//! documents live in the `documents` collection, named templates in
+//! `templates`, scheduled prints in `schedules`, the job history in `jobs`
+//! and jobs held for quiet hours in `held`. The backend is picked by
+//! [`ServerConfig::storage`](super::ServerConfig::storage):
+//!
+//! | [`StorageConfig`] | Records live |
+//! |-------------------|--------------|
+//! | `Memory` (default) | in memory, gone on restart |
+//! | `Directory(dir)` | in `dir/<collection>/<key>.json` |
+//! | `Sqlite(file)` | in one sqlite database (`sqlite` feature) |
+//! | `Custom(storage)` | wherever a library user's backend puts them |
+//!
+//! Collection names and keys are limited to ASCII letters, digits, `-` and
+//! `_`, so every backend can use them as file names or table keys as is.
+
+use async_trait::async_trait;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+/// Longest allowed collection name or key.
+pub const MAX_KEY_LEN: usize = 64;
+
+/// A store of byte records, grouped into collections.
+///
+/// Backends only move bytes; `put_json` and `get_json` (on
+/// `dyn Storage`) add the JSON on top.
+#[async_trait]
+pub trait Storage: Send + Sync {
+    /// Save `value` under `key`, replacing any previous value.
+    async fn put(&self, collection: &str, key: &str, value: Vec<u8>) -> io::Result<()>;
+
+    /// The value under `key`, if there is one.
+    async fn get(&self, collection: &str, key: &str) -> io::Result<Option<Vec<u8>>>;
+
+    /// Remove `key`. Returns whether it was there.
+    async fn delete(&self, collection: &str, key: &str) -> io::Result<bool>;
+
+    /// All keys in a collection, sorted.
+    async fn keys(&self, collection: &str) -> io::Result<Vec<String>>;
+}
+
+impl dyn Storage {
+    /// Save `value` as JSON.
+    pub async fn put_json<T: Serialize + ?Sized>(
+        &self,
+        collection: &str,
+        key: &str,
+        value: &T,
+    ) -> io::Result<()> {
+        self.put(collection, key, serde_json::to_vec_pretty(value)?)
+            .await
+    }
+
+    /// Load a JSON value. Records that don't parse as `T` are an
+    /// [`io::ErrorKind::InvalidData`] error.
+    pub async fn get_json<T: DeserializeOwned>(
+        &self,
+        collection: &str,
+        key: &str,
+    ) -> io::Result<Option<T>> {
+        match self.get(collection, key).await? {
+            Some(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Check that a collection name or key is safe for every backend.
+pub fn check_key(key: &str) -> io::Result<()> {
+    let valid = !key.is_empty()
+        && key.len() <= MAX_KEY_LEN
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("invalid storage key '{}'", key),
+        ))
+    }
+}
+
+/// Which [`Storage`] backend the server uses.
+#[derive(Clone, Default)]
+pub enum StorageConfig {
+    /// Keep records in memory only.
+    #[default]
+    Memory,
+    /// One JSON file per record under this directory.
+    Directory(PathBuf),
+    /// One sqlite database file holding every record.
+    #[cfg(feature = "sqlite")]
+    Sqlite(PathBuf),
+    /// A backend supplied by the embedding application.
+    Custom(Arc<dyn Storage>),
+}
+
+impl StorageConfig {
+    /// Create the configured backend.
+    ///
+    /// ## Errors
+    ///
+    /// Fails when a sqlite database can't be opened or set up.
+    pub fn open(&self) -> io::Result<Arc<dyn Storage>> {
+        Ok(match self {
+            StorageConfig::Memory => Arc::new(MemoryStorage::default()),
+            StorageConfig::Directory(dir) => Arc::new(FileStorage::new(dir.clone())),
+            #[cfg(feature = "sqlite")]
+            StorageConfig::Sqlite(path) => Arc::new(SqliteStorage::open(path)?),
+            StorageConfig::Custom(storage) => storage.clone(),
+        })
+    }
+}
+
+impl fmt::Debug for StorageConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StorageConfig::Memory => write!(f, "Memory"),
+            StorageConfig::Directory(dir) => f.debug_tuple("Directory").field(dir).finish(),
+            #[cfg(feature = "sqlite")]
+            StorageConfig::Sqlite(path) => f.debug_tuple("Sqlite").field(path).finish(),
+            StorageConfig::Custom(_) => write!(f, "Custom(..)"),
+        }
+    }
+}
+
+/// Records in memory, lost on restart.
+#[derive(Default)]
+pub struct MemoryStorage {
+    records: RwLock<BTreeMap<(String, String), Vec<u8>>>,
+}
+
+#[async_trait]
+impl Storage for MemoryStorage {
+    async fn put(&self, collection: &str, key: &str, value: Vec<u8>) -> io::Result<()> {
+        check_key(collection)?;
+        check_key(key)?;
+        self.records
+            .write()
+            .await
+            .insert((collection.to_string(), key.to_string()), value);
+        Ok(())
+    }
+
+    async fn get(&self, collection: &str, key: &str) -> io::Result<Option<Vec<u8>>> {
+        check_key(collection)?;
+        check_key(key)?;
+        Ok(self
+            .records
+            .read()
+            .await
+            .get(&(collection.to_string(), key.to_string()))
+            .cloned())
+    }
+
+    async fn delete(&self, collection: &str, key: &str) -> io::Result<bool> {
+        check_key(collection)?;
+        check_key(key)?;
+        Ok(self
+            .records
+            .write()
+            .await
+            .remove(&(collection.to_string(), key.to_string()))
+            .is_some())
+    }
+
+    async fn keys(&self, collection: &str) -> io::Result<Vec<String>> {
+        check_key(collection)?;
+        Ok(self
+            .records
+            .read()
+            .await
+            .keys()
+            .filter(|(c, _)| c == collection)
+            .map(|(_, key)| key.clone())
+            .collect())
+    }
+}
+
+/// Records as `<root>/<collection>/<key>.json` files.
+///
+/// Writes go to a temporary file that is then renamed over the record, so
+/// a crash mid-write never leaves half a record behind.
+pub struct FileStorage {
+    root: PathBuf,
+}
+
+impl FileStorage {
+    pub fn new(root: PathBuf) -> Self {
+        Self { root }
+    }
+
+    fn path(&self, collection: &str, key: &str) -> io::Result<PathBuf> {
+        check_key(collection)?;
+        check_key(key)?;
+        Ok(self.root.join(collection).join(format!("{}.json", key)))
+    }
+}
+
+#[async_trait]
+impl Storage for FileStorage {
+    async fn put(&self, collection: &str, key: &str, value: Vec<u8>) -> io::Result<()> {
+        let path = self.path(collection, key)?;
+        tokio::fs::create_dir_all(self.root.join(collection)).await?;
+        let temp = path.with_extension("json.tmp");
+        tokio::fs::write(&temp, value).await?;
+        tokio::fs::rename(&temp, &path).await
+    }
+
+    async fn get(&self, collection: &str, key: &str) -> io::Result<Option<Vec<u8>>> {
+        match tokio::fs::read(self.path(collection, key)?).await {
+            Ok(bytes) => Ok(Some(bytes)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    async fn delete(&self, collection: &str, key: &str) -> io::Result<bool> {
+        match tokio::fs::remove_file(self.path(collection, key)?).await {
+            Ok(()) => Ok(true),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    async fn keys(&self, collection: &str) -> io::Result<Vec<String>> {
+        check_key(collection)?;
+        let mut entries = match tokio::fs::read_dir(self.root.join(collection)).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+        let mut keys = Vec::new();
+        while let Some(entry) = entries.next_entry().await? {
+            let name = entry.file_name();
+            let Some(key) = name.to_str().and_then(|n| n.strip_suffix(".json")) else {
+                continue;
+            };
+            if check_key(key).is_ok() {
+                keys.push(key.to_string());
+            }
+        }
+        keys.sort();
+        Ok(keys)
+    }
+}
+
+/// Records as rows of one sqlite table, keyed by collection and key.
+///
+/// Queries run on the blocking pool, one at a time.
+#[cfg(feature = "sqlite")]
+pub struct SqliteStorage {
+    connection: Arc<std::sync::Mutex<rusqlite::Connection>>,
+}
+
+#[cfg(feature = "sqlite")]
+impl SqliteStorage {
+    /// Open (or create) the database at `path`.
+    pub fn open(path: &std::path::Path) -> io::Result<Self> {
+        let connection = rusqlite::Connection::open(path).map_err(io::Error::other)?;
+        connection
+            .execute_batch(
+                "PRAGMA journal_mode = WAL;
+                 CREATE TABLE IF NOT EXISTS records (
+                     collection TEXT NOT NULL,
+                     key TEXT NOT NULL,
+                     value BLOB NOT NULL,
+                     PRIMARY KEY (collection, key)
+                 );",
+            )
+            .map_err(io::Error::other)?;
+        Ok(Self {
+            connection: Arc::new(std::sync::Mutex::new(connection)),
+        })
+    }
+
+    /// Run `f` with the connection on the blocking pool.
+    async fn run<R, F>(&self, f: F) -> io::Result<R>
+    where
+        R: Send + 'static,
+        F: FnOnce(&rusqlite::Connection) -> rusqlite::Result<R> + Send + 'static,
+    {
+        let connection = Arc::clone(&self.connection);
+        tokio::task::spawn_blocking(move || {
+            let connection = connection
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            f(&connection).map_err(io::Error::other)
+        })
+        .await
+        .map_err(io::Error::other)?
+    }
+}
+
+#[cfg(feature = "sqlite")]
+#[async_trait]
+impl Storage for SqliteStorage {
+    async fn put(&self, collection: &str, key: &str, value: Vec<u8>) -> io::Result<()> {
+        check_key(collection)?;
+        check_key(key)?;
+        let (collection, key) = (collection.to_string(), key.to_string());
+        self.run(move |db| {
+            db.execute(
+                "INSERT OR REPLACE INTO records (collection, key, value) VALUES (?1, ?2, ?3)",
+                rusqlite::params![collection, key, value],
+            )
+            .map(|_| ())
+        })
+        .await
+    }
+
+    async fn get(&self, collection: &str, key: &str) -> io::Result<Option<Vec<u8>>> {
+        use rusqlite::OptionalExtension;
+
+        check_key(collection)?;
+        check_key(key)?;
+        let (collection, key) = (collection.to_string(), key.to_string());
+        self.run(move |db| {
+            db.query_row(
+                "SELECT value FROM records WHERE collection = ?1 AND key = ?2",
+                rusqlite::params![collection, key],
+                |row| row.get(0),
+            )
+            .optional()
+        })
+        .await
+    }
+
+    async fn delete(&self, collection: &str, key: &str) -> io::Result<bool> {
+        check_key(collection)?;
+        check_key(key)?;
+        let (collection, key) = (collection.to_string(), key.to_string());
+        self.run(move |db| {
+            db.execute(
+                "DELETE FROM records WHERE collection = ?1 AND key = ?2",
+                rusqlite::params![collection, key],
+            )
+            .map(|removed| removed > 0)
+        })
+        .await
+    }
+
+    async fn keys(&self, collection: &str) -> io::Result<Vec<String>> {
+        check_key(collection)?;
+        let collection = collection.to_string();
+        self.run(move |db| {
+            db.prepare("SELECT key FROM records WHERE collection = ?1 ORDER BY key")?
+                .query_map([collection], |row| row.get(0))?
+                .collect()
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn exercise(storage: Arc<dyn Storage>) {
+        assert_eq!(storage.get("documents", "abc").await.unwrap(), None);
+        storage
+            .put_json("documents", "abc", &serde_json::json!({"n": 1}))
+            .await
+            .unwrap();
+        storage
+            .put("documents", "0ff", b"{}".to_vec())
+            .await
+            .unwrap();
+        storage.put("templates", "x", b"{}".to_vec()).await.unwrap();
+
+        let value: serde_json::Value = storage.get_json("documents", "abc").await.unwrap().unwrap();
+        assert_eq!(value["n"], 1);
+        assert_eq!(storage.keys("documents").await.unwrap(), ["0ff", "abc"]);
+        assert_eq!(storage.keys("empty").await.unwrap(), Vec::<String>::new());
+
+        assert!(storage.delete("documents", "abc").await.unwrap());
+        assert!(!storage.delete("documents", "abc").await.unwrap());
+        assert_eq!(storage.keys("documents").await.unwrap(), ["0ff"]);
+
+        // Keys can't escape their collection
+        for key in ["", "../secret", "a/b", "a.json"] {
+            let err = storage.put("documents", key, Vec::new()).await.unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidInput, "{:?}", key);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_memory_storage() {
+        exercise(StorageConfig::Memory.open().unwrap()).await;
+    }
+
+    #[tokio::test]
+    async fn test_file_storage() {
+        let dir = std::env::temp_dir().join(format!("estrella-storage-{}", std::process::id()));
+        exercise(StorageConfig::Directory(dir.clone()).open().unwrap()).await;
+        assert!(dir.join("templates").join("x.json").exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn test_sqlite_storage() {
+        let path = std::env::temp_dir().join(format!("estrella-storage-{}.db", std::process::id()));
+        let config = StorageConfig::Sqlite(path.clone());
+        exercise(config.open().unwrap()).await;
+
+        // Records survive reopening the database
+        let reopened = config.open().unwrap();
+        assert_eq!(reopened.keys("documents").await.unwrap(), ["0ff"]);
+        assert_eq!(
+            reopened.get("templates", "x").await.unwrap(),
+            Some(b"{}".to_vec())
+        );
+        drop(reopened);
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{}", path.display(), suffix));
+        }
+    }
+}
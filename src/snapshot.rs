@@ -0,0 +1,155 @@
+//! # Layout Snapshots
+//!
+//! Helpers for pinning receipt layouts in downstream test suites. A
+//! document snapshot has two parts:
+//!
+//! - a **perceptual hash** of the preview, one 64-bit difference hash per
+//!   576-row page, which stays put under byte-level noise but changes when
+//!   the receipt visibly changes;
+//! - the **normalized IR** (the optimized program's disassembly), which
+//!   pins the exact command stream.
+//!
+//! Built-in date variables are pinned to [`SNAPSHOT_TIME`] so snapshots
+//! don't change from one day to the next; `{{sunrise}}` and `{{sunset}}`
+//! are given in UTC.
+//!
+//! With the `snapshot` feature enabled, [`assert_document_snapshot!`]
+//! stores the snapshot with [insta](https://insta.rs):
+//!
+//! ```ignore
+//! #[test]
+//! fn receipt_layout() {
+//!     let doc = my_app::build_receipt(&order);
+//!     estrella::assert_document_snapshot!(doc);
+//! }
+//! ```
+//!
+//! Review changes with `cargo insta review` after upgrading estrella.
+
+use chrono::Offset;
+
+use crate::document::{Document, builtin_variables_at, sun_variables};
+use crate::ir::analyze::disassemble;
+use crate::preview::{PreviewError, RawRaster, render_raw};
+
+/// Point in time used for `{{date}}`, `{{time}}`, etc. in snapshots.
+pub const SNAPSHOT_TIME: &str = "2026-01-27T09:30:00";
+
+/// Rows covered by each perceptual hash.
+const PAGE_ROWS: usize = 576;
+
+/// Render the snapshot text for a document.
+pub fn document_snapshot(doc: &Document) -> Result<String, PreviewError> {
+    let mut doc = doc.clone();
+    let now: chrono::NaiveDateTime = SNAPSHOT_TIME.parse().expect("valid snapshot time");
+    let mut builtins = builtin_variables_at(&now, doc.locale.as_deref());
+    if let Some(location) = &doc.location {
+        builtins.extend(sun_variables(now.date(), location, chrono::Utc.fix()));
+    }
+    for (name, value) in builtins {
+        doc.variables.entry(name).or_insert(value);
+    }
+
+    let program = doc.compile();
+    let raw = render_raw(&program)?;
+    let hashes: Vec<String> = perceptual_hashes(&raw)
+        .iter()
+        .map(|hash| format!("{:016x}", hash))
+        .collect();
+
+    Ok(format!(
+        "preview: {}x{}\nphash: {}\n---\n{}",
+        raw.width,
+        raw.height,
+        hashes.join(" "),
+        disassemble(&program)
+    ))
+}
+
+/// Difference hashes of a raster, one per [`PAGE_ROWS`]-row page.
+///
+/// Each page is averaged down to a 9×8 grid of darkness values; bit `n` is
+/// set when a cell is darker than its right neighbour.
+pub fn perceptual_hashes(raw: &RawRaster) -> Vec<u64> {
+    let width_bytes = raw.width.div_ceil(8);
+    let pixel = |x: usize, y: usize| raw.data[y * width_bytes + x / 8] & (0x80 >> (x % 8)) != 0;
+
+    (0..raw.height)
+        .step_by(PAGE_ROWS)
+        .map(|top| {
+            let rows = PAGE_ROWS.min(raw.height - top);
+            let mut grid = [[0.0f32; 9]; 8];
+            for (gy, row) in grid.iter_mut().enumerate() {
+                let (y0, y1) = (top + gy * rows / 8, top + ((gy + 1) * rows / 8).max(1));
+                for (gx, cell) in row.iter_mut().enumerate() {
+                    let (x0, x1) = (gx * raw.width / 9, (gx + 1) * raw.width / 9);
+                    let area = ((y1 - y0) * (x1 - x0)).max(1);
+                    let dark = (y0..y1)
+                        .flat_map(|y| (x0..x1).map(move |x| (x, y)))
+                        .filter(|&(x, y)| pixel(x, y))
+                        .count();
+                    *cell = dark as f32 / area as f32;
+                }
+            }
+
+            let mut hash = 0u64;
+            for (gy, row) in grid.iter().enumerate() {
+                for (gx, pair) in row.windows(2).enumerate() {
+                    if pair[0] > pair[1] {
+                        hash |= 1 << (gy * 8 + gx);
+                    }
+                }
+            }
+            hash
+        })
+        .collect()
+}
+
+/// Assert that a document's layout matches its stored insta snapshot.
+///
+/// Accepts an optional snapshot name, like `insta::assert_snapshot!`.
+#[cfg(feature = "snapshot")]
+#[macro_export]
+macro_rules! assert_document_snapshot {
+    ($doc:expr $(,)?) => {
+        $crate::__insta::assert_snapshot!(
+            $crate::snapshot::document_snapshot(&$doc).expect("document preview failed")
+        )
+    };
+    ($name:expr, $doc:expr $(,)?) => {
+        $crate::__insta::assert_snapshot!(
+            $name,
+            $crate::snapshot::document_snapshot(&$doc).expect("document preview failed")
+        )
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document::{Component, Text};
+
+    #[test]
+    fn test_snapshot_pins_dates() {
+        let mut doc = Document::new();
+        doc.push(Component::Text(Text::new("{{date}}")));
+        let snapshot = document_snapshot(&doc).unwrap();
+        assert!(snapshot.contains("January 27, 2026"));
+        assert_eq!(snapshot, document_snapshot(&doc).unwrap());
+
+        // User variables still win over the pinned builtins
+        doc.variables.insert("date".into(), "tomorrow".into());
+        assert!(document_snapshot(&doc).unwrap().contains("tomorrow"));
+    }
+
+    #[test]
+    fn test_perceptual_hash_tracks_visible_changes() {
+        let hash = |text: &str| {
+            let mut doc = Document::new();
+            doc.push(Component::Text(Text::new(text)));
+            perceptual_hashes(&render_raw(&doc.compile()).unwrap())
+        };
+        assert_eq!(hash("HELLO WORLD"), hash("HELLO WORLD"));
+        assert_ne!(hash("HELLO WORLD"), hash("                  HELLO"));
+    }
+}
@@ -40,6 +40,7 @@
 //! - **8-bit characters**: CS8 (8 data bits, no parity)
 //! - **No echo**: Disable ECHO, ECHONL
 //! - **Non-canonical mode**: Disable ICANON (no line buffering)
+//! - **Read timeout**: Status reads give up after one second (VMIN 0, VTIME 10)
 //!
 //! ## Paced Writes
 //!
@@ -47,16 +48,48 @@
 //! `tcdrain()` blocks until the data has been physically transmitted over
 //! Bluetooth. This naturally paces writes to the actual Bluetooth throughput,
 //! preventing the printer's internal buffer from overflowing.
+//!
+//! `send_program()` streams codegen output straight into these chunks, so
+//! long prints never build their full byte stream in memory.
+//!
+//! Some printers still drop data on very long rasters (multi-metre weaves).
+//! [`WritePacing`] tunes the chunking: smaller chunks, a pause after each
+//! one, and optional flow control that enables Automatic Status Back and
+//! holds the next chunk while the printer reports itself offline. Set it
+//! with [`BluetoothTransport::set_pacing`] or as a query on the device
+//! path, which works wherever a device string is accepted:
+//!
+//! ```text
+//! /dev/rfcomm0?chunk_size=1024&chunk_delay_ms=20&flow_control=true
+//! ```
+//!
+//! ## Async Writes
+//!
+//! [`AsyncBluetoothTransport`] opens the device non-blocking and paces the
+//! same chunks on the tokio runtime: instead of blocking in `tcdrain()`, it
+//! polls the TTY's output queue until it is empty.
 
 use std::fs::{self, File, OpenOptions};
-use std::io::{self, Write};
+use std::io::{self, BufWriter, Read, Write};
+use std::os::unix::fs::OpenOptionsExt;
 use std::os::unix::io::AsRawFd;
 use std::path::Path;
 use std::process::Command;
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
+#[cfg(feature = "async")]
+use async_trait::async_trait;
+#[cfg(feature = "async")]
+use tokio::io::unix::AsyncFd;
+
+#[cfg(feature = "async")]
+use super::AsyncTransport;
+use super::Transport;
 use crate::error::EstrellaError;
+use crate::ir::Program;
+use crate::printer::PrinterConfig;
+use crate::protocol::status::{self, AsbStatus};
 
 /// Default RFCOMM device path
 pub const DEFAULT_DEVICE: &str = "/dev/rfcomm0";
@@ -69,6 +102,96 @@ const CHUNK_SIZE: usize = 4096;
 /// printer time to finish processing the current job before receiving the next Init.
 const JOB_DELAY_MS: u64 = 2000;
 
+/// How long `read_status` waits for the printer to answer, in tenths of a
+/// second (the TTY's `VTIME`).
+const STATUS_TIMEOUT_DS: u8 = 10;
+
+/// Largest status response read at once (bytes).
+const STATUS_BUFFER_SIZE: usize = 64;
+
+/// How often flow control checks for a status report while the printer is
+/// offline.
+const OFFLINE_POLL: Duration = Duration::from_millis(100);
+
+/// How long flow control waits for an offline printer to come back.
+const OFFLINE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// How often async writes check whether a chunk has left the OS buffer.
+#[cfg(feature = "async")]
+const DRAIN_POLL: Duration = Duration::from_millis(5);
+
+/// # Write Pacing
+///
+/// How [`BluetoothTransport`] splits and paces large writes.
+///
+/// ```
+/// use std::time::Duration;
+/// use estrella::transport::bluetooth::WritePacing;
+///
+/// let pacing = WritePacing::from_query("chunk_size=1024&chunk_delay_ms=20").unwrap();
+/// assert_eq!(pacing.chunk_size, 1024);
+/// assert_eq!(pacing.chunk_delay, Duration::from_millis(20));
+/// assert!(!pacing.flow_control);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WritePacing {
+    /// Bytes written per chunk (default: 4096).
+    pub chunk_size: usize,
+    /// Pause after each chunk has been transmitted (default: none).
+    pub chunk_delay: Duration,
+    /// Enable Automatic Status Back and wait while the printer reports
+    /// itself offline before sending the next chunk (default: off).
+    pub flow_control: bool,
+}
+
+impl Default for WritePacing {
+    fn default() -> Self {
+        Self {
+            chunk_size: CHUNK_SIZE,
+            chunk_delay: Duration::ZERO,
+            flow_control: false,
+        }
+    }
+}
+
+impl WritePacing {
+    /// Parse `key=value` pairs joined by `&`: `chunk_size` (bytes),
+    /// `chunk_delay_ms` and `flow_control` (`true`/`false`). Missing keys
+    /// keep their defaults.
+    pub fn from_query(query: &str) -> Result<Self, String> {
+        let mut pacing = Self::default();
+        for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+            let (key, value) = pair
+                .split_once('=')
+                .ok_or_else(|| format!("Invalid device option '{}': expected key=value", pair))?;
+            let invalid = |_| format!("Invalid value for {}: '{}'", key, value);
+            match key {
+                "chunk_size" => {
+                    pacing.chunk_size = value.parse().map_err(invalid)?;
+                    if pacing.chunk_size == 0 {
+                        return Err("chunk_size must be at least 1".into());
+                    }
+                }
+                "chunk_delay_ms" => {
+                    pacing.chunk_delay = Duration::from_millis(value.parse().map_err(invalid)?)
+                }
+                "flow_control" => {
+                    pacing.flow_control = value
+                        .parse()
+                        .map_err(|_| format!("Invalid value for {}: '{}'", key, value))?
+                }
+                _ => {
+                    return Err(format!(
+                        "Unknown device option '{}'. Use chunk_size, chunk_delay_ms or flow_control",
+                        key
+                    ));
+                }
+            }
+        }
+        Ok(pacing)
+    }
+}
+
 /// # Bluetooth Printer Transport
 ///
 /// Manages a connection to a Star printer over Bluetooth RFCOMM.
@@ -76,7 +199,7 @@ const JOB_DELAY_MS: u64 = 2000;
 /// ## Example
 ///
 /// ```no_run
-/// use estrella::transport::bluetooth::BluetoothTransport;
+/// use estrella::transport::{BluetoothTransport, Transport};
 /// use estrella::protocol::commands;
 ///
 /// let mut transport = BluetoothTransport::open("/dev/rfcomm0")?;
@@ -90,7 +213,7 @@ const JOB_DELAY_MS: u64 = 2000;
 /// ```
 pub struct BluetoothTransport {
     file: File,
-    chunk_size: usize,
+    pacing: WritePacing,
 }
 
 impl BluetoothTransport {
@@ -98,7 +221,8 @@ impl BluetoothTransport {
     ///
     /// ## Parameters
     ///
-    /// - `device`: Path to the RFCOMM device (e.g., "/dev/rfcomm0")
+    /// - `device`: Path to the RFCOMM device (e.g., "/dev/rfcomm0"),
+    ///   optionally followed by `?` and [`WritePacing`] options
     ///
     /// ## TTY Configuration
     ///
@@ -113,20 +237,19 @@ impl BluetoothTransport {
     /// - The device doesn't exist
     /// - Permission denied (may need root or dialout group)
     /// - TTY configuration fails
+    /// - The pacing options are invalid
     pub fn open<P: AsRef<Path>>(device: P) -> Result<Self, EstrellaError> {
-        let path = device.as_ref();
-
-        let file = OpenOptions::new().write(true).open(path).map_err(|e| {
-            EstrellaError::Transport(format!("Failed to open {}: {}", path.display(), e))
-        })?;
-
-        // Configure TTY for raw mode
-        configure_tty_raw(file.as_raw_fd())?;
+        let (path, pacing) = split_device(device.as_ref())?;
+        Self::open_with_pacing(path, pacing)
+    }
 
-        Ok(Self {
-            file,
-            chunk_size: CHUNK_SIZE,
-        })
+    /// Open a Bluetooth connection with explicit write pacing.
+    pub fn open_with_pacing<P: AsRef<Path>>(
+        device: P,
+        pacing: WritePacing,
+    ) -> Result<Self, EstrellaError> {
+        let file = open_tty(device.as_ref(), 0)?;
+        Ok(Self { file, pacing })
     }
 
     /// Open with default device path (/dev/rfcomm0)
@@ -139,14 +262,155 @@ impl BluetoothTransport {
     /// Larger chunks are faster but may overflow the Bluetooth buffer.
     /// Default is 4096 bytes.
     pub fn set_chunk_size(&mut self, size: usize) {
-        self.chunk_size = size;
+        self.pacing.chunk_size = size.max(1);
+    }
+
+    /// Set chunk size, inter-chunk delay and flow control at once.
+    pub fn set_pacing(&mut self, pacing: WritePacing) {
+        self.pacing = WritePacing {
+            chunk_size: pacing.chunk_size.max(1),
+            ..pacing
+        };
+    }
+
+    /// Current write pacing.
+    pub fn pacing(&self) -> WritePacing {
+        self.pacing
+    }
+
+    /// Block until all written data has been physically transmitted.
+    ///
+    /// This calls `tcdrain()` on the underlying file descriptor, which
+    /// blocks until the OS output buffer has been fully sent over the
+    /// Bluetooth RFCOMM link. Without this, `flush()` only pushes data
+    /// from userspace to the kernel buffer, and the printer may not have
+    /// received it yet.
+    #[cfg(unix)]
+    fn tcdrain(&self) -> Result<(), EstrellaError> {
+        let result = unsafe { libc::tcdrain(self.file.as_raw_fd()) };
+        if result != 0 {
+            return Err(EstrellaError::Transport(format!(
+                "tcdrain failed: {}",
+                io::Error::last_os_error()
+            )));
+        }
+        Ok(())
+    }
+
+    #[cfg(not(unix))]
+    fn tcdrain(&self) -> Result<(), EstrellaError> {
+        Ok(())
+    }
+
+    /// Whether the printer has sent something to read, waiting up to
+    /// `timeout` for it.
+    #[cfg(unix)]
+    fn status_pending(&self, timeout: Duration) -> Result<bool, EstrellaError> {
+        let mut fds = libc::pollfd {
+            fd: self.file.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        let result = unsafe { libc::poll(&mut fds, 1, timeout.as_millis() as libc::c_int) };
+        if result < 0 {
+            return Err(EstrellaError::Transport(format!(
+                "poll failed: {}",
+                io::Error::last_os_error()
+            )));
+        }
+        Ok(result > 0 && fds.revents & libc::POLLIN != 0)
+    }
+
+    #[cfg(not(unix))]
+    fn status_pending(&self, _timeout: Duration) -> Result<bool, EstrellaError> {
+        Ok(false)
     }
 
+    /// Flow control: read the status reports the printer has sent since
+    /// the last chunk, and while the latest one says it is offline, wait
+    /// for one saying it is back.
+    fn wait_while_offline(&mut self) -> Result<(), EstrellaError> {
+        let deadline = Instant::now() + OFFLINE_TIMEOUT;
+        let mut offline = false;
+        loop {
+            let wait = if offline {
+                OFFLINE_POLL
+            } else {
+                Duration::ZERO
+            };
+            if self.status_pending(wait)? {
+                if let Some(report) = AsbStatus::parse_all(&self.read_status()?).last() {
+                    offline = report.offline;
+                }
+                continue;
+            }
+            if !offline {
+                return Ok(());
+            }
+            if Instant::now() >= deadline {
+                return Err(EstrellaError::Transport(format!(
+                    "Printer stayed offline for {}s (cover open or out of paper?)",
+                    OFFLINE_TIMEOUT.as_secs()
+                )));
+            }
+        }
+    }
+
+    /// Write one chunk and pace it: wait for it to be transmitted, pause
+    /// for the chunk delay, then apply flow control.
+    fn write_chunk(&mut self, chunk: &[u8]) -> Result<(), EstrellaError> {
+        self.file
+            .write_all(chunk)
+            .map_err(|e| EstrellaError::Transport(format!("Write failed: {}", e)))?;
+
+        // Block until this chunk has been physically transmitted.
+        // This prevents the OS from buffering all chunks at once,
+        // which would overwhelm the printer's internal buffer.
+        self.tcdrain()?;
+
+        if !self.pacing.chunk_delay.is_zero() {
+            thread::sleep(self.pacing.chunk_delay);
+        }
+        if self.pacing.flow_control {
+            self.wait_while_offline()?;
+        }
+        Ok(())
+    }
+
+    /// Write a segment of data with chunking and pacing.
+    ///
+    /// Data is written in chunks (4KB by default). After each chunk,
+    /// `tcdrain()` blocks until the data has been physically transmitted
+    /// over Bluetooth. This naturally paces writes to the actual link
+    /// throughput (~700 KB/s for BT SPP), preventing the printer's ~100KB
+    /// internal buffer from overflowing.
+    fn write_segment(&mut self, data: &[u8]) -> Result<(), EstrellaError> {
+        if data.is_empty() {
+            return Ok(());
+        }
+
+        if data.len() <= self.pacing.chunk_size {
+            // Small write - send directly
+            self.file
+                .write_all(data)
+                .map_err(|e| EstrellaError::Transport(format!("Write failed: {}", e)))?;
+        } else {
+            // Large write - chunk it with tcdrain pacing
+            for chunk in data.chunks(self.pacing.chunk_size) {
+                self.write_chunk(chunk)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Transport for BluetoothTransport {
     /// Write data to the printer.
     ///
     /// Small writes are sent directly. Large writes are automatically
     /// chunked to avoid buffer overflow.
-    pub fn write_all(&mut self, data: &[u8]) -> Result<(), EstrellaError> {
+    fn write_all(&mut self, data: &[u8]) -> Result<(), EstrellaError> {
         self.write_segment(data)?;
 
         self.file
@@ -156,6 +420,52 @@ impl BluetoothTransport {
         Ok(())
     }
 
+    /// Flush and block until all written data has been transmitted
+    /// (`tcdrain()`).
+    fn flush(&mut self) -> Result<(), EstrellaError> {
+        self.file
+            .flush()
+            .map_err(|e| EstrellaError::Transport(format!("Flush failed: {}", e)))?;
+        self.tcdrain()
+    }
+
+    /// Read a status response, waiting up to a second for the first byte.
+    fn read_status(&mut self) -> Result<Vec<u8>, EstrellaError> {
+        let mut buf = [0u8; STATUS_BUFFER_SIZE];
+        let n = self
+            .file
+            .read(&mut buf)
+            .map_err(|e| EstrellaError::Transport(format!("Read failed: {}", e)))?;
+        if n == 0 {
+            return Err(EstrellaError::Transport(
+                "No status response from printer".into(),
+            ));
+        }
+        Ok(buf[..n].to_vec())
+    }
+
+    /// Stream a program to the printer.
+    ///
+    /// Codegen writes straight into `chunk_size` blocks that are paced like
+    /// [`write_all`](Self::write_all), so the full byte stream of a long
+    /// print is never held in memory. With flow control on, Automatic
+    /// Status Back is enabled first.
+    fn send_program(
+        &mut self,
+        program: &Program,
+        config: &PrinterConfig,
+    ) -> Result<(), EstrellaError> {
+        if self.pacing.flow_control {
+            self.write_chunk(&status::asb_enable())?;
+        }
+        let chunk_size = self.pacing.chunk_size;
+        let mut writer = BufWriter::with_capacity(chunk_size, PacedWriter(self));
+        program
+            .write_to(&mut writer, config)
+            .and_then(|()| writer.flush())
+            .map_err(|e| EstrellaError::Transport(format!("Write failed: {}", e)))
+    }
+
     /// Send multiple independent print programs with pauses between them.
     ///
     /// Each program is sent completely, then the transport pauses for 1 second
@@ -165,31 +475,31 @@ impl BluetoothTransport {
     /// ## Example
     ///
     /// ```ignore
-    /// use estrella::transport::bluetooth::BluetoothTransport;
+    /// use estrella::transport::{BluetoothTransport, Transport};
     /// use estrella::ir::Program;
+    /// use estrella::printer::PrinterConfig;
     ///
     /// let programs: Vec<Program> = program.split_for_long_print();
     /// let mut transport = BluetoothTransport::open("/dev/rfcomm0")?;
-    /// transport.send_programs(&programs)?;
+    /// transport.send_programs(&programs, &PrinterConfig::TSP650II)?;
     /// ```
-    pub fn send_programs(&mut self, programs: &[crate::ir::Program]) -> Result<(), EstrellaError> {
+    fn send_programs(
+        &mut self,
+        programs: &[Program],
+        config: &PrinterConfig,
+    ) -> Result<(), EstrellaError> {
         let total = programs.len();
         println!("[send_programs] Sending {} program(s) to printer", total);
 
         for (i, program) in programs.iter().enumerate() {
-            let bytes = program.to_bytes();
             println!(
-                "[send_programs] Job {}/{}: {} bytes",
+                "[send_programs] Job {}/{}: {} ops",
                 i + 1,
                 total,
-                bytes.len()
+                program.len()
             );
 
-            self.write_segment(&bytes)?;
-
-            self.file
-                .flush()
-                .map_err(|e| EstrellaError::Transport(format!("Flush failed: {}", e)))?;
+            self.send_program(program, config)?;
 
             // Pause between jobs (but not after the last one)
             if i < programs.len() - 1 {
@@ -211,66 +521,260 @@ impl BluetoothTransport {
         println!("[send_programs] All jobs sent successfully");
         Ok(())
     }
+}
 
-    /// Block until all written data has been physically transmitted.
+/// `io::Write` adapter that sends each write as one paced chunk.
+///
+/// Each `write` sends at most `chunk_size` bytes, then blocks on
+/// `tcdrain()` until they have left the OS buffer (plus the chunk delay
+/// and flow control, if set).
+struct PacedWriter<'a>(&'a mut BluetoothTransport);
+
+impl Write for PacedWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = buf.len().min(self.0.pacing.chunk_size);
+        self.0.write_chunk(&buf[..n]).map_err(io::Error::other)?;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.file.flush()
+    }
+}
+
+/// # Async Bluetooth Printer Transport
+///
+/// [`BluetoothTransport`] for async code. The device is opened
+/// non-blocking and registered with the tokio reactor; chunks are paced
+/// like the blocking transport, but waits for the link (and the chunk
+/// delay and flow control) are awaited instead of blocking a thread.
+#[cfg(feature = "async")]
+pub struct AsyncBluetoothTransport {
+    fd: AsyncFd<File>,
+    pacing: WritePacing,
+}
+
+#[cfg(feature = "async")]
+impl AsyncBluetoothTransport {
+    /// Open an RFCOMM device, with optional [`WritePacing`] options after
+    /// a `?` as for [`BluetoothTransport::open`].
     ///
-    /// This calls `tcdrain()` on the underlying file descriptor, which
-    /// blocks until the OS output buffer has been fully sent over the
-    /// Bluetooth RFCOMM link. Without this, `flush()` only pushes data
-    /// from userspace to the kernel buffer, and the printer may not have
-    /// received it yet.
-    #[cfg(unix)]
-    fn tcdrain(&self) -> Result<(), EstrellaError> {
-        let result = unsafe { libc::tcdrain(self.file.as_raw_fd()) };
-        if result != 0 {
-            return Err(EstrellaError::Transport(format!(
-                "tcdrain failed: {}",
-                io::Error::last_os_error()
-            )));
+    /// Must be called within a tokio runtime.
+    pub fn open<P: AsRef<Path>>(device: P) -> Result<Self, EstrellaError> {
+        let (path, pacing) = split_device(device.as_ref())?;
+        Self::open_with_pacing(path, pacing)
+    }
+
+    /// Open an RFCOMM device with explicit write pacing.
+    pub fn open_with_pacing<P: AsRef<Path>>(
+        device: P,
+        pacing: WritePacing,
+    ) -> Result<Self, EstrellaError> {
+        let file = open_tty(device.as_ref(), libc::O_NONBLOCK)?;
+        let fd = AsyncFd::new(file)
+            .map_err(|e| EstrellaError::Transport(format!("Failed to register device: {}", e)))?;
+        Ok(Self {
+            fd,
+            pacing: WritePacing {
+                chunk_size: pacing.chunk_size.max(1),
+                ..pacing
+            },
+        })
+    }
+
+    /// Write all of `data` as the device accepts it.
+    async fn write_raw(&self, mut data: &[u8]) -> Result<(), EstrellaError> {
+        while !data.is_empty() {
+            let mut guard = self
+                .fd
+                .writable()
+                .await
+                .map_err(|e| EstrellaError::Transport(format!("Write failed: {}", e)))?;
+            match guard.try_io(|file| file.get_ref().write(data)) {
+                Ok(Ok(n)) => data = &data[n..],
+                Ok(Err(e)) => {
+                    return Err(EstrellaError::Transport(format!("Write failed: {}", e)));
+                }
+                Err(_would_block) => continue,
+            }
         }
         Ok(())
     }
 
-    #[cfg(not(unix))]
-    fn tcdrain(&self) -> Result<(), EstrellaError> {
-        Ok(())
+    /// Read whatever the printer has sent, or `None` if nothing has
+    /// arrived.
+    fn try_read(&self) -> Result<Option<Vec<u8>>, EstrellaError> {
+        let mut buf = [0u8; STATUS_BUFFER_SIZE];
+        match self.fd.get_ref().read(&mut buf) {
+            Ok(0) => Ok(None),
+            Ok(n) => Ok(Some(buf[..n].to_vec())),
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => Ok(None),
+            Err(e) => Err(EstrellaError::Transport(format!("Read failed: {}", e))),
+        }
     }
 
-    /// Write a segment of data with chunking and pacing.
-    ///
-    /// Data is written in 4KB chunks. After each chunk, `tcdrain()` blocks
-    /// until the data has been physically transmitted over Bluetooth. This
-    /// naturally paces writes to the actual link throughput (~700 KB/s for
-    /// BT SPP), preventing the printer's ~100KB internal buffer from
-    /// overflowing.
-    fn write_segment(&mut self, data: &[u8]) -> Result<(), EstrellaError> {
-        if data.is_empty() {
-            return Ok(());
+    /// Wait until the OS has transmitted everything written so far (the
+    /// async equivalent of `tcdrain()`).
+    async fn drain(&self) -> Result<(), EstrellaError> {
+        while output_queue_len(self.fd.get_ref().as_raw_fd())? > 0 {
+            tokio::time::sleep(DRAIN_POLL).await;
         }
+        Ok(())
+    }
 
-        if data.len() <= self.chunk_size {
-            // Small write - send directly
-            self.file
-                .write_all(data)
-                .map_err(|e| EstrellaError::Transport(format!("Write failed: {}", e)))?;
-        } else {
-            // Large write - chunk it with tcdrain pacing
-            for chunk in data.chunks(self.chunk_size) {
-                self.file
-                    .write_all(chunk)
-                    .map_err(|e| EstrellaError::Transport(format!("Write failed: {}", e)))?;
-
-                // Block until this chunk has been physically transmitted.
-                // This prevents the OS from buffering all chunks at once,
-                // which would overwhelm the printer's internal buffer.
-                self.tcdrain()?;
+    /// Flow control, as [`BluetoothTransport`] does it.
+    async fn wait_while_offline(&self) -> Result<(), EstrellaError> {
+        let deadline = Instant::now() + OFFLINE_TIMEOUT;
+        let mut offline = false;
+        loop {
+            if let Some(data) = self.try_read()? {
+                if let Some(report) = AsbStatus::parse_all(&data).last() {
+                    offline = report.offline;
+                }
+                continue;
+            }
+            if !offline {
+                return Ok(());
+            }
+            if Instant::now() >= deadline {
+                return Err(EstrellaError::Transport(format!(
+                    "Printer stayed offline for {}s (cover open or out of paper?)",
+                    OFFLINE_TIMEOUT.as_secs()
+                )));
             }
+            tokio::time::sleep(OFFLINE_POLL).await;
         }
+    }
+
+    /// Write one chunk and pace it like [`BluetoothTransport`].
+    async fn write_chunk(&self, chunk: &[u8]) -> Result<(), EstrellaError> {
+        self.write_raw(chunk).await?;
+        self.drain().await?;
+        if !self.pacing.chunk_delay.is_zero() {
+            tokio::time::sleep(self.pacing.chunk_delay).await;
+        }
+        if self.pacing.flow_control {
+            self.wait_while_offline().await?;
+        }
+        Ok(())
+    }
+}
 
+#[cfg(feature = "async")]
+#[async_trait]
+impl AsyncTransport for AsyncBluetoothTransport {
+    /// Write data to the printer. Large writes are chunked and paced.
+    async fn write_all(&mut self, data: &[u8]) -> Result<(), EstrellaError> {
+        if data.len() <= self.pacing.chunk_size {
+            return self.write_raw(data).await;
+        }
+        for chunk in data.chunks(self.pacing.chunk_size) {
+            self.write_chunk(chunk).await?;
+        }
+        Ok(())
+    }
+
+    /// Wait until all written data has been transmitted.
+    async fn flush(&mut self) -> Result<(), EstrellaError> {
+        self.drain().await
+    }
+
+    /// Read a status response, waiting up to a second for it.
+    async fn read_status(&mut self) -> Result<Vec<u8>, EstrellaError> {
+        let timeout = Duration::from_millis(STATUS_TIMEOUT_DS as u64 * 100);
+        let read = async {
+            loop {
+                let mut guard = self
+                    .fd
+                    .readable()
+                    .await
+                    .map_err(|e| EstrellaError::Transport(format!("Read failed: {}", e)))?;
+                let mut buf = [0u8; STATUS_BUFFER_SIZE];
+                match guard.try_io(|file| file.get_ref().read(&mut buf)) {
+                    Ok(Ok(n)) if n > 0 => return Ok(buf[..n].to_vec()),
+                    Ok(Ok(_)) => guard.clear_ready(),
+                    Ok(Err(e)) => {
+                        return Err(EstrellaError::Transport(format!("Read failed: {}", e)));
+                    }
+                    Err(_would_block) => continue,
+                }
+            }
+        };
+        tokio::time::timeout(timeout, read)
+            .await
+            .map_err(|_| EstrellaError::Transport("No status response from printer".into()))?
+    }
+
+    /// Stream a program in paced chunks, enabling Automatic Status Back
+    /// first when flow control is on.
+    ///
+    /// Codegen runs one `chunk_size` block ahead of the writes, like the
+    /// blocking transport's `send_program`.
+    async fn send_program(
+        &mut self,
+        program: &Program,
+        config: &PrinterConfig,
+    ) -> Result<(), EstrellaError> {
+        if self.pacing.flow_control {
+            self.write_chunk(&status::asb_enable()).await?;
+        }
+        let chunk_size = self.pacing.chunk_size;
+        for bytes in program.byte_chunks(config, chunk_size) {
+            for chunk in bytes.chunks(chunk_size) {
+                self.write_chunk(chunk).await?;
+            }
+        }
         Ok(())
     }
 }
 
+/// Split a device string into its path and [`WritePacing`] options
+/// (`/dev/rfcomm0?chunk_size=1024`).
+fn split_device(device: &Path) -> Result<(&Path, WritePacing), EstrellaError> {
+    match device.to_str().and_then(|s| s.split_once('?')) {
+        Some((path, query)) => {
+            let pacing = WritePacing::from_query(query).map_err(EstrellaError::Transport)?;
+            Ok((Path::new(path), pacing))
+        }
+        None => Ok((device, WritePacing::default())),
+    }
+}
+
+/// Open an RFCOMM device read-write (with extra open `flags`) in raw mode.
+fn open_tty(path: &Path, flags: i32) -> Result<File, EstrellaError> {
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .custom_flags(flags)
+        .open(path)
+        .map_err(|e| {
+            EstrellaError::Transport(format!("Failed to open {}: {}", path.display(), e))
+        })?;
+
+    // Configure TTY for raw mode
+    configure_tty_raw(file.as_raw_fd())?;
+    Ok(file)
+}
+
+/// Bytes written to a TTY that haven't been transmitted yet (`TIOCOUTQ`).
+#[cfg(all(unix, feature = "async"))]
+fn output_queue_len(fd: i32) -> Result<usize, EstrellaError> {
+    let mut queued: libc::c_int = 0;
+    let result = unsafe { libc::ioctl(fd, libc::TIOCOUTQ, &mut queued) };
+    if result != 0 {
+        return Err(EstrellaError::Transport(format!(
+            "TIOCOUTQ failed: {}",
+            io::Error::last_os_error()
+        )));
+    }
+    Ok(queued.max(0) as usize)
+}
+
+#[cfg(all(not(unix), feature = "async"))]
+fn output_queue_len(_fd: i32) -> Result<usize, EstrellaError> {
+    Ok(0)
+}
+
 /// Configure a file descriptor for raw TTY mode.
 ///
 /// This disables all input/output processing so binary data passes through
@@ -324,6 +828,10 @@ fn configure_tty_raw(fd: i32) -> Result<(), EstrellaError> {
     termios.c_cflag &= !(libc::CSIZE | libc::PARENB);
     termios.c_cflag |= libc::CS8;
 
+    // Reads return whatever arrived, or nothing after STATUS_TIMEOUT_DS
+    termios.c_cc[libc::VMIN] = 0;
+    termios.c_cc[libc::VTIME] = STATUS_TIMEOUT_DS;
+
     // Apply settings immediately
     let result = unsafe { libc::tcsetattr(fd, libc::TCSANOW, &termios) };
     if result != 0 {
@@ -531,6 +1039,37 @@ mod tests {
         assert!(!is_valid_mac("not-a-mac")); // garbage
     }
 
+    #[test]
+    fn test_split_device() {
+        let (path, pacing) = split_device(Path::new("/dev/rfcomm1?chunk_size=256")).unwrap();
+        assert_eq!(path, Path::new("/dev/rfcomm1"));
+        assert_eq!(pacing.chunk_size, 256);
+
+        let (path, pacing) = split_device(Path::new(DEFAULT_DEVICE)).unwrap();
+        assert_eq!(path, Path::new(DEFAULT_DEVICE));
+        assert_eq!(pacing, WritePacing::default());
+
+        assert!(split_device(Path::new("/dev/rfcomm0?speed=fast")).is_err());
+    }
+
+    #[test]
+    fn test_write_pacing_from_query() {
+        assert_eq!(WritePacing::from_query("").unwrap(), WritePacing::default());
+        assert_eq!(
+            WritePacing::from_query("chunk_size=512&chunk_delay_ms=15&flow_control=true").unwrap(),
+            WritePacing {
+                chunk_size: 512,
+                chunk_delay: Duration::from_millis(15),
+                flow_control: true,
+            }
+        );
+        assert!(WritePacing::from_query("chunk_size=0").is_err());
+        assert!(WritePacing::from_query("chunk_size=big").is_err());
+        assert!(WritePacing::from_query("flow_control=yes").is_err());
+        assert!(WritePacing::from_query("baud=9600").is_err());
+        assert!(WritePacing::from_query("chunk_size").is_err());
+    }
+
     // Note: Most transport tests require actual hardware.
     // Integration tests should be run manually with a connected printer.
 }
@@ -0,0 +1,254 @@
+//! # Mock Transport
+//!
+//! An in-memory printer for tests. [`MockTransport`] records every byte it
+//! is sent and answers status reads from a queue of canned responses, so
+//! the path from a [`Document`](crate::document::Document) to the wire can
+//! be tested without hardware.
+//!
+//! ## Example
+//!
+//! ```
+//! use estrella::document::{Component, Document, Text};
+//! use estrella::printer::PrinterConfig;
+//! use estrella::transport::{MockTransport, Transport};
+//!
+//! let doc = Document {
+//!     document: vec![Component::Text(Text {
+//!         content: "Hello".into(),
+//!         ..Default::default()
+//!     })],
+//!     ..Default::default()
+//! };
+//!
+//! let mut printer = MockTransport::new();
+//! printer.send_program(&doc.print_program()?, &PrinterConfig::TSP650II)?;
+//! assert_eq!(printer.bytes(), doc.build()?.as_slice());
+//!
+//! # Ok::<(), estrella::error::EstrellaError>(())
+//! ```
+//!
+//! ## Golden Byte Streams
+//!
+//! [`MockTransport::assert_golden`] compares the captured bytes with a file.
+//! Run the tests with `UPDATE_GOLDEN=1` to (re)write the file instead.
+
+use std::collections::VecDeque;
+use std::fs;
+use std::path::Path;
+
+use super::Transport;
+use crate::error::EstrellaError;
+use crate::ir::Program;
+use crate::printer::PrinterConfig;
+
+/// Set to rewrite golden files from the captured bytes.
+const UPDATE_GOLDEN_VAR: &str = "UPDATE_GOLDEN";
+
+/// # Recording Printer Transport
+///
+/// Implements [`Transport`] without a printer, plus accessors for what
+/// was sent.
+#[derive(Debug, Default)]
+pub struct MockTransport {
+    /// Everything written so far.
+    written: Vec<u8>,
+    /// End offset in `written` of each program sent.
+    job_ends: Vec<usize>,
+    /// Responses returned by `read_status`, oldest first.
+    status: VecDeque<Vec<u8>>,
+}
+
+impl MockTransport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queue a status response for the next [`Transport::read_status`].
+    pub fn push_status(&mut self, response: impl Into<Vec<u8>>) {
+        self.status.push_back(response.into());
+    }
+
+    /// All bytes written so far.
+    pub fn bytes(&self) -> &[u8] {
+        &self.written
+    }
+
+    /// The bytes of each program sent, in order. Raw
+    /// [`Transport::write_all`] data counts toward the next job.
+    pub fn jobs(&self) -> Vec<&[u8]> {
+        let mut start = 0;
+        self.job_ends
+            .iter()
+            .map(|&end| {
+                let job = &self.written[start..end];
+                start = end;
+                job
+            })
+            .collect()
+    }
+
+    /// Forget everything written so far (queued status responses are kept).
+    pub fn clear(&mut self) {
+        self.written.clear();
+        self.job_ends.clear();
+    }
+
+    /// Write the captured bytes to a file, e.g. to inspect them or to
+    /// create a golden file. [`Program::from_bytes`] reads them back as IR.
+    pub fn dump(&self, path: impl AsRef<Path>) -> Result<(), EstrellaError> {
+        fs::write(path, &self.written)?;
+        Ok(())
+    }
+
+    /// Assert that the captured bytes match a golden file.
+    ///
+    /// With `UPDATE_GOLDEN=1` in the environment the file is written
+    /// instead, so intentional changes can be accepted by re-running.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if the file is missing or differs, naming the first
+    /// differing byte offset.
+    pub fn assert_golden(&self, path: impl AsRef<Path>) {
+        let path = path.as_ref();
+        if std::env::var_os(UPDATE_GOLDEN_VAR).is_some() {
+            self.dump(path)
+                .unwrap_or_else(|e| panic!("Failed to write {}: {}", path.display(), e));
+            return;
+        }
+
+        let golden = fs::read(path).unwrap_or_else(|e| {
+            panic!(
+                "Golden file {} not readable ({}). Run with {}=1 to create it.",
+                path.display(),
+                e,
+                UPDATE_GOLDEN_VAR
+            )
+        });
+        if let Some(offset) = first_difference(&self.written, &golden) {
+            panic!(
+                "Captured bytes differ from {} at byte {:#06x} ({} bytes captured, {} expected). \
+                 Run with {}=1 to update it if the change is intentional.",
+                path.display(),
+                offset,
+                self.written.len(),
+                golden.len(),
+                UPDATE_GOLDEN_VAR
+            );
+        }
+    }
+}
+
+impl Transport for MockTransport {
+    /// Record data as if it were written to the printer.
+    fn write_all(&mut self, data: &[u8]) -> Result<(), EstrellaError> {
+        self.written.extend_from_slice(data);
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), EstrellaError> {
+        Ok(())
+    }
+
+    /// Read the next queued status response.
+    ///
+    /// ## Errors
+    ///
+    /// Returns an error if no response is queued, like a printer that
+    /// doesn't answer.
+    fn read_status(&mut self) -> Result<Vec<u8>, EstrellaError> {
+        self.status
+            .pop_front()
+            .ok_or_else(|| EstrellaError::Transport("No status response from printer".into()))
+    }
+
+    /// Record a program's bytes as one job.
+    fn send_program(
+        &mut self,
+        program: &Program,
+        config: &PrinterConfig,
+    ) -> Result<(), EstrellaError> {
+        program
+            .write_to(&mut self.written, config)
+            .map_err(|e| EstrellaError::Transport(format!("Write failed: {}", e)))?;
+        self.job_ends.push(self.written.len());
+        Ok(())
+    }
+
+    /// Record multiple programs as separate jobs (without the real
+    /// transports' pauses).
+    fn send_programs(
+        &mut self,
+        programs: &[Program],
+        config: &PrinterConfig,
+    ) -> Result<(), EstrellaError> {
+        for program in programs {
+            self.send_program(program, config)?;
+        }
+        Ok(())
+    }
+}
+
+/// Offset of the first differing byte, or `None` if the streams are equal.
+fn first_difference(a: &[u8], b: &[u8]) -> Option<usize> {
+    a.iter()
+        .zip(b)
+        .position(|(x, y)| x != y)
+        .or_else(|| (a.len() != b.len()).then_some(a.len().min(b.len())))
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::Op;
+
+    #[test]
+    fn test_records_jobs() {
+        let mut first = Program::with_init();
+        first.push(Op::Text("one".into()));
+        let mut second = Program::new();
+        second.push(Op::Cut { partial: true });
+
+        let mut printer = MockTransport::new();
+        printer
+            .send_programs(&[first.clone(), second.clone()], &PrinterConfig::TSP650II)
+            .unwrap();
+
+        let jobs = printer.jobs();
+        assert_eq!(jobs, [first.to_bytes(), second.to_bytes()]);
+        assert_eq!(printer.bytes(), [jobs[0], jobs[1]].concat());
+
+        printer.clear();
+        assert!(printer.bytes().is_empty());
+        assert!(printer.jobs().is_empty());
+    }
+
+    #[test]
+    fn test_status_queue() {
+        let mut printer = MockTransport::new();
+        printer.push_status([0x23, 0x86]);
+        printer.push_status(vec![0x23, 0x8e]);
+
+        assert_eq!(printer.read_status().unwrap(), [0x23, 0x86]);
+        assert_eq!(printer.read_status().unwrap(), [0x23, 0x8e]);
+        assert!(printer.read_status().is_err());
+    }
+
+    #[test]
+    fn test_golden_round_trip() {
+        let path = std::env::temp_dir().join(format!("estrella-mock-{}.bin", std::process::id()));
+        let mut printer = MockTransport::new();
+        printer.write_all(b"\x1b@hello").unwrap();
+        printer.dump(&path).unwrap();
+        printer.assert_golden(&path);
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(first_difference(b"abc", b"abc"), None);
+        assert_eq!(first_difference(b"abc", b"abd"), Some(2));
+        assert_eq!(first_difference(b"ab", b"abc"), Some(2));
+    }
+}
@@ -1,17 +1,388 @@
 //! # Printer Transport Layer
 //!
 //! This module provides communication backends for sending data to printers.
+//! Every backend implements the [`Transport`] trait, so code that prints
+//! takes a `&mut dyn Transport` (or a `Box<dyn Transport>`) and works with
+//! any of them, including transports defined outside this crate.
 //!
 //! ## Available Transports
 //!
 //! - [`bluetooth`]: Bluetooth RFCOMM for wireless printing (Linux)
+//! - [`tcp`]: Raw TCP (port 9100) for LAN-connected printers
+//! - [`mock`]: In-memory recorder for tests
+//!
+//! [`open`] picks the transport from the device string: `tcp://host[:port]`
+//! connects over the network, anything else is opened as an RFCOMM device
+//! path.
+//!
+//! ## Async Transports
+//!
+//! With the `async` feature, [`AsyncTransport`] is the same interface for async code (the server):
+//! writes are awaited on the tokio runtime instead of blocking a thread, so
+//! dropping the future stops sending. [`open_async`] picks between
+//! [`AsyncTcpTransport`] and [`AsyncBluetoothTransport`] like [`open`]
+//! does, and [`Blocking`] adapts any [`Transport`].
 //!
 //! ## Future Transports
 //!
 //! - USB serial
-//! - Network (TCP/IP)
-//! - Mock transport for testing
 
 pub mod bluetooth;
+pub mod mock;
+pub mod tcp;
 
+#[cfg(feature = "async")]
+pub use bluetooth::AsyncBluetoothTransport;
 pub use bluetooth::BluetoothTransport;
+pub use mock::MockTransport;
+#[cfg(feature = "async")]
+pub use tcp::AsyncTcpTransport;
+pub use tcp::TcpTransport;
+
+#[cfg(feature = "async")]
+use std::future::Future;
+#[cfg(feature = "async")]
+use std::pin::Pin;
+#[cfg(feature = "async")]
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+#[cfg(feature = "async")]
+use async_trait::async_trait;
+
+use crate::error::EstrellaError;
+use crate::ir::Program;
+use crate::printer::PrinterConfig;
+
+/// Delay between independent print jobs in [`Transport::send_programs`].
+pub const JOB_DELAY: Duration = Duration::from_millis(2000);
+
+/// Bytes generated per write when the provided `send_program` of
+/// [`Transport`] and [`AsyncTransport`] streams a program.
+pub const STREAM_CHUNK_SIZE: usize = 16 * 1024;
+
+/// A connection that print data can be written to.
+///
+/// Implement [`write_all`](Self::write_all), [`flush`](Self::flush) and
+/// [`read_status`](Self::read_status); the provided methods build on them.
+/// [`send_program`](Self::send_program) streams codegen output in chunks;
+/// backends override it to pace those writes themselves.
+///
+/// ## Example
+///
+/// ```no_run
+/// use estrella::protocol::commands;
+/// use estrella::transport::{self, Transport};
+///
+/// fn reset(printer: &mut dyn Transport) -> Result<(), estrella::EstrellaError> {
+///     printer.write_all(&commands::init())?;
+///     printer.flush()
+/// }
+///
+/// let mut printer = transport::open("tcp://192.168.1.50:9100")?;
+/// reset(printer.as_mut())?;
+///
+/// # Ok::<(), estrella::error::EstrellaError>(())
+/// ```
+pub trait Transport: Send {
+    /// Write data to the printer.
+    fn write_all(&mut self, data: &[u8]) -> Result<(), EstrellaError>;
+
+    /// Block until everything written so far has left this machine.
+    fn flush(&mut self) -> Result<(), EstrellaError>;
+
+    /// Read the printer's next status response (e.g. after sending a
+    /// status request command).
+    ///
+    /// ## Errors
+    ///
+    /// Returns an error if the printer doesn't answer in time or the
+    /// transport can't receive data.
+    fn read_status(&mut self) -> Result<Vec<u8>, EstrellaError>;
+
+    /// Send a program to the printer, generating its bytes one
+    /// [`STREAM_CHUNK_SIZE`] chunk at a time as they are written.
+    fn send_program(
+        &mut self,
+        program: &Program,
+        config: &PrinterConfig,
+    ) -> Result<(), EstrellaError> {
+        for chunk in program.byte_chunks(config, STREAM_CHUNK_SIZE) {
+            self.write_all(&chunk)?;
+        }
+        self.flush()
+    }
+
+    /// Send multiple independent print programs, pausing for [`JOB_DELAY`]
+    /// after each so the printer finishes it before receiving the next.
+    fn send_programs(
+        &mut self,
+        programs: &[Program],
+        config: &PrinterConfig,
+    ) -> Result<(), EstrellaError> {
+        for (i, program) in programs.iter().enumerate() {
+            self.send_program(program, config)?;
+            if i + 1 < programs.len() {
+                self.flush()?;
+                thread::sleep(JOB_DELAY);
+            }
+        }
+        self.flush()
+    }
+}
+
+impl<T: Transport + ?Sized> Transport for Box<T> {
+    fn write_all(&mut self, data: &[u8]) -> Result<(), EstrellaError> {
+        (**self).write_all(data)
+    }
+
+    fn flush(&mut self) -> Result<(), EstrellaError> {
+        (**self).flush()
+    }
+
+    fn read_status(&mut self) -> Result<Vec<u8>, EstrellaError> {
+        (**self).read_status()
+    }
+
+    fn send_program(
+        &mut self,
+        program: &Program,
+        config: &PrinterConfig,
+    ) -> Result<(), EstrellaError> {
+        (**self).send_program(program, config)
+    }
+
+    fn send_programs(
+        &mut self,
+        programs: &[Program],
+        config: &PrinterConfig,
+    ) -> Result<(), EstrellaError> {
+        (**self).send_programs(programs, config)
+    }
+}
+
+/// A connection that print data can be written to without blocking the
+/// async runtime.
+///
+/// The async counterpart of [`Transport`], with the same methods. Dropping
+/// a pending call stops sending at the next await, so a caller can cancel
+/// between [`send_program`](Self::send_program) calls and leave the printer
+/// on a command boundary.
+///
+/// ## Example
+///
+/// ```no_run
+/// use estrella::ir::Program;
+/// use estrella::printer::PrinterConfig;
+/// use estrella::transport;
+///
+/// # async fn example() -> Result<(), estrella::EstrellaError> {
+/// let mut printer = transport::open_async("tcp://192.168.1.50:9100").await?;
+/// printer
+///     .send_program(&Program::with_init(), &PrinterConfig::TSP650II)
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(feature = "async")]
+#[async_trait]
+pub trait AsyncTransport: Send {
+    /// Write data to the printer.
+    async fn write_all(&mut self, data: &[u8]) -> Result<(), EstrellaError>;
+
+    /// Wait until everything written so far has left this machine.
+    async fn flush(&mut self) -> Result<(), EstrellaError>;
+
+    /// Read the printer's next status response.
+    ///
+    /// ## Errors
+    ///
+    /// Returns an error if the printer doesn't answer in time or the
+    /// transport can't receive data.
+    async fn read_status(&mut self) -> Result<Vec<u8>, EstrellaError>;
+
+    /// Send a program to the printer, generating its bytes one
+    /// [`STREAM_CHUNK_SIZE`] chunk at a time as they are written.
+    async fn send_program(
+        &mut self,
+        program: &Program,
+        config: &PrinterConfig,
+    ) -> Result<(), EstrellaError> {
+        for chunk in program.byte_chunks(config, STREAM_CHUNK_SIZE) {
+            self.write_all(&chunk).await?;
+        }
+        self.flush().await
+    }
+
+    /// Send multiple independent print programs, pausing for [`JOB_DELAY`]
+    /// after each so the printer finishes it before receiving the next.
+    async fn send_programs(
+        &mut self,
+        programs: &[Program],
+        config: &PrinterConfig,
+    ) -> Result<(), EstrellaError> {
+        for (i, program) in programs.iter().enumerate() {
+            self.send_program(program, config).await?;
+            if i + 1 < programs.len() {
+                self.flush().await?;
+                tokio::time::sleep(JOB_DELAY).await;
+            }
+        }
+        self.flush().await
+    }
+}
+
+#[cfg(feature = "async")]
+#[async_trait]
+impl<T: AsyncTransport + ?Sized> AsyncTransport for Box<T> {
+    async fn write_all(&mut self, data: &[u8]) -> Result<(), EstrellaError> {
+        (**self).write_all(data).await
+    }
+
+    async fn flush(&mut self) -> Result<(), EstrellaError> {
+        (**self).flush().await
+    }
+
+    async fn read_status(&mut self) -> Result<Vec<u8>, EstrellaError> {
+        (**self).read_status().await
+    }
+
+    async fn send_program(
+        &mut self,
+        program: &Program,
+        config: &PrinterConfig,
+    ) -> Result<(), EstrellaError> {
+        (**self).send_program(program, config).await
+    }
+
+    async fn send_programs(
+        &mut self,
+        programs: &[Program],
+        config: &PrinterConfig,
+    ) -> Result<(), EstrellaError> {
+        (**self).send_programs(programs, config).await
+    }
+}
+
+/// # Blocking Transport Adapter
+///
+/// Runs a synchronous [`Transport`] on tokio's blocking pool so it can be
+/// used as an [`AsyncTransport`], e.g. a custom transport behind the
+/// server. Each call is one blocking task; a dropped call still finishes
+/// the write it started.
+///
+/// ```
+/// use estrella::ir::Program;
+/// use estrella::printer::PrinterConfig;
+/// use estrella::transport::{AsyncTransport, Blocking, MockTransport};
+///
+/// # tokio::runtime::Runtime::new().unwrap().block_on(async {
+/// let mut printer = Blocking::new(MockTransport::new());
+/// let program = Program::with_init();
+/// printer.send_program(&program, &PrinterConfig::TSP650II).await.unwrap();
+/// assert_eq!(printer.into_inner().bytes(), program.to_bytes());
+/// # });
+/// ```
+#[cfg(feature = "async")]
+pub struct Blocking<T>(Arc<Mutex<T>>);
+
+#[cfg(feature = "async")]
+impl<T: Transport + 'static> Blocking<T> {
+    pub fn new(transport: T) -> Self {
+        Self(Arc::new(Mutex::new(transport)))
+    }
+
+    /// The wrapped transport.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if a dropped call is still running on the blocking pool.
+    pub fn into_inner(self) -> T {
+        Arc::try_unwrap(self.0)
+            .ok()
+            .expect("blocking transport call still running")
+            .into_inner()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Run `f` with the transport on the blocking pool.
+    async fn run<R, F>(&self, f: F) -> Result<R, EstrellaError>
+    where
+        R: Send + 'static,
+        F: FnOnce(&mut T) -> Result<R, EstrellaError> + Send + 'static,
+    {
+        let transport = Arc::clone(&self.0);
+        tokio::task::spawn_blocking(move || {
+            let mut transport = transport
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            f(&mut transport)
+        })
+        .await
+        .map_err(|e| EstrellaError::Transport(format!("Task error: {}", e)))?
+    }
+}
+
+#[cfg(feature = "async")]
+#[async_trait]
+impl<T: Transport + 'static> AsyncTransport for Blocking<T> {
+    async fn write_all(&mut self, data: &[u8]) -> Result<(), EstrellaError> {
+        let data = data.to_vec();
+        self.run(move |t| t.write_all(&data)).await
+    }
+
+    async fn flush(&mut self) -> Result<(), EstrellaError> {
+        self.run(|t| t.flush()).await
+    }
+
+    async fn read_status(&mut self) -> Result<Vec<u8>, EstrellaError> {
+        self.run(|t| t.read_status()).await
+    }
+
+    async fn send_program(
+        &mut self,
+        program: &Program,
+        config: &PrinterConfig,
+    ) -> Result<(), EstrellaError> {
+        let (program, config) = (program.clone(), *config);
+        self.run(move |t| t.send_program(&program, &config)).await
+    }
+}
+
+/// Opens a printer from a device string. [`open`] is the standard one;
+/// provide your own to route prints through a custom [`Transport`].
+pub type OpenTransport = fn(&str) -> Result<Box<dyn Transport>, EstrellaError>;
+
+/// A boxed future, as returned by [`OpenAsyncTransport`] functions.
+#[cfg(feature = "async")]
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// Opens a printer for async use. [`open_async`] is the standard one;
+/// wrap a custom [`Transport`] in [`Blocking`] to provide your own.
+#[cfg(feature = "async")]
+pub type OpenAsyncTransport =
+    for<'a> fn(&'a str) -> BoxFuture<'a, Result<Box<dyn AsyncTransport>, EstrellaError>>;
+
+/// Open `tcp://host[:port]` over the network, or any other string as a
+/// Bluetooth RFCOMM device path.
+pub fn open(device: &str) -> Result<Box<dyn Transport>, EstrellaError> {
+    if device.starts_with(tcp::SCHEME) {
+        Ok(Box::new(TcpTransport::open(device)?))
+    } else {
+        Ok(Box::new(BluetoothTransport::open(device)?))
+    }
+}
+
+/// [`open`] for async code: connect to `tcp://host[:port]` without
+/// blocking, or open any other string as a Bluetooth RFCOMM device path.
+#[cfg(feature = "async")]
+pub fn open_async(device: &str) -> BoxFuture<'_, Result<Box<dyn AsyncTransport>, EstrellaError>> {
+    Box::pin(async move {
+        if device.starts_with(tcp::SCHEME) {
+            Ok(Box::new(AsyncTcpTransport::open(device).await?) as Box<dyn AsyncTransport>)
+        } else {
+            Ok(Box::new(AsyncBluetoothTransport::open(device)?) as Box<dyn AsyncTransport>)
+        }
+    })
+}
@@ -0,0 +1,365 @@
+//! # TCP/IP Transport
+//!
+//! This module sends data to LAN-connected Star printers over a raw TCP
+//! socket (port 9100, a.k.a. "RAW" or "JetDirect" printing). The printer
+//! treats the byte stream exactly like the Bluetooth serial stream, so the
+//! same StarPRNT programs work unchanged.
+//!
+//! ## Device Strings
+//!
+//! Addresses are written as `tcp://host[:port]`, e.g. `tcp://192.168.1.50:9100`.
+//! The port defaults to 9100.
+//!
+//! ## Flow Control
+//!
+//! Unlike RFCOMM, TCP has its own flow control: when the printer's buffer
+//! fills up it stops acknowledging, and writes block until it catches up.
+//! No manual pacing is needed, so programs are streamed through a plain
+//! buffered writer.
+//!
+//! [`AsyncTcpTransport`] is the same connection on tokio's non-blocking
+//! sockets.
+
+use std::io::{BufWriter, ErrorKind, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+#[cfg(feature = "async")]
+use async_trait::async_trait;
+#[cfg(feature = "async")]
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+#[cfg(feature = "async")]
+use super::AsyncTransport;
+use super::Transport;
+use crate::error::EstrellaError;
+use crate::ir::Program;
+use crate::printer::PrinterConfig;
+
+/// URL scheme that selects this transport.
+pub const SCHEME: &str = "tcp://";
+
+/// Default raw printing port.
+pub const DEFAULT_PORT: u16 = 9100;
+
+/// How long to wait for the printer to accept a connection.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How long a single write may block before the printer is considered gone.
+const WRITE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How long `read_status` waits for the printer to answer.
+const STATUS_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Largest status response read at once (bytes).
+const STATUS_BUFFER_SIZE: usize = 64;
+
+/// Buffer size for streamed programs (bytes).
+const BUFFER_SIZE: usize = 4096;
+
+/// Parse a `tcp://host[:port]` device string into a `host:port` address.
+///
+/// Returns `None` if the string doesn't use the `tcp://` scheme.
+///
+/// ```
+/// use estrella::transport::tcp::parse_address;
+///
+/// assert_eq!(parse_address("tcp://192.168.1.50").as_deref(), Some("192.168.1.50:9100"));
+/// assert_eq!(parse_address("tcp://printer.lan:9101").as_deref(), Some("printer.lan:9101"));
+/// assert_eq!(parse_address("/dev/rfcomm0"), None);
+/// ```
+pub fn parse_address(device: &str) -> Option<String> {
+    let rest = device.strip_prefix(SCHEME)?.trim_end_matches('/');
+    let has_port = match rest.strip_prefix('[') {
+        // IPv6 literal: [addr]:port
+        Some(v6) => v6.contains("]:"),
+        None => rest.contains(':'),
+    };
+    Some(if has_port {
+        rest.to_string()
+    } else {
+        format!("{}:{}", rest, DEFAULT_PORT)
+    })
+}
+
+/// # TCP Printer Transport
+///
+/// Manages a raw TCP connection to a network printer. Independent jobs
+/// sent with [`Transport::send_programs`] are paused like over Bluetooth.
+///
+/// ## Example
+///
+/// ```no_run
+/// use estrella::transport::{TcpTransport, Transport};
+/// use estrella::protocol::commands;
+///
+/// let mut transport = TcpTransport::open("tcp://192.168.1.50:9100")?;
+/// transport.write_all(&commands::init())?;
+///
+/// # Ok::<(), estrella::error::EstrellaError>(())
+/// ```
+pub struct TcpTransport {
+    stream: TcpStream,
+}
+
+impl TcpTransport {
+    /// Connect to a printer given as `tcp://host[:port]`.
+    ///
+    /// ## Errors
+    ///
+    /// Returns an error if the device string isn't a `tcp://` address, the
+    /// host can't be resolved, or the printer doesn't accept the connection.
+    pub fn open(device: &str) -> Result<Self, EstrellaError> {
+        let address = parse_address(device).ok_or_else(|| {
+            EstrellaError::Transport(format!(
+                "Invalid TCP device '{}'. Expected tcp://host[:port]",
+                device
+            ))
+        })?;
+        Self::connect(&address)
+    }
+
+    /// Connect to a printer at a `host:port` address.
+    pub fn connect(address: &str) -> Result<Self, EstrellaError> {
+        let addrs = address.to_socket_addrs().map_err(|e| {
+            EstrellaError::Transport(format!("Failed to resolve {}: {}", address, e))
+        })?;
+
+        let mut last_error = None;
+        for addr in addrs {
+            match TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT) {
+                Ok(stream) => {
+                    stream
+                        .set_write_timeout(Some(WRITE_TIMEOUT))
+                        .and_then(|()| stream.set_read_timeout(Some(STATUS_TIMEOUT)))
+                        .and_then(|()| stream.set_nodelay(true))
+                        .map_err(|e| {
+                            EstrellaError::Transport(format!("Failed to configure socket: {}", e))
+                        })?;
+                    return Ok(Self { stream });
+                }
+                Err(e) => last_error = Some(e),
+            }
+        }
+
+        Err(EstrellaError::Transport(match last_error {
+            Some(e) => format!("Failed to connect to {}: {}", address, e),
+            None => format!("No addresses found for {}", address),
+        }))
+    }
+}
+
+impl Transport for TcpTransport {
+    /// Write data to the printer.
+    fn write_all(&mut self, data: &[u8]) -> Result<(), EstrellaError> {
+        self.stream
+            .write_all(data)
+            .and_then(|()| self.stream.flush())
+            .map_err(|e| EstrellaError::Transport(format!("Write failed: {}", e)))
+    }
+
+    fn flush(&mut self) -> Result<(), EstrellaError> {
+        self.stream
+            .flush()
+            .map_err(|e| EstrellaError::Transport(format!("Flush failed: {}", e)))
+    }
+
+    /// Read a status response, waiting up to two seconds for it.
+    fn read_status(&mut self) -> Result<Vec<u8>, EstrellaError> {
+        let mut buf = [0u8; STATUS_BUFFER_SIZE];
+        match self.stream.read(&mut buf) {
+            Ok(0) => Err(EstrellaError::Transport(
+                "Printer closed the connection".into(),
+            )),
+            Ok(n) => Ok(buf[..n].to_vec()),
+            Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => Err(
+                EstrellaError::Transport("No status response from printer".into()),
+            ),
+            Err(e) => Err(EstrellaError::Transport(format!("Read failed: {}", e))),
+        }
+    }
+
+    /// Stream a program to the printer.
+    fn send_program(
+        &mut self,
+        program: &Program,
+        config: &PrinterConfig,
+    ) -> Result<(), EstrellaError> {
+        let mut writer = BufWriter::with_capacity(BUFFER_SIZE, &mut self.stream);
+        program
+            .write_to(&mut writer, config)
+            .and_then(|()| writer.flush())
+            .map_err(|e| EstrellaError::Transport(format!("Write failed: {}", e)))
+    }
+}
+
+/// # Async TCP Printer Transport
+///
+/// [`TcpTransport`] for async code: connects and writes on tokio's
+/// non-blocking sockets, with the same timeouts.
+#[cfg(feature = "async")]
+pub struct AsyncTcpTransport {
+    stream: tokio::net::TcpStream,
+}
+
+#[cfg(feature = "async")]
+impl AsyncTcpTransport {
+    /// Connect to a printer given as `tcp://host[:port]`.
+    ///
+    /// ## Errors
+    ///
+    /// Same as [`TcpTransport::open`].
+    pub async fn open(device: &str) -> Result<Self, EstrellaError> {
+        let address = parse_address(device).ok_or_else(|| {
+            EstrellaError::Transport(format!(
+                "Invalid TCP device '{}'. Expected tcp://host[:port]",
+                device
+            ))
+        })?;
+        Self::connect(&address).await
+    }
+
+    /// Connect to a printer at a `host:port` address.
+    pub async fn connect(address: &str) -> Result<Self, EstrellaError> {
+        let stream = tokio::time::timeout(CONNECT_TIMEOUT, tokio::net::TcpStream::connect(address))
+            .await
+            .map_err(|_| EstrellaError::Transport(format!("Timed out connecting to {}", address)))?
+            .map_err(|e| {
+                EstrellaError::Transport(format!("Failed to connect to {}: {}", address, e))
+            })?;
+        stream
+            .set_nodelay(true)
+            .map_err(|e| EstrellaError::Transport(format!("Failed to configure socket: {}", e)))?;
+        Ok(Self { stream })
+    }
+}
+
+#[cfg(feature = "async")]
+#[async_trait]
+impl AsyncTransport for AsyncTcpTransport {
+    /// Write data to the printer, giving up if a write blocks for more than
+    /// 30 seconds.
+    async fn write_all(&mut self, data: &[u8]) -> Result<(), EstrellaError> {
+        tokio::time::timeout(WRITE_TIMEOUT, self.stream.write_all(data))
+            .await
+            .map_err(|_| EstrellaError::Transport("Write timed out".into()))?
+            .map_err(|e| EstrellaError::Transport(format!("Write failed: {}", e)))
+    }
+
+    async fn flush(&mut self) -> Result<(), EstrellaError> {
+        self.stream
+            .flush()
+            .await
+            .map_err(|e| EstrellaError::Transport(format!("Flush failed: {}", e)))
+    }
+
+    /// Read a status response, waiting up to two seconds for it.
+    async fn read_status(&mut self) -> Result<Vec<u8>, EstrellaError> {
+        let mut buf = [0u8; STATUS_BUFFER_SIZE];
+        match tokio::time::timeout(STATUS_TIMEOUT, self.stream.read(&mut buf)).await {
+            Err(_) => Err(EstrellaError::Transport(
+                "No status response from printer".into(),
+            )),
+            Ok(Ok(0)) => Err(EstrellaError::Transport(
+                "Printer closed the connection".into(),
+            )),
+            Ok(Ok(n)) => Ok(buf[..n].to_vec()),
+            Ok(Err(e)) => Err(EstrellaError::Transport(format!("Read failed: {}", e))),
+        }
+    }
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::Op;
+    use std::net::TcpListener;
+    use std::thread;
+
+    #[test]
+    fn test_parse_address() {
+        assert_eq!(parse_address("tcp://10.0.0.2").unwrap(), "10.0.0.2:9100");
+        assert_eq!(
+            parse_address("tcp://10.0.0.2:9101/").unwrap(),
+            "10.0.0.2:9101"
+        );
+        assert_eq!(parse_address("tcp://[::1]").unwrap(), "[::1]:9100");
+        assert_eq!(parse_address("tcp://[::1]:9101").unwrap(), "[::1]:9101");
+        assert!(parse_address("10.0.0.2:9100").is_none());
+        assert!(parse_address("/dev/rfcomm0").is_none());
+    }
+
+    #[test]
+    fn test_send_program_over_socket() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let device = format!("tcp://{}", listener.local_addr().unwrap());
+        let reader = thread::spawn(move || {
+            let (mut socket, _) = listener.accept().unwrap();
+            let mut received = Vec::new();
+            socket.read_to_end(&mut received).unwrap();
+            received
+        });
+
+        let mut program = Program::with_init();
+        program.push(Op::Text("Hello".into()));
+        program.push(Op::Newline);
+        {
+            let mut transport = TcpTransport::open(&device).unwrap();
+            transport
+                .send_program(&program, &PrinterConfig::TSP650II)
+                .unwrap();
+        }
+
+        assert_eq!(reader.join().unwrap(), program.to_bytes());
+    }
+
+    #[test]
+    fn test_read_status() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let device = format!("tcp://{}", listener.local_addr().unwrap());
+        let printer = thread::spawn(move || {
+            let (mut socket, _) = listener.accept().unwrap();
+            socket.write_all(&[0x23, 0x86]).unwrap();
+            // Keep the socket open until the client has read the status
+            let mut rest = Vec::new();
+            socket.read_to_end(&mut rest).unwrap();
+        });
+
+        {
+            let mut transport = TcpTransport::open(&device).unwrap();
+            assert_eq!(transport.read_status().unwrap(), [0x23, 0x86]);
+        }
+        printer.join().unwrap();
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn test_async_send_programs_over_socket() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let device = format!("tcp://{}", listener.local_addr().unwrap());
+        let reader = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            socket.write_all(&[0x23, 0x86]).await.unwrap();
+            let mut received = Vec::new();
+            socket.read_to_end(&mut received).await.unwrap();
+            received
+        });
+
+        let mut program = Program::with_init();
+        program.push(Op::Text("Hello".into()));
+        {
+            let mut transport = AsyncTcpTransport::open(&device).await.unwrap();
+            assert_eq!(transport.read_status().await.unwrap(), [0x23, 0x86]);
+            transport
+                .send_program(&program, &PrinterConfig::TSP650II)
+                .await
+                .unwrap();
+        }
+
+        assert_eq!(reader.await.unwrap(), program.to_bytes());
+    }
+}
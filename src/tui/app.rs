@@ -0,0 +1,359 @@
+//! TUI state: the browsable entries, selection, previews and print jobs.
+//!
+//! Kept free of terminal I/O so key handling can be tested directly.
+
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{Receiver, Sender};
+
+use crate::document::{Component, Document, Pattern, compat};
+use crate::preview::terminal::braille_lines;
+use crate::printer::PrinterConfig;
+use crate::receipt;
+use crate::render::patterns;
+
+/// Sections of the entry list, in tab order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Section {
+    Patterns,
+    Receipts,
+    Documents,
+    Queue,
+}
+
+impl Section {
+    pub const ALL: [Section; 4] = [
+        Section::Patterns,
+        Section::Receipts,
+        Section::Documents,
+        Section::Queue,
+    ];
+
+    pub fn title(self) -> &'static str {
+        match self {
+            Section::Patterns => "Patterns",
+            Section::Receipts => "Receipts",
+            Section::Documents => "Documents",
+            Section::Queue => "Queue",
+        }
+    }
+}
+
+/// Something that can be previewed and printed.
+#[derive(Debug, Clone)]
+pub enum Entry {
+    Pattern(&'static str),
+    Receipt(&'static str),
+    Document(PathBuf),
+}
+
+impl Entry {
+    pub fn name(&self) -> String {
+        match self {
+            Entry::Pattern(name) | Entry::Receipt(name) => name.to_string(),
+            Entry::Document(path) => path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Build the document for this entry. Patterns use their golden
+    /// parameters so the preview matches what gets printed.
+    pub fn document(&self) -> Result<Document, String> {
+        match self {
+            Entry::Pattern(name) => {
+                let pattern = patterns::by_name_golden(name)
+                    .ok_or_else(|| format!("Unknown pattern '{}'", name))?;
+                let (_, height) = pattern.default_dimensions();
+                let mut doc = Document::new();
+                doc.push(Component::Pattern(Pattern {
+                    name: name.to_string(),
+                    height: Some(height),
+                    ..Default::default()
+                }));
+                Ok(doc)
+            }
+            Entry::Receipt(name) => {
+                receipt::document_by_name(name).ok_or_else(|| format!("Unknown receipt '{}'", name))
+            }
+            Entry::Document(path) => {
+                let json = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+                let value = serde_json::from_str(&json).map_err(|e| e.to_string())?;
+                compat::parse_document(value).map(|upgraded| upgraded.document)
+            }
+        }
+    }
+}
+
+/// State of a print job.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JobStatus {
+    Queued,
+    Printing,
+    Done,
+    Failed(String),
+}
+
+/// A print job in the queue.
+#[derive(Debug, Clone)]
+pub struct Job {
+    pub id: usize,
+    pub name: String,
+    pub status: JobStatus,
+}
+
+/// A job for the print worker.
+pub struct PrintRequest {
+    pub id: usize,
+    pub document: Document,
+}
+
+/// Cached preview of the selected entry.
+pub struct Preview {
+    /// Width the lines were rendered for.
+    pub columns: usize,
+    pub lines: Vec<String>,
+    pub error: Option<String>,
+}
+
+/// TUI state.
+pub struct App {
+    pub section: Section,
+    pub selected: [usize; 4],
+    pub patterns: Vec<Entry>,
+    pub receipts: Vec<Entry>,
+    pub documents: Vec<Entry>,
+    pub jobs: Vec<Job>,
+    pub preview: Option<Preview>,
+    /// Preview width in terminal columns.
+    pub preview_columns: usize,
+    /// First preview line shown.
+    pub scroll: usize,
+    pub quit: bool,
+    /// Printer that previews are laid out for.
+    printer: PrinterConfig,
+    requests: Sender<PrintRequest>,
+    updates: Receiver<(usize, JobStatus)>,
+}
+
+impl App {
+    pub fn new(
+        documents_dir: Option<&Path>,
+        printer: PrinterConfig,
+        requests: Sender<PrintRequest>,
+        updates: Receiver<(usize, JobStatus)>,
+    ) -> Self {
+        Self {
+            section: Section::Patterns,
+            selected: [0; 4],
+            patterns: patterns::list_patterns()
+                .into_iter()
+                .map(Entry::Pattern)
+                .collect(),
+            receipts: receipt::list_receipts()
+                .iter()
+                .map(|name| Entry::Receipt(name))
+                .collect(),
+            documents: documents_dir.map(list_documents).unwrap_or_default(),
+            jobs: Vec::new(),
+            preview: None,
+            preview_columns: 72,
+            scroll: 0,
+            quit: false,
+            printer,
+            requests,
+            updates,
+        }
+    }
+
+    fn section_index(&self) -> usize {
+        Section::ALL
+            .iter()
+            .position(|s| *s == self.section)
+            .unwrap_or(0)
+    }
+
+    /// Entries of the current section (empty for the queue).
+    pub fn entries(&self) -> &[Entry] {
+        match self.section {
+            Section::Patterns => &self.patterns,
+            Section::Receipts => &self.receipts,
+            Section::Documents => &self.documents,
+            Section::Queue => &[],
+        }
+    }
+
+    /// Number of rows in the current section's list.
+    pub fn len(&self) -> usize {
+        match self.section {
+            Section::Queue => self.jobs.len(),
+            _ => self.entries().len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn selected(&self) -> usize {
+        self.selected[self.section_index()]
+    }
+
+    pub fn selected_entry(&self) -> Option<&Entry> {
+        self.entries().get(self.selected())
+    }
+
+    /// Move the selection by `delta` rows, clamped to the list.
+    pub fn move_selection(&mut self, delta: isize) {
+        let len = self.len();
+        let index = self.section_index();
+        self.selected[index] = self.selected[index]
+            .saturating_add_signed(delta)
+            .min(len.saturating_sub(1));
+        self.invalidate_preview();
+    }
+
+    /// Switch to the next (or previous) section.
+    pub fn cycle_section(&mut self, forward: bool) {
+        let count = Section::ALL.len();
+        let index = self.section_index();
+        let next = if forward {
+            (index + 1) % count
+        } else {
+            (index + count - 1) % count
+        };
+        self.section = Section::ALL[next];
+        self.invalidate_preview();
+    }
+
+    pub fn scroll_preview(&mut self, delta: isize) {
+        let max = self
+            .preview
+            .as_ref()
+            .map_or(0, |p| p.lines.len().saturating_sub(1));
+        self.scroll = self.scroll.saturating_add_signed(delta).min(max);
+    }
+
+    fn invalidate_preview(&mut self) {
+        self.preview = None;
+        self.scroll = 0;
+    }
+
+    /// Render the selected entry's preview if it isn't cached at the
+    /// current width.
+    pub fn ensure_preview(&mut self) {
+        if self
+            .preview
+            .as_ref()
+            .is_some_and(|p| p.columns == self.preview_columns)
+        {
+            return;
+        }
+        let Some(entry) = self.selected_entry() else {
+            return;
+        };
+        let preview = match entry
+            .document()
+            .and_then(|doc| doc.to_raster(&self.printer).map_err(|e| e.to_string()))
+        {
+            Ok(raw) => Preview {
+                columns: self.preview_columns,
+                lines: braille_lines(&raw, self.preview_columns),
+                error: None,
+            },
+            Err(e) => Preview {
+                columns: self.preview_columns,
+                lines: Vec::new(),
+                error: Some(e),
+            },
+        };
+        self.preview = Some(preview);
+    }
+
+    /// Queue the selected entry for printing.
+    pub fn print_selected(&mut self) {
+        let Some(entry) = self.selected_entry() else {
+            return;
+        };
+        let name = entry.name();
+        let id = self.jobs.len() + 1;
+        let status = match entry.document() {
+            Ok(document) => match self.requests.send(PrintRequest { id, document }) {
+                Ok(()) => JobStatus::Queued,
+                Err(_) => JobStatus::Failed("print worker stopped".into()),
+            },
+            Err(e) => JobStatus::Failed(e),
+        };
+        self.jobs.push(Job { id, name, status });
+    }
+
+    /// Apply job status updates from the print worker.
+    pub fn poll_jobs(&mut self) {
+        while let Ok((id, status)) = self.updates.try_recv() {
+            if let Some(job) = self.jobs.iter_mut().find(|job| job.id == id) {
+                job.status = status;
+            }
+        }
+    }
+
+    /// Jobs that haven't finished yet.
+    pub fn pending_jobs(&self) -> usize {
+        self.jobs
+            .iter()
+            .filter(|job| matches!(job.status, JobStatus::Queued | JobStatus::Printing))
+            .count()
+    }
+}
+
+/// JSON documents in a directory, sorted by name.
+fn list_documents(dir: &Path) -> Vec<Entry> {
+    let mut paths: Vec<PathBuf> = std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok().map(|e| e.path()))
+                .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+                .collect()
+        })
+        .unwrap_or_default();
+    paths.sort();
+    paths.into_iter().map(Entry::Document).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+
+    #[test]
+    fn test_navigation_and_print_queue() {
+        let (requests, worker) = mpsc::channel();
+        let (updates_tx, updates) = mpsc::channel();
+        let mut app = App::new(None, PrinterConfig::TSP650II, requests, updates);
+
+        app.move_selection(-5);
+        assert_eq!(app.selected(), 0);
+        app.move_selection(1_000);
+        assert_eq!(app.selected(), app.patterns.len() - 1);
+
+        app.cycle_section(true);
+        assert_eq!(app.section, Section::Receipts);
+        assert_eq!(app.selected(), 0);
+        app.ensure_preview();
+        assert!(!app.preview.as_ref().unwrap().lines.is_empty());
+
+        app.print_selected();
+        let request = worker.try_recv().unwrap();
+        assert_eq!(request.id, 1);
+        assert_eq!(app.pending_jobs(), 1);
+
+        updates_tx.send((1, JobStatus::Done)).unwrap();
+        app.poll_jobs();
+        assert_eq!(app.jobs[0].status, JobStatus::Done);
+        assert_eq!(app.pending_jobs(), 0);
+
+        app.cycle_section(false);
+        app.cycle_section(false);
+        assert_eq!(app.section, Section::Queue);
+        assert_eq!(app.len(), 1);
+    }
+}
@@ -0,0 +1,174 @@
+//! # Terminal UI
+//!
+//! `estrella tui` browses patterns, receipts and saved documents, previews
+//! the selection as braille art (see [`crate::preview::terminal`]) and prints
+//! it with a single key. Prints run one at a time on a background thread, so
+//! the list stays responsive while a long pattern streams over Bluetooth.
+//!
+//! | Key | Action |
+//! |-----|--------|
+//! | `Tab` / `←` `→` | Switch section |
+//! | `↑` `↓` / `j` `k` | Select |
+//! | `PgUp` / `PgDn` | Scroll preview |
+//! | `p` / `Enter` | Print selection |
+//! | `q` / `Esc` | Quit |
+
+mod app;
+
+pub use app::{App, Entry, Job, JobStatus, PrintRequest, Section};
+
+use std::path::PathBuf;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+use ratatui::Frame;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, List, ListItem, ListState, Paragraph, Tabs};
+
+use crate::EstrellaError;
+use crate::printer::PrinterConfig;
+use crate::transport::{self, Transport};
+
+/// How long to wait for input before refreshing job status.
+const TICK: Duration = Duration::from_millis(200);
+
+/// Run the TUI until the user quits. Prints go to `device` and are laid out
+/// for `printer`; JSON documents in `documents_dir` are listed under
+/// "Documents".
+pub fn run(
+    device: String,
+    printer: PrinterConfig,
+    documents_dir: Option<PathBuf>,
+) -> Result<(), EstrellaError> {
+    let (requests, jobs) = mpsc::channel::<PrintRequest>();
+    let (updates, status) = mpsc::channel();
+    thread::spawn(move || {
+        for job in jobs {
+            let _ = updates.send((job.id, JobStatus::Printing));
+            let result = job
+                .document
+                .print_program_with_config(&printer)
+                .and_then(|program| transport::open(&device)?.send_program(&program, &printer));
+            let _ = updates.send((
+                job.id,
+                match result {
+                    Ok(()) => JobStatus::Done,
+                    Err(e) => JobStatus::Failed(e.to_string()),
+                },
+            ));
+        }
+    });
+
+    let mut app = App::new(documents_dir.as_deref(), printer, requests, status);
+    let mut terminal = ratatui::init();
+    let result = event_loop(&mut terminal, &mut app);
+    ratatui::restore();
+    result
+}
+
+fn event_loop(terminal: &mut ratatui::DefaultTerminal, app: &mut App) -> Result<(), EstrellaError> {
+    while !app.quit {
+        app.poll_jobs();
+        terminal.draw(|frame| draw(frame, app))?;
+
+        if event::poll(TICK)?
+            && let Event::Key(key) = event::read()?
+            && key.kind == KeyEventKind::Press
+        {
+            handle_key(app, key.code);
+        }
+    }
+    Ok(())
+}
+
+fn handle_key(app: &mut App, code: KeyCode) {
+    match code {
+        KeyCode::Char('q') | KeyCode::Esc => app.quit = true,
+        KeyCode::Tab | KeyCode::Right => app.cycle_section(true),
+        KeyCode::BackTab | KeyCode::Left => app.cycle_section(false),
+        KeyCode::Down | KeyCode::Char('j') => app.move_selection(1),
+        KeyCode::Up | KeyCode::Char('k') => app.move_selection(-1),
+        KeyCode::PageDown => app.scroll_preview(10),
+        KeyCode::PageUp => app.scroll_preview(-10),
+        KeyCode::Char('p') | KeyCode::Enter => app.print_selected(),
+        _ => {}
+    }
+}
+
+fn draw(frame: &mut Frame, app: &mut App) {
+    let [tabs_area, main_area, status_area] = Layout::vertical([
+        Constraint::Length(1),
+        Constraint::Min(0),
+        Constraint::Length(1),
+    ])
+    .areas(frame.area());
+    let [list_area, preview_area] =
+        Layout::horizontal([Constraint::Length(28), Constraint::Min(0)]).areas(main_area);
+
+    let selected_tab = Section::ALL.iter().position(|s| *s == app.section);
+    frame.render_widget(
+        Tabs::new(Section::ALL.iter().map(|s| s.title()))
+            .select(selected_tab)
+            .highlight_style(Style::new().add_modifier(Modifier::REVERSED)),
+        tabs_area,
+    );
+
+    let items: Vec<ListItem> = match app.section {
+        Section::Queue => app
+            .jobs
+            .iter()
+            .map(|job| {
+                let status = match &job.status {
+                    JobStatus::Queued => "queued".to_string(),
+                    JobStatus::Printing => "printing".to_string(),
+                    JobStatus::Done => "done".to_string(),
+                    JobStatus::Failed(e) => format!("failed: {}", e),
+                };
+                ListItem::new(format!("#{} {} ({})", job.id, job.name, status))
+            })
+            .collect(),
+        _ => app
+            .entries()
+            .iter()
+            .map(|entry| ListItem::new(entry.name()))
+            .collect(),
+    };
+    let mut state = ListState::default().with_selected((!app.is_empty()).then(|| app.selected()));
+    frame.render_stateful_widget(
+        List::new(items)
+            .block(Block::bordered().title(app.section.title()))
+            .highlight_style(Style::new().add_modifier(Modifier::REVERSED)),
+        list_area,
+        &mut state,
+    );
+
+    // Braille is two pixels per column; leave room for the border
+    app.preview_columns = preview_area.width.saturating_sub(2).max(1) as usize;
+    app.ensure_preview();
+    let preview_block = Block::bordered().title("Preview");
+    let preview = match &app.preview {
+        Some(preview) => match &preview.error {
+            Some(error) => Paragraph::new(error.as_str()),
+            None => Paragraph::new(
+                preview.lines[app.scroll.min(preview.lines.len())..]
+                    .iter()
+                    .map(|line| Line::raw(line.as_str()))
+                    .collect::<Vec<_>>(),
+            ),
+        },
+        None => Paragraph::new(""),
+    };
+    frame.render_widget(preview.block(preview_block), preview_area);
+
+    frame.render_widget(
+        Line::raw(format!(
+            " {} pending · Tab section · ↑↓ select · PgUp/PgDn scroll · p print · q quit",
+            app.pending_jobs()
+        )),
+        status_area,
+    );
+}
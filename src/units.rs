@@ -0,0 +1,222 @@
+//! # Physical Units
+//!
+//! Paper is measured in millimeters, printed in dots and fed in quarter
+//! millimeters. Every conversion between them goes through this module,
+//! so the CLI, components and server agree on what "40mm" means:
+//!
+//! | Unit | Size | Used by |
+//! |------|------|---------|
+//! | dot | 1/203 inch (≈ 0.125mm) at 203 DPI | raster graphics, canvas coordinates |
+//! | feed unit | 0.25mm | `Op::Feed`, ESC J |
+//! | mm | | JSON documents, CLI lengths |
+//!
+//! [`Length`] is a size given in any of them. As text it is `"12.5mm"`,
+//! `"100"` (dots, also `"100px"`) or `"50%"` (of an extent, where one
+//! applies):
+//!
+//! ```
+//! use estrella::PrinterConfig;
+//! use estrella::units::{self, Length};
+//!
+//! let band: Length = "40mm".parse().unwrap();
+//! assert_eq!(band.to_dots(0, &PrinterConfig::TSP650II), 320);
+//!
+//! assert_eq!(units::mm_to_dots(10.0, 203), 80);
+//! assert_eq!(units::mm_to_feed_units(3.0), 12);
+//! ```
+
+use serde::{Deserialize, Serialize};
+
+use crate::printer::PrinterConfig;
+
+/// Millimeters in an inch.
+pub const MM_PER_INCH: f32 = 25.4;
+
+/// Paper feed units (`Op::Feed`, ESC J) per millimeter.
+pub const FEED_UNITS_PER_MM: f32 = 4.0;
+
+/// Dots per millimeter at `dpi` (8 at 203 DPI).
+#[inline]
+pub fn dots_per_mm(dpi: u16) -> f32 {
+    dpi as f32 / MM_PER_INCH
+}
+
+/// Millimeters in dots at `dpi`, rounded. Negative lengths are 0.
+#[inline]
+pub fn mm_to_dots(mm: f32, dpi: u16) -> u16 {
+    (mm * dots_per_mm(dpi)).round() as u16
+}
+
+/// Dots at `dpi` in millimeters.
+#[inline]
+pub fn dots_to_mm(dots: u32, dpi: u16) -> f32 {
+    dots as f32 / dots_per_mm(dpi)
+}
+
+/// Millimeters in feed units, rounded and clamped to one feed (0-255,
+/// up to 63.75mm).
+#[inline]
+pub fn mm_to_feed_units(mm: f32) -> u8 {
+    (mm * FEED_UNITS_PER_MM).round().clamp(0.0, 255.0) as u8
+}
+
+/// Feed units in millimeters.
+#[inline]
+pub fn feed_units_to_mm(units: u8) -> f32 {
+    units as f32 / FEED_UNITS_PER_MM
+}
+
+/// A length in dots, millimeters or a percentage of some extent.
+///
+/// Millimeters resolve against the printer's DPI, so layouts keep their
+/// physical size across printers.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Length {
+    Dots(i32),
+    Mm(f32),
+    Percent(f32),
+}
+
+impl Default for Length {
+    fn default() -> Self {
+        Length::Dots(0)
+    }
+}
+
+impl From<i32> for Length {
+    fn from(dots: i32) -> Self {
+        Length::Dots(dots)
+    }
+}
+
+impl Length {
+    /// Resolve to dots. Percentages are of `extent` (canvas width or height).
+    pub fn to_dots(self, extent: usize, config: &PrinterConfig) -> i32 {
+        match self {
+            Length::Dots(dots) => dots,
+            Length::Mm(mm) => (mm * config.dots_per_mm()).round() as i32,
+            Length::Percent(pct) => (pct / 100.0 * extent as f32).round() as i32,
+        }
+    }
+
+    /// Resolve a length that stands on its own, like a print height, to
+    /// dots. It must be positive, and can't be a percentage.
+    pub fn to_positive_dots(self, config: &PrinterConfig) -> Result<usize, String> {
+        if let Length::Percent(_) = self {
+            return Err(format!("Length '{}' must be in mm or dots", self));
+        }
+        match self.to_dots(0, config) {
+            dots if dots > 0 => Ok(dots as usize),
+            _ => Err(format!("Length '{}' must be positive", self)),
+        }
+    }
+
+    /// Like [`Self::to_positive_dots`], in millimeters.
+    pub fn to_positive_mm(self, config: &PrinterConfig) -> Result<f32, String> {
+        match self {
+            Length::Mm(mm) if mm > 0.0 => Ok(mm),
+            Length::Mm(_) => Err(format!("Length '{}' must be positive", self)),
+            _ => Ok(dots_to_mm(
+                self.to_positive_dots(config)? as u32,
+                config.dpi,
+            )),
+        }
+    }
+}
+
+impl std::str::FromStr for Length {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim().to_lowercase();
+        let number = |n: &str| {
+            n.trim().parse::<f32>().map_err(|_| {
+                format!(
+                    "Invalid length '{}'. Use mm ('15mm'), dots ('120') or a percentage ('50%')",
+                    s
+                )
+            })
+        };
+        if let Some(mm) = s.strip_suffix("mm") {
+            Ok(Length::Mm(number(mm)?))
+        } else if let Some(pct) = s.strip_suffix('%') {
+            Ok(Length::Percent(number(pct)?))
+        } else {
+            let dots = s.strip_suffix("px").unwrap_or(&s);
+            Ok(Length::Dots(number(dots)?.round() as i32))
+        }
+    }
+}
+
+impl std::fmt::Display for Length {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Length::Dots(dots) => write!(f, "{}", dots),
+            Length::Mm(mm) => write!(f, "{}mm", mm),
+            Length::Percent(pct) => write!(f, "{}%", pct),
+        }
+    }
+}
+
+impl Serialize for Length {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Length::Dots(dots) => serializer.serialize_i32(*dots),
+            other => serializer.serialize_str(&other.to_string()),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Length {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum LengthValue {
+            Number(f64),
+            Text(String),
+        }
+
+        match LengthValue::deserialize(deserializer)? {
+            LengthValue::Number(n) => Ok(Length::Dots(n.round() as i32)),
+            LengthValue::Text(s) => s.parse().map_err(serde::de::Error::custom),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_conversions() {
+        assert_eq!(mm_to_dots(40.0, 203), 320);
+        assert_eq!(mm_to_dots(-1.0, 203), 0);
+        assert!((dots_to_mm(80, 203) - 10.0).abs() < 0.1);
+        assert_eq!(mm_to_feed_units(100.0), 255);
+        assert_eq!(feed_units_to_mm(12), 3.0);
+    }
+
+    #[test]
+    fn test_parse_length() {
+        assert_eq!("12.5MM".parse(), Ok(Length::Mm(12.5)));
+        assert_eq!(" 120px".parse(), Ok(Length::Dots(120)));
+        assert_eq!("50%".parse(), Ok(Length::Percent(50.0)));
+        assert!("tall".parse::<Length>().is_err());
+        for length in [Length::Dots(-3), Length::Mm(2.5), Length::Percent(10.0)] {
+            assert_eq!(length.to_string().parse(), Ok(length));
+        }
+    }
+
+    #[test]
+    fn test_positive_lengths() {
+        let config = PrinterConfig::TSP650II;
+        assert_eq!(Length::Mm(15.0).to_positive_dots(&config), Ok(120));
+        assert_eq!(
+            Length::Dots(80).to_positive_mm(&config).map(f32::round),
+            Ok(10.0)
+        );
+        assert!(Length::Mm(0.0).to_positive_dots(&config).is_err());
+        assert!(Length::Mm(-2.0).to_positive_mm(&config).is_err());
+        assert!(Length::Percent(50.0).to_positive_dots(&config).is_err());
+    }
+}
@@ -15,9 +15,13 @@
 //! ```bash
 //! make golden
 //! ```
+//!
+//! Patterns and PNG previews need the `patterns` feature.
+
+#![cfg(feature = "patterns")]
 
 use estrella::PrinterConfig;
-use estrella::document::{self, Component, Divider, Document, Text};
+use estrella::document::{self, Component, Divider, Document, EmitContext, Text};
 use estrella::ir::{Op, Program};
 use estrella::receipt;
 use estrella::render::dither::{self, DitheringAlgorithm};
@@ -67,7 +71,7 @@ fn build_pattern_document(name: &str, height: usize) -> Document {
 
 /// Generate printer commands using raster mode via Document.
 fn generate_raster_commands(name: &str, height: usize) -> Vec<u8> {
-    build_pattern_document(name, height).build()
+    build_pattern_document(name, height).build().unwrap()
 }
 
 /// Generate printer commands using band mode.
@@ -93,9 +97,10 @@ fn generate_band_commands(name: &str, height: usize) -> Vec<u8> {
         size: [3, 2],
         ..Default::default()
     };
-    title_text.emit(&mut program.ops);
+    let ctx = EmitContext::default();
+    title_text.emit(&ctx, &mut program.ops);
     let divider = Divider::default();
-    divider.emit(&mut program.ops);
+    divider.emit(&ctx, &mut program.ops);
 
     // Band mode graphics
     let width_bytes = (width as u16).div_ceil(8) as u8;
@@ -266,7 +271,7 @@ fn generate_golden_files() {
     write_golden("ripple_band", "bin", &band_cmd);
 
     // Preview PNGs for all patterns
-    for &name in patterns::list_patterns() {
+    for name in patterns::list_patterns() {
         if PLATFORM_DEPENDENT_PATTERNS.contains(&name) {
             continue;
         }
@@ -392,7 +397,7 @@ fn test_binary_ripple_band() {
 /// Test that all pattern previews match their golden PNGs
 #[test]
 fn test_preview_all_patterns() {
-    for &name in patterns::list_patterns() {
+    for name in patterns::list_patterns() {
         // Skip platform-dependent patterns (chaotic dynamics, heavy FP iteration)
         if PLATFORM_DEPENDENT_PATTERNS.contains(&name) {
             continue;
@@ -0,0 +1,351 @@
+//! # Protocol Property Tests
+//!
+//! Property-based tests for the StarPRNT builders, codegen and decoder.
+//!
+//! - **Round-trip**: random op sequences survive `ops → bytes → ops`
+//!   (modulo the protocol's documented lossiness, see [`canonical`]).
+//! - **Length encoding**: every length-prefixed command's header matches
+//!   its payload, across the whole parameter range.
+//! - **Clamping**: builders never emit out-of-range parameters.
+//! - **Robustness**: the decoder never panics on arbitrary bytes.
+//!
+//! The same properties are available as fuzz targets in `fuzz/`.
+
+use estrella::ir::{BarcodeKind, Op, Program};
+use estrella::protocol::barcode::barcode1d::HriPosition;
+use estrella::protocol::barcode::qr::QrErrorLevel;
+use estrella::protocol::barcode::{pdf417, qr};
+use estrella::protocol::codepage::Encoder;
+use estrella::protocol::commands::PrintSpeed;
+use estrella::protocol::text::{Alignment, Font, LineSpacing};
+use estrella::protocol::{commands, graphics, text};
+use proptest::prelude::*;
+
+// ============================================================================
+// STRATEGIES
+// ============================================================================
+
+fn alignment() -> impl Strategy<Value = Alignment> {
+    prop_oneof![
+        Just(Alignment::Left),
+        Just(Alignment::Center),
+        Just(Alignment::Right)
+    ]
+}
+
+fn font() -> impl Strategy<Value = Font> {
+    prop_oneof![Just(Font::A), Just(Font::B), Just(Font::C)]
+}
+
+fn line_spacing() -> impl Strategy<Value = LineSpacing> {
+    prop_oneof![Just(LineSpacing::Tight), Just(LineSpacing::Normal)]
+}
+
+fn print_speed() -> impl Strategy<Value = PrintSpeed> {
+    prop_oneof![
+        Just(PrintSpeed::High),
+        Just(PrintSpeed::Medium),
+        Just(PrintSpeed::Low)
+    ]
+}
+
+fn error_level() -> impl Strategy<Value = QrErrorLevel> {
+    prop_oneof![
+        Just(QrErrorLevel::L),
+        Just(QrErrorLevel::M),
+        Just(QrErrorLevel::Q),
+        Just(QrErrorLevel::H)
+    ]
+}
+
+fn barcode_kind() -> impl Strategy<Value = BarcodeKind> {
+    prop_oneof![
+        Just(BarcodeKind::Code39),
+        Just(BarcodeKind::Code128),
+        Just(BarcodeKind::Ean13),
+        Just(BarcodeKind::UpcA),
+        Just(BarcodeKind::Itf),
+        Just(BarcodeKind::Code93),
+        Just(BarcodeKind::Nw7)
+    ]
+}
+
+/// HRI positions that survive a round trip (the printer has no "above").
+fn hri_position() -> impl Strategy<Value = HriPosition> {
+    prop_oneof![Just(HriPosition::None), Just(HriPosition::Below)]
+}
+
+/// Raster graphics with data matching their dimensions.
+fn raster() -> impl Strategy<Value = Op> {
+    (1u16..=128, 1u16..=600).prop_flat_map(|(width, height)| {
+        let len = width.div_ceil(8) as usize * height as usize;
+        prop::collection::vec(any::<u8>(), len).prop_map(move |data| Op::Raster {
+            width,
+            height,
+            data,
+        })
+    })
+}
+
+fn band() -> impl Strategy<Value = Op> {
+    (1u8..=16, 1usize..=60).prop_flat_map(|(width_bytes, rows)| {
+        prop::collection::vec(any::<u8>(), width_bytes as usize * rows)
+            .prop_map(move |data| Op::Band { width_bytes, data })
+    })
+}
+
+/// Ops that codegen can express. `GrayRaster`, `ResetStyle`, `SetWrap` and
+/// `Marker` are lowered or dropped by codegen, so they have no byte-level identity.
+fn op() -> impl Strategy<Value = Op> {
+    prop_oneof![
+        Just(Op::Init),
+        any::<bool>().prop_map(|partial| Op::Cut { partial }),
+        any::<u8>().prop_map(|units| Op::Feed { units }),
+        Just(Op::FeedToMark),
+        alignment().prop_map(Op::SetAlign),
+        font().prop_map(Op::SetFont),
+        any::<bool>().prop_map(Op::SetBold),
+        any::<bool>().prop_map(Op::SetUnderline),
+        any::<bool>().prop_map(Op::SetInvert),
+        (any::<u8>(), any::<u8>()).prop_map(|(height, width)| Op::SetSize { height, width }),
+        any::<u8>().prop_map(Op::SetExpandedWidth),
+        any::<u8>().prop_map(Op::SetExpandedHeight),
+        any::<bool>().prop_map(Op::SetSmoothing),
+        any::<bool>().prop_map(Op::SetUpperline),
+        any::<bool>().prop_map(Op::SetUpsideDown),
+        any::<bool>().prop_map(Op::SetReduced),
+        any::<u8>().prop_map(Op::SetCodepage),
+        line_spacing().prop_map(Op::SetLineSpacing),
+        (commands::MIN_DENSITY..=commands::MAX_DENSITY).prop_map(Op::SetDensity),
+        print_speed().prop_map(Op::SetSpeed),
+        "[ -~ñéü°█┌─┐]{1,40}".prop_map(Op::Text),
+        Just(Op::Newline),
+        raster(),
+        band(),
+        (any::<String>(), any::<u8>(), error_level()).prop_map(|(data, cell_size, error_level)| {
+            Op::QrCode {
+                data,
+                cell_size,
+                error_level,
+            }
+        }),
+        (any::<String>(), any::<u8>(), any::<u8>()).prop_map(|(data, module_width, ecc_level)| {
+            Op::Pdf417 {
+                data,
+                module_width,
+                ecc_level,
+            }
+        }),
+        (
+            barcode_kind(),
+            "[A-Z0-9 .-]{0,20}",
+            any::<u8>(),
+            hri_position(),
+        )
+            .prop_map(|(kind, data, height, hri)| Op::Barcode1D {
+                kind,
+                data,
+                height,
+                hri,
+            }),
+        any::<u16>().prop_map(Op::SetAbsolutePosition),
+        ("[ -~]{2}", 1u8..=2, 1u8..=2).prop_map(|(key, scale_x, scale_y)| Op::NvPrint {
+            key,
+            scale_x,
+            scale_y,
+        }),
+        "[ -~]{2}".prop_map(|key| Op::NvDelete { key }),
+        ("[ -~]{2}", 1u16..=64, 1u16..=32).prop_flat_map(|(key, width, height)| {
+            let len = width.div_ceil(8) as usize * height as usize;
+            prop::collection::vec(any::<u8>(), len).prop_map(move |data| Op::NvStore {
+                key: key.clone(),
+                width,
+                height,
+                data,
+            })
+        }),
+    ]
+}
+
+// ============================================================================
+// CANONICAL FORM
+// ============================================================================
+
+/// What the decoder is expected to return for `ops`.
+///
+/// Mirrors the protocol's lossiness: parameters are clamped by the
+/// builders, raster widths round up to whole bytes and split into 256-row
+/// chunks, bands are padded to 24 rows with a feed after each, text
+/// switches code page where the current one lacks a character, and
+/// adjacent text runs merge.
+fn canonical(ops: &[Op]) -> Vec<Op> {
+    let mut out: Vec<Op> = Vec::new();
+    let mut encoder = Encoder::default();
+    for op in ops {
+        match op {
+            Op::Init => {
+                encoder = Encoder::default();
+                out.push(Op::Init);
+            }
+            Op::SetCodepage(page) => {
+                encoder.set_page(*page);
+                out.push(op.clone());
+            }
+            Op::Text(s) => {
+                for run in encoder.runs(s) {
+                    if run.switch {
+                        out.push(Op::SetCodepage(run.page));
+                    }
+                    match out.last_mut() {
+                        Some(Op::Text(prev)) => prev.push_str(run.text),
+                        _ => out.push(Op::Text(run.text.to_string())),
+                    }
+                }
+            }
+            Op::SetSize { height, width } => out.push(Op::SetSize {
+                height: (*height).min(7),
+                width: (*width).min(7),
+            }),
+            Op::SetExpandedWidth(n) => out.push(Op::SetExpandedWidth((*n).min(7))),
+            Op::SetExpandedHeight(n) => out.push(Op::SetExpandedHeight((*n).min(7))),
+            Op::Raster {
+                width,
+                height,
+                data,
+            } => {
+                let width_bytes = width.div_ceil(8) as usize;
+                for (i, chunk) in data.chunks(width_bytes * 256).enumerate() {
+                    let rows = (*height as usize - i * 256).min(256);
+                    out.push(Op::Raster {
+                        width: width_bytes as u16 * 8,
+                        height: rows as u16,
+                        data: chunk.to_vec(),
+                    });
+                }
+            }
+            Op::Band { width_bytes, data } => {
+                let band_size = *width_bytes as usize * 24;
+                for chunk in data.chunks(band_size) {
+                    let mut padded = chunk.to_vec();
+                    padded.resize(band_size, 0x00);
+                    out.push(Op::Band {
+                        width_bytes: *width_bytes,
+                        data: padded,
+                    });
+                    out.push(Op::Feed { units: 12 });
+                }
+            }
+            Op::QrCode {
+                data,
+                cell_size,
+                error_level,
+            } => out.push(Op::QrCode {
+                data: data.clone(),
+                cell_size: (*cell_size).clamp(1, 8),
+                error_level: *error_level,
+            }),
+            Op::Pdf417 {
+                data,
+                module_width,
+                ecc_level,
+            } => out.push(Op::Pdf417 {
+                data: data.clone(),
+                module_width: (*module_width).clamp(1, 15),
+                ecc_level: (*ecc_level).min(8),
+            }),
+            Op::Barcode1D {
+                kind,
+                data,
+                height,
+                hri,
+            } => out.push(Op::Barcode1D {
+                kind: *kind,
+                data: data.clone(),
+                height: (*height).max(1),
+                hri: *hri,
+            }),
+            other => out.push(other.clone()),
+        }
+    }
+    out
+}
+
+// ============================================================================
+// PROPERTIES
+// ============================================================================
+
+proptest! {
+    #[test]
+    fn round_trip_ops_bytes_ops(ops in prop::collection::vec(op(), 0..24)) {
+        let program = Program { ops: ops.clone() };
+        let decoded = Program::from_bytes(&program.to_bytes()).unwrap();
+        prop_assert_eq!(decoded.ops, canonical(&ops));
+    }
+
+    #[test]
+    fn decoder_never_panics(bytes in prop::collection::vec(any::<u8>(), 0..512)) {
+        let _ = Program::from_bytes(&bytes);
+    }
+
+    #[test]
+    fn decoder_never_panics_on_truncated_commands(
+        ops in prop::collection::vec(op(), 1..8),
+        cut in any::<prop::sample::Index>(),
+    ) {
+        let bytes = Program { ops }.to_bytes();
+        let _ = Program::from_bytes(&bytes[..cut.index(bytes.len() + 1)]);
+    }
+
+    #[test]
+    fn raster_header_matches_payload(width in 1u16..=2048, height in 0u16..=300) {
+        let width_bytes = width.div_ceil(8);
+        let data = vec![0u8; width_bytes as usize * height as usize];
+        let cmd = graphics::raster(width, height, &data);
+        prop_assert_eq!(u16::from_le_bytes([cmd[4], cmd[5]]), width_bytes);
+        prop_assert_eq!(u16::from_le_bytes([cmd[6], cmd[7]]), height);
+        prop_assert_eq!(cmd.len(), 9 + data.len());
+    }
+
+    #[test]
+    fn barcode_data_header_matches_payload(len in prop_oneof![0usize..1024, 65530usize..65600]) {
+        let data = vec![b'A'; len];
+
+        let cmd = qr::set_data(&data);
+        let declared = u16::from_le_bytes([cmd[6], cmd[7]]) as usize;
+        prop_assert_eq!(cmd.len() - 8, declared);
+        prop_assert_eq!(declared, len.min(u16::MAX as usize));
+
+        let cmd = pdf417::set_data(&data);
+        let declared = u16::from_le_bytes([cmd[4], cmd[5]]) as usize;
+        prop_assert_eq!(cmd.len() - 6, declared);
+        prop_assert_eq!(declared, len.min(u16::MAX as usize));
+    }
+
+    #[test]
+    fn builders_clamp_parameters(a in any::<u8>(), b in any::<u8>()) {
+        let size = text::size(a, b);
+        prop_assert!(size[2] <= 7 && size[3] <= 7);
+        prop_assert!(text::expanded_width(a)[2] <= 7);
+        prop_assert!(text::expanded_height(a)[2] <= 7);
+
+        let reduced = text::reduced(a, b);
+        prop_assert!(reduced[3] <= 1 && reduced[4] <= 2);
+
+        prop_assert!((1..=8).contains(&qr::set_cell_size(a)[5]));
+        prop_assert!(pdf417::set_ecc_level(a)[5] <= 8);
+        prop_assert!((1..=15).contains(&pdf417::set_module_width(a)[5]));
+        prop_assert!((1..=10).contains(&pdf417::set_module_aspect(a)[5]));
+
+        let ratio = pdf417::set_size_ratio(a, b);
+        prop_assert!((1..=99).contains(&ratio[6]) && (1..=99).contains(&ratio[7]));
+        let fixed = pdf417::set_size_fixed(a, b);
+        prop_assert!(fixed[6] == 0 || (3..=90).contains(&fixed[6]));
+        prop_assert!(fixed[7] == 0 || (1..=30).contains(&fixed[7]));
+    }
+
+    #[test]
+    fn feed_mm_never_panics(mm in any::<f32>()) {
+        let cmd = commands::feed_mm(mm);
+        prop_assert_eq!(cmd.len(), 3);
+    }
+}